@@ -0,0 +1,32 @@
+#!/bin/bash
+# Download the Quran text index used by verse detection (post-processing)
+# Run this script from the project root directory
+#
+# Texts are from the Tanzil Project (https://tanzil.net), distributed under its
+# terms of use: verbatim copies only, with the license notice kept intact.
+
+set -e
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+PROJECT_ROOT="$(dirname "$SCRIPT_DIR")"
+RESOURCES_DIR="$PROJECT_ROOT/src-tauri/resources"
+TANZIL_URL="https://tanzil.net/pub/download/index.php"
+
+echo "Downloading Quran text index..."
+
+mkdir -p "$RESOURCES_DIR"
+
+# Imlaei (standard orthography), used for matching and for Imlaei substitutions
+echo "Downloading Imlaei text..."
+curl -sfL "$TANZIL_URL?quranType=simple&marks=true&sajdah=true&outType=txt-2&agree=true" \
+    -o "$RESOURCES_DIR/quran-simple.txt"
+
+# Uthmani script
+echo "Downloading Uthmani text..."
+curl -sfL "$TANZIL_URL?quranType=uthmani&marks=true&sajdah=true&tatweel=true&outType=txt-2&agree=true" \
+    -o "$RESOURCES_DIR/quran-uthmani.txt"
+
+echo ""
+echo "Quran text index downloaded successfully!"
+echo ""
+wc -l "$RESOURCES_DIR"/quran-*.txt
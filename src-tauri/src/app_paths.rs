@@ -0,0 +1,340 @@
+use crate::error::TahweelError;
+use crate::logging;
+use crate::long_path;
+use crate::path_policy;
+use crate::profiles;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Folder under the platform data dir holding everything the app keeps on disk
+const APP_DIR: &str = "tahweel";
+/// Folder of the logs area holding crash reports
+pub(crate) const CRASHES_DIR: &str = "crashes";
+
+/// Makes the temporary file names of `write_atomic` unique within the process
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Areas of the app-data layout, `<data dir>/tahweel/<area>/`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Area {
+    /// Settings, profiles and the recent-files list
+    Config,
+    /// Sign-in tokens and other files that can be recreated
+    Cache,
+    /// Render workspaces (page images, spools, downloads); emptied as jobs finish
+    Workspaces,
+    /// Rotating logs and crash reports
+    Logs,
+    /// Job history and search databases
+    Db,
+}
+
+impl Area {
+    fn dir_name(self) -> &'static str {
+        match self {
+            Area::Config => "config",
+            Area::Cache => "cache",
+            Area::Workspaces => "workspaces",
+            Area::Logs => "logs",
+            Area::Db => "db",
+        }
+    }
+}
+
+/// Where the app keeps its files, as shown in the UI
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AppPaths {
+    pub root: String,
+    pub config: String,
+    pub cache: String,
+    pub workspaces: String,
+    pub logs: String,
+    pub db: String,
+    /// Profile the config, cache and db folders belong to
+    pub profile: String,
+}
+
+/// Root of the layout; `TAHWEEL_TEST_DATA_DIR` replaces the platform data dir
+pub fn root() -> PathBuf {
+    std::env::var("TAHWEEL_TEST_DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| dirs::data_dir().unwrap_or_else(|| PathBuf::from(".")))
+        .join(APP_DIR)
+}
+
+/// Folder of `area`, shared by all profiles
+pub fn dir(area: Area) -> PathBuf {
+    root().join(area.dir_name())
+}
+
+/// Folder of `area` for the active profile, created if needed
+pub fn scoped_dir(area: Area) -> PathBuf {
+    let dir = profiles::scoped(dir(area));
+    fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Folder crash reports are written to
+pub fn crash_dir() -> PathBuf {
+    dir(Area::Logs).join(CRASHES_DIR)
+}
+
+/// Replace `path` with `data` in one step: another process (a second window, a
+/// second launch) reading it sees the old or the new content, never a torn file
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = temp_sibling(path);
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        e
+    })
+}
+
+/// Unique temporary file next to `path`, so concurrent writers never share one
+fn temp_sibling(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+/// Per-profile files of a legacy `tahweel` folder and the area each moves to
+type LegacyFiles = &'static [(&'static str, Area)];
+
+/// Files of the old `<config dir>/tahweel` folder
+const LEGACY_CONFIG_FILES: LegacyFiles = &[("settings.json", Area::Config)];
+/// Files of the old `<data dir>/tahweel` folder (SQLite side files included)
+const LEGACY_DATA_FILES: LegacyFiles = &[
+    ("jobs.db", Area::Db),
+    ("jobs.db-wal", Area::Db),
+    ("jobs.db-shm", Area::Db),
+    ("search.db", Area::Db),
+    ("search.db-wal", Area::Db),
+    ("search.db-shm", Area::Db),
+    ("recent.json", Area::Config),
+];
+/// Files of the old `<cache dir>/tahweel` folder
+const LEGACY_CACHE_FILES: LegacyFiles = &[("token.json", Area::Cache)];
+
+/// Move `from` to `to` unless `to` already exists; returns whether it moved.
+/// Safe against another process migrating at the same time: whoever renames
+/// first wins and the other finds the source gone.
+fn move_entry(from: &Path, to: &Path) -> io::Result<bool> {
+    if !from.exists() || to.exists() {
+        return Ok(false);
+    }
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    match fs::rename(from, to) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        // On another volume (a cache dir elsewhere): copy under a temporary name,
+        // then put it in place and remove the original
+        Err(_) if from.is_file() => {
+            let tmp = temp_sibling(to);
+            fs::copy(from, &tmp)?;
+            fs::rename(&tmp, to)?;
+            fs::remove_file(from)?;
+            Ok(true)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Move the files of the legacy `tahweel` folders (`config`, `data`, `cache`;
+/// each may be the same folder, as on macOS and Windows) into the layout under
+/// `root`, keeping every profile's files in that profile's folder. Files already
+/// in the layout win; the moved paths (new locations) are returned.
+pub fn migrate_from(config: &Path, data: &Path, cache: &Path, root: &Path) -> Vec<PathBuf> {
+    let mut moved = Vec::new();
+    let mut try_move = |from: PathBuf, to: PathBuf| match move_entry(&from, &to) {
+        Ok(true) => moved.push(to),
+        Ok(false) => {}
+        Err(e) => eprintln!(
+            "Failed to move {} to {}: {}",
+            from.display(),
+            to.display(),
+            e
+        ),
+    };
+
+    for (base, files) in [
+        (config, LEGACY_CONFIG_FILES),
+        (data, LEGACY_DATA_FILES),
+        (cache, LEGACY_CACHE_FILES),
+    ] {
+        for profile in profiles::list_in(base) {
+            let legacy = profiles::scoped_to(base, &profile);
+            if base == config {
+                // A profile exists by its config folder, even with no settings yet
+                fs::create_dir_all(profiles::scoped_to(
+                    &root.join(Area::Config.dir_name()),
+                    &profile,
+                ))
+                .ok();
+            }
+            for (name, area) in files {
+                let target = profiles::scoped_to(&root.join(area.dir_name()), &profile);
+                try_move(legacy.join(name), target.join(name));
+            }
+            if profile != profiles::DEFAULT_PROFILE {
+                // Only removed once empty, i.e. when nothing else lives there
+                let _ = fs::remove_dir(&legacy);
+            }
+        }
+    }
+
+    try_move(
+        config.join("profiles.json"),
+        root.join(Area::Config.dir_name()).join("profiles.json"),
+    );
+    let crashes = root.join(Area::Logs.dir_name()).join(CRASHES_DIR);
+    if let Ok(entries) = fs::read_dir(data.join(CRASHES_DIR)) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            try_move(entry.path(), crashes.join(entry.file_name()));
+        }
+    }
+    let _ = fs::remove_dir(data.join(CRASHES_DIR));
+    for base in [config, data, cache] {
+        let _ = fs::remove_dir(base.join("profiles"));
+        if base != root {
+            let _ = fs::remove_dir(base);
+        }
+    }
+    moved
+}
+
+/// Move files from the locations used before the unified layout (separate
+/// config, data and cache folders); call at startup before anything reads them
+pub fn migrate_legacy() -> Vec<PathBuf> {
+    let legacy = |dir: Option<PathBuf>| dir.unwrap_or_else(|| PathBuf::from(".")).join(APP_DIR);
+    migrate_from(
+        &legacy(dirs::config_dir()),
+        &legacy(dirs::data_dir()),
+        &legacy(dirs::cache_dir()),
+        &root(),
+    )
+}
+
+/// Folders of the app-data layout for the active profile, for display in the UI
+#[tauri::command]
+pub async fn get_app_paths() -> Result<AppPaths, TahweelError> {
+    let logs = logging::log_dir()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| dir(Area::Logs));
+    Ok(AppPaths {
+        root: long_path::path_string(&root())?,
+        config: long_path::path_string(&scoped_dir(Area::Config))?,
+        cache: long_path::path_string(&scoped_dir(Area::Cache))?,
+        workspaces: long_path::path_string(&path_policy::workspace())?,
+        logs: long_path::path_string(&logs)?,
+        db: long_path::path_string(&scoped_dir(Area::Db))?,
+        profile: profiles::active(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_areas_are_under_root() {
+        let root = root();
+        for area in [
+            Area::Config,
+            Area::Cache,
+            Area::Workspaces,
+            Area::Logs,
+            Area::Db,
+        ] {
+            assert_eq!(dir(area).parent(), Some(root.as_path()));
+        }
+        assert!(crash_dir().starts_with(dir(Area::Logs)));
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("nested").join("settings.json");
+        write_atomic(&path, b"one").unwrap();
+        write_atomic(&path, b"two").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"two");
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_migrate_from_separate_folders() {
+        let dir = tempdir().unwrap();
+        let config = dir.path().join("config-home").join("tahweel");
+        let data = dir.path().join("data-home").join("tahweel");
+        let cache = dir.path().join("cache-home").join("tahweel");
+        let root = dir.path().join("layout");
+        fs::create_dir_all(config.join("profiles").join("work")).unwrap();
+        fs::write(config.join("settings.json"), "{}").unwrap();
+        fs::write(config.join("profiles.json"), r#"{"active":"work"}"#).unwrap();
+        fs::create_dir_all(data.join("profiles").join("work")).unwrap();
+        fs::write(data.join("jobs.db"), "db").unwrap();
+        fs::write(data.join("profiles").join("work").join("recent.json"), "[]").unwrap();
+        fs::create_dir_all(data.join("crashes")).unwrap();
+        fs::write(data.join("crashes").join("crash-1.json"), "{}").unwrap();
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("token.json"), "{}").unwrap();
+
+        let moved = migrate_from(&config, &data, &cache, &root);
+        assert_eq!(moved.len(), 6);
+        assert!(root.join("config").join("settings.json").exists());
+        assert!(root.join("config").join("profiles.json").exists());
+        // The profile keeps existing without any settings of its own
+        assert!(root.join("config").join("profiles").join("work").is_dir());
+        assert!(root.join("db").join("jobs.db").exists());
+        assert!(root
+            .join("config")
+            .join("profiles")
+            .join("work")
+            .join("recent.json")
+            .exists());
+        assert!(root
+            .join("logs")
+            .join("crashes")
+            .join("crash-1.json")
+            .exists());
+        assert!(root.join("cache").join("token.json").exists());
+        assert!(!data.exists());
+        assert!(!cache.exists());
+
+        // A second run has nothing left to do
+        assert!(migrate_from(&config, &data, &cache, &root).is_empty());
+    }
+
+    #[test]
+    fn test_migrate_into_shared_root_keeps_existing_files() {
+        // macOS and Windows: config and data share the folder the layout lives in
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("tahweel");
+        let cache = dir.path().join("cache").join("tahweel");
+        fs::create_dir_all(root.join("config")).unwrap();
+        fs::write(root.join("settings.json"), "old").unwrap();
+        fs::write(root.join("config").join("settings.json"), "new").unwrap();
+        fs::write(root.join("search.db"), "index").unwrap();
+
+        let moved = migrate_from(&root, &root, &cache, &root);
+        assert_eq!(moved, vec![root.join("db").join("search.db")]);
+        assert_eq!(
+            fs::read_to_string(root.join("config").join("settings.json")).unwrap(),
+            "new"
+        );
+        assert!(root.join("settings.json").exists());
+    }
+}
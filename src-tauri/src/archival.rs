@@ -0,0 +1,147 @@
+use crate::error::TahweelError;
+use crate::long_path;
+use crate::path_policy;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Directories an archival-mode run writes to. Outputs and temporary files both
+/// live in app-owned folders, so read-only media and network shares are only read.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ArchivalPaths {
+    #[serde(rename = "outputDirectory")]
+    pub output_directory: String,
+    pub workspace: String,
+}
+
+/// Create `dir` and prove it is writable with a probe file; returns the canonical path
+fn ensure_writable(dir: &Path, what: &str) -> Result<PathBuf, TahweelError> {
+    fs::create_dir_all(dir).map_err(|e| {
+        TahweelError::Io(format!(
+            "Failed to create {} {}: {}",
+            what,
+            dir.display(),
+            e
+        ))
+    })?;
+    let probe = dir.join(format!(".tahweel-write-test-{}", uuid::Uuid::new_v4()));
+    fs::write(&probe, b"").map_err(|e| {
+        TahweelError::Io(format!(
+            "The {} is not writable {}: {}",
+            what,
+            dir.display(),
+            e
+        ))
+    })?;
+    let _ = fs::remove_file(&probe);
+    Ok(dir.canonicalize()?)
+}
+
+/// Verify an archival run can start: the output directory and workspace must be
+/// writable, and no source may sit directly in the output directory (its outputs
+/// would land beside it)
+pub fn prepare(
+    output_dir: &Path,
+    workspace: &Path,
+    sources: &[PathBuf],
+) -> Result<ArchivalPaths, TahweelError> {
+    let output = ensure_writable(output_dir, "archival output directory")?;
+    let workspace = ensure_writable(workspace, "workspace")?;
+
+    if let Some(source) = sources.iter().find(|source| {
+        source
+            .parent()
+            .and_then(|dir| dir.canonicalize().ok())
+            .as_deref()
+            == Some(&output)
+    }) {
+        return Err(TahweelError::InvalidInput(format!(
+            "{} is in the archival output directory; move it elsewhere or turn off archival mode",
+            source.display()
+        )));
+    }
+
+    Ok(ArchivalPaths {
+        output_directory: long_path::path_string(&output)?,
+        workspace: long_path::path_string(&workspace)?,
+    })
+}
+
+/// Keep archival outputs under the app data directory on desktop (mobile already
+/// has an app output directory); call once from `setup`
+pub fn init(app: &AppHandle) {
+    use tauri::Manager;
+
+    if let Ok(data) = app.path().app_data_dir() {
+        path_policy::set_archival_output_dir(data.join("outputs"));
+    }
+}
+
+/// Check archival mode upfront for `source_paths` and return where the run writes
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn prepare_archival_output(
+    source_paths: Vec<String>,
+) -> Result<ArchivalPaths, TahweelError> {
+    let output_dir = path_policy::archival_output_dir().ok_or_else(|| {
+        TahweelError::NotFound("No app output directory available for archival mode".to_string())
+    })?;
+    let sources: Vec<PathBuf> = source_paths.iter().map(PathBuf::from).collect();
+    prepare(output_dir, &path_policy::workspace(), &sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_prepare_creates_directories() {
+        let root = tempdir().unwrap();
+        let output = root.path().join("app").join("outputs");
+        let workspace = root.path().join("work");
+        let sources = vec![root.path().join("share").join("book.pdf")];
+
+        let paths = prepare(&output, &workspace, &sources).unwrap();
+        assert!(output.is_dir());
+        assert!(workspace.is_dir());
+        assert_eq!(
+            PathBuf::from(&paths.output_directory),
+            output.canonicalize().unwrap()
+        );
+        assert_eq!(fs::read_dir(&output).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_prepare_rejects_source_in_output_dir() {
+        let root = tempdir().unwrap();
+        let workspace = root.path().join("work");
+        let sources = vec![root.path().join("book.pdf")];
+
+        let result = prepare(root.path(), &workspace, &sources);
+        assert!(matches!(result, Err(TahweelError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_prepare_rejects_file_as_output_dir() {
+        let root = tempdir().unwrap();
+        let file = root.path().join("outputs");
+        fs::write(&file, b"").unwrap();
+
+        let result = prepare(&file, root.path(), &[]);
+        assert!(matches!(result, Err(TahweelError::Io(_))));
+    }
+
+    #[test]
+    fn test_paths_serialization() {
+        let paths = ArchivalPaths {
+            output_directory: "/data/outputs".to_string(),
+            workspace: "/tmp/tahweel".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(paths).unwrap(),
+            serde_json::json!({"outputDirectory": "/data/outputs", "workspace": "/tmp/tahweel"})
+        );
+    }
+}
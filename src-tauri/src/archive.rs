@@ -0,0 +1,250 @@
+use crate::output::sha256_file;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Version of the ZIP manifest schema
+pub(crate) const MANIFEST_VERSION: u32 = 1;
+/// Name of the manifest entry at the root of the archive
+pub(crate) const MANIFEST_NAME: &str = "manifest.json";
+
+/// File entry recorded in the package manifest
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Manifest describing the contents of a packaged job
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackageManifest {
+    pub version: u32,
+    pub source: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: u64,
+    pub files: Vec<ManifestEntry>,
+}
+
+/// Expand output paths into `(archive name, file path)` pairs.
+/// Directories (e.g. per-page hOCR folders) are included recursively under their own name.
+pub fn collect_entries(paths: &[String]) -> Result<Vec<(String, PathBuf)>, String> {
+    fn walk(dir: &Path, prefix: &str, out: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+        let mut children: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .collect();
+        children.sort();
+        for child in children {
+            let name = format!(
+                "{}/{}",
+                prefix,
+                child.file_name().unwrap_or_default().to_string_lossy()
+            );
+            if child.is_dir() {
+                walk(&child, &name, out)?;
+            } else {
+                out.push((name, child));
+            }
+        }
+        Ok(())
+    }
+
+    let mut entries = Vec::new();
+    for path in paths {
+        let path = Path::new(path);
+        let name = path
+            .file_name()
+            .ok_or_else(|| format!("Invalid output path: {}", path.display()))?
+            .to_string_lossy()
+            .to_string();
+
+        if path.is_dir() {
+            walk(path, &name, &mut entries)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        } else if path.is_file() {
+            entries.push((name, path.to_path_buf()));
+        } else {
+            return Err(format!("Output file not found: {}", path.display()));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for (name, _) in &entries {
+        if name == MANIFEST_NAME || !seen.insert(name.as_str()) {
+            return Err(format!("Duplicate entry in package: {}", name));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Package job outputs (files and directories) into a single ZIP with a `manifest.json`
+/// listing each entry's size and SHA-256. Returns the path of the written archive.
+#[tauri::command]
+pub async fn package_outputs(
+    zip_path: String,
+    files: Vec<String>,
+    source_path: Option<String>,
+) -> Result<String, String> {
+    let entries = collect_entries(&files)?;
+
+    let file = fs::File::create(&zip_path).map_err(|e| format!("Failed to create ZIP: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut manifest_files = Vec::with_capacity(entries.len());
+    for (name, path) in &entries {
+        let mut source = fs::File::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| format!("Failed to add {} to ZIP: {}", name, e))?;
+        let size = io::copy(&mut source, &mut zip)
+            .map_err(|e| format!("Failed to add {} to ZIP: {}", name, e))?;
+
+        manifest_files.push(ManifestEntry {
+            path: name.clone(),
+            size,
+            sha256: sha256_file(path)?,
+        });
+    }
+
+    let manifest = PackageManifest {
+        version: MANIFEST_VERSION,
+        source: source_path,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        files: manifest_files,
+    };
+    let json = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    zip.start_file(MANIFEST_NAME, options)
+        .map_err(|e| format!("Failed to add manifest to ZIP: {}", e))?;
+    io::Write::write_all(&mut zip, json.as_bytes())
+        .map_err(|e| format!("Failed to add manifest to ZIP: {}", e))?;
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finalize ZIP: {}", e))?;
+    Ok(zip_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    fn read_entry(zip_path: &Path, name: &str) -> String {
+        let mut archive = zip::ZipArchive::new(fs::File::open(zip_path).unwrap()).unwrap();
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn test_collect_entries_recurses_directories() {
+        let temp = tempdir().unwrap();
+        let txt = temp.path().join("book.txt");
+        let pages = temp.path().join("book_hocr");
+        fs::write(&txt, "text").unwrap();
+        fs::create_dir(&pages).unwrap();
+        fs::write(pages.join("page-0002.hocr"), "b").unwrap();
+        fs::write(pages.join("page-0001.hocr"), "a").unwrap();
+
+        let entries = collect_entries(&[
+            txt.to_string_lossy().to_string(),
+            pages.to_string_lossy().to_string(),
+        ])
+        .unwrap();
+
+        let names: Vec<&str> = entries.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "book.txt",
+                "book_hocr/page-0001.hocr",
+                "book_hocr/page-0002.hocr"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_entries_missing_file() {
+        let result = collect_entries(&["/nonexistent/book.txt".to_string()]);
+        assert!(result.unwrap_err().contains("Output file not found"));
+    }
+
+    #[test]
+    fn test_collect_entries_rejects_duplicates() {
+        let temp = tempdir().unwrap();
+        let a = temp.path().join("a");
+        let b = temp.path().join("b");
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        fs::write(a.join("book.txt"), "1").unwrap();
+        fs::write(b.join("book.txt"), "2").unwrap();
+
+        let result = collect_entries(&[
+            a.join("book.txt").to_string_lossy().to_string(),
+            b.join("book.txt").to_string_lossy().to_string(),
+        ]);
+        assert!(result.unwrap_err().contains("Duplicate entry"));
+    }
+
+    #[tokio::test]
+    async fn test_package_outputs_writes_files_and_manifest() {
+        let temp = tempdir().unwrap();
+        let txt = temp.path().join("book.txt");
+        let json = temp.path().join("book.json");
+        fs::write(&txt, "نص الكتاب").unwrap();
+        fs::write(&json, "{}").unwrap();
+        let zip_path = temp.path().join("book.zip");
+
+        let result = package_outputs(
+            zip_path.to_string_lossy().to_string(),
+            vec![
+                txt.to_string_lossy().to_string(),
+                json.to_string_lossy().to_string(),
+            ],
+            Some("/docs/book.pdf".to_string()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result, zip_path.to_string_lossy());
+        assert_eq!(read_entry(&zip_path, "book.txt"), "نص الكتاب");
+
+        let manifest: PackageManifest =
+            serde_json::from_str(&read_entry(&zip_path, MANIFEST_NAME)).unwrap();
+        assert_eq!(manifest.version, MANIFEST_VERSION);
+        assert_eq!(manifest.source.as_deref(), Some("/docs/book.pdf"));
+        assert_eq!(manifest.files.len(), 2);
+        assert_eq!(manifest.files[0].path, "book.txt");
+        assert_eq!(manifest.files[0].size, "نص الكتاب".len() as u64);
+        assert_eq!(manifest.files[0].sha256, sha256_file(&txt).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_package_outputs_invalid_zip_path() {
+        let temp = tempdir().unwrap();
+        let txt = temp.path().join("book.txt");
+        fs::write(&txt, "text").unwrap();
+
+        let result = package_outputs(
+            "/nonexistent/dir/book.zip".to_string(),
+            vec![txt.to_string_lossy().to_string()],
+            None,
+        )
+        .await;
+        assert!(result.unwrap_err().contains("Failed to create ZIP"));
+    }
+}
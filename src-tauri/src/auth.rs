@@ -1,15 +1,20 @@
+use crate::app_paths::{self, Area};
+use crate::error::TahweelError;
+use crate::http::{self, execute_with_retry, OperationClass};
+use crate::settings;
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpListener;
 
 const CLIENT_ID: &str = "512416833080-808aqp20iith31t9rgtdmsgc53jp0sc2.apps.googleusercontent.com";
 const CLIENT_SECRET: &str = "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";
 const REDIRECT_URI: &str = "http://localhost:3027/";
-const AUTH_SCOPE: &str = "https://www.googleapis.com/auth/drive.file";
+pub(crate) const AUTH_SCOPE: &str = "https://www.googleapis.com/auth/drive.file";
 
 // Base URLs - can be overridden via environment variables for testing
-fn oauth_token_url() -> String {
+pub(crate) fn oauth_token_url() -> String {
     std::env::var("TAHWEEL_TEST_OAUTH_URL")
         .unwrap_or_else(|_| "https://oauth2.googleapis.com/token".to_string())
 }
@@ -114,14 +119,12 @@ const SUCCESS_HTML: &str = r#"<!DOCTYPE html>
 </html>"#;
 
 fn get_token_path() -> std::path::PathBuf {
-    let base = dirs::cache_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-    let dir = base.join("tahweel");
-    fs::create_dir_all(&dir).ok();
-    dir.join("token.json")
+    app_paths::scoped_dir(Area::Cache).join("token.json")
 }
 
 #[tauri::command]
-pub async fn start_oauth_flow(_app: tauri::AppHandle) -> Result<AuthTokens, String> {
+#[tracing::instrument(skip_all, err)]
+pub async fn start_oauth_flow(_app: tauri::AppHandle) -> Result<AuthTokens, TahweelError> {
     // Build authorization URL
     let auth_url = format!(
         "https://accounts.google.com/o/oauth2/v2/auth?\
@@ -139,56 +142,68 @@ pub async fn start_oauth_flow(_app: tauri::AppHandle) -> Result<AuthTokens, Stri
     // Start TCP server to receive callback (async)
     let listener = TcpListener::bind("127.0.0.1:3027")
         .await
-        .map_err(|e| format!("Failed to bind to port 3027: {}", e))?;
+        .map_err(|e| TahweelError::Io(format!("Failed to bind to port 3027: {}", e)))?;
 
     // Open browser AFTER binding the port (so the callback URL is ready)
-    open::that(&auth_url).map_err(|e| format!("Failed to open browser: {}", e))?;
-
-    // Wait for the OAuth callback
-    let code = loop {
-        let (mut stream, _) = listener
-            .accept()
-            .await
-            .map_err(|e| format!("Failed to accept connection: {}", e))?;
-
-        let (reader, mut writer) = stream.split();
-        let mut buf_reader = BufReader::new(reader);
-        let mut request_line = String::new();
-
-        buf_reader
-            .read_line(&mut request_line)
-            .await
-            .map_err(|e| format!("Failed to read request: {}", e))?;
-
-        // Check if this is the OAuth callback
-        if let Some(code) = extract_code(&request_line) {
-            // Send success response
-            let response = format!(
-                "HTTP/1.1 200 OK\r\n\
-                Content-Type: text/html; charset=utf-8\r\n\
-                Content-Length: {}\r\n\
-                Connection: close\r\n\
-                \r\n\
-                {}",
-                SUCCESS_HTML.len(),
-                SUCCESS_HTML
-            );
-            writer.write_all(response.as_bytes()).await.ok();
-            writer.flush().await.ok();
-            break code;
-        } else {
-            // Send 404 for other requests (like favicon.ico)
-            let response = "HTTP/1.1 404 Not Found\r\nConnection: close\r\n\r\n";
-            writer.write_all(response.as_bytes()).await.ok();
-            writer.flush().await.ok();
+    open::that(&auth_url)
+        .map_err(|e| TahweelError::Io(format!("Failed to open browser: {}", e)))?;
+
+    // Wait for the OAuth callback, giving up after the configured sign-in timeout
+    let login_timeout = Duration::from_secs(settings::current().auth.login_timeout_secs);
+    tracing::info!(
+        timeout_secs = login_timeout.as_secs(),
+        "Waiting for OAuth callback"
+    );
+    let wait_for_code = async {
+        loop {
+            let (mut stream, _) = listener
+                .accept()
+                .await
+                .map_err(|e| TahweelError::Io(format!("Failed to accept connection: {}", e)))?;
+
+            let (reader, mut writer) = stream.split();
+            let mut buf_reader = BufReader::new(reader);
+            let mut request_line = String::new();
+
+            buf_reader
+                .read_line(&mut request_line)
+                .await
+                .map_err(|e| TahweelError::Io(format!("Failed to read request: {}", e)))?;
+
+            // Check if this is the OAuth callback
+            if let Some(code) = extract_code(&request_line) {
+                // Send success response
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\n\
+                    Content-Type: text/html; charset=utf-8\r\n\
+                    Content-Length: {}\r\n\
+                    Connection: close\r\n\
+                    \r\n\
+                    {}",
+                    SUCCESS_HTML.len(),
+                    SUCCESS_HTML
+                );
+                writer.write_all(response.as_bytes()).await.ok();
+                writer.flush().await.ok();
+                break Ok::<String, TahweelError>(code);
+            } else {
+                // Send 404 for other requests (like favicon.ico)
+                let response = "HTTP/1.1 404 Not Found\r\nConnection: close\r\n\r\n";
+                writer.write_all(response.as_bytes()).await.ok();
+                writer.flush().await.ok();
+            }
         }
     };
+    let code = tokio::time::timeout(login_timeout, wait_for_code)
+        .await
+        .map_err(|_| TahweelError::Timeout("Timed out waiting for Google sign-in".to_string()))??;
 
     // Exchange code for tokens
     let tokens = exchange_code_for_tokens(&code).await?;
 
     // Store tokens
     store_tokens(&tokens)?;
+    tracing::info!("Signed in");
 
     Ok(tokens)
 }
@@ -214,27 +229,43 @@ fn extract_code(request_line: &str) -> Option<String> {
         .map(|(_, value)| value.to_string())
 }
 
-async fn exchange_code_for_tokens(code: &str) -> Result<AuthTokens, String> {
-    let client = reqwest::Client::new();
-    let response = client
-        .post(oauth_token_url())
-        .form(&[
-            ("code", code),
-            ("client_id", CLIENT_ID),
-            ("client_secret", CLIENT_SECRET),
-            ("redirect_uri", REDIRECT_URI),
-            ("grant_type", "authorization_code"),
-        ])
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    if !response.status().is_success() {
-        let text = response.text().await.unwrap_or_default();
-        return Err(format!("Token exchange failed: {}", text));
+/// A rejected token request means the grant is no longer valid (sign in again),
+/// unless Google itself is failing or throttling.
+fn token_error(status: reqwest::StatusCode, message: String) -> TahweelError {
+    if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        TahweelError::from_status(status, message)
+    } else {
+        TahweelError::Auth(message)
     }
+}
 
-    let token_response: TokenResponse = response.json().await.map_err(|e| e.to_string())?;
+async fn exchange_code_for_tokens(code: &str) -> Result<AuthTokens, TahweelError> {
+    let client = http::client(OperationClass::Api)?;
+    let token_response: TokenResponse = execute_with_retry(|| async {
+        let response = client
+            .post(oauth_token_url())
+            .form(&[
+                ("code", code),
+                ("client_id", CLIENT_ID),
+                ("client_secret", CLIENT_SECRET),
+                ("redirect_uri", REDIRECT_URI),
+                ("grant_type", "authorization_code"),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(token_error(
+                status,
+                format!("Token exchange failed: {}", text),
+            ));
+        }
+
+        Ok(response.json().await?)
+    })
+    .await?;
 
     Ok(AuthTokens {
         access_token: token_response.access_token,
@@ -243,10 +274,10 @@ async fn exchange_code_for_tokens(code: &str) -> Result<AuthTokens, String> {
     })
 }
 
-fn store_tokens(tokens: &AuthTokens) -> Result<(), String> {
+fn store_tokens(tokens: &AuthTokens) -> Result<(), TahweelError> {
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("System time error: {}", e))?
+        .map_err(|e| TahweelError::Internal(format!("System time error: {}", e)))?
         .as_secs();
 
     let stored = StoredTokens {
@@ -255,33 +286,40 @@ fn store_tokens(tokens: &AuthTokens) -> Result<(), String> {
         expires_at: now + tokens.expires_in,
     };
 
-    let json = serde_json::to_string_pretty(&stored).map_err(|e| e.to_string())?;
-    fs::write(get_token_path(), json).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&stored)?;
+    app_paths::write_atomic(&get_token_path(), json.as_bytes())?;
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn refresh_access_token(refresh_token: String) -> Result<AuthTokens, String> {
-    let client = reqwest::Client::new();
-    let response = client
-        .post(oauth_token_url())
-        .form(&[
-            ("refresh_token", refresh_token.as_str()),
-            ("client_id", CLIENT_ID),
-            ("client_secret", CLIENT_SECRET),
-            ("grant_type", "refresh_token"),
-        ])
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    if !response.status().is_success() {
-        let text = response.text().await.unwrap_or_default();
-        return Err(format!("Token refresh failed: {}", text));
-    }
+#[tracing::instrument(skip_all, err)]
+pub async fn refresh_access_token(refresh_token: String) -> Result<AuthTokens, TahweelError> {
+    let client = http::client(OperationClass::Api)?;
+    let token_response: TokenResponse = execute_with_retry(|| async {
+        let response = client
+            .post(oauth_token_url())
+            .form(&[
+                ("refresh_token", refresh_token.as_str()),
+                ("client_id", CLIENT_ID),
+                ("client_secret", CLIENT_SECRET),
+                ("grant_type", "refresh_token"),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(token_error(
+                status,
+                format!("Token refresh failed: {}", text),
+            ));
+        }
 
-    let token_response: TokenResponse = response.json().await.map_err(|e| e.to_string())?;
+        Ok(response.json().await?)
+    })
+    .await?;
 
     let tokens = AuthTokens {
         access_token: token_response.access_token,
@@ -290,23 +328,25 @@ pub async fn refresh_access_token(refresh_token: String) -> Result<AuthTokens, S
     };
 
     store_tokens(&tokens)?;
+    tracing::info!(expires_in = tokens.expires_in, "Access token refreshed");
 
     Ok(tokens)
 }
 
 #[tauri::command]
-pub async fn load_stored_tokens() -> Result<Option<AuthTokens>, String> {
+#[tracing::instrument(skip_all, err)]
+pub async fn load_stored_tokens() -> Result<Option<AuthTokens>, TahweelError> {
     let path = get_token_path();
     if !path.exists() {
         return Ok(None);
     }
 
-    let json = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let stored: StoredTokens = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    let json = fs::read_to_string(&path)?;
+    let stored: StoredTokens = serde_json::from_str(&json)?;
 
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("System time error: {}", e))?
+        .map_err(|e| TahweelError::Internal(format!("System time error: {}", e)))?
         .as_secs();
 
     // Return tokens with remaining time
@@ -320,30 +360,36 @@ pub async fn load_stored_tokens() -> Result<Option<AuthTokens>, String> {
 }
 
 #[tauri::command]
-pub async fn clear_auth_tokens() -> Result<(), String> {
+#[tracing::instrument(skip_all, err)]
+pub async fn clear_auth_tokens() -> Result<(), TahweelError> {
     let path = get_token_path();
     if path.exists() {
-        fs::remove_file(&path).map_err(|e| e.to_string())?;
+        fs::remove_file(&path)?;
     }
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_user_info(access_token: String) -> Result<UserInfo, String> {
-    let client = reqwest::Client::new();
-    let response = client
-        .get(userinfo_url())
-        .bearer_auth(&access_token)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    if !response.status().is_success() {
-        return Err("Failed to get user info".to_string());
-    }
+#[tracing::instrument(skip_all, err)]
+pub async fn get_user_info(access_token: String) -> Result<UserInfo, TahweelError> {
+    let client = http::client(OperationClass::Api)?;
+    execute_with_retry(|| async {
+        let response = client
+            .get(userinfo_url())
+            .bearer_auth(&access_token)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(TahweelError::from_status(
+                response.status(),
+                "Failed to get user info".to_string(),
+            ));
+        }
 
-    let info: UserInfo = response.json().await.map_err(|e| e.to_string())?;
-    Ok(info)
+        Ok(response.json::<UserInfo>().await?)
+    })
+    .await
 }
 
 #[cfg(test)]
@@ -837,7 +883,9 @@ mod tests {
 
         mock.assert_async().await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Token exchange failed"));
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("Token exchange failed"));
+        assert_eq!(err.kind(), "auth");
     }
 
     #[tokio::test]
@@ -956,7 +1004,40 @@ mod tests {
 
         mock.assert_async().await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Token refresh failed"));
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("Token refresh failed"));
+        assert_eq!(err.kind(), "auth");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_access_token_retries_server_errors() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_OAUTH_URL"]);
+        let _guard = TokenFileGuard::new();
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_OAUTH_URL", server.url());
+
+        let unavailable = server
+            .mock("POST", "/")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        let ok = server
+            .mock("POST", "/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"access_token": "after_retry", "expires_in": 3600, "token_type": "Bearer"}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let tokens = refresh_access_token("refresh".to_string()).await.unwrap();
+
+        unavailable.assert_async().await;
+        ok.assert_async().await;
+        assert_eq!(tokens.access_token, "after_retry");
     }
 
     #[tokio::test]
@@ -1026,7 +1107,10 @@ mod tests {
 
         mock.assert_async().await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Failed to get user info"));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Failed to get user info"));
     }
 
     #[test]
@@ -0,0 +1,408 @@
+use crate::auth::AUTH_SCOPE;
+use crate::error::TahweelError;
+use crate::google_drive::drive_files_url;
+use crate::http::{self, OperationClass};
+use serde::{Deserialize, Serialize};
+
+/// The only OCR backend: upload to Drive, convert to a Google Doc, export as text
+pub const GOOGLE_DRIVE: &str = "googleDrive";
+
+/// Tokens expiring sooner than this get a warning (the frontend refreshes them)
+const TOKEN_EXPIRY_WARNING_SECS: u64 = 300;
+
+fn tokeninfo_url() -> String {
+    std::env::var("TAHWEEL_TEST_TOKENINFO_URL")
+        .unwrap_or_else(|_| "https://oauth2.googleapis.com/tokeninfo".to_string())
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    /// Usable, but worth telling the user about
+    Warning,
+    /// Jobs on this backend will fail until it is fixed
+    Failed,
+}
+
+/// One diagnostic, with what to do about it when it is not `ok`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BackendCheck {
+    /// `token`, `scope`, or `api`
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl BackendCheck {
+    fn new(name: &str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn with_hint(mut self, hint: &str) -> Self {
+        self.hint = Some(hint.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BackendHealth {
+    pub backend: String,
+    /// No check failed
+    pub usable: bool,
+    pub checks: Vec<BackendCheck>,
+}
+
+impl BackendHealth {
+    fn new(backend: &str, checks: Vec<BackendCheck>) -> Self {
+        Self {
+            backend: backend.to_string(),
+            usable: checks.iter().all(|c| c.status != CheckStatus::Failed),
+            checks,
+        }
+    }
+}
+
+/// Response of Google's tokeninfo endpoint (numbers come as strings)
+#[derive(Debug, Deserialize)]
+struct TokenInfo {
+    #[serde(default)]
+    scope: String,
+    expires_in: Option<String>,
+}
+
+/// Whether the granted scopes let us create and read files in Drive
+pub fn has_drive_scope(scopes: &str) -> bool {
+    scopes
+        .split_whitespace()
+        .any(|scope| scope == AUTH_SCOPE || scope == "https://www.googleapis.com/auth/drive")
+}
+
+/// Token validity, expiry, and granted scopes
+async fn check_token(
+    client: &reqwest::Client,
+    url: &str,
+    access_token: &str,
+) -> Result<Vec<BackendCheck>, TahweelError> {
+    let response = client
+        .get(url)
+        .query(&[("access_token", access_token)])
+        .send()
+        .await?;
+    // tokeninfo answers 400 for expired or revoked tokens
+    if matches!(response.status().as_u16(), 400 | 401) {
+        return Ok(vec![BackendCheck::new(
+            "token",
+            CheckStatus::Failed,
+            "The access token is expired or was revoked",
+        )
+        .with_hint("Sign out and sign in to Google Drive again")]);
+    }
+    if !response.status().is_success() {
+        return Err(TahweelError::from_status(
+            response.status(),
+            "Failed to check the access token".to_string(),
+        ));
+    }
+    let info: TokenInfo = response.json().await?;
+
+    let expires_in = info.expires_in.and_then(|s| s.parse::<u64>().ok());
+    let token = match expires_in {
+        Some(secs) if secs < TOKEN_EXPIRY_WARNING_SECS => BackendCheck::new(
+            "token",
+            CheckStatus::Warning,
+            format!("The access token expires in {} seconds", secs),
+        )
+        .with_hint("It is refreshed automatically; sign in again if that fails"),
+        _ => BackendCheck::new("token", CheckStatus::Ok, "The access token is valid"),
+    };
+    let scope = if has_drive_scope(&info.scope) {
+        BackendCheck::new("scope", CheckStatus::Ok, "Google Drive access is granted")
+    } else {
+        BackendCheck::new(
+            "scope",
+            CheckStatus::Failed,
+            "Google Drive access was not granted",
+        )
+        .with_hint("Sign out, sign in again, and allow access to Google Drive files")
+    };
+    Ok(vec![token, scope])
+}
+
+/// A minimal authorized Drive request
+async fn check_drive_api(client: &reqwest::Client, url: &str, access_token: &str) -> BackendCheck {
+    let response = client
+        .get(url)
+        .bearer_auth(access_token)
+        .query(&[("pageSize", "1"), ("fields", "files(id)")])
+        .send()
+        .await;
+    let status = match response {
+        Ok(response) => response.status(),
+        Err(e) => {
+            return BackendCheck::new(
+                "api",
+                CheckStatus::Failed,
+                TahweelError::from(e).to_string(),
+            )
+            .with_hint("Check the internet connection and proxy settings")
+        }
+    };
+    match status.as_u16() {
+        200..=299 => BackendCheck::new("api", CheckStatus::Ok, "Google Drive is reachable"),
+        401 => BackendCheck::new(
+            "api",
+            CheckStatus::Failed,
+            "Google Drive rejected the access token",
+        )
+        .with_hint("Sign out and sign in to Google Drive again"),
+        403 => BackendCheck::new(
+            "api",
+            CheckStatus::Failed,
+            "Google Drive refused access (403)",
+        )
+        .with_hint("The Drive API may be disabled or the daily quota used up; try again later"),
+        429 => BackendCheck::new(
+            "api",
+            CheckStatus::Warning,
+            "Google Drive is rate limiting requests",
+        )
+        .with_hint("Jobs will be slower; lower the OCR concurrency if this persists"),
+        code => BackendCheck::new(
+            "api",
+            CheckStatus::Failed,
+            format!("Google Drive answered with HTTP {}", code),
+        )
+        .with_hint("Google may be having problems; try again later"),
+    }
+}
+
+async fn check_google_drive(
+    client: &reqwest::Client,
+    tokeninfo_url: &str,
+    files_url: &str,
+    access_token: Option<&str>,
+) -> Vec<BackendCheck> {
+    let Some(token) = access_token.filter(|t| !t.is_empty()) else {
+        return vec![
+            BackendCheck::new("token", CheckStatus::Failed, "Not signed in")
+                .with_hint("Sign in to Google Drive"),
+        ];
+    };
+    let mut checks = match check_token(client, tokeninfo_url, token).await {
+        Ok(checks) => checks,
+        // Not being able to ask does not make the token bad; the API check decides
+        Err(e) => vec![BackendCheck::new(
+            "token",
+            CheckStatus::Warning,
+            format!("Could not check the access token: {}", e),
+        )],
+    };
+    if checks.iter().any(|c| c.status == CheckStatus::Failed) {
+        return checks;
+    }
+    checks.push(check_drive_api(client, files_url, token).await);
+    checks
+}
+
+/// Check that an OCR backend is usable before a job starts: for Google Drive, a
+/// valid token with Drive access and a reachable Drive API. Failed checks carry
+/// a hint the UI shows to the user.
+#[tauri::command]
+#[tracing::instrument(skip(access_token), err)]
+pub async fn check_backend(
+    backend_id: String,
+    access_token: Option<String>,
+) -> Result<BackendHealth, TahweelError> {
+    if backend_id != GOOGLE_DRIVE {
+        return Err(TahweelError::InvalidInput(format!(
+            "Unknown OCR backend: {}",
+            backend_id
+        )));
+    }
+    let client = http::client(OperationClass::Api)?;
+    let checks = check_google_drive(
+        &client,
+        &tokeninfo_url(),
+        &drive_files_url(),
+        access_token.as_deref(),
+    )
+    .await;
+    let health = BackendHealth::new(&backend_id, checks);
+    tracing::info!(usable = health.usable, "Backend checked");
+    Ok(health)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statuses(checks: &[BackendCheck]) -> Vec<(&str, CheckStatus)> {
+        checks.iter().map(|c| (c.name.as_str(), c.status)).collect()
+    }
+
+    #[test]
+    fn test_has_drive_scope() {
+        assert!(has_drive_scope(
+            "openid https://www.googleapis.com/auth/drive.file"
+        ));
+        assert!(has_drive_scope("https://www.googleapis.com/auth/drive"));
+        assert!(!has_drive_scope(
+            "https://www.googleapis.com/auth/drive.readonly email"
+        ));
+        assert!(!has_drive_scope(""));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_backend_is_rejected() {
+        let result = check_backend("tesseract".to_string(), None).await;
+        assert!(matches!(result, Err(TahweelError::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn test_missing_token_fails_without_requests() {
+        let client = reqwest::Client::new();
+        let checks =
+            check_google_drive(&client, "http://127.0.0.1:9", "http://127.0.0.1:9", None).await;
+        assert_eq!(statuses(&checks), vec![("token", CheckStatus::Failed)]);
+        assert!(checks[0].hint.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_healthy_drive() {
+        let mut server = mockito::Server::new_async().await;
+        let tokeninfo = server
+            .mock("GET", "/tokeninfo")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "access_token".into(),
+                "token".into(),
+            ))
+            .with_body(
+                r#"{"scope": "https://www.googleapis.com/auth/drive.file", "expires_in": "3400"}"#,
+            )
+            .create_async()
+            .await;
+        let files = server
+            .mock("GET", "/files")
+            .match_header("authorization", "Bearer token")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"files": []}"#)
+            .create_async()
+            .await;
+
+        let client = reqwest::Client::new();
+        let checks = check_google_drive(
+            &client,
+            &format!("{}/tokeninfo", server.url()),
+            &format!("{}/files", server.url()),
+            Some("token"),
+        )
+        .await;
+        tokeninfo.assert_async().await;
+        files.assert_async().await;
+        assert_eq!(
+            statuses(&checks),
+            vec![
+                ("token", CheckStatus::Ok),
+                ("scope", CheckStatus::Ok),
+                ("api", CheckStatus::Ok)
+            ]
+        );
+        assert!(BackendHealth::new(GOOGLE_DRIVE, checks).usable);
+    }
+
+    #[tokio::test]
+    async fn test_missing_scope_and_expiring_token() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/tokeninfo")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"scope": "email", "expires_in": "60"}"#)
+            .create_async()
+            .await;
+        let files = server
+            .mock("GET", "/files")
+            .match_query(mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+
+        let client = reqwest::Client::new();
+        let checks = check_google_drive(
+            &client,
+            &format!("{}/tokeninfo", server.url()),
+            &format!("{}/files", server.url()),
+            Some("token"),
+        )
+        .await;
+        files.assert_async().await;
+        assert_eq!(
+            statuses(&checks),
+            vec![
+                ("token", CheckStatus::Warning),
+                ("scope", CheckStatus::Failed)
+            ]
+        );
+        assert!(!BackendHealth::new(GOOGLE_DRIVE, checks).usable);
+    }
+
+    #[tokio::test]
+    async fn test_revoked_token() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/tokeninfo")
+            .match_query(mockito::Matcher::Any)
+            .with_status(400)
+            .with_body(r#"{"error": "invalid_token"}"#)
+            .create_async()
+            .await;
+
+        let client = reqwest::Client::new();
+        let checks = check_google_drive(
+            &client,
+            &format!("{}/tokeninfo", server.url()),
+            &format!("{}/files", server.url()),
+            Some("token"),
+        )
+        .await;
+        assert_eq!(statuses(&checks), vec![("token", CheckStatus::Failed)]);
+        assert!(checks[0].hint.as_deref().unwrap().contains("sign in"));
+    }
+
+    #[tokio::test]
+    async fn test_drive_api_errors() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/files")
+            .match_query(mockito::Matcher::Any)
+            .with_status(403)
+            .create_async()
+            .await;
+        let client = reqwest::Client::new();
+        let check = check_drive_api(&client, &format!("{}/files", server.url()), "token").await;
+        assert_eq!(check.status, CheckStatus::Failed);
+        assert!(check.hint.unwrap().contains("quota"));
+
+        let unreachable = check_drive_api(&client, "http://127.0.0.1:9/files", "token").await;
+        assert_eq!(unreachable.status, CheckStatus::Failed);
+        assert!(unreachable.hint.unwrap().contains("internet"));
+    }
+
+    #[test]
+    fn test_health_serialization() {
+        let health = BackendHealth::new(
+            GOOGLE_DRIVE,
+            vec![BackendCheck::new("api", CheckStatus::Warning, "slow")],
+        );
+        let value = serde_json::to_value(&health).unwrap();
+        assert_eq!(value["backend"], "googleDrive");
+        assert_eq!(value["usable"], true);
+        assert_eq!(value["checks"][0]["status"], "warning");
+    }
+}
@@ -0,0 +1,311 @@
+use crate::error::TahweelError;
+use crate::output::{build_page_records, sha256_file, PageInput};
+use crate::path_policy::{self, PathAccess};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Version of the Baheth ingestion schema written by this exporter
+pub const BAHETH_SCHEMA_VERSION: u32 = 1;
+
+fn default_language() -> String {
+    "ar".to_string()
+}
+
+/// Book metadata entered by the user for a Baheth/iEasyBooks contribution
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BookMetadata {
+    pub title: String,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edition: Option<String>,
+    #[serde(
+        rename = "publicationYear",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub publication_year: Option<i32>,
+    /// ISO 639-1 code of the book's main language
+    #[serde(default = "default_language")]
+    pub language: String,
+    #[serde(default)]
+    pub categories: Vec<String>,
+}
+
+/// The scanned source the text was recognized from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BahethSource {
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    /// SHA-256 of the source file; `None` when it is no longer readable
+    pub sha256: Option<String>,
+    #[serde(rename = "pageCount")]
+    pub page_count: u32,
+}
+
+/// Application that produced the export
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BahethGenerator {
+    pub name: String,
+    pub version: String,
+}
+
+/// One page of the exported book
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BahethPage {
+    pub number: u32,
+    #[serde(rename = "sourcePage")]
+    pub source_page: u32,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footnotes: Option<String>,
+    /// SHA-256 of `text`, so ingestion can detect pages changed after export
+    #[serde(rename = "textSha256")]
+    pub text_sha256: String,
+    #[serde(rename = "imageSha256")]
+    pub image_sha256: Option<String>,
+}
+
+/// Top-level structure of a Baheth export file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BahethExport {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: u32,
+    pub book: BookMetadata,
+    pub source: BahethSource,
+    pub generator: BahethGenerator,
+    pub pages: Vec<BahethPage>,
+    /// SHA-256 over the page text checksums in order, identifying the whole text
+    #[serde(rename = "contentSha256")]
+    pub content_sha256: String,
+}
+
+fn sha256_text(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+/// Trim metadata fields and drop empty ones; the title is required
+fn normalize_metadata(mut metadata: BookMetadata) -> Result<BookMetadata, TahweelError> {
+    metadata.title = metadata.title.trim().to_string();
+    if metadata.title.is_empty() {
+        return Err(TahweelError::InvalidInput(
+            "A book title is required for a Baheth export".to_string(),
+        ));
+    }
+    let clean_list = |list: Vec<String>| -> Vec<String> {
+        list.into_iter()
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    };
+    let clean = |value: Option<String>| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    metadata.authors = clean_list(metadata.authors);
+    metadata.categories = clean_list(metadata.categories);
+    metadata.publisher = clean(metadata.publisher);
+    metadata.edition = clean(metadata.edition);
+    metadata.language = metadata.language.trim().to_lowercase();
+    if metadata.language.is_empty() {
+        metadata.language = default_language();
+    }
+    Ok(metadata)
+}
+
+/// Build the export document for a book's OCR results
+pub fn build_export(
+    source_path: &str,
+    metadata: BookMetadata,
+    pages: Vec<PageInput>,
+) -> Result<BahethExport, TahweelError> {
+    let book = normalize_metadata(metadata)?;
+    let source = Path::new(source_path);
+    let pages: Vec<BahethPage> = build_page_records(pages)
+        .into_iter()
+        .map(|record| BahethPage {
+            number: record.page,
+            source_page: record.source_page,
+            text_sha256: sha256_text(&record.text),
+            text: record.text,
+            footnotes: record.footnotes,
+            image_sha256: record.image_checksum,
+        })
+        .collect();
+
+    let mut content = Sha256::new();
+    for page in &pages {
+        content.update(page.text_sha256.as_bytes());
+    }
+
+    Ok(BahethExport {
+        schema_version: BAHETH_SCHEMA_VERSION,
+        book,
+        source: BahethSource {
+            file_name: source
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            sha256: source.is_file().then(|| sha256_file(source).ok()).flatten(),
+            page_count: pages.len() as u32,
+        },
+        generator: BahethGenerator {
+            name: "tahweel".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+        pages,
+        content_sha256: format!("{:x}", content.finalize()),
+    })
+}
+
+/// Write `<output_path>.baheth.json` in the Baheth library ingestion schema:
+/// book metadata, page texts, and checksums. Returns the written path.
+#[tauri::command]
+pub async fn write_baheth_export(
+    output_path: String,
+    source_path: String,
+    metadata: BookMetadata,
+    pages: Vec<PageInput>,
+) -> Result<String, TahweelError> {
+    let path = format!("{}.baheth.json", output_path);
+    let target = path_policy::check(Path::new(&path), PathAccess::Write)?;
+    let export = build_export(&source_path, metadata, pages)?;
+    let json =
+        serde_json::to_string_pretty(&export).map_err(|e| TahweelError::Internal(e.to_string()))?;
+    fs::write(&target, json)
+        .map_err(|e| TahweelError::Io(format!("Failed to write Baheth export: {}", e)))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn metadata(title: &str) -> BookMetadata {
+        serde_json::from_value(serde_json::json!({ "title": title })).unwrap()
+    }
+
+    fn page(text: &str) -> PageInput {
+        serde_json::from_value(serde_json::json!({ "text": text })).unwrap()
+    }
+
+    #[test]
+    fn test_metadata_defaults() {
+        let book = metadata("كتاب");
+        assert_eq!(book.language, "ar");
+        assert!(book.authors.is_empty());
+        assert_eq!(book.publication_year, None);
+    }
+
+    #[test]
+    fn test_normalize_metadata() {
+        let book = normalize_metadata(BookMetadata {
+            title: "  رياض الصالحين ".to_string(),
+            authors: vec!["النووي".to_string(), " ".to_string()],
+            publisher: Some("".to_string()),
+            edition: Some(" الأولى ".to_string()),
+            publication_year: Some(1990),
+            language: " AR ".to_string(),
+            categories: vec![],
+        })
+        .unwrap();
+        assert_eq!(book.title, "رياض الصالحين");
+        assert_eq!(book.authors, vec!["النووي"]);
+        assert_eq!(book.publisher, None);
+        assert_eq!(book.edition.as_deref(), Some("الأولى"));
+        assert_eq!(book.language, "ar");
+
+        let err = normalize_metadata(metadata("  ")).unwrap_err();
+        assert!(matches!(err, TahweelError::InvalidInput(_)));
+    }
+
+    #[test]
+    fn test_build_export_checksums() {
+        let temp = tempdir().unwrap();
+        let source = temp.path().join("book.pdf");
+        fs::write(&source, b"%PDF").unwrap();
+
+        let mut first = page(" الصفحة الأولى ");
+        first.footnotes = Some("١. حاشية".to_string());
+        let export = build_export(
+            &source.to_string_lossy(),
+            metadata("كتاب"),
+            vec![first, page("الصفحة الثانية")],
+        )
+        .unwrap();
+
+        assert_eq!(export.schema_version, BAHETH_SCHEMA_VERSION);
+        assert_eq!(export.source.file_name, "book.pdf");
+        assert_eq!(export.source.sha256, sha256_file(&source).ok());
+        assert_eq!(export.source.page_count, 2);
+        assert_eq!(export.pages[0].text, "الصفحة الأولى");
+        assert_eq!(export.pages[0].text_sha256, sha256_text("الصفحة الأولى"));
+        assert_eq!(export.pages[0].footnotes.as_deref(), Some("١. حاشية"));
+        assert_eq!(export.pages[1].number, 2);
+
+        let mut content = Sha256::new();
+        content.update(export.pages[0].text_sha256.as_bytes());
+        content.update(export.pages[1].text_sha256.as_bytes());
+        assert_eq!(export.content_sha256, format!("{:x}", content.finalize()));
+    }
+
+    #[test]
+    fn test_build_export_missing_source() {
+        let export =
+            build_export("/nonexistent/book.pdf", metadata("كتاب"), vec![page("نص")]).unwrap();
+        assert_eq!(export.source.file_name, "book.pdf");
+        assert_eq!(export.source.sha256, None);
+    }
+
+    #[tokio::test]
+    async fn test_write_baheth_export() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("book").to_string_lossy().to_string();
+
+        let path = write_baheth_export(
+            base.clone(),
+            "/nonexistent/book.pdf".to_string(),
+            metadata("كتاب"),
+            vec![page("نص")],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(path, format!("{}.baheth.json", base));
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["schemaVersion"], 1);
+        assert_eq!(json["book"]["title"], "كتاب");
+        assert_eq!(json["generator"]["name"], "tahweel");
+        assert_eq!(json["pages"][0]["text"], "نص");
+        assert!(json["pages"][0]["textSha256"].is_string());
+        assert!(json["pages"][0].get("footnotes").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_write_baheth_export_outside_allowed_paths_is_rejected() {
+        let result = write_baheth_export(
+            "/tahweel-denied/book".to_string(),
+            "book.pdf".to_string(),
+            metadata("كتاب"),
+            vec![page("نص")],
+        )
+        .await;
+        assert_eq!(result.unwrap_err().kind(), "pathNotAllowed");
+    }
+
+    #[tokio::test]
+    async fn test_write_baheth_export_requires_title() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("book").to_string_lossy().to_string();
+        let result = write_baheth_export(base, "book.pdf".to_string(), metadata(""), vec![]).await;
+        assert!(matches!(result, Err(TahweelError::InvalidInput(_))));
+        assert!(!temp.path().join("book.baheth.json").exists());
+    }
+}
@@ -0,0 +1,295 @@
+use crate::error::TahweelError;
+use crate::google_drive;
+use crate::long_path;
+use crate::path_policy;
+use image::{ImageFormat, RgbaImage};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Exit code of `sh` when the command it runs is not installed
+const SHELL_COMMAND_NOT_FOUND: i32 = 127;
+/// The snipping overlay gives no signal when dismissed: a capture that has not
+/// reached the clipboard by then counts as cancelled
+const CLIPBOARD_CAPTURE_TIMEOUT: Duration = Duration::from_secs(60);
+const CLIPBOARD_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Interactive region screenshot commands for `os` (a `std::env::consts::OS` value),
+/// in order of preference. Each lets the user drag a region and writes it to `output`
+/// as PNG; a non-zero exit or a missing file means the selection was cancelled.
+pub fn region_capture_commands(output: &Path, os: &str, wayland: bool) -> Vec<Command> {
+    match os {
+        "macos" => {
+            let mut command = Command::new("screencapture");
+            command.args(["-i", "-x"]).arg(output);
+            vec![command]
+        }
+        "linux" | "freebsd" | "openbsd" | "netbsd" | "dragonfly" => {
+            let mut commands = Vec::new();
+
+            let mut gnome = Command::new("gnome-screenshot");
+            gnome.args(["-a", "-f"]).arg(output);
+            commands.push(gnome);
+
+            let mut spectacle = Command::new("spectacle");
+            spectacle.args(["-b", "-n", "-r", "-o"]).arg(output);
+            commands.push(spectacle);
+
+            if wayland {
+                // wlroots compositors: slurp selects the region, grim captures it
+                let mut grim = Command::new("sh");
+                grim.args(["-c", r#"grim -g "$(slurp)" "$1""#, "sh"])
+                    .arg(output);
+                commands.push(grim);
+            } else {
+                let mut maim = Command::new("maim");
+                maim.arg("-s").arg(output);
+                commands.push(maim);
+
+                let mut scrot = Command::new("scrot");
+                scrot.args(["-s", "-o"]).arg(output);
+                commands.push(scrot);
+            }
+            commands
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Command opening the system snipping overlay for `os`, which puts the selected
+/// region on the clipboard instead of writing a file: `ms-screenclip:` on Windows
+/// 10 and later (Snip & Sketch, or the Snipping Tool on Windows 11)
+pub fn clipboard_capture_command(os: &str) -> Option<Command> {
+    match os {
+        "windows" => {
+            let mut command = Command::new("explorer.exe");
+            command.arg("ms-screenclip:");
+            Some(command)
+        }
+        _ => None,
+    }
+}
+
+/// Size and RGBA pixels of an image on the clipboard
+#[derive(Debug, PartialEq)]
+struct ClipboardImage {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+fn read_clipboard_image(app: &AppHandle) -> Option<ClipboardImage> {
+    let image = app.clipboard().read_image().ok()?;
+    Some(ClipboardImage {
+        width: image.width(),
+        height: image.height(),
+        rgba: image.rgba().to_vec(),
+    })
+}
+
+/// Poll `read` until it returns an image other than `before`; `None` after `timeout`
+fn wait_for_new_image(
+    before: Option<ClipboardImage>,
+    mut read: impl FnMut() -> Option<ClipboardImage>,
+    timeout: Duration,
+    interval: Duration,
+) -> Option<ClipboardImage> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        std::thread::sleep(interval);
+        match read() {
+            Some(image) if before.as_ref() != Some(&image) => return Some(image),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Open the snipping overlay with `command` and save the region it puts on the
+/// clipboard to `output`. Returns `Ok(false)` when nothing was captured in time.
+fn capture_through_clipboard(
+    mut command: Command,
+    output: &Path,
+    app: &AppHandle,
+) -> Result<bool, TahweelError> {
+    let before = read_clipboard_image(app);
+    // explorer.exe exits as soon as the overlay is open, often with a non-zero code
+    command.status().map_err(|e| {
+        if e.kind() == io::ErrorKind::NotFound {
+            TahweelError::NotFound("No screen capture tool found".to_string())
+        } else {
+            TahweelError::Io(format!("Failed to start screen capture: {}", e))
+        }
+    })?;
+    let Some(image) = wait_for_new_image(
+        before,
+        || read_clipboard_image(app),
+        CLIPBOARD_CAPTURE_TIMEOUT,
+        CLIPBOARD_POLL_INTERVAL,
+    ) else {
+        return Ok(false);
+    };
+    let image = RgbaImage::from_raw(image.width, image.height, image.rgba)
+        .ok_or_else(|| TahweelError::InvalidInput("Clipboard image data is invalid".to_string()))?;
+    image
+        .save_with_format(output, ImageFormat::Png)
+        .map_err(|e| TahweelError::Io(format!("Failed to save screen capture: {}", e)))?;
+    Ok(true)
+}
+
+/// Let the user select a screen region and save it to `output`.
+/// Returns `Ok(false)` when the selection was cancelled.
+fn capture_region(output: &Path, app: &AppHandle) -> Result<bool, TahweelError> {
+    if let Some(command) = clipboard_capture_command(std::env::consts::OS) {
+        return capture_through_clipboard(command, output, app);
+    }
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    for mut command in region_capture_commands(output, std::env::consts::OS, wayland) {
+        match command.status() {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                return Err(TahweelError::Io(format!(
+                    "Failed to start screen capture: {}",
+                    e
+                )))
+            }
+            Ok(status) if status.code() == Some(SHELL_COMMAND_NOT_FOUND) => continue,
+            Ok(status) => return Ok(status.success() && output.is_file()),
+        }
+    }
+    Err(TahweelError::NotFound(
+        "No screen capture tool found".to_string(),
+    ))
+}
+
+/// Path in the workspace for a new capture
+fn capture_path() -> PathBuf {
+    path_policy::workspace().join(format!("tahweel-capture-{}.png", uuid::Uuid::new_v4()))
+}
+
+/// Capture a user-selected screen region and OCR it. Returns `None` when the
+/// user cancels the selection. The capture is removed from the workspace afterwards.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn ocr_screen_region(
+    access_token: String,
+    app: AppHandle,
+) -> Result<Option<String>, TahweelError> {
+    let output = capture_path();
+    if let Some(dir) = output.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let target = output.clone();
+    let captured = tauri::async_runtime::spawn_blocking(move || capture_region(&target, &app))
+        .await
+        .map_err(|e| TahweelError::Internal(format!("Screen capture failed: {}", e)))??;
+    if !captured {
+        let _ = fs::remove_file(&output);
+        tracing::info!("Screen capture cancelled");
+        return Ok(None);
+    }
+
+    let path = long_path::path_string(&output)?;
+    let text = google_drive::ocr_image_file(&path, &access_token).await;
+    let _ = fs::remove_file(&output);
+    Ok(Some(text?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn programs(commands: &[Command]) -> Vec<String> {
+        commands
+            .iter()
+            .map(|c| c.get_program().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_macos_uses_interactive_screencapture() {
+        let commands = region_capture_commands(Path::new("/tmp/c.png"), "macos", false);
+        assert_eq!(programs(&commands), vec!["screencapture"]);
+        let args: Vec<_> = commands[0].get_args().collect();
+        assert_eq!(args, vec!["-i", "-x", "/tmp/c.png"]);
+    }
+
+    #[test]
+    fn test_linux_tools_depend_on_session() {
+        let x11 = region_capture_commands(Path::new("/tmp/c.png"), "linux", false);
+        assert_eq!(
+            programs(&x11),
+            vec!["gnome-screenshot", "spectacle", "maim", "scrot"]
+        );
+
+        let wayland = region_capture_commands(Path::new("/tmp/c.png"), "linux", true);
+        assert_eq!(
+            programs(&wayland),
+            vec!["gnome-screenshot", "spectacle", "sh"]
+        );
+        let args: Vec<_> = wayland[2].get_args().collect();
+        assert_eq!(args.last().unwrap().to_str().unwrap(), "/tmp/c.png");
+    }
+
+    #[test]
+    fn test_output_path_is_passed_as_its_own_argument() {
+        let path = Path::new("/tmp/مجلد فيه مسافات/c.png");
+        for command in region_capture_commands(path, "linux", false) {
+            assert_eq!(command.get_args().last().unwrap(), path.as_os_str());
+        }
+    }
+
+    #[test]
+    fn test_windows_opens_the_snipping_overlay() {
+        let command = clipboard_capture_command("windows").unwrap();
+        assert_eq!(command.get_program(), "explorer.exe");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, vec!["ms-screenclip:"]);
+
+        assert!(clipboard_capture_command("macos").is_none());
+        assert!(clipboard_capture_command("linux").is_none());
+    }
+
+    fn image(pixel: u8) -> ClipboardImage {
+        ClipboardImage {
+            width: 1,
+            height: 1,
+            rgba: vec![pixel, pixel, pixel, 255],
+        }
+    }
+
+    #[test]
+    fn test_waits_for_a_new_clipboard_image() {
+        let mut reads = vec![Some(image(0)), None, Some(image(9))].into_iter();
+        let captured = wait_for_new_image(
+            Some(image(0)),
+            || reads.next().flatten(),
+            Duration::from_secs(5),
+            Duration::from_millis(1),
+        );
+        assert_eq!(captured, Some(image(9)));
+    }
+
+    #[test]
+    fn test_unchanged_clipboard_counts_as_cancelled() {
+        let captured = wait_for_new_image(
+            Some(image(0)),
+            || Some(image(0)),
+            Duration::from_millis(20),
+            Duration::from_millis(1),
+        );
+        assert_eq!(captured, None);
+    }
+
+    #[test]
+    fn test_capture_path_is_in_workspace() {
+        let path = capture_path();
+        assert!(path.starts_with(path_policy::workspace()));
+        assert_eq!(path.extension().unwrap(), "png");
+    }
+}
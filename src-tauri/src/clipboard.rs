@@ -0,0 +1,243 @@
+use crate::error::TahweelError;
+use crate::google_drive;
+use crate::long_path;
+use crate::path_policy;
+use image::{imageops, ImageFormat, Rgb, RgbImage};
+use std::fs;
+use std::io::Read;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Output files are read in chunks of this size
+const CLIPBOARD_CHUNK_SIZE: usize = 1024 * 1024;
+/// Largest text accepted for the clipboard (a very large book is ~20 MB of text)
+const MAX_CLIPBOARD_BYTES: usize = 64 * 1024 * 1024;
+/// Clipboard images narrower than this are upscaled before OCR (small
+/// screenshots of UI text are otherwise read poorly)
+const MIN_OCR_WIDTH: u32 = 1000;
+/// Largest upscaling factor for small clipboard images
+const MAX_UPSCALE: u32 = 4;
+
+/// Read an output file for the clipboard in fixed-size chunks, failing as soon as
+/// it exceeds `max_bytes` instead of loading an arbitrarily large file into memory.
+pub fn read_text_chunked(path: &str, max_bytes: usize) -> Result<String, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let mut bytes = Vec::new();
+    let mut chunk = vec![0u8; CLIPBOARD_CHUNK_SIZE];
+
+    loop {
+        let read = file
+            .read(&mut chunk)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        if read == 0 {
+            break;
+        }
+        if bytes.len() + read > max_bytes {
+            return Err(format!(
+                "Text is too large for the clipboard (limit {} MB)",
+                max_bytes / (1024 * 1024)
+            ));
+        }
+        bytes.extend_from_slice(&chunk[..read]);
+    }
+
+    String::from_utf8(bytes).map_err(|_| format!("File is not valid UTF-8 text: {}", path))
+}
+
+/// Resolve the text to copy from either an inline string or an output file path
+pub fn resolve_clipboard_text(
+    text: Option<String>,
+    output_path: Option<String>,
+) -> Result<String, String> {
+    match (text, output_path) {
+        (Some(text), None) => {
+            if text.len() > MAX_CLIPBOARD_BYTES {
+                return Err(format!(
+                    "Text is too large for the clipboard (limit {} MB)",
+                    MAX_CLIPBOARD_BYTES / (1024 * 1024)
+                ));
+            }
+            Ok(text)
+        }
+        (None, Some(path)) => read_text_chunked(&path, MAX_CLIPBOARD_BYTES),
+        _ => Err("Provide either text or an output path to copy".to_string()),
+    }
+}
+
+/// Copy text to the system clipboard. Pass `output_path` to copy a whole output
+/// file from disk without sending the text through the webview.
+/// Returns the number of characters copied.
+#[tauri::command]
+pub async fn copy_text_to_clipboard(
+    text: Option<String>,
+    output_path: Option<String>,
+    app: AppHandle,
+) -> Result<usize, String> {
+    let text = resolve_clipboard_text(text, output_path)?;
+    let length = text.chars().count();
+    app.clipboard()
+        .write_text(text)
+        .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
+    Ok(length)
+}
+
+/// Turn clipboard RGBA pixels into an image ready for OCR: transparency is
+/// flattened onto white (so dark text on a transparent background stays
+/// readable) and small images are upscaled.
+pub fn normalize_clipboard_image(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<RgbImage, TahweelError> {
+    if width == 0 || height == 0 || rgba.len() != width as usize * height as usize * 4 {
+        return Err(TahweelError::InvalidInput(
+            "Clipboard image data is invalid".to_string(),
+        ));
+    }
+
+    let flattened = RgbImage::from_fn(width, height, |x, y| {
+        let i = (y as usize * width as usize + x as usize) * 4;
+        let alpha = u32::from(rgba[i + 3]);
+        let blend = |c: u8| ((u32::from(c) * alpha + 255 * (255 - alpha)) / 255) as u8;
+        Rgb([blend(rgba[i]), blend(rgba[i + 1]), blend(rgba[i + 2])])
+    });
+
+    if width >= MIN_OCR_WIDTH {
+        return Ok(flattened);
+    }
+    let factor = ((MIN_OCR_WIDTH + width - 1) / width).min(MAX_UPSCALE);
+    Ok(imageops::resize(
+        &flattened,
+        width * factor,
+        height * factor,
+        imageops::FilterType::CatmullRom,
+    ))
+}
+
+/// OCR the image on the system clipboard and return its text. With `copy_result`,
+/// the text replaces the image on the clipboard.
+#[tauri::command]
+#[tracing::instrument(skip(access_token, app), err)]
+pub async fn ocr_clipboard_image(
+    access_token: String,
+    copy_result: Option<bool>,
+    app: AppHandle,
+) -> Result<String, TahweelError> {
+    let normalized = {
+        let image = app
+            .clipboard()
+            .read_image()
+            .map_err(|_| TahweelError::NotFound("No image on the clipboard".to_string()))?;
+        normalize_clipboard_image(image.rgba(), image.width(), image.height())?
+    };
+
+    let workspace = path_policy::workspace();
+    fs::create_dir_all(&workspace)?;
+    let path = workspace.join(format!("tahweel-clipboard-{}.png", uuid::Uuid::new_v4()));
+    normalized
+        .save_with_format(&path, ImageFormat::Png)
+        .map_err(|e| TahweelError::Io(format!("Failed to save clipboard image: {}", e)))?;
+
+    let text = google_drive::ocr_image_file(&long_path::path_string(&path)?, &access_token).await;
+    let _ = fs::remove_file(&path);
+    let text = text?;
+
+    if copy_result.unwrap_or(false) {
+        app.clipboard()
+            .write_text(text.clone())
+            .map_err(|e| TahweelError::Internal(format!("Failed to copy to clipboard: {}", e)))?;
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_inline_text() {
+        let text = resolve_clipboard_text(Some("نص".to_string()), None).unwrap();
+        assert_eq!(text, "نص");
+    }
+
+    #[test]
+    fn test_resolve_requires_exactly_one_source() {
+        assert!(resolve_clipboard_text(None, None).is_err());
+        assert!(resolve_clipboard_text(Some("a".into()), Some("b".into())).is_err());
+    }
+
+    #[test]
+    fn test_read_text_chunked_spans_multiple_chunks() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("book.txt");
+        let content = "كلمة ".repeat(CLIPBOARD_CHUNK_SIZE / 4);
+        fs::write(&path, &content).unwrap();
+
+        let text = resolve_clipboard_text(None, Some(path.to_string_lossy().to_string())).unwrap();
+        assert_eq!(text, content);
+    }
+
+    #[test]
+    fn test_read_text_chunked_enforces_limit() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("book.txt");
+        fs::write(&path, "x".repeat(100)).unwrap();
+
+        let result = read_text_chunked(&path.to_string_lossy(), 50);
+        assert!(result.unwrap_err().contains("too large"));
+    }
+
+    #[test]
+    fn test_read_text_chunked_rejects_binary() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("book.docx");
+        fs::write(&path, [0xff, 0xfe, 0x00]).unwrap();
+
+        let result = read_text_chunked(&path.to_string_lossy(), MAX_CLIPBOARD_BYTES);
+        assert!(result.unwrap_err().contains("not valid UTF-8"));
+    }
+
+    #[test]
+    fn test_normalize_flattens_transparency_onto_white() {
+        // Opaque black, fully transparent black, half-transparent red
+        let rgba = [0, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 128];
+        let image = normalize_clipboard_image(&rgba, 3, 1).unwrap();
+        assert_eq!(image.dimensions(), (3 * MAX_UPSCALE, MAX_UPSCALE));
+
+        let large: Vec<u8> = [0, 0, 0, 0].repeat(MIN_OCR_WIDTH as usize);
+        let image = normalize_clipboard_image(&large, MIN_OCR_WIDTH, 1).unwrap();
+        assert_eq!(image.get_pixel(0, 0), &Rgb([255, 255, 255]));
+
+        let mixed: Vec<u8> = [[0, 0, 0, 255], [255, 0, 0, 128]]
+            .repeat(MIN_OCR_WIDTH as usize / 2)
+            .concat();
+        let image = normalize_clipboard_image(&mixed, MIN_OCR_WIDTH, 1).unwrap();
+        assert_eq!(image.get_pixel(0, 0), &Rgb([0, 0, 0]));
+        assert_eq!(image.get_pixel(1, 0), &Rgb([255, 127, 127]));
+    }
+
+    #[test]
+    fn test_normalize_upscales_small_images() {
+        let rgba = [255u8; 400 * 50 * 4];
+        let image = normalize_clipboard_image(&rgba, 400, 50).unwrap();
+        assert_eq!(image.dimensions(), (1200, 150));
+
+        let rgba = [255u8; 10 * 10 * 4];
+        let image = normalize_clipboard_image(&rgba, 10, 10).unwrap();
+        assert_eq!(image.dimensions(), (10 * MAX_UPSCALE, 10 * MAX_UPSCALE));
+    }
+
+    #[test]
+    fn test_normalize_rejects_bad_buffers() {
+        assert!(normalize_clipboard_image(&[], 0, 0).is_err());
+        let err = normalize_clipboard_image(&[0; 7], 1, 2).unwrap_err();
+        assert_eq!(err.kind(), "invalidInput");
+    }
+
+    #[test]
+    fn test_read_text_chunked_missing_file() {
+        let result = read_text_chunked("/nonexistent/book.txt", MAX_CLIPBOARD_BYTES);
+        assert!(result.unwrap_err().contains("Failed to open"));
+    }
+}
@@ -0,0 +1,308 @@
+use crate::encryption;
+use crate::layout::PageLayout;
+use image::GenericImageView;
+use std::path::Path;
+
+/// Gutters are only searched for between these fractions of the page width,
+/// so page margins are never mistaken for column gaps
+const GUTTER_SEARCH_START: f64 = 0.15;
+const GUTTER_SEARCH_END: f64 = 0.85;
+/// Minimum gutter width as a fraction of the page width
+const MIN_GUTTER_RATIO: f64 = 0.02;
+/// Share of words (or dark pixels per image column) allowed to cross a gutter,
+/// tolerating titles and rules that span both columns
+const GUTTER_TOLERANCE: f64 = 0.05;
+/// Grayscale level below which a pixel counts as ink
+const INK_THRESHOLD: u8 = 128;
+
+/// Find gutters in a horizontal coverage profile: runs inside the central search area
+/// where coverage stays at or below `max_coverage`. Returns the center x of each gutter.
+pub fn find_gutters(profile: &[u32], max_coverage: u32) -> Vec<u32> {
+    let width = profile.len();
+    let start = (width as f64 * GUTTER_SEARCH_START) as usize;
+    let end = (width as f64 * GUTTER_SEARCH_END) as usize;
+    let min_width = ((width as f64 * MIN_GUTTER_RATIO) as usize).max(1);
+
+    let mut gutters = Vec::new();
+    let mut run_start: Option<usize> = None;
+    // A trailing sentinel closes a run that reaches the end of the search area
+    let search = profile[start..end.min(width)]
+        .iter()
+        .map(|&coverage| coverage <= max_coverage)
+        .chain(std::iter::once(false));
+    for (offset, empty) in search.enumerate() {
+        let x = start + offset;
+        match (empty, run_start) {
+            (true, None) => run_start = Some(x),
+            (false, Some(s)) => {
+                if x - s >= min_width {
+                    gutters.push(((s + x) / 2) as u32);
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    gutters
+}
+
+/// Split the page's words into columns and return the text in reading order:
+/// columns right-to-left for RTL pages, lines top-to-bottom within each column.
+/// Pages without geometry or without a gutter return their text unchanged.
+pub fn order_columns_text(layout: &PageLayout, rtl: bool) -> String {
+    if !layout.has_geometry() || layout.width == 0 {
+        return layout.text.clone();
+    }
+
+    let mut profile = vec![0u32; layout.width as usize];
+    for word in &layout.words {
+        let end = word.bbox.right().min(layout.width) as usize;
+        for coverage in &mut profile[(word.bbox.x as usize).min(end)..end] {
+            *coverage += 1;
+        }
+    }
+    let tolerance = (layout.words.len() as f64 * GUTTER_TOLERANCE) as u32;
+    let gutters = find_gutters(&profile, tolerance);
+    if gutters.is_empty() {
+        return layout.text.clone();
+    }
+
+    let mut columns: Vec<PageLayout> = (0..=gutters.len())
+        .map(|_| PageLayout {
+            width: layout.width,
+            height: layout.height,
+            text: String::new(),
+            words: Vec::new(),
+        })
+        .collect();
+    for word in &layout.words {
+        let center = word.bbox.x + word.bbox.width / 2;
+        let column = gutters.iter().filter(|&&g| center > g).count();
+        columns[column].words.push(word.clone());
+    }
+    if rtl {
+        columns.reverse();
+    }
+
+    columns
+        .iter()
+        .filter(|column| column.has_geometry())
+        .map(|column| {
+            column
+                .lines(rtl)
+                .iter()
+                .map(|line| {
+                    line.iter()
+                        .map(|w| w.text.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Detect column gutters in a rendered page image from its vertical ink profile
+pub fn detect_image_gutters(image: &image::DynamicImage) -> Vec<u32> {
+    let gray = image.to_luma8();
+    let (width, height) = gray.dimensions();
+    let mut profile = vec![0u32; width as usize];
+    for (x, _, pixel) in gray.enumerate_pixels() {
+        if pixel.0[0] < INK_THRESHOLD {
+            profile[x as usize] += 1;
+        }
+    }
+    find_gutters(&profile, (height as f64 * GUTTER_TOLERANCE * 0.1) as u32)
+}
+
+/// Reorder each page's words into column-aware reading order
+#[tauri::command]
+pub async fn order_columns(pages: Vec<PageLayout>, rtl: bool) -> Vec<String> {
+    pages
+        .iter()
+        .map(|layout| order_columns_text(layout, rtl))
+        .collect()
+}
+
+/// Split a page image at detected column gutters before OCR, so each column is
+/// recognized separately. Column images are written next to the page image as
+/// `page-0001-col-1.png`, ordered for reading (right-to-left when `rtl`).
+/// Returns the original path when no gutter is found.
+#[tauri::command]
+pub async fn split_page_columns(image_path: String, rtl: bool) -> Result<Vec<String>, String> {
+    let path = Path::new(&image_path);
+    let (image, key) = encryption::open_image(path)?;
+    let gutters = detect_image_gutters(&image);
+    if gutters.is_empty() {
+        return Ok(vec![image_path]);
+    }
+
+    let (width, height) = image.dimensions();
+    let mut bounds: Vec<(u32, u32)> = std::iter::once(0)
+        .chain(gutters.iter().copied())
+        .zip(gutters.iter().copied().chain(std::iter::once(width)))
+        .collect();
+    if rtl {
+        bounds.reverse();
+    }
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+
+    bounds
+        .iter()
+        .enumerate()
+        .map(|(i, &(start, end))| {
+            let column_path = parent.join(format!("{}-col-{}.png", stem, i + 1));
+            encryption::save_png(
+                &image.crop_imm(start, 0, end - start, height),
+                &column_path,
+                key,
+            )
+            .map_err(|e| format!("Failed to save column image: {}", e.message()))?;
+            Ok(column_path.to_string_lossy().to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{BoundingBox, OcrWord};
+    use image::{Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    fn word(text: &str, x: u32, y: u32, width: u32) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            bbox: BoundingBox {
+                x,
+                y,
+                width,
+                height: 20,
+            },
+            confidence: None,
+        }
+    }
+
+    fn two_column_page() -> PageLayout {
+        PageLayout {
+            width: 1000,
+            height: 1000,
+            text: "interleaved".to_string(),
+            words: vec![
+                word("R1a", 730, 100, 170),
+                word("R1b", 550, 100, 170),
+                word("L1a", 280, 100, 170),
+                word("L1b", 100, 100, 170),
+                word("R2", 550, 140, 350),
+                word("L2", 100, 140, 350),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_find_gutters_central_run() {
+        let mut profile = vec![5u32; 100];
+        for coverage in &mut profile[45..55] {
+            *coverage = 0;
+        }
+        assert_eq!(find_gutters(&profile, 0), vec![50]);
+    }
+
+    #[test]
+    fn test_find_gutters_ignores_margins() {
+        let mut profile = vec![5u32; 100];
+        for coverage in &mut profile[0..10] {
+            *coverage = 0;
+        }
+        assert!(find_gutters(&profile, 0).is_empty());
+    }
+
+    #[test]
+    fn test_order_columns_text_rtl() {
+        let text = order_columns_text(&two_column_page(), true);
+        assert_eq!(text, "R1a R1b\nR2\n\nL1a L1b\nL2");
+    }
+
+    #[test]
+    fn test_order_columns_text_ltr() {
+        let text = order_columns_text(&two_column_page(), false);
+        assert_eq!(text, "L1b L1a\nL2\n\nR1b R1a\nR2");
+    }
+
+    #[test]
+    fn test_order_columns_text_single_column_unchanged() {
+        let mut layout = two_column_page();
+        layout.words.push(word("wide", 100, 200, 800));
+        layout.words.push(word("wide2", 100, 240, 800));
+        assert_eq!(order_columns_text(&layout, true), "interleaved");
+    }
+
+    #[test]
+    fn test_order_columns_text_without_geometry() {
+        let layout = PageLayout {
+            width: 100,
+            height: 100,
+            text: "plain".to_string(),
+            words: vec![],
+        };
+        assert_eq!(order_columns_text(&layout, true), "plain");
+    }
+
+    fn write_two_column_image(path: &Path) {
+        let mut image = RgbImage::from_pixel(200, 100, Rgb([255, 255, 255]));
+        for y in 10..90 {
+            for x in (20..90).chain(110..180) {
+                if y % 4 == 0 {
+                    image.put_pixel(x, y, Rgb([0, 0, 0]));
+                }
+            }
+        }
+        image.save(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_split_page_columns_rtl_order() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("page-0001.png");
+        write_two_column_image(&path);
+
+        let columns = split_page_columns(path.to_string_lossy().to_string(), true)
+            .await
+            .unwrap();
+
+        assert_eq!(columns.len(), 2);
+        assert!(columns[0].ends_with("page-0001-col-1.png"));
+        let first = image::open(&columns[0]).unwrap();
+        let second = image::open(&columns[1]).unwrap();
+        assert_eq!(first.width() + second.width(), 200);
+        // RTL: the first column image is the right half of the page
+        assert_eq!(first.width(), 200 - 100);
+    }
+
+    #[tokio::test]
+    async fn test_split_page_columns_single_column() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("page-0001.png");
+        RgbImage::from_pixel(100, 100, Rgb([0, 0, 0]))
+            .save(&path)
+            .unwrap();
+
+        let result = split_page_columns(path.to_string_lossy().to_string(), false)
+            .await
+            .unwrap();
+        assert_eq!(result, vec![path.to_string_lossy().to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_split_page_columns_missing_image() {
+        let result = split_page_columns("/nonexistent/page.png".to_string(), true).await;
+        assert!(result.unwrap_err().contains("Failed to open image"));
+    }
+}
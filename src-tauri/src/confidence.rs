@@ -0,0 +1,165 @@
+use crate::error::TahweelError;
+use crate::report::{self, QualityFlag};
+use crate::settings;
+use serde::{Deserialize, Serialize};
+
+/// Pages with a larger share of unexpected characters get a second pass, even
+/// below the proofreading threshold in `report`
+const AMBIGUOUS_GARBAGE_RATIO: f64 = 0.1;
+/// Word agreement between the two passes below which a page is flagged
+pub const LOW_AGREEMENT_THRESHOLD: f64 = 0.85;
+/// DPI used for the second pass of pages first rendered at the maximum DPI
+const SECOND_PASS_FALLBACK_DPI: u32 = 200;
+const DPI_MAX: u32 = 300;
+
+/// Text of a page OCRed a second time
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SecondPassText {
+    pub page: u32,
+    pub text: String,
+}
+
+/// Pages to OCR again and the DPI to render them at
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DualPassPlan {
+    pub dpi: u32,
+    pub pages: Vec<u32>,
+}
+
+/// Whether a page's first-pass text is worth a second opinion: it was flagged
+/// for proofreading or has noticeable garbage. Empty pages are usually blank
+/// and are skipped.
+pub fn is_ambiguous(text: &str) -> bool {
+    let stats = report::page_stats(1, text);
+    if stats.flags.contains(&QualityFlag::Empty) {
+        return false;
+    }
+    !stats.flags.is_empty() || stats.garbage_ratio > AMBIGUOUS_GARBAGE_RATIO
+}
+
+/// DPI for the second pass: the maximum, or a lower one if the first pass used it
+pub fn second_pass_dpi(dpi: u32) -> u32 {
+    if dpi < DPI_MAX {
+        DPI_MAX
+    } else {
+        SECOND_PASS_FALLBACK_DPI
+    }
+}
+
+/// Plan a second pass over the ambiguous pages of `pages` (in page order)
+pub fn plan(pages: &[String], dpi: u32) -> DualPassPlan {
+    DualPassPlan {
+        dpi: second_pass_dpi(dpi),
+        pages: pages
+            .iter()
+            .enumerate()
+            .filter(|(_, text)| is_ambiguous(text))
+            .map(|(i, _)| i as u32 + 1)
+            .collect(),
+    }
+}
+
+/// Word-level agreement of two OCR results from 0.0 to 1.0: twice the longest
+/// common word subsequence over the total word count (1.0 when both are empty)
+pub fn agreement(first: &str, second: &str) -> f64 {
+    let a: Vec<&str> = first.split_whitespace().collect();
+    let b: Vec<&str> = second.split_whitespace().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    let mut previous = vec![0usize; b.len() + 1];
+    let mut current = vec![0usize; b.len() + 1];
+    for word in &a {
+        for (j, other) in b.iter().enumerate() {
+            current[j + 1] = if word == other {
+                previous[j] + 1
+            } else {
+                previous[j + 1].max(current[j])
+            };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    let common = previous[b.len()];
+    2.0 * common as f64 / (a.len() + b.len()) as f64
+}
+
+/// Pages to OCR a second time for the `dualPassOcr` quality mode, given the
+/// first-pass texts and DPI. Returns no pages when the mode is off.
+#[tauri::command]
+pub async fn plan_dual_pass(pages: Vec<String>, dpi: u32) -> Result<DualPassPlan, TahweelError> {
+    if !settings::current().dual_pass_ocr {
+        return Ok(DualPassPlan {
+            dpi: second_pass_dpi(dpi),
+            pages: Vec::new(),
+        });
+    }
+    Ok(plan(&pages, dpi))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLEAN: &str = "بسم الله الرحمن الرحيم، الحمد لله رب العالمين";
+
+    #[test]
+    fn test_is_ambiguous() {
+        assert!(!is_ambiguous(CLEAN));
+        assert!(!is_ambiguous("  \n"));
+        assert!(is_ambiguous("الفصل الأول"));
+        assert!(is_ambiguous("بسم الله الرحمن الرحيم الحمد لله xyzw"));
+    }
+
+    #[test]
+    fn test_second_pass_dpi() {
+        assert_eq!(second_pass_dpi(150), 300);
+        assert_eq!(second_pass_dpi(300), SECOND_PASS_FALLBACK_DPI);
+    }
+
+    #[test]
+    fn test_plan_selects_ambiguous_pages() {
+        let pages = vec![
+            CLEAN.to_string(),
+            "ﾟ∂ ¶§ xx ©® qq".to_string(),
+            String::new(),
+            "عنوان".to_string(),
+        ];
+        let plan = plan(&pages, 150);
+        assert_eq!(plan.dpi, 300);
+        assert_eq!(plan.pages, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_agreement_identical_and_empty() {
+        assert_eq!(agreement(CLEAN, CLEAN), 1.0);
+        assert_eq!(agreement("", " \n"), 1.0);
+        assert_eq!(agreement(CLEAN, ""), 0.0);
+    }
+
+    #[test]
+    fn test_agreement_ignores_whitespace_layout() {
+        assert_eq!(
+            agreement("الحمد لله\nرب العالمين", "الحمد  لله رب\tالعالمين"),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_agreement_partial() {
+        // 3 of 4 words shared on each side: 2 * 3 / 8
+        assert_eq!(
+            agreement("الحمد لله رب العالمين", "الحمد لله ربب العالمين"),
+            0.75
+        );
+        assert!(agreement("a b c d", "d c b a") < LOW_AGREEMENT_THRESHOLD);
+    }
+
+    #[tokio::test]
+    async fn test_plan_dual_pass_disabled_by_default() {
+        let plan = plan_dual_pass(vec!["عنوان".to_string()], 150)
+            .await
+            .unwrap();
+        assert!(plan.pages.is_empty());
+    }
+}
@@ -0,0 +1,362 @@
+use crate::app_paths;
+use crate::incremental::load_partial_output;
+use crate::logging;
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Version of the crash report schema
+const CRASH_REPORT_VERSION: u32 = 1;
+/// Log lines captured into a crash report
+const CRASH_LOG_LINES: usize = 50;
+
+/// Incremental jobs that have started but not been finalized, keyed by output path
+static ACTIVE_JOBS: Mutex<BTreeMap<String, ActiveJob>> = Mutex::new(BTreeMap::new());
+
+/// A job that was writing partial output when the app crashed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActiveJob {
+    #[serde(rename = "outputPath")]
+    pub output_path: String,
+    pub source: String,
+    #[serde(rename = "totalPages")]
+    pub total_pages: u32,
+}
+
+/// Crash report written to disk by the panic hook
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CrashReport {
+    pub version: u32,
+    #[serde(rename = "appVersion")]
+    pub app_version: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: u64,
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    #[serde(rename = "lastEvents")]
+    pub last_events: Vec<String>,
+    #[serde(rename = "interruptedJobs")]
+    pub interrupted_jobs: Vec<ActiveJob>,
+    /// Set once the user has seen the recovery dialog for this report
+    #[serde(default)]
+    pub acknowledged: bool,
+}
+
+/// An interrupted job that can be resumed from its partial output
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ResumableJob {
+    #[serde(rename = "outputPath")]
+    pub output_path: String,
+    pub source: String,
+    #[serde(rename = "totalPages")]
+    pub total_pages: u32,
+    #[serde(rename = "completedPages")]
+    pub completed_pages: u32,
+}
+
+/// What the recovery dialog shows after a crash
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashRecovery {
+    #[serde(rename = "reportPath")]
+    pub report_path: String,
+    pub report: CrashReport,
+    pub jobs: Vec<ResumableJob>,
+}
+
+/// Track a job writing partial output so a crash report can offer to resume it
+pub fn register_job(output_path: &str, source: &str, total_pages: u32) {
+    let mut jobs = ACTIVE_JOBS.lock().unwrap_or_else(|p| p.into_inner());
+    jobs.insert(
+        output_path.to_string(),
+        ActiveJob {
+            output_path: output_path.to_string(),
+            source: source.to_string(),
+            total_pages,
+        },
+    );
+}
+
+pub fn unregister_job(output_path: &str) {
+    let mut jobs = ACTIVE_JOBS.lock().unwrap_or_else(|p| p.into_inner());
+    jobs.remove(output_path);
+}
+
+/// Snapshot of the active jobs. Uses `try_lock` because it runs inside the panic
+/// hook, where the panicking thread may already hold the lock.
+fn active_jobs() -> Vec<ActiveJob> {
+    match ACTIVE_JOBS.try_lock() {
+        Ok(jobs) => jobs.values().cloned().collect(),
+        Err(std::sync::TryLockError::Poisoned(p)) => p.into_inner().values().cloned().collect(),
+        Err(std::sync::TryLockError::WouldBlock) => Vec::new(),
+    }
+}
+
+pub fn build_crash_report(
+    message: String,
+    location: Option<String>,
+    backtrace: String,
+    last_events: Vec<String>,
+    interrupted_jobs: Vec<ActiveJob>,
+) -> CrashReport {
+    CrashReport {
+        version: CRASH_REPORT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        message: logging::redact(&message),
+        location,
+        thread: std::thread::current().name().map(String::from),
+        backtrace,
+        last_events,
+        interrupted_jobs,
+        acknowledged: false,
+    }
+}
+
+/// Write a report as `crash-<millis>.json` in `dir`
+pub fn write_crash_report(dir: &Path, report: &CrashReport) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create crash directory: {}", e))?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = dir.join(format!("crash-{}.json", millis));
+    let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write crash report: {}", e))?;
+    Ok(path)
+}
+
+/// Newest report in `dir` the user has not acknowledged yet
+pub fn latest_pending_report(dir: &Path) -> Result<Option<(PathBuf, CrashReport)>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read crash directory: {}", e)),
+    };
+
+    let mut reports: Vec<(PathBuf, CrashReport)> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with("crash-") && n.ends_with(".json"))
+                .unwrap_or(false)
+        })
+        .filter_map(|path| {
+            let content = fs::read_to_string(&path).ok()?;
+            let report: CrashReport = serde_json::from_str(&content).ok()?;
+            Some((path, report))
+        })
+        .filter(|(_, report)| !report.acknowledged)
+        .collect();
+
+    reports.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(reports.pop())
+}
+
+/// Mark a report as seen so the recovery dialog is not shown again
+pub fn acknowledge_report(path: &Path) -> Result<(), String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read crash report: {}", e))?;
+    let mut report: CrashReport = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse crash report: {}", e))?;
+    report.acknowledged = true;
+    let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Failed to write crash report: {}", e))
+}
+
+/// Only files directly inside the crash directory may be opened or modified
+fn validate_report_path(dir: &Path, report_path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(report_path);
+    if path.parent() != Some(dir) {
+        return Err(format!("Not a crash report: {}", report_path));
+    }
+    Ok(path)
+}
+
+/// Install a panic hook that writes a crash report (backtrace, recent log lines,
+/// interrupted jobs) before the default hook runs. Call before building the app so
+/// failures inside Tauri itself (including `run()` errors) are recorded too.
+pub fn install_panic_hook() {
+    let dir = app_paths::crash_dir();
+    let previous = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Unknown panic".to_string());
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        let last_events = logging::log_dir()
+            .and_then(|log_dir| logging::read_recent_lines(log_dir, CRASH_LOG_LINES).ok())
+            .unwrap_or_default();
+
+        let report = build_crash_report(
+            message,
+            location,
+            Backtrace::force_capture().to_string(),
+            last_events,
+            active_jobs(),
+        );
+        tracing::error!(message = %report.message, "Application panicked");
+        let _ = write_crash_report(&dir, &report);
+
+        previous(info);
+    }));
+}
+
+/// Return the pending crash report (if the last session crashed) together with
+/// the interrupted jobs that still have partial output to resume from.
+#[tauri::command]
+pub async fn get_crash_recovery() -> Result<Option<CrashRecovery>, String> {
+    let Some((path, report)) = latest_pending_report(&app_paths::crash_dir())? else {
+        return Ok(None);
+    };
+
+    let mut jobs = Vec::new();
+    for job in &report.interrupted_jobs {
+        if let Ok(Some(index)) = load_partial_output(job.output_path.clone()).await {
+            jobs.push(ResumableJob {
+                output_path: job.output_path.clone(),
+                source: index.source,
+                total_pages: index.total_pages,
+                completed_pages: index.completed.len() as u32,
+            });
+        }
+    }
+
+    Ok(Some(CrashRecovery {
+        report_path: path.to_string_lossy().to_string(),
+        report,
+        jobs,
+    }))
+}
+
+/// Dismiss the recovery dialog for a report
+#[tauri::command]
+pub async fn dismiss_crash_report(report_path: String) -> Result<(), String> {
+    acknowledge_report(&validate_report_path(
+        &app_paths::crash_dir(),
+        &report_path,
+    )?)
+}
+
+/// Open a crash report in the default viewer
+#[tauri::command]
+pub async fn open_crash_report(report_path: String) -> Result<(), String> {
+    let path = validate_report_path(&app_paths::crash_dir(), &report_path)?;
+    open::that(&path).map_err(|e| format!("Failed to open crash report: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn report(message: &str) -> CrashReport {
+        build_crash_report(
+            message.to_string(),
+            Some("src/pdf.rs:10:5".to_string()),
+            "backtrace".to_string(),
+            vec!["INFO started".to_string()],
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn test_build_crash_report_redacts_message() {
+        let report = report("request failed: Bearer ya29.secret");
+        assert_eq!(report.message, "request failed: Bearer [REDACTED]");
+        assert_eq!(report.version, CRASH_REPORT_VERSION);
+        assert!(!report.acknowledged);
+    }
+
+    #[test]
+    fn test_crash_report_serialization() {
+        let json = serde_json::to_value(report("boom")).unwrap();
+        assert_eq!(json["appVersion"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["lastEvents"][0], "INFO started");
+        assert!(json["interruptedJobs"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_write_and_find_pending_report() {
+        let dir = tempdir().unwrap();
+        let path = write_crash_report(dir.path(), &report("boom")).unwrap();
+
+        let (found, pending) = latest_pending_report(dir.path()).unwrap().unwrap();
+        assert_eq!(found, path);
+        assert_eq!(pending.message, "boom");
+    }
+
+    #[test]
+    fn test_latest_pending_report_prefers_newest() {
+        let dir = tempdir().unwrap();
+        let json = |message: &str| serde_json::to_string(&report(message)).unwrap();
+        fs::write(dir.path().join("crash-1000.json"), json("old")).unwrap();
+        fs::write(dir.path().join("crash-2000.json"), json("new")).unwrap();
+        fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let (_, pending) = latest_pending_report(dir.path()).unwrap().unwrap();
+        assert_eq!(pending.message, "new");
+    }
+
+    #[test]
+    fn test_acknowledged_report_is_not_pending() {
+        let dir = tempdir().unwrap();
+        let path = write_crash_report(dir.path(), &report("boom")).unwrap();
+
+        acknowledge_report(&path).unwrap();
+
+        assert!(latest_pending_report(dir.path()).unwrap().is_none());
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("\"acknowledged\": true"));
+    }
+
+    #[test]
+    fn test_latest_pending_report_missing_dir() {
+        let dir = tempdir().unwrap();
+        assert!(latest_pending_report(&dir.path().join("missing"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_register_and_unregister_job() {
+        register_job("/tmp/crash-test/book", "/tmp/book.pdf", 12);
+        let job = active_jobs()
+            .into_iter()
+            .find(|j| j.output_path == "/tmp/crash-test/book")
+            .unwrap();
+        assert_eq!(job.total_pages, 12);
+
+        unregister_job("/tmp/crash-test/book");
+        assert!(!active_jobs()
+            .iter()
+            .any(|j| j.output_path == "/tmp/crash-test/book"));
+    }
+
+    #[test]
+    fn test_validate_report_path_rejects_other_dirs() {
+        let dir = tempdir().unwrap();
+        assert!(validate_report_path(dir.path(), "/etc/passwd").is_err());
+        let inside = dir.path().join("crash-1.json");
+        assert_eq!(
+            validate_report_path(dir.path(), inside.to_str().unwrap()).unwrap(),
+            inside
+        );
+    }
+}
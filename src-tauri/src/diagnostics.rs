@@ -0,0 +1,383 @@
+use crate::backend_health;
+use crate::logging;
+use crate::pdf::find_pdfium_library;
+use crate::report::LAST_REPORT_FILE;
+use crate::settings;
+use pdfium_render::prelude::Pdfium;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Log lines included in a bundle (the newest ones)
+const BUNDLE_LOG_LINES: usize = 2000;
+
+/// PDFium availability, since a missing or broken library is the most common setup issue
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PdfiumInfo {
+    /// File name of the library that was found (no directory, to avoid leaking paths)
+    pub library: Option<String>,
+    pub loaded: bool,
+    pub error: Option<String>,
+    /// PDFium API release the bindings target (e.g. `7543`); the library itself
+    /// does not report its build
+    #[serde(rename = "apiVersion")]
+    pub api_version: Option<String>,
+    /// SHA-256 of the library file, which identifies the exact binary
+    pub sha256: Option<String>,
+}
+
+/// Version and compatibility details, for bug reports, the updater and `system.json`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AppInfo {
+    #[serde(rename = "appVersion")]
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub family: String,
+    pub pdfium: PdfiumInfo,
+    /// OCR backends this build can use
+    pub backends: Vec<String>,
+    /// Schema version of `settings.json` this build writes
+    #[serde(rename = "settingsVersion")]
+    pub settings_version: u32,
+}
+
+/// App and platform details written to `system.json`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SystemInfo {
+    #[serde(flatten)]
+    pub app: AppInfo,
+    #[serde(rename = "createdAt")]
+    pub created_at: u64,
+}
+
+/// Replace the user's home directory with `~` and mask any tokens
+pub fn sanitize_text(text: &str, home: Option<&Path>) -> String {
+    let text = match home.and_then(|h| h.to_str()).filter(|h| !h.is_empty()) {
+        Some(home) => text.replace(home, "~"),
+        None => text.to_string(),
+    };
+    logging::redact(&text)
+}
+
+fn pdfium_info(app: &AppHandle) -> PdfiumInfo {
+    let path = match find_pdfium_library(app) {
+        Ok(path) => path,
+        Err(_) => {
+            return PdfiumInfo {
+                library: None,
+                loaded: false,
+                error: Some("PDFium library not found".to_string()),
+                api_version: None,
+                sha256: None,
+            }
+        }
+    };
+
+    let library = path.file_name().map(|n| n.to_string_lossy().to_string());
+    let sha256 = fs::read(&path)
+        .ok()
+        .map(|bytes| format!("{:x}", Sha256::digest(bytes)));
+    match Pdfium::bind_to_library(path.to_string_lossy().as_ref()) {
+        Ok(bindings) => PdfiumInfo {
+            library,
+            loaded: true,
+            error: None,
+            api_version: Some(api_version_name(&format!("{:?}", bindings.version()))),
+            sha256,
+        },
+        Err(e) => PdfiumInfo {
+            library,
+            loaded: false,
+            error: Some(e.to_string()),
+            api_version: None,
+            sha256,
+        },
+    }
+}
+
+/// `V7543` (the bindings' `PdfiumApiVersion`) -> `7543`
+fn api_version_name(version: &str) -> String {
+    version.strip_prefix('V').unwrap_or(version).to_lowercase()
+}
+
+fn app_info(app: &AppHandle) -> AppInfo {
+    AppInfo {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        family: std::env::consts::FAMILY.to_string(),
+        pdfium: pdfium_info(app),
+        backends: vec![backend_health::GOOGLE_DRIVE.to_string()],
+        settings_version: settings::SETTINGS_VERSION,
+    }
+}
+
+fn system_info(app: &AppHandle) -> SystemInfo {
+    SystemInfo {
+        app: app_info(app),
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    }
+}
+
+/// Collect the bundle entries as (name, contents). Everything textual is sanitized.
+pub fn build_bundle_entries(
+    log_lines: &[String],
+    settings: &settings::Settings,
+    system: &SystemInfo,
+    last_report: Option<&str>,
+    home: Option<&Path>,
+) -> Result<Vec<(&'static str, String)>, String> {
+    let mut logs = log_lines.join("\n");
+    if !logs.is_empty() {
+        logs.push('\n');
+    }
+    // Sanitize paths before serializing: JSON escaping would hide Windows home paths
+    let settings = settings::Settings {
+        output_directory: settings
+            .output_directory
+            .as_deref()
+            .map(|dir| sanitize_text(dir, home)),
+        ..settings.redacted()
+    };
+    let settings_json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    let system_json = serde_json::to_string_pretty(system).map_err(|e| e.to_string())?;
+
+    let mut entries = vec![
+        ("logs.txt", sanitize_text(&logs, home)),
+        ("settings.json", settings_json),
+        ("system.json", sanitize_text(&system_json, home)),
+    ];
+    if let Some(report) = last_report {
+        entries.push(("last-job-report.json", sanitize_text(report, home)));
+    }
+    Ok(entries)
+}
+
+/// Write entries into a new ZIP at `zip_path`
+pub fn write_bundle(zip_path: &Path, entries: &[(&str, String)]) -> Result<(), String> {
+    let file = fs::File::create(zip_path)
+        .map_err(|e| format!("Failed to create diagnostics bundle: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for (name, contents) in entries {
+        zip.start_file(*name, options)
+            .map_err(|e| format!("Failed to add {} to bundle: {}", name, e))?;
+        zip.write_all(contents.as_bytes())
+            .map_err(|e| format!("Failed to add {} to bundle: {}", name, e))?;
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finalize diagnostics bundle: {}", e))?;
+    Ok(())
+}
+
+/// Default bundle location: the Downloads folder, falling back to the temp dir
+fn default_bundle_path() -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    dirs::download_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(format!("tahweel-diagnostics-{}.zip", timestamp))
+}
+
+/// Zip recent logs, sanitized settings, app/OS/PDFium info, and the last job report
+/// into one file that can be attached to a GitHub issue. Returns the bundle path.
+#[tauri::command]
+#[tracing::instrument(skip(app), err)]
+pub async fn create_diagnostics_bundle(
+    output_path: Option<String>,
+    app: AppHandle,
+) -> Result<String, String> {
+    let zip_path = output_path
+        .map(PathBuf::from)
+        .unwrap_or_else(default_bundle_path);
+
+    let (log_lines, last_report) = match logging::log_dir() {
+        Some(dir) => (
+            logging::read_recent_lines(dir, BUNDLE_LOG_LINES)?,
+            fs::read_to_string(dir.join(LAST_REPORT_FILE)).ok(),
+        ),
+        None => (Vec::new(), None),
+    };
+
+    let entries = build_bundle_entries(
+        &log_lines,
+        &settings::current(),
+        &system_info(&app),
+        last_report.as_deref(),
+        dirs::home_dir().as_deref(),
+    )?;
+    write_bundle(&zip_path, &entries)?;
+
+    tracing::info!("Created diagnostics bundle");
+    Ok(zip_path.to_string_lossy().to_string())
+}
+
+/// App version, PDFium build, OCR backends, platform and settings schema
+/// version, for bug reports and update checks
+#[tauri::command]
+pub async fn get_app_info(app: AppHandle) -> AppInfo {
+    app_info(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    fn sample_system() -> SystemInfo {
+        SystemInfo {
+            app: AppInfo {
+                app_version: "0.1.0".to_string(),
+                os: "linux".to_string(),
+                arch: "x86_64".to_string(),
+                family: "unix".to_string(),
+                pdfium: PdfiumInfo {
+                    library: Some("libpdfium.so".to_string()),
+                    loaded: true,
+                    error: None,
+                    api_version: Some("7543".to_string()),
+                    sha256: Some("ab12".to_string()),
+                },
+                backends: vec!["googleDrive".to_string()],
+                settings_version: 1,
+            },
+            created_at: 1,
+        }
+    }
+
+    fn read_entry(zip_path: &Path, name: &str) -> String {
+        let mut archive = zip::ZipArchive::new(fs::File::open(zip_path).unwrap()).unwrap();
+        let mut contents = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_sanitize_text_replaces_home_and_tokens() {
+        let home = Path::new("/home/amina");
+        let text = "Reading /home/amina/Books/a.pdf with Bearer ya29.secret";
+        assert_eq!(
+            sanitize_text(text, Some(home)),
+            "Reading ~/Books/a.pdf with Bearer [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_text_without_home() {
+        assert_eq!(sanitize_text("/tmp/out", None), "/tmp/out");
+    }
+
+    #[test]
+    fn test_build_bundle_entries_sanitizes_settings() {
+        let settings = settings::Settings {
+            output_directory: Some("/home/amina/Output".to_string()),
+            ..Default::default()
+        };
+        let entries = build_bundle_entries(
+            &["line one".to_string()],
+            &settings,
+            &sample_system(),
+            None,
+            Some(Path::new("/home/amina")),
+        )
+        .unwrap();
+
+        let names: Vec<&str> = entries.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["logs.txt", "settings.json", "system.json"]);
+        assert!(entries[1].1.contains("~/Output"));
+        assert!(!entries[1].1.contains("/home/amina"));
+        assert_eq!(entries[0].1, "line one\n");
+    }
+
+    #[test]
+    fn test_build_bundle_entries_redacts_storage_secret() {
+        let mut settings = settings::Settings::default();
+        settings.storage.secret_access_key = "wJalrXUtnFEMI".to_string();
+        settings.webdav.password = "app-password-123".to_string();
+        settings.email.password = "smtp-password-456".to_string();
+        let entries = build_bundle_entries(&[], &settings, &sample_system(), None, None).unwrap();
+        assert!(!entries[1].1.contains("wJalrXUtnFEMI"));
+        assert!(!entries[1].1.contains("app-password-123"));
+        assert!(!entries[1].1.contains("smtp-password-456"));
+        assert!(entries[1].1.contains("[REDACTED]"));
+    }
+
+    #[test]
+    fn test_build_bundle_entries_includes_last_report() {
+        let entries = build_bundle_entries(
+            &[],
+            &settings::Settings::default(),
+            &sample_system(),
+            Some(r#"{"pageCount": 3}"#),
+            None,
+        )
+        .unwrap();
+
+        let (name, contents) = entries.last().unwrap();
+        assert_eq!(*name, "last-job-report.json");
+        assert!(contents.contains("pageCount"));
+        assert_eq!(entries[0].1, "");
+    }
+
+    #[test]
+    fn test_write_bundle_round_trip() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("diag.zip");
+        let entries = build_bundle_entries(
+            &["started".to_string(), "Bearer abc".to_string()],
+            &settings::Settings::default(),
+            &sample_system(),
+            None,
+            None,
+        )
+        .unwrap();
+
+        write_bundle(&zip_path, &entries).unwrap();
+
+        assert_eq!(
+            read_entry(&zip_path, "logs.txt"),
+            "started\nBearer [REDACTED]\n"
+        );
+        let system: serde_json::Value =
+            serde_json::from_str(&read_entry(&zip_path, "system.json")).unwrap();
+        assert_eq!(system["appVersion"], "0.1.0");
+        assert_eq!(system["pdfium"]["loaded"], true);
+        assert_eq!(system["pdfium"]["apiVersion"], "7543");
+        assert_eq!(system["backends"], serde_json::json!(["googleDrive"]));
+        assert_eq!(system["settingsVersion"], 1);
+        assert_eq!(system["createdAt"], 1);
+    }
+
+    #[test]
+    fn test_api_version_name() {
+        assert_eq!(api_version_name("V7543"), "7543");
+        assert_eq!(api_version_name("Future"), "future");
+    }
+
+    #[test]
+    fn test_write_bundle_invalid_path() {
+        let result = write_bundle(Path::new("/nonexistent/dir/diag.zip"), &[]);
+        assert!(result
+            .unwrap_err()
+            .contains("Failed to create diagnostics bundle"));
+    }
+}
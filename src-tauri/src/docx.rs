@@ -0,0 +1,766 @@
+use crate::encryption;
+use crate::ocr_xml::escape_xml;
+use crate::output::{is_arabic_text, DocumentMetadata, PageInput};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// Maximum effective lines per page before adjacent short lines are merged
+const MAX_EFFECTIVE_LINES: usize = 40;
+/// Lines longer than this are assumed to wrap to two visual lines
+const LINE_WRAP_THRESHOLD: usize = 80;
+/// Body text size in half-points (10pt)
+const BODY_FONT_SIZE: u32 = 20;
+/// Footnote text size in half-points (8pt)
+const FOOTNOTE_FONT_SIZE: u32 = 16;
+/// Default displayed width of embedded page images, in pixels at 96 DPI
+pub(crate) const DEFAULT_IMAGE_WIDTH: u32 = 450;
+/// Widest image that fits the default page text area, in pixels at 96 DPI
+const MAX_IMAGE_WIDTH: u32 = 600;
+/// Embedded images keep this many pixels per displayed pixel for legible zooming
+const IMAGE_OVERSAMPLE: u32 = 2;
+/// JPEG quality for embedded page images
+const IMAGE_JPEG_QUALITY: u8 = 80;
+/// English Metric Units per pixel at 96 DPI
+const EMU_PER_PIXEL: u64 = 9525;
+
+const CONTENT_TYPES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Default Extension="jpeg" ContentType="image/jpeg"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+</Types>"#;
+
+const ROOT_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+</Relationships>"#;
+
+/// Package parts added for the core properties (title, author, subject)
+const CORE_CONTENT_TYPE: &str = r#"<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>"#;
+const CORE_RELATIONSHIP: &str = r#"<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>"#;
+
+const STYLES_RELATIONSHIP: &str = r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#;
+const IMAGE_RELATIONSHIP_TYPE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/image";
+
+const STYLES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/></w:style>
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="120"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:bCs/><w:sz w:val="32"/><w:szCs w:val="32"/></w:rPr></w:style>
+</w:styles>"#;
+
+/// `word/document.xml` around the body paragraphs
+const DOCUMENT_XML_START: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+    <w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\" \
+    xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\" \
+    xmlns:wp=\"http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing\">\
+    <w:body>";
+const DOCUMENT_XML_END: &str = "<w:sectPr/></w:body></w:document>";
+
+/// A downscaled page image ready to embed, with its displayed size in pixels
+#[derive(Debug, Clone)]
+pub struct DocxImage {
+    pub jpeg: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// One page of the document: optional scan image above its OCR text, and
+/// footnotes below a rule
+#[derive(Debug, Clone)]
+pub struct DocxPage {
+    pub text: String,
+    pub image: Option<DocxImage>,
+    pub footnotes: Option<String>,
+}
+
+impl DocxPage {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            image: None,
+            footnotes: None,
+        }
+    }
+
+    /// Page text with its separated footnotes, if any
+    pub fn with_footnotes(text: impl Into<String>, footnotes: Option<String>) -> Self {
+        Self {
+            footnotes: footnotes.filter(|notes| !notes.trim().is_empty()),
+            ..Self::text(text)
+        }
+    }
+}
+
+/// A titled group of pages (one per source file when assembling a book)
+#[derive(Debug, Clone)]
+pub struct DocxSection {
+    pub heading: Option<String>,
+    pub pages: Vec<DocxPage>,
+}
+
+/// Load a page image, downscale it for embedding, and re-encode it as JPEG.
+/// `display_width` is the width the image is shown at in the document.
+pub fn prepare_page_image(path: &str, display_width: u32) -> Result<DocxImage, String> {
+    let display_width = display_width.clamp(1, MAX_IMAGE_WIDTH);
+    let (image, _) = encryption::open_image(Path::new(path))
+        .map_err(|e| format!("Failed to open image {}: {}", path, e.message()))?;
+    if image.width() == 0 || image.height() == 0 {
+        return Err(format!("Image has no content: {}", path));
+    }
+
+    let (width, height) = (image.width(), image.height());
+    let target_width = display_width * IMAGE_OVERSAMPLE;
+    let image = if width > target_width {
+        image.resize(target_width, u32::MAX, FilterType::Triangle)
+    } else {
+        image
+    };
+
+    let mut jpeg = Cursor::new(Vec::new());
+    let encoder = JpegEncoder::new_with_quality(&mut jpeg, IMAGE_JPEG_QUALITY);
+    image
+        .into_rgb8()
+        .write_with_encoder(encoder)
+        .map_err(|e| format!("Failed to encode image {}: {}", path, e))?;
+
+    Ok(DocxImage {
+        jpeg: jpeg.into_inner(),
+        width: display_width,
+        height: ((height as u64 * display_width as u64) / width as u64).max(1) as u32,
+    })
+}
+
+/// Normalize page text the same way as the frontend DOCX writer:
+/// unify line endings, collapse runs of identical whitespace, trim.
+pub fn normalize_page_text(text: &str) -> String {
+    let unified = text.replace("\r\n", "\n").replace('\r', "\n");
+    let mut out = String::with_capacity(unified.len());
+    let mut previous: Option<char> = None;
+    for c in unified.chars() {
+        if c.is_whitespace() && previous == Some(c) {
+            continue;
+        }
+        out.push(c);
+        previous = Some(c);
+    }
+    out.trim().to_string()
+}
+
+/// Compact text by iteratively merging the shortest adjacent line pairs until the
+/// page's effective line count (counting long lines twice) fits on one page.
+pub fn compact_text(text: &str) -> String {
+    let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+
+    while lines.len() >= 2 {
+        let wrapped = lines
+            .iter()
+            .filter(|l| l.chars().count() > LINE_WRAP_THRESHOLD)
+            .count();
+        if lines.len() + wrapped <= MAX_EFFECTIVE_LINES {
+            break;
+        }
+
+        let min_index = (0..lines.len() - 1)
+            .min_by_key(|&i| lines[i].chars().count() + lines[i + 1].chars().count())
+            .unwrap_or(0);
+        let next = lines.remove(min_index + 1);
+        lines[min_index] = format!("{} {}", lines[min_index], next);
+    }
+
+    lines.join("\n")
+}
+
+fn paragraph_properties(rtl: bool, style: Option<&str>) -> String {
+    let mut props = String::from("<w:pPr>");
+    if let Some(style) = style {
+        let _ = write!(props, "<w:pStyle w:val=\"{}\"/>", style);
+    }
+    if rtl {
+        props.push_str("<w:bidi/>");
+    }
+    let _ = write!(
+        props,
+        "<w:jc w:val=\"{}\"/></w:pPr>",
+        if rtl { "right" } else { "left" }
+    );
+    props
+}
+
+fn text_run(text: &str, rtl: bool, size: Option<u32>) -> String {
+    let mut run = String::from("<w:r><w:rPr>");
+    if rtl {
+        run.push_str("<w:rtl/>");
+    }
+    if let Some(size) = size {
+        let _ = write!(run, "<w:sz w:val=\"{size}\"/><w:szCs w:val=\"{size}\"/>");
+    }
+    let _ = write!(
+        run,
+        "</w:rPr><w:t xml:space=\"preserve\">{}</w:t></w:r>",
+        escape_xml(text)
+    );
+    run
+}
+
+/// Build an inline picture run referencing the image relationship `rel_id`
+fn image_paragraph(image: &DocxImage, rel_id: &str, id: usize) -> String {
+    let cx = image.width as u64 * EMU_PER_PIXEL;
+    let cy = image.height as u64 * EMU_PER_PIXEL;
+    format!(
+        "<w:p><w:pPr><w:jc w:val=\"center\"/></w:pPr><w:r><w:drawing>\
+        <wp:inline distT=\"0\" distB=\"0\" distL=\"0\" distR=\"0\">\
+        <wp:extent cx=\"{cx}\" cy=\"{cy}\"/><wp:docPr id=\"{id}\" name=\"Page image {id}\"/>\
+        <a:graphic xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\">\
+        <a:graphicData uri=\"http://schemas.openxmlformats.org/drawingml/2006/picture\">\
+        <pic:pic xmlns:pic=\"http://schemas.openxmlformats.org/drawingml/2006/picture\">\
+        <pic:nvPicPr><pic:cNvPr id=\"{id}\" name=\"page-{id:04}.jpeg\"/><pic:cNvPicPr/></pic:nvPicPr>\
+        <pic:blipFill><a:blip r:embed=\"{rel_id}\"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill>\
+        <pic:spPr><a:xfrm><a:off x=\"0\" y=\"0\"/><a:ext cx=\"{cx}\" cy=\"{cy}\"/></a:xfrm>\
+        <a:prstGeom prst=\"rect\"><a:avLst/></a:prstGeom></pic:spPr></pic:pic>\
+        </a:graphicData></a:graphic></wp:inline></w:drawing></w:r></w:p>"
+    )
+}
+
+/// Relationship ID and media part name for the `n`th embedded image (1-based)
+fn image_part(n: usize) -> (String, String) {
+    (format!("rIdImg{}", n), format!("media/page-{:04}.jpeg", n))
+}
+
+/// Build one paragraph of page text with line breaks, ending with a page break
+/// when `page_break` is set. Footnote paragraphs are smaller and have a rule above.
+fn text_paragraph(text: &str, footnote: bool, page_break: bool) -> String {
+    let text = compact_text(&normalize_page_text(text));
+    let rtl = is_arabic_text(&text);
+
+    let mut xml = String::from("<w:p>");
+    let mut props = paragraph_properties(rtl, None);
+    if footnote {
+        props = props.replacen(
+            "<w:pPr>",
+            "<w:pPr><w:pBdr><w:top w:val=\"single\" w:sz=\"4\" w:space=\"4\" w:color=\"auto\"/></w:pBdr>",
+            1,
+        );
+    }
+    xml.push_str(&props);
+    let size = if footnote {
+        FOOTNOTE_FONT_SIZE
+    } else {
+        BODY_FONT_SIZE
+    };
+    let lines: Vec<&str> = text.split('\n').collect();
+    for (i, line) in lines.iter().enumerate() {
+        xml.push_str(&text_run(line, rtl, Some(size)));
+        if i < lines.len() - 1 {
+            xml.push_str("<w:r><w:br/></w:r>");
+        }
+    }
+    if page_break {
+        xml.push_str("<w:r><w:br w:type=\"page\"/></w:r>");
+    }
+    xml.push_str("</w:p>");
+    xml
+}
+
+/// Build the body XML for one page: a paragraph with line breaks, then its
+/// footnotes if any, followed by a page break unless it is the last page of the document.
+fn page_paragraph(text: &str, footnotes: Option<&str>, is_last_page: bool) -> String {
+    match footnotes {
+        Some(notes) => {
+            text_paragraph(text, false, false) + &text_paragraph(notes, true, !is_last_page)
+        }
+        None => text_paragraph(text, false, !is_last_page),
+    }
+}
+
+fn heading_paragraph(text: &str) -> String {
+    let rtl = is_arabic_text(text);
+    format!(
+        "<w:p>{}{}</w:p>",
+        paragraph_properties(rtl, Some("Heading1")),
+        text_run(text, rtl, None)
+    )
+}
+
+/// Build the `word/document.xml` part for the given sections
+pub fn build_document_xml(sections: &[DocxSection]) -> String {
+    let total_pages: usize = sections.iter().map(|s| s.pages.len()).sum();
+    let mut body = String::new();
+    let mut page_index = 0;
+    let mut image_index = 0;
+
+    for section in sections {
+        if let Some(heading) = &section.heading {
+            body.push_str(&heading_paragraph(heading));
+        }
+        for page in &section.pages {
+            page_index += 1;
+            if let Some(image) = &page.image {
+                image_index += 1;
+                let (rel_id, _) = image_part(image_index);
+                body.push_str(&image_paragraph(image, &rel_id, image_index));
+            }
+            body.push_str(&page_paragraph(
+                &page.text,
+                page.footnotes.as_deref(),
+                page_index == total_pages,
+            ));
+        }
+    }
+
+    format!("{}{}{}", DOCUMENT_XML_START, body, DOCUMENT_XML_END)
+}
+
+/// `docProps/core.xml` with the metadata's title, author (as creator) and subject
+fn build_core_xml(metadata: &DocumentMetadata) -> String {
+    let metadata = metadata.normalized();
+    let mut properties = String::new();
+    for (tag, value) in [
+        ("dc:title", &metadata.title),
+        ("dc:creator", &metadata.author),
+        ("dc:subject", &metadata.subject),
+    ] {
+        if let Some(value) = value {
+            let _ = write!(properties, "<{tag}>{}</{tag}>", escape_xml(value));
+        }
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+        <cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" \
+        xmlns:dc=\"http://purl.org/dc/elements/1.1/\">{}</cp:coreProperties>",
+        properties
+    )
+}
+
+/// Every package part except the document and its images: content types, root and
+/// document relationships (`relationships` lists the document's), styles, and the
+/// core properties when `metadata` is not empty
+fn package_parts(metadata: &DocumentMetadata, relationships: &str) -> Vec<(&'static str, String)> {
+    let document_rels = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+        <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">{}</Relationships>",
+        relationships
+    );
+    let core = (!metadata.is_empty()).then(|| build_core_xml(metadata));
+    let (content_types, root_rels) = if core.is_some() {
+        (
+            CONTENT_TYPES_XML.replace("</Types>", &format!("{}</Types>", CORE_CONTENT_TYPE)),
+            ROOT_RELS_XML.replace(
+                "</Relationships>",
+                &format!("{}</Relationships>", CORE_RELATIONSHIP),
+            ),
+        )
+    } else {
+        (CONTENT_TYPES_XML.to_string(), ROOT_RELS_XML.to_string())
+    };
+
+    let mut parts = vec![
+        ("[Content_Types].xml", content_types),
+        ("_rels/.rels", root_rels),
+        ("word/_rels/document.xml.rels", document_rels),
+        ("word/styles.xml", STYLES_XML.to_string()),
+    ];
+    if let Some(core) = core {
+        parts.push(("docProps/core.xml", core));
+    }
+    parts
+}
+
+fn write_part<W: Write + std::io::Seek>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    content: &[u8],
+) -> Result<(), String> {
+    zip.start_file(name, SimpleFileOptions::default())
+        .map_err(|e| format!("Failed to write DOCX part {}: {}", name, e))?;
+    zip.write_all(content)
+        .map_err(|e| format!("Failed to write DOCX part {}: {}", name, e))
+}
+
+/// Write a DOCX package containing the given sections to `path`. Non-empty
+/// `metadata` is stored as the document's core properties.
+pub fn write_docx(
+    path: &str,
+    sections: &[DocxSection],
+    metadata: &DocumentMetadata,
+) -> Result<(), String> {
+    let file = fs::File::create(path).map_err(|e| format!("Failed to create DOCX: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+
+    let images: Vec<&DocxImage> = sections
+        .iter()
+        .flat_map(|s| s.pages.iter().filter_map(|p| p.image.as_ref()))
+        .collect();
+
+    let mut relationships = String::from(STYLES_RELATIONSHIP);
+    for i in 1..=images.len() {
+        let (rel_id, target) = image_part(i);
+        let _ = write!(
+            relationships,
+            "<Relationship Id=\"{}\" Type=\"{}\" Target=\"{}\"/>",
+            rel_id, IMAGE_RELATIONSHIP_TYPE, target
+        );
+    }
+
+    for (name, content) in package_parts(metadata, &relationships) {
+        write_part(&mut zip, name, content.as_bytes())?;
+    }
+    write_part(
+        &mut zip,
+        "word/document.xml",
+        build_document_xml(sections).as_bytes(),
+    )?;
+    for (i, image) in images.iter().enumerate() {
+        let (_, target) = image_part(i + 1);
+        write_part(&mut zip, &format!("word/{}", target), &image.jpeg)?;
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finalize DOCX: {}", e))?;
+    Ok(())
+}
+
+/// Write a text-only DOCX of `page_count` pages to `path`, asking `page` for each
+/// page's text and footnotes in order. The document part is streamed into the
+/// package, so only one page is held in memory at a time.
+pub fn write_docx_streamed<F>(
+    path: &str,
+    page_count: usize,
+    metadata: &DocumentMetadata,
+    mut page: F,
+) -> Result<(), String>
+where
+    F: FnMut(usize) -> Result<(String, Option<String>), String>,
+{
+    let file = fs::File::create(path).map_err(|e| format!("Failed to create DOCX: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+
+    for (name, content) in package_parts(metadata, STYLES_RELATIONSHIP) {
+        write_part(&mut zip, name, content.as_bytes())?;
+    }
+    write_part(&mut zip, "word/document.xml", DOCUMENT_XML_START.as_bytes())?;
+    for index in 0..page_count {
+        let (text, footnotes) = page(index)?;
+        let footnotes = footnotes.filter(|notes| !notes.trim().is_empty());
+        let xml = page_paragraph(&text, footnotes.as_deref(), index + 1 == page_count);
+        zip.write_all(xml.as_bytes())
+            .map_err(|e| format!("Failed to write DOCX part word/document.xml: {}", e))?;
+    }
+    zip.write_all(DOCUMENT_XML_END.as_bytes())
+        .map_err(|e| format!("Failed to write DOCX part word/document.xml: {}", e))?;
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finalize DOCX: {}", e))?;
+    Ok(())
+}
+
+/// Write `<output_path>.docx`, optionally embedding each page's downscaled scan
+/// above its text for proofreading. `image_width` is the displayed width in pixels;
+/// `metadata` (the source PDF's, with the job's overrides) fills the core properties.
+#[tauri::command]
+pub async fn write_docx_output(
+    output_path: String,
+    pages: Vec<PageInput>,
+    embed_images: bool,
+    image_width: Option<u32>,
+    metadata: Option<DocumentMetadata>,
+) -> Result<String, String> {
+    let image_width = image_width.unwrap_or(DEFAULT_IMAGE_WIDTH);
+    let pages = pages
+        .into_iter()
+        .map(|page| {
+            let image = match (&page.image_path, embed_images) {
+                (Some(path), true) => Some(prepare_page_image(path, image_width)?),
+                _ => None,
+            };
+            Ok(DocxPage {
+                image,
+                ..DocxPage::with_footnotes(page.text, page.footnotes)
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let path = format!("{}.docx", output_path);
+    write_docx(
+        &path,
+        &[DocxSection {
+            heading: None,
+            pages,
+        }],
+        &metadata.unwrap_or_default(),
+    )?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn section(heading: Option<&str>, pages: &[&str]) -> DocxSection {
+        DocxSection {
+            heading: heading.map(str::to_string),
+            pages: pages.iter().map(|p| DocxPage::text(*p)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_page_text() {
+        assert_eq!(normalize_page_text("  a\r\nb\rc  "), "a\nb\nc");
+        assert_eq!(normalize_page_text("a   b\n\n\nc"), "a b\nc");
+        assert_eq!(normalize_page_text("a \nb"), "a \nb");
+    }
+
+    #[test]
+    fn test_compact_text_short_text_unchanged() {
+        let text = "line 1\nline 2\nline 3";
+        assert_eq!(compact_text(text), text);
+    }
+
+    #[test]
+    fn test_compact_text_merges_to_limit() {
+        let text = (0..60)
+            .map(|i| format!("l{}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let compacted = compact_text(&text);
+        assert_eq!(compacted.lines().count(), MAX_EFFECTIVE_LINES);
+        assert!(compacted.contains("l0 l1"));
+    }
+
+    #[test]
+    fn test_compact_text_counts_wrapped_lines() {
+        let long = "x".repeat(LINE_WRAP_THRESHOLD + 1);
+        let text = vec![long.as_str(); 25].join("\n");
+        let compacted = compact_text(&text);
+        let lines: Vec<&str> = compacted.lines().collect();
+        let wrapped = lines
+            .iter()
+            .filter(|l| l.chars().count() > LINE_WRAP_THRESHOLD)
+            .count();
+        assert!(lines.len() + wrapped <= MAX_EFFECTIVE_LINES);
+    }
+
+    #[test]
+    fn test_document_xml_rtl_paragraph() {
+        let xml = build_document_xml(&[section(None, &["مرحبا\nبالعالم"])]);
+        assert!(xml.contains("<w:bidi/>"));
+        assert!(xml.contains("<w:jc w:val=\"right\"/>"));
+        assert!(xml.contains("<w:rtl/>"));
+        assert!(xml.contains("<w:r><w:br/></w:r>"));
+        assert!(!xml.contains("w:type=\"page\""));
+    }
+
+    #[test]
+    fn test_document_xml_page_breaks_between_pages() {
+        let xml = build_document_xml(&[
+            section(Some("Part 1"), &["one", "two"]),
+            section(Some("Part 2"), &["three"]),
+        ]);
+        assert_eq!(xml.matches("w:type=\"page\"").count(), 2);
+        assert_eq!(xml.matches("Heading1").count(), 2);
+        assert!(xml.find("Part 2").unwrap() > xml.find(">two<").unwrap());
+        assert!(xml.contains("<w:jc w:val=\"left\"/>"));
+    }
+
+    #[test]
+    fn test_document_xml_footnotes_below_rule() {
+        let page = DocxPage::with_footnotes("متن", Some("١. حاشية".to_string()));
+        let xml = build_document_xml(&[
+            DocxSection {
+                heading: None,
+                pages: vec![page],
+            },
+            section(None, &["two"]),
+        ]);
+        let notes = xml.find(">١. حاشية<").unwrap();
+        assert!(xml.find(">متن<").unwrap() < notes);
+        assert_eq!(xml.matches("<w:pBdr>").count(), 1);
+        assert!(xml.contains("<w:sz w:val=\"16\"/>"));
+        // The page break follows the footnotes
+        assert!(xml.find("w:type=\"page\"").unwrap() > notes);
+        assert!(DocxPage::with_footnotes("x", Some(" ".to_string()))
+            .footnotes
+            .is_none());
+    }
+
+    #[test]
+    fn test_document_xml_escapes_text() {
+        let xml = build_document_xml(&[section(None, &["a < b & c"])]);
+        assert!(xml.contains("a &lt; b &amp; c"));
+    }
+
+    #[test]
+    fn test_write_docx_creates_valid_package() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("book.docx");
+        write_docx(
+            path.to_str().unwrap(),
+            &[section(Some("عنوان"), &["نص"])],
+            &DocumentMetadata::default(),
+        )
+        .unwrap();
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 5);
+        let mut document = String::new();
+        archive
+            .by_name("word/document.xml")
+            .unwrap()
+            .read_to_string(&mut document)
+            .unwrap();
+        assert!(document.contains("عنوان"));
+        assert!(archive.by_name("[Content_Types].xml").is_ok());
+        assert!(archive.by_name("docProps/core.xml").is_err());
+    }
+
+    #[test]
+    fn test_write_docx_core_properties() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("book.docx");
+        let metadata = DocumentMetadata {
+            title: Some("الأم & الرسالة".to_string()),
+            author: Some("الشافعي".to_string()),
+            subject: None,
+        };
+        write_docx(path.to_str().unwrap(), &[section(None, &["نص"])], &metadata).unwrap();
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 6);
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            content
+        };
+        let core = read("docProps/core.xml");
+        assert!(core.contains("<dc:title>الأم &amp; الرسالة</dc:title>"));
+        assert!(core.contains("<dc:creator>الشافعي</dc:creator>"));
+        assert!(!core.contains("dc:subject"));
+        assert!(read("_rels/.rels").contains("Target=\"docProps/core.xml\""));
+        assert!(read("[Content_Types].xml").contains("PartName=\"/docProps/core.xml\""));
+    }
+
+    #[test]
+    fn test_write_docx_invalid_path() {
+        let result = write_docx(
+            "/nonexistent/dir/book.docx",
+            &[],
+            &DocumentMetadata::default(),
+        );
+        assert!(result.unwrap_err().contains("Failed to create DOCX"));
+    }
+
+    fn write_png(dir: &Path, name: &str, width: u32, height: u32) -> String {
+        let path = dir.join(name);
+        image::RgbImage::new(width, height).save(&path).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_prepare_page_image_downscales() {
+        let temp = tempdir().unwrap();
+        let path = write_png(temp.path(), "page.png", 2000, 3000);
+
+        let image = prepare_page_image(&path, 300).unwrap();
+        assert_eq!(image.width, 300);
+        assert_eq!(image.height, 450);
+        let (w, h) = image::load_from_memory(&image.jpeg)
+            .unwrap()
+            .to_rgb8()
+            .dimensions();
+        assert_eq!((w, h), (600, 900));
+    }
+
+    #[test]
+    fn test_prepare_page_image_clamps_width() {
+        let temp = tempdir().unwrap();
+        let path = write_png(temp.path(), "page.png", 100, 50);
+
+        let image = prepare_page_image(&path, 5000).unwrap();
+        assert_eq!(image.width, MAX_IMAGE_WIDTH);
+        assert_eq!(image.height, MAX_IMAGE_WIDTH / 2);
+    }
+
+    #[test]
+    fn test_prepare_page_image_missing() {
+        let result = prepare_page_image("/nonexistent/page.png", 300);
+        assert!(result.unwrap_err().contains("Failed to open image"));
+    }
+
+    #[test]
+    fn test_document_xml_embeds_image_before_text() {
+        let page = DocxPage {
+            image: Some(DocxImage {
+                jpeg: vec![],
+                width: 100,
+                height: 200,
+            }),
+            ..DocxPage::text("نص")
+        };
+        let xml = build_document_xml(&[DocxSection {
+            heading: None,
+            pages: vec![page],
+        }]);
+        assert!(xml.contains("r:embed=\"rIdImg1\""));
+        assert!(xml.contains("cx=\"952500\" cy=\"1905000\""));
+        assert!(xml.find("<w:drawing>").unwrap() < xml.find(">نص<").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_write_docx_output_with_images() {
+        let temp = tempdir().unwrap();
+        let image_path = write_png(temp.path(), "page-0001.png", 800, 1000);
+        let base = temp.path().join("book").to_string_lossy().to_string();
+        let page: PageInput = serde_json::from_value(serde_json::json!({
+            "text": "صفحة",
+            "imagePath": image_path
+        }))
+        .unwrap();
+
+        let path = write_docx_output(base, vec![page], true, Some(200), None)
+            .await
+            .unwrap();
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        assert!(archive.by_name("word/media/page-0001.jpeg").is_ok());
+        let mut rels = String::new();
+        archive
+            .by_name("word/_rels/document.xml.rels")
+            .unwrap()
+            .read_to_string(&mut rels)
+            .unwrap();
+        assert!(rels.contains("Id=\"rIdImg1\""));
+        assert!(rels.contains("Target=\"media/page-0001.jpeg\""));
+    }
+
+    #[tokio::test]
+    async fn test_write_docx_output_without_images() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("book").to_string_lossy().to_string();
+        let page: PageInput = serde_json::from_value(serde_json::json!({
+            "text": "text",
+            "imagePath": "/nonexistent/page.png"
+        }))
+        .unwrap();
+
+        let path = write_docx_output(base, vec![page], false, None, None)
+            .await
+            .unwrap();
+        let archive = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 5);
+    }
+}
@@ -0,0 +1,581 @@
+use crate::error::TahweelError;
+use crate::events::{ThrottledEmitter, PROGRESS_INTERVAL};
+use crate::google_drive;
+use crate::http::{self, OperationClass};
+use crate::long_path;
+use crate::output;
+use crate::path_policy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// Partial download inside a URL's download folder, kept between attempts
+const PARTIAL_FILE: &str = "download.part";
+/// Minimum bytes between `download-progress` events
+const PROGRESS_STEP_BYTES: u64 = 1024 * 1024;
+
+/// Options for `process_url` and `process_drive_file`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UrlJobOptions {
+    /// Where outputs go; defaults to the configured output directory, then the
+    /// system downloads folder
+    #[serde(rename = "outputDirectory")]
+    pub output_directory: Option<String>,
+}
+
+/// A downloaded input, ready to be processed like a picked file
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DownloadedFile {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    #[serde(rename = "outputDirectory")]
+    pub output_directory: String,
+    /// Whether an earlier partial download was continued
+    pub resumed: bool,
+}
+
+/// Payload of the `download-progress` event
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DownloadProgress {
+    pub url: String,
+    #[serde(rename = "downloadedBytes")]
+    pub downloaded_bytes: u64,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: Option<u64>,
+}
+
+/// Accept only http(s) URLs
+pub fn parse_url(value: &str) -> Result<url::Url, TahweelError> {
+    let url = url::Url::parse(value.trim())
+        .map_err(|e| TahweelError::InvalidInput(format!("Invalid URL {}: {}", value, e)))?;
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        scheme => Err(TahweelError::InvalidInput(format!(
+            "Unsupported URL scheme: {}",
+            scheme
+        ))),
+    }
+}
+
+/// Workspace folder for a URL. It is derived from the URL so a retried download
+/// finds its partial file and resumes.
+pub fn download_dir(workspace: &Path, url: &url::Url) -> PathBuf {
+    let digest = format!("{:x}", Sha256::digest(url.as_str().as_bytes()));
+    workspace.join(format!("tahweel-download-{}", &digest[..16]))
+}
+
+/// Keep only the last path component of a server-provided name, without
+/// characters Windows does not allow in file names
+fn sanitize_name(name: &str) -> Option<String> {
+    let name = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .trim_matches('"');
+    if name.is_empty() || name == "." || name == ".." {
+        return None;
+    }
+    Some(
+        name.chars()
+            .map(|c| match c {
+                '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect(),
+    )
+}
+
+/// File name from `Content-Disposition` (`filename*=UTF-8''...` or `filename=...`),
+/// falling back to the URL's last path segment
+pub fn file_name_for(url: &url::Url, content_disposition: Option<&str>) -> String {
+    let from_header = content_disposition.and_then(|header| {
+        let params: Vec<&str> = header.split(';').map(str::trim).collect();
+        let extended = params.iter().find_map(|p| {
+            let value = p.strip_prefix("filename*=")?;
+            let encoded = value.split_once("''").map_or(value, |(_, v)| v);
+            urlencoding::decode(encoded).ok().map(|s| s.into_owned())
+        });
+        extended
+            .or_else(|| {
+                params
+                    .iter()
+                    .find_map(|p| p.strip_prefix("filename=").map(str::to_string))
+            })
+            .and_then(|name| sanitize_name(&name))
+    });
+
+    from_header
+        .or_else(|| {
+            url.path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .and_then(|segment| urlencoding::decode(segment).ok())
+                .and_then(|name| sanitize_name(&name))
+        })
+        .unwrap_or_else(|| "download".to_string())
+}
+
+/// File extension for a supported input type, detected from the file's contents
+/// (a server's name or `Content-Type` can be wrong, e.g. an HTML error page)
+fn supported_extension(path: &Path) -> Result<(&'static str, String), TahweelError> {
+    let mime_type = infer::get_from_path(path)?
+        .map(|kind| kind.mime_type().to_string())
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    let extension = match mime_type.as_str() {
+        "application/pdf" => "pdf",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        _ => {
+            return Err(TahweelError::InvalidInput(format!(
+                "Unsupported file type: {}",
+                mime_type
+            )))
+        }
+    };
+    Ok((extension, mime_type))
+}
+
+/// `name` with an extension matching `extension` (appended when missing or wrong)
+fn name_with_extension(name: &str, extension: &str) -> String {
+    let current = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    let matches = match current.as_deref() {
+        Some("jpeg") => extension == "jpg",
+        Some(current) => current == extension,
+        None => false,
+    };
+    if matches {
+        name.to_string()
+    } else {
+        format!("{}.{}", name, extension)
+    }
+}
+
+/// Download `url` into `dir`, continuing a partial file from an earlier attempt
+/// with a `Range` request. `access_token` authorizes Drive downloads. Returns the
+/// partial file (now complete), the file name the server suggested, and whether
+/// the download was resumed.
+pub async fn download_to(
+    client: &reqwest::Client,
+    url: &url::Url,
+    access_token: Option<&str>,
+    dir: &Path,
+    mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<(PathBuf, String, bool), TahweelError> {
+    fs::create_dir_all(dir)?;
+    let partial = dir.join(PARTIAL_FILE);
+    let offset = fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+
+    let mut request = client.get(url.as_str());
+    if let Some(token) = access_token {
+        request = request.bearer_auth(token);
+    }
+    if offset > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request.send().await?;
+    let status = response.status();
+    let name = file_name_for(
+        url,
+        response
+            .headers()
+            .get(reqwest::header::CONTENT_DISPOSITION)
+            .and_then(|v| v.to_str().ok()),
+    );
+
+    // The partial file already holds the whole resource
+    if offset > 0 && status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        on_progress(offset, Some(offset));
+        return Ok((partial, name, true));
+    }
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        return Err(TahweelError::from_status(
+            status,
+            format!("Download failed ({}): {}", status, text),
+        ));
+    }
+
+    // Servers without range support answer 200 with the whole file
+    let resumed = offset > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut downloaded = if resumed { offset } else { 0 };
+    let total = response.content_length().map(|len| len + downloaded);
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&partial)?;
+
+    let mut reported = downloaded;
+    on_progress(downloaded, total);
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk)?;
+        downloaded += chunk.len() as u64;
+        if downloaded - reported >= PROGRESS_STEP_BYTES {
+            on_progress(downloaded, total);
+            reported = downloaded;
+        }
+    }
+    file.flush()?;
+    if reported != downloaded {
+        on_progress(downloaded, total);
+    }
+    Ok((partial, name, resumed))
+}
+
+/// Give a finished download its final name, or remove it when its contents are
+/// not a supported input
+fn finish_download(
+    partial: &Path,
+    name: &str,
+) -> Result<(PathBuf, &'static str, String), TahweelError> {
+    let (extension, mime_type) = match supported_extension(partial) {
+        Ok(found) => found,
+        Err(e) => {
+            let _ = fs::remove_file(partial);
+            return Err(e);
+        }
+    };
+    let path = partial.with_file_name(name_with_extension(name, extension));
+    fs::rename(partial, &path)?;
+    Ok((path, extension, mime_type))
+}
+
+/// Download a remote file (e.g. a direct archive.org link) into the workspace with
+/// `download-progress` events, verify it is a supported input, and return it for
+/// processing as a normal job. A failed download keeps its partial file, and calling
+/// again with the same URL resumes it.
+#[tauri::command]
+#[tracing::instrument(skip(options, app), err)]
+pub async fn process_url(
+    url: String,
+    options: Option<UrlJobOptions>,
+    app: AppHandle,
+) -> Result<DownloadedFile, TahweelError> {
+    let options = options.unwrap_or_default();
+    let parsed = parse_url(&url)?;
+    let output_directory = output::workspace_input_output_dir(
+        options.output_directory,
+        app.path().download_dir().ok(),
+    )?;
+    let dir = download_dir(&path_policy::workspace(), &parsed);
+
+    let client = http::client(OperationClass::Upload)?;
+    let (partial, name, resumed) =
+        download_to(&client, &parsed, None, &dir, progress_emitter(&app, &url)).await?;
+    let (path, _, mime_type) = finish_download(&partial, &name)?;
+    let downloaded = downloaded_file(&path, mime_type, output_directory, resumed)?;
+    tracing::info!(size = downloaded.size, resumed, mime_type = %downloaded.mime_type, "Downloaded input from URL");
+    Ok(downloaded)
+}
+
+/// Emits rate-limited `download-progress` events for `url`; the last one is sent
+/// when the returned callback is dropped
+fn progress_emitter<'a>(app: &AppHandle, url: &'a str) -> impl FnMut(u64, Option<u64>) + 'a {
+    let progress = ThrottledEmitter::for_event(app, "download-progress", PROGRESS_INTERVAL);
+    move |downloaded, total| {
+        progress.emit(DownloadProgress {
+            url: url.to_string(),
+            downloaded_bytes: downloaded,
+            total_bytes: total,
+        });
+    }
+}
+
+fn downloaded_file(
+    path: &Path,
+    mime_type: String,
+    output_directory: String,
+    resumed: bool,
+) -> Result<DownloadedFile, TahweelError> {
+    Ok(DownloadedFile {
+        name: path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        path: long_path::path_string(path)?,
+        size: fs::metadata(path)?.len(),
+        mime_type,
+        output_directory,
+        resumed,
+    })
+}
+
+/// Download a file picked from the user's Google Drive (see `list_drive_files`)
+/// into the workspace and return it for processing as a normal job, like
+/// `process_url`. Google Docs are downloaded as their PDF export.
+#[tauri::command]
+#[tracing::instrument(skip(access_token, options, app), err)]
+pub async fn process_drive_file(
+    file_id: String,
+    access_token: String,
+    options: Option<UrlJobOptions>,
+    app: AppHandle,
+) -> Result<DownloadedFile, TahweelError> {
+    let options = options.unwrap_or_default();
+    let output_directory = output::workspace_input_output_dir(
+        options.output_directory,
+        app.path().download_dir().ok(),
+    )?;
+    let entry = google_drive::get_drive_entry(&file_id, &access_token).await?;
+    let source = parse_url(&entry.download_url())?;
+    let dir = download_dir(&path_policy::workspace(), &source);
+
+    let client = http::client(OperationClass::Upload)?;
+    let (partial, _, resumed) = download_to(
+        &client,
+        &source,
+        Some(&access_token),
+        &dir,
+        progress_emitter(&app, source.as_str()),
+    )
+    .await?;
+    let (path, _, mime_type) = finish_download(&partial, &entry.name)?;
+    let downloaded = downloaded_file(&path, mime_type, output_directory, resumed)?;
+    tracing::info!(size = downloaded.size, resumed, mime_type = %downloaded.mime_type, "Downloaded input from Drive");
+    Ok(downloaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const PDF: &[u8] = b"%PDF-1.7\n1 0 obj\n<<>>\nendobj\ntrailer\n<<>>\n%%EOF\n";
+
+    fn url(value: &str) -> url::Url {
+        url::Url::parse(value).unwrap()
+    }
+
+    fn client() -> reqwest::Client {
+        reqwest::Client::builder().no_proxy().build().unwrap()
+    }
+
+    #[test]
+    fn test_parse_url_accepts_only_http() {
+        assert!(parse_url("https://archive.org/download/x/x.pdf").is_ok());
+        assert!(parse_url(" http://example.com/a.png ").is_ok());
+        assert_eq!(
+            parse_url("file:///etc/passwd").unwrap_err().kind(),
+            "invalidInput"
+        );
+        assert!(parse_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_download_dir_is_stable_per_url() {
+        let ws = Path::new("/ws");
+        let a = download_dir(ws, &url("https://example.com/a.pdf"));
+        assert_eq!(a, download_dir(ws, &url("https://example.com/a.pdf")));
+        assert_ne!(a, download_dir(ws, &url("https://example.com/b.pdf")));
+        assert!(a.starts_with(ws));
+    }
+
+    #[test]
+    fn test_file_name_for() {
+        let u = url("https://archive.org/download/item/%D9%83%D8%AA%D8%A7%D8%A8.pdf");
+        assert_eq!(file_name_for(&u, None), "كتاب.pdf");
+        assert_eq!(
+            file_name_for(&u, Some(r#"attachment; filename="book.pdf""#)),
+            "book.pdf"
+        );
+        assert_eq!(
+            file_name_for(
+                &u,
+                Some("attachment; filename=x.pdf; filename*=UTF-8''%D8%B3%D9%81%D8%B1.pdf")
+            ),
+            "سفر.pdf"
+        );
+        assert_eq!(
+            file_name_for(&u, Some(r#"attachment; filename="../../etc/evil.pdf""#)),
+            "evil.pdf"
+        );
+        assert_eq!(
+            file_name_for(&u, Some(r#"attachment; filename="a:b?.pdf""#)),
+            "a_b_.pdf"
+        );
+        assert_eq!(
+            file_name_for(&url("https://example.com/"), None),
+            "download"
+        );
+    }
+
+    #[test]
+    fn test_name_with_extension() {
+        assert_eq!(name_with_extension("book.pdf", "pdf"), "book.pdf");
+        assert_eq!(name_with_extension("scan.JPEG", "jpg"), "scan.JPEG");
+        assert_eq!(name_with_extension("download", "pdf"), "download.pdf");
+        assert_eq!(name_with_extension("page.png", "pdf"), "page.png.pdf");
+    }
+
+    #[tokio::test]
+    async fn test_download_full_file() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/files/book.pdf")
+            .with_status(200)
+            .with_body(PDF)
+            .create_async()
+            .await;
+        let temp = tempdir().unwrap();
+        let mut progress = Vec::new();
+
+        let (partial, name, resumed) = download_to(
+            &client(),
+            &url(&format!("{}/files/book.pdf", server.url())),
+            None,
+            temp.path(),
+            |done, total| progress.push((done, total)),
+        )
+        .await
+        .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(name, "book.pdf");
+        assert!(!resumed);
+        assert_eq!(fs::read(&partial).unwrap(), PDF);
+        let len = PDF.len() as u64;
+        assert_eq!(progress.last(), Some(&(len, Some(len))));
+
+        let (path, extension, mime_type) = finish_download(&partial, &name).unwrap();
+        assert_eq!(path, temp.path().join("book.pdf"));
+        assert_eq!(extension, "pdf");
+        assert_eq!(mime_type, "application/pdf");
+        assert!(!partial.exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_resumes_partial_file() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/book.pdf")
+            .match_header("range", "bytes=4-")
+            .with_status(206)
+            .with_body(&PDF[4..])
+            .create_async()
+            .await;
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join(PARTIAL_FILE), &PDF[..4]).unwrap();
+
+        let (partial, _, resumed) = download_to(
+            &client(),
+            &url(&format!("{}/book.pdf", server.url())),
+            None,
+            temp.path(),
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+
+        mock.assert_async().await;
+        assert!(resumed);
+        assert_eq!(fs::read(&partial).unwrap(), PDF);
+    }
+
+    #[tokio::test]
+    async fn test_download_restarts_without_range_support() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/book.pdf")
+            .with_status(200)
+            .with_body(PDF)
+            .create_async()
+            .await;
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join(PARTIAL_FILE), b"stale bytes").unwrap();
+
+        let (partial, _, resumed) = download_to(
+            &client(),
+            &url(&format!("{}/book.pdf", server.url())),
+            None,
+            temp.path(),
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+
+        assert!(!resumed);
+        assert_eq!(fs::read(&partial).unwrap(), PDF);
+    }
+
+    #[tokio::test]
+    async fn test_download_http_error() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/missing.pdf")
+            .with_status(404)
+            .create_async()
+            .await;
+        let temp = tempdir().unwrap();
+
+        let err = download_to(
+            &client(),
+            &url(&format!("{}/missing.pdf", server.url())),
+            None,
+            temp.path(),
+            |_, _| {},
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), "api");
+    }
+
+    #[tokio::test]
+    async fn test_download_sends_access_token() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/drive/v3/files/abc?alt=media")
+            .match_header("authorization", "Bearer token")
+            .with_status(200)
+            .with_body(PDF)
+            .create_async()
+            .await;
+        let temp = tempdir().unwrap();
+
+        let (partial, _, _) = download_to(
+            &client(),
+            &url(&format!("{}/drive/v3/files/abc?alt=media", server.url())),
+            Some("token"),
+            temp.path(),
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+
+        mock.assert_async().await;
+        let (path, _, _) = finish_download(&partial, "تقرير").unwrap();
+        assert_eq!(path.file_name().unwrap(), "تقرير.pdf");
+    }
+
+    #[test]
+    fn test_finish_download_rejects_unsupported_content() {
+        let temp = tempdir().unwrap();
+        let partial = temp.path().join(PARTIAL_FILE);
+        fs::write(&partial, b"<html>Not found</html>").unwrap();
+
+        let err = finish_download(&partial, "book.pdf").unwrap_err();
+        assert_eq!(err.kind(), "invalidInput");
+        assert!(!partial.exists());
+    }
+
+    #[test]
+    fn test_finish_download_adds_missing_extension() {
+        let temp = tempdir().unwrap();
+        let partial = temp.path().join(PARTIAL_FILE);
+        fs::write(&partial, PDF).unwrap();
+
+        let (path, _, _) = finish_download(&partial, "download").unwrap();
+        assert_eq!(path.file_name().unwrap(), "download.pdf");
+    }
+}
@@ -0,0 +1,171 @@
+use crate::error::TahweelError;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+/// Largest image Google Docs accepts, in pixels (25 megapixels)
+pub const MAX_PIXELS: u64 = 25_000_000;
+/// Largest image Drive runs OCR on when converting it to a Google Doc (2 MB)
+pub const MAX_BYTES: usize = 2 * 1000 * 1000;
+/// Re-encodes tried before a page is given up on
+const MAX_ATTEMPTS: u32 = 8;
+/// Extra shrink on each attempt, as PNG size does not scale exactly with pixel count
+const SHRINK_MARGIN: f64 = 0.9;
+
+/// A page that had to be made smaller to stay within Drive's limits
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DownscaledPage {
+    /// 1-based page number
+    pub page: u32,
+    #[serde(rename = "originalWidth")]
+    pub original_width: u32,
+    #[serde(rename = "originalHeight")]
+    pub original_height: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Size of the saved PNG
+    pub bytes: usize,
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, TahweelError> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| TahweelError::Io(format!("Failed to encode PNG: {}", e)))?;
+    Ok(png)
+}
+
+/// Encode `image` as a PNG of at most `max_pixels` pixels and `max_bytes` bytes,
+/// scaling it down (aspect ratio kept) until it fits. Returns the PNG and, when
+/// the page was scaled, what was done to it.
+pub fn fit_png_within(
+    image: &DynamicImage,
+    page: u32,
+    max_pixels: u64,
+    max_bytes: usize,
+) -> Result<(Vec<u8>, Option<DownscaledPage>), TahweelError> {
+    let (original_width, original_height) = (image.width(), image.height());
+    let pixels = original_width as u64 * original_height as u64;
+    let mut scale = if pixels > max_pixels {
+        (max_pixels as f64 / pixels as f64).sqrt()
+    } else {
+        1.0
+    };
+
+    for _ in 0..MAX_ATTEMPTS {
+        let (width, height) = if scale < 1.0 {
+            (
+                ((original_width as f64 * scale).floor() as u32).max(1),
+                ((original_height as f64 * scale).floor() as u32).max(1),
+            )
+        } else {
+            (original_width, original_height)
+        };
+        let png = if (width, height) == (original_width, original_height) {
+            encode_png(image)?
+        } else {
+            encode_png(&image.resize_exact(width, height, FilterType::CatmullRom))?
+        };
+        if png.len() <= max_bytes {
+            let downscaled =
+                (width != original_width || height != original_height).then(|| DownscaledPage {
+                    page,
+                    original_width,
+                    original_height,
+                    width,
+                    height,
+                    bytes: png.len(),
+                });
+            return Ok((png, downscaled));
+        }
+        scale *= (max_bytes as f64 / png.len() as f64).sqrt() * SHRINK_MARGIN;
+    }
+
+    Err(TahweelError::InvalidInput(format!(
+        "Page {} could not be reduced below {} bytes for Google Drive",
+        page, max_bytes
+    )))
+}
+
+/// Encode a rendered page within Drive's documented image limits (`MAX_PIXELS`, `MAX_BYTES`)
+pub fn fit_png(
+    image: &DynamicImage,
+    page: u32,
+) -> Result<(Vec<u8>, Option<DownscaledPage>), TahweelError> {
+    fit_png_within(image, page, MAX_PIXELS, MAX_BYTES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// Noisy image that compresses poorly, so its PNG size tracks its pixel count
+    fn noise(width: u32, height: u32) -> DynamicImage {
+        let mut seed: u32 = 0x9e37_79b9;
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |_, _| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let [r, g, b, _] = seed.to_le_bytes();
+            Rgb([r, g, b])
+        }))
+    }
+
+    #[test]
+    fn test_small_page_is_untouched() {
+        let image = noise(40, 60);
+        let (png, downscaled) = fit_png_within(&image, 1, 10_000, 1_000_000).unwrap();
+        assert!(downscaled.is_none());
+        assert_eq!(image::load_from_memory(&png).unwrap().width(), 40);
+    }
+
+    #[test]
+    fn test_pixel_limit_keeps_aspect_ratio() {
+        let image = noise(200, 300);
+        let (png, downscaled) = fit_png_within(&image, 3, 6_000, 10_000_000).unwrap();
+        let downscaled = downscaled.unwrap();
+        assert_eq!(downscaled.page, 3);
+        assert_eq!(
+            (downscaled.original_width, downscaled.original_height),
+            (200, 300)
+        );
+        assert!(downscaled.width as u64 * downscaled.height as u64 <= 6_000);
+        let ratio = downscaled.width as f64 / downscaled.height as f64;
+        assert!((ratio - 2.0 / 3.0).abs() < 0.01);
+        assert_eq!(downscaled.bytes, png.len());
+    }
+
+    #[test]
+    fn test_byte_limit_shrinks_until_it_fits() {
+        let image = noise(200, 200);
+        let (png, downscaled) = fit_png_within(&image, 1, MAX_PIXELS, 30_000).unwrap();
+        assert!(png.len() <= 30_000);
+        let downscaled = downscaled.unwrap();
+        assert!(downscaled.width < 200);
+        let decoded = image::load_from_memory(&png).unwrap();
+        assert_eq!(decoded.width(), downscaled.width);
+    }
+
+    #[test]
+    fn test_impossible_limit_is_an_error() {
+        let result = fit_png_within(&noise(50, 50), 7, MAX_PIXELS, 10);
+        assert!(matches!(result, Err(TahweelError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_downscaled_page_serialization() {
+        let page = DownscaledPage {
+            page: 2,
+            original_width: 6000,
+            original_height: 9000,
+            width: 4082,
+            height: 6123,
+            bytes: 1_900_000,
+        };
+        let json = serde_json::to_value(&page).unwrap();
+        assert_eq!(json["originalWidth"], 6000);
+        assert_eq!(json["width"], 4082);
+    }
+}
@@ -0,0 +1,419 @@
+use crate::error::TahweelError;
+use crate::launch;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Folders nested deeper than this below a dropped folder are not listed
+const MAX_DEPTH: usize = 16;
+/// Upper bound on files returned for one drop
+const MAX_FILES: usize = 10_000;
+
+/// A file ready to be enqueued
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DroppedFile {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+}
+
+/// Result of expanding dropped files and folders
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ExpandedPaths {
+    pub files: Vec<DroppedFile>,
+    #[serde(rename = "totalSize")]
+    pub total_size: u64,
+    /// Files seen but not listed (unsupported type, filtered out, or over the limit)
+    pub skipped: usize,
+    /// Default output directory: the first dropped folder, or the first file's folder
+    #[serde(rename = "baseDirectory")]
+    pub base_directory: Option<String>,
+}
+
+/// Value of an ASCII, Arabic-Indic (U+0660-0669) or Extended Arabic-Indic
+/// (U+06F0-06F9) digit
+fn digit_value(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => Some(c as u32 - '0' as u32),
+        '\u{0660}'..='\u{0669}' => Some(c as u32 - 0x0660),
+        '\u{06F0}'..='\u{06F9}' => Some(c as u32 - 0x06F0),
+        _ => None,
+    }
+}
+
+fn is_digit(c: char) -> bool {
+    digit_value(c).is_some()
+}
+
+/// Split a string into alternating digit and non-digit runs
+fn chunks(s: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut in_digits = None;
+    for (i, c) in s.char_indices() {
+        let digit = is_digit(c);
+        if in_digits.is_some_and(|d| d != digit) {
+            out.push(&s[start..i]);
+            start = i;
+        }
+        in_digits = Some(digit);
+    }
+    if start < s.len() {
+        out.push(&s[start..]);
+    }
+    out
+}
+
+/// Compare two digit runs by value, whatever digits they are written with
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    let ascii = |s: &str| -> String {
+        s.chars()
+            .filter_map(digit_value)
+            .filter_map(|d| char::from_digit(d, 10))
+            .collect()
+    };
+    let (a, b) = (ascii(a), ascii(b));
+    let a_trimmed = a.trim_start_matches('0');
+    let b_trimmed = b.trim_start_matches('0');
+    a_trimmed
+        .len()
+        .cmp(&b_trimmed.len())
+        .then_with(|| a_trimmed.cmp(b_trimmed))
+}
+
+/// Compare one folder or file name chunk by chunk: digit runs by value, the
+/// rest lowercased, by code point
+fn compare_segments(a: &str, b: &str) -> Ordering {
+    let a_chunks = chunks(a);
+    let b_chunks = chunks(b);
+    for (x, y) in a_chunks.iter().zip(b_chunks.iter()) {
+        let x_digits = x.starts_with(is_digit);
+        let y_digits = y.starts_with(is_digit);
+        let ordering = if x_digits && y_digits {
+            compare_numbers(x, y)
+        } else {
+            x.to_lowercase().cmp(&y.to_lowercase())
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a_chunks.len().cmp(&b_chunks.len())
+}
+
+/// Order names the way people expect: `page2` before `page10`, case-insensitive,
+/// Arabic-Indic digits read as numbers. Paths are compared folder by folder;
+/// exact ties fall back to code points so the order is stable. `naturalCompare`
+/// in utils/naturalSort.ts is the same algorithm, and both are checked against
+/// tests/fixtures/natural_sort.json.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let is_separator = |c: char| c == '/' || c == '\\';
+    let a_parts: Vec<&str> = a.split(is_separator).collect();
+    let b_parts: Vec<&str> = b.split(is_separator).collect();
+    for (x, y) in a_parts.iter().zip(b_parts.iter()) {
+        let ordering = compare_segments(x, y);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a_parts.len().cmp(&b_parts.len()).then_with(|| a.cmp(b))
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.starts_with('.'))
+        .unwrap_or(false)
+}
+
+/// Extensions to keep: the requested ones that are supported, or all supported ones
+fn wanted(path: &Path, extensions: &[String]) -> bool {
+    if !launch::is_supported_file(path) {
+        return false;
+    }
+    if extensions.is_empty() {
+        return true;
+    }
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    extensions
+        .iter()
+        .any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(&ext))
+}
+
+/// Files under `dir`, recursively, naturally sorted by their path relative to `dir`.
+/// Symlinked folders are not followed, so links cannot loop.
+fn list_dir(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| !is_hidden(path))
+        .collect();
+    entries.sort_by(|a, b| {
+        let a_name = a.file_name().unwrap_or_default().to_string_lossy();
+        let b_name = b.file_name().unwrap_or_default().to_string_lossy();
+        natural_cmp(&a_name, &b_name)
+    });
+
+    for path in entries {
+        let Ok(meta) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if meta.is_dir() {
+            if depth < MAX_DEPTH {
+                list_dir(&path, depth + 1, out);
+            }
+        } else if path.is_file() {
+            out.push(path);
+        }
+    }
+}
+
+/// Resolve dropped files and folders into the supported files to process.
+/// Fails when none of the dropped paths exists or a dropped folder cannot be
+/// read; unreadable folders below it are skipped.
+pub fn expand_paths(
+    paths: &[String],
+    extensions: &[String],
+) -> Result<ExpandedPaths, TahweelError> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    let mut base_directory = None;
+
+    for dropped in paths {
+        let path = PathBuf::from(dropped);
+        if path.is_dir() {
+            fs::read_dir(&path).map_err(|e| {
+                TahweelError::Io(format!("Failed to read folder {}: {}", path.display(), e))
+            })?;
+            base_directory.get_or_insert_with(|| path.clone());
+            list_dir(&path, 0, &mut candidates);
+        } else if path.is_file() {
+            base_directory
+                .get_or_insert_with(|| path.parent().map(Path::to_path_buf).unwrap_or_default());
+            candidates.push(path);
+        }
+    }
+    if base_directory.is_none() && !paths.is_empty() {
+        return Err(TahweelError::NotFound(format!(
+            "Dropped paths not found: {}",
+            paths.join(", ")
+        )));
+    }
+
+    let mut files: Vec<DroppedFile> = Vec::new();
+    let mut seen: Vec<PathBuf> = Vec::new();
+    let mut skipped = 0;
+    for path in candidates {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if seen.contains(&canonical) {
+            continue;
+        }
+        seen.push(canonical);
+
+        if !wanted(&path, extensions) || files.len() >= MAX_FILES {
+            skipped += 1;
+            continue;
+        }
+        files.push(DroppedFile {
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            size: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+            path: path.to_string_lossy().to_string(),
+        });
+    }
+
+    Ok(ExpandedPaths {
+        total_size: files.iter().map(|f| f.size).sum(),
+        files,
+        skipped,
+        base_directory: base_directory.map(|d| d.to_string_lossy().to_string()),
+    })
+}
+
+/// Expand paths dropped on the window: folders are listed recursively (hidden
+/// entries skipped), only supported files are kept, optionally narrowed to
+/// `extensions`. The files are granted like dialog picks so outputs can be
+/// written next to them.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(count = paths.len()))]
+pub async fn expand_dropped_paths(
+    paths: Vec<String>,
+    extensions: Option<Vec<String>>,
+    app: AppHandle,
+) -> Result<ExpandedPaths, TahweelError> {
+    let extensions = extensions.unwrap_or_default();
+    let expanded = tauri::async_runtime::spawn_blocking(move || expand_paths(&paths, &extensions))
+        .await
+        .map_err(|e| TahweelError::Internal(format!("Failed to list dropped files: {}", e)))??;
+
+    launch::grant_files(&app, expanded.files.iter().map(|f| PathBuf::from(&f.path)));
+    tracing::info!(
+        files = expanded.files.len(),
+        skipped = expanded.skipped,
+        "Expanded dropped paths"
+    );
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn touch(path: &Path, bytes: usize) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, vec![0u8; bytes]).unwrap();
+    }
+
+    fn names(expanded: &ExpandedPaths) -> Vec<&str> {
+        expanded.files.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec!["page10.png", "Page2.png", "page1.png", "page02b.png"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec!["page1.png", "Page2.png", "page02b.png", "page10.png"]
+        );
+    }
+
+    #[test]
+    fn test_natural_cmp_arabic_names() {
+        assert_eq!(natural_cmp("كتاب 2.pdf", "كتاب 10.pdf"), Ordering::Less);
+        assert_eq!(natural_cmp("a.pdf", "a.pdf"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_natural_cmp_arabic_indic_digits() {
+        let mut names = vec!["صفحة ١٠.png", "صفحة ۳.png", "صفحة 2.png", "صفحة ١.png"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec!["صفحة ١.png", "صفحة 2.png", "صفحة ۳.png", "صفحة ١٠.png"]
+        );
+    }
+
+    #[test]
+    fn test_natural_cmp_shared_vectors() {
+        // The same vectors are checked against naturalCompare in naturalSort.test.ts
+        let groups: Vec<Vec<String>> =
+            serde_json::from_str(include_str!("../tests/fixtures/natural_sort.json")).unwrap();
+        for expected in groups {
+            let mut names = expected.clone();
+            names.reverse();
+            names.sort_by(|a, b| natural_cmp(a, b));
+            assert_eq!(names, expected);
+        }
+    }
+
+    #[test]
+    fn test_expand_folder_recursively_in_natural_order() {
+        let dir = tempdir().unwrap();
+        touch(&dir.path().join("scan10.png"), 3);
+        touch(&dir.path().join("scan2.png"), 2);
+        touch(&dir.path().join("notes.txt"), 1);
+        touch(&dir.path().join("part1").join("book.pdf"), 5);
+        touch(&dir.path().join(".cache").join("hidden.pdf"), 1);
+
+        let expanded = expand_paths(&[dir.path().to_string_lossy().to_string()], &[]).unwrap();
+
+        assert_eq!(
+            names(&expanded),
+            vec!["book.pdf", "scan2.png", "scan10.png"]
+        );
+        assert_eq!(expanded.total_size, 10);
+        assert_eq!(expanded.skipped, 1);
+        assert_eq!(
+            expanded.base_directory.as_deref(),
+            Some(dir.path().to_string_lossy().as_ref())
+        );
+    }
+
+    #[test]
+    fn test_expand_keeps_dropped_file_order() {
+        let dir = tempdir().unwrap();
+        let b = dir.path().join("b.pdf");
+        let a = dir.path().join("a.jpg");
+        touch(&b, 1);
+        touch(&a, 1);
+
+        let expanded = expand_paths(
+            &[
+                b.to_string_lossy().to_string(),
+                a.to_string_lossy().to_string(),
+                b.to_string_lossy().to_string(),
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(names(&expanded), vec!["b.pdf", "a.jpg"]);
+        assert_eq!(
+            expanded.base_directory.as_deref(),
+            Some(dir.path().to_string_lossy().as_ref())
+        );
+    }
+
+    #[test]
+    fn test_expand_with_extension_filter() {
+        let dir = tempdir().unwrap();
+        touch(&dir.path().join("a.pdf"), 1);
+        touch(&dir.path().join("b.PNG"), 1);
+        touch(&dir.path().join("c.txt"), 1);
+
+        let expanded = expand_paths(
+            &[dir.path().to_string_lossy().to_string()],
+            &[".png".to_string(), "txt".to_string()],
+        )
+        .unwrap();
+        assert_eq!(names(&expanded), vec!["b.PNG"]);
+        assert_eq!(expanded.skipped, 2);
+    }
+
+    #[test]
+    fn test_expand_missing_paths() {
+        let error = expand_paths(&["/nonexistent/drop".to_string()], &[]).unwrap_err();
+        assert_eq!(error.kind(), "notFound");
+        assert!(expand_paths(&[], &[]).unwrap().files.is_empty());
+    }
+
+    #[test]
+    fn test_expand_skips_missing_paths_next_to_existing_ones() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.pdf");
+        touch(&a, 1);
+
+        let expanded = expand_paths(
+            &[
+                "/nonexistent/drop".to_string(),
+                a.to_string_lossy().to_string(),
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(names(&expanded), vec!["a.pdf"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_expand_does_not_follow_symlinked_folders() {
+        let dir = tempdir().unwrap();
+        touch(&dir.path().join("a.pdf"), 1);
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("loop")).unwrap();
+
+        let expanded = expand_paths(&[dir.path().to_string_lossy().to_string()], &[]).unwrap();
+        assert_eq!(names(&expanded), vec!["a.pdf"]);
+    }
+}
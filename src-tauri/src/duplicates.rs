@@ -0,0 +1,265 @@
+use crate::encryption;
+use crate::error::TahweelError;
+use crate::settings;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Grayscale thumbnail compared for near duplicates; fine enough that pages of
+/// the same layout differ where their words do
+const THUMB_WIDTH: u32 = 96;
+const THUMB_HEIGHT: u32 = 128;
+/// Pages whose thumbnails differ by at most this much per pixel on average
+/// (of 255) are near duplicates
+const NEAR_DUPLICATE_MAX_DIFFERENCE: f64 = 4.0;
+/// Near duplicates must also have the same dimensions within this fraction
+const SIZE_TOLERANCE: f64 = 0.02;
+
+/// A page that repeats an earlier page of the same document
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DuplicatePage {
+    /// 1-based page number
+    pub page: u32,
+    /// The page's first occurrence
+    #[serde(rename = "duplicateOf")]
+    pub duplicate_of: u32,
+    /// Identical pixels; otherwise visually near-identical (a rescan)
+    pub exact: bool,
+    /// OCR is skipped and the first occurrence's text reused (`skipDuplicatePages`)
+    pub skipped: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Fingerprint {
+    width: u32,
+    height: u32,
+    digest: [u8; 32],
+    thumb: Vec<u8>,
+}
+
+fn fingerprint(image: &DynamicImage) -> Fingerprint {
+    let (width, height) = image.dimensions();
+    let mut hasher = Sha256::new();
+    hasher.update(width.to_le_bytes());
+    hasher.update(height.to_le_bytes());
+    hasher.update(image.to_rgb8().as_raw());
+
+    Fingerprint {
+        width,
+        height,
+        digest: hasher.finalize().into(),
+        thumb: image
+            .resize_exact(THUMB_WIDTH, THUMB_HEIGHT, FilterType::Triangle)
+            .to_luma8()
+            .into_raw(),
+    }
+}
+
+/// Mean absolute difference between two pages' thumbnails
+fn difference(a: &Fingerprint, b: &Fingerprint) -> f64 {
+    let total: u64 = a
+        .thumb
+        .iter()
+        .zip(&b.thumb)
+        .map(|(x, y)| x.abs_diff(*y) as u64)
+        .sum();
+    total as f64 / a.thumb.len().max(1) as f64
+}
+
+fn same_size(a: &Fingerprint, b: &Fingerprint) -> bool {
+    let close = |x: u32, y: u32| (x as f64 - y as f64).abs() <= x.max(y) as f64 * SIZE_TOLERANCE;
+    close(a.width, b.width) && close(a.height, b.height)
+}
+
+/// Match each page against the earlier pages that are not duplicates themselves:
+/// an identical digest first, otherwise the closest near-identical page
+fn find_duplicates(pages: &[Fingerprint], skip: bool) -> Vec<DuplicatePage> {
+    let mut originals: Vec<usize> = Vec::new();
+    let mut duplicates = Vec::new();
+
+    for (index, page) in pages.iter().enumerate() {
+        let exact = originals
+            .iter()
+            .copied()
+            .find(|&o| pages[o].digest == page.digest);
+        let found = exact.map(|o| (o, true)).or_else(|| {
+            originals
+                .iter()
+                .copied()
+                .filter(|&o| same_size(&pages[o], page))
+                .map(|o| (o, difference(&pages[o], page)))
+                .filter(|&(_, d)| d <= NEAR_DUPLICATE_MAX_DIFFERENCE)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(o, _)| (o, false))
+        });
+
+        match found {
+            Some((original, exact)) => duplicates.push(DuplicatePage {
+                page: index as u32 + 1,
+                duplicate_of: original as u32 + 1,
+                exact,
+                skipped: skip,
+            }),
+            None => originals.push(index),
+        }
+    }
+    duplicates
+}
+
+/// Find pages of one document that repeat an earlier page (scans sometimes
+/// contain the same page twice). With the `skipDuplicatePages` option on, they
+/// are marked `skipped` so the pipeline reuses the first occurrence's text.
+#[tauri::command]
+pub async fn detect_duplicate_pages(
+    image_paths: Vec<String>,
+) -> Result<Vec<DuplicatePage>, TahweelError> {
+    // Decoding and hashing every page is CPU-bound; keep it off the async runtime
+    let fingerprints = tauri::async_runtime::spawn_blocking(move || {
+        image_paths
+            .par_iter()
+            .map(|path| {
+                let (image, _) = encryption::open_image(Path::new(path))?;
+                Ok(fingerprint(&image))
+            })
+            .collect::<Result<Vec<_>, TahweelError>>()
+    })
+    .await
+    .map_err(|e| TahweelError::Internal(format!("Failed to fingerprint pages: {}", e)))??;
+    Ok(find_duplicates(
+        &fingerprints,
+        settings::current().skip_duplicate_pages,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    /// A page with text-like lines whose lengths depend on `seed`
+    fn page(seed: u32) -> RgbImage {
+        let mut image = RgbImage::from_pixel(170, 220, Rgb([255, 255, 255]));
+        for line in 0..12 {
+            let y = 20 + line * 16;
+            let end = 20 + (seed * 37 + line * 53) % 130;
+            for x in 20..end {
+                for dy in 0..6 {
+                    image.put_pixel(x, y + dy, Rgb([0, 0, 0]));
+                }
+            }
+        }
+        image
+    }
+
+    fn fingerprint_of(image: RgbImage) -> Fingerprint {
+        fingerprint(&DynamicImage::ImageRgb8(image))
+    }
+
+    #[test]
+    fn test_exact_duplicate() {
+        let pages = [
+            fingerprint_of(page(1)),
+            fingerprint_of(page(2)),
+            fingerprint_of(page(1)),
+        ];
+        assert_eq!(
+            find_duplicates(&pages, false),
+            vec![DuplicatePage {
+                page: 3,
+                duplicate_of: 1,
+                exact: true,
+                skipped: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_near_duplicate_from_scan_noise() {
+        let mut rescan = page(4);
+        for (i, pixel) in rescan.pixels_mut().enumerate() {
+            if i % 97 == 0 {
+                *pixel = Rgb([200, 200, 200]);
+            }
+        }
+        let pages = [fingerprint_of(page(4)), fingerprint_of(rescan)];
+        let duplicates = find_duplicates(&pages, true);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].duplicate_of, 1);
+        assert!(!duplicates[0].exact);
+        assert!(duplicates[0].skipped);
+    }
+
+    #[test]
+    fn test_different_pages_are_not_duplicates() {
+        let pages: Vec<Fingerprint> = (0..6).map(|seed| fingerprint_of(page(seed))).collect();
+        assert!(find_duplicates(&pages, true).is_empty());
+    }
+
+    #[test]
+    fn test_same_layout_different_words_are_not_duplicates() {
+        // Full-width lines of "words" whose gaps fall in different places
+        let words = |seed: u32| {
+            let mut image = RgbImage::from_pixel(340, 440, Rgb([255, 255, 255]));
+            for line in 0..24 {
+                let y = 30 + line * 16;
+                for x in 30..310 {
+                    if (x * 7 + line * 13 + seed * 29) % 23 > 4 {
+                        for dy in 0..7 {
+                            image.put_pixel(x, y + dy, Rgb([0, 0, 0]));
+                        }
+                    }
+                }
+            }
+            image
+        };
+        let pages = [fingerprint_of(words(1)), fingerprint_of(words(2))];
+        assert!(difference(&pages[0], &pages[1]) > NEAR_DUPLICATE_MAX_DIFFERENCE);
+        assert!(find_duplicates(&pages, true).is_empty());
+    }
+
+    #[test]
+    fn test_duplicates_point_to_first_occurrence() {
+        let pages = [
+            fingerprint_of(page(7)),
+            fingerprint_of(page(7)),
+            fingerprint_of(page(7)),
+        ];
+        let duplicates = find_duplicates(&pages, false);
+        assert_eq!(
+            duplicates
+                .iter()
+                .map(|d| d.duplicate_of)
+                .collect::<Vec<_>>(),
+            vec![1, 1]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detect_duplicate_pages_reads_images() {
+        let temp = tempdir().unwrap();
+        let paths: Vec<String> = [1, 2, 1]
+            .iter()
+            .enumerate()
+            .map(|(i, &seed)| {
+                let path = temp.path().join(format!("page-{:04}.png", i + 1));
+                page(seed).save(&path).unwrap();
+                path.to_string_lossy().to_string()
+            })
+            .collect();
+
+        let duplicates = detect_duplicate_pages(paths).await.unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].page, 3);
+        // The option is off by default: detected, but still OCRed
+        assert!(!duplicates[0].skipped);
+
+        let missing = detect_duplicate_pages(vec!["/nonexistent/page.png".to_string()])
+            .await
+            .unwrap_err();
+        assert!(missing.message().contains("Failed to open image"));
+    }
+}
@@ -0,0 +1,730 @@
+use crate::error::TahweelError;
+use crate::http::{self, OperationClass};
+use crate::path_policy::{self, PathAccess};
+use crate::settings::{self, EmailSettings, SmtpSecurity};
+use crate::util::civil_date;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Base64 line length required by MIME (RFC 2045)
+const BASE64_LINE_LEN: usize = 76;
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// An output file offered for the email, with its download link if it was
+/// uploaded somewhere
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmailFile {
+    pub path: String,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// How each output was delivered by the email
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct EmailSummary {
+    pub attached: Vec<String>,
+    /// Too large to attach; the email links to them
+    pub linked: Vec<String>,
+    /// Too large to attach and without a link; only named in the email
+    pub omitted: Vec<String>,
+}
+
+/// A file chosen to be attached
+struct Attachment {
+    name: String,
+    content_type: &'static str,
+    data: Vec<u8>,
+}
+
+trait SmtpStream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> SmtpStream for T {}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn content_type(name: &str) -> &'static str {
+    match name.rsplit('.').next() {
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("json") => "application/json",
+        Some("docx") => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        Some("zip") => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// RFC 5322 date, e.g. `Fri, 24 May 2013 00:00:00 +0000`
+pub fn rfc5322_date(unix_secs: u64) -> String {
+    let (year, month, day) = civil_date(unix_secs);
+    let weekday = ((unix_secs / 86_400 + 4) % 7) as usize;
+    let secs = unix_secs % 86_400;
+    format!(
+        "{}, {} {} {} {:02}:{:02}:{:02} +0000",
+        WEEKDAYS[weekday],
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// RFC 2047 encoded word for non-ASCII header text
+fn encode_header(text: &str) -> String {
+    if text.is_ascii() {
+        text.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", BASE64.encode(text))
+    }
+}
+
+/// Base64 wrapped at the MIME line length
+fn base64_lines(data: &[u8]) -> String {
+    let encoded = BASE64.encode(data);
+    encoded
+        .as_bytes()
+        .chunks(BASE64_LINE_LEN)
+        .map(|line| String::from_utf8_lossy(line).to_string())
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+/// Sender address: `from`, falling back to the login name
+fn sender(email: &EmailSettings) -> &str {
+    if email.from.is_empty() {
+        &email.username
+    } else {
+        &email.from
+    }
+}
+
+/// Whether `host` is this machine, where a login without TLS never leaves it
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_matches(|c| c == '[' || c == ']')
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Check that the email settings are complete enough to send, and that the
+/// addresses cannot smuggle extra headers or SMTP commands
+fn check_configured(email: &EmailSettings) -> Result<(), TahweelError> {
+    let missing: Vec<&str> = [
+        ("host", email.host.as_str()),
+        ("from", sender(email)),
+        ("to", email.to.as_str()),
+    ]
+    .iter()
+    .filter(|(_, value)| value.is_empty())
+    .map(|(name, _)| *name)
+    .collect();
+    if !missing.is_empty() {
+        return Err(TahweelError::InvalidInput(format!(
+            "Email settings are incomplete: {}",
+            missing.join(", ")
+        )));
+    }
+    for (name, address) in [("from", sender(email)), ("to", email.to.as_str())] {
+        if address
+            .chars()
+            .any(|c| c.is_control() || c == '<' || c == '>')
+        {
+            return Err(TahweelError::InvalidInput(format!(
+                "Email address in \"{}\" has characters that are not allowed",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Attach outputs in order while they fit in `max_bytes`; link or name the rest
+fn plan_attachments(
+    files: &[EmailFile],
+    max_bytes: u64,
+) -> Result<(Vec<Attachment>, EmailSummary), TahweelError> {
+    let mut attachments = Vec::new();
+    let mut summary = EmailSummary::default();
+    let mut used = 0u64;
+    for file in files {
+        let name = file_name(&file.path);
+        let path = path_policy::check(Path::new(&file.path), PathAccess::Read)?;
+        let size = fs::metadata(&path)
+            .map_err(|e| TahweelError::Io(format!("Failed to read {}: {}", file.path, e)))?
+            .len();
+        if used + size <= max_bytes {
+            let data = fs::read(&path)
+                .map_err(|e| TahweelError::Io(format!("Failed to read {}: {}", file.path, e)))?;
+            used += size;
+            summary.attached.push(file.path.clone());
+            attachments.push(Attachment {
+                content_type: content_type(&name),
+                name,
+                data,
+            });
+        } else if file.url.is_some() {
+            summary.linked.push(file.path.clone());
+        } else {
+            summary.omitted.push(file.path.clone());
+        }
+    }
+    Ok((attachments, summary))
+}
+
+/// Subject and plain-text body in the UI language
+fn compose(
+    job_name: &str,
+    files: &[EmailFile],
+    summary: &EmailSummary,
+    language: &str,
+) -> (String, String) {
+    let arabic = language == "ar";
+    let subject = if arabic {
+        format!("تحويل: {} جاهز", job_name)
+    } else {
+        format!("Tahweel: {} is ready", job_name)
+    };
+    let mut body = if arabic {
+        format!("انتهى تحويل {}.\n", job_name)
+    } else {
+        format!("Tahweel finished converting {}.\n", job_name)
+    };
+    let mut section = |title: &str, paths: &[String], with_links: bool| {
+        if paths.is_empty() {
+            return;
+        }
+        body.push('\n');
+        body.push_str(title);
+        body.push('\n');
+        for path in paths {
+            let url = files
+                .iter()
+                .find(|file| &file.path == path)
+                .and_then(|file| file.url.as_deref())
+                .filter(|_| with_links);
+            match url {
+                Some(url) => body.push_str(&format!("- {}: {}\n", file_name(path), url)),
+                None => body.push_str(&format!("- {}\n", file_name(path))),
+            }
+        }
+    };
+    if arabic {
+        section("المرفقات:", &summary.attached, false);
+        section("ملفات أكبر من أن تُرفق، حمّلها من:", &summary.linked, true);
+        section(
+            "ملفات أكبر من أن تُرفق (تجدها على الجهاز):",
+            &summary.omitted,
+            false,
+        );
+    } else {
+        section("Attached:", &summary.attached, false);
+        section("Too large to attach, download from:", &summary.linked, true);
+        section(
+            "Too large to attach (find them on the computer):",
+            &summary.omitted,
+            false,
+        );
+    }
+    (subject, body)
+}
+
+/// Full MIME message: a text part followed by the attachments
+fn build_message(
+    email: &EmailSettings,
+    subject: &str,
+    body: &str,
+    attachments: &[Attachment],
+    unix_secs: u64,
+    boundary: &str,
+) -> String {
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\n\
+        Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n",
+        sender(email),
+        email.to,
+        encode_header(subject),
+        rfc5322_date(unix_secs),
+        boundary
+    );
+    message.push_str(&format!(
+        "--{}\r\nContent-Type: text/plain; charset=utf-8\r\n\
+        Content-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+        boundary,
+        base64_lines(body.as_bytes())
+    ));
+    for attachment in attachments {
+        let name = encode_header(&attachment.name);
+        message.push_str(&format!(
+            "--{}\r\nContent-Type: {}; name=\"{}\"\r\nContent-Transfer-Encoding: base64\r\n\
+            Content-Disposition: attachment; filename=\"{}\"\r\n\r\n{}\r\n",
+            boundary,
+            attachment.content_type,
+            name,
+            name,
+            base64_lines(&attachment.data)
+        ));
+    }
+    message.push_str(&format!("--{}--\r\n", boundary));
+    message
+}
+
+/// An SMTP session over a plain or TLS stream
+struct SmtpClient {
+    stream: BufReader<Box<dyn SmtpStream>>,
+    read_timeout: Duration,
+}
+
+impl SmtpClient {
+    fn new(stream: Box<dyn SmtpStream>, read_timeout: Duration) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+            read_timeout,
+        }
+    }
+
+    /// Read a (possibly multi-line) reply and check its code against `expected`
+    async fn reply(&mut self, expected: &[u16]) -> Result<String, TahweelError> {
+        let mut text = String::new();
+        loop {
+            let mut line = String::new();
+            let read = tokio::time::timeout(self.read_timeout, self.stream.read_line(&mut line))
+                .await
+                .map_err(|_| TahweelError::Timeout("SMTP server did not answer".to_string()))??;
+            if read == 0 {
+                return Err(TahweelError::Network(
+                    "SMTP server closed the connection".to_string(),
+                ));
+            }
+            text.push_str(&line);
+            // The last line of a reply has a space after the code, others a dash
+            if line.as_bytes().get(3) != Some(&b'-') {
+                break;
+            }
+        }
+        let code: u16 = text.get(..3).and_then(|c| c.parse().ok()).unwrap_or(0);
+        if expected.contains(&code) {
+            Ok(text)
+        } else if code == 535 || code == 530 {
+            Err(TahweelError::Auth(format!("SMTP: {}", text.trim())))
+        } else {
+            Err(TahweelError::Api(format!("SMTP: {}", text.trim())))
+        }
+    }
+
+    async fn command(&mut self, line: &str, expected: &[u16]) -> Result<String, TahweelError> {
+        let stream = self.stream.get_mut();
+        stream.write_all(line.as_bytes()).await?;
+        stream.write_all(b"\r\n").await?;
+        stream.flush().await?;
+        self.reply(expected).await
+    }
+
+    /// Send the message body after `DATA`, dot-stuffing lines that start with `.`
+    async fn data(&mut self, message: &str) -> Result<(), TahweelError> {
+        self.command("DATA", &[354]).await?;
+        let stuffed = message.replace("\r\n.", "\r\n..");
+        let stream = self.stream.get_mut();
+        stream.write_all(stuffed.as_bytes()).await?;
+        let terminator: &[u8] = if stuffed.ends_with("\r\n") {
+            b".\r\n"
+        } else {
+            b"\r\n.\r\n"
+        };
+        stream.write_all(terminator).await?;
+        stream.flush().await?;
+        self.reply(&[250]).await.map(|_| ())
+    }
+}
+
+async fn tls_connect(
+    host: &str,
+    stream: Box<dyn SmtpStream>,
+) -> Result<Box<dyn SmtpStream>, TahweelError> {
+    let connector = tokio_native_tls::native_tls::TlsConnector::new()
+        .map_err(|e| TahweelError::Internal(e.to_string()))?;
+    let stream = tokio_native_tls::TlsConnector::from(connector)
+        .connect(host, stream)
+        .await
+        .map_err(|e| TahweelError::Network(format!("TLS with {} failed: {}", host, e)))?;
+    Ok(Box::new(stream))
+}
+
+/// Deliver `message` through the configured SMTP server. A login is only sent
+/// over TLS, or in the clear to a server on this machine.
+async fn send_message(
+    email: &EmailSettings,
+    message: &str,
+    connect_timeout: Duration,
+    read_timeout: Duration,
+) -> Result<(), TahweelError> {
+    if !email.username.is_empty()
+        && email.security == SmtpSecurity::None
+        && !is_loopback(&email.host)
+    {
+        return Err(TahweelError::InvalidInput(format!(
+            "Refusing to send the SMTP password to {} without TLS; choose TLS or STARTTLS",
+            email.host
+        )));
+    }
+    let tcp = tokio::time::timeout(
+        connect_timeout,
+        TcpStream::connect((email.host.as_str(), email.port)),
+    )
+    .await
+    .map_err(|_| TahweelError::Timeout(format!("Connecting to {} timed out", email.host)))?
+    .map_err(|e| TahweelError::Network(format!("Could not connect to {}: {}", email.host, e)))?;
+    let stream: Box<dyn SmtpStream> = match email.security {
+        SmtpSecurity::Tls => tls_connect(&email.host, Box::new(tcp)).await?,
+        SmtpSecurity::StartTls | SmtpSecurity::None => Box::new(tcp),
+    };
+
+    let mut client = SmtpClient::new(stream, read_timeout);
+    client.reply(&[220]).await?;
+    client.command("EHLO tahweel", &[250]).await?;
+    if email.security == SmtpSecurity::StartTls {
+        client.command("STARTTLS", &[220]).await?;
+        let stream = tls_connect(&email.host, client.stream.into_inner()).await?;
+        client = SmtpClient::new(stream, read_timeout);
+        client.command("EHLO tahweel", &[250]).await?;
+    }
+    if !email.username.is_empty() {
+        let credentials = BASE64.encode(format!("\0{}\0{}", email.username, email.password));
+        client
+            .command(&format!("AUTH PLAIN {}", credentials), &[235])
+            .await?;
+    }
+    client
+        .command(&format!("MAIL FROM:<{}>", sender(email)), &[250])
+        .await?;
+    client
+        .command(&format!("RCPT TO:<{}>", email.to), &[250, 251])
+        .await?;
+    client.data(message).await?;
+    // The message is accepted at this point; a failed QUIT does not matter
+    let _ = client.command("QUIT", &[221]).await;
+    Ok(())
+}
+
+/// Email the outputs of `job_name` to the configured address. Files that do
+/// not fit in the attachment limit are sent as links when they have one.
+/// Returns `None` when emailing results is disabled.
+#[tauri::command]
+pub async fn email_job_results(
+    job_name: String,
+    files: Vec<EmailFile>,
+) -> Result<Option<EmailSummary>, TahweelError> {
+    let current = settings::current();
+    let email = current.email;
+    if !email.enabled {
+        return Ok(None);
+    }
+    check_configured(&email)?;
+
+    let max_bytes = u64::from(email.max_attachment_mb) * 1024 * 1024;
+    let (attachments, summary) = plan_attachments(&files, max_bytes)?;
+    let (subject, body) = compose(&job_name, &files, &summary, &current.language);
+    let unix_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let boundary = format!("tahweel-{}", uuid::Uuid::new_v4().simple());
+    let message = build_message(&email, &subject, &body, &attachments, unix_secs, &boundary);
+
+    let (connect_timeout, read_timeout) =
+        http::timeouts_for(OperationClass::Upload, &current.timeouts);
+    send_message(&email, &message, connect_timeout, read_timeout).await?;
+    Ok(Some(summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use tokio::net::TcpListener;
+
+    fn email(port: u16) -> EmailSettings {
+        EmailSettings {
+            enabled: true,
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::None,
+            username: "amina@example.com".to_string(),
+            password: "secret".to_string(),
+            to: "amina@example.com".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn file(dir: &Path, name: &str, size: usize, url: Option<&str>) -> EmailFile {
+        let path = dir.join(name);
+        fs::write(&path, vec![b'a'; size]).unwrap();
+        EmailFile {
+            path: path.to_string_lossy().to_string(),
+            url: url.map(str::to_string),
+        }
+    }
+
+    /// A scripted SMTP server that accepts one message and returns the commands
+    /// it received and the message data
+    async fn fake_server(
+        auth_reply: &'static str,
+    ) -> (u16, tokio::task::JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(socket);
+            let mut commands = Vec::new();
+            let mut data = String::new();
+            stream
+                .get_mut()
+                .write_all(b"220 fake ESMTP\r\n")
+                .await
+                .unwrap();
+            loop {
+                let mut line = String::new();
+                if stream.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_string();
+                commands.push(line.clone());
+                let reply: &[u8] = if line.starts_with("EHLO") {
+                    b"250-fake\r\n250 AUTH PLAIN\r\n"
+                } else if line.starts_with("AUTH") {
+                    auth_reply.as_bytes()
+                } else if line == "DATA" {
+                    stream
+                        .get_mut()
+                        .write_all(b"354 go ahead\r\n")
+                        .await
+                        .unwrap();
+                    loop {
+                        let mut body_line = String::new();
+                        stream.read_line(&mut body_line).await.unwrap();
+                        if body_line == ".\r\n" {
+                            break;
+                        }
+                        data.push_str(&body_line);
+                    }
+                    b"250 queued\r\n"
+                } else if line == "QUIT" {
+                    stream.get_mut().write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    b"250 ok\r\n"
+                };
+                stream.get_mut().write_all(reply).await.unwrap();
+            }
+            (commands, data)
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_rfc5322_date() {
+        assert_eq!(rfc5322_date(0), "Thu, 1 Jan 1970 00:00:00 +0000");
+        assert_eq!(
+            rfc5322_date(1_709_210_096),
+            "Thu, 29 Feb 2024 12:34:56 +0000"
+        );
+    }
+
+    #[test]
+    fn test_encode_header() {
+        assert_eq!(encode_header("Tahweel: book"), "Tahweel: book");
+        assert_eq!(
+            encode_header("كتاب"),
+            format!("=?UTF-8?B?{}?=", BASE64.encode("كتاب"))
+        );
+    }
+
+    #[test]
+    fn test_check_configured() {
+        assert!(check_configured(&email(25)).is_ok());
+        let err = check_configured(&EmailSettings::default()).unwrap_err();
+        assert!(err.message().contains("host, from, to"));
+    }
+
+    #[test]
+    fn test_check_configured_rejects_header_injection() {
+        let mut settings = email(25);
+        settings.to = "amina@example.com\r\nBcc: eve@example.com".to_string();
+        assert_eq!(
+            check_configured(&settings).unwrap_err().kind(),
+            "invalidInput"
+        );
+
+        let mut settings = email(25);
+        settings.from = "amina@example.com>\r\nRCPT TO:<eve@example.com".to_string();
+        let err = check_configured(&settings).unwrap_err();
+        assert!(err.message().contains("\"from\""));
+    }
+
+    #[test]
+    fn test_plan_attachments_rejects_paths_outside_the_policy() {
+        let files = vec![EmailFile {
+            path: "/etc/passwd".to_string(),
+            url: None,
+        }];
+        let err = plan_attachments(&files, 1000).err().unwrap();
+        assert_eq!(err.kind(), "pathNotAllowed");
+    }
+
+    #[tokio::test]
+    async fn test_send_message_refuses_a_login_without_tls() {
+        let mut settings = email(25);
+        settings.host = "smtp.example.com".to_string();
+        let err = send_message(
+            &settings,
+            "Subject: test\r\n",
+            Duration::from_secs(5),
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), "invalidInput");
+        assert!(is_loopback("localhost"));
+        assert!(is_loopback("[::1]"));
+    }
+
+    #[test]
+    fn test_plan_attachments_links_large_files() {
+        let temp = tempdir().unwrap();
+        let files = vec![
+            file(temp.path(), "book.txt", 600, None),
+            file(
+                temp.path(),
+                "book.docx",
+                600,
+                Some("https://cdn.example.com/book.docx"),
+            ),
+            file(temp.path(), "book.json", 600, None),
+            file(temp.path(), "small.txt", 300, None),
+        ];
+
+        let (attachments, summary) = plan_attachments(&files, 1000).unwrap();
+
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0].name, "book.txt");
+        assert_eq!(attachments[1].name, "small.txt");
+        assert_eq!(
+            summary.attached,
+            vec![files[0].path.clone(), files[3].path.clone()]
+        );
+        assert_eq!(summary.linked, vec![files[1].path.clone()]);
+        assert_eq!(summary.omitted, vec![files[2].path.clone()]);
+    }
+
+    #[test]
+    fn test_compose_lists_links() {
+        let files = vec![
+            EmailFile {
+                path: "/out/book.txt".to_string(),
+                url: None,
+            },
+            EmailFile {
+                path: "/out/book.docx".to_string(),
+                url: Some("https://cdn.example.com/book.docx".to_string()),
+            },
+        ];
+        let summary = EmailSummary {
+            attached: vec!["/out/book.txt".to_string()],
+            linked: vec!["/out/book.docx".to_string()],
+            omitted: vec![],
+        };
+
+        let (subject, body) = compose("book.pdf", &files, &summary, "en");
+        assert_eq!(subject, "Tahweel: book.pdf is ready");
+        assert!(body.contains("Attached:\n- book.txt\n"));
+        assert!(body.contains("- book.docx: https://cdn.example.com/book.docx"));
+
+        let (subject, _) = compose("book.pdf", &files, &summary, "ar");
+        assert_eq!(subject, "تحويل: book.pdf جاهز");
+    }
+
+    #[test]
+    fn test_build_message_structure() {
+        let attachments = vec![Attachment {
+            name: "كتاب.txt".to_string(),
+            content_type: "text/plain; charset=utf-8",
+            data: "نص".as_bytes().to_vec(),
+        }];
+        let message = build_message(&email(25), "Subject", "Body", &attachments, 0, "b1");
+
+        assert!(message.starts_with("From: amina@example.com\r\nTo: amina@example.com\r\n"));
+        assert!(message.contains("Content-Type: multipart/mixed; boundary=\"b1\""));
+        assert!(message.contains(&format!(
+            "Content-Disposition: attachment; filename=\"=?UTF-8?B?{}?=\"",
+            BASE64.encode("كتاب.txt")
+        )));
+        assert!(message.contains(&BASE64.encode("نص")));
+        assert!(message.ends_with("--b1--\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_send_message_conversation() {
+        let (port, server) = fake_server("235 ok\r\n").await;
+        let message = "Subject: test\r\n\r\n.leading dot\r\n";
+
+        send_message(
+            &email(port),
+            message,
+            Duration::from_secs(5),
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+
+        let (commands, data) = server.await.unwrap();
+        let credentials = BASE64.encode("\0amina@example.com\0secret");
+        assert_eq!(
+            commands,
+            vec![
+                "EHLO tahweel".to_string(),
+                format!("AUTH PLAIN {}", credentials),
+                "MAIL FROM:<amina@example.com>".to_string(),
+                "RCPT TO:<amina@example.com>".to_string(),
+                "DATA".to_string(),
+                "QUIT".to_string(),
+            ]
+        );
+        assert_eq!(data, "Subject: test\r\n\r\n..leading dot\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_send_message_auth_rejected() {
+        let (port, _server) = fake_server("535 bad credentials\r\n").await;
+        let err = send_message(
+            &email(port),
+            "Subject: test\r\n",
+            Duration::from_secs(5),
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, TahweelError::Auth(_)));
+    }
+
+    #[tokio::test]
+    async fn test_email_job_results_disabled_by_default() {
+        let summary = email_job_results("book.pdf".to_string(), vec![])
+            .await
+            .unwrap();
+        assert!(summary.is_none());
+    }
+}
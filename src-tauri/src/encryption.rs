@@ -0,0 +1,266 @@
+use crate::error::TahweelError;
+use crate::settings;
+use image::{DynamicImage, ImageFormat};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Marks an encrypted workspace file; followed by the key id and nonce
+const MAGIC: &[u8; 6] = b"TWENC1";
+const KEY_ID_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + KEY_ID_LEN + NONCE_LEN;
+
+/// Identifies the in-memory key a workspace file was encrypted with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KeyId([u8; KEY_ID_LEN]);
+
+struct Keyring {
+    /// Scope (a render workspace or partial-output directory) to its key
+    scopes: BTreeMap<String, KeyId>,
+    keys: BTreeMap<KeyId, LessSafeKey>,
+}
+
+/// Keys live only in memory: files encrypted by a previous run can't be read again
+static KEYRING: Mutex<Keyring> = Mutex::new(Keyring {
+    scopes: BTreeMap::new(),
+    keys: BTreeMap::new(),
+});
+
+fn crypto_error(context: &str) -> TahweelError {
+    TahweelError::Internal(format!("Workspace encryption failed: {}", context))
+}
+
+/// Whether the `encryptWorkspace` option is on
+pub fn enabled() -> bool {
+    settings::current().encrypt_workspace
+}
+
+/// The key for `scope`, created with random bytes on first use
+pub fn scope_key(scope: &str) -> Result<KeyId, TahweelError> {
+    let mut keyring = KEYRING.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(id) = keyring.scopes.get(scope) {
+        return Ok(*id);
+    }
+
+    let rng = SystemRandom::new();
+    let mut id = [0u8; KEY_ID_LEN];
+    let mut bytes = [0u8; 32];
+    rng.fill(&mut id)
+        .and_then(|_| rng.fill(&mut bytes))
+        .map_err(|_| crypto_error("no random source"))?;
+    let key = UnboundKey::new(&AES_256_GCM, &bytes).map_err(|_| crypto_error("invalid key"))?;
+    bytes.fill(0);
+
+    let id = KeyId(id);
+    keyring.keys.insert(id, LessSafeKey::new(key));
+    keyring.scopes.insert(scope.to_string(), id);
+    Ok(id)
+}
+
+/// The key already created for `scope`, if any
+pub fn existing_key(scope: &str) -> Option<KeyId> {
+    let keyring = KEYRING.lock().unwrap_or_else(|e| e.into_inner());
+    keyring.scopes.get(scope).copied()
+}
+
+/// `scope_key(scope)` when the option is on, `None` otherwise
+pub fn key_if_enabled(scope: &str) -> Result<Option<KeyId>, TahweelError> {
+    if enabled() {
+        scope_key(scope).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Drop the key for `scope`; anything still encrypted with it becomes unreadable
+pub fn forget(scope: &str) {
+    let mut keyring = KEYRING.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(id) = keyring.scopes.remove(scope) {
+        keyring.keys.remove(&id);
+    }
+}
+
+/// Whether `data` starts with the encrypted-file header
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.len() >= HEADER_LEN && data.starts_with(MAGIC)
+}
+
+/// Encrypt `plaintext` as `MAGIC | key id | nonce | ciphertext+tag`
+pub fn encrypt(key: KeyId, plaintext: &[u8]) -> Result<Vec<u8>, TahweelError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| crypto_error("no random source"))?;
+
+    let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + AES_256_GCM.tag_len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&key.0);
+    out.extend_from_slice(&nonce);
+    let mut sealed = plaintext.to_vec();
+
+    let keyring = KEYRING.lock().unwrap_or_else(|e| e.into_inner());
+    let sealing_key = keyring
+        .keys
+        .get(&key)
+        .ok_or_else(|| crypto_error("key was discarded"))?;
+    sealing_key
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(&out[..MAGIC.len() + KEY_ID_LEN]),
+            &mut sealed,
+        )
+        .map_err(|_| crypto_error("seal failed"))?;
+    out.append(&mut sealed);
+    Ok(out)
+}
+
+/// Decrypt data written by `encrypt`, returning the key it used.
+/// Data without the header is returned unchanged with no key.
+pub fn decrypt(data: Vec<u8>) -> Result<(Vec<u8>, Option<KeyId>), TahweelError> {
+    if !is_encrypted(&data) {
+        return Ok((data, None));
+    }
+    let mut id = [0u8; KEY_ID_LEN];
+    id.copy_from_slice(&data[MAGIC.len()..MAGIC.len() + KEY_ID_LEN]);
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce.copy_from_slice(&data[MAGIC.len() + KEY_ID_LEN..HEADER_LEN]);
+    let key = KeyId(id);
+
+    let keyring = KEYRING.lock().unwrap_or_else(|e| e.into_inner());
+    let opening_key = keyring.keys.get(&key).ok_or_else(|| {
+        TahweelError::InvalidInput(
+            "Encrypted workspace file from another session can't be read".to_string(),
+        )
+    })?;
+    let mut sealed = data[HEADER_LEN..].to_vec();
+    let plaintext_len = opening_key
+        .open_in_place(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(&data[..MAGIC.len() + KEY_ID_LEN]),
+            &mut sealed,
+        )
+        .map_err(|_| {
+            TahweelError::InvalidInput("Encrypted workspace file is corrupted".to_string())
+        })?
+        .len();
+    sealed.truncate(plaintext_len);
+    Ok((sealed, Some(key)))
+}
+
+/// Write `data` to `path`, encrypted when `key` is given
+pub fn write_file(path: &Path, data: &[u8], key: Option<KeyId>) -> Result<(), TahweelError> {
+    match key {
+        Some(key) => fs::write(path, encrypt(key, data)?)?,
+        None => fs::write(path, data)?,
+    }
+    Ok(())
+}
+
+/// Read a workspace file, decrypting it if it was encrypted
+pub fn read_file(path: &Path) -> Result<Vec<u8>, TahweelError> {
+    decrypt(fs::read(path)?).map(|(data, _)| data)
+}
+
+/// Open a page image that may be encrypted; also returns its key so images
+/// derived from it (crops) can be encrypted the same way
+pub fn open_image(path: &Path) -> Result<(DynamicImage, Option<KeyId>), TahweelError> {
+    let data =
+        fs::read(path).map_err(|e| TahweelError::Io(format!("Failed to open image: {}", e)))?;
+    let (data, key) = decrypt(data)?;
+    let image = image::load_from_memory(&data)
+        .map_err(|e| TahweelError::InvalidInput(format!("Failed to open image: {}", e)))?;
+    Ok((image, key))
+}
+
+/// Save `image` as PNG at `path`, encrypted when `key` is given
+pub fn save_png(image: &DynamicImage, path: &Path, key: Option<KeyId>) -> Result<(), TahweelError> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| TahweelError::Io(format!("Failed to encode PNG: {}", e)))?;
+    write_file(path, &png, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_round_trip() {
+        let key = scope_key("test-round-trip").unwrap();
+        let sealed = encrypt(key, "نص الصفحة".as_bytes()).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.windows(4).any(|w| w == "نص".as_bytes()));
+
+        let (plain, used) = decrypt(sealed).unwrap();
+        assert_eq!(plain, "نص الصفحة".as_bytes());
+        assert_eq!(used, Some(key));
+    }
+
+    #[test]
+    fn test_scope_key_is_stable_per_scope() {
+        let first = scope_key("test-scope-a").unwrap();
+        assert_eq!(scope_key("test-scope-a").unwrap(), first);
+        assert_eq!(existing_key("test-scope-a"), Some(first));
+        assert_ne!(scope_key("test-scope-b").unwrap(), first);
+        assert_eq!(existing_key("test-scope-missing"), None);
+    }
+
+    #[test]
+    fn test_plain_data_passes_through() {
+        let (plain, key) = decrypt(b"\x89PNG plain".to_vec()).unwrap();
+        assert_eq!(plain, b"\x89PNG plain");
+        assert_eq!(key, None);
+    }
+
+    #[test]
+    fn test_tampered_data_is_rejected() {
+        let key = scope_key("test-tamper").unwrap();
+        let mut sealed = encrypt(key, b"page text").unwrap();
+        *sealed.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            decrypt(sealed),
+            Err(TahweelError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_forgotten_key_cannot_decrypt() {
+        let key = scope_key("test-forget").unwrap();
+        let sealed = encrypt(key, b"page text").unwrap();
+        forget("test-forget");
+        assert!(decrypt(sealed).is_err());
+        assert!(encrypt(key, b"more").is_err());
+        assert_eq!(existing_key("test-forget"), None);
+    }
+
+    #[test]
+    fn test_image_round_trip_keeps_key() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("page-0001.png");
+        let key = scope_key("test-image").unwrap();
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 3, Rgb([10, 20, 30])));
+
+        save_png(&image, &path, Some(key)).unwrap();
+        assert!(image::open(&path).is_err());
+        let (opened, used) = open_image(&path).unwrap();
+        assert_eq!(opened.to_rgb8(), image.to_rgb8());
+        assert_eq!(used, Some(key));
+
+        save_png(&image, &path, None).unwrap();
+        assert!(image::open(&path).is_ok());
+        assert_eq!(open_image(&path).unwrap().1, None);
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        assert!(!enabled());
+        assert_eq!(key_if_enabled("test-disabled").unwrap(), None);
+    }
+}
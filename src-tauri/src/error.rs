@@ -0,0 +1,238 @@
+use crate::{error_catalog, settings};
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// Error returned by commands. Serialized as `{ kind, messageKey, message, details, retriable }`:
+/// `message` is localized in the `language` setting, `details` is the technical text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TahweelError {
+    /// Sign-in failed, or the access token was rejected (401)
+    Auth(String),
+    /// The request never reached the server (DNS, connection refused, TLS)
+    Network(String),
+    Timeout(String),
+    /// 429 from a Google API
+    RateLimited(String),
+    /// 5xx from a Google API
+    Server(String),
+    /// Any other unsuccessful or unreadable API response
+    Api(String),
+    /// A local file or directory does not exist
+    NotFound(String),
+    Io(String),
+    /// The PDFium library is missing or could not be loaded
+    PdfiumUnavailable(String),
+    /// A PDF could not be opened or rendered
+    Pdf(String),
+    InvalidInput(String),
+    /// A path outside the areas the user granted (see `path_policy`)
+    PathNotAllowed(String),
+    /// A written output failed verification (see `verify`)
+    OutputInvalid(String),
+    Internal(String),
+}
+
+impl TahweelError {
+    /// Classify an unsuccessful HTTP response
+    pub fn from_status(status: reqwest::StatusCode, message: String) -> Self {
+        match status.as_u16() {
+            401 => TahweelError::Auth(message),
+            408 => TahweelError::Timeout(message),
+            429 => TahweelError::RateLimited(message),
+            500..=599 => TahweelError::Server(message),
+            _ => TahweelError::Api(message),
+        }
+    }
+
+    /// Stable identifier the UI can branch on
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TahweelError::Auth(_) => "auth",
+            TahweelError::Network(_) => "network",
+            TahweelError::Timeout(_) => "timeout",
+            TahweelError::RateLimited(_) => "rateLimited",
+            TahweelError::Server(_) => "server",
+            TahweelError::Api(_) => "api",
+            TahweelError::NotFound(_) => "notFound",
+            TahweelError::Io(_) => "io",
+            TahweelError::PdfiumUnavailable(_) => "pdfiumUnavailable",
+            TahweelError::Pdf(_) => "pdf",
+            TahweelError::InvalidInput(_) => "invalidInput",
+            TahweelError::PathNotAllowed(_) => "pathNotAllowed",
+            TahweelError::OutputInvalid(_) => "outputInvalid",
+            TahweelError::Internal(_) => "internal",
+        }
+    }
+
+    /// i18n key for the user-facing message (`errors.<kind>` in `src/i18n`)
+    pub fn message_key(&self) -> String {
+        format!("errors.{}", self.kind())
+    }
+
+    /// User-facing message in `language` (see `error_catalog`)
+    pub fn localized_message(&self, language: &str) -> &'static str {
+        error_catalog::message(self.kind(), language)
+    }
+
+    /// Technical detail, for logs and bug reports
+    pub fn message(&self) -> &str {
+        match self {
+            TahweelError::Auth(m)
+            | TahweelError::Network(m)
+            | TahweelError::Timeout(m)
+            | TahweelError::RateLimited(m)
+            | TahweelError::Server(m)
+            | TahweelError::Api(m)
+            | TahweelError::NotFound(m)
+            | TahweelError::Io(m)
+            | TahweelError::PdfiumUnavailable(m)
+            | TahweelError::Pdf(m)
+            | TahweelError::InvalidInput(m)
+            | TahweelError::PathNotAllowed(m)
+            | TahweelError::OutputInvalid(m)
+            | TahweelError::Internal(m) => m,
+        }
+    }
+
+    /// Whether the same request may succeed if repeated later
+    pub fn is_retriable(&self) -> bool {
+        matches!(
+            self,
+            TahweelError::Network(_)
+                | TahweelError::Timeout(_)
+                | TahweelError::RateLimited(_)
+                | TahweelError::Server(_)
+        )
+    }
+}
+
+impl fmt::Display for TahweelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for TahweelError {}
+
+impl Serialize for TahweelError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let language = settings::current().language;
+        let mut state = serializer.serialize_struct("TahweelError", 5)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("messageKey", &self.message_key())?;
+        state.serialize_field("message", self.localized_message(&language))?;
+        state.serialize_field("details", self.message())?;
+        state.serialize_field("retriable", &self.is_retriable())?;
+        state.end()
+    }
+}
+
+impl From<reqwest::Error> for TahweelError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            TahweelError::Timeout(e.to_string())
+        } else if e.is_decode() {
+            TahweelError::Api(format!("Unexpected response: {}", e))
+        } else if e.is_builder() {
+            TahweelError::Internal(e.to_string())
+        } else if let Some(status) = e.status() {
+            TahweelError::from_status(status, e.to_string())
+        } else {
+            TahweelError::Network(e.to_string())
+        }
+    }
+}
+
+impl From<std::io::Error> for TahweelError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => TahweelError::NotFound(e.to_string()),
+            std::io::ErrorKind::TimedOut => TahweelError::Timeout(e.to_string()),
+            _ => TahweelError::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for TahweelError {
+    fn from(e: serde_json::Error) -> Self {
+        TahweelError::Internal(e.to_string())
+    }
+}
+
+/// Lets commands that still return `Result<_, String>` call typed helpers with `?`
+impl From<TahweelError> for String {
+    fn from(e: TahweelError) -> Self {
+        e.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn test_serialization_shape() {
+        let json = serde_json::to_value(TahweelError::RateLimited("slow down".into())).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "rateLimited",
+                "messageKey": "errors.rateLimited",
+                "message": "تم تجاوز حد الطلبات. حاول مرة أخرى بعد قليل.",
+                "details": "slow down",
+                "retriable": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_localized_message() {
+        let error = TahweelError::Auth("invalid_grant: Token has been expired".into());
+        assert_eq!(
+            error.localized_message("en"),
+            "Your sign-in has expired. Please sign in again."
+        );
+        assert!(!error.localized_message("ar").contains("invalid_grant"));
+    }
+
+    #[test]
+    fn test_from_status() {
+        let classify = |code: u16| {
+            TahweelError::from_status(StatusCode::from_u16(code).unwrap(), String::new()).kind()
+        };
+        assert_eq!(classify(401), "auth");
+        assert_eq!(classify(403), "api");
+        assert_eq!(classify(404), "api");
+        assert_eq!(classify(408), "timeout");
+        assert_eq!(classify(429), "rateLimited");
+        assert_eq!(classify(503), "server");
+    }
+
+    #[test]
+    fn test_retriable_kinds() {
+        assert!(TahweelError::Network(String::new()).is_retriable());
+        assert!(TahweelError::Timeout(String::new()).is_retriable());
+        assert!(TahweelError::Server(String::new()).is_retriable());
+        assert!(!TahweelError::Auth(String::new()).is_retriable());
+        assert!(!TahweelError::Api(String::new()).is_retriable());
+        assert!(!TahweelError::Pdf(String::new()).is_retriable());
+    }
+
+    #[test]
+    fn test_from_io_error() {
+        let not_found = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        assert_eq!(TahweelError::from(not_found).kind(), "notFound");
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(TahweelError::from(denied).kind(), "io");
+    }
+
+    #[test]
+    fn test_display_and_string_conversion() {
+        let error = TahweelError::Pdf("Failed to load PDF: bad header".into());
+        assert_eq!(error.to_string(), "Failed to load PDF: bad header");
+        let message: String = error.into();
+        assert_eq!(message, "Failed to load PDF: bad header");
+    }
+}
@@ -0,0 +1,143 @@
+/// Languages the catalog covers; the first is used for unknown languages
+/// (Arabic, matching the frontend's default locale)
+pub const LANGUAGES: &[&str] = &["ar", "en"];
+
+/// (kind, Arabic, English) for every `TahweelError` kind. Keep in sync with the
+/// `errors` section of `src/i18n/index.ts`.
+const CATALOG: &[(&str, &str, &str)] = &[
+    (
+        "auth",
+        "انتهت صلاحية تسجيل الدخول. يرجى تسجيل الدخول مرة أخرى.",
+        "Your sign-in has expired. Please sign in again.",
+    ),
+    (
+        "network",
+        "تعذر الاتصال بالخادم. تحقق من اتصالك بالإنترنت.",
+        "Could not reach the server. Check your internet connection.",
+    ),
+    ("timeout", "انتهت مهلة الطلب.", "The request timed out."),
+    (
+        "rateLimited",
+        "تم تجاوز حد الطلبات. حاول مرة أخرى بعد قليل.",
+        "Too many requests. Please try again shortly.",
+    ),
+    (
+        "server",
+        "خدمة Google غير متاحة حاليًا. حاول مرة أخرى لاحقًا.",
+        "Google's service is unavailable. Please try again later.",
+    ),
+    ("api", "رفضت Google الطلب.", "Google rejected the request."),
+    ("notFound", "الملف غير موجود.", "File not found."),
+    (
+        "io",
+        "تعذرت قراءة الملف أو كتابته.",
+        "Could not read or write the file.",
+    ),
+    (
+        "pdfiumUnavailable",
+        "مكتبة PDFium غير متوفرة.",
+        "The PDFium library is not available.",
+    ),
+    ("pdf", "تعذر فتح ملف PDF.", "Could not open the PDF file."),
+    ("invalidInput", "مدخلات غير صالحة.", "Invalid input."),
+    (
+        "pathNotAllowed",
+        "لا يُسمح بالوصول إلى هذا المسار.",
+        "Access to this path is not allowed.",
+    ),
+    (
+        "outputInvalid",
+        "الملف الناتج تالف ولم يُحفظ بشكل صحيح.",
+        "An output file is corrupt and was not written correctly.",
+    ),
+    (
+        "internal",
+        "حدث خطأ غير متوقع.",
+        "An unexpected error occurred.",
+    ),
+];
+
+/// Whether `language` has translations
+pub fn is_supported_language(language: &str) -> bool {
+    LANGUAGES.contains(&language)
+}
+
+/// User-facing message for an error `kind` in `language`.
+/// Unknown kinds use the `internal` message; unknown languages use Arabic.
+pub fn message(kind: &str, language: &str) -> &'static str {
+    let (_, arabic, english) = CATALOG
+        .iter()
+        .find(|(k, _, _)| *k == kind)
+        .or_else(|| CATALOG.iter().find(|(k, _, _)| *k == "internal"))
+        .copied()
+        .unwrap_or(("internal", "", ""));
+    match language {
+        "en" => english,
+        _ => arabic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::TahweelError;
+
+    fn all_kinds() -> Vec<&'static str> {
+        [
+            TahweelError::Auth(String::new()),
+            TahweelError::Network(String::new()),
+            TahweelError::Timeout(String::new()),
+            TahweelError::RateLimited(String::new()),
+            TahweelError::Server(String::new()),
+            TahweelError::Api(String::new()),
+            TahweelError::NotFound(String::new()),
+            TahweelError::Io(String::new()),
+            TahweelError::PdfiumUnavailable(String::new()),
+            TahweelError::Pdf(String::new()),
+            TahweelError::InvalidInput(String::new()),
+            TahweelError::PathNotAllowed(String::new()),
+            TahweelError::OutputInvalid(String::new()),
+            TahweelError::Internal(String::new()),
+        ]
+        .iter()
+        .map(TahweelError::kind)
+        .collect()
+    }
+
+    #[test]
+    fn test_every_kind_has_translations() {
+        for kind in all_kinds() {
+            assert!(
+                CATALOG.iter().any(|(k, _, _)| *k == kind),
+                "missing catalog entry for {}",
+                kind
+            );
+            assert!(!message(kind, "ar").is_empty());
+            assert!(!message(kind, "en").is_empty());
+            assert_ne!(message(kind, "ar"), message(kind, "en"));
+        }
+    }
+
+    #[test]
+    fn test_message_by_language() {
+        assert_eq!(message("timeout", "en"), "The request timed out.");
+        assert_eq!(message("timeout", "ar"), "انتهت مهلة الطلب.");
+    }
+
+    #[test]
+    fn test_unknown_language_falls_back_to_arabic() {
+        assert_eq!(message("pdf", "fr"), message("pdf", "ar"));
+    }
+
+    #[test]
+    fn test_unknown_kind_uses_internal() {
+        assert_eq!(message("mystery", "en"), "An unexpected error occurred.");
+    }
+
+    #[test]
+    fn test_supported_languages() {
+        assert!(is_supported_language("ar"));
+        assert!(is_supported_language("en"));
+        assert!(!is_supported_language("EN"));
+    }
+}
@@ -0,0 +1,183 @@
+use crate::job_channels;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Shortest gap between two progress events of one emitter
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+struct State<T> {
+    last_sent: Option<Instant>,
+    pending: Option<T>,
+}
+
+/// Rate-limited progress events: at most one per `min_interval`, with updates in
+/// between coalesced into the latest one, which is sent on the next update after
+/// the interval or by `flush` (also run on drop), so the final state always arrives.
+/// Safe to share between render threads.
+pub struct ThrottledEmitter<T, S: Fn(&T)> {
+    sink: S,
+    min_interval: Duration,
+    state: Mutex<State<T>>,
+}
+
+impl<T: Serialize + Clone> ThrottledEmitter<T, Box<dyn Fn(&T) + Send + Sync>> {
+    /// Emit `event` to the frontend through `app`
+    pub fn for_event(app: &AppHandle, event: &'static str, min_interval: Duration) -> Self {
+        let app = app.clone();
+        Self::new(
+            min_interval,
+            Box::new(move |payload: &T| {
+                let _ = app.emit(event, payload.clone());
+            }),
+        )
+    }
+
+    /// Like `for_event`, also sending each payload to the subscribers of
+    /// `job_id` (see `job_channels`)
+    pub fn for_job_event(
+        app: &AppHandle,
+        event: &'static str,
+        job_id: Option<String>,
+        min_interval: Duration,
+    ) -> Self {
+        let app = app.clone();
+        Self::new(
+            min_interval,
+            Box::new(move |payload: &T| {
+                let _ = app.emit(event, payload.clone());
+                if let Some(job_id) = job_id.as_deref() {
+                    job_channels::send(job_id, event, payload);
+                }
+            }),
+        )
+    }
+}
+
+impl<T, S: Fn(&T)> ThrottledEmitter<T, S> {
+    pub fn new(min_interval: Duration, sink: S) -> Self {
+        Self {
+            sink,
+            min_interval,
+            state: Mutex::new(State {
+                last_sent: None,
+                pending: None,
+            }),
+        }
+    }
+
+    /// Send `value`, or keep it as the latest state until the interval has passed
+    pub fn emit(&self, value: T) {
+        self.emit_with(|| value);
+    }
+
+    /// Like `emit`, with the value built under the emitter's lock so values from
+    /// concurrent callers (e.g. a shared page counter) are ordered
+    pub fn emit_with(&self, make: impl FnOnce() -> T) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let value = make();
+        let now = Instant::now();
+        let due = state
+            .last_sent
+            .map_or(true, |sent| now.duration_since(sent) >= self.min_interval);
+        if due {
+            state.pending = None;
+            state.last_sent = Some(now);
+            (self.sink)(&value);
+        } else {
+            state.pending = Some(value);
+        }
+    }
+
+    /// Send the coalesced state, if an update is still waiting
+    pub fn flush(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(value) = state.pending.take() {
+            state.last_sent = Some(Instant::now());
+            (self.sink)(&value);
+        }
+    }
+}
+
+impl<T, S: Fn(&T)> Drop for ThrottledEmitter<T, S> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn recorder() -> (Arc<Mutex<Vec<u32>>>, impl Fn(&u32)) {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let sent = sent.clone();
+            move |value: &u32| sent.lock().unwrap().push(*value)
+        };
+        (sent, sink)
+    }
+
+    #[test]
+    fn test_coalesces_updates_within_interval() {
+        let (sent, sink) = recorder();
+        let emitter = ThrottledEmitter::new(Duration::from_secs(60), sink);
+        for page in 1..=2000 {
+            emitter.emit(page);
+        }
+        assert_eq!(*sent.lock().unwrap(), vec![1]);
+
+        emitter.flush();
+        assert_eq!(*sent.lock().unwrap(), vec![1, 2000]);
+        // Nothing new to send
+        emitter.flush();
+        assert_eq!(sent.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_flushes_on_drop() {
+        let (sent, sink) = recorder();
+        {
+            let emitter = ThrottledEmitter::new(Duration::from_secs(60), sink);
+            emitter.emit(1);
+            emitter.emit(2);
+            emitter.emit(3);
+        }
+        assert_eq!(*sent.lock().unwrap(), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_sends_again_after_interval() {
+        let (sent, sink) = recorder();
+        let emitter = ThrottledEmitter::new(Duration::from_millis(20), sink);
+        emitter.emit(1);
+        emitter.emit(2);
+        std::thread::sleep(Duration::from_millis(30));
+        emitter.emit(3);
+        assert_eq!(*sent.lock().unwrap(), vec![1, 3]);
+        drop(emitter);
+        assert_eq!(*sent.lock().unwrap(), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_concurrent_updates_end_at_the_last_value() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let (sent, sink) = recorder();
+        let count = AtomicU32::new(0);
+        let emitter = ThrottledEmitter::new(Duration::from_secs(60), sink);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..250 {
+                        emitter.emit_with(|| count.fetch_add(1, Ordering::Relaxed) + 1);
+                    }
+                });
+            }
+        });
+        drop(emitter);
+        assert_eq!(sent.lock().unwrap().last(), Some(&2000));
+    }
+}
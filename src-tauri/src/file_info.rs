@@ -0,0 +1,262 @@
+use crate::error::TahweelError;
+use crate::output;
+use crate::pdf;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::AppHandle;
+
+/// Space on the volume holding a path
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DiskSpace {
+    /// The existing directory the figures were read from
+    pub path: String,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    /// Space usable by the current user (may be less than the free space)
+    #[serde(rename = "availableBytes")]
+    pub available_bytes: u64,
+}
+
+/// Facts about an input file, computed once in the backend
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FileInfo {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    /// Seconds since the Unix epoch
+    #[serde(rename = "modifiedAt")]
+    pub modified_at: Option<u64>,
+    pub sha256: String,
+    /// Sniffed from the file's contents, falling back to its extension
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    /// Only set for PDFs
+    #[serde(rename = "pageCount")]
+    pub page_count: Option<u32>,
+}
+
+/// Nearest existing ancestor of `path` (output folders may not be created yet)
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|p| !p.as_os_str().is_empty() && p.exists())
+        .map(Path::to_path_buf)
+}
+
+// `statvfs` field widths differ between platforms, so the conversions are not always no-ops
+#[cfg(unix)]
+#[allow(clippy::useless_conversion)]
+fn volume_space(path: &Path) -> Result<(u64, u64), String> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| format!("Invalid path: {}", e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `c_path` is NUL-terminated and `stat` is a valid out-pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    let block_size = u64::from(stat.f_frsize);
+    Ok((
+        u64::from(stat.f_blocks) * block_size,
+        u64::from(stat.f_bavail) * block_size,
+    ))
+}
+
+#[cfg(windows)]
+fn volume_space(path: &Path) -> Result<(u64, u64), String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    let mut total = 0u64;
+    let mut free = 0u64;
+    // SAFETY: `wide` is NUL-terminated and the out-pointers are valid
+    if unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, &mut total, &mut free) } == 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok((total, available))
+}
+
+/// Total and available space on the volume holding `path`
+pub fn disk_space(path: &Path) -> Result<DiskSpace, TahweelError> {
+    let existing = existing_ancestor(path)
+        .ok_or_else(|| TahweelError::NotFound(format!("No such path: {}", path.display())))?;
+    let (total_bytes, available_bytes) = volume_space(&existing)
+        .map_err(|e| TahweelError::Io(format!("Failed to read disk space: {}", e)))?;
+    Ok(DiskSpace {
+        path: existing.to_string_lossy().to_string(),
+        total_bytes,
+        available_bytes,
+    })
+}
+
+/// MIME type from the file's magic bytes, or from its extension when unrecognized
+pub fn sniff_mime_type(path: &Path) -> String {
+    if let Ok(Some(kind)) = infer::get_from_path(path) {
+        return kind.mime_type().to_string();
+    }
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "pdf" => "application/pdf",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "txt" => "text/plain",
+        _ => "application/octet-stream",
+    }
+    .to_string()
+}
+
+/// Everything but the page count, which needs PDFium
+pub fn basic_info(path: &Path) -> Result<FileInfo, TahweelError> {
+    let metadata = fs::metadata(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            TahweelError::NotFound(format!("File not found: {}", path.display()))
+        }
+        _ => TahweelError::Io(format!("Failed to read {}: {}", path.display(), e)),
+    })?;
+    if !metadata.is_file() {
+        return Err(TahweelError::InvalidInput(format!(
+            "Not a file: {}",
+            path.display()
+        )));
+    }
+    Ok(FileInfo {
+        path: path.to_string_lossy().to_string(),
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        size: metadata.len(),
+        modified_at: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
+        sha256: output::sha256_file(path).map_err(TahweelError::Io)?,
+        mime_type: sniff_mime_type(path),
+        page_count: None,
+    })
+}
+
+/// Disk space for the volume holding `path`; missing folders are measured at their
+/// nearest existing parent
+#[tauri::command]
+pub async fn get_disk_space(path: String) -> Result<DiskSpace, TahweelError> {
+    disk_space(Path::new(&path))
+}
+
+/// Size, modification time, checksum, MIME type and (for PDFs) page count of a file
+#[tauri::command]
+#[tracing::instrument(skip(app), err)]
+pub async fn get_file_info(path: String, app: AppHandle) -> Result<FileInfo, TahweelError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut info = basic_info(Path::new(&path))?;
+        if info.mime_type == "application/pdf" {
+            info.page_count = Some(pdf::page_count(&path, &app)?);
+        }
+        Ok(info)
+    })
+    .await
+    .map_err(|e| TahweelError::Internal(format!("Failed to read file info: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_disk_space_for_existing_dir() {
+        let dir = tempdir().unwrap();
+        let space = disk_space(dir.path()).unwrap();
+        assert!(space.total_bytes > 0);
+        assert!(space.available_bytes <= space.total_bytes);
+        assert_eq!(space.path, dir.path().to_string_lossy());
+    }
+
+    #[test]
+    fn test_disk_space_uses_nearest_existing_parent() {
+        let dir = tempdir().unwrap();
+        let missing = dir.path().join("out").join("nested");
+        let space = disk_space(&missing).unwrap();
+        assert_eq!(space.path, dir.path().to_string_lossy());
+    }
+
+    #[test]
+    fn test_sniff_mime_type_prefers_contents() {
+        let dir = tempdir().unwrap();
+        let disguised = dir.path().join("scan.jpg");
+        fs::write(&disguised, b"%PDF-1.7\n").unwrap();
+        assert_eq!(sniff_mime_type(&disguised), "application/pdf");
+
+        let png = dir.path().join("image.bin");
+        fs::write(&png, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        assert_eq!(sniff_mime_type(&png), "image/png");
+    }
+
+    #[test]
+    fn test_sniff_mime_type_falls_back_to_extension() {
+        let dir = tempdir().unwrap();
+        let jpg = dir.path().join("empty.JPG");
+        fs::write(&jpg, b"").unwrap();
+        assert_eq!(sniff_mime_type(&jpg), "image/jpeg");
+
+        let unknown = dir.path().join("data.xyz");
+        fs::write(&unknown, b"abc").unwrap();
+        assert_eq!(sniff_mime_type(&unknown), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_basic_info() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, b"abc").unwrap();
+
+        let info = basic_info(&path).unwrap();
+        assert_eq!(info.name, "notes.txt");
+        assert_eq!(info.size, 3);
+        assert!(info.modified_at.unwrap() > 0);
+        assert_eq!(
+            info.sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(info.mime_type, "text/plain");
+        assert!(info.page_count.is_none());
+    }
+
+    #[test]
+    fn test_basic_info_errors() {
+        let dir = tempdir().unwrap();
+        assert_eq!(
+            basic_info(&dir.path().join("missing.pdf"))
+                .unwrap_err()
+                .kind(),
+            "notFound"
+        );
+        assert_eq!(basic_info(dir.path()).unwrap_err().kind(), "invalidInput");
+    }
+
+    #[test]
+    fn test_file_info_serializes_camel_case() {
+        let info = FileInfo {
+            path: "/a/b.pdf".to_string(),
+            name: "b.pdf".to_string(),
+            size: 1,
+            modified_at: Some(2),
+            sha256: "x".to_string(),
+            mime_type: "application/pdf".to_string(),
+            page_count: Some(3),
+        };
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["modifiedAt"], 2);
+        assert_eq!(json["mimeType"], "application/pdf");
+        assert_eq!(json["pageCount"], 3);
+    }
+}
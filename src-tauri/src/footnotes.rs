@@ -0,0 +1,392 @@
+use crate::encryption;
+use crate::error::TahweelError;
+use crate::layout::{BoundingBox, OcrWord, PageLayout};
+use crate::settings;
+use image::GenericImageView;
+use serde::Serialize;
+use std::path::Path;
+
+/// Footnotes are only searched for below this fraction of the page height
+const RULE_SEARCH_START: f64 = 0.4;
+/// ...and above this one, so bottom borders and page numbers are ignored
+const RULE_SEARCH_END: f64 = 0.95;
+/// Minimum length of a footnote rule as a fraction of the page width
+const MIN_RULE_RATIO: f64 = 0.15;
+/// Maximum thickness of a footnote rule as a fraction of the page height
+const MAX_RULE_THICKNESS_RATIO: f64 = 0.01;
+/// Rows just above and below a rule (as a fraction of the page height) that must be blank
+const RULE_CLEARANCE_RATIO: f64 = 0.004;
+/// Grayscale level below which a pixel counts as ink
+const INK_THRESHOLD: u8 = 128;
+/// Without a visible rule, footnote lines are at most this share of the body line height
+const FOOTNOTE_HEIGHT_RATIO: f64 = 0.85;
+/// ...and start after a gap at least this many times the median line gap
+const FOOTNOTE_GAP_RATIO: f64 = 1.5;
+
+/// Line written between a page's body and its footnotes in plain-text outputs
+pub const FOOTNOTE_SEPARATOR: &str = "\n\n__________\n";
+
+/// A page split into its body and footnotes (image paths or text, depending on
+/// the command). `footnotes` is `None` when the page has none.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FootnoteSplit {
+    pub body: String,
+    pub footnotes: Option<String>,
+}
+
+/// Page text followed by its footnotes, if any, below the footnote separator
+pub fn page_text_with_footnotes(body: &str, footnotes: Option<&str>) -> String {
+    match footnotes.map(str::trim).filter(|notes| !notes.is_empty()) {
+        Some(notes) => format!("{}{}{}", body.trim(), FOOTNOTE_SEPARATOR, notes),
+        None => body.trim().to_string(),
+    }
+}
+
+/// Find the footnote rule in a rendered page image: a thin horizontal line in
+/// the lower part of the page with blank rows around it and text below it.
+/// Returns the y coordinate of the rule's center.
+pub fn detect_image_rule(image: &image::DynamicImage) -> Option<u32> {
+    let gray = image.to_luma8();
+    let (width, height) = gray.dimensions();
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    // Per row: total ink and the longest horizontal run of ink
+    let rows: Vec<(u32, u32)> = (0..height)
+        .map(|y| {
+            let (mut ink, mut run, mut longest) = (0, 0, 0);
+            for x in 0..width {
+                if gray.get_pixel(x, y).0[0] < INK_THRESHOLD {
+                    ink += 1;
+                    run += 1;
+                    longest = longest.max(run);
+                } else {
+                    run = 0;
+                }
+            }
+            (ink, longest)
+        })
+        .collect();
+
+    let min_run = ((width as f64 * MIN_RULE_RATIO) as u32).max(1);
+    let max_thickness = ((height as f64 * MAX_RULE_THICKNESS_RATIO) as u32).max(1);
+    let clearance = ((height as f64 * RULE_CLEARANCE_RATIO) as u32).max(1);
+    // A rule row is mostly one run; text rows scatter their ink across the line
+    let is_rule_row = |y: u32| {
+        let (ink, longest) = rows[y as usize];
+        longest >= min_run && ink <= longest + longest / 5
+    };
+    let is_blank_row = |y: u32| rows[y as usize].0 < min_run / 4;
+
+    let start = (height as f64 * RULE_SEARCH_START) as u32;
+    let end = (height as f64 * RULE_SEARCH_END) as u32;
+    let mut y = start;
+    while y < end {
+        if !is_rule_row(y) {
+            y += 1;
+            continue;
+        }
+        let top = y;
+        while y < end && is_rule_row(y) {
+            y += 1;
+        }
+        let bottom = y;
+        let isolated = top >= clearance
+            && bottom + clearance <= height
+            && (top - clearance..top).all(is_blank_row)
+            && (bottom..bottom + clearance).all(is_blank_row);
+        let text_below = (bottom..height).any(|row| rows[row as usize].0 > 0);
+        if bottom - top <= max_thickness && isolated && text_below {
+            return Some((top + bottom) / 2);
+        }
+    }
+    None
+}
+
+fn median(mut values: Vec<u32>) -> u32 {
+    values.sort_unstable();
+    values.get(values.len() / 2).copied().unwrap_or(0)
+}
+
+fn lines_text(lines: &[Vec<&OcrWord>]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|w| w.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Split a page with word positions into body and footnote text. Without a
+/// visible rule, footnotes are the trailing lines in the lower part of the page
+/// that follow a wide gap and are set in a smaller font than the body.
+/// Pages without geometry or footnotes return their text as the body.
+pub fn split_layout_footnotes(layout: &PageLayout, rtl: bool) -> FootnoteSplit {
+    let unsplit = FootnoteSplit {
+        body: layout.text.clone(),
+        footnotes: None,
+    };
+    if !layout.has_geometry() {
+        return unsplit;
+    }
+
+    let lines = layout.lines(rtl);
+    let boxes: Vec<BoundingBox> = lines
+        .iter()
+        .filter_map(|line| BoundingBox::union(line.iter().map(|w| &w.bbox)))
+        .collect();
+    if boxes.len() < 3 {
+        return unsplit;
+    }
+    let gaps: Vec<u32> = boxes
+        .windows(2)
+        .map(|pair| pair[1].y.saturating_sub(pair[0].bottom()))
+        .collect();
+    let median_gap = median(gaps.clone()).max(1);
+    let search_start = (layout.height as f64 * RULE_SEARCH_START) as u32;
+
+    let split = (1..boxes.len()).find(|&i| {
+        let body_height = median(boxes[..i].iter().map(|b| b.height).collect());
+        let notes_height = median(boxes[i..].iter().map(|b| b.height).collect());
+        boxes[i].y >= search_start
+            && gaps[i - 1] as f64 >= median_gap as f64 * FOOTNOTE_GAP_RATIO
+            && notes_height as f64 <= body_height as f64 * FOOTNOTE_HEIGHT_RATIO
+    });
+    match split {
+        Some(i) => FootnoteSplit {
+            body: lines_text(&lines[..i]),
+            footnotes: Some(lines_text(&lines[i..])),
+        },
+        None => unsplit,
+    }
+}
+
+/// Split a page image at its footnote rule into `page-0001-body.png` and
+/// `page-0001-notes.png` next to it, so both parts are recognized separately.
+/// Returns the original path as the body when no rule is found.
+pub fn split_footnote_images(image_path: &str) -> Result<FootnoteSplit, TahweelError> {
+    let path = Path::new(image_path);
+    let (image, key) = encryption::open_image(path)?;
+    let Some(rule) = detect_image_rule(&image) else {
+        return Ok(FootnoteSplit {
+            body: image_path.to_string(),
+            footnotes: None,
+        });
+    };
+
+    let (width, height) = image.dimensions();
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let save = |suffix: &str, top: u32, bottom: u32| {
+        let part_path = parent.join(format!("{}-{}.png", stem, suffix));
+        encryption::save_png(
+            &image.crop_imm(0, top, width, bottom - top),
+            &part_path,
+            key,
+        )
+        .map_err(|e| TahweelError::Io(format!("Failed to save footnote image: {}", e.message())))?;
+        Ok::<_, TahweelError>(part_path.to_string_lossy().to_string())
+    };
+    Ok(FootnoteSplit {
+        body: save("body", 0, rule)?,
+        footnotes: Some(save("notes", rule + 1, height)?),
+    })
+}
+
+/// Split a page image into body and footnote images when the
+/// `separateFootnotes` option is on; otherwise returns the page unchanged
+#[tauri::command]
+pub async fn split_page_footnotes(image_path: String) -> Result<FootnoteSplit, TahweelError> {
+    if !settings::current().separate_footnotes {
+        return Ok(FootnoteSplit {
+            body: image_path,
+            footnotes: None,
+        });
+    }
+    split_footnote_images(&image_path)
+}
+
+/// Separate each page's footnotes using word positions from geometry-aware
+/// backends when the `separateFootnotes` option is on
+#[tauri::command]
+pub async fn separate_footnotes(pages: Vec<PageLayout>, rtl: bool) -> Vec<FootnoteSplit> {
+    let enabled = settings::current().separate_footnotes;
+    pages
+        .iter()
+        .map(|layout| {
+            if enabled {
+                split_layout_footnotes(layout, rtl)
+            } else {
+                FootnoteSplit {
+                    body: layout.text.clone(),
+                    footnotes: None,
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    fn word(text: &str, x: u32, y: u32, height: u32) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            bbox: BoundingBox {
+                x,
+                y,
+                width: 100,
+                height,
+            },
+            confidence: None,
+        }
+    }
+
+    /// Text-like rows: short dashes separated by spaces
+    fn draw_text_rows(image: &mut RgbImage, top: u32, bottom: u32) {
+        for y in (top..bottom).filter(|y| y % 6 < 3) {
+            for x in (20..180).filter(|x| x % 10 < 6) {
+                image.put_pixel(x, y, Rgb([0, 0, 0]));
+            }
+        }
+    }
+
+    fn page_with_rule(path: &Path, rule: bool) {
+        let mut image = RgbImage::from_pixel(200, 400, Rgb([255, 255, 255]));
+        draw_text_rows(&mut image, 20, 280);
+        if rule {
+            for x in 120..180 {
+                image.put_pixel(x, 300, Rgb([0, 0, 0]));
+            }
+        }
+        draw_text_rows(&mut image, 320, 370);
+        image.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_page_text_with_footnotes() {
+        assert_eq!(
+            page_text_with_footnotes(" body ", Some("1. note")),
+            "body\n\n__________\n1. note"
+        );
+        assert_eq!(page_text_with_footnotes("body", Some("  ")), "body");
+        assert_eq!(page_text_with_footnotes("body", None), "body");
+    }
+
+    #[test]
+    fn test_detect_image_rule() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("page.png");
+        page_with_rule(&path, true);
+        assert_eq!(detect_image_rule(&image::open(&path).unwrap()), Some(300));
+
+        page_with_rule(&path, false);
+        assert_eq!(detect_image_rule(&image::open(&path).unwrap()), None);
+    }
+
+    #[test]
+    fn test_detect_image_rule_ignores_rule_without_text_below() {
+        let mut image = RgbImage::from_pixel(200, 400, Rgb([255, 255, 255]));
+        draw_text_rows(&mut image, 20, 280);
+        for x in 20..180 {
+            image.put_pixel(x, 300, Rgb([0, 0, 0]));
+        }
+        assert_eq!(detect_image_rule(&image.into()), None);
+    }
+
+    #[test]
+    fn test_split_footnote_images() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("page-0001.png");
+        page_with_rule(&path, true);
+
+        let split = split_footnote_images(&path.to_string_lossy()).unwrap();
+        assert!(split.body.ends_with("page-0001-body.png"));
+        let notes = split.footnotes.unwrap();
+        assert!(notes.ends_with("page-0001-notes.png"));
+        assert_eq!(image::open(&split.body).unwrap().height(), 300);
+        assert_eq!(image::open(&notes).unwrap().height(), 99);
+    }
+
+    #[test]
+    fn test_split_footnote_images_without_rule() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("page-0001.png");
+        page_with_rule(&path, false);
+        let path = path.to_string_lossy().to_string();
+
+        let split = split_footnote_images(&path).unwrap();
+        assert_eq!(
+            split,
+            FootnoteSplit {
+                body: path,
+                footnotes: None
+            }
+        );
+        let error = split_footnote_images("/nonexistent/page.png").unwrap_err();
+        assert_eq!(error.kind(), "io");
+        assert!(error.message().contains("Failed to open image"));
+    }
+
+    #[test]
+    fn test_split_layout_footnotes() {
+        let layout = PageLayout {
+            width: 1000,
+            height: 1000,
+            text: "unsplit".to_string(),
+            words: vec![
+                word("b1", 100, 100, 30),
+                word("b2", 100, 140, 30),
+                word("b3", 100, 180, 30),
+                word("b4", 100, 220, 30),
+                word("n1", 100, 800, 20),
+                word("n2", 100, 825, 20),
+            ],
+        };
+        let split = split_layout_footnotes(&layout, true);
+        assert_eq!(split.body, "b1\nb2\nb3\nb4");
+        assert_eq!(split.footnotes.as_deref(), Some("n1\nn2"));
+    }
+
+    #[test]
+    fn test_split_layout_footnotes_same_font_unchanged() {
+        let layout = PageLayout {
+            width: 1000,
+            height: 1000,
+            text: "unsplit".to_string(),
+            words: vec![
+                word("b1", 100, 100, 30),
+                word("b2", 100, 140, 30),
+                word("b3", 100, 180, 30),
+                word("b4", 100, 220, 30),
+                word("b5", 100, 800, 30),
+            ],
+        };
+        let split = split_layout_footnotes(&layout, true);
+        assert_eq!(split.body, "unsplit");
+        assert_eq!(split.footnotes, None);
+    }
+
+    #[tokio::test]
+    async fn test_split_page_footnotes_disabled_by_default() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("page-0001.png");
+        page_with_rule(&path, true);
+        let path = path.to_string_lossy().to_string();
+
+        let split = split_page_footnotes(path.clone()).await.unwrap();
+        assert_eq!(split.body, path);
+        assert_eq!(split.footnotes, None);
+    }
+}
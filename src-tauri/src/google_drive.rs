@@ -1,11 +1,36 @@
+use crate::backend_health::GOOGLE_DRIVE;
+use crate::encryption;
+use crate::error::TahweelError;
+use crate::http::{self, execute_with_retry, execute_with_retry_counted, OperationClass, Retries};
+use crate::job_db::JobDb;
+use crate::job_usage;
+use crate::jobs::{self, JobEventKind, UploadSession};
+use crate::language;
+use crate::logging;
+use crate::long_path;
+use crate::memory;
+use crate::perf::{self, Stage};
+use crate::quota;
 use reqwest::multipart;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 const GOOGLE_DOCS_MIME_TYPE: &str = "application/vnd.google-apps.document";
+/// Pages larger than this are sent with a resumable upload, in chunks of this size
+/// (Drive requires a multiple of 256 KiB)
+const RESUMABLE_CHUNK_BYTES: usize = 4 * 256 * 1024;
+/// Drive keeps a resumable session for a week; older saved sessions are not resumed
+const UPLOAD_SESSION_MAX_AGE_SECS: i64 = 6 * 24 * 60 * 60;
+/// Delay between conversion readiness polls: doubles from the first value up to the cap
+const CONVERSION_POLL_INITIAL_MS: u64 = 250;
+const CONVERSION_POLL_MAX_MS: u64 = 2_000;
+/// Stop waiting for the conversion after this long and export what Drive has
+const CONVERSION_WAIT_MAX_MS: u64 = 20_000;
 
 // Base URLs - can be overridden via environment variables for testing
 fn drive_upload_url() -> String {
@@ -15,20 +40,53 @@ fn drive_upload_url() -> String {
     })
 }
 
-fn drive_files_url() -> String {
+fn drive_resumable_upload_url() -> String {
+    std::env::var("TAHWEEL_TEST_DRIVE_RESUMABLE_URL").unwrap_or_else(|_| {
+        "https://www.googleapis.com/upload/drive/v3/files?uploadType=resumable&fields=id"
+            .to_string()
+    })
+}
+
+pub(crate) fn drive_files_url() -> String {
     std::env::var("TAHWEEL_TEST_DRIVE_FILES_URL")
         .unwrap_or_else(|_| "https://www.googleapis.com/drive/v3/files".to_string())
 }
 
+pub(crate) fn docs_api_url() -> String {
+    std::env::var("TAHWEEL_TEST_DOCS_URL")
+        .unwrap_or_else(|_| "https://docs.googleapis.com/v1/documents".to_string())
+}
+
 #[derive(Debug, Serialize)]
 pub struct UploadResult {
     #[serde(rename = "fileId")]
     pub file_id: String,
+    /// Number of retries needed before the upload succeeded
+    pub retries: u32,
+    /// Of them, retries after Drive throttled the upload (429); the frontend
+    /// lowers its Drive concurrency for each
+    pub throttled: u32,
+    #[serde(rename = "elapsedMs")]
+    pub elapsed_ms: u64,
 }
 
 #[derive(Debug, Serialize)]
 pub struct ExportResult {
     pub text: String,
+    /// Number of retries needed before the export succeeded
+    pub retries: u32,
+    /// Of them, retries after Drive throttled the export (429)
+    pub throttled: u32,
+    #[serde(rename = "elapsedMs")]
+    pub elapsed_ms: u64,
+}
+
+/// A Google Doc created to hold the combined OCR result
+#[derive(Debug, Serialize)]
+pub struct GoogleDocResult {
+    #[serde(rename = "documentId")]
+    pub document_id: String,
+    pub url: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -36,18 +94,66 @@ struct DriveFile {
     id: String,
 }
 
-/// Upload a file to Google Drive as a Google Document (triggers OCR)
+/// The fields of a converted document polled before exporting it
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+struct ConversionStatus {
+    #[serde(rename = "mimeType", default)]
+    mime_type: String,
+    /// Bumped on every change, including the conversion writing its text
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(rename = "exportLinks", default)]
+    export_links: HashMap<String, String>,
+}
+
+/// Whether the conversion looks finished: a Google Doc that can be exported
+/// as text. After a poll that showed it unfinished, its version must also have
+/// stopped changing, as the conversion bumps it while writing the text.
+fn conversion_ready(previous: Option<&ConversionStatus>, current: &ConversionStatus) -> bool {
+    current.mime_type == GOOGLE_DOCS_MIME_TYPE
+        && current.export_links.contains_key("text/plain")
+        && current.version.is_some()
+        && previous.map_or(true, |previous| previous.version == current.version)
+}
+
+/// Upload a file to Google Drive as a Google Document (triggers OCR).
+/// `job_id` tags the log span so a job's uploads can be traced together, and
+/// records the upload time for `get_performance_summary`. `ocr_language` is an
+/// ISO 639-1 hint for the page's language; without it Drive detects the language.
 #[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    err,
+    fields(job = job_id.as_deref(), page = logging::page_from_path(&file_path))
+)]
 pub async fn upload_to_google_drive(
     file_path: String,
     access_token: String,
-) -> Result<UploadResult, String> {
+    job_id: Option<String>,
+    ocr_language: Option<String>,
+) -> Result<UploadResult, TahweelError> {
+    if let Some(code) = ocr_language.as_deref() {
+        if !language::is_valid_hint(code) {
+            return Err(TahweelError::InvalidInput(format!(
+                "Invalid OCR language: {}",
+                code
+            )));
+        }
+    }
     let path = Path::new(&file_path);
-    if !path.exists() {
-        return Err(format!("File not found: {}", file_path));
+    let readable = long_path::extended(path);
+    if !readable.exists() {
+        return Err(TahweelError::NotFound(format!(
+            "File not found: {}",
+            file_path
+        )));
     }
 
-    let file_content = fs::read(&file_path).map_err(|e| e.to_string())?;
+    // Encrypted page images are decrypted only in memory for the upload
+    let file_content = encryption::read_file(&readable)?;
+    // Counted against the memory budget (without waiting) while the upload holds it
+    let _memory = memory::ACCOUNTANT.track(file_content.len() as u64);
+    let size = file_content.len() as u64;
     let file_name = uuid::Uuid::new_v4().to_string();
 
     // Determine MIME type from extension
@@ -64,58 +170,563 @@ pub async fn upload_to_google_drive(
         _ => "application/octet-stream",
     };
 
-    execute_with_retry(|| async {
-        let client = reqwest::Client::new();
+    let metadata = serde_json::json!({
+        "name": file_name,
+        "mimeType": GOOGLE_DOCS_MIME_TYPE
+    });
+
+    // Images queued directly (not split from a PDF) are a one-page job
+    let page = logging::page_from_path(&file_path).unwrap_or(1);
+    let started = Instant::now();
+    let uploaded = if file_content.len() > RESUMABLE_CHUNK_BYTES {
+        upload_resumable(
+            &metadata,
+            &file_content,
+            mime_type,
+            ocr_language.as_deref(),
+            &access_token,
+            &jobs::jobs_db_path(),
+        )
+        .await
+    } else {
+        upload_multipart(
+            &metadata,
+            file_content,
+            mime_type,
+            ocr_language.as_deref(),
+            &access_token,
+        )
+        .await
+    };
+    let (
+        file_id,
+        Retries {
+            count: retries,
+            throttled,
+        },
+    ) = uploaded.map_err(|e| {
+        let message = format!("Upload failed: {}", e.message());
+        jobs::log_event(job_id.as_deref(), JobEventKind::Error, Some(page), message);
+        e
+    })?;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    tracing::info!(%file_id, retries, elapsed_ms, "Uploaded page for OCR");
+    if retries > 0 {
+        let message = format!("Upload needed {} retries", retries);
+        jobs::log_event(job_id.as_deref(), JobEventKind::Retry, Some(page), message);
+    }
+    quota::consume(GOOGLE_DRIVE, 1, u64::from(retries) + 1);
+    if let Some(job_id) = job_id.as_deref() {
+        perf::record(job_id, page, Stage::Upload, elapsed_ms);
+        job_usage::add_transfer(job_id, size, 0, u64::from(retries) + 1);
+        job_usage::observe_memory(job_id, memory::ACCOUNTANT.in_use());
+    }
+
+    Ok(UploadResult {
+        file_id,
+        retries,
+        throttled,
+        elapsed_ms,
+    })
+}
+
+/// MIME type for a finished output document, so Drive stores it as-is (no conversion)
+fn output_mime_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+        .as_str()
+    {
+        "txt" => "text/plain",
+        "json" => "application/json",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "zip" => "application/zip",
+        "xml" => "application/xml",
+        "hocr" | "html" => "text/html",
+        "csv" => "text/csv",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Upload a finished output file to Google Drive, keeping its real name and type.
+/// Unlike OCR uploads, the file is not converted and is meant to stay in Drive.
+#[tauri::command]
+#[tracing::instrument(skip(access_token), err)]
+pub async fn upload_output_to_google_drive(
+    file_path: String,
+    folder_id: Option<String>,
+    access_token: String,
+) -> Result<UploadResult, TahweelError> {
+    let path = Path::new(&file_path);
+    let readable = long_path::extended(path);
+    if !readable.is_file() {
+        return Err(TahweelError::NotFound(format!(
+            "File not found: {}",
+            file_path
+        )));
+    }
+
+    let file_content = fs::read(&readable)?;
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let mime_type = output_mime_type(path);
+
+    let mut metadata = serde_json::json!({
+        "name": file_name,
+        "mimeType": mime_type
+    });
+    if let Some(folder_id) = folder_id.filter(|id| !id.is_empty()) {
+        metadata["parents"] = serde_json::json!([folder_id]);
+    }
+
+    let started = Instant::now();
+    let (
+        file_id,
+        Retries {
+            count: retries,
+            throttled,
+        },
+    ) = upload_multipart(&metadata, file_content, mime_type, None, &access_token).await?;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    tracing::info!(
+        path = %path.display(),
+        %file_id,
+        retries,
+        elapsed_ms,
+        "Uploaded output"
+    );
+
+    Ok(UploadResult {
+        file_id,
+        retries,
+        throttled,
+        elapsed_ms,
+    })
+}
 
-        // Create metadata
-        let metadata = serde_json::json!({
-            "name": file_name,
-            "mimeType": GOOGLE_DOCS_MIME_TYPE
-        });
+/// Send a multipart (metadata + content) upload with retry, passing `ocr_language`
+/// as Drive's OCR language hint. Returns the new file ID and the retries needed.
+async fn upload_multipart(
+    metadata: &serde_json::Value,
+    content: Vec<u8>,
+    mime_type: &str,
+    ocr_language: Option<&str>,
+    access_token: &str,
+) -> Result<(String, Retries), TahweelError> {
+    execute_with_retry_counted(|| async {
+        let client = http::client(OperationClass::Upload)?;
 
-        let metadata_part = multipart::Part::text(metadata.to_string())
-            .mime_str("application/json")
-            .map_err(|e| e.to_string())?;
+        let metadata_part =
+            multipart::Part::text(metadata.to_string()).mime_str("application/json")?;
 
-        let file_part = multipart::Part::bytes(file_content.clone())
-            .mime_str(mime_type)
-            .map_err(|e| e.to_string())?;
+        let file_part = multipart::Part::bytes(content.clone()).mime_str(mime_type)?;
 
         let form = multipart::Form::new()
             .part("metadata", metadata_part)
             .part("file", file_part);
 
+        let mut request = client.post(drive_upload_url()).bearer_auth(access_token);
+        if let Some(code) = ocr_language {
+            request = request.query(&[("ocrLanguage", code)]);
+        }
+        let response = request.multipart(form).send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(TahweelError::from_status(
+                status,
+                format!("Upload failed ({}): {}", status, text),
+            ));
+        }
+
+        let drive_file: DriveFile = response.json().await?;
+
+        Ok(drive_file.id)
+    })
+    .await
+}
+
+/// Key of a saved upload session: the same page content, type and OCR language
+/// (a page re-rendered after a restart produces identical bytes)
+fn upload_session_key(content: &[u8], mime_type: &str, ocr_language: Option<&str>) -> String {
+    format!(
+        "{:x}:{}:{}",
+        Sha256::digest(content),
+        mime_type,
+        ocr_language.unwrap_or("")
+    )
+}
+
+/// Offset after the bytes Drive confirmed in a `Range: bytes=0-N` header (none: 0)
+fn confirmed_offset(range: Option<&str>) -> u64 {
+    range
+        .and_then(|r| r.strip_prefix("bytes=0-"))
+        .and_then(|end| end.trim().parse::<u64>().ok())
+        .map(|end| end + 1)
+        .unwrap_or(0)
+}
+
+/// Where a resumable session stands after a request
+enum SessionStatus {
+    /// Drive has the first `n` bytes
+    Incomplete(u64),
+    Complete(String),
+    /// The session is gone (404/410) and the upload has to start over
+    Expired,
+}
+
+async fn session_status(response: reqwest::Response) -> Result<SessionStatus, TahweelError> {
+    let status = response.status();
+    match status.as_u16() {
+        200 | 201 => {
+            let drive_file: DriveFile = response.json().await?;
+            Ok(SessionStatus::Complete(drive_file.id))
+        }
+        308 => Ok(SessionStatus::Incomplete(confirmed_offset(
+            response
+                .headers()
+                .get(reqwest::header::RANGE)
+                .and_then(|v| v.to_str().ok()),
+        ))),
+        404 | 410 => Ok(SessionStatus::Expired),
+        _ => {
+            let text = response.text().await.unwrap_or_default();
+            Err(TahweelError::from_status(
+                status,
+                format!("Upload failed ({}): {}", status, text),
+            ))
+        }
+    }
+}
+
+/// Run `f` against the session store; failures are logged, never fatal to the upload
+async fn with_session_store<T: Send + 'static>(
+    store: &Path,
+    f: impl FnOnce(&mut rusqlite::Connection) -> Result<T, TahweelError> + Send + 'static,
+) -> Option<T> {
+    let written = match JobDb::shared(store, jobs::init_schema) {
+        Ok(db) => db.write_async(f).await,
+        Err(e) => Err(e),
+    };
+    match written {
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::warn!(error = %e, "Upload session store unavailable");
+            None
+        }
+    }
+}
+
+/// Drop the saved session of the upload `key`
+async fn forget_session(store: &Path, key: &str) {
+    let key = key.to_string();
+    with_session_store(store, move |conn| jobs::delete_upload_session(conn, &key)).await;
+}
+
+/// Open a resumable upload session, returning its URI
+async fn start_upload_session(
+    client: &reqwest::Client,
+    metadata: &serde_json::Value,
+    size: usize,
+    mime_type: &str,
+    ocr_language: Option<&str>,
+    access_token: &str,
+) -> Result<String, TahweelError> {
+    let mut request = client
+        .post(drive_resumable_upload_url())
+        .bearer_auth(access_token)
+        .header("X-Upload-Content-Type", mime_type)
+        .header("X-Upload-Content-Length", size);
+    if let Some(code) = ocr_language {
+        request = request.query(&[("ocrLanguage", code)]);
+    }
+    let response = request.json(metadata).send().await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(TahweelError::from_status(
+            status,
+            format!("Upload failed ({}): {}", status, text),
+        ));
+    }
+    response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .ok_or_else(|| TahweelError::Api("Upload session has no location".to_string()))
+}
+
+/// Upload `content` with Drive's resumable protocol in `RESUMABLE_CHUNK_BYTES` chunks.
+/// The session URI and confirmed offset are saved in the job database (`store`)
+/// after every chunk, so a retry, or a run after an app restart, continues where
+/// Drive stopped instead of sending the whole page again.
+async fn upload_resumable(
+    metadata: &serde_json::Value,
+    content: &[u8],
+    mime_type: &str,
+    ocr_language: Option<&str>,
+    access_token: &str,
+    store: &Path,
+) -> Result<(String, Retries), TahweelError> {
+    let key = upload_session_key(content, mime_type, ocr_language);
+    let size = content.len() as u64;
+
+    execute_with_retry_counted(|| async {
+        let client = http::client(OperationClass::Upload)?;
+        let saved_key = key.clone();
+        let saved = with_session_store(store, move |conn| {
+            jobs::load_upload_session(conn, &saved_key, UPLOAD_SESSION_MAX_AGE_SECS)
+        })
+        .await
+        .flatten()
+        .filter(|session| session.size == size);
+
+        // Ask Drive how much of a saved session it has before sending more
+        let mut session = None;
+        if let Some(saved) = saved {
+            let response = client
+                .put(&saved.session_uri)
+                .bearer_auth(access_token)
+                .header(reqwest::header::CONTENT_RANGE, format!("bytes */{}", size))
+                .body(Vec::new())
+                .send()
+                .await?;
+            match session_status(response).await? {
+                SessionStatus::Complete(file_id) => {
+                    forget_session(store, &key).await;
+                    return Ok(file_id);
+                }
+                SessionStatus::Incomplete(offset) => {
+                    tracing::info!(offset, size, "Resuming page upload");
+                    session = Some((saved.session_uri, offset));
+                }
+                SessionStatus::Expired => {
+                    forget_session(store, &key).await;
+                }
+            }
+        }
+        let (session_uri, mut offset) = match session {
+            Some(session) => session,
+            None => {
+                let uri = start_upload_session(
+                    &client,
+                    metadata,
+                    content.len(),
+                    mime_type,
+                    ocr_language,
+                    access_token,
+                )
+                .await?;
+                (uri, 0)
+            }
+        };
+
+        loop {
+            let end = (offset as usize + RESUMABLE_CHUNK_BYTES).min(content.len());
+            let response = client
+                .put(&session_uri)
+                .bearer_auth(access_token)
+                .header(
+                    reqwest::header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", offset, end - 1, size),
+                )
+                .body(content[offset as usize..end].to_vec())
+                .send()
+                .await?;
+            match session_status(response).await? {
+                SessionStatus::Complete(file_id) => {
+                    forget_session(store, &key).await;
+                    return Ok(file_id);
+                }
+                SessionStatus::Incomplete(confirmed) => {
+                    offset = confirmed;
+                    let saved_key = key.clone();
+                    let session = UploadSession {
+                        session_uri: session_uri.clone(),
+                        bytes_sent: offset,
+                        size,
+                    };
+                    with_session_store(store, move |conn| {
+                        jobs::save_upload_session(conn, &saved_key, &session)
+                    })
+                    .await;
+                }
+                SessionStatus::Expired => {
+                    forget_session(store, &key).await;
+                    return Err(TahweelError::Server(
+                        "Upload session expired; starting over".to_string(),
+                    ));
+                }
+            }
+        }
+    })
+    .await
+}
+
+/// Create an empty Google Doc (optionally inside a Drive folder) that pages are
+/// appended to as they complete. The document is kept as the canonical result.
+#[tauri::command]
+#[tracing::instrument(skip(access_token), err)]
+pub async fn create_google_doc(
+    title: String,
+    folder_id: Option<String>,
+    access_token: String,
+) -> Result<GoogleDocResult, TahweelError> {
+    let mut metadata = serde_json::json!({
+        "name": title,
+        "mimeType": GOOGLE_DOCS_MIME_TYPE
+    });
+    if let Some(folder_id) = folder_id.filter(|id| !id.is_empty()) {
+        metadata["parents"] = serde_json::json!([folder_id]);
+    }
+
+    let document_id = execute_with_retry(|| async {
+        let client = http::client(OperationClass::Api)?;
+
         let response = client
-            .post(drive_upload_url())
+            .post(format!("{}?fields=id", drive_files_url()))
             .bearer_auth(&access_token)
-            .multipart(form)
+            .json(&metadata)
             .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
-            return Err(format!("Upload failed ({}): {}", status, text));
+            return Err(TahweelError::from_status(
+                status,
+                format!("Document creation failed ({}): {}", status, text),
+            ));
         }
 
-        let drive_file: DriveFile = response.json().await.map_err(|e| e.to_string())?;
+        let drive_file: DriveFile = response.json().await?;
+        Ok(drive_file.id)
+    })
+    .await?;
 
-        Ok(UploadResult {
-            file_id: drive_file.id,
-        })
+    Ok(GoogleDocResult {
+        url: format!("https://docs.google.com/document/d/{}/edit", document_id),
+        document_id,
     })
-    .await
 }
 
-/// Export a Google Document as plain text
+/// Build the Docs API batchUpdate requests that append one page of text
+fn append_requests(text: &str, page_break_before: bool) -> serde_json::Value {
+    let end = serde_json::json!({ "segmentId": "" });
+    let mut requests = Vec::new();
+    if page_break_before {
+        requests.push(serde_json::json!({
+            "insertPageBreak": { "endOfSegmentLocation": end }
+        }));
+    }
+    requests.push(serde_json::json!({
+        "insertText": { "endOfSegmentLocation": end, "text": text.trim() }
+    }));
+    serde_json::json!({ "requests": requests })
+}
+
+/// Append a page of text to the end of a Google Doc via the Docs API batchUpdate
 #[tauri::command]
-pub async fn export_google_doc_as_text(
-    file_id: String,
+#[tracing::instrument(skip(text, access_token), err, fields(chars = text.chars().count()))]
+pub async fn append_to_google_doc(
+    document_id: String,
+    text: String,
+    page_break_before: bool,
     access_token: String,
-) -> Result<ExportResult, String> {
+) -> Result<(), TahweelError> {
+    let body = append_requests(&text, page_break_before);
+
     execute_with_retry(|| async {
-        let client = reqwest::Client::new();
+        let client = http::client(OperationClass::Api)?;
+
+        let url = format!("{}/{}:batchUpdate", docs_api_url(), document_id);
+
+        let response = client
+            .post(&url)
+            .bearer_auth(&access_token)
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(TahweelError::from_status(
+                status,
+                format!("Document update failed ({}): {}", status, text),
+            ));
+        }
+
+        Ok(())
+    })
+    .await
+}
+
+async fn conversion_status(
+    file_id: &str,
+    access_token: &str,
+) -> Result<ConversionStatus, TahweelError> {
+    let client = http::client(OperationClass::Api)?;
+    let url = format!(
+        "{}/{}?fields=mimeType,version,exportLinks",
+        drive_files_url(),
+        file_id
+    );
+    let response = client.get(&url).bearer_auth(access_token).send().await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(TahweelError::from_status(
+            status,
+            format!("Status check failed ({})", status),
+        ));
+    }
+    Ok(response.json().await?)
+}
+
+/// Poll `files.get` until Drive has finished converting `file_id` (see
+/// `conversion_ready`), with capped exponential backoff. Best effort: a failed
+/// poll or a conversion still changing after `CONVERSION_WAIT_MAX_MS` goes
+/// straight to the export, which reports real errors itself. Returns how many
+/// polls were made.
+async fn wait_for_conversion(file_id: &str, access_token: &str) -> u32 {
+    let started = Instant::now();
+    let mut delay = Duration::from_millis(CONVERSION_POLL_INITIAL_MS);
+    let mut previous = None;
+    let mut polls = 0;
+    loop {
+        polls += 1;
+        let status = match conversion_status(file_id, access_token).await {
+            Ok(status) => status,
+            Err(error) => {
+                tracing::debug!(%error, "Conversion status unavailable; exporting now");
+                return polls;
+            }
+        };
+        if conversion_ready(previous.as_ref(), &status) {
+            return polls;
+        }
+        if started.elapsed() + delay > Duration::from_millis(CONVERSION_WAIT_MAX_MS) {
+            tracing::warn!(file_id, "Conversion still in progress; exporting anyway");
+            return polls;
+        }
+        previous = Some(status);
+        sleep(delay).await;
+        delay = (delay * 2).min(Duration::from_millis(CONVERSION_POLL_MAX_MS));
+    }
+}
+
+async fn export_text(file_id: &str, access_token: &str) -> Result<(String, Retries), TahweelError> {
+    execute_with_retry_counted(|| async {
+        let client = http::client(OperationClass::Api)?;
 
         let url = format!(
             "{}/{}/export?mimeType=text/plain",
@@ -123,31 +734,87 @@ pub async fn export_google_doc_as_text(
             file_id
         );
 
-        let response = client
-            .get(&url)
-            .bearer_auth(&access_token)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+        let response = client.get(&url).bearer_auth(access_token).send().await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
-            return Err(format!("Export failed ({}): {}", status, text));
+            return Err(TahweelError::from_status(
+                status,
+                format!("Export failed ({}): {}", status, text),
+            ));
         }
 
-        let text = response.text().await.map_err(|e| e.to_string())?;
-
-        Ok(ExportResult { text })
+        Ok(response.text().await?)
     })
     .await
 }
 
+/// Export a Google Document as plain text. An empty export may be a conversion
+/// still being written, so only then is Drive polled until it has finished
+/// (`wait_for_conversion`) and the document exported again. With `job_id` and
+/// `page`, the export time is recorded for `get_performance_summary`.
+#[tauri::command]
+#[tracing::instrument(skip(access_token), err)]
+pub async fn export_google_doc_as_text(
+    file_id: String,
+    access_token: String,
+    job_id: Option<String>,
+    page: Option<u32>,
+) -> Result<ExportResult, TahweelError> {
+    let started = Instant::now();
+    let log_failure = |e: TahweelError| {
+        let message = format!("Export failed: {}", e.message());
+        jobs::log_event(job_id.as_deref(), JobEventKind::Error, page, message);
+        e
+    };
+    let (mut text, mut attempts) = export_text(&file_id, &access_token)
+        .await
+        .map_err(log_failure)?;
+    let mut calls = 1;
+    if text.trim().is_empty() {
+        let polls = wait_for_conversion(&file_id, &access_token).await;
+        let (again, more) = export_text(&file_id, &access_token)
+            .await
+            .map_err(log_failure)?;
+        text = again;
+        attempts.count += more.count;
+        attempts.throttled += more.throttled;
+        calls += u64::from(polls) + 1;
+    }
+    let Retries {
+        count: retries,
+        throttled,
+    } = attempts;
+    if retries > 0 {
+        let message = format!("Export needed {} retries", retries);
+        jobs::log_event(job_id.as_deref(), JobEventKind::Retry, page, message);
+    }
+
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    let calls = calls + u64::from(retries);
+    quota::consume(GOOGLE_DRIVE, 0, calls);
+    if let Some(job_id) = job_id.as_deref() {
+        perf::record(job_id, page.unwrap_or(1), Stage::Export, elapsed_ms);
+        job_usage::add_transfer(job_id, 0, text.len() as u64, calls);
+    }
+    Ok(ExportResult {
+        text,
+        retries,
+        throttled,
+        elapsed_ms,
+    })
+}
+
 /// Delete a file from Google Drive
 #[tauri::command]
-pub async fn delete_google_drive_file(file_id: String, access_token: String) -> Result<(), String> {
+#[tracing::instrument(skip(access_token), err)]
+pub async fn delete_google_drive_file(
+    file_id: String,
+    access_token: String,
+) -> Result<(), TahweelError> {
     execute_with_retry(|| async {
-        let client = reqwest::Client::new();
+        let client = http::client(OperationClass::Api)?;
 
         let url = format!("{}/{}", drive_files_url(), file_id);
 
@@ -155,14 +822,16 @@ pub async fn delete_google_drive_file(file_id: String, access_token: String) ->
             .delete(&url)
             .bearer_auth(&access_token)
             .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         // 204 No Content is success for delete
         if !response.status().is_success() && response.status() != reqwest::StatusCode::NO_CONTENT {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
-            return Err(format!("Delete failed ({}): {}", status, text));
+            return Err(TahweelError::from_status(
+                status,
+                format!("Delete failed ({}): {}", status, text),
+            ));
         }
 
         Ok(())
@@ -170,55 +839,163 @@ pub async fn delete_google_drive_file(file_id: String, access_token: String) ->
     .await
 }
 
-/// Execute a function with exponential backoff retry for transient errors.
-/// Retries up to 5 times with exponential backoff (1.5^n seconds + jitter).
-/// Retriable errors: 429 (rate limit), 5xx (server errors), timeouts.
-async fn execute_with_retry<F, Fut, T>(f: F) -> Result<T, String>
+/// Page size of `list_drive_files`
+const DRIVE_LIST_PAGE_SIZE: u32 = 50;
+/// Fields requested for each listed or imported Drive file
+const DRIVE_ENTRY_FIELDS: &str = "id,name,mimeType,size,modifiedTime";
+
+/// A file in the user's Drive that can be imported as an input
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DriveEntry {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    /// Bytes; Google Docs files have no size
+    #[serde(default, deserialize_with = "size_from_string")]
+    pub size: Option<u64>,
+    #[serde(rename = "modifiedTime", default)]
+    pub modified_time: Option<String>,
+}
+
+/// Drive sends sizes as decimal strings
+fn size_from_string<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
 where
-    F: Fn() -> Fut,
-    Fut: std::future::Future<Output = Result<T, String>>,
+    D: serde::Deserializer<'de>,
 {
-    let mut retries = 0u32;
-    let max_retries = 5;
-
-    loop {
-        match f().await {
-            Ok(result) => return Ok(result),
-            Err(e) => {
-                // Check if error is retriable (rate limit, timeout, server error)
-                let is_retriable = e.contains("429")
-                    || e.contains("500")
-                    || e.contains("502")
-                    || e.contains("503")
-                    || e.contains("504")
-                    || e.contains("timeout")
-                    || e.contains("Timeout");
-
-                if !is_retriable || retries >= max_retries {
-                    return Err(e);
-                }
+    let size: Option<String> = Option::deserialize(deserializer)?;
+    Ok(size.and_then(|size| size.parse().ok()))
+}
 
-                // Exponential backoff with jitter using UUID for better randomness
-                let delay_secs = (1.5_f64.powi(retries as i32)).min(15.0);
-                let jitter = random_jitter(); // 0.0 to 1.0
-                let delay = Duration::from_secs_f64(delay_secs + jitter);
+impl DriveEntry {
+    /// Google Docs, Slides, ... have no file contents and are exported as PDF
+    pub fn is_google_file(&self) -> bool {
+        self.mime_type.starts_with("application/vnd.google-apps.")
+    }
 
-                sleep(delay).await;
-                retries += 1;
-            }
+    /// URL the file's contents (or its PDF export) are downloaded from
+    pub fn download_url(&self) -> String {
+        if self.is_google_file() {
+            format!(
+                "{}/{}/export?mimeType=application/pdf",
+                drive_files_url(),
+                self.id
+            )
+        } else {
+            format!("{}/{}?alt=media", drive_files_url(), self.id)
         }
     }
 }
 
-/// Generate random jitter value between 0.0 and 1.0 using UUID v4.
-/// UUID v4 uses cryptographically secure random number generation,
-/// providing much better randomness than timestamp-based approaches.
-fn random_jitter() -> f64 {
-    // UUID v4 bytes are random; use the first 4 bytes as u32 for jitter
-    let uuid_bytes = uuid::Uuid::new_v4();
-    let bytes = uuid_bytes.as_bytes();
-    let random_u32 = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
-    (random_u32 as f64) / (u32::MAX as f64)
+/// One page of `list_drive_files`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DriveFileList {
+    pub files: Vec<DriveEntry>,
+    /// Pass back to get the next page; missing on the last one
+    #[serde(rename = "nextPageToken", default)]
+    pub next_page_token: Option<String>,
+}
+
+/// Drive search query for importable files (PDFs, images, Google Docs), whose
+/// names contain `name` when given
+pub fn drive_list_query(name: Option<&str>) -> String {
+    let mut query = "trashed = false and (mimeType = 'application/pdf' \
+        or mimeType = 'image/png' or mimeType = 'image/jpeg' \
+        or mimeType = 'application/vnd.google-apps.document')"
+        .to_string();
+    if let Some(name) = name.map(str::trim).filter(|name| !name.is_empty()) {
+        let escaped = name.replace('\\', "\\\\").replace('\'', "\\'");
+        query.push_str(&format!(" and name contains '{}'", escaped));
+    }
+    query
+}
+
+/// List the Drive files that can be imported, most recently modified first.
+/// With the app's `drive.file` scope, Drive only lists files the app created or
+/// the user opened with it.
+#[tauri::command]
+#[tracing::instrument(skip(access_token), err)]
+pub async fn list_drive_files(
+    access_token: String,
+    name: Option<String>,
+    page_token: Option<String>,
+) -> Result<DriveFileList, TahweelError> {
+    let query = drive_list_query(name.as_deref());
+    let fields = format!("nextPageToken,files({})", DRIVE_ENTRY_FIELDS);
+    let page_size = DRIVE_LIST_PAGE_SIZE.to_string();
+    execute_with_retry(|| async {
+        let client = http::client(OperationClass::Api)?;
+        let mut params = vec![
+            ("q", query.as_str()),
+            ("fields", fields.as_str()),
+            ("orderBy", "modifiedTime desc"),
+            ("pageSize", page_size.as_str()),
+        ];
+        if let Some(token) = page_token.as_deref() {
+            params.push(("pageToken", token));
+        }
+
+        let response = client
+            .get(drive_files_url())
+            .query(&params)
+            .bearer_auth(&access_token)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(TahweelError::from_status(
+                status,
+                format!("Listing Drive files failed ({}): {}", status, text),
+            ));
+        }
+        Ok(response.json::<DriveFileList>().await?)
+    })
+    .await
+}
+
+/// Name and type of a Drive file, to download it
+pub async fn get_drive_entry(
+    file_id: &str,
+    access_token: &str,
+) -> Result<DriveEntry, TahweelError> {
+    execute_with_retry(|| async {
+        let client = http::client(OperationClass::Api)?;
+        let response = client
+            .get(format!("{}/{}", drive_files_url(), file_id))
+            .query(&[("fields", DRIVE_ENTRY_FIELDS)])
+            .bearer_auth(access_token)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(TahweelError::from_status(
+                status,
+                format!("Getting Drive file failed ({}): {}", status, text),
+            ));
+        }
+        Ok(response.json::<DriveEntry>().await?)
+    })
+    .await
+}
+
+/// OCR a single image end to end (upload, export, delete) and return its text.
+/// Used by commands that OCR one-off images outside the page pipeline.
+pub(crate) async fn ocr_image_file(
+    file_path: &str,
+    access_token: &str,
+) -> Result<String, TahweelError> {
+    let upload =
+        upload_to_google_drive(file_path.to_string(), access_token.to_string(), None, None).await?;
+    let export =
+        export_google_doc_as_text(upload.file_id.clone(), access_token.to_string(), None, None)
+            .await;
+    // The converted doc is only a vehicle for OCR; a failed delete leaves it in Drive
+    if let Err(e) = delete_google_drive_file(upload.file_id, access_token.to_string()).await {
+        tracing::warn!(error = %e, "Failed to delete OCR document");
+    }
+    Ok(export?.text)
 }
 
 #[cfg(test)]
@@ -260,30 +1037,6 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_random_jitter_returns_value_in_range() {
-        for _ in 0..100 {
-            let value = random_jitter();
-            assert!(value >= 0.0, "Jitter value should be >= 0.0");
-            assert!(value <= 1.0, "Jitter value should be <= 1.0");
-        }
-    }
-
-    #[test]
-    fn test_random_jitter_has_variance() {
-        // Verify we get different values (not deterministic)
-        let values: Vec<f64> = (0..10).map(|_| random_jitter()).collect();
-        // Count unique values by checking consecutive differences
-        let unique_count = values
-            .windows(2)
-            .filter(|w| (w[0] - w[1]).abs() > f64::EPSILON)
-            .count();
-        assert!(
-            unique_count > 0,
-            "Random jitter should produce varying values"
-        );
-    }
-
     #[test]
     fn test_mime_type_detection_png() {
         let path = std::path::Path::new("/test/image.png");
@@ -457,30 +1210,18 @@ mod tests {
         assert!(!is_retriable);
     }
 
-    #[test]
-    fn test_exponential_backoff_calculation() {
-        // Test that backoff increases exponentially and caps at 15 seconds
-        let base: f64 = 1.5;
-
-        let delay_0 = base.powi(0).min(15.0);
-        let delay_1 = base.powi(1).min(15.0);
-        let delay_5 = base.powi(5).min(15.0);
-        let delay_10 = base.powi(10).min(15.0);
-
-        assert!((delay_0 - 1.0).abs() < 0.001);
-        assert!((delay_1 - 1.5).abs() < 0.001);
-        assert!((delay_5 - 7.59375).abs() < 0.001);
-        assert!((delay_10 - 15.0).abs() < 0.001); // Capped at 15
-    }
-
     #[test]
     fn test_upload_result_serialization() {
         let result = UploadResult {
             file_id: "abc123".to_string(),
+            retries: 0,
+            throttled: 0,
+            elapsed_ms: 0,
         };
 
         let json = serde_json::to_string(&result).unwrap();
         assert!(json.contains("fileId")); // Check camelCase rename
+        assert!(json.contains("elapsedMs"));
         assert!(json.contains("abc123"));
 
         // Verify it can be parsed back (as generic value since UploadResult doesn't derive Deserialize)
@@ -492,6 +1233,9 @@ mod tests {
     fn test_export_result_serialization() {
         let result = ExportResult {
             text: "Hello World\nLine 2".to_string(),
+            retries: 0,
+            throttled: 0,
+            elapsed_ms: 0,
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -506,6 +1250,9 @@ mod tests {
     fn test_export_result_with_unicode() {
         let result = ExportResult {
             text: "مرحبا بالعالم".to_string(),
+            retries: 0,
+            throttled: 0,
+            elapsed_ms: 0,
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -517,6 +1264,9 @@ mod tests {
     fn test_export_result_with_empty_text() {
         let result = ExportResult {
             text: String::new(),
+            retries: 0,
+            throttled: 0,
+            elapsed_ms: 0,
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -610,39 +1360,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_backoff_delay_all_retries() {
-        // Test all retry delays to ensure they follow the pattern
-        let base: f64 = 1.5;
-        let max_retries = 5u32;
-
-        for retry in 0..max_retries {
-            let delay = base.powi(retry as i32).min(15.0);
-            assert!(delay >= 1.0, "Delay should be at least 1 second");
-            assert!(delay <= 15.0, "Delay should be capped at 15 seconds");
-
-            // Verify exponential growth
-            if retry > 0 {
-                let prev_delay = base.powi((retry - 1) as i32).min(15.0);
-                assert!(delay >= prev_delay, "Delay should increase or stay capped");
-            }
-        }
-    }
-
-    #[test]
-    fn test_jitter_adds_variability_to_delay() {
-        // Test that delay + jitter produces values in expected range
-        let base_delay = 1.5_f64.powi(2); // ~2.25 seconds
-
-        for _ in 0..50 {
-            let jitter = random_jitter();
-            let total_delay = base_delay + jitter;
-
-            assert!(total_delay >= base_delay);
-            assert!(total_delay <= base_delay + 1.0);
-        }
-    }
-
     #[test]
     fn test_mime_type_detection_png_uppercase() {
         let path = std::path::Path::new("/test/IMAGE.PNG");
@@ -722,12 +1439,15 @@ mod tests {
         let result = upload_to_google_drive(
             "/nonexistent/path/to/file.png".to_string(),
             "fake_token".to_string(),
+            None,
+            None,
         )
         .await;
 
         assert!(result.is_err());
         let err = result.unwrap_err();
-        assert!(err.contains("File not found"));
+        assert!(err.to_string().contains("File not found"));
+        assert_eq!(err.kind(), "notFound");
     }
 
     #[tokio::test]
@@ -742,165 +1462,92 @@ mod tests {
 
         // This will fail at the HTTP request stage (invalid token),
         // but it proves the file reading logic works
-        let result = upload_to_google_drive(temp_path, "invalid_token".to_string()).await;
+        let result =
+            upload_to_google_drive(temp_path, "invalid_token".to_string(), None, None).await;
 
         // Should fail with HTTP error, not file error
         assert!(result.is_err());
         let err = result.unwrap_err();
-        assert!(!err.contains("File not found"));
+        assert!(!err.to_string().contains("File not found"));
     }
 
+    // Mock HTTP tests for Google Drive API - use EnvGuard to serialize access
     #[tokio::test]
-    async fn test_execute_with_retry_immediate_success() {
-        // Test that execute_with_retry returns immediately on success
-        use std::sync::atomic::{AtomicU32, Ordering};
-        use std::sync::Arc;
-
-        let call_count = Arc::new(AtomicU32::new(0));
-        let call_count_clone = call_count.clone();
-
-        let result = execute_with_retry(|| {
-            let count = call_count_clone.clone();
-            async move {
-                count.fetch_add(1, Ordering::SeqCst);
-                Ok::<_, String>("success".to_string())
-            }
-        })
-        .await;
+    async fn test_upload_to_google_drive_success() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
 
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "success");
-        assert_eq!(call_count.load(Ordering::SeqCst), 1);
-    }
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_UPLOAD_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        let mock_url = server.url();
 
-    #[tokio::test]
-    async fn test_execute_with_retry_non_retriable_error() {
-        // Test that non-retriable errors fail immediately
-        use std::sync::atomic::{AtomicU32, Ordering};
-        use std::sync::Arc;
-
-        let call_count = Arc::new(AtomicU32::new(0));
-        let call_count_clone = call_count.clone();
-
-        let result = execute_with_retry(|| {
-            let count = call_count_clone.clone();
-            async move {
-                count.fetch_add(1, Ordering::SeqCst);
-                Err::<String, _>("Bad Request (400): Invalid".to_string())
-            }
-        })
-        .await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_UPLOAD_URL", &mock_url);
 
-        assert!(result.is_err());
-        assert_eq!(call_count.load(Ordering::SeqCst), 1); // Only called once
-    }
+        // Create a temp file to upload
+        let mut temp_file = NamedTempFile::with_suffix(".png").unwrap();
+        temp_file.write_all(b"fake png content").unwrap();
+        let temp_path = temp_file.path().to_string_lossy().to_string();
 
-    #[tokio::test]
-    async fn test_execute_with_retry_retries_on_retriable_error() {
-        // Test that retriable errors are retried
-        use std::sync::atomic::{AtomicU32, Ordering};
-        use std::sync::Arc;
-
-        let call_count = Arc::new(AtomicU32::new(0));
-        let call_count_clone = call_count.clone();
-
-        let result = execute_with_retry(|| {
-            let count = call_count_clone.clone();
-            async move {
-                let current = count.fetch_add(1, Ordering::SeqCst);
-                if current < 2 {
-                    Err("Rate limit (429): Too many requests".to_string())
-                } else {
-                    Ok("success after retries".to_string())
-                }
-            }
-        })
-        .await;
-
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "success after retries");
-        assert_eq!(call_count.load(Ordering::SeqCst), 3); // Called 3 times
-    }
-
-    #[tokio::test]
-    async fn test_execute_with_retry_max_retries_exceeded() {
-        // Test that we give up after max retries
-        use std::sync::atomic::{AtomicU32, Ordering};
-        use std::sync::Arc;
-
-        let call_count = Arc::new(AtomicU32::new(0));
-        let call_count_clone = call_count.clone();
-
-        let result = execute_with_retry(|| {
-            let count = call_count_clone.clone();
-            async move {
-                count.fetch_add(1, Ordering::SeqCst);
-                Err::<String, _>("Server error (500): Always fails".to_string())
-            }
-        })
-        .await;
-
-        assert!(result.is_err());
-        // Initial call + 5 retries = 6 total calls
-        assert_eq!(call_count.load(Ordering::SeqCst), 6);
-    }
+        let mock = server
+            .mock("POST", "/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": "file123abc"}"#)
+            .create_async()
+            .await;
 
-    #[tokio::test]
-    async fn test_execute_with_retry_timeout_error() {
-        use std::sync::atomic::{AtomicU32, Ordering};
-        use std::sync::Arc;
-
-        let call_count = Arc::new(AtomicU32::new(0));
-        let call_count_clone = call_count.clone();
-
-        let result = execute_with_retry(|| {
-            let count = call_count_clone.clone();
-            async move {
-                let current = count.fetch_add(1, Ordering::SeqCst);
-                if current < 1 {
-                    Err("Connection timeout".to_string())
-                } else {
-                    Ok("recovered from timeout".to_string())
-                }
-            }
-        })
-        .await;
+        let result = upload_to_google_drive(temp_path, "valid_token".to_string(), None, None).await;
 
+        mock.assert_async().await;
         assert!(result.is_ok());
-        assert_eq!(call_count.load(Ordering::SeqCst), 2);
+        let upload_result = result.unwrap();
+        assert_eq!(upload_result.file_id, "file123abc");
+        assert_eq!(upload_result.retries, 0);
     }
 
-    // Mock HTTP tests for Google Drive API - use EnvGuard to serialize access
     #[tokio::test]
-    async fn test_upload_to_google_drive_success() {
+    async fn test_upload_to_google_drive_sends_ocr_language() {
         use std::io::Write;
         use tempfile::NamedTempFile;
 
         let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_UPLOAD_URL"]);
         let mut server = mockito::Server::new_async().await;
-        let mock_url = server.url();
-
-        std::env::set_var("TAHWEEL_TEST_DRIVE_UPLOAD_URL", &mock_url);
+        std::env::set_var("TAHWEEL_TEST_DRIVE_UPLOAD_URL", server.url());
 
-        // Create a temp file to upload
         let mut temp_file = NamedTempFile::with_suffix(".png").unwrap();
         temp_file.write_all(b"fake png content").unwrap();
         let temp_path = temp_file.path().to_string_lossy().to_string();
 
         let mock = server
             .mock("POST", "/")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "ocrLanguage".into(),
+                "fa".into(),
+            ))
             .with_status(200)
-            .with_header("content-type", "application/json")
             .with_body(r#"{"id": "file123abc"}"#)
             .create_async()
             .await;
 
-        let result = upload_to_google_drive(temp_path, "valid_token".to_string()).await;
-
+        let result = upload_to_google_drive(
+            temp_path.clone(),
+            "valid_token".to_string(),
+            None,
+            Some("fa".to_string()),
+        )
+        .await;
         mock.assert_async().await;
         assert!(result.is_ok());
-        let upload_result = result.unwrap();
-        assert_eq!(upload_result.file_id, "file123abc");
+
+        let err = upload_to_google_drive(
+            temp_path,
+            "valid_token".to_string(),
+            None,
+            Some("fa&x=1".to_string()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), "invalidInput");
     }
 
     #[tokio::test]
@@ -927,11 +1574,54 @@ mod tests {
             .create_async()
             .await;
 
-        let result = upload_to_google_drive(temp_path, "bad_token".to_string()).await;
+        let result = upload_to_google_drive(temp_path, "bad_token".to_string(), None, None).await;
 
         // We don't assert the mock count - we just verify the behavior
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Upload failed"));
+        assert!(result.unwrap_err().to_string().contains("Upload failed"));
+    }
+
+    #[tokio::test]
+    async fn test_ocr_image_file_uploads_exports_and_deletes() {
+        use tempfile::NamedTempFile;
+
+        let _env = EnvGuard::new(&[
+            "TAHWEEL_TEST_DRIVE_UPLOAD_URL",
+            "TAHWEEL_TEST_DRIVE_FILES_URL",
+        ]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_UPLOAD_URL", server.url());
+        std::env::set_var("TAHWEEL_TEST_DRIVE_FILES_URL", server.url());
+
+        let image = NamedTempFile::with_suffix(".png").unwrap();
+        fs::write(image.path(), b"fake png").unwrap();
+
+        let upload = server
+            .mock("POST", "/")
+            .with_status(200)
+            .with_body(r#"{"id": "capture1"}"#)
+            .create_async()
+            .await;
+        let export = server
+            .mock("GET", "/capture1/export?mimeType=text/plain")
+            .with_status(200)
+            .with_body("نص الصورة")
+            .create_async()
+            .await;
+        let delete = server
+            .mock("DELETE", "/capture1")
+            .with_status(204)
+            .create_async()
+            .await;
+
+        let text = ocr_image_file(image.path().to_str().unwrap(), "token")
+            .await
+            .unwrap();
+
+        upload.assert_async().await;
+        export.assert_async().await;
+        delete.assert_async().await;
+        assert_eq!(text, "نص الصورة");
     }
 
     #[tokio::test]
@@ -950,7 +1640,8 @@ mod tests {
             .create_async()
             .await;
 
-        let result = export_google_doc_as_text("file123".to_string(), "token".to_string()).await;
+        let result =
+            export_google_doc_as_text("file123".to_string(), "token".to_string(), None, None).await;
 
         mock.assert_async().await;
         assert!(result.is_ok());
@@ -959,6 +1650,207 @@ mod tests {
         assert!(export_result.text.contains("Second line"));
     }
 
+    fn converted(version: &str) -> ConversionStatus {
+        ConversionStatus {
+            mime_type: GOOGLE_DOCS_MIME_TYPE.to_string(),
+            version: Some(version.to_string()),
+            export_links: HashMap::from([("text/plain".to_string(), "https://x".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_conversion_ready_needs_a_settled_google_doc() {
+        let done = converted("7");
+        // Already finished on the first poll
+        assert!(conversion_ready(None, &done));
+        assert!(conversion_ready(Some(&done), &done));
+        // Still being written
+        assert!(!conversion_ready(Some(&converted("6")), &done));
+
+        let not_converted = ConversionStatus {
+            mime_type: "image/png".to_string(),
+            ..done.clone()
+        };
+        assert!(!conversion_ready(Some(&not_converted), &not_converted));
+        let no_text_export = ConversionStatus {
+            export_links: HashMap::new(),
+            ..done.clone()
+        };
+        assert!(!conversion_ready(Some(&no_text_export), &no_text_export));
+    }
+
+    #[test]
+    fn test_drive_list_query() {
+        let all = drive_list_query(None);
+        assert!(all.starts_with("trashed = false and (mimeType = 'application/pdf'"));
+        assert_eq!(drive_list_query(Some("  ")), all);
+        assert_eq!(
+            drive_list_query(Some("ابن القيم's")),
+            format!("{} and name contains 'ابن القيم\\'s'", all)
+        );
+    }
+
+    #[test]
+    fn test_drive_entry_download_url() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_FILES_URL"]);
+        std::env::set_var("TAHWEEL_TEST_DRIVE_FILES_URL", "http://drive");
+        let mut entry: DriveEntry = serde_json::from_value(serde_json::json!({
+            "id": "f1", "name": "book.pdf", "mimeType": "application/pdf", "size": "2048"
+        }))
+        .unwrap();
+        assert_eq!(entry.size, Some(2048));
+        assert_eq!(entry.download_url(), "http://drive/f1?alt=media");
+
+        entry.mime_type = GOOGLE_DOCS_MIME_TYPE.to_string();
+        assert!(entry.is_google_file());
+        assert_eq!(
+            entry.download_url(),
+            "http://drive/f1/export?mimeType=application/pdf"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_drive_files() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_FILES_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_FILES_URL", server.url());
+
+        let mock = server
+            .mock("GET", "/")
+            .match_header("authorization", "Bearer token")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), drive_list_query(Some("book"))),
+                mockito::Matcher::UrlEncoded("pageToken".into(), "p2".into()),
+                mockito::Matcher::UrlEncoded("orderBy".into(), "modifiedTime desc".into()),
+            ]))
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "files": [{
+                        "id": "f1",
+                        "name": "book.pdf",
+                        "mimeType": "application/pdf",
+                        "size": "10",
+                        "modifiedTime": "2026-01-01T00:00:00Z"
+                    }]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let list = list_drive_files(
+            "token".to_string(),
+            Some("book".to_string()),
+            Some("p2".to_string()),
+        )
+        .await
+        .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(list.next_page_token, None);
+        assert_eq!(list.files.len(), 1);
+        assert_eq!(list.files[0].name, "book.pdf");
+        assert_eq!(list.files[0].size, Some(10));
+    }
+
+    #[tokio::test]
+    async fn test_get_drive_entry_not_found() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_FILES_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_FILES_URL", server.url());
+
+        server
+            .mock("GET", "/missing")
+            .match_query(mockito::Matcher::Any)
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let err = get_drive_entry("missing", "token").await.unwrap_err();
+        assert_eq!(err.kind(), "api");
+    }
+
+    fn status_mock(server: &mut mockito::ServerGuard, file_id: &str) -> mockito::Mock {
+        server
+            .mock("GET", format!("/{}", file_id).as_str())
+            .match_query(mockito::Matcher::UrlEncoded(
+                "fields".into(),
+                "mimeType,version,exportLinks".into(),
+            ))
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "mimeType": GOOGLE_DOCS_MIME_TYPE,
+                    "version": "3",
+                    "exportLinks": {"text/plain": "https://docs.google.com/export"}
+                })
+                .to_string(),
+            )
+    }
+
+    #[tokio::test]
+    async fn test_export_with_text_skips_the_conversion_check() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_FILES_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_FILES_URL", server.url());
+
+        let status = status_mock(&mut server, "doc1")
+            .expect(0)
+            .create_async()
+            .await;
+        let export = server
+            .mock("GET", "/doc1/export?mimeType=text/plain")
+            .with_status(200)
+            .with_body("نص")
+            .create_async()
+            .await;
+
+        let result = export_google_doc_as_text("doc1".to_string(), "token".to_string(), None, None)
+            .await
+            .unwrap();
+
+        status.assert_async().await;
+        export.assert_async().await;
+        assert_eq!(result.text, "نص");
+    }
+
+    #[tokio::test]
+    async fn test_empty_export_waits_for_conversion() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_FILES_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_FILES_URL", server.url());
+
+        // Finished on the first poll, so the document is exported again at once
+        let status = status_mock(&mut server, "doc1")
+            .expect(1)
+            .create_async()
+            .await;
+        let empty = server
+            .mock("GET", "/doc1/export?mimeType=text/plain")
+            .with_status(200)
+            .with_body("\r\n")
+            .expect(1)
+            .create_async()
+            .await;
+        let export = server
+            .mock("GET", "/doc1/export?mimeType=text/plain")
+            .with_status(200)
+            .with_body("نص")
+            .create_async()
+            .await;
+
+        let result = export_google_doc_as_text("doc1".to_string(), "token".to_string(), None, None)
+            .await
+            .unwrap();
+
+        status.assert_async().await;
+        empty.assert_async().await;
+        export.assert_async().await;
+        assert_eq!(result.text, "نص");
+        assert_eq!(result.retries, 0);
+    }
+
     #[tokio::test]
     async fn test_export_google_doc_as_text_arabic() {
         let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_FILES_URL"]);
@@ -976,13 +1868,47 @@ mod tests {
             .create_async()
             .await;
 
-        let result = export_google_doc_as_text("arabic_doc".to_string(), "token".to_string()).await;
+        let result =
+            export_google_doc_as_text("arabic_doc".to_string(), "token".to_string(), None, None)
+                .await;
 
         mock.assert_async().await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap().text, arabic_text);
     }
 
+    #[tokio::test]
+    async fn test_export_google_doc_as_text_gzip() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_FILES_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_FILES_URL", server.url());
+
+        let book_text = "سطر من الكتاب\n".repeat(500);
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(book_text.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+        assert!(gzipped.len() * 4 < book_text.len());
+
+        let mock = server
+            .mock("GET", "/book/export?mimeType=text/plain")
+            .match_header("accept-encoding", "gzip")
+            .with_status(200)
+            .with_header("content-encoding", "gzip")
+            .with_body(gzipped)
+            .create_async()
+            .await;
+
+        let result = export_google_doc_as_text("book".to_string(), "token".to_string(), None, None)
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.text, book_text);
+    }
+
     #[tokio::test]
     async fn test_export_google_doc_as_text_failure() {
         let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_FILES_URL"]);
@@ -998,11 +1924,13 @@ mod tests {
             .create_async()
             .await;
 
-        let result = export_google_doc_as_text("notfound".to_string(), "token".to_string()).await;
+        let result =
+            export_google_doc_as_text("notfound".to_string(), "token".to_string(), None, None)
+                .await;
 
         mock.assert_async().await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Export failed"));
+        assert!(result.unwrap_err().to_string().contains("Export failed"));
     }
 
     #[tokio::test]
@@ -1067,7 +1995,7 @@ mod tests {
 
         mock.assert_async().await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Delete failed"));
+        assert!(result.unwrap_err().to_string().contains("Delete failed"));
     }
 
     #[test]
@@ -1103,4 +2031,480 @@ mod tests {
         let url = drive_files_url();
         assert_eq!(url, "http://mock/files");
     }
+
+    #[test]
+    fn test_output_mime_types() {
+        assert_eq!(output_mime_type(Path::new("book.txt")), "text/plain");
+        assert_eq!(
+            output_mime_type(Path::new("book.DOCX")),
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        );
+        assert_eq!(output_mime_type(Path::new("book.json")), "application/json");
+        assert_eq!(output_mime_type(Path::new("book.zip")), "application/zip");
+        assert_eq!(
+            output_mime_type(Path::new("book")),
+            "application/octet-stream"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_output_to_google_drive_keeps_name_and_folder() {
+        use tempfile::tempdir;
+
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_UPLOAD_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_UPLOAD_URL", server.url());
+
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("كتاب.txt");
+        fs::write(&path, "نص").unwrap();
+
+        let mock = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::Regex(r#""name":"كتاب.txt""#.to_string()),
+                mockito::Matcher::Regex(r#""parents":\["folder42"\]"#.to_string()),
+                mockito::Matcher::Regex(r#""mimeType":"text/plain""#.to_string()),
+            ]))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": "output123"}"#)
+            .create_async()
+            .await;
+
+        let result = upload_output_to_google_drive(
+            path.to_string_lossy().to_string(),
+            Some("folder42".to_string()),
+            "valid_token".to_string(),
+        )
+        .await
+        .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.file_id, "output123");
+    }
+
+    #[tokio::test]
+    async fn test_upload_output_to_google_drive_long_exotic_path() {
+        use tempfile::tempdir;
+
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_UPLOAD_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_UPLOAD_URL", server.url());
+
+        let temp = tempdir().unwrap();
+        let mut dir = temp.path().to_path_buf();
+        while dir.as_os_str().len() < 300 {
+            dir.push("مجلد طويل 📚");
+        }
+        fs::create_dir_all(long_path::extended(&dir)).unwrap();
+        let path = dir.join("كتاب 😀.txt");
+        fs::write(long_path::extended(&path), "نص").unwrap();
+
+        let mock = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(
+                r#""name":"كتاب 😀.txt""#.to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": "long123"}"#)
+            .create_async()
+            .await;
+
+        let result = upload_output_to_google_drive(
+            path.to_str().unwrap().to_string(),
+            None,
+            "valid_token".to_string(),
+        )
+        .await
+        .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.file_id, "long123");
+    }
+
+    #[tokio::test]
+    async fn test_upload_output_to_google_drive_without_folder() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_UPLOAD_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_UPLOAD_URL", server.url());
+
+        let mut temp_file = NamedTempFile::with_suffix(".docx").unwrap();
+        temp_file.write_all(b"fake docx").unwrap();
+
+        let mock = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex("parents".to_string()))
+            .expect(0)
+            .create_async()
+            .await;
+        let _ok = server
+            .mock("POST", "/")
+            .with_status(200)
+            .with_body(r#"{"id": "output456"}"#)
+            .create_async()
+            .await;
+
+        let result = upload_output_to_google_drive(
+            temp_file.path().to_string_lossy().to_string(),
+            None,
+            "valid_token".to_string(),
+        )
+        .await
+        .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.file_id, "output456");
+    }
+
+    #[tokio::test]
+    async fn test_upload_output_to_google_drive_file_not_found() {
+        let result = upload_output_to_google_drive(
+            "/nonexistent/book.txt".to_string(),
+            None,
+            "token".to_string(),
+        )
+        .await;
+        assert!(result.unwrap_err().to_string().contains("File not found"));
+    }
+
+    #[test]
+    fn test_docs_api_url_default() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DOCS_URL"]);
+        assert_eq!(docs_api_url(), "https://docs.googleapis.com/v1/documents");
+    }
+
+    #[test]
+    fn test_append_requests_with_page_break() {
+        let body = append_requests("  نص الصفحة \n", true);
+        let requests = body["requests"].as_array().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0]["insertPageBreak"].is_object());
+        assert_eq!(requests[1]["insertText"]["text"], "نص الصفحة");
+    }
+
+    #[test]
+    fn test_append_requests_first_page() {
+        let body = append_requests("text", false);
+        let requests = body["requests"].as_array().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0]["insertText"]["endOfSegmentLocation"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_create_google_doc_success() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_FILES_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_FILES_URL", server.url());
+
+        let mock = server
+            .mock("POST", "/")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "fields".to_string(),
+                "id".to_string(),
+            ))
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"name": "كتاب", "mimeType": "application/vnd.google-apps.document", "parents": ["folder1"]}"#
+                    .to_string(),
+            ))
+            .with_status(200)
+            .with_body(r#"{"id": "doc123"}"#)
+            .create_async()
+            .await;
+
+        let result = create_google_doc(
+            "كتاب".to_string(),
+            Some("folder1".to_string()),
+            "valid_token".to_string(),
+        )
+        .await
+        .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.document_id, "doc123");
+        assert_eq!(result.url, "https://docs.google.com/document/d/doc123/edit");
+    }
+
+    #[tokio::test]
+    async fn test_create_google_doc_failure() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_FILES_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_FILES_URL", server.url());
+
+        let _mock = server
+            .mock("POST", "/")
+            .match_query(mockito::Matcher::Any)
+            .with_status(403)
+            .create_async()
+            .await;
+
+        let result = create_google_doc("book".to_string(), None, "bad".to_string()).await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Document creation failed"));
+    }
+
+    #[tokio::test]
+    async fn test_append_to_google_doc_success() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DOCS_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DOCS_URL", server.url());
+
+        let mock = server
+            .mock("POST", "/doc123:batchUpdate")
+            .match_body(mockito::Matcher::Regex("insertPageBreak".to_string()))
+            .with_status(200)
+            .with_body("{}")
+            .create_async()
+            .await;
+
+        let result = append_to_google_doc(
+            "doc123".to_string(),
+            "page two".to_string(),
+            true,
+            "valid_token".to_string(),
+        )
+        .await;
+
+        mock.assert_async().await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_append_to_google_doc_failure() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DOCS_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DOCS_URL", server.url());
+
+        let _mock = server
+            .mock("POST", "/doc123:batchUpdate")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let result = append_to_google_doc(
+            "doc123".to_string(),
+            "text".to_string(),
+            false,
+            "token".to_string(),
+        )
+        .await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Document update failed"));
+    }
+
+    const RESUMABLE_TEST_SIZE: usize = 2 * RESUMABLE_CHUNK_BYTES + RESUMABLE_CHUNK_BYTES / 2;
+
+    fn resumable_content() -> Vec<u8> {
+        (0..RESUMABLE_TEST_SIZE).map(|i| (i % 251) as u8).collect()
+    }
+
+    async fn mock_chunk(
+        server: &mut mockito::ServerGuard,
+        range: &str,
+        status: usize,
+        confirmed: Option<&str>,
+    ) -> mockito::Mock {
+        let mut mock = server
+            .mock("PUT", "/session")
+            .match_header("content-range", range)
+            .with_status(status);
+        if let Some(confirmed) = confirmed {
+            mock = mock.with_header("range", confirmed);
+        }
+        if status == 200 {
+            mock = mock.with_body(r#"{"id": "bigfile"}"#);
+        }
+        mock.create_async().await
+    }
+
+    #[test]
+    fn test_confirmed_offset() {
+        assert_eq!(confirmed_offset(Some("bytes=0-1048575")), 1048576);
+        assert_eq!(confirmed_offset(Some("bytes=0-0")), 1);
+        assert_eq!(confirmed_offset(None), 0);
+        assert_eq!(confirmed_offset(Some("garbage")), 0);
+    }
+
+    #[tokio::test]
+    async fn test_resumable_upload_sends_chunks_and_clears_session() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_RESUMABLE_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_RESUMABLE_URL", server.url());
+        let temp = tempfile::tempdir().unwrap();
+        let store = temp.path().join("jobs.db");
+        let content = resumable_content();
+
+        let start = server
+            .mock("POST", "/")
+            .match_header("x-upload-content-length", "2621440")
+            .with_status(200)
+            .with_header("location", &format!("{}/session", server.url()))
+            .create_async()
+            .await;
+        let first = mock_chunk(
+            &mut server,
+            "bytes 0-1048575/2621440",
+            308,
+            Some("bytes=0-1048575"),
+        )
+        .await;
+        let second = mock_chunk(
+            &mut server,
+            "bytes 1048576-2097151/2621440",
+            308,
+            Some("bytes=0-2097151"),
+        )
+        .await;
+        let last = mock_chunk(&mut server, "bytes 2097152-2621439/2621440", 200, None).await;
+
+        let metadata = serde_json::json!({"name": "page.png"});
+        let (file_id, retries) =
+            upload_resumable(&metadata, &content, "image/png", None, "token", &store)
+                .await
+                .unwrap();
+
+        start.assert_async().await;
+        first.assert_async().await;
+        second.assert_async().await;
+        last.assert_async().await;
+        assert_eq!(file_id, "bigfile");
+        assert_eq!(retries.count, 0);
+        let key = upload_session_key(&content, "image/png", None);
+        let conn = jobs::open_db(&store).unwrap();
+        assert!(
+            jobs::load_upload_session(&conn, &key, UPLOAD_SESSION_MAX_AGE_SECS)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_resumable_upload_continues_from_saved_offset() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_RESUMABLE_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_RESUMABLE_URL", server.url());
+        let temp = tempfile::tempdir().unwrap();
+        let store = temp.path().join("jobs.db");
+        let content = resumable_content();
+        let metadata = serde_json::json!({"name": "page.png"});
+
+        // First run: Drive takes one chunk, then rejects the next
+        let start = server
+            .mock("POST", "/")
+            .with_status(200)
+            .with_header("location", &format!("{}/session", server.url()))
+            .expect(1)
+            .create_async()
+            .await;
+        let first = mock_chunk(
+            &mut server,
+            "bytes 0-1048575/2621440",
+            308,
+            Some("bytes=0-1048575"),
+        )
+        .await;
+        let rejected = mock_chunk(&mut server, "bytes 1048576-2097151/2621440", 403, None).await;
+        let err = upload_resumable(&metadata, &content, "image/png", None, "token", &store)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), "api");
+        first.assert_async().await;
+        rejected.assert_async().await;
+        rejected.remove_async().await;
+
+        let key = upload_session_key(&content, "image/png", None);
+        let saved = jobs::load_upload_session(
+            &jobs::open_db(&store).unwrap(),
+            &key,
+            UPLOAD_SESSION_MAX_AGE_SECS,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(saved.bytes_sent, 1048576);
+        assert_eq!(saved.size, 2621440);
+
+        // Second run (as after a restart): ask Drive where it stopped, send the rest
+        let status = mock_chunk(&mut server, "bytes */2621440", 308, Some("bytes=0-1048575")).await;
+        let second = mock_chunk(
+            &mut server,
+            "bytes 1048576-2097151/2621440",
+            308,
+            Some("bytes=0-2097151"),
+        )
+        .await;
+        let last = mock_chunk(&mut server, "bytes 2097152-2621439/2621440", 200, None).await;
+        let (file_id, _) =
+            upload_resumable(&metadata, &content, "image/png", None, "token", &store)
+                .await
+                .unwrap();
+
+        assert_eq!(file_id, "bigfile");
+        start.assert_async().await;
+        status.assert_async().await;
+        second.assert_async().await;
+        last.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_expired_resumable_session_starts_over() {
+        let _env = EnvGuard::new(&["TAHWEEL_TEST_DRIVE_RESUMABLE_URL"]);
+        let mut server = mockito::Server::new_async().await;
+        std::env::set_var("TAHWEEL_TEST_DRIVE_RESUMABLE_URL", server.url());
+        let temp = tempfile::tempdir().unwrap();
+        let store = temp.path().join("jobs.db");
+        let content = b"small page".to_vec();
+        let key = upload_session_key(&content, "image/png", Some("ar"));
+        jobs::save_upload_session(
+            &jobs::open_db(&store).unwrap(),
+            &key,
+            &UploadSession {
+                session_uri: format!("{}/gone", server.url()),
+                bytes_sent: 4,
+                size: 10,
+            },
+        )
+        .unwrap();
+
+        let gone = server
+            .mock("PUT", "/gone")
+            .with_status(404)
+            .create_async()
+            .await;
+        let start = server
+            .mock("POST", "/")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "ocrLanguage".into(),
+                "ar".into(),
+            ))
+            .with_status(200)
+            .with_header("location", &format!("{}/session", server.url()))
+            .create_async()
+            .await;
+        let upload = mock_chunk(&mut server, "bytes 0-9/10", 200, None).await;
+
+        let metadata = serde_json::json!({"name": "page.png"});
+        let (file_id, _) = upload_resumable(
+            &metadata,
+            &content,
+            "image/png",
+            Some("ar"),
+            "token",
+            &store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(file_id, "bigfile");
+        gone.assert_async().await;
+        start.assert_async().await;
+        upload.assert_async().await;
+    }
 }
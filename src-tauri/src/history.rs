@@ -0,0 +1,307 @@
+use crate::error::TahweelError;
+use crate::job_db::db_error;
+use crate::jobs;
+use crate::path_policy::{self, PathAccess};
+use crate::util::civil_date;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Current version of the JSON history export
+pub const HISTORY_EXPORT_VERSION: u32 = 1;
+
+/// File format of a history export
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryFormat {
+    /// One row per month, then a `total` row
+    Csv,
+    /// Months, totals and the jobs they count
+    Json,
+}
+
+/// Jobs completed from `fromMs` (inclusive) to `toMs` (exclusive), in
+/// milliseconds since the Unix epoch; an open end is unbounded
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct DateRange {
+    #[serde(rename = "fromMs", default)]
+    pub from_ms: Option<i64>,
+    #[serde(rename = "toMs", default)]
+    pub to_ms: Option<i64>,
+}
+
+/// Documents and pages completed in one month (UTC)
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MonthSummary {
+    /// `YYYY-MM`
+    pub month: String,
+    pub documents: u32,
+    pub pages: u64,
+}
+
+/// A completed job counted in the export
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct HistoryJob {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    #[serde(rename = "sourcePath")]
+    pub source_path: String,
+    /// `YYYY-MM-DD` (UTC) the job was first recorded
+    #[serde(rename = "completedOn")]
+    pub completed_on: String,
+    pub pages: u64,
+    pub tags: Vec<String>,
+}
+
+/// Summary of the job history over a date range
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct HistoryReport {
+    pub version: u32,
+    #[serde(rename = "dateRange")]
+    pub date_range: DateRange,
+    pub documents: u32,
+    pub pages: u64,
+    /// Oldest first; months without jobs are left out
+    pub months: Vec<MonthSummary>,
+    pub jobs: Vec<HistoryJob>,
+}
+
+/// Result of `export_history`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ExportedHistory {
+    pub path: String,
+    pub documents: u32,
+    pub pages: u64,
+}
+
+/// Summarize the jobs completed in `range`, by the month they were first recorded
+pub fn summarize(conn: &Connection, range: DateRange) -> Result<HistoryReport, TahweelError> {
+    let mut statement = conn
+        .prepare(
+            "SELECT id, source_path, created_at,
+                 (SELECT COUNT(*) FROM job_pages WHERE job_pages.job_id = jobs.id)
+             FROM jobs
+             WHERE created_at * 1000 >= ?1 AND created_at * 1000 < ?2
+             ORDER BY created_at, id",
+        )
+        .map_err(db_error)?;
+    let rows = statement
+        .query_map(
+            params![
+                range.from_ms.unwrap_or(i64::MIN),
+                range.to_ms.unwrap_or(i64::MAX)
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            },
+        )
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(db_error)?;
+
+    let mut months: Vec<MonthSummary> = Vec::new();
+    let mut jobs = Vec::with_capacity(rows.len());
+    for (job_id, source_path, created_at, pages) in rows {
+        let (year, month, day) = civil_date(created_at.max(0) as u64);
+        let pages = pages.max(0) as u64;
+        let key = format!("{:04}-{:02}", year, month);
+        // Rows come in date order, so a month's jobs are consecutive
+        match months.last_mut() {
+            Some(summary) if summary.month == key => {
+                summary.documents += 1;
+                summary.pages += pages;
+            }
+            _ => months.push(MonthSummary {
+                month: key,
+                documents: 1,
+                pages,
+            }),
+        }
+        jobs.push(HistoryJob {
+            tags: jobs::load_labels(conn, &job_id)?.tags,
+            job_id,
+            source_path,
+            completed_on: format!("{:04}-{:02}-{:02}", year, month, day),
+            pages,
+        });
+    }
+
+    Ok(HistoryReport {
+        version: HISTORY_EXPORT_VERSION,
+        date_range: range,
+        documents: months.iter().map(|m| m.documents).sum(),
+        pages: months.iter().map(|m| m.pages).sum(),
+        months,
+        jobs,
+    })
+}
+
+/// Monthly rows of the report as CSV, ending with a `total` row
+pub fn to_csv(report: &HistoryReport) -> String {
+    let mut csv = String::from("month,documents,pages\n");
+    for month in &report.months {
+        csv.push_str(&format!(
+            "{},{},{}\n",
+            month.month, month.documents, month.pages
+        ));
+    }
+    csv.push_str(&format!("total,{},{}\n", report.documents, report.pages));
+    csv
+}
+
+/// Write `report` to `path` in `format`
+pub fn write_report(
+    report: &HistoryReport,
+    format: HistoryFormat,
+    path: &Path,
+) -> Result<(), TahweelError> {
+    let data = match format {
+        HistoryFormat::Csv => to_csv(report),
+        HistoryFormat::Json => serde_json::to_string_pretty(report)?,
+    };
+    fs::write(path, data)?;
+    Ok(())
+}
+
+/// Export how many documents and pages were processed per month, from the job
+/// history, as CSV or JSON at `output_path`
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn export_history(
+    format: HistoryFormat,
+    date_range: Option<DateRange>,
+    output_path: String,
+) -> Result<ExportedHistory, TahweelError> {
+    let path = path_policy::check(Path::new(&output_path), PathAccess::Write)?;
+    let report = jobs::db()?
+        .read_async(move |conn| summarize(conn, date_range.unwrap_or_default()))
+        .await?;
+    write_report(&report, format, &path)?;
+    Ok(ExportedHistory {
+        path: path.to_string_lossy().to_string(),
+        documents: report.documents,
+        pages: report.pages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::{DocxOptions, JobRecord};
+    use crate::output::OutputFormat;
+    use tempfile::tempdir;
+
+    /// 2026-01-15 and 2026-02-03, 00:00 UTC
+    const JAN_15: i64 = 1_768_435_200;
+    const FEB_3: i64 = 1_770_076_800;
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        jobs::init_schema(&conn).unwrap();
+        conn
+    }
+
+    fn add_job(conn: &mut Connection, id: &str, pages: usize, created_at: i64) {
+        jobs::record(
+            conn,
+            &JobRecord {
+                id: id.to_string(),
+                source_path: format!("/books/{}.pdf", id),
+                output_path: format!("/out/{}", id),
+                formats: vec![OutputFormat::Txt],
+                page_separator: "\n".to_string(),
+                pages: vec!["text".to_string(); pages],
+                docx: DocxOptions::default(),
+            },
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE jobs SET created_at = ?1 WHERE id = ?2",
+            params![created_at, id],
+        )
+        .unwrap();
+    }
+
+    fn history() -> Connection {
+        let mut conn = db();
+        add_job(&mut conn, "a", 10, JAN_15);
+        add_job(&mut conn, "b", 5, JAN_15 + 3600);
+        add_job(&mut conn, "c", 7, FEB_3);
+        jobs::set_tags(&mut conn, "c", &["Waqf".to_string()]).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_summarize_groups_by_month() {
+        let report = summarize(&history(), DateRange::default()).unwrap();
+        assert_eq!(report.documents, 3);
+        assert_eq!(report.pages, 22);
+        assert_eq!(
+            report.months,
+            vec![
+                MonthSummary {
+                    month: "2026-01".to_string(),
+                    documents: 2,
+                    pages: 15,
+                },
+                MonthSummary {
+                    month: "2026-02".to_string(),
+                    documents: 1,
+                    pages: 7,
+                },
+            ]
+        );
+        assert_eq!(report.jobs[2].completed_on, "2026-02-03");
+        assert_eq!(report.jobs[2].tags, vec!["waqf".to_string()]);
+    }
+
+    #[test]
+    fn test_summarize_filters_by_date_range() {
+        let conn = history();
+        let february = DateRange {
+            from_ms: Some(FEB_3 * 1000),
+            to_ms: None,
+        };
+        let report = summarize(&conn, february).unwrap();
+        assert_eq!(report.documents, 1);
+        assert_eq!(report.jobs[0].job_id, "c");
+
+        let before_february = DateRange {
+            from_ms: None,
+            to_ms: Some(FEB_3 * 1000),
+        };
+        assert_eq!(summarize(&conn, before_february).unwrap().documents, 2);
+    }
+
+    #[test]
+    fn test_csv_has_monthly_and_total_rows() {
+        let report = summarize(&history(), DateRange::default()).unwrap();
+        assert_eq!(
+            to_csv(&report),
+            "month,documents,pages\n2026-01,2,15\n2026-02,1,7\ntotal,3,22\n"
+        );
+        let empty = summarize(&db(), DateRange::default()).unwrap();
+        assert_eq!(to_csv(&empty), "month,documents,pages\ntotal,0,0\n");
+    }
+
+    #[test]
+    fn test_write_report_json() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        let report = summarize(&history(), DateRange::default()).unwrap();
+        write_report(&report, HistoryFormat::Json, &path).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["version"], HISTORY_EXPORT_VERSION);
+        assert_eq!(json["pages"], 22);
+        assert_eq!(json["months"][0]["month"], "2026-01");
+        assert_eq!(json["jobs"][0]["sourcePath"], "/books/a.pdf");
+        assert!(json["dateRange"]["fromMs"].is_null());
+    }
+}
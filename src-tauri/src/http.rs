@@ -0,0 +1,648 @@
+use crate::error::TahweelError;
+use crate::settings::{self, NetworkSimulationSettings, TimeoutSettings};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// Kind of Google API request, which decides the timeouts it gets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationClass {
+    /// Small requests: token exchange, export, delete, Docs updates
+    Api,
+    /// Large transfers: multipart uploads of page images and outputs, and
+    /// downloads of remote inputs
+    Upload,
+}
+
+/// Connect and read timeouts for `class`
+pub fn timeouts_for(class: OperationClass, timeouts: &TimeoutSettings) -> (Duration, Duration) {
+    let (connect, read) = match class {
+        OperationClass::Api => (timeouts.api_connect_secs, timeouts.api_read_secs),
+        OperationClass::Upload => (timeouts.upload_connect_secs, timeouts.upload_read_secs),
+    };
+    (Duration::from_secs(connect), Duration::from_secs(read))
+}
+
+fn build(connect: Duration, read: Duration) -> Result<reqwest::Client, TahweelError> {
+    Ok(reqwest::Client::builder()
+        .connect_timeout(connect)
+        .read_timeout(read)
+        .gzip(true)
+        .build()?)
+}
+
+/// HTTP client for `class` with the timeouts from the `timeouts` settings.
+/// A request that stalls longer than the read timeout fails as `timeout`,
+/// which the Drive retry loop treats as retriable. Requests advertise gzip
+/// (Drive exports compress about 4x) and responses are decompressed as read.
+pub fn client(class: OperationClass) -> Result<reqwest::Client, TahweelError> {
+    let (connect, read) = timeouts_for(class, &settings::current().timeouts);
+    build(connect, read)
+}
+
+/// Base of the exponential backoff between retries, in seconds
+const BACKOFF_BASE: f64 = 1.5;
+
+/// How transient failures of a request are retried
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Cap on the backoff, before jitter
+    pub max_delay_secs: f64,
+    /// Errors worth retrying
+    pub retriable: fn(&TahweelError) -> bool,
+    /// Latency and failures injected into every attempt (developer mode)
+    pub simulation: NetworkSimulationSettings,
+}
+
+impl RetryPolicy {
+    /// The `retry` settings, retrying errors flagged `is_retriable`
+    /// (rate limits, 5xx, timeouts, network failures)
+    pub fn from_settings() -> Self {
+        let settings = settings::current();
+        Self {
+            max_retries: settings.retry.max_retries,
+            max_delay_secs: settings.retry.max_delay_secs,
+            retriable: TahweelError::is_retriable,
+            simulation: settings.network_simulation,
+        }
+    }
+
+    /// Backoff before retry `retry` (0-based): 1.5^n seconds, capped at `max_delay_secs`
+    pub fn delay_secs(&self, retry: u32) -> f64 {
+        BACKOFF_BASE.powi(retry as i32).min(self.max_delay_secs)
+    }
+}
+
+/// Retries a request needed before it succeeded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Retries {
+    pub count: u32,
+    /// Of the failed attempts, those the server throttled (`RateLimited`), so
+    /// callers can back off even when a retry then went through
+    pub throttled: u32,
+}
+
+/// Run `f`, retrying failures `policy` deems retriable up to `policy.max_retries`
+/// times with exponential backoff plus up to a second of jitter. Returns the
+/// result and the retries that were needed.
+pub async fn with_retry<F, Fut, T>(policy: &RetryPolicy, f: F) -> Result<(T, Retries), TahweelError>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<T, TahweelError>>,
+{
+    let mut retries = Retries::default();
+    loop {
+        let attempt = match simulate(&policy.simulation).await {
+            Some(e) => Err(e),
+            None => f().await,
+        };
+        match attempt {
+            Ok(result) => return Ok((result, retries)),
+            Err(e) => {
+                if !(policy.retriable)(&e) || retries.count >= policy.max_retries {
+                    return Err(e);
+                }
+
+                let delay =
+                    Duration::from_secs_f64(policy.delay_secs(retries.count) + random_jitter());
+                tracing::warn!(
+                    retry = retries.count + 1,
+                    delay_ms = delay.as_millis() as u64,
+                    error = %e,
+                    "Retrying request"
+                );
+
+                sleep(delay).await;
+                retries.count += 1;
+                if matches!(e, TahweelError::RateLimited(_)) {
+                    retries.throttled += 1;
+                }
+            }
+        }
+    }
+}
+
+/// The transient error an attempt fails with under `simulation`, given two
+/// random rolls in [0, 1): one decides whether it fails, the other how
+pub fn simulated_failure(
+    simulation: &NetworkSimulationSettings,
+    fail_roll: f64,
+    kind_roll: f64,
+) -> Option<TahweelError> {
+    if !simulation.enabled || fail_roll >= simulation.failure_rate {
+        return None;
+    }
+    let message = "Simulated failure (network simulation)".to_string();
+    Some(match (kind_roll * 4.0) as u32 {
+        0 => TahweelError::Network(message),
+        1 => TahweelError::Timeout(message),
+        2 => TahweelError::RateLimited(message),
+        _ => TahweelError::Server(message),
+    })
+}
+
+/// Apply the developer network simulation to one attempt: wait out the injected
+/// latency, then maybe fail it
+async fn simulate(simulation: &NetworkSimulationSettings) -> Option<TahweelError> {
+    if !simulation.enabled {
+        return None;
+    }
+    let delay_ms = simulation.latency_ms as f64 + simulation.jitter_ms as f64 * random_jitter();
+    if delay_ms > 0.0 {
+        sleep(Duration::from_secs_f64(delay_ms / 1000.0)).await;
+    }
+    let failure = simulated_failure(simulation, random_jitter(), random_jitter());
+    if let Some(e) = &failure {
+        tracing::debug!(error = %e, "Simulated request failure");
+    }
+    failure
+}
+
+/// Execute a Google API request with `with_retry` under the `retry` settings:
+/// up to `retry.maxRetries` retries (default 5), 1.5^n seconds apart (capped at
+/// `retry.maxDelaySecs`, + jitter). Used by every Drive, Docs and OAuth request.
+pub async fn execute_with_retry<F, Fut, T>(f: F) -> Result<T, TahweelError>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<T, TahweelError>>,
+{
+    execute_with_retry_counted(f)
+        .await
+        .map(|(result, _)| result)
+}
+
+/// Same as `execute_with_retry`, but also returns the retries that were needed.
+pub async fn execute_with_retry_counted<F, Fut, T>(f: F) -> Result<(T, Retries), TahweelError>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<T, TahweelError>>,
+{
+    with_retry(&RetryPolicy::from_settings(), f).await
+}
+
+/// Generate random jitter value between 0.0 and 1.0 using UUID v4.
+/// UUID v4 uses cryptographically secure random number generation,
+/// providing much better randomness than timestamp-based approaches.
+pub fn random_jitter() -> f64 {
+    // UUID v4 bytes are random; use the first 4 bytes as u32 for jitter
+    let uuid_bytes = uuid::Uuid::new_v4();
+    let bytes = uuid_bytes.as_bytes();
+    let random_u32 = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    (random_u32 as f64) / (u32::MAX as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeouts_for_class() {
+        let timeouts = TimeoutSettings {
+            api_connect_secs: 1,
+            api_read_secs: 2,
+            upload_connect_secs: 3,
+            upload_read_secs: 4,
+        };
+        assert_eq!(
+            timeouts_for(OperationClass::Api, &timeouts),
+            (Duration::from_secs(1), Duration::from_secs(2))
+        );
+        assert_eq!(
+            timeouts_for(OperationClass::Upload, &timeouts),
+            (Duration::from_secs(3), Duration::from_secs(4))
+        );
+    }
+
+    #[test]
+    fn test_client_builds_from_settings() {
+        assert!(client(OperationClass::Api).is_ok());
+        assert!(client(OperationClass::Upload).is_ok());
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_client_decodes_gzip_responses() {
+        let mut server = mockito::Server::new_async().await;
+        let _gzipped = server
+            .mock("GET", "/gzip")
+            .match_header("accept-encoding", "gzip")
+            .with_header("content-encoding", "gzip")
+            .with_body(gzip("نص الصفحة".as_bytes()))
+            .create_async()
+            .await;
+        let _plain = server
+            .mock("GET", "/plain")
+            .with_body("plain text")
+            .create_async()
+            .await;
+
+        let client = client(OperationClass::Api).unwrap();
+        let get = |path: &str| client.get(format!("{}{}", server.url(), path)).send();
+        assert_eq!(
+            get("/gzip").await.unwrap().text().await.unwrap(),
+            "نص الصفحة"
+        );
+        assert_eq!(
+            get("/plain").await.unwrap().text().await.unwrap(),
+            "plain text"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stalled_response_times_out() {
+        // A server that accepts the connection but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        });
+
+        let client = build(Duration::from_secs(1), Duration::from_millis(200)).unwrap();
+        let err: TahweelError = client
+            .get(format!("http://{}/", addr))
+            .send()
+            .await
+            .unwrap_err()
+            .into();
+
+        assert_eq!(err.kind(), "timeout");
+        assert!(err.is_retriable());
+        server.abort();
+    }
+
+    #[test]
+    fn test_random_jitter_returns_value_in_range() {
+        for _ in 0..100 {
+            let value = random_jitter();
+            assert!(value >= 0.0, "Jitter value should be >= 0.0");
+            assert!(value <= 1.0, "Jitter value should be <= 1.0");
+        }
+    }
+
+    #[test]
+    fn test_random_jitter_has_variance() {
+        // Verify we get different values (not deterministic)
+        let values: Vec<f64> = (0..10).map(|_| random_jitter()).collect();
+        // Count unique values by checking consecutive differences
+        let unique_count = values
+            .windows(2)
+            .filter(|w| (w[0] - w[1]).abs() > f64::EPSILON)
+            .count();
+        assert!(
+            unique_count > 0,
+            "Random jitter should produce varying values"
+        );
+    }
+
+    #[test]
+    fn test_exponential_backoff_calculation() {
+        // Test that backoff increases exponentially and caps at 15 seconds
+        let base: f64 = 1.5;
+
+        let delay_0 = base.powi(0).min(15.0);
+        let delay_1 = base.powi(1).min(15.0);
+        let delay_5 = base.powi(5).min(15.0);
+        let delay_10 = base.powi(10).min(15.0);
+
+        assert!((delay_0 - 1.0).abs() < 0.001);
+        assert!((delay_1 - 1.5).abs() < 0.001);
+        assert!((delay_5 - 7.59375).abs() < 0.001);
+        assert!((delay_10 - 15.0).abs() < 0.001); // Capped at 15
+    }
+
+    #[test]
+    fn test_backoff_delay_all_retries() {
+        // Test all retry delays to ensure they follow the pattern
+        let base: f64 = 1.5;
+        let max_retries = 5u32;
+
+        for retry in 0..max_retries {
+            let delay = base.powi(retry as i32).min(15.0);
+            assert!(delay >= 1.0, "Delay should be at least 1 second");
+            assert!(delay <= 15.0, "Delay should be capped at 15 seconds");
+
+            // Verify exponential growth
+            if retry > 0 {
+                let prev_delay = base.powi((retry - 1) as i32).min(15.0);
+                assert!(delay >= prev_delay, "Delay should increase or stay capped");
+            }
+        }
+    }
+
+    #[test]
+    fn test_jitter_adds_variability_to_delay() {
+        // Test that delay + jitter produces values in expected range
+        let base_delay = 1.5_f64.powi(2); // ~2.25 seconds
+
+        for _ in 0..50 {
+            let jitter = random_jitter();
+            let total_delay = base_delay + jitter;
+
+            assert!(total_delay >= base_delay);
+            assert!(total_delay <= base_delay + 1.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_immediate_success() {
+        // Test that execute_with_retry returns immediately on success
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        let call_count = Arc::new(AtomicU32::new(0));
+        let call_count_clone = call_count.clone();
+
+        let result = execute_with_retry(|| {
+            let count = call_count_clone.clone();
+            async move {
+                count.fetch_add(1, Ordering::SeqCst);
+                Ok::<_, TahweelError>("success".to_string())
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), "success");
+        assert_eq!(call_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_non_retriable_error() {
+        // Test that non-retriable errors fail immediately
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        let call_count = Arc::new(AtomicU32::new(0));
+        let call_count_clone = call_count.clone();
+
+        let result = execute_with_retry(|| {
+            let count = call_count_clone.clone();
+            async move {
+                count.fetch_add(1, Ordering::SeqCst);
+                Err::<String, _>(TahweelError::Api("Bad Request (400): Invalid".to_string()))
+            }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(call_count.load(Ordering::SeqCst), 1); // Only called once
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_retries_on_retriable_error() {
+        // Test that retriable errors are retried
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        let call_count = Arc::new(AtomicU32::new(0));
+        let call_count_clone = call_count.clone();
+
+        let result = execute_with_retry(|| {
+            let count = call_count_clone.clone();
+            async move {
+                let current = count.fetch_add(1, Ordering::SeqCst);
+                if current < 2 {
+                    Err(TahweelError::RateLimited(
+                        "Rate limit (429): Too many requests".to_string(),
+                    ))
+                } else {
+                    Ok("success after retries".to_string())
+                }
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), "success after retries");
+        assert_eq!(call_count.load(Ordering::SeqCst), 3); // Called 3 times
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_counts_throttled_attempts() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let policy = RetryPolicy {
+            max_retries: 5,
+            max_delay_secs: 0.0,
+            retriable: TahweelError::is_retriable,
+            simulation: NetworkSimulationSettings::default(),
+        };
+        let calls = AtomicU32::new(0);
+        let (_, retries) = with_retry(&policy, || async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 | 2 => Err(TahweelError::RateLimited("429".to_string())),
+                1 => Err(TahweelError::Server("503".to_string())),
+                _ => Ok(()),
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            retries,
+            Retries {
+                count: 3,
+                throttled: 2
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_max_retries_exceeded() {
+        // Test that we give up after max retries
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        let call_count = Arc::new(AtomicU32::new(0));
+        let call_count_clone = call_count.clone();
+
+        let result = execute_with_retry(|| {
+            let count = call_count_clone.clone();
+            async move {
+                count.fetch_add(1, Ordering::SeqCst);
+                Err::<String, _>(TahweelError::Server(
+                    "Server error (500): Always fails".to_string(),
+                ))
+            }
+        })
+        .await;
+
+        assert!(result.is_err());
+        // Initial call + 5 retries = 6 total calls
+        assert_eq!(call_count.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_counted_reports_retries() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        let call_count = Arc::new(AtomicU32::new(0));
+        let call_count_clone = call_count.clone();
+
+        let result = execute_with_retry_counted(|| {
+            let count = call_count_clone.clone();
+            async move {
+                if count.fetch_add(1, Ordering::SeqCst) < 1 {
+                    Err(TahweelError::Server(
+                        "Server error (503): Unavailable".to_string(),
+                    ))
+                } else {
+                    Ok("done".to_string())
+                }
+            }
+        })
+        .await;
+
+        let (value, retries) = result.unwrap();
+        assert_eq!(value, "done");
+        assert_eq!(
+            retries,
+            Retries {
+                count: 1,
+                throttled: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_counted_no_retries_on_success() {
+        let (value, retries) = execute_with_retry_counted(|| async { Ok::<_, TahweelError>(42) })
+            .await
+            .unwrap();
+        assert_eq!(value, 42);
+        assert_eq!(retries, Retries::default());
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_timeout_error() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        let call_count = Arc::new(AtomicU32::new(0));
+        let call_count_clone = call_count.clone();
+
+        let result = execute_with_retry(|| {
+            let count = call_count_clone.clone();
+            async move {
+                let current = count.fetch_add(1, Ordering::SeqCst);
+                if current < 1 {
+                    Err(TahweelError::Timeout("Connection timeout".to_string()))
+                } else {
+                    Ok("recovered from timeout".to_string())
+                }
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(call_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_uses_the_given_policy() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let policy = RetryPolicy {
+            max_retries: 1,
+            max_delay_secs: 0.0,
+            retriable: |e| e.kind() == "auth",
+            simulation: NetworkSimulationSettings::default(),
+        };
+        let calls = AtomicU32::new(0);
+        let result = with_retry(&policy, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(TahweelError::Auth("expired".to_string()))
+        })
+        .await;
+        assert_eq!(result.unwrap_err().kind(), "auth");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // The default retriable errors are not retried under this policy
+        calls.store(0, Ordering::SeqCst);
+        let result = with_retry(&policy, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(TahweelError::Server("503".to_string()))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_simulated_failure() {
+        let mut simulation = NetworkSimulationSettings {
+            enabled: true,
+            latency_ms: 0,
+            jitter_ms: 0,
+            failure_rate: 0.25,
+        };
+        assert!(simulated_failure(&simulation, 0.25, 0.0).is_none());
+        let kinds: Vec<_> = [0.0, 0.3, 0.6, 0.9]
+            .iter()
+            .map(|&roll| simulated_failure(&simulation, 0.1, roll).unwrap().kind())
+            .collect();
+        assert_eq!(kinds, ["network", "timeout", "rateLimited", "server"]);
+        assert!(simulated_failure(&simulation, 0.1, 0.0)
+            .unwrap()
+            .is_retriable());
+
+        simulation.enabled = false;
+        assert!(simulated_failure(&simulation, 0.0, 0.0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_under_network_simulation() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let mut policy = RetryPolicy {
+            max_retries: 2,
+            max_delay_secs: 0.0,
+            retriable: TahweelError::is_retriable,
+            simulation: NetworkSimulationSettings {
+                enabled: true,
+                latency_ms: 30,
+                jitter_ms: 0,
+                failure_rate: 1.0,
+            },
+        };
+        // Every attempt fails before reaching the request
+        let calls = AtomicU32::new(0);
+        let result = with_retry(&policy, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        })
+        .await;
+        assert!(result.unwrap_err().is_retriable());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        // Without failures, the latency is still added
+        policy.simulation.failure_rate = 0.0;
+        let started = std::time::Instant::now();
+        let (_, retries) = with_retry(&policy, || async { Ok(()) }).await.unwrap();
+        assert_eq!(retries.count, 0);
+        assert!(started.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_policy_delay_secs() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            max_delay_secs: 3.0,
+            retriable: TahweelError::is_retriable,
+            simulation: NetworkSimulationSettings::default(),
+        };
+        assert_eq!(policy.delay_secs(0), 1.0);
+        assert_eq!(policy.delay_secs(1), 1.5);
+        assert_eq!(policy.delay_secs(4), 3.0);
+    }
+}
@@ -0,0 +1,456 @@
+use crate::drive_safe::DownscaledPage;
+use crate::encryption;
+use crate::error::TahweelError;
+use crate::events::{ThrottledEmitter, PROGRESS_INTERVAL};
+use crate::job_usage;
+use crate::long_path;
+use crate::mobile;
+use crate::orientation;
+use crate::path_policy;
+use crate::pdf::{self, PageOutput, SplitProgress};
+use crate::perf::{self, Stage};
+use crate::settings;
+use image::metadata::Orientation;
+use image::{DynamicImage, GrayImage, ImageBuffer, ImageDecoder, ImageReader, Luma, Pixel, Rgb};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Instant;
+use tauri::AppHandle;
+
+/// Longer side of the downscaled copy skew and borders are detected on
+const ANALYSIS_SIZE: u32 = 800;
+/// Steepest skew, either way, `detect_skew` looks for (degrees)
+const MAX_SKEW_DEGREES: f64 = 5.0;
+const SKEW_STEP_DEGREES: f64 = 0.25;
+/// Smaller skews are left alone: turning the page would only blur it
+const MIN_SKEW_DEGREES: f64 = 0.3;
+/// Edge rows and columns with less ink than this share are empty margin
+const EMPTY_BORDER_SHARE: f64 = 0.005;
+/// Edge rows and columns with more ink than this share are dark surroundings
+/// (a table under a photographed page, a scanner lid shadow)
+const DARK_BORDER_SHARE: f64 = 0.6;
+/// Margin kept around the content when cropping, as a share of each side
+const CROP_MARGIN: f64 = 0.02;
+
+/// Optional clean-up of each image before OCR
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct ImageDocumentOptions {
+    /// Straighten pages photographed or scanned at a slight angle
+    pub deskew: bool,
+    /// Cut off empty margins and dark surroundings
+    pub crop: bool,
+}
+
+/// An input image that could not be turned into a page
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SkippedImage {
+    pub path: String,
+    pub error: String,
+}
+
+/// Page images of a document assembled from loose images, in a workspace folder
+/// released like a rendered PDF's (`cleanup_temp_dir`)
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageDocument {
+    #[serde(rename = "imagePaths")]
+    pub image_paths: Vec<String>,
+    #[serde(rename = "tempDir")]
+    pub temp_dir: String,
+    /// Input each page was made from, in page order
+    #[serde(rename = "sourcePaths")]
+    pub source_paths: Vec<String>,
+    /// Inputs left out because they could not be read
+    pub skipped: Vec<SkippedImage>,
+    /// Pages scaled down to Drive's limits (`driveSafeImages` setting)
+    #[serde(rename = "downscaledPages")]
+    pub downscaled_pages: Vec<DownscaledPage>,
+}
+
+/// Decode the image at `path` the right way up: photos taken with the phone
+/// turned are stored sideways with an EXIF orientation tag
+fn open_upright(path: &Path) -> Result<DynamicImage, TahweelError> {
+    let invalid =
+        |e: image::ImageError| TahweelError::InvalidInput(format!("Failed to read image: {}", e));
+    let mut decoder = ImageReader::open(path)
+        .map_err(|e| TahweelError::Io(format!("Failed to open image: {}", e)))?
+        .with_guessed_format()
+        .map_err(|e| TahweelError::Io(format!("Failed to open image: {}", e)))?
+        .into_decoder()
+        .map_err(invalid)?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder).map_err(invalid)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+fn analysis_copy(image: &DynamicImage) -> GrayImage {
+    if image.width().max(image.height()) > ANALYSIS_SIZE {
+        image.thumbnail(ANALYSIS_SIZE, ANALYSIS_SIZE).to_luma8()
+    } else {
+        image.to_luma8()
+    }
+}
+
+/// `image` turned `degrees` about its centre (positive is clockwise), keeping its
+/// size; corners uncovered by the turn are filled with `fill`
+fn rotate<P: Pixel>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    degrees: f64,
+    fill: P,
+) -> ImageBuffer<P, Vec<P::Subpixel>> {
+    let (width, height) = image.dimensions();
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let (dx, dy) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+        let sx = cos * dx + sin * dy + cx;
+        let sy = -sin * dx + cos * dy + cy;
+        if sx < 0.0 || sy < 0.0 || sx >= width as f64 || sy >= height as f64 {
+            fill
+        } else {
+            *image.get_pixel(sx as u32, sy as u32)
+        }
+    })
+}
+
+/// Turn (degrees, clockwise) that makes the text lines of `gray` horizontal:
+/// the one whose row ink profile shows the sharpest line/gap bands
+pub fn detect_skew(gray: &GrayImage) -> f64 {
+    let steps = (MAX_SKEW_DEGREES / SKEW_STEP_DEGREES).round() as i32;
+    let mut best = (
+        0.0,
+        orientation::sharpness(&orientation::ink_profile(gray, true)),
+    );
+    for step in (-steps..=steps).filter(|&step| step != 0) {
+        let degrees = f64::from(step) * SKEW_STEP_DEGREES;
+        let turned = rotate(gray, degrees, Luma([255]));
+        let sharpness = orientation::sharpness(&orientation::ink_profile(&turned, true));
+        if sharpness > best.1 {
+            best = (degrees, sharpness);
+        }
+    }
+    best.0
+}
+
+fn deskew(image: DynamicImage) -> DynamicImage {
+    let degrees = detect_skew(&analysis_copy(&image));
+    if degrees.abs() < MIN_SKEW_DEGREES {
+        return image;
+    }
+    match image {
+        DynamicImage::ImageLuma8(gray) => {
+            DynamicImage::ImageLuma8(rotate(&gray, degrees, Luma([255])))
+        }
+        other => DynamicImage::ImageRgb8(rotate(&other.to_rgb8(), degrees, Rgb([255; 3]))),
+    }
+}
+
+/// First and last index (exclusive) of `profile` that is content rather than
+/// border, for lines `length` pixels long
+fn content_span(profile: &[u32], length: u32) -> Option<(usize, usize)> {
+    let is_content = |&ink: &u32| {
+        let share = f64::from(ink) / f64::from(length.max(1));
+        (EMPTY_BORDER_SHARE..=DARK_BORDER_SHARE).contains(&share)
+    };
+    let first = profile.iter().position(is_content)?;
+    let last = profile.iter().rposition(is_content)?;
+    Some((first, last + 1))
+}
+
+/// Bounds `(x, y, width, height)` of the content of `gray`, or `None` when
+/// nothing is left to cut or no content was found
+pub fn content_bounds(gray: &GrayImage) -> Option<(u32, u32, u32, u32)> {
+    let (width, height) = gray.dimensions();
+    let (top, bottom) = content_span(&orientation::ink_profile(gray, true), width)?;
+    let (left, right) = content_span(&orientation::ink_profile(gray, false), height)?;
+    let margin_x = (f64::from(width) * CROP_MARGIN) as usize;
+    let margin_y = (f64::from(height) * CROP_MARGIN) as usize;
+    let left = left.saturating_sub(margin_x) as u32;
+    let top = top.saturating_sub(margin_y) as u32;
+    let right = (right + margin_x).min(width as usize) as u32;
+    let bottom = (bottom + margin_y).min(height as usize) as u32;
+    let bounds = (left, top, right - left, bottom - top);
+    (bounds != (0, 0, width, height)).then_some(bounds)
+}
+
+fn crop(image: DynamicImage) -> DynamicImage {
+    let gray = analysis_copy(&image);
+    let Some((x, y, width, height)) = content_bounds(&gray) else {
+        return image;
+    };
+    // Bounds were found on the downscaled copy
+    let scale_x = f64::from(image.width()) / f64::from(gray.width());
+    let scale_y = f64::from(image.height()) / f64::from(gray.height());
+    let x = (f64::from(x) * scale_x) as u32;
+    let y = (f64::from(y) * scale_y) as u32;
+    let width = ((f64::from(width) * scale_x).ceil() as u32).min(image.width() - x);
+    let height = ((f64::from(height) * scale_y).ceil() as u32).min(image.height() - y);
+    image.crop_imm(x, y, width.max(1), height.max(1))
+}
+
+/// Decode, straighten and crop one input image (see `ImageDocumentOptions`)
+pub fn normalize(path: &Path, options: ImageDocumentOptions) -> Result<DynamicImage, TahweelError> {
+    let mut image = open_upright(path)?;
+    if options.deskew {
+        image = deskew(image);
+    }
+    if options.crop {
+        image = crop(image);
+    }
+    Ok(image)
+}
+
+/// Turn loose images (e.g. photos of a book's pages, in reading order) into the
+/// pages of one document: each is decoded upright (EXIF orientation), optionally
+/// straightened and cropped, and saved like a rendered PDF page (`colorMode`,
+/// `driveSafeImages`, workspace encryption) as `page-0001.png` onwards in a new
+/// workspace folder. Images that cannot be read are skipped and listed; `job_id`
+/// tags the `split-progress` events and the per-page timings.
+#[tauri::command]
+#[tracing::instrument(skip(image_paths, job_id, app), err, fields(job = job_id.as_deref(), images = image_paths.len()))]
+pub async fn prepare_image_document(
+    image_paths: Vec<String>,
+    options: Option<ImageDocumentOptions>,
+    job_id: Option<String>,
+    app: AppHandle,
+) -> Result<ImageDocument, TahweelError> {
+    if image_paths.is_empty() {
+        return Err(TahweelError::InvalidInput(
+            "No images to convert".to_string(),
+        ));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        build_document(&image_paths, options.unwrap_or_default(), job_id, &app)
+    })
+    .await
+    .map_err(|e| TahweelError::Internal(format!("Image conversion failed: {}", e)))?
+}
+
+fn page_path(dir: &Path, page_number: usize) -> PathBuf {
+    dir.join(format!("page-{:04}.png", page_number))
+}
+
+fn build_document(
+    image_paths: &[String],
+    options: ImageDocumentOptions,
+    job_id: Option<String>,
+    app: &AppHandle,
+) -> Result<ImageDocument, TahweelError> {
+    let settings = settings::current();
+    let workspace = path_policy::workspace();
+    fs::create_dir_all(&workspace)
+        .map_err(|e| TahweelError::Io(format!("Failed to create temp directory: {}", e)))?;
+    let dir = tempfile::Builder::new()
+        .prefix("tahweel-images-")
+        .tempdir_in(&workspace)
+        .map_err(|e| TahweelError::Io(format!("Failed to create temp directory: {}", e)))?
+        .keep();
+    let dir_str = long_path::path_string(&dir)?;
+    let discard = || {
+        let _ = fs::remove_dir_all(&dir);
+        encryption::forget(&dir_str);
+    };
+    let output = PageOutput::new(&settings, encryption::key_if_enabled(&dir_str)?);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(mobile::render_threads(settings.render_threads, cfg!(mobile)) as usize)
+        .build()
+        .map_err(|e| {
+            TahweelError::Internal(format!("Failed to create render thread pool: {}", e))
+        })?;
+
+    // Each image is saved as soon as it is converted, under its input position,
+    // so only the images being worked on are held in memory
+    let total = image_paths.len() as u32;
+    let done = AtomicU32::new(0);
+    let progress =
+        ThrottledEmitter::for_job_event(app, "split-progress", job_id.clone(), PROGRESS_INTERVAL);
+    // An unreadable image is skipped, but a page that cannot be saved fails the document
+    type Converted = Result<(Option<DownscaledPage>, u64), TahweelError>;
+    let converted: Result<Vec<Converted>, TahweelError> = pool.install(|| {
+        image_paths
+            .par_iter()
+            .enumerate()
+            .map(|(index, path)| {
+                let started = Instant::now();
+                let cpu = job_usage::CpuTimer::start();
+                let page = match normalize(&long_path::extended(Path::new(path)), options) {
+                    Ok(image) => {
+                        let page_number = index as u32 + 1;
+                        let saved = page_path(&dir, index + 1);
+                        Ok(pdf::save_page(image, page_number, &saved, output)?)
+                    }
+                    Err(error) => Err(error),
+                };
+                if let Some(job_id) = job_id.as_deref() {
+                    job_usage::add_cpu(job_id, cpu.elapsed_ms());
+                }
+                progress.emit_with(|| {
+                    let count = done.fetch_add(1, Ordering::Relaxed) + 1;
+                    SplitProgress::new(job_id.clone(), count, total)
+                });
+                Ok(page.map(|downscaled| (downscaled, started.elapsed().as_millis() as u64)))
+            })
+            .collect()
+    });
+    progress.flush();
+    let converted = match converted {
+        Ok(converted) => converted,
+        Err(error) => {
+            discard();
+            return Err(error);
+        }
+    };
+
+    // Unreadable images are left out and the pages after them renumbered
+    let mut document = ImageDocument {
+        image_paths: Vec::with_capacity(image_paths.len()),
+        temp_dir: dir_str.clone(),
+        source_paths: Vec::with_capacity(image_paths.len()),
+        skipped: Vec::new(),
+        downscaled_pages: Vec::new(),
+    };
+    for (index, (source, result)) in image_paths.iter().zip(converted).enumerate() {
+        let (downscaled, elapsed_ms) = match result {
+            Ok(page) => page,
+            Err(error) => {
+                tracing::warn!(%error, "Skipping unreadable image");
+                let _ = fs::remove_file(page_path(&dir, index + 1));
+                document.skipped.push(SkippedImage {
+                    path: source.clone(),
+                    error: error.message().to_string(),
+                });
+                continue;
+            }
+        };
+        let page_number = document.image_paths.len() + 1;
+        let path = page_path(&dir, page_number);
+        if page_number != index + 1 {
+            if let Err(e) = fs::rename(page_path(&dir, index + 1), &path) {
+                discard();
+                return Err(TahweelError::Io(format!("Failed to number pages: {}", e)));
+            }
+        }
+        if let Some(job_id) = job_id.as_deref() {
+            perf::record(job_id, page_number as u32, Stage::Render, elapsed_ms);
+        }
+        document.image_paths.push(long_path::path_string(&path)?);
+        document.source_paths.push(source.clone());
+        document
+            .downscaled_pages
+            .extend(downscaled.map(|page| DownscaledPage {
+                page: page_number as u32,
+                ..page
+            }));
+    }
+    if document.image_paths.is_empty() {
+        discard();
+        return Err(TahweelError::InvalidInput(
+            "None of the images could be read".to_string(),
+        ));
+    }
+    tracing::info!(
+        pages = document.image_paths.len(),
+        skipped = document.skipped.len(),
+        "Prepared pages from images"
+    );
+    Ok(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orientation::INK_THRESHOLD;
+    use image::RgbImage;
+    use tempfile::tempdir;
+
+    /// A page of horizontal text-like lines on white
+    fn lined_page(width: u32, height: u32) -> GrayImage {
+        let mut page = GrayImage::from_pixel(width, height, Luma([255]));
+        for line in 0..10 {
+            let top = 40 + line * 30;
+            for y in top..top + 6 {
+                for x in 40..width - 40 {
+                    page.put_pixel(x, y, Luma([0]));
+                }
+            }
+        }
+        page
+    }
+
+    #[test]
+    fn test_detect_skew_straightens_turned_lines() {
+        let page = lined_page(400, 400);
+        assert_eq!(detect_skew(&page), 0.0);
+        let turned = rotate(&page, 2.0, Luma([255]));
+        let skew = detect_skew(&turned);
+        assert!((skew + 2.0).abs() <= SKEW_STEP_DEGREES, "skew {}", skew);
+    }
+
+    #[test]
+    fn test_content_bounds_trims_margins_and_dark_surroundings() {
+        // Content in the middle of a white page
+        let mut page = GrayImage::from_pixel(200, 300, Luma([255]));
+        for y in 100..150 {
+            for x in 50..150 {
+                page.put_pixel(x, y, Luma([(x % 2 * 255) as u8]));
+            }
+        }
+        let (x, y, width, height) = content_bounds(&page).unwrap();
+        assert!(x < 50 && x >= 45, "x {}", x);
+        assert!(y < 100 && y >= 90, "y {}", y);
+        assert!(x + width > 150 && x + width <= 155);
+        assert!(y + height > 150 && y + height <= 160);
+
+        // A dark band along the top edge is cut off too
+        let mut photo = page.clone();
+        for y in 0..30 {
+            for x in 0..200 {
+                photo.put_pixel(x, y, Luma([INK_THRESHOLD / 2]));
+            }
+        }
+        assert_eq!(content_bounds(&photo).unwrap().1, y);
+
+        // Nothing to cut from a page filled edge to edge, or from a blank one
+        let full = GrayImage::from_fn(50, 50, |x, y| Luma([((x + y) % 2 * 255) as u8]));
+        assert_eq!(content_bounds(&full), None);
+        assert_eq!(
+            content_bounds(&GrayImage::from_pixel(50, 50, Luma([255]))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_normalize_applies_options() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("photo.png");
+        let mut page = RgbImage::from_pixel(200, 300, Rgb([255; 3]));
+        for y in 100..150 {
+            for x in 50..150 {
+                page.put_pixel(x, y, Rgb([(x % 2 * 255) as u8; 3]));
+            }
+        }
+        page.save(&path).unwrap();
+
+        let plain = normalize(&path, ImageDocumentOptions::default()).unwrap();
+        assert_eq!((plain.width(), plain.height()), (200, 300));
+        let cropped = normalize(
+            &path,
+            ImageDocumentOptions {
+                deskew: true,
+                crop: true,
+            },
+        )
+        .unwrap();
+        assert!(cropped.width() < 120 && cropped.height() < 80);
+
+        assert!(normalize(
+            &dir.path().join("missing.jpg"),
+            ImageDocumentOptions::default()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,529 @@
+use crate::crash;
+use crate::docx::{write_docx, DocxPage, DocxSection};
+use crate::encryption;
+use crate::error::TahweelError;
+use crate::footnotes::page_text_with_footnotes;
+use crate::long_path;
+use crate::output::{
+    build_page_records, DocumentMetadata, JsonOutput, OutputFormat, PageInput, JSON_OUTPUT_VERSION,
+};
+use crate::path_policy::{self, PathAccess};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Version of the partial-output index schema
+const PARTIAL_INDEX_VERSION: u32 = 1;
+/// Append-only log of completed pages (one JSON object per line)
+const PAGES_LOG: &str = "pages.jsonl";
+/// Index of completed pages, replaced atomically after each append
+const INDEX_FILE: &str = "index.json";
+/// Prefix of log lines holding an encrypted page (`encryptWorkspace`)
+const ENCRYPTED_LINE_PREFIX: &str = "enc:";
+/// Suffix of the live TXT a job appends pages to as they are recognized
+const LIVE_TXT_SUFFIX: &str = ".partial.txt";
+
+/// Job state persisted next to the outputs while a job is running
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PartialIndex {
+    pub version: u32,
+    pub source: String,
+    #[serde(rename = "totalPages")]
+    pub total_pages: u32,
+    /// Zero-based indices of pages whose log entries are complete
+    pub completed: BTreeSet<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LoggedPage {
+    index: u32,
+    page: PageInput,
+}
+
+/// Result of finishing a partial output
+#[derive(Debug, Serialize)]
+pub struct FinalizedOutput {
+    pub paths: Vec<String>,
+    /// Missing pages (zero-based) that were written as empty text
+    #[serde(rename = "missingPages")]
+    pub missing_pages: Vec<u32>,
+}
+
+/// Directory holding the in-progress state for `<output_path>`
+pub fn partial_dir(output_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.partial", output_path))
+}
+
+fn read_index(dir: &Path) -> Result<Option<PartialIndex>, String> {
+    let path = dir.join(INDEX_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read partial index: {}", e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse partial index: {}", e))
+}
+
+/// Write the index to a temporary file and rename it over the old one,
+/// so a crash never leaves a half-written index behind.
+fn write_index_atomic(dir: &Path, index: &PartialIndex) -> Result<(), String> {
+    let json = serde_json::to_string(index).map_err(|e| e.to_string())?;
+    let tmp = dir.join(format!("{}.tmp", INDEX_FILE));
+    fs::write(&tmp, json).map_err(|e| format!("Failed to write partial index: {}", e))?;
+    fs::rename(&tmp, dir.join(INDEX_FILE))
+        .map_err(|e| format!("Failed to write partial index: {}", e))
+}
+
+/// Encryption scope of a partial-output directory
+fn key_scope(dir: &Path) -> String {
+    dir.to_string_lossy().to_string()
+}
+
+/// Decode a log line, decrypting it first when it was written encrypted.
+/// Lines encrypted in an earlier session can't be read and count as missing.
+fn parse_log_line(line: &str) -> Option<LoggedPage> {
+    match line.strip_prefix(ENCRYPTED_LINE_PREFIX) {
+        Some(sealed) => {
+            let (plain, _) = encryption::decrypt(BASE64.decode(sealed).ok()?).ok()?;
+            serde_json::from_slice(&plain).ok()
+        }
+        None => serde_json::from_str(line).ok(),
+    }
+}
+
+/// Read the pages recorded in the log, keeping only entries listed in the index.
+/// Truncated trailing lines (from a crash mid-append) are ignored; the last entry wins.
+fn read_logged_pages(dir: &Path, index: &PartialIndex) -> BTreeMap<u32, PageInput> {
+    let content = fs::read_to_string(dir.join(PAGES_LOG)).unwrap_or_default();
+    content
+        .lines()
+        .filter_map(parse_log_line)
+        .filter(|logged| index.completed.contains(&logged.index))
+        .map(|logged| (logged.index, logged.page))
+        .collect()
+}
+
+/// Record a completed page for `<output_path>`, creating the partial state on first use.
+#[tauri::command]
+pub async fn append_page_output(
+    output_path: String,
+    source_path: String,
+    total_pages: u32,
+    page_index: u32,
+    page: PageInput,
+) -> Result<(), String> {
+    if page_index >= total_pages {
+        return Err(format!(
+            "Page index {} out of range (total {})",
+            page_index, total_pages
+        ));
+    }
+
+    let dir = partial_dir(&output_path);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create partial output: {}", e))?;
+
+    let mut index = match read_index(&dir)? {
+        Some(index) if index.source == source_path && index.total_pages == total_pages => index,
+        _ => PartialIndex {
+            version: PARTIAL_INDEX_VERSION,
+            source: source_path,
+            total_pages,
+            completed: BTreeSet::new(),
+        },
+    };
+
+    let mut line = serde_json::to_string(&LoggedPage {
+        index: page_index,
+        page,
+    })
+    .map_err(|e| e.to_string())?;
+    if let Some(key) = encryption::key_if_enabled(&key_scope(&dir))? {
+        let sealed = encryption::encrypt(key, line.as_bytes())?;
+        line = format!("{}{}", ENCRYPTED_LINE_PREFIX, BASE64.encode(sealed));
+    }
+    line.push('\n');
+
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(PAGES_LOG))
+        .map_err(|e| format!("Failed to open page log: {}", e))?;
+    log.write_all(line.as_bytes())
+        .and_then(|_| log.sync_data())
+        .map_err(|e| format!("Failed to append page: {}", e))?;
+
+    index.completed.insert(page_index);
+    write_index_atomic(&dir, &index)?;
+    crash::register_job(&output_path, &index.source, total_pages);
+    Ok(())
+}
+
+/// Load the partial state for `<output_path>`, if a previous run left one.
+/// The frontend skips the completed pages when resuming.
+#[tauri::command]
+pub async fn load_partial_output(output_path: String) -> Result<Option<PartialIndex>, String> {
+    let dir = partial_dir(&output_path);
+    let Some(mut index) = read_index(&dir)? else {
+        return Ok(None);
+    };
+
+    // Only report pages that can actually be reconstructed from the log
+    let logged = read_logged_pages(&dir, &index);
+    index.completed.retain(|i| logged.contains_key(i));
+    Ok(Some(index))
+}
+
+/// Assemble the final outputs from the partial state and remove it.
+#[tauri::command]
+pub async fn finalize_partial_output(
+    output_path: String,
+    formats: Vec<OutputFormat>,
+    page_separator: String,
+) -> Result<FinalizedOutput, String> {
+    let dir = partial_dir(&output_path);
+    let index =
+        read_index(&dir)?.ok_or_else(|| format!("No partial output found for {}", output_path))?;
+    let mut logged = read_logged_pages(&dir, &index);
+
+    let mut missing_pages = Vec::new();
+    let pages: Vec<PageInput> = (0..index.total_pages)
+        .map(|i| {
+            logged.remove(&i).unwrap_or_else(|| {
+                missing_pages.push(i);
+                PageInput {
+                    text: String::new(),
+                    source_page: Some(i + 1),
+                    image_path: None,
+                    backend: None,
+                    retries: 0,
+                    timing: Default::default(),
+                    tables: Vec::new(),
+                    quran: Vec::new(),
+                    language: None,
+                    footnotes: None,
+                }
+            })
+        })
+        .collect();
+
+    let mut paths = Vec::new();
+
+    if formats.contains(&OutputFormat::Txt) {
+        let texts: Vec<String> = pages
+            .iter()
+            .map(|p| page_text_with_footnotes(&p.text, p.footnotes.as_deref()))
+            .collect();
+        let path = format!("{}.txt", output_path);
+        fs::write(&path, texts.join(&page_separator))
+            .map_err(|e| format!("Failed to write TXT output: {}", e))?;
+        paths.push(path);
+    }
+
+    if formats.contains(&OutputFormat::Docx) {
+        let path = format!("{}.docx", output_path);
+        write_docx(
+            &path,
+            &[DocxSection {
+                heading: None,
+                pages: pages
+                    .iter()
+                    .map(|p| DocxPage::with_footnotes(p.text.clone(), p.footnotes.clone()))
+                    .collect(),
+            }],
+            &DocumentMetadata::default(),
+        )?;
+        paths.push(path);
+    }
+
+    if formats.contains(&OutputFormat::Json) {
+        let records = build_page_records(pages);
+        let output = JsonOutput {
+            version: JSON_OUTPUT_VERSION,
+            source: index.source.clone(),
+            page_count: records.len() as u32,
+            pages: records,
+        };
+        let json = serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?;
+        let path = format!("{}.json", output_path);
+        fs::write(&path, json).map_err(|e| format!("Failed to write JSON output: {}", e))?;
+        paths.push(path);
+    }
+
+    fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove partial output: {}", e))?;
+    encryption::forget(&key_scope(&dir));
+    crash::unregister_job(&output_path);
+
+    Ok(FinalizedOutput {
+        paths,
+        missing_pages,
+    })
+}
+
+/// Live TXT of `<output_path>` (`liveTxt` setting)
+pub fn live_txt_path(output_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}", output_path, LIVE_TXT_SUFFIX))
+}
+
+/// Line introducing a page in the live TXT; pages arrive out of order when
+/// several are recognized at once
+fn live_page_marker(page: u32) -> String {
+    format!("===== Page {} =====", page)
+}
+
+/// `output_path` checked for writing, as the base the live TXT files hang off
+fn live_txt_base(output_path: &str) -> Result<String, TahweelError> {
+    let base = path_policy::check(Path::new(output_path), PathAccess::Write)?;
+    long_path::path_string(&base)
+}
+
+/// Start an empty live TXT for `<output_path>`, replacing one left by an earlier run
+#[tauri::command]
+pub async fn start_live_txt(output_path: String) -> Result<(), TahweelError> {
+    let output_path = live_txt_base(&output_path)?;
+    fs::write(live_txt_path(&output_path), "")
+        .map_err(|e| TahweelError::Io(format!("Failed to create live TXT: {}", e)))
+}
+
+/// Append a recognized page (1-based) to the live TXT of `<output_path>`
+#[tauri::command]
+pub async fn append_live_txt(
+    output_path: String,
+    page: u32,
+    text: String,
+) -> Result<(), TahweelError> {
+    let output_path = live_txt_base(&output_path)?;
+    let entry = format!("{}\n{}\n\n", live_page_marker(page), text.trim());
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(live_txt_path(&output_path))
+        .and_then(|mut file| file.write_all(entry.as_bytes()))
+        .map_err(|e| TahweelError::Io(format!("Failed to append to live TXT: {}", e)))
+}
+
+/// End the live TXT of `<output_path>`. With `text`, the final `<output_path>.txt`
+/// is written to a temporary file and renamed into place, so it appears whole;
+/// the live TXT is removed either way.
+#[tauri::command]
+pub async fn finish_live_txt(
+    output_path: String,
+    text: Option<String>,
+) -> Result<(), TahweelError> {
+    let output_path = live_txt_base(&output_path)?;
+    if let Some(text) = text {
+        let path = format!("{}.txt", output_path);
+        let tmp = format!("{}.tmp", path);
+        fs::write(&tmp, text)
+            .map_err(|e| TahweelError::Io(format!("Failed to write TXT output: {}", e)))?;
+        fs::rename(&tmp, &path).map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            TahweelError::Io(format!("Failed to write TXT output: {}", e))
+        })?;
+    }
+    match fs::remove_file(live_txt_path(&output_path)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(TahweelError::Io(format!(
+            "Failed to remove live TXT: {}",
+            e
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn page(text: &str) -> PageInput {
+        serde_json::from_value(serde_json::json!({ "text": text })).unwrap()
+    }
+
+    fn base(temp: &tempfile::TempDir) -> String {
+        temp.path().join("book").to_string_lossy().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_append_and_load_partial_output() {
+        let temp = tempdir().unwrap();
+        let output = base(&temp);
+
+        append_page_output(output.clone(), "book.pdf".into(), 3, 2, page("three"))
+            .await
+            .unwrap();
+        append_page_output(output.clone(), "book.pdf".into(), 3, 0, page("one"))
+            .await
+            .unwrap();
+
+        let index = load_partial_output(output).await.unwrap().unwrap();
+        assert_eq!(index.source, "book.pdf");
+        assert_eq!(index.total_pages, 3);
+        assert_eq!(index.completed.into_iter().collect::<Vec<_>>(), vec![0, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_live_txt() {
+        let temp = tempdir().unwrap();
+        let output = base(&temp);
+        fs::write(live_txt_path(&output), "from an earlier run").unwrap();
+
+        start_live_txt(output.clone()).await.unwrap();
+        append_live_txt(output.clone(), 2, "الصفحة الثانية\n".into())
+            .await
+            .unwrap();
+        append_live_txt(output.clone(), 1, "first".into())
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(live_txt_path(&output)).unwrap(),
+            "===== Page 2 =====\nالصفحة الثانية\n\n===== Page 1 =====\nfirst\n\n"
+        );
+
+        finish_live_txt(output.clone(), Some("first\n---\nالصفحة الثانية".into()))
+            .await
+            .unwrap();
+        assert!(!live_txt_path(&output).exists());
+        assert_eq!(
+            fs::read_to_string(format!("{}.txt", output)).unwrap(),
+            "first\n---\nالصفحة الثانية"
+        );
+        assert!(!Path::new(&format!("{}.txt.tmp", output)).exists());
+
+        // Without TXT among the formats, the live file is only removed
+        start_live_txt(output.clone()).await.unwrap();
+        finish_live_txt(output.clone(), None).await.unwrap();
+        assert!(!live_txt_path(&output).exists());
+        finish_live_txt(output, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_live_txt_outside_allowed_paths_is_rejected() {
+        let output = "/tahweel-denied/book".to_string();
+        let err = start_live_txt(output.clone()).await.unwrap_err();
+        assert_eq!(err.kind(), "pathNotAllowed");
+        let err = append_live_txt(output.clone(), 1, "text".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), "pathNotAllowed");
+        let err = finish_live_txt(output, Some("text".into()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), "pathNotAllowed");
+    }
+
+    #[tokio::test]
+    async fn test_load_partial_output_none() {
+        let temp = tempdir().unwrap();
+        assert!(load_partial_output(base(&temp)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_load_ignores_truncated_log_entry() {
+        let temp = tempdir().unwrap();
+        let output = base(&temp);
+        append_page_output(output.clone(), "book.pdf".into(), 2, 0, page("one"))
+            .await
+            .unwrap();
+
+        // Simulate a crash: the index claims page 1 but its log line is cut off
+        let dir = partial_dir(&output);
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(dir.join(PAGES_LOG))
+            .unwrap();
+        log.write_all(br#"{"index":1,"page":{"te"#).unwrap();
+        let mut index = read_index(&dir).unwrap().unwrap();
+        index.completed.insert(1);
+        write_index_atomic(&dir, &index).unwrap();
+
+        let loaded = load_partial_output(output).await.unwrap().unwrap();
+        assert_eq!(loaded.completed.into_iter().collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn test_parse_encrypted_log_line() {
+        let json = r#"{"index":3,"page":{"text":"نص"}}"#;
+        let key = encryption::scope_key("test-incremental-line").unwrap();
+        let line = format!(
+            "{}{}",
+            ENCRYPTED_LINE_PREFIX,
+            BASE64.encode(encryption::encrypt(key, json.as_bytes()).unwrap())
+        );
+
+        let logged = parse_log_line(&line).unwrap();
+        assert_eq!(logged.index, 3);
+        assert_eq!(logged.page.text, "نص");
+        assert_eq!(parse_log_line(json).unwrap().index, 3);
+
+        // A key from an earlier session is gone, so the page counts as missing
+        encryption::forget("test-incremental-line");
+        assert!(parse_log_line(&line).is_none());
+        assert!(parse_log_line("enc:not base64!").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_append_resets_state_for_different_source() {
+        let temp = tempdir().unwrap();
+        let output = base(&temp);
+        append_page_output(output.clone(), "old.pdf".into(), 2, 0, page("old"))
+            .await
+            .unwrap();
+        append_page_output(output.clone(), "new.pdf".into(), 5, 4, page("new"))
+            .await
+            .unwrap();
+
+        let index = load_partial_output(output).await.unwrap().unwrap();
+        assert_eq!(index.source, "new.pdf");
+        assert_eq!(index.completed.into_iter().collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[tokio::test]
+    async fn test_append_rejects_out_of_range_index() {
+        let temp = tempdir().unwrap();
+        let result = append_page_output(base(&temp), "book.pdf".into(), 2, 2, page("x")).await;
+        assert!(result.unwrap_err().contains("out of range"));
+    }
+
+    #[tokio::test]
+    async fn test_finalize_partial_output_in_order() {
+        let temp = tempdir().unwrap();
+        let output = base(&temp);
+        for (i, text) in [(1, "two"), (0, "one"), (1, "two again")] {
+            append_page_output(output.clone(), "book.pdf".into(), 3, i, page(text))
+                .await
+                .unwrap();
+        }
+
+        let result = finalize_partial_output(
+            output.clone(),
+            vec![OutputFormat::Txt, OutputFormat::Json],
+            "|".to_string(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.missing_pages, vec![2]);
+        assert_eq!(
+            fs::read_to_string(format!("{}.txt", output)).unwrap(),
+            "one|two again|"
+        );
+        let parsed: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(format!("{}.json", output)).unwrap()).unwrap();
+        assert_eq!(parsed["source"], "book.pdf");
+        assert_eq!(parsed["pageCount"], 3);
+        assert!(!partial_dir(&output).exists());
+    }
+
+    #[tokio::test]
+    async fn test_finalize_without_partial_output() {
+        let temp = tempdir().unwrap();
+        let result =
+            finalize_partial_output(base(&temp), vec![OutputFormat::Txt], "|".into()).await;
+        assert!(result.unwrap_err().contains("No partial output"));
+    }
+}
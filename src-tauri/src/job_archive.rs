@@ -0,0 +1,551 @@
+use crate::archive::{ManifestEntry, PackageManifest, MANIFEST_NAME, MANIFEST_VERSION};
+use crate::error::TahweelError;
+use crate::jobs::{self, DocxOptions, JobRecord};
+use crate::output::{sha256_file, DocumentMetadata, OutputFormat};
+use crate::path_policy::{self, PathAccess};
+use crate::pdf;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Version of the job archive layout
+pub const JOB_ARCHIVE_VERSION: u32 = 1;
+/// Extension of job archive files (a ZIP)
+pub const JOB_ARCHIVE_EXTENSION: &str = "tahweeljob";
+
+const JOB_ENTRY: &str = "job.json";
+const SETTINGS_ENTRY: &str = "settings.json";
+const REPORT_ENTRY: &str = "report.json";
+const OUTPUTS_DIR: &str = "outputs";
+const PAGES_DIR: &str = "pages";
+
+/// The job record stored in `job.json`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchivedJob {
+    pub version: u32,
+    pub id: String,
+    #[serde(rename = "sourcePath")]
+    pub source_path: String,
+    /// Output file name without extension (e.g. `book` for `book.txt`)
+    #[serde(rename = "outputName")]
+    pub output_name: String,
+    pub formats: Vec<OutputFormat>,
+    #[serde(rename = "pageSeparator")]
+    pub page_separator: String,
+    pub pages: Vec<String>,
+    /// DOCX core properties; archives of older versions have none
+    #[serde(default)]
+    pub metadata: DocumentMetadata,
+}
+
+/// Result of `import_job_archive`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ImportedJob {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    /// Output path without extension, as recorded for the imported job
+    #[serde(rename = "outputPath")]
+    pub output_path: String,
+    /// Files written by the import
+    pub paths: Vec<String>,
+    /// Settings the job was produced with (secrets redacted); not applied
+    pub settings: Value,
+}
+
+fn sha256_bytes(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+fn zip_error(e: zip::result::ZipError) -> TahweelError {
+    TahweelError::Io(format!("Job archive error: {}", e))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Files to archive for `job` as `(entry name, path)`: its existing outputs,
+/// its quality report, and the given page images
+fn file_entries(job: &JobRecord, page_images: &[PathBuf]) -> Vec<(String, PathBuf)> {
+    let mut entries = Vec::new();
+    for format in &job.formats {
+        let extension = match format {
+            OutputFormat::Txt => "txt",
+            OutputFormat::Json => "json",
+            OutputFormat::Docx => "docx",
+        };
+        let path = PathBuf::from(format!("{}.{}", job.output_path, extension));
+        if let (true, Some(name)) = (path.is_file(), path.file_name()) {
+            entries.push((
+                format!("{}/{}", OUTPUTS_DIR, name.to_string_lossy()),
+                path.clone(),
+            ));
+        }
+    }
+    let report = PathBuf::from(format!("{}.report.json", job.output_path));
+    if report.is_file() {
+        entries.push((REPORT_ENTRY.to_string(), report));
+    }
+    for image in page_images {
+        if let Some(name) = image.file_name() {
+            entries.push((
+                format!("{}/{}", PAGES_DIR, name.to_string_lossy()),
+                image.clone(),
+            ));
+        }
+    }
+    entries
+}
+
+/// Write `job` and its files to a job archive at `archive_path`
+pub fn write_job_archive(
+    job: &JobRecord,
+    settings_json: &str,
+    page_images: &[PathBuf],
+    archive_path: &Path,
+) -> Result<(), TahweelError> {
+    let archived = ArchivedJob {
+        version: JOB_ARCHIVE_VERSION,
+        id: job.id.clone(),
+        source_path: job.source_path.clone(),
+        output_name: Path::new(&job.output_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        formats: job.formats.clone(),
+        page_separator: job.page_separator.clone(),
+        pages: job.pages.clone(),
+        metadata: job.docx.metadata.clone(),
+    };
+    let job_json = serde_json::to_string_pretty(&archived)?;
+
+    // Write next to the target and rename, so a failed export never leaves a partial archive
+    let tmp_path = archive_path.with_extension(format!("{}.tmp", JOB_ARCHIVE_EXTENSION));
+    let mut zip = ZipWriter::new(fs::File::create(&tmp_path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut manifest_files = Vec::new();
+
+    for (name, data) in [
+        (JOB_ENTRY, job_json.as_bytes()),
+        (SETTINGS_ENTRY, settings_json.as_bytes()),
+    ] {
+        zip.start_file(name, options).map_err(zip_error)?;
+        zip.write_all(data)?;
+        manifest_files.push(ManifestEntry {
+            path: name.to_string(),
+            size: data.len() as u64,
+            sha256: sha256_bytes(data),
+        });
+    }
+    for (name, path) in file_entries(job, page_images) {
+        zip.start_file(name.as_str(), options).map_err(zip_error)?;
+        let size = io::copy(&mut fs::File::open(&path)?, &mut zip)?;
+        manifest_files.push(ManifestEntry {
+            path: name,
+            size,
+            sha256: sha256_file(&path).map_err(TahweelError::Io)?,
+        });
+    }
+
+    let manifest = PackageManifest {
+        version: MANIFEST_VERSION,
+        source: Some(job.source_path.clone()),
+        created_at: now_secs(),
+        files: manifest_files,
+    };
+    zip.start_file(MANIFEST_NAME, options).map_err(zip_error)?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+    zip.finish().map_err(zip_error)?;
+
+    fs::rename(&tmp_path, archive_path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        TahweelError::Io(format!("Failed to write job archive: {}", e))
+    })
+}
+
+/// A single, plain file name (no separators, not `.`/`..`)
+fn plain_name(name: &str) -> Option<&str> {
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\'])
+        && !name.contains(':');
+    valid.then_some(name)
+}
+
+/// Where an archive entry is restored to, or `None` for entries kept in memory
+/// or not part of the layout
+fn entry_target(name: &str, output_dir: &Path, output_name: &str) -> Option<PathBuf> {
+    if name == REPORT_ENTRY {
+        return Some(output_dir.join(format!("{}.report.json", output_name)));
+    }
+    let (dir, file) = name.split_once('/')?;
+    let file = plain_name(file)?;
+    match dir {
+        OUTPUTS_DIR => Some(output_dir.join(file)),
+        PAGES_DIR => Some(output_dir.join(format!("{}-pages", output_name)).join(file)),
+        _ => None,
+    }
+}
+
+fn read_entry(archive: &mut ZipArchive<fs::File>, name: &str) -> Result<Vec<u8>, TahweelError> {
+    let mut entry = archive.by_name(name).map_err(|e| {
+        TahweelError::InvalidInput(format!("Job archive is missing {}: {}", name, e))
+    })?;
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Restore a job archive into `output_dir` and record the job in `conn`.
+/// Every entry is checked against the manifest before anything is written;
+/// existing files are only replaced when `overwrite` is set.
+pub fn restore_job_archive(
+    conn: &mut rusqlite::Connection,
+    archive_path: &Path,
+    output_dir: &Path,
+    overwrite: bool,
+) -> Result<ImportedJob, TahweelError> {
+    let mut archive = ZipArchive::new(fs::File::open(archive_path)?)
+        .map_err(|e| TahweelError::InvalidInput(format!("Not a job archive: {}", e)))?;
+    let manifest: PackageManifest =
+        serde_json::from_slice(&read_entry(&mut archive, MANIFEST_NAME)?).map_err(|e| {
+            TahweelError::InvalidInput(format!("Invalid job archive manifest: {}", e))
+        })?;
+    let job: ArchivedJob = serde_json::from_slice(&read_entry(&mut archive, JOB_ENTRY)?)
+        .map_err(|e| TahweelError::InvalidInput(format!("Invalid job record: {}", e)))?;
+    if job.version > JOB_ARCHIVE_VERSION {
+        return Err(TahweelError::InvalidInput(format!(
+            "Job archive version {} is newer than this app supports ({})",
+            job.version, JOB_ARCHIVE_VERSION
+        )));
+    }
+    let output_name = plain_name(&job.output_name)
+        .ok_or_else(|| {
+            TahweelError::InvalidInput("Invalid output name in job archive".to_string())
+        })?
+        .to_string();
+
+    // Verify everything first so a corrupted archive writes nothing
+    let mut settings = Value::Null;
+    let mut files = Vec::new();
+    for entry in &manifest.files {
+        let data = read_entry(&mut archive, &entry.path)?;
+        if sha256_bytes(&data) != entry.sha256 {
+            return Err(TahweelError::InvalidInput(format!(
+                "Job archive entry {} is corrupted (checksum mismatch)",
+                entry.path
+            )));
+        }
+        if entry.path == SETTINGS_ENTRY {
+            settings = serde_json::from_slice(&data).unwrap_or(Value::Null);
+        } else if let Some(target) = entry_target(&entry.path, output_dir, &output_name) {
+            if target.exists() && !overwrite {
+                return Err(TahweelError::InvalidInput(format!(
+                    "{} already exists",
+                    target.display()
+                )));
+            }
+            files.push((target, data));
+        }
+    }
+
+    let mut paths = Vec::with_capacity(files.len());
+    for (target, data) in files {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, data)?;
+        paths.push(target.to_string_lossy().to_string());
+    }
+
+    let output_path = output_dir.join(&output_name).to_string_lossy().to_string();
+    jobs::record(
+        conn,
+        &JobRecord {
+            id: job.id.clone(),
+            source_path: job.source_path,
+            output_path: output_path.clone(),
+            formats: job.formats,
+            page_separator: job.page_separator,
+            pages: job.pages,
+            // The scans' original paths are not carried across machines
+            docx: DocxOptions {
+                metadata: job.metadata,
+                ..DocxOptions::default()
+            },
+        },
+    )?;
+
+    Ok(ImportedJob {
+        job_id: job.id,
+        output_path,
+        paths,
+        settings,
+    })
+}
+
+/// Export a completed job as a single `<output>.tahweeljob` file (or
+/// `archive_path`) holding its outputs, quality report, page text, and the
+/// current settings. `include_pages` also renders the source PDF's pages
+/// (or copies the source image) into it. Returns the archive path.
+#[tauri::command]
+#[tracing::instrument(skip(app), err)]
+pub async fn export_job_archive(
+    app: AppHandle,
+    job_id: String,
+    archive_path: Option<String>,
+    include_pages: bool,
+) -> Result<String, TahweelError> {
+    let id = job_id.clone();
+    let job = jobs::db()?
+        .read_async(move |conn| jobs::load(conn, &id))
+        .await?
+        .ok_or_else(|| TahweelError::NotFound(format!("Job not found: {}", job_id)))?;
+    let archive_path =
+        archive_path.unwrap_or_else(|| format!("{}.{}", job.output_path, JOB_ARCHIVE_EXTENSION));
+    let archive_path = path_policy::check(Path::new(&archive_path), PathAccess::Write)?;
+
+    let workspace = path_policy::workspace();
+    fs::create_dir_all(&workspace)?;
+    let scratch = tempfile::tempdir_in(&workspace)?;
+    let source = Path::new(&job.source_path);
+    let page_images = if !include_pages || !source.is_file() {
+        Vec::new()
+    } else if source
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+    {
+        pdf::render_pages(&app, source, settings::current().dpi, scratch.path())?
+    } else {
+        vec![source.to_path_buf()]
+    };
+
+    let settings_json = serde_json::to_string_pretty(&settings::current().redacted())?;
+    write_job_archive(&job, &settings_json, &page_images, &archive_path)?;
+    Ok(archive_path.to_string_lossy().to_string())
+}
+
+/// Restore a job archive: write its outputs (and page images, if included)
+/// into `output_dir` (default: the archive's folder) and record the job so
+/// proofreading and corrections work on this machine
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn import_job_archive(
+    archive_path: String,
+    output_dir: Option<String>,
+    overwrite: bool,
+) -> Result<ImportedJob, TahweelError> {
+    let archive = Path::new(&archive_path);
+    let output_dir = match output_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => archive
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| TahweelError::InvalidInput("Invalid archive path".to_string()))?,
+    };
+    let output_dir = path_policy::check(&output_dir, PathAccess::Write)?;
+    let archive = archive.to_path_buf();
+    jobs::db()?
+        .write_async(move |conn| restore_job_archive(conn, &archive, &output_dir, overwrite))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        jobs::init_schema(&conn).unwrap();
+        conn
+    }
+
+    fn job(dir: &Path) -> JobRecord {
+        let output_path = dir.join("book").to_string_lossy().to_string();
+        fs::write(
+            format!("{}.txt", output_path),
+            "الصفحة الأولى\n---\nالصفحة الثانية",
+        )
+        .unwrap();
+        fs::write(
+            format!("{}.report.json", output_path),
+            r#"{"pageCount": 2}"#,
+        )
+        .unwrap();
+        JobRecord {
+            id: "job-1".to_string(),
+            source_path: "/books/book.pdf".to_string(),
+            output_path,
+            formats: vec![OutputFormat::Txt, OutputFormat::Docx],
+            page_separator: "\n---\n".to_string(),
+            pages: vec!["الصفحة الأولى".to_string(), "الصفحة الثانية".to_string()],
+            docx: DocxOptions::default(),
+        }
+    }
+
+    fn entry_names(path: &Path) -> Vec<String> {
+        let archive = ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_entry_target_rejects_escapes() {
+        let dir = Path::new("/out");
+        assert_eq!(
+            entry_target("outputs/book.txt", dir, "book"),
+            Some(PathBuf::from("/out/book.txt"))
+        );
+        assert_eq!(
+            entry_target("pages/page-0001.png", dir, "book"),
+            Some(PathBuf::from("/out/book-pages/page-0001.png"))
+        );
+        assert_eq!(
+            entry_target("report.json", dir, "book"),
+            Some(PathBuf::from("/out/book.report.json"))
+        );
+        assert_eq!(entry_target("outputs/../evil.txt", dir, "book"), None);
+        assert_eq!(entry_target("outputs/..", dir, "book"), None);
+        assert_eq!(entry_target("other/file.txt", dir, "book"), None);
+        assert_eq!(entry_target("job.json", dir, "book"), None);
+    }
+
+    #[test]
+    fn test_write_job_archive_contents() {
+        let temp = tempdir().unwrap();
+        let job = job(temp.path());
+        let image = temp.path().join("page-0001.png");
+        fs::write(&image, b"png").unwrap();
+        let archive = temp.path().join("book.tahweeljob");
+
+        write_job_archive(&job, "{}", &[image], &archive).unwrap();
+
+        // The DOCX was never written, so only the TXT output is archived
+        assert_eq!(
+            entry_names(&archive),
+            vec![
+                "job.json",
+                "manifest.json",
+                "outputs/book.txt",
+                "pages/page-0001.png",
+                "report.json",
+                "settings.json"
+            ]
+        );
+        assert!(!temp.path().join("book.tahweeljob.tmp").exists());
+    }
+
+    #[test]
+    fn test_round_trip_restores_outputs_and_job() {
+        let source = tempdir().unwrap();
+        let job = job(source.path());
+        let archive = source.path().join("book.tahweeljob");
+        write_job_archive(&job, r#"{"dpi": 300}"#, &[], &archive).unwrap();
+
+        let target = tempdir().unwrap();
+        let mut conn = db();
+        let imported = restore_job_archive(&mut conn, &archive, target.path(), false).unwrap();
+
+        let output_path = target.path().join("book").to_string_lossy().to_string();
+        assert_eq!(imported.job_id, "job-1");
+        assert_eq!(imported.output_path, output_path);
+        assert_eq!(imported.settings["dpi"], 300);
+        assert_eq!(
+            fs::read_to_string(target.path().join("book.txt")).unwrap(),
+            "الصفحة الأولى\n---\nالصفحة الثانية"
+        );
+        assert!(target.path().join("book.report.json").is_file());
+
+        let restored = jobs::load(&conn, "job-1").unwrap().unwrap();
+        assert_eq!(restored.output_path, output_path);
+        assert_eq!(restored.pages, job.pages);
+        assert_eq!(restored.formats, job.formats);
+    }
+
+    #[test]
+    fn test_restore_refuses_to_overwrite() {
+        let temp = tempdir().unwrap();
+        let job = job(temp.path());
+        let archive = temp.path().join("book.tahweeljob");
+        write_job_archive(&job, "{}", &[], &archive).unwrap();
+        fs::write(temp.path().join("book.txt"), "edited locally").unwrap();
+
+        let err = restore_job_archive(&mut db(), &archive, temp.path(), false).unwrap_err();
+        assert!(matches!(err, TahweelError::InvalidInput(_)));
+        assert_eq!(
+            fs::read_to_string(temp.path().join("book.txt")).unwrap(),
+            "edited locally"
+        );
+
+        restore_job_archive(&mut db(), &archive, temp.path(), true).unwrap();
+        assert_ne!(
+            fs::read_to_string(temp.path().join("book.txt")).unwrap(),
+            "edited locally"
+        );
+    }
+
+    #[test]
+    fn test_restore_rejects_corrupted_entry() {
+        let temp = tempdir().unwrap();
+        let archive = temp.path().join("bad.tahweeljob");
+        let mut zip = ZipWriter::new(fs::File::create(&archive).unwrap());
+        let options = SimpleFileOptions::default();
+        let job = ArchivedJob {
+            version: JOB_ARCHIVE_VERSION,
+            id: "job-1".to_string(),
+            source_path: "book.pdf".to_string(),
+            output_name: "book".to_string(),
+            formats: vec![OutputFormat::Txt],
+            page_separator: "\n".to_string(),
+            pages: vec![],
+            metadata: DocumentMetadata::default(),
+        };
+        let manifest = PackageManifest {
+            version: MANIFEST_VERSION,
+            source: None,
+            created_at: 0,
+            files: vec![ManifestEntry {
+                path: "outputs/book.txt".to_string(),
+                size: 4,
+                sha256: sha256_bytes(b"text"),
+            }],
+        };
+        for (name, data) in [
+            (JOB_ENTRY, serde_json::to_vec(&job).unwrap()),
+            (MANIFEST_NAME, serde_json::to_vec(&manifest).unwrap()),
+            ("outputs/book.txt", b"tampered".to_vec()),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(&data).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let out = temp.path().join("out");
+        let err = restore_job_archive(&mut db(), &archive, &out, false).unwrap_err();
+        assert!(err.message().contains("checksum mismatch"));
+        assert!(!out.join("book.txt").exists());
+    }
+
+    #[test]
+    fn test_restore_rejects_non_archive() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("book.txt");
+        fs::write(&path, "not a zip").unwrap();
+        let err = restore_job_archive(&mut db(), &path, temp.path(), false).unwrap_err();
+        assert!(matches!(err, TahweelError::InvalidInput(_)));
+    }
+}
@@ -0,0 +1,153 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use tauri::ipc::Channel;
+
+/// One event of a job, as sent on its channels: the name of the global event
+/// it mirrors (`split-progress`, `stage-timing`, `job-report`) and its payload
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct JobEvent {
+    pub event: &'static str,
+    pub payload: Value,
+}
+
+type Sink = Box<dyn Fn(&JobEvent) + Send + Sync>;
+
+struct Subscription {
+    id: u32,
+    job_id: String,
+    sink: Sink,
+}
+
+static SUBSCRIPTIONS: Mutex<Vec<Subscription>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Send every event of `job_id` to `sink` until `unsubscribe` is called with
+/// the returned id
+fn subscribe(job_id: &str, sink: Sink) -> u32 {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    SUBSCRIPTIONS
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .push(Subscription {
+            id,
+            job_id: job_id.to_string(),
+            sink,
+        });
+    id
+}
+
+/// Stop a subscription; returns whether it existed
+fn unsubscribe(id: u32) -> bool {
+    let mut subscriptions = SUBSCRIPTIONS.lock().unwrap_or_else(|p| p.into_inner());
+    let before = subscriptions.len();
+    subscriptions.retain(|subscription| subscription.id != id);
+    subscriptions.len() != before
+}
+
+/// Send `payload` as `event` to the subscribers of `job_id`. Events are still
+/// emitted globally by their callers; this only adds the per-job copy.
+pub fn send<T: Serialize>(job_id: &str, event: &'static str, payload: &T) {
+    let subscriptions = SUBSCRIPTIONS.lock().unwrap_or_else(|p| p.into_inner());
+    let mut matching = subscriptions
+        .iter()
+        .filter(|subscription| subscription.job_id == job_id)
+        .peekable();
+    if matching.peek().is_none() {
+        return;
+    }
+    let event = match serde_json::to_value(payload) {
+        Ok(payload) => JobEvent { event, payload },
+        Err(error) => {
+            tracing::warn!(%error, event, "Failed to serialize a job event");
+            return;
+        }
+    };
+    for subscription in matching {
+        (subscription.sink)(&event);
+    }
+}
+
+/// Receive the progress, timing and report events of one job on `on_event`.
+/// Returns a subscription id for `unsubscribe_job_events`.
+#[tauri::command]
+pub fn subscribe_job_events(job_id: String, on_event: Channel<JobEvent>) -> u32 {
+    subscribe(
+        &job_id,
+        Box::new(move |event| {
+            let _ = on_event.send(event.clone());
+        }),
+    )
+}
+
+/// Stop a subscription made with `subscribe_job_events`
+#[tauri::command]
+pub fn unsubscribe_job_events(subscription: u32) -> bool {
+    unsubscribe(subscription)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn recorder() -> (Arc<Mutex<Vec<JobEvent>>>, Sink) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink: Sink = {
+            let received = received.clone();
+            Box::new(move |event| received.lock().unwrap().push(event.clone()))
+        };
+        (received, sink)
+    }
+
+    #[test]
+    fn test_events_reach_only_their_job() {
+        let first = uuid::Uuid::new_v4().to_string();
+        let second = uuid::Uuid::new_v4().to_string();
+        let (first_events, sink) = recorder();
+        subscribe(&first, sink);
+        let (second_events, sink) = recorder();
+        subscribe(&second, sink);
+
+        send(
+            &first,
+            "split-progress",
+            &serde_json::json!({"currentPage": 1}),
+        );
+        send(
+            &second,
+            "split-progress",
+            &serde_json::json!({"currentPage": 7}),
+        );
+        send(
+            &first,
+            "split-progress",
+            &serde_json::json!({"currentPage": 2}),
+        );
+
+        let pages = |events: &Mutex<Vec<JobEvent>>| -> Vec<Value> {
+            events
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|event| event.payload["currentPage"].clone())
+                .collect()
+        };
+        assert_eq!(pages(&first_events), vec![1, 2]);
+        assert_eq!(pages(&second_events), vec![7]);
+        assert_eq!(first_events.lock().unwrap()[0].event, "split-progress");
+    }
+
+    #[test]
+    fn test_unsubscribe_stops_events() {
+        let job = uuid::Uuid::new_v4().to_string();
+        let (received, sink) = recorder();
+        let id = subscribe(&job, sink);
+        send(&job, "stage-timing", &1);
+        assert!(unsubscribe(id));
+        assert!(!unsubscribe(id));
+        send(&job, "stage-timing", &2);
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+}
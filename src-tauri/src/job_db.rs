@@ -0,0 +1,304 @@
+use crate::error::TahweelError;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// How long a statement waits for a lock held by another connection or
+/// process (a second window, a second launch) before failing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Idle read connections kept open per database
+const MAX_IDLE_READERS: usize = 4;
+
+/// Work run on the writer thread
+type WriteJob = Box<dyn FnOnce(&mut Connection) + Send>;
+
+/// Databases opened in this process, by path
+static SHARED: OnceLock<Mutex<HashMap<PathBuf, Arc<JobDb>>>> = OnceLock::new();
+
+pub(crate) fn db_error(e: rusqlite::Error) -> TahweelError {
+    TahweelError::Io(format!("Job database error: {}", e))
+}
+
+/// Settings every connection to the job database uses: WAL, so readers never
+/// block the writer or each other, and a busy timeout for locks held elsewhere
+pub fn configure(conn: &Connection) -> Result<(), TahweelError> {
+    conn.busy_timeout(BUSY_TIMEOUT).map_err(db_error)?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(db_error)?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(db_error)
+}
+
+/// The job database: one writer thread that runs every change in turn, and a
+/// pool of read-only connections. Commands, the heartbeat and maintenance
+/// share it, so their writes never interleave or fail with `SQLITE_BUSY`.
+pub struct JobDb {
+    path: PathBuf,
+    writer: Mutex<Sender<WriteJob>>,
+    readers: Mutex<Vec<Connection>>,
+}
+
+impl JobDb {
+    /// Open the database at `path` (created with its schema if needed) and
+    /// start its writer thread
+    pub fn open(
+        path: &Path,
+        init_schema: fn(&Connection) -> Result<(), TahweelError>,
+    ) -> Result<Arc<JobDb>, TahweelError> {
+        let mut conn = Connection::open(path).map_err(db_error)?;
+        configure(&conn)?;
+        init_schema(&conn)?;
+
+        let (sender, receiver) = mpsc::channel::<WriteJob>();
+        std::thread::Builder::new()
+            .name("job-db-writer".to_string())
+            .spawn(move || {
+                for job in receiver {
+                    // A panicking write fails its caller, not the writer
+                    let _ = panic::catch_unwind(AssertUnwindSafe(|| job(&mut conn)));
+                }
+            })?;
+
+        Ok(Arc::new(JobDb {
+            path: path.to_path_buf(),
+            writer: Mutex::new(sender),
+            readers: Mutex::new(Vec::new()),
+        }))
+    }
+
+    /// The process-wide handle of the database at `path`, opened on first use
+    pub fn shared(
+        path: &Path,
+        init_schema: fn(&Connection) -> Result<(), TahweelError>,
+    ) -> Result<Arc<JobDb>, TahweelError> {
+        let mut shared = SHARED
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|p| p.into_inner());
+        if let Some(db) = shared.get(path) {
+            return Ok(db.clone());
+        }
+        let db = JobDb::open(path, init_schema)?;
+        shared.insert(path.to_path_buf(), db.clone());
+        Ok(db)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn queue(&self, job: WriteJob) -> Result<(), TahweelError> {
+        self.writer
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .send(job)
+            .map_err(|_| TahweelError::Internal("Job database writer stopped".to_string()))
+    }
+
+    /// Run `f` on the writer thread and wait for its result. Writes run one at
+    /// a time in the order they were queued. Async code uses `write_async`.
+    pub fn write<T, F>(&self, f: F) -> Result<T, TahweelError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, TahweelError> + Send + 'static,
+    {
+        let (reply, result) = mpsc::sync_channel(1);
+        self.queue(Box::new(move |conn| {
+            let _ = reply.send(f(conn));
+        }))?;
+        result
+            .recv()
+            .map_err(|_| TahweelError::Internal("Job database write failed".to_string()))?
+    }
+
+    /// `write` for async commands: the result comes back on a oneshot, so the
+    /// runtime worker is free while the write waits its turn
+    pub async fn write_async<T, F>(&self, f: F) -> Result<T, TahweelError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, TahweelError> + Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        self.queue(Box::new(move |conn| {
+            let _ = reply.send(f(conn));
+        }))?;
+        result
+            .await
+            .map_err(|_| TahweelError::Internal("Job database write failed".to_string()))?
+    }
+
+    /// Queue `f` on the writer thread without waiting for it, for best-effort
+    /// writes; `f` handles its own errors
+    pub fn write_detached(
+        &self,
+        f: impl FnOnce(&mut Connection) + Send + 'static,
+    ) -> Result<(), TahweelError> {
+        self.queue(Box::new(f))
+    }
+
+    /// Run `f` on a pooled read-only connection, on this thread. It sees the
+    /// last committed state; writes from it fail.
+    pub fn read<T>(
+        &self,
+        f: impl FnOnce(&Connection) -> Result<T, TahweelError>,
+    ) -> Result<T, TahweelError> {
+        let pooled = self.readers.lock().unwrap_or_else(|p| p.into_inner()).pop();
+        let conn = match pooled {
+            Some(conn) => conn,
+            None => {
+                let conn = Connection::open(&self.path).map_err(db_error)?;
+                configure(&conn)?;
+                conn.pragma_update(None, "query_only", true)
+                    .map_err(db_error)?;
+                conn
+            }
+        };
+        let result = f(&conn);
+        let mut readers = self.readers.lock().unwrap_or_else(|p| p.into_inner());
+        if readers.len() < MAX_IDLE_READERS {
+            readers.push(conn);
+        }
+        result
+    }
+
+    /// `read` for async commands, run on the blocking thread pool
+    pub async fn read_async<T, F>(self: Arc<Self>, f: F) -> Result<T, TahweelError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, TahweelError> + Send + 'static,
+    {
+        tauri::async_runtime::spawn_blocking(move || self.read(f))
+            .await
+            .map_err(|e| TahweelError::Internal(format!("Job database read failed: {}", e)))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+    use tempfile::tempdir;
+
+    fn schema(conn: &Connection) -> Result<(), TahweelError> {
+        conn.execute_batch("CREATE TABLE IF NOT EXISTS counter (id INTEGER PRIMARY KEY, n INTEGER NOT NULL); INSERT OR IGNORE INTO counter VALUES (1, 0);")
+            .map_err(db_error)
+    }
+
+    fn count(db: &JobDb) -> i64 {
+        db.read(|conn| {
+            conn.query_row("SELECT n FROM counter WHERE id = 1", [], |row| row.get(0))
+                .map_err(db_error)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_concurrent_writes_are_serialized() {
+        let dir = tempdir().unwrap();
+        let db = JobDb::open(&dir.path().join("jobs.db"), schema).unwrap();
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..25 {
+                        db.write(|conn| {
+                            // Read-modify-write: lost updates would show if writes interleaved
+                            let tx = conn.transaction().map_err(db_error)?;
+                            let n: i64 = tx
+                                .query_row("SELECT n FROM counter WHERE id = 1", [], |row| {
+                                    row.get(0)
+                                })
+                                .map_err(db_error)?;
+                            tx.execute("UPDATE counter SET n = ?1 WHERE id = 1", params![n + 1])
+                                .map_err(db_error)?;
+                            tx.commit().map_err(db_error)
+                        })
+                        .unwrap();
+                        count(&db);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(count(&db), 200);
+    }
+
+    #[test]
+    fn test_uses_wal_and_read_only_readers() {
+        let dir = tempdir().unwrap();
+        let db = JobDb::open(&dir.path().join("jobs.db"), schema).unwrap();
+        let mode: String = db
+            .write(|conn| {
+                conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))
+                    .map_err(db_error)
+            })
+            .unwrap();
+        assert_eq!(mode, "wal");
+        let write_from_reader = db.read(|conn| {
+            conn.execute("UPDATE counter SET n = 5", [])
+                .map_err(db_error)
+        });
+        assert!(write_from_reader.is_err());
+    }
+
+    #[test]
+    fn test_failed_write_keeps_the_writer() {
+        let dir = tempdir().unwrap();
+        let db = JobDb::open(&dir.path().join("jobs.db"), schema).unwrap();
+        let failed: Result<(), _> = db.write(|_| panic!("bad write"));
+        assert!(matches!(failed, Err(TahweelError::Internal(_))));
+        db.write(|conn| {
+            conn.execute("UPDATE counter SET n = 3", [])
+                .map_err(db_error)
+        })
+        .unwrap();
+        assert_eq!(count(&db), 3);
+    }
+
+    #[tokio::test]
+    async fn test_async_access_sees_queued_writes() {
+        let dir = tempdir().unwrap();
+        let db = JobDb::open(&dir.path().join("jobs.db"), schema).unwrap();
+        db.write_detached(|conn| {
+            conn.execute("UPDATE counter SET n = n + 1", []).unwrap();
+        })
+        .unwrap();
+        // Queued behind the detached write, so it sees it
+        let n = db
+            .write_async(|conn| {
+                conn.execute("UPDATE counter SET n = n * 10", [])
+                    .map_err(db_error)?;
+                conn.query_row("SELECT n FROM counter WHERE id = 1", [], |row| row.get(0))
+                    .map_err(db_error)
+            })
+            .await
+            .unwrap();
+        assert_eq!(n, 10_i64);
+        let read = db
+            .clone()
+            .read_async(|conn| {
+                conn.query_row("SELECT n FROM counter WHERE id = 1", [], |row| row.get(0))
+                    .map_err(db_error)
+            })
+            .await
+            .unwrap();
+        assert_eq!(read, 10_i64);
+    }
+
+    #[test]
+    fn test_shared_reuses_one_handle_per_path() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("jobs.db");
+        let first = JobDb::shared(&path, schema).unwrap();
+        let second = JobDb::shared(&path, schema).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.path(), path);
+    }
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Resources a job used: what it cost in CPU, memory, network and Drive quota
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// CPU time of rendering (wall time where the platform has no per-thread clock)
+    #[serde(rename = "cpuMs")]
+    pub cpu_ms: u64,
+    /// Largest estimated memory the pipeline held while the job ran (see `memory`)
+    #[serde(rename = "peakMemoryBytes")]
+    pub peak_memory_bytes: u64,
+    #[serde(rename = "bytesUploaded")]
+    pub bytes_uploaded: u64,
+    #[serde(rename = "bytesDownloaded")]
+    pub bytes_downloaded: u64,
+    /// Drive requests: upload and export attempts and conversion status polls
+    #[serde(rename = "driveApiCalls")]
+    pub drive_api_calls: u64,
+}
+
+impl ResourceUsage {
+    /// Add `other` to this usage; the peak is the larger of the two
+    pub fn merge(&mut self, other: &ResourceUsage) {
+        self.cpu_ms += other.cpu_ms;
+        self.peak_memory_bytes = self.peak_memory_bytes.max(other.peak_memory_bytes);
+        self.bytes_uploaded += other.bytes_uploaded;
+        self.bytes_downloaded += other.bytes_downloaded;
+        self.drive_api_calls += other.drive_api_calls;
+    }
+}
+
+/// Usage of running jobs not yet saved to the job database (see `jobs::end_job`)
+static PENDING: Mutex<Option<HashMap<String, ResourceUsage>>> = Mutex::new(None);
+
+fn update(job_id: &str, f: impl FnOnce(&mut ResourceUsage)) {
+    let mut pending = PENDING.lock().unwrap_or_else(|p| p.into_inner());
+    f(pending
+        .get_or_insert_with(HashMap::new)
+        .entry(job_id.to_string())
+        .or_default());
+}
+
+/// Add `ms` of CPU time to `job_id`
+pub fn add_cpu(job_id: &str, ms: u64) {
+    update(job_id, |usage| usage.cpu_ms += ms);
+}
+
+/// Note that the pipeline held an estimated `bytes` while working on `job_id`
+pub fn observe_memory(job_id: &str, bytes: u64) {
+    update(job_id, |usage| {
+        usage.peak_memory_bytes = usage.peak_memory_bytes.max(bytes)
+    });
+}
+
+/// Add a Drive operation of `job_id`: bytes sent and received and requests made
+pub fn add_transfer(job_id: &str, uploaded: u64, downloaded: u64, api_calls: u64) {
+    update(job_id, |usage| {
+        usage.bytes_uploaded += uploaded;
+        usage.bytes_downloaded += downloaded;
+        usage.drive_api_calls += api_calls;
+    });
+}
+
+/// Usage of `job_id` since it was last taken
+pub fn pending(job_id: &str) -> Option<ResourceUsage> {
+    PENDING
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .as_ref()
+        .and_then(|pending| pending.get(job_id).copied())
+}
+
+/// Remove and return the usage of `job_id` since it was last taken
+pub fn take(job_id: &str) -> Option<ResourceUsage> {
+    PENDING
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .as_mut()
+        .and_then(|pending| pending.remove(job_id))
+}
+
+/// CPU time the calling thread has used so far
+#[cfg(unix)]
+fn thread_cpu_time() -> Option<Duration> {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // Writes only to `time`
+    let result = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) };
+    (result == 0).then(|| Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+}
+
+#[cfg(not(unix))]
+fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// Measures the CPU time of work done on the current thread
+pub struct CpuTimer {
+    cpu: Option<Duration>,
+    wall: Instant,
+}
+
+impl CpuTimer {
+    pub fn start() -> Self {
+        Self {
+            cpu: thread_cpu_time(),
+            wall: Instant::now(),
+        }
+    }
+
+    /// CPU time since `start`, or wall time without a per-thread clock
+    pub fn elapsed_ms(&self) -> u64 {
+        match (self.cpu, thread_cpu_time()) {
+            (Some(start), Some(now)) => now.saturating_sub(start).as_millis() as u64,
+            _ => self.wall.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulates_until_taken() {
+        let job = uuid::Uuid::new_v4().to_string();
+        add_cpu(&job, 120);
+        add_cpu(&job, 30);
+        observe_memory(&job, 4096);
+        observe_memory(&job, 1024);
+        add_transfer(&job, 1000, 0, 2);
+        add_transfer(&job, 0, 250, 3);
+        let expected = ResourceUsage {
+            cpu_ms: 150,
+            peak_memory_bytes: 4096,
+            bytes_uploaded: 1000,
+            bytes_downloaded: 250,
+            drive_api_calls: 5,
+        };
+        assert_eq!(pending(&job), Some(expected));
+        assert_eq!(take(&job), Some(expected));
+        assert_eq!(take(&job), None);
+    }
+
+    #[test]
+    fn test_merge_keeps_the_larger_peak() {
+        let mut usage = ResourceUsage {
+            cpu_ms: 10,
+            peak_memory_bytes: 500,
+            bytes_uploaded: 1,
+            bytes_downloaded: 2,
+            drive_api_calls: 3,
+        };
+        usage.merge(&ResourceUsage {
+            cpu_ms: 5,
+            peak_memory_bytes: 200,
+            bytes_uploaded: 1,
+            bytes_downloaded: 1,
+            drive_api_calls: 1,
+        });
+        assert_eq!(usage.cpu_ms, 15);
+        assert_eq!(usage.peak_memory_bytes, 500);
+        assert_eq!(usage.bytes_uploaded, 2);
+        assert_eq!(usage.bytes_downloaded, 3);
+        assert_eq!(usage.drive_api_calls, 4);
+    }
+
+    #[test]
+    fn test_cpu_timer_counts_busy_work() {
+        let timer = CpuTimer::start();
+        let mut x = 0u64;
+        let started = Instant::now();
+        while started.elapsed() < Duration::from_millis(30) {
+            x = x.wrapping_add(std::hint::black_box(1));
+        }
+        assert!(x > 0);
+        assert!(timer.elapsed_ms() >= 10);
+    }
+}
@@ -0,0 +1,2521 @@
+use crate::app_paths::{self, Area};
+use crate::docx::{prepare_page_image, write_docx, DocxPage, DocxSection, DEFAULT_IMAGE_WIDTH};
+use crate::error::TahweelError;
+use crate::job_db::{self, db_error, JobDb};
+use crate::job_usage::{self, ResourceUsage};
+use crate::output::{
+    build_page_records, DocumentMetadata, JsonOutput, OutputFormat, PageInput, JSON_OUTPUT_VERSION,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+/// How often this process refreshes the heartbeat of its running jobs and
+/// looks for jobs left behind by a process that is gone
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// A running job of another session whose heartbeat is older than this is
+/// treated as interrupted, even when its PID is taken by another process
+const STALE_AFTER_MS: i64 = 2 * 60 * 1000;
+/// Weight of a new job in a backend's moving average of time per page
+const THROUGHPUT_WEIGHT: f64 = 0.3;
+/// Longest tag kept, in characters
+const MAX_TAG_CHARS: usize = 64;
+
+/// Identifies this process's rows in `running_jobs`
+static SESSION: OnceLock<String> = OnceLock::new();
+/// Interrupted jobs found since the frontend last collected them
+static INTERRUPTED: Mutex<Vec<InterruptedJob>> = Mutex::new(Vec::new());
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY,
+    source_path TEXT NOT NULL,
+    output_path TEXT NOT NULL,
+    formats TEXT NOT NULL,
+    page_separator TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS job_pages (
+    job_id TEXT NOT NULL,
+    page INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    PRIMARY KEY (job_id, page)
+);
+CREATE TABLE IF NOT EXISTS page_edits (
+    id INTEGER PRIMARY KEY,
+    job_id TEXT NOT NULL,
+    page INTEGER NOT NULL,
+    previous_text TEXT NOT NULL,
+    text TEXT NOT NULL,
+    edited_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS upload_sessions (
+    content_key TEXT PRIMARY KEY,
+    session_uri TEXT NOT NULL,
+    bytes_sent INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS job_events (
+    id INTEGER PRIMARY KEY,
+    job_id TEXT NOT NULL,
+    at_ms INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    page INTEGER,
+    message TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS job_events_by_job ON job_events (job_id, id);
+CREATE TABLE IF NOT EXISTS partial_pages (
+    job_id TEXT NOT NULL,
+    page INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    PRIMARY KEY (job_id, page)
+);
+CREATE TABLE IF NOT EXISTS job_stops (
+    job_id TEXT PRIMARY KEY,
+    source_path TEXT NOT NULL,
+    output_path TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    error_kind TEXT,
+    message TEXT NOT NULL,
+    at_ms INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS running_jobs (
+    job_id TEXT PRIMARY KEY,
+    source_path TEXT NOT NULL,
+    output_path TEXT NOT NULL,
+    session TEXT NOT NULL,
+    pid INTEGER NOT NULL,
+    heartbeat_ms INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS throughput_stats (
+    backend TEXT NOT NULL,
+    dpi INTEGER NOT NULL,
+    ms_per_page REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (backend, dpi)
+);
+CREATE TABLE IF NOT EXISTS quota_usage (
+    backend TEXT NOT NULL,
+    period_start INTEGER NOT NULL,
+    pages INTEGER NOT NULL,
+    api_calls INTEGER NOT NULL,
+    PRIMARY KEY (backend, period_start)
+);
+CREATE TABLE IF NOT EXISTS job_tags (
+    job_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (job_id, tag)
+);
+CREATE INDEX IF NOT EXISTS job_tags_by_tag ON job_tags (tag, job_id);
+CREATE TABLE IF NOT EXISTS job_notes (
+    job_id TEXT PRIMARY KEY,
+    note TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS job_documents (
+    job_id TEXT PRIMARY KEY,
+    docx TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS job_usage (
+    job_id TEXT PRIMARY KEY,
+    cpu_ms INTEGER NOT NULL,
+    peak_memory_bytes INTEGER NOT NULL,
+    bytes_uploaded INTEGER NOT NULL,
+    bytes_downloaded INTEGER NOT NULL,
+    drive_api_calls INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+";
+
+/// A completed job and its per-page text
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobRecord {
+    pub id: String,
+    pub source_path: String,
+    /// Output path without extension
+    pub output_path: String,
+    pub formats: Vec<OutputFormat>,
+    pub page_separator: String,
+    pub pages: Vec<String>,
+    pub docx: DocxOptions,
+}
+
+/// How a job's DOCX was written, so regenerating it keeps its properties and scans
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DocxOptions {
+    /// Core properties (the source PDF's, with the job's overrides)
+    #[serde(default)]
+    pub metadata: DocumentMetadata,
+    /// Scan embedded above each page's text (see write_docx_output), by page
+    #[serde(default, rename = "pageImages", skip_serializing_if = "Vec::is_empty")]
+    pub page_images: Vec<Option<String>>,
+    /// Displayed width of the embedded scans in pixels
+    #[serde(
+        default,
+        rename = "imageWidth",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub image_width: Option<u32>,
+}
+
+/// Corrected text for one page (1-based)
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct PageEdit {
+    pub page: u32,
+    pub text: String,
+}
+
+/// Result of `apply_corrections`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AppliedCorrections {
+    /// Pages whose text changed
+    #[serde(rename = "editedPages")]
+    pub edited_pages: Vec<u32>,
+    /// Output files that were rewritten
+    pub paths: Vec<String>,
+}
+
+/// What a job event records
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobEventKind {
+    /// The job moved to another stage (rendering, OCR, writing, done, cancelled)
+    Stage,
+    /// A request or page was tried again
+    Retry,
+    Error,
+    /// A page or step was left out (duplicate pages, ...)
+    Skip,
+}
+
+impl JobEventKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobEventKind::Stage => "stage",
+            JobEventKind::Retry => "retry",
+            JobEventKind::Error => "error",
+            JobEventKind::Skip => "skip",
+        }
+    }
+
+    fn parse(kind: &str) -> Option<Self> {
+        [Self::Stage, Self::Retry, Self::Error, Self::Skip]
+            .into_iter()
+            .find(|k| k.as_str() == kind)
+    }
+}
+
+/// One entry of a job's event log
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct JobEvent {
+    /// Milliseconds since the Unix epoch
+    #[serde(rename = "atMs")]
+    pub at_ms: i64,
+    pub kind: JobEventKind,
+    /// 1-based page the event is about, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    pub message: String,
+}
+
+/// Why a job stopped before writing its outputs
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StopReason {
+    /// The user cancelled the run
+    Cancelled,
+    /// An error ended the job
+    Failed,
+    /// The app was killed or crashed while the job was running
+    Interrupted,
+}
+
+impl StopReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            StopReason::Cancelled => "cancelled",
+            StopReason::Failed => "failed",
+            StopReason::Interrupted => "interrupted",
+        }
+    }
+
+    fn parse(reason: &str) -> Option<Self> {
+        [Self::Cancelled, Self::Failed, Self::Interrupted]
+            .into_iter()
+            .find(|r| r.as_str() == reason)
+    }
+}
+
+/// How and where a job stopped midway
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobStop {
+    pub reason: StopReason,
+    /// `kind` of the error that ended the job (`auth`, `network`, ...), if any
+    #[serde(rename = "errorKind", skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+    pub message: String,
+    #[serde(rename = "sourcePath")]
+    pub source_path: String,
+    /// Output path without extension the job would have written
+    #[serde(rename = "outputPath")]
+    pub output_path: String,
+    /// Milliseconds since the Unix epoch
+    #[serde(rename = "atMs", default)]
+    pub at_ms: i64,
+}
+
+/// Text of one page recognized before the job stopped (1-based)
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PartialPage {
+    pub page: u32,
+    pub text: String,
+}
+
+/// What a stopped (or still running) job recognized so far
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PartialResults {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    /// Missing while the job is still running
+    pub stop: Option<JobStop>,
+    pub pages: Vec<PartialPage>,
+    /// Pages before the last recognized one that have no text
+    #[serde(rename = "missingPages")]
+    pub missing_pages: Vec<u32>,
+}
+
+/// A job whose app session died while it ran, as sent with `jobs-interrupted`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct InterruptedJob {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    #[serde(rename = "sourcePath")]
+    pub source_path: String,
+    /// Output path without extension the job would have written
+    #[serde(rename = "outputPath")]
+    pub output_path: String,
+    /// Pages recognized before the session died
+    pub pages: u32,
+}
+
+/// How long a backend took per page in earlier jobs, for seeding ETAs
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ThroughputEstimate {
+    /// Moving average over jobs, recent jobs weighing more
+    #[serde(rename = "msPerPage")]
+    pub ms_per_page: f64,
+    /// Jobs the average is built from
+    pub samples: u32,
+    /// DPI the jobs ran at (0: automatic DPI); the nearest one recorded when
+    /// the requested DPI has no history
+    pub dpi: u32,
+}
+
+/// Result of `assemble_partial_results`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AssembledPartial {
+    pub paths: Vec<String>,
+    /// Pages written as empty text
+    #[serde(rename = "missingPages")]
+    pub missing_pages: Vec<u32>,
+}
+
+/// A job's tags and note, for organizing the history
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct JobLabels {
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+}
+
+/// A job carrying a searched tag. Paths are missing for a job that was tagged
+/// but has not finished (or was never recorded).
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TaggedJob {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    #[serde(rename = "sourcePath")]
+    pub source_path: Option<String>,
+    /// Output path without extension
+    #[serde(rename = "outputPath")]
+    pub output_path: Option<String>,
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+    /// Seconds since the Unix epoch the job was last recorded
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<i64>,
+}
+
+/// Everything the job database knows about one job. Fields of a job that was
+/// never recorded (still running, failed, or cancelled) are missing.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct JobDetails {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    #[serde(rename = "sourcePath")]
+    pub source_path: Option<String>,
+    /// Output path without extension
+    #[serde(rename = "outputPath")]
+    pub output_path: Option<String>,
+    pub formats: Vec<OutputFormat>,
+    #[serde(rename = "pageCount")]
+    pub page_count: u32,
+    /// Seconds since the Unix epoch the job was first and last recorded
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<i64>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<i64>,
+    pub running: bool,
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+    /// None until the job has used any measured resource
+    pub usage: Option<ResourceUsage>,
+}
+
+impl JobDetails {
+    fn new(job_id: &str) -> Self {
+        JobDetails {
+            job_id: job_id.to_string(),
+            source_path: None,
+            output_path: None,
+            formats: Vec::new(),
+            page_count: 0,
+            created_at: None,
+            updated_at: None,
+            running: false,
+            tags: Vec::new(),
+            note: None,
+            usage: None,
+        }
+    }
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Location of the job database
+pub(crate) fn jobs_db_path() -> PathBuf {
+    app_paths::scoped_dir(Area::Db).join("jobs.db")
+}
+
+/// Open (and create if needed) a standalone connection to a job database.
+/// The app goes through `db()`, which serializes writes.
+pub fn open_db(path: &Path) -> Result<Connection, TahweelError> {
+    let conn = Connection::open(path).map_err(db_error)?;
+    job_db::configure(&conn)?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+/// The app's job database, shared by every command and background task
+pub(crate) fn db() -> Result<Arc<JobDb>, TahweelError> {
+    JobDb::shared(&jobs_db_path(), init_schema)
+}
+
+pub fn init_schema(conn: &Connection) -> Result<(), TahweelError> {
+    conn.execute_batch(SCHEMA).map_err(db_error)
+}
+
+/// Store (or replace) a job's pages. Edit history of a replaced job is kept.
+pub fn record(conn: &mut Connection, job: &JobRecord) -> Result<(), TahweelError> {
+    let formats = serde_json::to_string(&job.formats)?;
+    let now = now_secs();
+    let tx = conn.transaction().map_err(db_error)?;
+    tx.execute(
+        "INSERT INTO jobs (id, source_path, output_path, formats, page_separator, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+         ON CONFLICT(id) DO UPDATE SET source_path = ?2, output_path = ?3, formats = ?4,
+             page_separator = ?5, updated_at = ?6",
+        params![
+            job.id,
+            job.source_path,
+            job.output_path,
+            formats,
+            job.page_separator,
+            now
+        ],
+    )
+    .map_err(db_error)?;
+    tx.execute("DELETE FROM job_pages WHERE job_id = ?1", params![job.id])
+        .map_err(db_error)?;
+    // The job finished, so what it saved while running is no longer needed
+    tx.execute(
+        "DELETE FROM partial_pages WHERE job_id = ?1",
+        params![job.id],
+    )
+    .and_then(|_| tx.execute("DELETE FROM job_stops WHERE job_id = ?1", params![job.id]))
+    .map_err(db_error)?;
+    {
+        let mut insert = tx
+            .prepare("INSERT INTO job_pages (job_id, page, text) VALUES (?1, ?2, ?3)")
+            .map_err(db_error)?;
+        for (i, text) in job.pages.iter().enumerate() {
+            insert
+                .execute(params![job.id, i as i64 + 1, text])
+                .map_err(db_error)?;
+        }
+    }
+    tx.execute(
+        "INSERT INTO job_documents (job_id, docx) VALUES (?1, ?2)
+         ON CONFLICT(job_id) DO UPDATE SET docx = ?2",
+        params![job.id, serde_json::to_string(&job.docx)?],
+    )
+    .map_err(db_error)?;
+    tx.commit().map_err(db_error)
+}
+
+/// Load a job and its pages
+pub fn load(conn: &Connection, job_id: &str) -> Result<Option<JobRecord>, TahweelError> {
+    let row: Option<(String, String, String, String)> = conn
+        .query_row(
+            "SELECT source_path, output_path, formats, page_separator FROM jobs WHERE id = ?1",
+            params![job_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(db_error)?;
+    let Some((source_path, output_path, formats, page_separator)) = row else {
+        return Ok(None);
+    };
+
+    let mut statement = conn
+        .prepare("SELECT text FROM job_pages WHERE job_id = ?1 ORDER BY page")
+        .map_err(db_error)?;
+    let pages = statement
+        .query_map(params![job_id], |row| row.get(0))
+        .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+        .map_err(db_error)?;
+    // Jobs recorded before their DOCX options were kept have none
+    let docx: Option<String> = conn
+        .query_row(
+            "SELECT docx FROM job_documents WHERE job_id = ?1",
+            params![job_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(db_error)?;
+
+    Ok(Some(JobRecord {
+        id: job_id.to_string(),
+        source_path,
+        output_path,
+        formats: serde_json::from_str(&formats)?,
+        page_separator,
+        pages,
+        docx: docx
+            .map(|docx| serde_json::from_str(&docx))
+            .transpose()?
+            .unwrap_or_default(),
+    }))
+}
+
+/// Apply `edits` to a stored job and record each changed page in the edit
+/// history. Returns the updated job and the pages that changed.
+pub fn apply_edits(
+    conn: &mut Connection,
+    job_id: &str,
+    edits: &[PageEdit],
+) -> Result<(JobRecord, Vec<u32>), TahweelError> {
+    let tx = conn.transaction().map_err(db_error)?;
+    let mut job = load(&tx, job_id)?
+        .ok_or_else(|| TahweelError::NotFound(format!("Job not found: {}", job_id)))?;
+
+    let now = now_secs();
+    let mut edited = Vec::new();
+    for edit in edits {
+        let index = (edit.page as usize)
+            .checked_sub(1)
+            .filter(|&i| i < job.pages.len())
+            .ok_or_else(|| {
+                TahweelError::InvalidInput(format!(
+                    "Page {} out of range (total {})",
+                    edit.page,
+                    job.pages.len()
+                ))
+            })?;
+        if job.pages[index] == edit.text {
+            continue;
+        }
+        tx.execute(
+            "INSERT INTO page_edits (job_id, page, previous_text, text, edited_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![job_id, edit.page, job.pages[index], edit.text, now],
+        )
+        .and_then(|_| {
+            tx.execute(
+                "UPDATE job_pages SET text = ?3 WHERE job_id = ?1 AND page = ?2",
+                params![job_id, edit.page, edit.text],
+            )
+        })
+        .map_err(db_error)?;
+        job.pages[index] = edit.text.clone();
+        if !edited.contains(&edit.page) {
+            edited.push(edit.page);
+        }
+    }
+
+    if !edited.is_empty() {
+        tx.execute(
+            "UPDATE jobs SET updated_at = ?2 WHERE id = ?1",
+            params![job_id, now],
+        )
+        .map_err(db_error)?;
+    }
+    tx.commit().map_err(db_error)?;
+    edited.sort_unstable();
+    Ok((job, edited))
+}
+
+/// Replace the page texts of an existing JSON output in place, keeping its other
+/// fields. Handles both the structured schema (`pages[].text`) and the simple
+/// page list (`[].content`). Returns false if the layout is not recognized.
+fn patch_json_pages(value: &mut Value, pages: &[String]) -> bool {
+    let (entries, field) = match value {
+        Value::Object(object) => match object.get_mut("pages") {
+            Some(Value::Array(entries)) => (entries, "text"),
+            _ => return false,
+        },
+        Value::Array(entries) => (entries, "content"),
+        _ => return false,
+    };
+    if entries.len() != pages.len() {
+        return false;
+    }
+    for (entry, text) in entries.iter_mut().zip(pages) {
+        match entry.as_object_mut() {
+            Some(entry) if entry.contains_key(field) => {
+                entry.insert(field.to_string(), Value::String(text.trim().to_string()));
+            }
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// A Drive resumable upload in progress, saved so it can continue after a restart
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadSession {
+    pub session_uri: String,
+    /// Bytes Drive has confirmed receiving
+    pub bytes_sent: u64,
+    pub size: u64,
+}
+
+/// The saved upload session for `content_key`, unless it was last updated more
+/// than `max_age_secs` ago (expired sessions are deleted)
+pub fn load_upload_session(
+    conn: &Connection,
+    content_key: &str,
+    max_age_secs: i64,
+) -> Result<Option<UploadSession>, TahweelError> {
+    conn.execute(
+        "DELETE FROM upload_sessions WHERE updated_at < ?1",
+        params![now_secs() - max_age_secs],
+    )
+    .map_err(db_error)?;
+    conn.query_row(
+        "SELECT session_uri, bytes_sent, size FROM upload_sessions WHERE content_key = ?1",
+        params![content_key],
+        |row| {
+            Ok(UploadSession {
+                session_uri: row.get(0)?,
+                bytes_sent: row.get::<_, i64>(1)? as u64,
+                size: row.get::<_, i64>(2)? as u64,
+            })
+        },
+    )
+    .optional()
+    .map_err(db_error)
+}
+
+/// Save (or update) the upload session for `content_key`
+pub fn save_upload_session(
+    conn: &Connection,
+    content_key: &str,
+    session: &UploadSession,
+) -> Result<(), TahweelError> {
+    conn.execute(
+        "INSERT INTO upload_sessions (content_key, session_uri, bytes_sent, size, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(content_key) DO UPDATE SET session_uri = ?2, bytes_sent = ?3, size = ?4,
+             updated_at = ?5",
+        params![
+            content_key,
+            session.session_uri,
+            session.bytes_sent as i64,
+            session.size as i64,
+            now_secs()
+        ],
+    )
+    .map_err(db_error)?;
+    Ok(())
+}
+
+/// Forget the upload session for `content_key` (finished or expired)
+pub fn delete_upload_session(conn: &Connection, content_key: &str) -> Result<(), TahweelError> {
+    conn.execute(
+        "DELETE FROM upload_sessions WHERE content_key = ?1",
+        params![content_key],
+    )
+    .map_err(db_error)?;
+    Ok(())
+}
+
+/// Append an event to `job_id`'s log
+pub fn add_event(conn: &Connection, job_id: &str, event: &JobEvent) -> Result<(), TahweelError> {
+    conn.execute(
+        "INSERT INTO job_events (job_id, at_ms, kind, page, message) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            job_id,
+            event.at_ms,
+            event.kind.as_str(),
+            event.page,
+            event.message
+        ],
+    )
+    .map_err(db_error)?;
+    Ok(())
+}
+
+/// `job_id`'s events in the order they were logged
+pub fn load_events(conn: &Connection, job_id: &str) -> Result<Vec<JobEvent>, TahweelError> {
+    let mut statement = conn
+        .prepare("SELECT at_ms, kind, page, message FROM job_events WHERE job_id = ?1 ORDER BY id")
+        .map_err(db_error)?;
+    let rows = statement
+        .query_map(params![job_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<u32>>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })
+        .map_err(db_error)?;
+
+    let mut events = Vec::new();
+    for row in rows {
+        let (at_ms, kind, page, message) = row.map_err(db_error)?;
+        // Kinds added by a later version are left out rather than failing the log
+        if let Some(kind) = JobEventKind::parse(&kind) {
+            events.push(JobEvent {
+                at_ms,
+                kind,
+                page,
+                message,
+            });
+        }
+    }
+    Ok(events)
+}
+
+/// Save the text of a page as soon as it is recognized (1-based `page`)
+pub fn save_partial_page(
+    conn: &Connection,
+    job_id: &str,
+    page: u32,
+    text: &str,
+) -> Result<(), TahweelError> {
+    conn.execute(
+        "INSERT INTO partial_pages (job_id, page, text) VALUES (?1, ?2, ?3)
+         ON CONFLICT(job_id, page) DO UPDATE SET text = ?3",
+        params![job_id, page, text],
+    )
+    .map_err(db_error)?;
+    Ok(())
+}
+
+/// Record why and where `job_id` stopped, replacing an earlier stop
+pub fn save_stop(conn: &Connection, job_id: &str, stop: &JobStop) -> Result<(), TahweelError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO job_stops
+             (job_id, source_path, output_path, reason, error_kind, message, at_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            job_id,
+            stop.source_path,
+            stop.output_path,
+            stop.reason.as_str(),
+            stop.error_kind,
+            stop.message,
+            stop.at_ms
+        ],
+    )
+    .map_err(db_error)?;
+    Ok(())
+}
+
+/// The pages `job_id` recognized so far and how it stopped, if it did
+pub fn load_partial(conn: &Connection, job_id: &str) -> Result<PartialResults, TahweelError> {
+    let row: Option<(String, Option<String>, String, String, String, i64)> = conn
+        .query_row(
+            "SELECT reason, error_kind, message, source_path, output_path, at_ms
+             FROM job_stops WHERE job_id = ?1",
+            params![job_id],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(db_error)?;
+    // Reasons added by a later version are left out like unknown event kinds
+    let stop = row.and_then(
+        |(reason, error_kind, message, source_path, output_path, at_ms)| {
+            Some(JobStop {
+                reason: StopReason::parse(&reason)?,
+                error_kind,
+                message,
+                source_path,
+                output_path,
+                at_ms,
+            })
+        },
+    );
+
+    let mut statement = conn
+        .prepare(
+            "SELECT page, text FROM partial_pages WHERE job_id = ?1 AND page > 0 ORDER BY page",
+        )
+        .map_err(db_error)?;
+    let pages = statement
+        .query_map(params![job_id], |row| {
+            Ok(PartialPage {
+                page: row.get(0)?,
+                text: row.get(1)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(db_error)?;
+
+    let last = pages.last().map(|p| p.page).unwrap_or(0);
+    let missing_pages = (1..=last)
+        .filter(|page| !pages.iter().any(|p| p.page == *page))
+        .collect();
+
+    Ok(PartialResults {
+        job_id: job_id.to_string(),
+        stop,
+        pages,
+        missing_pages,
+    })
+}
+
+/// Write a stopped job's recognized pages as `<output>-partial.<ext>`, with its
+/// missing pages left empty so page numbers still line up
+pub fn assemble_partial(
+    partial: &PartialResults,
+    formats: Vec<OutputFormat>,
+    page_separator: String,
+) -> Result<AssembledPartial, TahweelError> {
+    let stop = partial.stop.as_ref().ok_or_else(|| {
+        TahweelError::InvalidInput(format!("Job {} has not stopped", partial.job_id))
+    })?;
+    if partial.pages.is_empty() {
+        return Err(TahweelError::NotFound(format!(
+            "No pages were recognized before job {} stopped",
+            partial.job_id
+        )));
+    }
+
+    let mut pages = vec![String::new(); partial.missing_pages.len() + partial.pages.len()];
+    for page in &partial.pages {
+        pages[page.page as usize - 1] = page.text.clone();
+    }
+    let paths = regenerate_outputs(&JobRecord {
+        id: partial.job_id.clone(),
+        source_path: stop.source_path.clone(),
+        output_path: format!("{}-partial", stop.output_path),
+        formats,
+        page_separator,
+        pages,
+        docx: DocxOptions::default(),
+    })?;
+    Ok(AssembledPartial {
+        paths,
+        missing_pages: partial.missing_pages.clone(),
+    })
+}
+
+/// Log an event for `job_id` (when there is one) in the job database. Best
+/// effort: a database error is only traced, never fails the pipeline.
+pub(crate) fn log_event(
+    job_id: Option<&str>,
+    kind: JobEventKind,
+    page: Option<u32>,
+    message: impl Into<String>,
+) {
+    let Some(job_id) = job_id else {
+        return;
+    };
+    let event = JobEvent {
+        at_ms: now_millis(),
+        kind,
+        page,
+        message: message.into(),
+    };
+    let job_id = job_id.to_string();
+    // Queued without waiting, so logging never holds up the caller
+    let queued = db().and_then(|db| {
+        db.write_detached(move |conn| {
+            if let Err(error) = add_event(conn, &job_id, &event) {
+                tracing::warn!(%error, "Failed to log job event");
+            }
+        })
+    });
+    if let Err(error) = queued {
+        tracing::warn!(%error, "Failed to log job event");
+    }
+}
+
+/// Mark `job_id` as running in `session` (process `pid`)
+pub fn mark_running(
+    conn: &Connection,
+    job_id: &str,
+    source_path: &str,
+    output_path: &str,
+    session: &str,
+    pid: u32,
+    now_ms: i64,
+) -> Result<(), TahweelError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO running_jobs
+             (job_id, source_path, output_path, session, pid, heartbeat_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![job_id, source_path, output_path, session, pid, now_ms],
+    )
+    .map_err(db_error)?;
+    Ok(())
+}
+
+/// Whether any job is marked running, in this or another session
+pub fn has_running(conn: &Connection) -> Result<bool, TahweelError> {
+    conn.query_row("SELECT EXISTS (SELECT 1 FROM running_jobs)", [], |row| {
+        row.get(0)
+    })
+    .map_err(db_error)
+}
+
+/// Ids of completed jobs, least recently recorded first; with `before_secs`,
+/// only those last recorded before it
+pub fn jobs_oldest_first(
+    conn: &Connection,
+    before_secs: Option<i64>,
+) -> Result<Vec<String>, TahweelError> {
+    let mut statement = conn
+        .prepare("SELECT id FROM jobs WHERE updated_at < ?1 ORDER BY updated_at, id")
+        .map_err(db_error)?;
+    statement
+        .query_map(params![before_secs.unwrap_or(i64::MAX)], |row| row.get(0))
+        .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+        .map_err(db_error)
+}
+
+/// Delete completed jobs with everything kept for them: pages, edit history,
+/// event log, tags, note and DOCX options
+pub fn delete_jobs(conn: &mut Connection, job_ids: &[String]) -> Result<(), TahweelError> {
+    let tx = conn.transaction().map_err(db_error)?;
+    for job_id in job_ids {
+        for table in [
+            "job_pages",
+            "page_edits",
+            "job_events",
+            "job_tags",
+            "job_notes",
+            "job_usage",
+            "job_documents",
+        ] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE job_id = ?1", table),
+                params![job_id],
+            )
+            .map_err(db_error)?;
+        }
+        tx.execute("DELETE FROM jobs WHERE id = ?1", params![job_id])
+            .map_err(db_error)?;
+    }
+    tx.commit().map_err(db_error)
+}
+
+/// Delete event log entries older than `before_ms` of jobs that were never
+/// recorded (failed or discarded ones); returns how many were deleted
+pub fn delete_orphan_events(conn: &Connection, before_ms: i64) -> Result<usize, TahweelError> {
+    conn.execute(
+        "DELETE FROM job_events WHERE at_ms < ?1
+             AND job_id NOT IN (SELECT id FROM jobs)
+             AND job_id NOT IN (SELECT job_id FROM running_jobs)
+             AND job_id NOT IN (SELECT job_id FROM job_stops)",
+        params![before_ms],
+    )
+    .map_err(db_error)
+}
+
+/// `job_id` is no longer running (it finished, stopped, or was discarded)
+pub fn clear_running(conn: &Connection, job_id: &str) -> Result<(), TahweelError> {
+    conn.execute(
+        "DELETE FROM running_jobs WHERE job_id = ?1",
+        params![job_id],
+    )
+    .map_err(db_error)?;
+    Ok(())
+}
+
+/// Refresh the heartbeat of `session`'s running jobs
+pub fn heartbeat(conn: &Connection, session: &str, now_ms: i64) -> Result<(), TahweelError> {
+    conn.execute(
+        "UPDATE running_jobs SET heartbeat_ms = ?2 WHERE session = ?1",
+        params![session, now_ms],
+    )
+    .map_err(db_error)?;
+    Ok(())
+}
+
+/// Stop the running jobs of other sessions whose process is gone (`is_alive`
+/// returns `Some(false)`; `None` when it cannot tell) or whose heartbeat is
+/// stale, recording them as interrupted so their recognized pages can still
+/// be assembled
+pub fn interrupt_stale(
+    conn: &mut Connection,
+    session: &str,
+    now_ms: i64,
+    is_alive: impl Fn(u32) -> Option<bool>,
+) -> Result<Vec<InterruptedJob>, TahweelError> {
+    let tx = conn.transaction().map_err(db_error)?;
+    let rows: Vec<(String, String, String, u32, i64)> = {
+        let mut statement = tx
+            .prepare(
+                "SELECT job_id, source_path, output_path, pid, heartbeat_ms
+                 FROM running_jobs WHERE session != ?1 ORDER BY heartbeat_ms",
+            )
+            .map_err(db_error)?;
+        let rows = statement
+            .query_map(params![session], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(db_error)?;
+        rows
+    };
+
+    let mut interrupted = Vec::new();
+    for (job_id, source_path, output_path, pid, heartbeat_ms) in rows {
+        let stale = now_ms - heartbeat_ms > STALE_AFTER_MS;
+        if !stale && is_alive(pid) != Some(false) {
+            continue;
+        }
+        save_stop(
+            &tx,
+            &job_id,
+            &JobStop {
+                reason: StopReason::Interrupted,
+                error_kind: None,
+                message: "Tahweel closed while the job was running".to_string(),
+                source_path: source_path.clone(),
+                output_path: output_path.clone(),
+                at_ms: now_ms,
+            },
+        )?;
+        clear_running(&tx, &job_id)?;
+        let pages: u32 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM partial_pages WHERE job_id = ?1 AND page > 0",
+                params![job_id],
+                |row| row.get(0),
+            )
+            .map_err(db_error)?;
+        interrupted.push(InterruptedJob {
+            job_id,
+            source_path,
+            output_path,
+            pages,
+        });
+    }
+    tx.commit().map_err(db_error)?;
+    Ok(interrupted)
+}
+
+/// Forget everything kept for a job that did not finish: its recognized
+/// pages, how it stopped, and its running mark
+pub fn discard(conn: &Connection, job_id: &str) -> Result<(), TahweelError> {
+    conn.execute(
+        "DELETE FROM partial_pages WHERE job_id = ?1",
+        params![job_id],
+    )
+    .and_then(|_| conn.execute("DELETE FROM job_stops WHERE job_id = ?1", params![job_id]))
+    .map_err(db_error)?;
+    clear_running(conn, job_id)
+}
+
+/// Tags as stored: trimmed, inner whitespace collapsed, lowercase, at most
+/// `MAX_TAG_CHARS` characters, without empties or duplicates (first one kept)
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag: String = tag
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+            .chars()
+            .take(MAX_TAG_CHARS)
+            .collect();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// Replace a job's tags; returns them as stored
+pub fn set_tags(
+    conn: &mut Connection,
+    job_id: &str,
+    tags: &[String],
+) -> Result<Vec<String>, TahweelError> {
+    let tags = normalize_tags(tags);
+    let tx = conn.transaction().map_err(db_error)?;
+    tx.execute("DELETE FROM job_tags WHERE job_id = ?1", params![job_id])
+        .map_err(db_error)?;
+    {
+        let mut insert = tx
+            .prepare("INSERT INTO job_tags (job_id, tag) VALUES (?1, ?2)")
+            .map_err(db_error)?;
+        for tag in &tags {
+            insert.execute(params![job_id, tag]).map_err(db_error)?;
+        }
+    }
+    tx.commit().map_err(db_error)?;
+    Ok(tags)
+}
+
+/// Set a job's note; a blank note removes it
+pub fn set_note(conn: &Connection, job_id: &str, note: &str) -> Result<(), TahweelError> {
+    let note = note.trim();
+    if note.is_empty() {
+        conn.execute("DELETE FROM job_notes WHERE job_id = ?1", params![job_id])
+    } else {
+        conn.execute(
+            "INSERT INTO job_notes (job_id, note, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(job_id) DO UPDATE SET note = ?2, updated_at = ?3",
+            params![job_id, note, now_secs()],
+        )
+    }
+    .map_err(db_error)?;
+    Ok(())
+}
+
+/// A job's tags (alphabetical) and note
+pub fn load_labels(conn: &Connection, job_id: &str) -> Result<JobLabels, TahweelError> {
+    let mut statement = conn
+        .prepare("SELECT tag FROM job_tags WHERE job_id = ?1 ORDER BY tag")
+        .map_err(db_error)?;
+    let tags = statement
+        .query_map(params![job_id], |row| row.get(0))
+        .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+        .map_err(db_error)?;
+    let note = conn
+        .query_row(
+            "SELECT note FROM job_notes WHERE job_id = ?1",
+            params![job_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(db_error)?;
+    Ok(JobLabels { tags, note })
+}
+
+/// Jobs tagged `tag` (matched as stored, see `normalize_tags`), most recently
+/// recorded first; jobs never recorded come last
+pub fn search_by_tag(conn: &Connection, tag: &str) -> Result<Vec<TaggedJob>, TahweelError> {
+    let Some(tag) = normalize_tags(&[tag.to_string()]).pop() else {
+        return Err(TahweelError::InvalidInput("Empty tag".to_string()));
+    };
+    let mut statement = conn
+        .prepare(
+            "SELECT job_tags.job_id, jobs.source_path, jobs.output_path, jobs.updated_at
+             FROM job_tags LEFT JOIN jobs ON jobs.id = job_tags.job_id
+             WHERE job_tags.tag = ?1
+             ORDER BY jobs.updated_at IS NULL, jobs.updated_at DESC, job_tags.job_id",
+        )
+        .map_err(db_error)?;
+    let rows = statement
+        .query_map(params![tag], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<(String, _, _, _)>, _>>())
+        .map_err(db_error)?;
+    rows.into_iter()
+        .map(|(job_id, source_path, output_path, updated_at)| {
+            let JobLabels { tags, note } = load_labels(conn, &job_id)?;
+            Ok(TaggedJob {
+                job_id,
+                source_path,
+                output_path,
+                tags,
+                note,
+                updated_at,
+            })
+        })
+        .collect()
+}
+
+/// Add `usage` to what is saved for `job_id` (a job's shards and reruns add up)
+pub fn save_usage(
+    conn: &Connection,
+    job_id: &str,
+    usage: &ResourceUsage,
+) -> Result<(), TahweelError> {
+    conn.execute(
+        "INSERT INTO job_usage (job_id, cpu_ms, peak_memory_bytes, bytes_uploaded,
+             bytes_downloaded, drive_api_calls, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(job_id) DO UPDATE SET cpu_ms = cpu_ms + ?2,
+             peak_memory_bytes = MAX(peak_memory_bytes, ?3),
+             bytes_uploaded = bytes_uploaded + ?4, bytes_downloaded = bytes_downloaded + ?5,
+             drive_api_calls = drive_api_calls + ?6, updated_at = ?7",
+        params![
+            job_id,
+            usage.cpu_ms as i64,
+            usage.peak_memory_bytes as i64,
+            usage.bytes_uploaded as i64,
+            usage.bytes_downloaded as i64,
+            usage.drive_api_calls as i64,
+            now_secs(),
+        ],
+    )
+    .map_err(db_error)?;
+    Ok(())
+}
+
+pub fn load_usage(conn: &Connection, job_id: &str) -> Result<Option<ResourceUsage>, TahweelError> {
+    conn.query_row(
+        "SELECT cpu_ms, peak_memory_bytes, bytes_uploaded, bytes_downloaded, drive_api_calls
+         FROM job_usage WHERE job_id = ?1",
+        params![job_id],
+        |row| {
+            Ok(ResourceUsage {
+                cpu_ms: row.get::<_, i64>(0)? as u64,
+                peak_memory_bytes: row.get::<_, i64>(1)? as u64,
+                bytes_uploaded: row.get::<_, i64>(2)? as u64,
+                bytes_downloaded: row.get::<_, i64>(3)? as u64,
+                drive_api_calls: row.get::<_, i64>(4)? as u64,
+            })
+        },
+    )
+    .optional()
+    .map_err(db_error)
+}
+
+/// What the database knows about `job_id`; None for a job it has never seen
+pub fn load_details(conn: &Connection, job_id: &str) -> Result<Option<JobDetails>, TahweelError> {
+    let row: Option<(String, String, String, i64, i64)> = conn
+        .query_row(
+            "SELECT source_path, output_path, formats, created_at, updated_at FROM jobs WHERE id = ?1",
+            params![job_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .optional()
+        .map_err(db_error)?;
+    let page_count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM job_pages WHERE job_id = ?1",
+            params![job_id],
+            |row| row.get(0),
+        )
+        .map_err(db_error)?;
+    let running: Option<(String, String)> = conn
+        .query_row(
+            "SELECT source_path, output_path FROM running_jobs WHERE job_id = ?1",
+            params![job_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(db_error)?;
+    let JobLabels { tags, note } = load_labels(conn, job_id)?;
+    let usage = load_usage(conn, job_id)?;
+    if row.is_none() && running.is_none() && usage.is_none() && tags.is_empty() && note.is_none() {
+        return Ok(None);
+    }
+
+    let mut details = JobDetails {
+        page_count: page_count as u32,
+        running: running.is_some(),
+        tags,
+        note,
+        usage,
+        ..JobDetails::new(job_id)
+    };
+    if let Some((source_path, output_path, formats, created_at, updated_at)) = row {
+        details.source_path = Some(source_path);
+        details.output_path = Some(output_path);
+        details.formats = serde_json::from_str(&formats)?;
+        details.created_at = Some(created_at);
+        details.updated_at = Some(updated_at);
+    } else if let Some((source_path, output_path)) = running {
+        details.source_path = Some(source_path);
+        details.output_path = Some(output_path);
+    }
+    Ok(Some(details))
+}
+
+/// Add to `backend`'s usage in the budget day starting at `period_start`
+/// (seconds); returns the day's pages and API calls so far. Earlier days are
+/// forgotten.
+pub fn add_quota_usage(
+    conn: &mut Connection,
+    backend: &str,
+    period_start: i64,
+    pages: u64,
+    api_calls: u64,
+) -> Result<(u64, u64), TahweelError> {
+    let tx = conn.transaction().map_err(db_error)?;
+    tx.execute(
+        "DELETE FROM quota_usage WHERE backend = ?1 AND period_start < ?2",
+        params![backend, period_start],
+    )
+    .map_err(db_error)?;
+    tx.execute(
+        "INSERT INTO quota_usage (backend, period_start, pages, api_calls) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(backend, period_start) DO UPDATE SET pages = pages + ?3,
+             api_calls = api_calls + ?4",
+        params![backend, period_start, pages as i64, api_calls as i64],
+    )
+    .map_err(db_error)?;
+    let usage = load_quota_usage(&tx, backend, period_start)?;
+    tx.commit().map_err(db_error)?;
+    Ok(usage)
+}
+
+/// Pages and API calls of `backend` in the budget day starting at `period_start`
+pub fn load_quota_usage(
+    conn: &Connection,
+    backend: &str,
+    period_start: i64,
+) -> Result<(u64, u64), TahweelError> {
+    let usage: Option<(i64, i64)> = conn
+        .query_row(
+            "SELECT pages, api_calls FROM quota_usage WHERE backend = ?1 AND period_start = ?2",
+            params![backend, period_start],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(db_error)?;
+    Ok(usage.map_or((0, 0), |(pages, calls)| (pages as u64, calls as u64)))
+}
+
+/// Fold a finished job's time per page into the backend's history at `dpi`
+pub fn record_throughput(
+    conn: &Connection,
+    backend: &str,
+    dpi: u32,
+    pages: u32,
+    elapsed_ms: u64,
+) -> Result<ThroughputEstimate, TahweelError> {
+    if pages == 0 {
+        return Err(TahweelError::InvalidInput(
+            "A job without pages has no throughput".to_string(),
+        ));
+    }
+    let observed = elapsed_ms as f64 / pages as f64;
+    let previous: Option<(f64, u32)> = conn
+        .query_row(
+            "SELECT ms_per_page, samples FROM throughput_stats WHERE backend = ?1 AND dpi = ?2",
+            params![backend, dpi],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(db_error)?;
+    let (ms_per_page, samples) = match previous {
+        Some((average, samples)) => (
+            average + THROUGHPUT_WEIGHT * (observed - average),
+            samples + 1,
+        ),
+        None => (observed, 1),
+    };
+    conn.execute(
+        "INSERT OR REPLACE INTO throughput_stats (backend, dpi, ms_per_page, samples, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![backend, dpi, ms_per_page, samples, now_secs()],
+    )
+    .map_err(db_error)?;
+    Ok(ThroughputEstimate {
+        ms_per_page,
+        samples,
+        dpi,
+    })
+}
+
+/// The backend's time per page at `dpi`, or at the nearest DPI it has history for
+pub fn throughput_estimate(
+    conn: &Connection,
+    backend: &str,
+    dpi: u32,
+) -> Result<Option<ThroughputEstimate>, TahweelError> {
+    conn.query_row(
+        "SELECT ms_per_page, samples, dpi FROM throughput_stats WHERE backend = ?1
+         ORDER BY ABS(dpi - ?2), dpi LIMIT 1",
+        params![backend, dpi],
+        |row| {
+            Ok(ThroughputEstimate {
+                ms_per_page: row.get(0)?,
+                samples: row.get(1)?,
+                dpi: row.get(2)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(db_error)
+}
+
+/// Whether process `pid` still exists; `None` where that cannot be checked
+#[cfg(unix)]
+fn process_alive(pid: u32) -> Option<bool> {
+    // Signal 0 only checks that the process exists and may be signalled
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    Some(result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM))
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> Option<bool> {
+    None
+}
+
+fn session() -> &'static str {
+    SESSION.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Refresh this session's heartbeat and record the jobs of dead sessions as
+/// interrupted, queueing them for `take_interrupted_jobs`
+fn check_running_jobs(app: &AppHandle) -> Result<(), TahweelError> {
+    let now = now_millis();
+    let interrupted = db()?.write(move |conn| {
+        heartbeat(conn, session(), now)?;
+        interrupt_stale(conn, session(), now, process_alive)
+    })?;
+    if interrupted.is_empty() {
+        return Ok(());
+    }
+    tracing::warn!(
+        count = interrupted.len(),
+        "Found jobs interrupted by an earlier session"
+    );
+    INTERRUPTED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .extend(interrupted.iter().cloned());
+    let _ = app.emit("jobs-interrupted", &interrupted);
+    Ok(())
+}
+
+/// Look for jobs left running by a killed or crashed session now and then
+/// every `HEARTBEAT_INTERVAL`, keeping this session's jobs alive; call once
+/// from `setup`
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name("job-heartbeat".to_string())
+        .spawn(move || loop {
+            if let Err(error) = check_running_jobs(&app) {
+                tracing::warn!(%error, "Failed to check running jobs");
+            }
+            std::thread::sleep(HEARTBEAT_INTERVAL);
+        });
+    if let Err(error) = spawned {
+        tracing::warn!(%error, "Failed to start the job heartbeat");
+    }
+}
+
+/// The job's pages for its DOCX, with the scans it was written with. A scan
+/// that can no longer be read leaves its page text-only.
+fn docx_pages(job: &JobRecord) -> Vec<DocxPage> {
+    let image_width = job.docx.image_width.unwrap_or(DEFAULT_IMAGE_WIDTH);
+    job.pages
+        .iter()
+        .enumerate()
+        .map(|(i, text)| {
+            let image = job
+                .docx
+                .page_images
+                .get(i)
+                .and_then(Option::as_deref)
+                .and_then(|path| match prepare_page_image(path, image_width) {
+                    Ok(image) => Some(image),
+                    Err(error) => {
+                        tracing::warn!(%error, "Page scan left out of the regenerated DOCX");
+                        None
+                    }
+                });
+            DocxPage {
+                image,
+                ..DocxPage::text(text.as_str())
+            }
+        })
+        .collect()
+}
+
+/// Rewrite the job's outputs in its selected formats from its current pages
+pub fn regenerate_outputs(job: &JobRecord) -> Result<Vec<String>, TahweelError> {
+    let mut paths = Vec::new();
+
+    if job.formats.contains(&OutputFormat::Txt) {
+        let texts: Vec<&str> = job.pages.iter().map(|p| p.trim()).collect();
+        let path = format!("{}.txt", job.output_path);
+        fs::write(&path, texts.join(&job.page_separator))?;
+        paths.push(path);
+    }
+
+    if job.formats.contains(&OutputFormat::Docx) {
+        let path = format!("{}.docx", job.output_path);
+        write_docx(
+            &path,
+            &[DocxSection {
+                heading: None,
+                pages: docx_pages(job),
+            }],
+            &job.docx.metadata,
+        )
+        .map_err(TahweelError::Io)?;
+        paths.push(path);
+    }
+
+    if job.formats.contains(&OutputFormat::Json) {
+        let path = format!("{}.json", job.output_path);
+        let patched = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .and_then(|mut value| patch_json_pages(&mut value, &job.pages).then_some(value));
+        let json = match patched {
+            Some(value) => serde_json::to_string_pretty(&value)?,
+            _ => {
+                let records = build_page_records(
+                    job.pages
+                        .iter()
+                        .map(|text| PageInput {
+                            text: text.clone(),
+                            source_page: None,
+                            image_path: None,
+                            backend: None,
+                            retries: 0,
+                            timing: Default::default(),
+                            tables: Vec::new(),
+                            quran: Vec::new(),
+                            language: None,
+                            footnotes: None,
+                        })
+                        .collect(),
+                );
+                serde_json::to_string_pretty(&JsonOutput {
+                    version: JSON_OUTPUT_VERSION,
+                    source: job.source_path.clone(),
+                    page_count: records.len() as u32,
+                    pages: records,
+                })?
+            }
+        };
+        fs::write(&path, json)?;
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
+/// Remember a completed job's pages (and how its DOCX was written) so later
+/// corrections can regenerate its outputs
+#[tauri::command]
+#[tracing::instrument(skip(pages, docx), err)]
+pub async fn record_job(
+    job_id: String,
+    source_path: String,
+    output_path: String,
+    formats: Vec<OutputFormat>,
+    page_separator: String,
+    pages: Vec<String>,
+    docx: Option<DocxOptions>,
+) -> Result<(), TahweelError> {
+    let job = JobRecord {
+        id: job_id,
+        source_path,
+        output_path,
+        formats,
+        page_separator,
+        pages,
+        docx: docx.unwrap_or_default(),
+    };
+    db()?.write_async(move |conn| record(conn, &job)).await
+}
+
+/// Save proofread text back into a job: update its stored pages, record the
+/// edits, and rewrite its outputs in the formats it was produced in
+#[tauri::command]
+#[tracing::instrument(skip(page_edits), err)]
+pub async fn apply_corrections(
+    job_id: String,
+    page_edits: Vec<PageEdit>,
+) -> Result<AppliedCorrections, TahweelError> {
+    let (job, edited_pages) = db()?
+        .write_async(move |conn| apply_edits(conn, &job_id, &page_edits))
+        .await?;
+    let paths = if edited_pages.is_empty() {
+        Vec::new()
+    } else {
+        tauri::async_runtime::spawn_blocking(move || regenerate_outputs(&job))
+            .await
+            .map_err(|e| TahweelError::Internal(format!("Rewriting outputs failed: {}", e)))??
+    };
+    Ok(AppliedCorrections {
+        edited_pages,
+        paths,
+    })
+}
+
+/// Add an event from the webview's pipeline (stage changes, skipped pages, ...)
+/// to the job's log
+#[tauri::command]
+pub async fn record_job_event(
+    job_id: String,
+    kind: JobEventKind,
+    page: Option<u32>,
+    message: String,
+) -> Result<(), TahweelError> {
+    let event = JobEvent {
+        at_ms: now_millis(),
+        kind,
+        page,
+        message,
+    };
+    db()?
+        .write_async(move |conn| add_event(conn, &job_id, &event))
+        .await
+}
+
+/// A job's event log (stages, retries, errors, skips) for its timeline view
+#[tauri::command]
+pub async fn get_job_events(job_id: String) -> Result<Vec<JobEvent>, TahweelError> {
+    db()?
+        .read_async(move |conn| load_events(conn, &job_id))
+        .await
+}
+
+/// Save a page's text as soon as it is recognized, so a job that is cancelled
+/// or fails midway keeps what it already OCR'd
+#[tauri::command]
+#[tracing::instrument(skip(text), err)]
+pub async fn record_partial_page(
+    job_id: String,
+    page: u32,
+    text: String,
+) -> Result<(), TahweelError> {
+    if page == 0 {
+        return Err(TahweelError::InvalidInput(
+            "Pages are numbered from 1".to_string(),
+        ));
+    }
+    db()?
+        .write_async(move |conn| save_partial_page(conn, &job_id, page, &text))
+        .await
+}
+
+/// Record why a job stopped before writing its outputs and where they would have gone
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn record_job_stop(job_id: String, stop: JobStop) -> Result<(), TahweelError> {
+    let stop = JobStop {
+        at_ms: now_millis(),
+        ..stop
+    };
+    db()?
+        .write_async(move |conn| save_stop(conn, &job_id, &stop))
+        .await
+}
+
+/// Mark a job as running in this session, so it is reported as interrupted
+/// if the app dies before `end_job`
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn begin_job(
+    job_id: String,
+    source_path: String,
+    output_path: String,
+) -> Result<(), TahweelError> {
+    let now = now_millis();
+    db()?
+        .write_async(move |conn| {
+            mark_running(
+                conn,
+                &job_id,
+                &source_path,
+                &output_path,
+                session(),
+                std::process::id(),
+                now,
+            )
+        })
+        .await
+}
+
+/// A job started with `begin_job` ended (finished, failed, or cancelled); the
+/// resources it used are saved to its history
+#[tauri::command]
+pub async fn end_job(job_id: String) -> Result<(), TahweelError> {
+    let usage = job_usage::take(&job_id);
+    db()?
+        .write_async(move |conn| {
+            if let Some(usage) = usage {
+                save_usage(conn, &job_id, &usage)?;
+            }
+            clear_running(conn, &job_id)
+        })
+        .await
+}
+
+/// Add a finished job's OCR time to the backend's throughput history
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn record_job_throughput(
+    backend: String,
+    dpi: u32,
+    pages: u32,
+    elapsed_ms: u64,
+) -> Result<ThroughputEstimate, TahweelError> {
+    db()?
+        .write_async(move |conn| record_throughput(conn, &backend, dpi, pages, elapsed_ms))
+        .await
+}
+
+/// Time per page from earlier jobs of `backend` near `dpi`; None without history
+#[tauri::command]
+pub async fn get_throughput_estimate(
+    backend: String,
+    dpi: u32,
+) -> Result<Option<ThroughputEstimate>, TahweelError> {
+    db()?
+        .read_async(move |conn| throughput_estimate(conn, &backend, dpi))
+        .await
+}
+
+/// Interrupted jobs found since the last call (see `jobs-interrupted`)
+#[tauri::command]
+pub async fn take_interrupted_jobs() -> Vec<InterruptedJob> {
+    std::mem::take(&mut *INTERRUPTED.lock().unwrap_or_else(|p| p.into_inner()))
+}
+
+/// Clean up after a job that did not finish: drop its recognized pages and stop record
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn discard_job(job_id: String) -> Result<(), TahweelError> {
+    db()?.write_async(move |conn| discard(conn, &job_id)).await
+}
+
+/// Replace a job's tags (e.g. a collection or funding project); returns them
+/// as stored, normalized
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn set_job_tags(job_id: String, tags: Vec<String>) -> Result<Vec<String>, TahweelError> {
+    db()?
+        .write_async(move |conn| set_tags(conn, &job_id, &tags))
+        .await
+}
+
+/// Set a job's free-text note; a blank note removes it
+#[tauri::command]
+#[tracing::instrument(skip(note), err)]
+pub async fn set_job_note(job_id: String, note: String) -> Result<(), TahweelError> {
+    db()?
+        .write_async(move |conn| set_note(conn, &job_id, &note))
+        .await
+}
+
+/// A job's tags and note
+#[tauri::command]
+pub async fn get_job_labels(job_id: String) -> Result<JobLabels, TahweelError> {
+    db()?
+        .read_async(move |conn| load_labels(conn, &job_id))
+        .await
+}
+
+/// Jobs in the history carrying `tag`
+#[tauri::command]
+pub async fn search_jobs_by_tag(tag: String) -> Result<Vec<TaggedJob>, TahweelError> {
+    db()?
+        .read_async(move |conn| search_by_tag(conn, &tag))
+        .await
+}
+
+/// A job's paths, page count, labels and resource usage (including what a
+/// running job has used so far)
+#[tauri::command]
+pub async fn get_job_details(job_id: String) -> Result<JobDetails, TahweelError> {
+    let pending = job_usage::pending(&job_id);
+    let id = job_id.clone();
+    let details = db()?
+        .read_async(move |conn| load_details(conn, &id))
+        .await?;
+    let mut details = match (details, pending) {
+        (Some(details), _) => details,
+        // Started without `begin_job`, nothing saved yet
+        (None, Some(_)) => JobDetails {
+            running: true,
+            ..JobDetails::new(&job_id)
+        },
+        (None, None) => {
+            return Err(TahweelError::NotFound(format!("Job not found: {}", job_id)));
+        }
+    };
+    if let Some(pending) = pending {
+        details
+            .usage
+            .get_or_insert_with(Default::default)
+            .merge(&pending);
+    }
+    Ok(details)
+}
+
+/// The per-page texts a job completed so far and why it stopped
+#[tauri::command]
+pub async fn get_partial_results(job_id: String) -> Result<PartialResults, TahweelError> {
+    db()?
+        .read_async(move |conn| load_partial(conn, &job_id))
+        .await
+}
+
+/// Write the outputs of a stopped job from the pages it completed
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn assemble_partial_results(
+    job_id: String,
+    formats: Vec<OutputFormat>,
+    page_separator: String,
+) -> Result<AssembledPartial, TahweelError> {
+    let partial = db()?
+        .read_async(move |conn| load_partial(conn, &job_id))
+        .await?;
+    assemble_partial(&partial, formats, page_separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        conn
+    }
+
+    fn job(output_path: &str, formats: Vec<OutputFormat>) -> JobRecord {
+        JobRecord {
+            id: "job-1".to_string(),
+            source_path: "/books/kitab.pdf".to_string(),
+            output_path: output_path.to_string(),
+            formats,
+            page_separator: "\n---\n".to_string(),
+            pages: vec!["الصفحه الاولى".to_string(), "الصفحة الثانية".to_string()],
+            docx: DocxOptions::default(),
+        }
+    }
+
+    fn edit_count(conn: &Connection, job_id: &str) -> u32 {
+        conn.query_row(
+            "SELECT COUNT(*) FROM page_edits WHERE job_id = ?1",
+            params![job_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    fn edit(page: u32, text: &str) -> PageEdit {
+        PageEdit {
+            page,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_job_events_round_trip_in_order() {
+        let conn = db();
+        let event = |at_ms, kind, page: Option<u32>, message: &str| JobEvent {
+            at_ms,
+            kind,
+            page,
+            message: message.to_string(),
+        };
+        add_event(&conn, "job-1", &event(20, JobEventKind::Stage, None, "ocr")).unwrap();
+        add_event(&conn, "job-2", &event(15, JobEventKind::Stage, None, "ocr")).unwrap();
+        add_event(
+            &conn,
+            "job-1",
+            &event(10, JobEventKind::Retry, Some(3), "Upload retried"),
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO job_events (job_id, at_ms, kind, message) VALUES ('job-1', 30, 'future', '')",
+            [],
+        )
+        .unwrap();
+
+        let events = load_events(&conn, "job-1").unwrap();
+        assert_eq!(
+            events,
+            vec![
+                event(20, JobEventKind::Stage, None, "ocr"),
+                event(10, JobEventKind::Retry, Some(3), "Upload retried"),
+            ]
+        );
+        assert!(load_events(&conn, "missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_job_event_serialization() {
+        let json = serde_json::to_value(JobEvent {
+            at_ms: 5,
+            kind: JobEventKind::Skip,
+            page: Some(2),
+            message: "Duplicate of page 1".to_string(),
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"atMs": 5, "kind": "skip", "page": 2, "message": "Duplicate of page 1"})
+        );
+        let kind: JobEventKind = serde_json::from_str("\"error\"").unwrap();
+        assert_eq!(kind, JobEventKind::Error);
+    }
+
+    #[test]
+    fn test_upload_session_round_trip() {
+        let conn = db();
+        assert_eq!(load_upload_session(&conn, "abc:png", 3600).unwrap(), None);
+
+        let mut session = UploadSession {
+            session_uri: "https://upload.example/session/1".to_string(),
+            bytes_sent: 0,
+            size: 3_000_000,
+        };
+        save_upload_session(&conn, "abc:png", &session).unwrap();
+        session.bytes_sent = 1_048_576;
+        save_upload_session(&conn, "abc:png", &session).unwrap();
+        assert_eq!(
+            load_upload_session(&conn, "abc:png", 3600).unwrap(),
+            Some(session)
+        );
+
+        delete_upload_session(&conn, "abc:png").unwrap();
+        assert_eq!(load_upload_session(&conn, "abc:png", 3600).unwrap(), None);
+    }
+
+    #[test]
+    fn test_expired_upload_session_is_dropped() {
+        let conn = db();
+        let session = UploadSession {
+            session_uri: "https://upload.example/session/2".to_string(),
+            bytes_sent: 10,
+            size: 20,
+        };
+        save_upload_session(&conn, "old", &session).unwrap();
+        conn.execute("UPDATE upload_sessions SET updated_at = 0", [])
+            .unwrap();
+
+        assert_eq!(load_upload_session(&conn, "old", 3600).unwrap(), None);
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM upload_sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_record_and_load_job() {
+        let mut conn = db();
+        let job = job("/out/kitab", vec![OutputFormat::Txt]);
+        record(&mut conn, &job).unwrap();
+        assert_eq!(load(&conn, "job-1").unwrap(), Some(job.clone()));
+        assert_eq!(load(&conn, "missing").unwrap(), None);
+
+        // Recording again replaces the pages
+        let shorter = JobRecord {
+            pages: vec!["واحدة".to_string()],
+            ..job
+        };
+        record(&mut conn, &shorter).unwrap();
+        assert_eq!(load(&conn, "job-1").unwrap().unwrap().pages, shorter.pages);
+    }
+
+    #[test]
+    fn test_apply_edits_records_history() {
+        let mut conn = db();
+        record(&mut conn, &job("/out/kitab", vec![OutputFormat::Txt])).unwrap();
+
+        let (updated, edited) = apply_edits(
+            &mut conn,
+            "job-1",
+            &[edit(1, "الصفحة الأولى"), edit(2, "الصفحة الثانية")],
+        )
+        .unwrap();
+        // Page 2 is unchanged, so only page 1 counts as an edit
+        assert_eq!(edited, vec![1]);
+        assert_eq!(updated.pages[0], "الصفحة الأولى");
+        assert_eq!(edit_count(&conn, "job-1"), 1);
+        assert_eq!(
+            load(&conn, "job-1").unwrap().unwrap().pages[0],
+            "الصفحة الأولى"
+        );
+    }
+
+    #[test]
+    fn test_apply_edits_rejects_bad_input() {
+        let mut conn = db();
+        record(&mut conn, &job("/out/kitab", vec![OutputFormat::Txt])).unwrap();
+
+        let err = apply_edits(&mut conn, "job-1", &[edit(1, "x"), edit(3, "y")]).unwrap_err();
+        assert!(matches!(err, TahweelError::InvalidInput(_)));
+        assert!(apply_edits(&mut conn, "job-1", &[edit(0, "x")]).is_err());
+        // The failed batch is rolled back entirely
+        assert_eq!(edit_count(&conn, "job-1"), 0);
+        assert_eq!(
+            load(&conn, "job-1").unwrap().unwrap().pages[0],
+            "الصفحه الاولى"
+        );
+
+        let err = apply_edits(&mut conn, "other", &[edit(1, "x")]).unwrap_err();
+        assert!(matches!(err, TahweelError::NotFound(_)));
+    }
+
+    fn stop(output_path: &str) -> JobStop {
+        JobStop {
+            reason: StopReason::Failed,
+            error_kind: Some("network".to_string()),
+            message: "Network error".to_string(),
+            source_path: "/books/kitab.pdf".to_string(),
+            output_path: output_path.to_string(),
+            at_ms: 5,
+        }
+    }
+
+    #[test]
+    fn test_partial_results_round_trip() {
+        let conn = db();
+        save_partial_page(&conn, "job-1", 3, "third").unwrap();
+        save_partial_page(&conn, "job-1", 1, "first").unwrap();
+        save_partial_page(&conn, "job-1", 1, "first again").unwrap();
+        save_partial_page(&conn, "job-2", 1, "other job").unwrap();
+
+        let running = load_partial(&conn, "job-1").unwrap();
+        assert!(running.stop.is_none());
+        assert_eq!(
+            running.pages,
+            vec![
+                PartialPage {
+                    page: 1,
+                    text: "first again".to_string()
+                },
+                PartialPage {
+                    page: 3,
+                    text: "third".to_string()
+                },
+            ]
+        );
+        assert_eq!(running.missing_pages, vec![2]);
+
+        save_stop(&conn, "job-1", &stop("/out/kitab")).unwrap();
+        assert_eq!(
+            load_partial(&conn, "job-1").unwrap().stop,
+            Some(stop("/out/kitab"))
+        );
+        assert!(load_partial(&conn, "missing").unwrap().pages.is_empty());
+    }
+
+    #[test]
+    fn test_interrupt_stale_running_jobs() {
+        let mut conn = db();
+        let now = 10 * STALE_AFTER_MS;
+        let fresh = now - 1_000;
+        let stale = now - STALE_AFTER_MS - 1;
+        for (job_id, session, pid, heartbeat_ms) in [
+            ("mine", "current", 1, stale),
+            ("dead-pid", "old", 2, fresh),
+            ("alive", "old", 3, fresh),
+            ("unknown", "old", 4, fresh),
+            ("stale", "old", 3, stale),
+        ] {
+            mark_running(
+                &conn,
+                job_id,
+                "/books/kitab.pdf",
+                "/out/kitab",
+                session,
+                pid,
+                heartbeat_ms,
+            )
+            .unwrap();
+        }
+        save_partial_page(&conn, "dead-pid", 1, "first").unwrap();
+        save_partial_page(&conn, "dead-pid", 2, "second").unwrap();
+
+        let is_alive = |pid| match pid {
+            2 => Some(false),
+            3 => Some(true),
+            _ => None,
+        };
+        let interrupted = interrupt_stale(&mut conn, "current", now, is_alive).unwrap();
+        assert_eq!(
+            interrupted,
+            vec![
+                InterruptedJob {
+                    job_id: "stale".to_string(),
+                    source_path: "/books/kitab.pdf".to_string(),
+                    output_path: "/out/kitab".to_string(),
+                    pages: 0,
+                },
+                InterruptedJob {
+                    job_id: "dead-pid".to_string(),
+                    source_path: "/books/kitab.pdf".to_string(),
+                    output_path: "/out/kitab".to_string(),
+                    pages: 2,
+                },
+            ]
+        );
+        let stop = load_partial(&conn, "dead-pid").unwrap().stop.unwrap();
+        assert_eq!(stop.reason, StopReason::Interrupted);
+        assert_eq!(stop.output_path, "/out/kitab");
+        // Reported once; the live ones stay
+        assert!(interrupt_stale(&mut conn, "current", now, is_alive)
+            .unwrap()
+            .is_empty());
+        let running: i64 = conn
+            .query_row("SELECT COUNT(*) FROM running_jobs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(running, 3);
+
+        // Another session later: only jobs without a recent heartbeat are stale
+        heartbeat(&conn, "current", now).unwrap();
+        clear_running(&conn, "alive").unwrap();
+        assert!(
+            interrupt_stale(&mut conn, "other", now + STALE_AFTER_MS, |_| None)
+                .unwrap()
+                .iter()
+                .map(|job| job.job_id.as_str())
+                .eq(["unknown"])
+        );
+    }
+
+    #[test]
+    fn test_discard_forgets_the_job() {
+        let conn = db();
+        mark_running(&conn, "job-1", "/a.pdf", "/a", "old", 1, 0).unwrap();
+        save_partial_page(&conn, "job-1", 1, "first").unwrap();
+        save_stop(&conn, "job-1", &stop("/out/kitab")).unwrap();
+
+        discard(&conn, "job-1").unwrap();
+        let partial = load_partial(&conn, "job-1").unwrap();
+        assert!(partial.stop.is_none());
+        assert!(partial.pages.is_empty());
+        let running: i64 = conn
+            .query_row("SELECT COUNT(*) FROM running_jobs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(running, 0);
+    }
+
+    #[test]
+    fn test_throughput_history() {
+        let conn = db();
+        assert_eq!(
+            throughput_estimate(&conn, "googleDrive", 150).unwrap(),
+            None
+        );
+
+        record_throughput(&conn, "googleDrive", 150, 10, 20_000).unwrap();
+        let estimate = record_throughput(&conn, "googleDrive", 150, 10, 30_000).unwrap();
+        assert_eq!(estimate.samples, 2);
+        assert!((estimate.ms_per_page - 2_300.0).abs() < 1e-6);
+        record_throughput(&conn, "googleDrive", 300, 4, 20_000).unwrap();
+
+        let exact = throughput_estimate(&conn, "googleDrive", 150)
+            .unwrap()
+            .unwrap();
+        assert_eq!(exact, estimate);
+        // No history at 200 DPI: the nearest DPI stands in
+        let nearest = throughput_estimate(&conn, "googleDrive", 200)
+            .unwrap()
+            .unwrap();
+        assert_eq!(nearest.dpi, 150);
+        assert_eq!(
+            throughput_estimate(&conn, "googleDrive", 280)
+                .unwrap()
+                .unwrap()
+                .ms_per_page,
+            5_000.0
+        );
+        assert_eq!(throughput_estimate(&conn, "other", 150).unwrap(), None);
+        assert_eq!(
+            record_throughput(&conn, "googleDrive", 150, 0, 1)
+                .unwrap_err()
+                .kind(),
+            "invalidInput"
+        );
+    }
+
+    #[test]
+    fn test_unknown_stop_reason_is_left_out() {
+        let conn = db();
+        conn.execute(
+            "INSERT INTO job_stops (job_id, source_path, output_path, reason, message, at_ms)
+             VALUES ('job-1', '/a.pdf', '/a', 'shutdown', '', 0)",
+            [],
+        )
+        .unwrap();
+        assert!(load_partial(&conn, "job-1").unwrap().stop.is_none());
+    }
+
+    #[test]
+    fn test_recording_a_finished_job_clears_partial_results() {
+        let mut conn = db();
+        save_partial_page(&conn, "job-1", 1, "first").unwrap();
+        save_stop(&conn, "job-1", &stop("/out/kitab")).unwrap();
+
+        record(&mut conn, &job("/out/kitab", vec![OutputFormat::Txt])).unwrap();
+        let partial = load_partial(&conn, "job-1").unwrap();
+        assert!(partial.stop.is_none());
+        assert!(partial.pages.is_empty());
+    }
+
+    #[test]
+    fn test_assemble_partial_fills_missing_pages() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("kitab").to_string_lossy().to_string();
+        let conn = db();
+        save_partial_page(&conn, "job-1", 1, "first").unwrap();
+        save_partial_page(&conn, "job-1", 3, "third").unwrap();
+
+        let running = load_partial(&conn, "job-1").unwrap();
+        let err = assemble_partial(&running, vec![OutputFormat::Txt], "|".to_string());
+        assert!(matches!(err, Err(TahweelError::InvalidInput(_))));
+
+        save_stop(&conn, "job-1", &stop(&base)).unwrap();
+        let assembled = assemble_partial(
+            &load_partial(&conn, "job-1").unwrap(),
+            vec![OutputFormat::Txt],
+            "|".to_string(),
+        )
+        .unwrap();
+        assert_eq!(assembled.paths, vec![format!("{}-partial.txt", base)]);
+        assert_eq!(assembled.missing_pages, vec![2]);
+        assert_eq!(
+            fs::read_to_string(&assembled.paths[0]).unwrap(),
+            "first||third"
+        );
+        assert!(!Path::new(&format!("{}.txt", base)).exists());
+    }
+
+    #[test]
+    fn test_assemble_partial_needs_pages() {
+        let conn = db();
+        save_stop(&conn, "job-1", &stop("/out/kitab")).unwrap();
+        let err = assemble_partial(
+            &load_partial(&conn, "job-1").unwrap(),
+            vec![OutputFormat::Txt],
+            "|".to_string(),
+        );
+        assert!(matches!(err, Err(TahweelError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_job_stop_serialization() {
+        let mut value = serde_json::to_value(stop("/out/kitab")).unwrap();
+        assert_eq!(value["reason"], "failed");
+        assert_eq!(value["errorKind"], "network");
+        assert_eq!(value["outputPath"], "/out/kitab");
+
+        // The webview sends stops without a timestamp
+        value.as_object_mut().unwrap().remove("atMs");
+        let parsed: JobStop = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.at_ms, 0);
+    }
+
+    #[test]
+    fn test_regenerate_outputs() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("kitab").to_string_lossy().to_string();
+        let job = job(
+            &base,
+            vec![OutputFormat::Txt, OutputFormat::Docx, OutputFormat::Json],
+        );
+
+        let paths = regenerate_outputs(&job).unwrap();
+        assert_eq!(paths.len(), 3);
+        assert_eq!(
+            fs::read_to_string(format!("{}.txt", base)).unwrap(),
+            "الصفحه الاولى\n---\nالصفحة الثانية"
+        );
+        assert!(Path::new(&format!("{}.docx", base)).is_file());
+        let json: JsonOutput =
+            serde_json::from_str(&fs::read_to_string(format!("{}.json", base)).unwrap()).unwrap();
+        assert_eq!(json.source, "/books/kitab.pdf");
+        assert_eq!(json.pages[1].text, "الصفحة الثانية");
+    }
+
+    #[test]
+    fn test_regenerate_json_keeps_existing_metadata() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("kitab").to_string_lossy().to_string();
+        fs::write(
+            format!("{}.json", base),
+            r#"[{"page": 1, "content": "old"}, {"page": 2, "content": "old"}]"#,
+        )
+        .unwrap();
+        let mut job = job(&base, vec![OutputFormat::Json]);
+        job.pages[1] = " مصححة ".to_string();
+
+        regenerate_outputs(&job).unwrap();
+        let json: Value =
+            serde_json::from_str(&fs::read_to_string(format!("{}.json", base)).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                { "page": 1, "content": "الصفحه الاولى" },
+                { "page": 2, "content": "مصححة" },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_regenerated_docx_keeps_metadata_and_page_images() {
+        use std::io::Read;
+
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("kitab").to_string_lossy().to_string();
+        let scan = temp.path().join("page-1.png");
+        image::RgbImage::new(40, 60).save(&scan).unwrap();
+        let mut conn = db();
+        let job = JobRecord {
+            docx: DocxOptions {
+                metadata: DocumentMetadata {
+                    title: Some("الرسالة".to_string()),
+                    author: Some("الشافعي".to_string()),
+                    subject: None,
+                },
+                page_images: vec![Some(scan.to_string_lossy().to_string()), None],
+                image_width: Some(300),
+            },
+            ..job(&base, vec![OutputFormat::Docx])
+        };
+        record(&mut conn, &job).unwrap();
+
+        let edits = [PageEdit {
+            page: 1,
+            text: "الصفحة الأولى".to_string(),
+        }];
+        let (updated, _) = apply_edits(&mut conn, "job-1", &edits).unwrap();
+        assert_eq!(updated.docx, job.docx);
+        regenerate_outputs(&updated).unwrap();
+
+        let mut archive =
+            zip::ZipArchive::new(fs::File::open(format!("{}.docx", base)).unwrap()).unwrap();
+        let mut core = String::new();
+        archive
+            .by_name("docProps/core.xml")
+            .unwrap()
+            .read_to_string(&mut core)
+            .unwrap();
+        assert!(core.contains("<dc:title>الرسالة</dc:title>"));
+        assert!(core.contains("<dc:creator>الشافعي</dc:creator>"));
+        assert!(archive.by_name("word/media/page-0001.jpeg").is_ok());
+        assert!(archive.by_name("word/media/page-0002.jpeg").is_err());
+    }
+
+    #[test]
+    fn test_regenerated_docx_without_its_scans_keeps_the_text() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("kitab").to_string_lossy().to_string();
+        let job = JobRecord {
+            docx: DocxOptions {
+                page_images: vec![Some("/nonexistent/page-1.png".to_string())],
+                ..DocxOptions::default()
+            },
+            ..job(&base, vec![OutputFormat::Docx])
+        };
+
+        assert_eq!(
+            regenerate_outputs(&job).unwrap(),
+            vec![format!("{}.docx", base)]
+        );
+    }
+
+    #[test]
+    fn test_patch_json_pages_rejects_mismatched_layout() {
+        let mut value = serde_json::json!({ "pages": [{ "text": "a" }] });
+        assert!(!patch_json_pages(
+            &mut value,
+            &["a".to_string(), "b".to_string()]
+        ));
+        let mut value = serde_json::json!({ "other": [] });
+        assert!(!patch_json_pages(&mut value, &[]));
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        assert_eq!(
+            normalize_tags(&strings(&[
+                " Waqf  Collection ",
+                "waqf collection",
+                "",
+                "مخطوطات"
+            ])),
+            strings(&["waqf collection", "مخطوطات"])
+        );
+        let long = "x".repeat(100);
+        assert_eq!(normalize_tags(&[long])[0].chars().count(), MAX_TAG_CHARS);
+    }
+
+    #[test]
+    fn test_set_tags_replaces_previous_tags() {
+        let mut conn = db();
+        set_tags(&mut conn, "job-1", &strings(&["grant-2024", "Fiqh"])).unwrap();
+        let stored = set_tags(&mut conn, "job-1", &strings(&["fiqh", "Hadith"])).unwrap();
+        assert_eq!(stored, strings(&["fiqh", "hadith"]));
+        assert_eq!(load_labels(&conn, "job-1").unwrap().tags, stored);
+        assert!(search_by_tag(&conn, "grant-2024").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_notes_set_and_clear() {
+        let conn = db();
+        set_note(&conn, "job-1", "  Pages 40-45 are water damaged ").unwrap();
+        assert_eq!(
+            load_labels(&conn, "job-1").unwrap().note.as_deref(),
+            Some("Pages 40-45 are water damaged")
+        );
+        set_note(&conn, "job-1", "  ").unwrap();
+        assert_eq!(load_labels(&conn, "job-1").unwrap(), JobLabels::default());
+    }
+
+    #[test]
+    fn test_usage_adds_up_and_shows_in_details() {
+        let mut conn = db();
+        assert_eq!(load_details(&conn, "job-1").unwrap(), None);
+        let usage = ResourceUsage {
+            cpu_ms: 1200,
+            peak_memory_bytes: 64 * 1024 * 1024,
+            bytes_uploaded: 500_000,
+            bytes_downloaded: 4_000,
+            drive_api_calls: 12,
+        };
+        save_usage(&conn, "job-1", &usage).unwrap();
+        save_usage(
+            &conn,
+            "job-1",
+            &ResourceUsage {
+                peak_memory_bytes: 1024,
+                drive_api_calls: 3,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        record(&mut conn, &job("/out/kitab", vec![OutputFormat::Txt])).unwrap();
+        set_tags(&mut conn, "job-1", &strings(&["waqf"])).unwrap();
+
+        let details = load_details(&conn, "job-1").unwrap().unwrap();
+        assert_eq!(details.source_path.as_deref(), Some("/books/kitab.pdf"));
+        assert_eq!(details.formats, vec![OutputFormat::Txt]);
+        assert_eq!(details.page_count, 2);
+        assert!(!details.running);
+        assert_eq!(details.tags, strings(&["waqf"]));
+        assert_eq!(
+            details.usage,
+            Some(ResourceUsage {
+                drive_api_calls: 15,
+                ..usage
+            })
+        );
+
+        delete_jobs(&mut conn, &strings(&["job-1"])).unwrap();
+        assert_eq!(load_usage(&conn, "job-1").unwrap(), None);
+    }
+
+    #[test]
+    fn test_details_of_a_running_job() {
+        let conn = db();
+        mark_running(&conn, "job-2", "/a.pdf", "/out/a", "session", 1, 0).unwrap();
+        let details = load_details(&conn, "job-2").unwrap().unwrap();
+        assert!(details.running);
+        assert_eq!(details.source_path.as_deref(), Some("/a.pdf"));
+        assert_eq!(details.created_at, None);
+        assert_eq!(details.usage, None);
+        let json = serde_json::to_value(&details).unwrap();
+        assert_eq!(json["pageCount"], 0);
+        assert_eq!(json["outputPath"], "/out/a");
+    }
+
+    #[test]
+    fn test_quota_usage_adds_up_per_day() {
+        let mut conn = db();
+        assert_eq!(load_quota_usage(&conn, "googleDrive", 100).unwrap(), (0, 0));
+        add_quota_usage(&mut conn, "googleDrive", 100, 1, 2).unwrap();
+        assert_eq!(
+            add_quota_usage(&mut conn, "googleDrive", 100, 1, 4).unwrap(),
+            (2, 6)
+        );
+        // A new day starts from zero and drops the old one
+        assert_eq!(
+            add_quota_usage(&mut conn, "googleDrive", 200, 1, 1).unwrap(),
+            (1, 1)
+        );
+        assert_eq!(load_quota_usage(&conn, "googleDrive", 100).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn test_search_by_tag() {
+        let mut conn = db();
+        record(&mut conn, &job("/out/kitab", vec![OutputFormat::Txt])).unwrap();
+        set_tags(&mut conn, "job-1", &strings(&["Waqf", "grant"])).unwrap();
+        set_note(&conn, "job-1", "Second volume").unwrap();
+        set_tags(&mut conn, "job-2", &strings(&["waqf"])).unwrap();
+
+        let found = search_by_tag(&conn, " WAQF ").unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].job_id, "job-1");
+        assert_eq!(found[0].source_path.as_deref(), Some("/books/kitab.pdf"));
+        assert_eq!(found[0].tags, strings(&["grant", "waqf"]));
+        assert_eq!(found[0].note.as_deref(), Some("Second volume"));
+        assert_eq!(found[1].job_id, "job-2");
+        assert!(found[1].source_path.is_none());
+
+        assert!(matches!(
+            search_by_tag(&conn, " "),
+            Err(TahweelError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::error::TahweelError;
+use serde::Serialize;
+
+/// Pages with fewer letters than this are too short to classify
+const MIN_LETTERS: usize = 20;
+/// Share of Arabic-script letters above which a page is Arabic (and below
+/// `1 - ARABIC_RATIO`, Latin); anything in between is mixed
+const ARABIC_RATIO: f64 = 0.8;
+/// Share of a page's Arabic-script letters specific to Persian or Urdu needed to pick that language
+const VARIANT_LETTER_RATIO: f64 = 0.01;
+/// Share of a page's Latin words that must be stopwords of a language to pick it
+const STOPWORD_RATIO: f64 = 0.08;
+
+const PERSIAN_LETTERS: &[char] = &['پ', 'چ', 'ژ', 'گ', 'ک', 'ی'];
+const URDU_LETTERS: &[char] = &['ٹ', 'ڈ', 'ڑ', 'ں', 'ے', 'ہ'];
+const ENGLISH_STOPWORDS: &[&str] = &[
+    "the", "and", "of", "to", "in", "is", "that", "for", "with", "as", "was", "on",
+];
+const FRENCH_STOPWORDS: &[&str] = &[
+    "le", "la", "les", "des", "et", "est", "une", "du", "que", "dans", "pour", "au",
+];
+
+/// Writing system of a page
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Script {
+    Arabic,
+    Latin,
+    Mixed,
+    /// Too little text to tell
+    Unknown,
+}
+
+/// Detected script and language of one page
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PageLanguage {
+    pub page: u32,
+    pub script: Script,
+    /// ISO 639-1 code to use as the page's OCR language hint, if confident
+    pub language: Option<String>,
+}
+
+fn is_arabic_letter(c: char) -> bool {
+    matches!(c, '\u{0620}'..='\u{064A}' | '\u{0671}'..='\u{06D3}' | '\u{06FA}'..='\u{06FC}')
+}
+
+fn is_latin_letter(c: char) -> bool {
+    c.is_ascii_alphabetic() || matches!(c, '\u{00C0}'..='\u{024F}' if c.is_alphabetic())
+}
+
+/// Language of a page written in Arabic script: Persian and Urdu use letters Arabic does not
+fn arabic_script_language(text: &str, letters: usize) -> &'static str {
+    let share =
+        |set: &[char]| text.chars().filter(|c| set.contains(c)).count() as f64 / letters as f64;
+    let persian = share(PERSIAN_LETTERS);
+    let urdu = share(URDU_LETTERS);
+    if urdu >= VARIANT_LETTER_RATIO && urdu >= persian {
+        "ur"
+    } else if persian >= VARIANT_LETTER_RATIO {
+        "fa"
+    } else {
+        "ar"
+    }
+}
+
+/// Language of a page written in Latin script, from common function words
+fn latin_script_language(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    let share = |stopwords: &[&str]| {
+        words
+            .iter()
+            .filter(|w| stopwords.contains(&w.as_str()))
+            .count() as f64
+            / words.len() as f64
+    };
+    let english = share(ENGLISH_STOPWORDS);
+    let french = share(FRENCH_STOPWORDS);
+    if english >= STOPWORD_RATIO && english >= french {
+        Some("en")
+    } else if french >= STOPWORD_RATIO {
+        Some("fr")
+    } else {
+        None
+    }
+}
+
+/// Detect a page's script from the share of Arabic and Latin letters, and its
+/// language where the script allows a confident guess. Mixed pages get no
+/// language so OCR detects it by itself.
+pub fn detect(text: &str) -> (Script, Option<&'static str>) {
+    let arabic = text.chars().filter(|&c| is_arabic_letter(c)).count();
+    let latin = text.chars().filter(|&c| is_latin_letter(c)).count();
+    let letters = arabic + latin;
+    if letters < MIN_LETTERS {
+        return (Script::Unknown, None);
+    }
+
+    let ratio = arabic as f64 / letters as f64;
+    if ratio >= ARABIC_RATIO {
+        (Script::Arabic, Some(arabic_script_language(text, arabic)))
+    } else if ratio <= 1.0 - ARABIC_RATIO {
+        (Script::Latin, latin_script_language(text))
+    } else {
+        (Script::Mixed, None)
+    }
+}
+
+/// Whether `code` looks like a language code Drive accepts as `ocrLanguage`
+pub fn is_valid_hint(code: &str) -> bool {
+    (2..=3).contains(&code.len()) && code.chars().all(|c| c.is_ascii_lowercase())
+}
+
+/// Detect the script and language of each page from its first-pass OCR text
+#[tauri::command]
+pub async fn detect_page_languages(pages: Vec<String>) -> Result<Vec<PageLanguage>, TahweelError> {
+    Ok(pages
+        .iter()
+        .enumerate()
+        .map(|(i, text)| {
+            let (script, language) = detect(text);
+            PageLanguage {
+                page: i as u32 + 1,
+                script,
+                language: language.map(str::to_string),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARABIC: &str = "الحمد لله رب العالمين والصلاة والسلام على أشرف الأنبياء والمرسلين";
+    const ENGLISH: &str =
+        "The history of the Arabic language is closely tied to the spread of Islam and the Quran.";
+    const FRENCH: &str = "La langue arabe est une langue sémitique parlée dans le monde arabe et dans les pays voisins.";
+
+    #[test]
+    fn test_detect_arabic() {
+        assert_eq!(detect(ARABIC), (Script::Arabic, Some("ar")));
+    }
+
+    #[test]
+    fn test_detect_persian_and_urdu() {
+        let persian = "این کتاب درباره تاریخ زبان فارسی و ادبیات گذشته است که پژوهشگران نوشته‌اند";
+        assert_eq!(detect(persian), (Script::Arabic, Some("fa")));
+        let urdu = "یہ کتاب اردو زبان کی تاریخ کے بارے میں ہے اور بہت اہم ہے";
+        assert_eq!(detect(urdu), (Script::Arabic, Some("ur")));
+    }
+
+    #[test]
+    fn test_detect_latin_languages() {
+        assert_eq!(detect(ENGLISH), (Script::Latin, Some("en")));
+        assert_eq!(detect(FRENCH), (Script::Latin, Some("fr")));
+        // Latin script without known function words gets no hint
+        assert_eq!(
+            detect("Lorem ipsum dolor sit amet consectetur adipiscing elit"),
+            (Script::Latin, None)
+        );
+    }
+
+    #[test]
+    fn test_detect_mixed_and_short() {
+        let mixed = format!("{} {}", ARABIC, ENGLISH);
+        assert_eq!(detect(&mixed), (Script::Mixed, None));
+        assert_eq!(detect("١٢ - Page 3"), (Script::Unknown, None));
+        assert_eq!(detect(""), (Script::Unknown, None));
+    }
+
+    #[test]
+    fn test_is_valid_hint() {
+        assert!(is_valid_hint("ar"));
+        assert!(is_valid_hint("fil"));
+        assert!(!is_valid_hint("AR"));
+        assert!(!is_valid_hint("en&x=1"));
+        assert!(!is_valid_hint(""));
+    }
+
+    #[tokio::test]
+    async fn test_detect_page_languages() {
+        let pages = detect_page_languages(vec![ARABIC.to_string(), ENGLISH.to_string()])
+            .await
+            .unwrap();
+        assert_eq!(pages[0].page, 1);
+        assert_eq!(pages[0].language.as_deref(), Some("ar"));
+        assert_eq!(pages[1].script, Script::Latin);
+        assert_eq!(pages[1].language.as_deref(), Some("en"));
+    }
+}
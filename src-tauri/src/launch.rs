@@ -0,0 +1,240 @@
+use crate::error::TahweelError;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, RunEvent};
+use tauri_plugin_fs::FsExt;
+
+/// Extensions accepted from the command line (same as the frontend's `SUPPORTED_EXTENSIONS`)
+const SUPPORTED_EXTENSIONS: &[&str] = &["pdf", "jpg", "jpeg", "png"];
+
+/// Files passed at launch that the frontend has not picked up yet
+static PENDING_FILES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Whether `path` has an extension Tahweel can process
+pub fn is_supported_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| SUPPORTED_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Local path of a `file://` URL. Other schemes are ignored: a link from a web page
+/// must not be able to queue arbitrary local files for upload.
+pub fn path_from_file_url(value: &str) -> Option<PathBuf> {
+    let url = url::Url::parse(value).ok()?;
+    if url.scheme() != "file" {
+        return None;
+    }
+    url.to_file_path().ok()
+}
+
+/// Keep existing supported files, canonicalized and without duplicates
+fn supported_files(paths: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if !path.is_file() || !is_supported_file(&path) {
+            continue;
+        }
+        let path = path.canonicalize().unwrap_or(path);
+        if !files.contains(&path) {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Existing supported files among launch arguments. `args[0]` is the executable;
+/// flags are skipped and relative paths resolve against `cwd` (the launching shell's).
+/// Some Linux file managers pass `file://` URLs instead of paths.
+pub fn file_args(args: &[String], cwd: &Path) -> Vec<PathBuf> {
+    let paths = args
+        .iter()
+        .skip(1)
+        .filter(|a| !a.starts_with('-'))
+        .filter_map(|arg| {
+            if arg.starts_with("file://") {
+                return path_from_file_url(arg);
+            }
+            let path = Path::new(arg);
+            Some(if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                cwd.join(path)
+            })
+        });
+    supported_files(paths)
+}
+
+/// Supported files among URLs from an "open with" event
+#[cfg_attr(not(any(target_os = "macos", target_os = "ios")), allow(dead_code))]
+pub fn file_urls<'a>(urls: impl IntoIterator<Item = &'a str>) -> Vec<PathBuf> {
+    supported_files(urls.into_iter().filter_map(path_from_file_url))
+}
+
+/// Add files to the fs scope, as the dialog plugin does for picked files
+pub fn grant_files(app: &AppHandle, files: impl IntoIterator<Item = PathBuf>) {
+    if let Some(scope) = app.try_fs_scope() {
+        for file in files {
+            let _ = scope.allow_file(file);
+        }
+    }
+}
+
+/// Queue files for the frontend and tell it to collect them with `take_launch_files`.
+/// The files are added to the fs scope, like dialog picks, so outputs can be written
+/// next to them.
+pub fn enqueue_files(app: &AppHandle, files: Vec<PathBuf>) {
+    if files.is_empty() {
+        return;
+    }
+    grant_files(app, files.iter().cloned());
+    tracing::info!(count = files.len(), "Files received at launch");
+    if let Ok(mut pending) = PENDING_FILES.lock() {
+        pending.extend(files.iter().map(|f| f.to_string_lossy().to_string()));
+    }
+    let _ = app.emit("launch-files", ());
+}
+
+/// Bring the main window to the front (restoring it if minimized or hidden)
+pub fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Called by the single-instance plugin when Tahweel is launched again:
+/// hand the new launch's files to this instance and focus it.
+pub fn handle_second_instance(app: &AppHandle, args: Vec<String>, cwd: String) {
+    tracing::info!("Second instance launched; forwarding its arguments");
+    enqueue_files(app, file_args(&args, Path::new(&cwd)));
+    focus_main_window(app);
+}
+
+/// App event loop hook. macOS delivers files opened with Tahweel (Finder "Open With",
+/// double-click on an associated file) as `Opened` events rather than arguments.
+pub fn handle_run_event(app: &AppHandle, event: &RunEvent) {
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    if let RunEvent::Opened { urls } = event {
+        enqueue_files(app, file_urls(urls.iter().map(|u| u.as_str())));
+        focus_main_window(app);
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+    let _ = (app, event);
+}
+
+fn drain_pending() -> Vec<String> {
+    PENDING_FILES
+        .lock()
+        .map(|mut pending| std::mem::take(&mut *pending))
+        .unwrap_or_default()
+}
+
+/// Files passed on the command line (first launch or a later one) not yet processed
+#[tauri::command]
+pub async fn take_launch_files() -> Result<Vec<String>, TahweelError> {
+    Ok(drain_pending())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_is_supported_file() {
+        assert!(is_supported_file(Path::new("/a/book.pdf")));
+        assert!(is_supported_file(Path::new("/a/SCAN.JPEG")));
+        assert!(!is_supported_file(Path::new("/a/notes.txt")));
+        assert!(!is_supported_file(Path::new("/a/pdf")));
+    }
+
+    #[test]
+    fn test_file_args_skips_executable_and_flags() {
+        let dir = tempdir().unwrap();
+        let pdf = dir.path().join("book.pdf");
+        fs::write(&pdf, b"%PDF").unwrap();
+        let pdf_arg = pdf.to_string_lossy().to_string();
+
+        assert!(file_args(&args(&[&pdf_arg]), dir.path()).is_empty());
+
+        let files = file_args(&args(&["tahweel", "--minimized", &pdf_arg]), dir.path());
+        assert_eq!(files, vec![pdf.canonicalize().unwrap()]);
+    }
+
+    #[test]
+    fn test_file_args_resolves_relative_paths() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("scan.png"), b"png").unwrap();
+
+        let files = file_args(&args(&["tahweel", "scan.png"]), dir.path());
+        assert_eq!(
+            files,
+            vec![dir.path().join("scan.png").canonicalize().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_file_args_filters_missing_and_unsupported() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), b"text").unwrap();
+        fs::create_dir(dir.path().join("folder.pdf")).unwrap();
+
+        let files = file_args(
+            &args(&["tahweel", "notes.txt", "missing.pdf", "folder.pdf"]),
+            dir.path(),
+        );
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn test_path_from_file_url() {
+        let path = path_from_file_url("file:///home/a/my%20books/book.pdf").unwrap();
+        assert_eq!(path, PathBuf::from("/home/a/my books/book.pdf"));
+        assert!(path_from_file_url("https://example.com/book.pdf").is_none());
+        assert!(path_from_file_url("tahweel://open?path=/etc/passwd").is_none());
+        assert!(path_from_file_url("/home/a/book.pdf").is_none());
+    }
+
+    #[test]
+    fn test_file_args_accepts_file_urls() {
+        let dir = tempdir().unwrap();
+        let pdf = dir.path().join("book.pdf");
+        fs::write(&pdf, b"%PDF").unwrap();
+        let url = url::Url::from_file_path(&pdf).unwrap().to_string();
+
+        let files = file_args(&args(&["tahweel", &url]), Path::new("/"));
+        assert_eq!(files, vec![pdf.canonicalize().unwrap()]);
+    }
+
+    #[test]
+    fn test_file_urls_filters_unsupported() {
+        let dir = tempdir().unwrap();
+        let png = dir.path().join("scan.png");
+        let txt = dir.path().join("notes.txt");
+        fs::write(&png, b"png").unwrap();
+        fs::write(&txt, b"text").unwrap();
+        let png_url = url::Url::from_file_path(&png).unwrap().to_string();
+        let txt_url = url::Url::from_file_path(&txt).unwrap().to_string();
+
+        let files = file_urls([png_url.as_str(), txt_url.as_str(), "https://x.test/a.pdf"]);
+        assert_eq!(files, vec![png.canonicalize().unwrap()]);
+    }
+
+    #[test]
+    fn test_file_args_deduplicates() {
+        let dir = tempdir().unwrap();
+        let pdf = dir.path().join("book.pdf");
+        fs::write(&pdf, b"%PDF").unwrap();
+        let absolute = pdf.to_string_lossy().to_string();
+
+        let files = file_args(&args(&["tahweel", &absolute, "book.pdf"]), dir.path());
+        assert_eq!(files.len(), 1);
+    }
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+
+/// Axis-aligned rectangle in image pixel coordinates (origin at top-left)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BoundingBox {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BoundingBox {
+    pub fn right(&self) -> u32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> u32 {
+        self.y + self.height
+    }
+
+    /// Smallest box containing all the given boxes
+    pub fn union<'a, I: IntoIterator<Item = &'a BoundingBox>>(boxes: I) -> Option<BoundingBox> {
+        boxes.into_iter().fold(None, |acc, b| {
+            Some(match acc {
+                None => *b,
+                Some(a) => {
+                    let x = a.x.min(b.x);
+                    let y = a.y.min(b.y);
+                    BoundingBox {
+                        x,
+                        y,
+                        width: a.right().max(b.right()) - x,
+                        height: a.bottom().max(b.bottom()) - y,
+                    }
+                }
+            })
+        })
+    }
+}
+
+/// A recognized word with its position, as returned by geometry-aware OCR backends
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OcrWord {
+    pub text: String,
+    #[serde(rename = "bbox")]
+    pub bbox: BoundingBox,
+    #[serde(default)]
+    pub confidence: Option<f32>,
+}
+
+/// Positional OCR result for one page. Backends without geometry (Google Drive)
+/// only provide `text`, leaving `words` empty.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageLayout {
+    pub width: u32,
+    pub height: u32,
+    pub text: String,
+    #[serde(default)]
+    pub words: Vec<OcrWord>,
+}
+
+impl PageLayout {
+    pub fn has_geometry(&self) -> bool {
+        !self.words.is_empty()
+    }
+
+    /// Group words into lines by vertical overlap, ordered top-to-bottom.
+    /// Words within a line are ordered right-to-left when `rtl` is set.
+    pub fn lines(&self, rtl: bool) -> Vec<Vec<&OcrWord>> {
+        let mut words: Vec<&OcrWord> = self.words.iter().collect();
+        words.sort_by_key(|w| (w.bbox.y, w.bbox.x));
+
+        let mut lines: Vec<Vec<&OcrWord>> = Vec::new();
+        for word in words {
+            let center = word.bbox.y + word.bbox.height / 2;
+            let existing = lines.iter_mut().find(|line| {
+                BoundingBox::union(line.iter().map(|w| &w.bbox))
+                    .map(|b| center >= b.y && center <= b.bottom())
+                    .unwrap_or(false)
+            });
+            match existing {
+                Some(line) => line.push(word),
+                None => lines.push(vec![word]),
+            }
+        }
+
+        for line in &mut lines {
+            if rtl {
+                line.sort_by_key(|w| std::cmp::Reverse(w.bbox.right()));
+            } else {
+                line.sort_by_key(|w| w.bbox.x);
+            }
+        }
+        lines.sort_by_key(|line| line.iter().map(|w| w.bbox.y).min().unwrap_or(0));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str, x: u32, y: u32, width: u32, height: u32) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            bbox: BoundingBox {
+                x,
+                y,
+                width,
+                height,
+            },
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn test_bounding_box_union() {
+        let a = BoundingBox {
+            x: 10,
+            y: 10,
+            width: 20,
+            height: 10,
+        };
+        let b = BoundingBox {
+            x: 5,
+            y: 15,
+            width: 10,
+            height: 30,
+        };
+
+        let union = BoundingBox::union([&a, &b]).unwrap();
+        assert_eq!(union.x, 5);
+        assert_eq!(union.y, 10);
+        assert_eq!(union.right(), 30);
+        assert_eq!(union.bottom(), 45);
+    }
+
+    #[test]
+    fn test_bounding_box_union_empty() {
+        assert!(BoundingBox::union(std::iter::empty()).is_none());
+    }
+
+    #[test]
+    fn test_lines_groups_by_vertical_overlap() {
+        let layout = PageLayout {
+            width: 1000,
+            height: 1000,
+            text: String::new(),
+            words: vec![
+                word("second", 100, 102, 80, 20),
+                word("first", 10, 100, 80, 20),
+                word("next", 10, 200, 80, 20),
+            ],
+        };
+
+        let lines = layout.lines(false);
+        assert_eq!(lines.len(), 2);
+        let texts: Vec<&str> = lines[0].iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, vec!["first", "second"]);
+        assert_eq!(lines[1][0].text, "next");
+    }
+
+    #[test]
+    fn test_lines_rtl_order() {
+        let layout = PageLayout {
+            width: 1000,
+            height: 1000,
+            text: String::new(),
+            words: vec![
+                word("يسار", 10, 100, 80, 20),
+                word("يمين", 500, 100, 80, 20),
+            ],
+        };
+
+        let lines = layout.lines(true);
+        let texts: Vec<&str> = lines[0].iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, vec!["يمين", "يسار"]);
+    }
+
+    #[test]
+    fn test_page_layout_without_words_has_no_geometry() {
+        let layout: PageLayout =
+            serde_json::from_str(r#"{"width": 10, "height": 20, "text": "hi"}"#).unwrap();
+        assert!(!layout.has_geometry());
+        assert!(layout.lines(false).is_empty());
+    }
+}
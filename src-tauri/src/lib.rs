@@ -1,26 +1,216 @@
+mod app_paths;
+mod archival;
+mod archive;
 mod auth;
+mod backend_health;
+mod baheth;
+mod capture;
+mod clipboard;
+mod columns;
+mod confidence;
+mod connectivity;
+mod crash;
+mod diagnostics;
+mod docx;
+mod download;
+mod drive_safe;
+mod dropped;
+mod duplicates;
+mod email;
+mod encryption;
+mod error;
+mod error_catalog;
+mod events;
+mod file_info;
+mod footnotes;
 mod google_drive;
+mod history;
+mod http;
+mod image_document;
+mod incremental;
+mod job_archive;
+mod job_channels;
+mod job_db;
+mod job_usage;
+mod jobs;
+mod language;
+mod launch;
+mod layout;
+mod logging;
+mod long_path;
+mod maintenance;
+mod masking;
+mod memory;
+mod mobile;
+mod ocr_xml;
+mod orientation;
+mod output;
+mod path_policy;
 mod pdf;
+mod perf;
+mod postprocess;
+mod power;
+mod profiles;
+mod quota;
+mod quran;
+mod recent;
+mod report;
+mod reveal;
+mod roi;
+mod scanner;
+mod search;
+mod settings;
+mod share;
+#[cfg(desktop)]
+mod shortcut;
+mod spool;
+mod storage;
+mod tables;
+mod trash;
+mod tray;
+mod util;
+mod verify;
+mod webdav;
 
+use app_paths::{get_app_paths, Area};
+use archival::prepare_archival_output;
+use archive::package_outputs;
 use auth::{
     clear_auth_tokens, get_user_info, load_stored_tokens, refresh_access_token, start_oauth_flow,
 };
-use google_drive::{delete_google_drive_file, export_google_doc_as_text, upload_to_google_drive};
-use pdf::{cleanup_temp_dir, extract_pdf_page, get_pdf_page_count, split_pdf, write_binary_file};
+use backend_health::check_backend;
+use baheth::write_baheth_export;
+use capture::ocr_screen_region;
+use clipboard::{copy_text_to_clipboard, ocr_clipboard_image};
+use columns::{order_columns, split_page_columns};
+use confidence::plan_dual_pass;
+use connectivity::check_connectivity;
+use crash::{dismiss_crash_report, get_crash_recovery, open_crash_report};
+use diagnostics::{create_diagnostics_bundle, get_app_info};
+use docx::write_docx_output;
+use download::{process_drive_file, process_url};
+use dropped::expand_dropped_paths;
+use duplicates::detect_duplicate_pages;
+use email::email_job_results;
+use error::TahweelError;
+use file_info::{get_disk_space, get_file_info};
+use footnotes::{separate_footnotes, split_page_footnotes};
+use google_drive::{
+    append_to_google_doc, create_google_doc, delete_google_drive_file, export_google_doc_as_text,
+    list_drive_files, upload_output_to_google_drive, upload_to_google_drive,
+};
+use history::export_history;
+use image_document::prepare_image_document;
+use incremental::{
+    append_live_txt, append_page_output, finalize_partial_output, finish_live_txt,
+    load_partial_output, start_live_txt,
+};
+use job_archive::{export_job_archive, import_job_archive};
+use job_channels::{subscribe_job_events, unsubscribe_job_events};
+use jobs::{
+    apply_corrections, assemble_partial_results, begin_job, discard_job, end_job, get_job_details,
+    get_job_events, get_job_labels, get_partial_results, get_throughput_estimate, record_job,
+    record_job_event, record_job_stop, record_job_throughput, record_partial_page,
+    search_jobs_by_tag, set_job_note, set_job_tags, take_interrupted_jobs,
+};
+use language::detect_page_languages;
+use launch::take_launch_files;
+use logging::{get_recent_logs, set_log_level};
+use maintenance::{get_storage_usage, run_maintenance_now};
+use masking::mask_page_image;
+use mobile::stage_input_file;
+use ocr_xml::write_xml_output;
+use orientation::correct_page_orientation;
+use output::{prepare_output_dir, resolve_output_dir, write_combined_output, write_json_output};
+use path_policy::PathAccess;
+use pdf::{
+    cancel_split, cleanup_temp_dir, extract_pdf_page, get_pdf_info, get_pdf_page_count, split_pdf,
+    write_binary_file,
+};
+use perf::get_performance_summary;
+use postprocess::{apply_postprocessing, postprocess_pages, preview_postprocessing};
+use power::get_power_status;
+use profiles::{create_profile, list_profiles, switch_profile};
+use quota::get_quota_status;
+use recent::{clear_recent_files, get_recent_files, pin_recent_file, record_recent_file};
+use report::generate_job_report;
+use reveal::{open_file, reveal_in_folder};
+use roi::crop_page_regions;
+use scanner::{list_scanners, scan_pages};
+use search::{index_document, remove_indexed_document, search_documents};
+use settings::{get_settings, update_settings};
+use share::{can_share_output, share_output};
+use spool::{discard_spool, spool_pages, write_spooled_outputs};
+use storage::upload_outputs_to_storage;
+use tables::extract_tables;
+use tauri::Manager;
+use tauri_plugin_fs::FsExt;
+use trash::undo_cleanup;
+use tray::update_tray_status;
+use verify::verify_outputs;
+use webdav::upload_outputs_to_webdav;
 
 /// Open a folder in the system file manager
 #[tauri::command]
-async fn open_folder(path: String) -> Result<(), String> {
-    open::that(&path).map_err(|e| format!("Failed to open folder: {}", e))
+async fn open_folder(path: String) -> Result<(), TahweelError> {
+    let path = path_policy::check(std::path::Path::new(&path), PathAccess::Open)?;
+    open::that(&path).map_err(|e| TahweelError::Io(format!("Failed to open folder: {}", e)))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Installed first so a failing `run()` below is reported through the hook as well
+    crash::install_panic_hook();
+    // Before anything reads settings, tokens or history
+    app_paths::migrate_legacy();
+    profiles::init(&std::env::args().collect::<Vec<_>>());
+
     tauri::Builder::default()
+        // Must be the first plugin so a second launch exits before initializing anything
+        .plugin(tauri_plugin_single_instance::init(
+            launch::handle_second_instance,
+        ))
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .setup(|app| {
+            // Paths picked through the dialog plugin are added to the fs scope
+            if let Some(scope) = app.try_fs_scope() {
+                path_policy::set_scope_check(move |path| scope.is_allowed(path));
+            }
+            perf::init(app.handle().clone());
+            power::init(app.handle());
+            quran::init(app.path().resource_dir().ok());
+            if cfg!(mobile) {
+                mobile::init(app.handle());
+            }
+            archival::init(app.handle());
+            // Logging is best-effort: a read-only log dir must not block startup
+            if let Err(e) = logging::init(&app_paths::dir(Area::Logs)) {
+                eprintln!("{}", e);
+            }
+            tracing::info!(version = env!("CARGO_PKG_VERSION"), "Tahweel started");
+            trash::init();
+            jobs::init(app.handle());
+            maintenance::init();
+            quota::init(app.handle());
+            if let Ok(cwd) = std::env::current_dir() {
+                let args: Vec<String> = std::env::args().collect();
+                launch::enqueue_files(app.handle(), launch::file_args(&args, &cwd));
+            }
+            // Some Linux desktops have no tray; the app works without it
+            if let Err(e) = tray::init(app.handle()) {
+                tracing::warn!(error = %e, "Failed to create tray icon");
+            }
+            // A shortcut taken by another app must not block startup
+            #[cfg(desktop)]
+            if let Err(e) = shortcut::init(app.handle()) {
+                tracing::warn!(error = %e, "Failed to register quick OCR shortcut");
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Auth commands
             start_oauth_flow,
@@ -32,17 +222,135 @@ pub fn run() {
             upload_to_google_drive,
             export_google_doc_as_text,
             delete_google_drive_file,
+            list_drive_files,
+            upload_output_to_google_drive,
+            ocr_screen_region,
+            ocr_clipboard_image,
+            create_google_doc,
+            append_to_google_doc,
             // PDF commands
             get_pdf_page_count,
+            get_pdf_info,
             split_pdf,
+            cancel_split,
             extract_pdf_page,
+            prepare_image_document,
             cleanup_temp_dir,
+            undo_cleanup,
             write_binary_file,
+            // Output commands
+            prepare_output_dir,
+            resolve_output_dir,
+            prepare_archival_output,
+            write_json_output,
+            write_xml_output,
+            write_combined_output,
+            write_docx_output,
+            package_outputs,
+            write_baheth_export,
+            upload_outputs_to_storage,
+            upload_outputs_to_webdav,
+            email_job_results,
+            can_share_output,
+            share_output,
+            export_job_archive,
+            import_job_archive,
+            append_page_output,
+            load_partial_output,
+            finalize_partial_output,
+            start_live_txt,
+            append_live_txt,
+            finish_live_txt,
+            verify_outputs,
+            spool_pages,
+            write_spooled_outputs,
+            discard_spool,
+            postprocess_pages,
+            apply_postprocessing,
+            preview_postprocessing,
+            generate_job_report,
+            plan_dual_pass,
+            extract_tables,
+            order_columns,
+            split_page_columns,
+            split_page_footnotes,
+            mask_page_image,
+            correct_page_orientation,
+            crop_page_regions,
+            separate_footnotes,
+            detect_duplicate_pages,
+            // Settings commands
+            get_settings,
+            update_settings,
+            // Profile commands
+            list_profiles,
+            create_profile,
+            switch_profile,
+            // Search commands
+            index_document,
+            search_documents,
+            remove_indexed_document,
             // Utility commands
+            copy_text_to_clipboard,
             open_folder,
+            reveal_in_folder,
+            open_file,
+            get_recent_logs,
+            set_log_level,
+            create_diagnostics_bundle,
+            get_app_info,
+            get_app_paths,
+            get_crash_recovery,
+            dismiss_crash_report,
+            open_crash_report,
+            update_tray_status,
+            take_launch_files,
+            expand_dropped_paths,
+            check_connectivity,
+            check_backend,
+            get_disk_space,
+            get_file_info,
+            get_recent_files,
+            record_recent_file,
+            pin_recent_file,
+            clear_recent_files,
+            get_performance_summary,
+            get_power_status,
+            get_quota_status,
+            get_storage_usage,
+            run_maintenance_now,
+            stage_input_file,
+            process_url,
+            process_drive_file,
+            list_scanners,
+            scan_pages,
+            record_job,
+            apply_corrections,
+            record_job_event,
+            get_job_events,
+            record_partial_page,
+            record_job_stop,
+            get_partial_results,
+            assemble_partial_results,
+            begin_job,
+            end_job,
+            take_interrupted_jobs,
+            discard_job,
+            set_job_tags,
+            set_job_note,
+            get_job_labels,
+            search_jobs_by_tag,
+            get_job_details,
+            subscribe_job_events,
+            unsubscribe_job_events,
+            export_history,
+            record_job_throughput,
+            get_throughput_estimate,
+            detect_page_languages,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| launch::handle_run_event(app, &event));
 }
 
 #[cfg(test)]
@@ -58,7 +366,7 @@ mod tests {
         match result {
             Ok(()) => {}
             Err(e) => {
-                assert!(e.contains("Failed to open folder"));
+                assert!(e.to_string().contains("Failed to open folder"));
             }
         }
     }
@@ -0,0 +1,332 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
+
+const LOG_FILE_PREFIX: &str = "tahweel";
+const LOG_FILE_SUFFIX: &str = "log";
+/// Daily files kept on disk before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+const DEFAULT_RECENT_LINES: usize = 500;
+const MAX_RECENT_LINES: usize = 5000;
+const REDACTED: &str = "[REDACTED]";
+
+/// Substrings that precede a secret. Google access tokens start with `ya29.`,
+/// refresh tokens with `1//`; the rest are header and form/JSON field names.
+const SECRET_MARKERS: &[&str] = &[
+    "ya29.",
+    "1//",
+    "Bearer ",
+    "access_token",
+    "refresh_token",
+    "client_secret",
+    "code=",
+];
+
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+static LEVEL_HANDLE: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+/// Keeps the background writer alive (and flushing) for the life of the process
+static WORKER_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/' | '~' | '+')
+}
+
+/// Mask OAuth tokens, secrets, and authorization codes in a log line
+pub fn redact(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    loop {
+        let next = SECRET_MARKERS
+            .iter()
+            .filter_map(|marker| rest.find(marker).map(|i| (i, *marker)))
+            .min_by_key(|(i, _)| *i);
+        let Some((index, marker)) = next else {
+            out.push_str(rest);
+            return out;
+        };
+
+        let after_marker = index + marker.len();
+        out.push_str(&rest[..after_marker]);
+
+        // Skip `": "` / `=` between a field name and its value
+        let tail = &rest[after_marker..];
+        let value = tail.trim_start_matches(['"', ':', '=', ' ']);
+        out.push_str(&tail[..tail.len() - value.len()]);
+
+        let remainder = value.trim_start_matches(is_token_char);
+        if remainder.len() < value.len() {
+            out.push_str(REDACTED);
+        }
+        rest = remainder;
+    }
+}
+
+/// Writer that redacts each formatted event before it reaches the log file
+pub struct RedactingWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let line = String::from_utf8_lossy(buf);
+        self.inner.write_all(redact(&line).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub struct RedactingMakeWriter<M> {
+    inner: M,
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            inner: self.inner.make_writer(),
+        }
+    }
+}
+
+/// Install the global subscriber writing daily-rotated files into `log_dir`
+pub fn init(log_dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(log_dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir)
+        .map_err(|e| format!("Failed to create log file appender: {}", e))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    let default_level = if cfg!(debug_assertions) {
+        LevelFilter::DEBUG
+    } else {
+        LevelFilter::INFO
+    };
+    let (level_layer, handle) = reload::Layer::new(default_level);
+
+    tracing_subscriber::registry()
+        .with(level_layer)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_target(false)
+                .with_writer(RedactingMakeWriter { inner: writer }),
+        )
+        .try_init()
+        .map_err(|e| format!("Failed to install log subscriber: {}", e))?;
+
+    let _ = LOG_DIR.set(log_dir.to_path_buf());
+    let _ = LEVEL_HANDLE.set(handle);
+    let _ = WORKER_GUARD.set(guard);
+    Ok(())
+}
+
+/// Parse a level name (`off`, `error`, `warn`, `info`, `debug`, `trace`)
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .trim()
+        .to_lowercase()
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("Unknown log level: {}", level))
+}
+
+/// Log files in `dir`, oldest first (the date suffix sorts chronologically)
+fn log_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read log directory: {}", e)),
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(LOG_FILE_PREFIX) && n.ends_with(LOG_FILE_SUFFIX))
+                .unwrap_or(false)
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Last `count` lines across the log files in `dir`, oldest first
+pub fn read_recent_lines(dir: &Path, count: usize) -> Result<Vec<String>, String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for path in log_files(dir)?.iter().rev() {
+        if lines.len() >= count {
+            break;
+        }
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read log file: {}", e))?;
+        let content = String::from_utf8_lossy(&bytes);
+        let needed = count - lines.len();
+        let mut file_lines: Vec<String> = content
+            .lines()
+            .rev()
+            .take(needed)
+            .map(String::from)
+            .collect();
+        file_lines.reverse();
+        file_lines.append(&mut lines);
+        lines = file_lines;
+    }
+
+    Ok(lines)
+}
+
+/// Page number encoded in a rendered page image name (`page-0007.png` -> 7)
+pub fn page_from_path(path: &str) -> Option<u32> {
+    let stem = Path::new(path).file_stem()?.to_str()?;
+    stem.strip_prefix("page-")?.parse().ok()
+}
+
+/// Directory log files are written to, once logging is initialized
+pub fn log_dir() -> Option<&'static Path> {
+    LOG_DIR.get().map(PathBuf::as_path)
+}
+
+/// Return the most recent log lines (default 500) for in-app troubleshooting
+#[tauri::command]
+pub async fn get_recent_logs(lines: Option<usize>) -> Result<Vec<String>, String> {
+    let Some(dir) = log_dir() else {
+        return Ok(Vec::new());
+    };
+    let count = lines.unwrap_or(DEFAULT_RECENT_LINES).min(MAX_RECENT_LINES);
+    read_recent_lines(dir, count)
+}
+
+/// Change the log verbosity at runtime
+#[tauri::command]
+pub async fn set_log_level(level: String) -> Result<(), String> {
+    let filter = parse_level(&level)?;
+    let handle = LEVEL_HANDLE
+        .get()
+        .ok_or("Logging is not initialized".to_string())?;
+    handle
+        .reload(filter)
+        .map_err(|e| format!("Failed to set log level: {}", e))?;
+    tracing::info!(level = %filter, "Log level changed");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_redact_bearer_header() {
+        assert_eq!(
+            redact("authorization: Bearer abc.DEF-123"),
+            "authorization: Bearer [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn test_redact_google_token_prefixes() {
+        assert_eq!(
+            redact("token ya29.a0AfH6SMB_x-y and 1//0gABC-def"),
+            "token ya29.[REDACTED] and 1//[REDACTED]"
+        );
+    }
+
+    #[test]
+    fn test_redact_json_fields() {
+        let line = r#"{"access_token": "secret1", "refresh_token":"secret2", "expires_in": 3599}"#;
+        assert_eq!(
+            redact(line),
+            r#"{"access_token": "[REDACTED]", "refresh_token":"[REDACTED]", "expires_in": 3599}"#
+        );
+    }
+
+    #[test]
+    fn test_redact_form_fields() {
+        assert_eq!(
+            redact("GET /?code=4/0AX4XfW&scope=email HTTP/1.1"),
+            "GET /?code=[REDACTED]&scope=email HTTP/1.1"
+        );
+        assert_eq!(
+            redact("client_secret=GOCSPX-abc&grant_type=refresh_token"),
+            "client_secret=[REDACTED]&grant_type=refresh_token"
+        );
+    }
+
+    #[test]
+    fn test_redact_leaves_plain_text() {
+        let line = "Uploaded page 3 in 1200ms (retries=0)";
+        assert_eq!(redact(line), line);
+    }
+
+    #[test]
+    fn test_redact_handles_multibyte_text() {
+        assert_eq!(
+            redact("نص عربي Bearer xyz نهاية"),
+            "نص عربي Bearer [REDACTED] نهاية"
+        );
+    }
+
+    #[test]
+    fn test_redacting_writer() {
+        let mut writer = RedactingWriter { inner: Vec::new() };
+        writer.write_all(b"Bearer secret\n").unwrap();
+        assert_eq!(
+            String::from_utf8(writer.inner).unwrap(),
+            "Bearer [REDACTED]\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_level("debug").unwrap(), LevelFilter::DEBUG);
+        assert_eq!(parse_level(" WARN ").unwrap(), LevelFilter::WARN);
+        assert_eq!(parse_level("off").unwrap(), LevelFilter::OFF);
+        assert!(parse_level("verbose").is_err());
+    }
+
+    #[test]
+    fn test_page_from_path() {
+        assert_eq!(page_from_path("/tmp/x/page-0007.png"), Some(7));
+        assert_eq!(page_from_path("/tmp/x/scan.png"), None);
+        assert_eq!(page_from_path("/tmp/x/page-abc.png"), None);
+    }
+
+    #[test]
+    fn test_read_recent_lines_spans_files() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("tahweel.2024-01-01.log"), "a\nb\nc\n").unwrap();
+        fs::write(dir.path().join("tahweel.2024-01-02.log"), "d\ne\n").unwrap();
+        fs::write(dir.path().join("other.txt"), "ignored\n").unwrap();
+
+        assert_eq!(
+            read_recent_lines(dir.path(), 3).unwrap(),
+            vec!["c", "d", "e"]
+        );
+        assert_eq!(read_recent_lines(dir.path(), 100).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_read_recent_lines_missing_dir() {
+        let dir = TempDir::new().unwrap();
+        let lines = read_recent_lines(&dir.path().join("missing"), 10).unwrap();
+        assert!(lines.is_empty());
+    }
+}
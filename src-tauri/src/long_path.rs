@@ -0,0 +1,191 @@
+use crate::error::TahweelError;
+use std::path::{Path, PathBuf};
+
+/// Longest path (in UTF-16 units) Windows APIs accept without the `\\?\` prefix.
+/// Directories are limited to MAX_PATH (260) minus room for an 8.3 file name.
+const MAX_UNPREFIXED: usize = 260 - 12;
+
+const VERBATIM: &str = r"\\?\";
+const VERBATIM_UNC: &str = r"\\?\UNC\";
+
+/// Resolve `.` and `..` and use backslashes: verbatim paths skip Windows'
+/// own normalization, so it has to happen before the prefix is added
+fn normalize_components(rest: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in rest.split(['\\', '/']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("\\")
+}
+
+/// The `\\?\` form of an absolute Windows path that is too long for MAX_PATH.
+/// Returns `None` when no prefix is needed or possible (short, relative, or
+/// already verbatim/device paths).
+pub fn extended_path_str(path: &str) -> Option<String> {
+    if path.starts_with(VERBATIM) || path.starts_with(r"\\.\") {
+        return None;
+    }
+    if path.encode_utf16().count() < MAX_UNPREFIXED {
+        return None;
+    }
+
+    let bytes = path.as_bytes();
+    let is_drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/');
+    if is_drive {
+        return Some(format!(
+            "{}{}\\{}",
+            VERBATIM,
+            &path[..2],
+            normalize_components(&path[3..])
+        ));
+    }
+
+    let unc = path
+        .strip_prefix(r"\\")
+        .or_else(|| path.strip_prefix("//"))?;
+    let normalized = normalize_components(unc);
+    // A UNC path needs at least `server\share`
+    if normalized.split('\\').count() < 2 {
+        return None;
+    }
+    Some(format!("{}{}", VERBATIM_UNC, normalized))
+}
+
+/// Path to hand to file APIs: long absolute paths get the `\\?\` prefix on Windows,
+/// other paths (and every path elsewhere) are returned as they are
+pub fn extended(path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        if let Some(prefixed) = path.to_str().and_then(extended_path_str) {
+            return PathBuf::from(prefixed);
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Drop a `\\?\` prefix so paths shown to the user (and sent back by the
+/// frontend) keep their familiar form; `extended` re-adds it when needed
+pub fn strip_extended_prefix(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix(VERBATIM_UNC) {
+        return format!(r"\\{}", rest);
+    }
+    path.strip_prefix(VERBATIM).unwrap_or(path).to_string()
+}
+
+/// Convert a path for the frontend without `to_string_lossy`: a path that is not
+/// valid Unicode is an error instead of a silently different path
+pub fn path_string(path: &Path) -> Result<String, TahweelError> {
+    path.to_str().map(strip_extended_prefix).ok_or_else(|| {
+        TahweelError::InvalidInput(format!("Path is not valid Unicode: {}", path.display()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn long_windows_dir() -> String {
+        let mut path = r"C:\Users\reader\كتب".to_string();
+        while path.encode_utf16().count() < 300 {
+            path.push_str(r"\مجلد طويل 📚");
+        }
+        path
+    }
+
+    #[test]
+    fn test_short_paths_are_unchanged() {
+        assert_eq!(extended_path_str(r"C:\books\كتاب.pdf"), None);
+        assert_eq!(extended_path_str(r"\\server\share\a.pdf"), None);
+    }
+
+    #[test]
+    fn test_long_drive_path_gets_prefix() {
+        let dir = long_windows_dir();
+        let prefixed = extended_path_str(&format!(r"{}\book.pdf", dir)).unwrap();
+        assert!(prefixed.starts_with(r"\\?\C:\Users\reader\كتب\"));
+        assert!(prefixed.ends_with(r"\book.pdf"));
+        assert!(!prefixed[4..].contains('/'));
+    }
+
+    #[test]
+    fn test_long_path_is_normalized() {
+        let dir = long_windows_dir();
+        let prefixed = extended_path_str(&format!(
+            r"{}/./extra\..\out//book.pdf",
+            dir.replace('\\', "/")
+        ))
+        .unwrap();
+        assert_eq!(prefixed, format!(r"\\?\{}\out\book.pdf", dir));
+    }
+
+    #[test]
+    fn test_long_unc_path_gets_unc_prefix() {
+        let dir = long_windows_dir().replacen(r"C:\", r"\\server\share\", 1);
+        let prefixed = extended_path_str(&dir).unwrap();
+        assert!(prefixed.starts_with(r"\\?\UNC\server\share\Users\"));
+        assert_eq!(strip_extended_prefix(&prefixed), dir);
+    }
+
+    #[test]
+    fn test_length_counts_utf16_units() {
+        // Emoji are two UTF-16 units each, so this crosses the limit below 248 chars
+        let path = format!(r"C:\{}", "📚".repeat(125));
+        assert!(path.chars().count() < MAX_UNPREFIXED);
+        assert!(extended_path_str(&path).is_some());
+    }
+
+    #[test]
+    fn test_relative_and_verbatim_paths_are_left_alone() {
+        let long_name = "a".repeat(300);
+        assert_eq!(extended_path_str(&format!(r"books\{}", long_name)), None);
+        assert_eq!(extended_path_str(&format!(r"\\?\C:\{}", long_name)), None);
+        assert_eq!(extended_path_str(&format!(r"\\.\pipe\{}", long_name)), None);
+    }
+
+    #[test]
+    fn test_strip_extended_prefix() {
+        assert_eq!(strip_extended_prefix(r"\\?\C:\a\b.pdf"), r"C:\a\b.pdf");
+        assert_eq!(
+            strip_extended_prefix(r"\\?\UNC\srv\share\a"),
+            r"\\srv\share\a"
+        );
+        assert_eq!(strip_extended_prefix("/home/a/b.pdf"), "/home/a/b.pdf");
+    }
+
+    #[test]
+    fn test_path_string_keeps_exotic_names_and_long_paths() {
+        let temp = tempdir().unwrap();
+        let mut dir = temp.path().to_path_buf();
+        while dir.as_os_str().len() < 300 {
+            dir.push("مجلد عميق 📚 folder");
+        }
+        fs::create_dir_all(extended(&dir)).unwrap();
+        let file = dir.join("كتاب 😀.pdf");
+        fs::write(extended(&file), b"%PDF").unwrap();
+
+        let as_string = path_string(&file).unwrap();
+        assert!(as_string.len() > 260);
+        assert!(as_string.ends_with("كتاب 😀.pdf"));
+        assert_eq!(fs::read(extended(Path::new(&as_string))).unwrap(), b"%PDF");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_path_string_rejects_non_unicode() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/tmp/\xff.pdf"));
+        assert_eq!(path_string(path).unwrap_err().kind(), "invalidInput");
+    }
+}
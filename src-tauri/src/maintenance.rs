@@ -0,0 +1,575 @@
+use crate::app_paths::{self, Area};
+use crate::error::TahweelError;
+use crate::job_db::{db_error, JobDb};
+use crate::jobs;
+use crate::path_policy;
+use crate::settings::{self, RetentionPolicy, RetentionSettings};
+use crate::trash;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// First maintenance run after startup, out of the way of launch work
+const STARTUP_DELAY: Duration = Duration::from_secs(2 * 60);
+/// Time between maintenance runs
+const INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Entries changed this recently are never removed: a running job may own them
+const ACTIVE_GRACE_SECS: u64 = 6 * 60 * 60;
+/// Share of the remaining jobs dropped per step while the history is too large
+const HISTORY_PRUNE_FRACTION: usize = 10;
+/// Cache files the app cannot do without (the sign-in token)
+const PROTECTED_CACHE: &[&str] = &["token.json"];
+
+/// Only one maintenance run at a time
+static RUNNING: Mutex<()> = Mutex::new(());
+
+/// Stores with a retention policy
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Store {
+    /// Rendered pages, downloads, spools and captures left in the workspace
+    Workspace,
+    /// The layout's `cache` area
+    Cache,
+    /// The job database
+    History,
+    /// Log files and crash reports
+    Logs,
+}
+
+pub const STORES: [Store; 4] = [Store::Workspace, Store::Cache, Store::History, Store::Logs];
+
+impl Store {
+    pub fn policy(self, retention: &RetentionSettings) -> RetentionPolicy {
+        match self {
+            Store::Workspace => retention.workspace,
+            Store::Cache => retention.cache,
+            Store::History => retention.history,
+            Store::Logs => retention.logs,
+        }
+    }
+
+    /// Folder (or, for the history, database file) the store lives in
+    pub fn path(self) -> PathBuf {
+        match self {
+            Store::Workspace => path_policy::workspace(),
+            Store::Cache => app_paths::scoped_dir(Area::Cache),
+            Store::History => jobs::jobs_db_path(),
+            Store::Logs => app_paths::dir(Area::Logs),
+        }
+    }
+}
+
+/// Size of a store, for the settings panel
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StoreUsage {
+    pub store: Store,
+    pub path: String,
+    pub bytes: u64,
+    /// Top-level files and folders (jobs, for the history)
+    pub entries: u32,
+    pub policy: RetentionPolicy,
+}
+
+/// What a maintenance run removed from one store
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StoreCleanup {
+    pub store: Store,
+    /// Files and folders (jobs, for the history) removed
+    pub removed: u32,
+    #[serde(rename = "freedBytes")]
+    pub freed_bytes: u64,
+    /// Not cleaned this time (the workspace while jobs run)
+    pub skipped: bool,
+}
+
+/// Result of a maintenance run
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MaintenanceReport {
+    pub stores: Vec<StoreCleanup>,
+}
+
+/// A top-level file or folder of a store
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Seconds since the Unix epoch
+    pub modified_secs: u64,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Bytes under `path` and its newest modification time; links are not followed
+fn measure(path: &Path) -> (u64, u64) {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return (0, 0);
+    };
+    let mut bytes = metadata.len();
+    let mut newest = modified_secs(&metadata);
+    if metadata.is_dir() {
+        for child in fs::read_dir(path).into_iter().flatten().flatten() {
+            let (child_bytes, child_newest) = measure(&child.path());
+            bytes += child_bytes;
+            newest = newest.max(child_newest);
+        }
+    }
+    (bytes, newest)
+}
+
+/// Top-level entries of `dir`, except those named in `protected`
+pub fn list_entries(dir: &Path, protected: &[&str]) -> Vec<Entry> {
+    let Ok(read) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    read.flatten()
+        .filter(|entry| {
+            !protected
+                .iter()
+                .any(|name| entry.file_name() == std::ffi::OsStr::new(name))
+        })
+        .map(|entry| {
+            let path = entry.path();
+            let (bytes, modified_secs) = measure(&path);
+            Entry {
+                path,
+                bytes,
+                modified_secs,
+            }
+        })
+        .collect()
+}
+
+/// Entries to remove under `policy`: those older than its age limit, then the
+/// oldest while the total is over its size limit. Entries changed within
+/// `ACTIVE_GRACE_SECS` are kept either way.
+pub fn plan_removals(entries: &[Entry], policy: RetentionPolicy, now_secs: u64) -> Vec<PathBuf> {
+    let mut by_age: Vec<&Entry> = entries.iter().collect();
+    by_age.sort_by_key(|entry| entry.modified_secs);
+    let removable = |entry: &Entry| entry.modified_secs + ACTIVE_GRACE_SECS <= now_secs;
+    let max_age_secs = u64::from(policy.max_age_days) * 86_400;
+    let max_bytes = u64::from(policy.max_size_mb) * 1024 * 1024;
+
+    let mut total: u64 = entries.iter().map(|entry| entry.bytes).sum();
+    let mut removals = Vec::new();
+    for entry in by_age {
+        if !removable(entry) {
+            continue;
+        }
+        let expired = policy.max_age_days > 0 && entry.modified_secs + max_age_secs <= now_secs;
+        let over_size = policy.max_size_mb > 0 && total > max_bytes;
+        if expired || over_size {
+            total -= entry.bytes;
+            removals.push(entry.path.clone());
+        }
+    }
+    removals
+}
+
+fn remove_entry(path: &Path) -> std::io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Removable entries of a file-based store. Crash reports count as log
+/// entries one by one; the trash purges itself and the sign-in token is kept.
+pub fn store_entries(store: Store) -> Vec<Entry> {
+    match store {
+        Store::Workspace => list_entries(&store.path(), &[trash::TRASH_DIR]),
+        Store::Cache => list_entries(&store.path(), PROTECTED_CACHE),
+        Store::Logs => {
+            let mut entries = list_entries(&store.path(), &[app_paths::CRASHES_DIR]);
+            entries.extend(list_entries(&app_paths::crash_dir(), &[]));
+            entries
+        }
+        Store::History => Vec::new(),
+    }
+}
+
+/// Enforce `policy` on `entries`, removing files and whole folders
+pub fn clean_entries(
+    store: Store,
+    entries: &[Entry],
+    policy: RetentionPolicy,
+    now_secs: u64,
+) -> StoreCleanup {
+    let mut cleanup = StoreCleanup {
+        store,
+        removed: 0,
+        freed_bytes: 0,
+        skipped: false,
+    };
+    for path in plan_removals(entries, policy, now_secs) {
+        let bytes = entries
+            .iter()
+            .find(|entry| entry.path == path)
+            .map_or(0, |entry| entry.bytes);
+        match remove_entry(&path) {
+            Ok(()) => {
+                cleanup.removed += 1;
+                cleanup.freed_bytes += bytes;
+            }
+            // Likely still open (a log being written on Windows); next run retries
+            Err(error) => {
+                tracing::warn!(path = %path.display(), %error, "Failed to remove expired entry")
+            }
+        }
+    }
+    cleanup
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Compact the job database and fold its write-ahead log back into the file,
+/// so the file size reflects what was deleted
+fn compact(conn: &rusqlite::Connection) -> Result<(), TahweelError> {
+    conn.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")
+        .map_err(db_error)
+}
+
+/// Enforce `policy` on the job database `db`: jobs last recorded before the
+/// age limit are deleted, then the oldest while the file is over the size
+/// limit. The file is compacted after deleting. Runs as one queued write, so
+/// no job is recorded halfway through.
+pub fn clean_history(
+    db: &JobDb,
+    policy: RetentionPolicy,
+    now_secs: u64,
+) -> Result<StoreCleanup, TahweelError> {
+    let mut cleanup = StoreCleanup {
+        store: Store::History,
+        removed: 0,
+        freed_bytes: 0,
+        skipped: false,
+    };
+    if policy.max_age_days == 0 && policy.max_size_mb == 0 {
+        return Ok(cleanup);
+    }
+    let db_path = db.path().to_path_buf();
+    db.write(move |conn| {
+        let size_before = file_size(&db_path);
+
+        if policy.max_age_days > 0 {
+            let cutoff = now_secs.saturating_sub(u64::from(policy.max_age_days) * 86_400) as i64;
+            let expired = jobs::jobs_oldest_first(conn, Some(cutoff))?;
+            jobs::delete_jobs(conn, &expired)?;
+            jobs::delete_orphan_events(conn, cutoff * 1000)?;
+            cleanup.removed += expired.len() as u32;
+            compact(conn)?;
+        }
+
+        let max_bytes = u64::from(policy.max_size_mb) * 1024 * 1024;
+        while policy.max_size_mb > 0 && file_size(&db_path) > max_bytes {
+            let remaining = jobs::jobs_oldest_first(conn, None)?;
+            if remaining.is_empty() {
+                break;
+            }
+            let batch = (remaining.len() / HISTORY_PRUNE_FRACTION).max(1);
+            jobs::delete_jobs(conn, &remaining[..batch])?;
+            cleanup.removed += batch as u32;
+            compact(conn)?;
+        }
+
+        cleanup.freed_bytes = size_before.saturating_sub(file_size(&db_path));
+        Ok(cleanup)
+    })
+}
+
+/// Enforce every store's retention policy. The workspace is left alone while
+/// a job runs, as its files may still be needed.
+pub fn run(retention: &RetentionSettings) -> MaintenanceReport {
+    let _running = RUNNING.lock().unwrap_or_else(|p| p.into_inner());
+    let now = now_secs();
+    let jobs_running = jobs::db()
+        .and_then(|db| db.read(jobs::has_running))
+        .unwrap_or(true);
+
+    let stores = STORES
+        .iter()
+        .map(|&store| {
+            let policy = store.policy(retention);
+            let cleanup = match store {
+                Store::Workspace if jobs_running => StoreCleanup {
+                    store,
+                    removed: 0,
+                    freed_bytes: 0,
+                    skipped: true,
+                },
+                Store::Workspace | Store::Cache | Store::Logs => {
+                    clean_entries(store, &store_entries(store), policy, now)
+                }
+                Store::History => jobs::db()
+                    .and_then(|db| clean_history(&db, policy, now))
+                    .unwrap_or_else(|error| {
+                        tracing::warn!(%error, "Failed to clean the job history");
+                        StoreCleanup {
+                            store,
+                            removed: 0,
+                            freed_bytes: 0,
+                            skipped: true,
+                        }
+                    }),
+            };
+            if cleanup.removed > 0 {
+                tracing::info!(
+                    ?store,
+                    removed = cleanup.removed,
+                    freed_bytes = cleanup.freed_bytes,
+                    "Maintenance cleaned store"
+                );
+            }
+            cleanup
+        })
+        .collect();
+    MaintenanceReport { stores }
+}
+
+/// Size and entry count of `store`
+pub fn usage(store: Store, retention: &RetentionSettings) -> Result<StoreUsage, TahweelError> {
+    let path = store.path();
+    let (bytes, entries) = match store {
+        Store::History => {
+            let entries = if path.exists() {
+                jobs::db()?
+                    .read(|conn| jobs::jobs_oldest_first(conn, None))?
+                    .len() as u32
+            } else {
+                0
+            };
+            (file_size(&path), entries)
+        }
+        _ => {
+            let entries = store_entries(store);
+            (
+                entries.iter().map(|entry| entry.bytes).sum(),
+                entries.len() as u32,
+            )
+        }
+    };
+    Ok(StoreUsage {
+        store,
+        path: path.to_string_lossy().to_string(),
+        bytes,
+        entries,
+        policy: store.policy(retention),
+    })
+}
+
+/// Run maintenance `STARTUP_DELAY` after startup, then every `INTERVAL`;
+/// call once from `setup`
+pub fn init() {
+    let spawned = std::thread::Builder::new()
+        .name("maintenance".to_string())
+        .spawn(|| {
+            std::thread::sleep(STARTUP_DELAY);
+            loop {
+                run(&settings::current().retention);
+                std::thread::sleep(INTERVAL);
+            }
+        });
+    if let Err(error) = spawned {
+        tracing::warn!(%error, "Failed to start the maintenance task");
+    }
+}
+
+/// Enforce the retention policies now instead of waiting for the next run
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn run_maintenance_now() -> Result<MaintenanceReport, TahweelError> {
+    tauri::async_runtime::spawn_blocking(|| run(&settings::current().retention))
+        .await
+        .map_err(|e| TahweelError::Internal(format!("Maintenance failed: {}", e)))
+}
+
+/// How much each store holds, with its retention policy
+#[tauri::command]
+pub async fn get_storage_usage() -> Result<Vec<StoreUsage>, TahweelError> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let retention = settings::current().retention;
+        STORES
+            .iter()
+            .map(|&store| usage(store, &retention))
+            .collect()
+    })
+    .await
+    .map_err(|e| TahweelError::Internal(format!("Failed to measure storage: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::{DocxOptions, JobRecord};
+    use crate::output::OutputFormat;
+    use rusqlite::params;
+    use tempfile::tempdir;
+
+    const DAY: u64 = 86_400;
+    const NOW: u64 = 1_000 * DAY;
+    const MB: u64 = 1024 * 1024;
+
+    fn entry(name: &str, megabytes: u64, age_days: u64) -> Entry {
+        Entry {
+            path: PathBuf::from(name),
+            bytes: megabytes * MB,
+            modified_secs: NOW - age_days * DAY,
+        }
+    }
+
+    fn names(paths: Vec<PathBuf>) -> Vec<String> {
+        paths
+            .into_iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_removals_by_age() {
+        let entries = [entry("old", 1, 10), entry("new", 1, 2)];
+        assert_eq!(
+            names(plan_removals(&entries, RetentionPolicy::new(7, 0), NOW)),
+            vec!["old"]
+        );
+        assert!(plan_removals(&entries, RetentionPolicy::new(0, 0), NOW).is_empty());
+    }
+
+    #[test]
+    fn test_plan_removals_by_size_removes_oldest_first() {
+        let entries = [entry("b", 30, 3), entry("a", 30, 5), entry("c", 30, 1)];
+        assert_eq!(
+            names(plan_removals(&entries, RetentionPolicy::new(0, 50), NOW)),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn test_plan_removals_keeps_recent_entries() {
+        let active = Entry {
+            modified_secs: NOW - 60,
+            ..entry("active", 500, 0)
+        };
+        let entries = [active, entry("old", 1, 1)];
+        assert_eq!(
+            names(plan_removals(&entries, RetentionPolicy::new(0, 10), NOW)),
+            vec!["old"]
+        );
+    }
+
+    #[test]
+    fn test_clean_entries_skips_protected_entries() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("token.json"), "{}").unwrap();
+        fs::create_dir(dir.path().join("tahweel-render")).unwrap();
+        fs::write(
+            dir.path().join("tahweel-render").join("page-0001.png"),
+            [0; 64],
+        )
+        .unwrap();
+
+        let later = now_secs() + 400 * DAY;
+        let entries = list_entries(dir.path(), PROTECTED_CACHE);
+        assert_eq!(entries.len(), 1);
+        let cleanup = clean_entries(Store::Cache, &entries, RetentionPolicy::new(30, 0), later);
+        assert_eq!(cleanup.removed, 1);
+        assert!(cleanup.freed_bytes >= 64);
+        assert!(dir.path().join("token.json").exists());
+        assert!(!dir.path().join("tahweel-render").exists());
+    }
+
+    fn record_job(conn: &mut rusqlite::Connection, id: &str, updated_at: i64) {
+        jobs::record(
+            conn,
+            &JobRecord {
+                id: id.to_string(),
+                source_path: format!("/books/{}.pdf", id),
+                output_path: format!("/out/{}", id),
+                formats: vec![OutputFormat::Txt],
+                page_separator: "\n".to_string(),
+                pages: vec!["نص".repeat(2000); 20],
+                docx: DocxOptions::default(),
+            },
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE jobs SET updated_at = ?1 WHERE id = ?2",
+            params![updated_at, id],
+        )
+        .unwrap();
+        jobs::set_tags(conn, id, &["waqf".to_string()]).unwrap();
+    }
+
+    #[test]
+    fn test_clean_history_by_age() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("jobs.db");
+        let mut conn = jobs::open_db(&db_path).unwrap();
+        record_job(&mut conn, "old", (NOW - 100 * DAY) as i64);
+        record_job(&mut conn, "new", (NOW - DAY) as i64);
+        drop(conn);
+
+        let db = JobDb::open(&db_path, jobs::init_schema).unwrap();
+        let cleanup = clean_history(&db, RetentionPolicy::new(30, 0), NOW).unwrap();
+        assert_eq!(cleanup.removed, 1);
+        let conn = jobs::open_db(&db_path).unwrap();
+        assert_eq!(jobs::jobs_oldest_first(&conn, None).unwrap(), vec!["new"]);
+        assert!(jobs::load_labels(&conn, "old").unwrap().tags.is_empty());
+        assert!(jobs::load(&conn, "old").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_clean_history_by_size_drops_oldest_jobs() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("jobs.db");
+        let mut conn = jobs::open_db(&db_path).unwrap();
+        for day in 0..40 {
+            record_job(&mut conn, &format!("job-{:02}", day), day);
+        }
+        drop(conn);
+        assert!(file_size(&db_path) > MB);
+
+        let db = JobDb::open(&db_path, jobs::init_schema).unwrap();
+        let cleanup = clean_history(&db, RetentionPolicy::new(0, 1), NOW).unwrap();
+        assert!(cleanup.removed > 0);
+        assert!(cleanup.freed_bytes > 0);
+        assert!(file_size(&db_path) <= MB);
+        let conn = jobs::open_db(&db_path).unwrap();
+        let remaining = jobs::jobs_oldest_first(&conn, None).unwrap();
+        assert_eq!(remaining.last().map(String::as_str), Some("job-39"));
+        assert!(!remaining.contains(&"job-00".to_string()));
+    }
+
+    #[test]
+    fn test_report_serialization() {
+        let report = MaintenanceReport {
+            stores: vec![StoreCleanup {
+                store: Store::Logs,
+                removed: 2,
+                freed_bytes: 2048,
+                skipped: false,
+            }],
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["stores"][0]["store"], "logs");
+        assert_eq!(json["stores"][0]["freedBytes"], 2048);
+    }
+}
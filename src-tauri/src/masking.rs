@@ -0,0 +1,179 @@
+use crate::encryption;
+use crate::error::TahweelError;
+use image::{DynamicImage, GenericImage, Rgba};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A rectangle (signature, stamp) blanked on rendered pages before upload.
+/// Coordinates are percentages of the page width and height, so the same
+/// region fits every DPI.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct MaskRegion {
+    /// 1-based page the region applies to; every page when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl MaskRegion {
+    pub fn applies_to(&self, page: u32) -> bool {
+        self.page.map_or(true, |p| p == page)
+    }
+
+    /// Pixel bounds `(left, top, right, bottom)` on a `width`x`height` image,
+    /// clamped to it; None when nothing of the region is on the image
+    pub fn pixel_bounds(&self, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+        let scale = |percent: f64, size: u32| {
+            ((percent.clamp(0.0, 100.0) / 100.0) * size as f64).round() as u32
+        };
+        let left = scale(self.x, width);
+        let top = scale(self.y, height);
+        let right = scale(self.x + self.width, width);
+        let bottom = scale(self.y + self.height, height);
+        (left < right && top < bottom).then_some((left, top, right, bottom))
+    }
+}
+
+/// Fill `regions` of `image` with white
+pub fn mask_image(image: &mut DynamicImage, regions: &[MaskRegion]) {
+    let (width, height) = (image.width(), image.height());
+    for (left, top, right, bottom) in regions
+        .iter()
+        .filter_map(|region| region.pixel_bounds(width, height))
+    {
+        for y in top..bottom {
+            for x in left..right {
+                image.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+        }
+    }
+}
+
+/// Blank the regions that apply to `page` on a rendered page image, writing
+/// `page-0001-masked.png` next to it (the original is kept for `keepPageImages`).
+/// Returns the original path when no region applies to the page.
+#[tauri::command]
+pub async fn mask_page_image(
+    image_path: String,
+    page: u32,
+    regions: Vec<MaskRegion>,
+) -> Result<String, TahweelError> {
+    let regions: Vec<MaskRegion> = regions
+        .into_iter()
+        .filter(|region| region.applies_to(page))
+        .collect();
+    if regions.is_empty() {
+        return Ok(image_path);
+    }
+
+    let path = Path::new(&image_path);
+    let (mut image, key) = encryption::open_image(path)?;
+    mask_image(&mut image, &regions);
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let masked = path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(format!("{}-masked.png", stem));
+    encryption::save_png(&image, &masked, key)?;
+    Ok(masked.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Luma, RgbImage};
+    use tempfile::tempdir;
+
+    fn region(page: Option<u32>, x: f64, y: f64, width: f64, height: f64) -> MaskRegion {
+        MaskRegion {
+            page,
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_pixel_bounds_scale_and_clamp() {
+        assert_eq!(
+            region(None, 50.0, 75.0, 50.0, 25.0).pixel_bounds(200, 400),
+            Some((100, 300, 200, 400))
+        );
+        assert_eq!(
+            region(None, 90.0, -10.0, 30.0, 20.0).pixel_bounds(100, 100),
+            Some((90, 0, 100, 10))
+        );
+        assert_eq!(
+            region(None, 10.0, 10.0, 0.0, 5.0).pixel_bounds(100, 100),
+            None
+        );
+        assert_eq!(
+            region(None, 120.0, 0.0, 10.0, 10.0).pixel_bounds(100, 100),
+            None
+        );
+    }
+
+    #[test]
+    fn test_applies_to_page() {
+        assert!(region(None, 0.0, 0.0, 1.0, 1.0).applies_to(7));
+        assert!(region(Some(2), 0.0, 0.0, 1.0, 1.0).applies_to(2));
+        assert!(!region(Some(2), 0.0, 0.0, 1.0, 1.0).applies_to(3));
+    }
+
+    #[test]
+    fn test_mask_image_whites_out_region_only() {
+        let mut image = DynamicImage::ImageLuma8(image::GrayImage::from_pixel(10, 10, Luma([0])));
+        mask_image(&mut image, &[region(None, 50.0, 50.0, 50.0, 50.0)]);
+
+        assert_eq!(image.get_pixel(7, 7), Rgba([255, 255, 255, 255]));
+        assert_eq!(image.get_pixel(2, 2), Rgba([0, 0, 0, 255]));
+    }
+
+    #[tokio::test]
+    async fn test_mask_page_image() {
+        let temp = tempdir().unwrap();
+        let page = temp.path().join("page-0002.png");
+        RgbImage::from_pixel(20, 20, image::Rgb([0, 0, 0]))
+            .save(&page)
+            .unwrap();
+        let page_path = page.to_string_lossy().to_string();
+        let regions = vec![region(Some(2), 0.0, 0.0, 50.0, 100.0)];
+
+        let masked = mask_page_image(page_path.clone(), 2, regions.clone())
+            .await
+            .unwrap();
+        assert!(masked.ends_with("page-0002-masked.png"));
+        let image = image::open(&masked).unwrap();
+        assert_eq!(image.get_pixel(5, 10), Rgba([255, 255, 255, 255]));
+        assert_eq!(image.get_pixel(15, 10), Rgba([0, 0, 0, 255]));
+        // The rendered page itself is left as it was
+        assert_eq!(
+            image::open(&page).unwrap().get_pixel(5, 10),
+            Rgba([0, 0, 0, 255])
+        );
+
+        let unmasked = mask_page_image(page_path.clone(), 1, regions)
+            .await
+            .unwrap();
+        assert_eq!(unmasked, page_path);
+    }
+
+    #[test]
+    fn test_region_deserialization() {
+        let regions: Vec<MaskRegion> = serde_json::from_value(serde_json::json!([
+            {"x": 70, "y": 80, "width": 25, "height": 15},
+            {"page": 3, "x": 0, "y": 0, "width": 10, "height": 10}
+        ]))
+        .unwrap();
+        assert_eq!(regions[0].page, None);
+        assert_eq!(regions[1], region(Some(3), 0.0, 0.0, 10.0, 10.0));
+    }
+}
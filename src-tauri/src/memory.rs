@@ -0,0 +1,212 @@
+use serde::Serialize;
+use std::sync::{Condvar, Mutex};
+
+/// Bytes per pixel held while rendering a page: the RGBA bitmap plus its RGB copy
+const RENDER_BYTES_PER_PIXEL: u64 = 4 + 3;
+
+/// Payload of the `memory-pressure` event, emitted when a render has to wait
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MemoryPressure {
+    #[serde(rename = "inUseBytes")]
+    pub in_use_bytes: u64,
+    #[serde(rename = "budgetBytes")]
+    pub budget_bytes: u64,
+    #[serde(rename = "requestedBytes")]
+    pub requested_bytes: u64,
+}
+
+/// Tracks estimated bytes held by in-flight pages across the pipeline
+pub struct MemoryAccountant {
+    in_use: Mutex<u64>,
+    released: Condvar,
+}
+
+/// Bytes reserved with a `MemoryAccountant`; released on drop
+pub struct Reservation<'a> {
+    accountant: &'a MemoryAccountant,
+    bytes: u64,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        let mut in_use = self
+            .accountant
+            .in_use
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        *in_use = in_use.saturating_sub(self.bytes);
+        self.accountant.released.notify_all();
+    }
+}
+
+impl MemoryAccountant {
+    pub const fn new() -> Self {
+        Self {
+            in_use: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Bytes currently reserved
+    pub fn in_use(&self) -> u64 {
+        *self
+            .in_use
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Reserve `bytes`, blocking while that would exceed `budget` (0 = unlimited).
+    /// `on_pressure` runs once before waiting. A request is always granted when
+    /// nothing else is reserved, so a page larger than the budget cannot stall.
+    pub fn reserve(
+        &self,
+        bytes: u64,
+        budget: u64,
+        on_pressure: impl FnOnce(MemoryPressure),
+    ) -> Reservation<'_> {
+        let mut in_use = self
+            .in_use
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let fits = |in_use: u64| budget == 0 || in_use == 0 || in_use + bytes <= budget;
+        if !fits(*in_use) {
+            on_pressure(MemoryPressure {
+                in_use_bytes: *in_use,
+                budget_bytes: budget,
+                requested_bytes: bytes,
+            });
+            in_use = self
+                .released
+                .wait_while(in_use, |in_use| !fits(*in_use))
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        *in_use += bytes;
+        Reservation {
+            accountant: self,
+            bytes,
+        }
+    }
+
+    /// Count `bytes` without waiting (for stages that must not block, like uploads)
+    pub fn track(&self, bytes: u64) -> Reservation<'_> {
+        self.reserve(bytes, 0, |_| {})
+    }
+}
+
+impl Default for MemoryAccountant {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The pipeline's accountant, shared by the render and upload stages
+pub static ACCOUNTANT: MemoryAccountant = MemoryAccountant::new();
+
+/// Configured budget in bytes (0 = unlimited)
+pub fn budget_bytes(budget_mb: u32) -> u64 {
+    u64::from(budget_mb) * 1024 * 1024
+}
+
+/// Estimated bytes held while rendering one page at `dpi`: the bitmap at the
+/// maximum render size plus the worker's own copy of the document
+pub fn render_page_bytes(dpi: u32, document_bytes: u64) -> u64 {
+    let width = u64::from(dpi) * crate::pdf::PAGE_WIDTH_INCHES as u64;
+    let height = u64::from(dpi) * crate::pdf::PAGE_HEIGHT_INCHES as u64;
+    width * height * RENDER_BYTES_PER_PIXEL + document_bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_reserve_and_release() {
+        let accountant = MemoryAccountant::new();
+        {
+            let _a = accountant.reserve(100, 1000, |_| panic!("no pressure expected"));
+            let _b = accountant.track(50);
+            assert_eq!(accountant.in_use(), 150);
+        }
+        assert_eq!(accountant.in_use(), 0);
+    }
+
+    #[test]
+    fn test_oversized_request_granted_when_idle() {
+        let accountant = MemoryAccountant::new();
+        let _a = accountant.reserve(5000, 1000, |_| panic!("no pressure expected"));
+        assert_eq!(accountant.in_use(), 5000);
+    }
+
+    #[test]
+    fn test_unlimited_budget_never_waits() {
+        let accountant = MemoryAccountant::new();
+        let _a = accountant.reserve(u64::MAX / 2, 0, |_| panic!("no pressure expected"));
+        let _b = accountant.reserve(10, 0, |_| panic!("no pressure expected"));
+    }
+
+    #[test]
+    fn test_reserve_waits_for_release_and_reports_pressure() {
+        let accountant = Arc::new(MemoryAccountant::new());
+        let first = accountant.reserve(800, 1000, |_| {});
+        let pressure = Arc::new(Mutex::new(None));
+        let granted = Arc::new(AtomicBool::new(false));
+
+        let waiter = {
+            let accountant = Arc::clone(&accountant);
+            let pressure = Arc::clone(&pressure);
+            let granted = Arc::clone(&granted);
+            thread::spawn(move || {
+                let _second = accountant.reserve(300, 1000, |p| {
+                    *pressure.lock().unwrap() = Some(p);
+                });
+                granted.store(true, Ordering::SeqCst);
+            })
+        };
+
+        thread::sleep(Duration::from_millis(100));
+        assert!(!granted.load(Ordering::SeqCst));
+        assert_eq!(
+            pressure.lock().unwrap().clone(),
+            Some(MemoryPressure {
+                in_use_bytes: 800,
+                budget_bytes: 1000,
+                requested_bytes: 300,
+            })
+        );
+
+        drop(first);
+        waiter.join().unwrap();
+        assert!(granted.load(Ordering::SeqCst));
+        assert_eq!(accountant.in_use(), 0);
+    }
+
+    #[test]
+    fn test_render_page_bytes() {
+        // 150 DPI: 1200 x 1800 pixels
+        assert_eq!(render_page_bytes(150, 0), 1200 * 1800 * 7);
+        assert_eq!(render_page_bytes(150, 10), 1200 * 1800 * 7 + 10);
+    }
+
+    #[test]
+    fn test_budget_bytes() {
+        assert_eq!(budget_bytes(0), 0);
+        assert_eq!(budget_bytes(2), 2 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_pressure_serializes_camel_case() {
+        let json = serde_json::to_value(MemoryPressure {
+            in_use_bytes: 1,
+            budget_bytes: 2,
+            requested_bytes: 3,
+        })
+        .unwrap();
+        assert_eq!(json["inUseBytes"], 1);
+        assert_eq!(json["budgetBytes"], 2);
+        assert_eq!(json["requestedBytes"], 3);
+    }
+}
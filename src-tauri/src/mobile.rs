@@ -0,0 +1,238 @@
+use crate::error::TahweelError;
+use crate::file_info;
+use crate::launch;
+use crate::path_policy;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tauri::AppHandle;
+use tauri_plugin_fs::{FilePath, FsExt, OpenOptions};
+
+/// Render threads used on mobile: each worker holds its own copy of the document and
+/// a full-page bitmap, which phones cannot afford per CPU core
+pub const MOBILE_RENDER_THREADS: u32 = 2;
+
+/// A picked input as a local file the pipeline can read
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StagedInput {
+    pub path: String,
+    pub name: String,
+    /// Whether `path` is a copy in the workspace (delete its folder when done)
+    pub staged: bool,
+}
+
+/// Android's Storage Access Framework hands out `content://` URIs instead of paths
+pub fn is_content_uri(value: &str) -> bool {
+    value.starts_with("content://")
+}
+
+/// Render pool size: the `renderThreads` setting, capped on mobile
+/// (where 0, "one per core", also means the mobile default)
+pub fn render_threads(configured: u32, mobile: bool) -> u32 {
+    if !mobile {
+        return configured;
+    }
+    match configured {
+        0 => MOBILE_RENDER_THREADS,
+        n => n.min(MOBILE_RENDER_THREADS),
+    }
+}
+
+/// File name for a content URI: its last segment, percent-decoded, with any
+/// document-ID prefix (`primary:Books/`, `msf:`) removed
+pub fn content_uri_name(uri: &str) -> String {
+    let segment = url::Url::parse(uri)
+        .ok()
+        .and_then(|url| {
+            url.path_segments()
+                .and_then(|mut segments| segments.next_back().map(str::to_string))
+        })
+        .unwrap_or_default();
+    let decoded = urlencoding::decode(&segment)
+        .map(|s| s.into_owned())
+        .unwrap_or(segment);
+    let name = decoded
+        .rsplit(['/', ':', '\\'])
+        .next()
+        .unwrap_or_default()
+        .trim();
+    if name.is_empty() || name == "." || name == ".." {
+        "input".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Give a staged copy a supported extension based on its contents
+/// (document IDs such as `msf:1234` have none)
+fn with_sniffed_extension(path: PathBuf) -> io::Result<PathBuf> {
+    if launch::is_supported_file(&path) {
+        return Ok(path);
+    }
+    let extension = match file_info::sniff_mime_type(&path).as_str() {
+        "application/pdf" => "pdf",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        _ => return Ok(path),
+    };
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    let renamed = path.with_file_name(name);
+    fs::rename(&path, &renamed)?;
+    Ok(renamed)
+}
+
+/// Copy `reader` into a fresh folder in `workspace` as `name`
+pub fn stage_reader(
+    mut reader: impl io::Read,
+    name: &str,
+    workspace: &Path,
+) -> Result<PathBuf, TahweelError> {
+    fs::create_dir_all(workspace)?;
+    let dir = tempfile::Builder::new()
+        .prefix("tahweel-input-")
+        .tempdir_in(workspace)
+        .map_err(|e| TahweelError::Io(format!("Failed to create staging directory: {}", e)))?
+        .keep();
+    let path = dir.join(name);
+    let mut file = fs::File::create(&path)?;
+    io::copy(&mut reader, &mut file)
+        .map_err(|e| TahweelError::Io(format!("Failed to copy {}: {}", name, e)))?;
+    Ok(with_sniffed_extension(path)?)
+}
+
+/// Set up app-private directories on mobile: the workspace moves to the app cache
+/// (the system temp dir is not writable there) and outputs default to the app's
+/// documents folder, since picked files have no writable folder of their own
+pub fn init(app: &AppHandle) {
+    use tauri::Manager;
+
+    if let Ok(cache) = app.path().app_cache_dir() {
+        path_policy::set_workspace_root(cache);
+    }
+    if let Ok(documents) = app.path().document_dir() {
+        path_policy::set_app_output_dir(documents.join("Tahweel"));
+    }
+}
+
+/// Make a picked input readable by path. `content://` URIs are copied into the
+/// workspace through the fs plugin (which resolves them on Android); plain paths
+/// are returned unchanged.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn stage_input_file(path: String, app: AppHandle) -> Result<StagedInput, TahweelError> {
+    if !is_content_uri(&path) {
+        let name = Path::new(&path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        return Ok(StagedInput {
+            path,
+            name,
+            staged: false,
+        });
+    }
+
+    let name = content_uri_name(&path);
+    let file_path = FilePath::from_str(&path)
+        .map_err(|_| TahweelError::InvalidInput(format!("Invalid URI: {}", path)))?;
+    let mut options = OpenOptions::new();
+    options.read(true);
+    let reader = app
+        .fs()
+        .open(file_path, options)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => TahweelError::NotFound(format!("File not found: {}", path)),
+            _ => TahweelError::Io(format!("Failed to open {}: {}", path, e)),
+        })?;
+
+    let workspace = path_policy::workspace();
+    let staged =
+        tauri::async_runtime::spawn_blocking(move || stage_reader(reader, &name, &workspace))
+            .await
+            .map_err(|e| TahweelError::Internal(format!("Failed to stage input: {}", e)))??;
+    tracing::info!("Staged content URI input");
+
+    Ok(StagedInput {
+        name: staged
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: staged.to_string_lossy().to_string(),
+        staged: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_is_content_uri() {
+        assert!(is_content_uri(
+            "content://com.android.providers.media.documents/document/image%3A12"
+        ));
+        assert!(!is_content_uri("/storage/emulated/0/Download/book.pdf"));
+        assert!(!is_content_uri("file:///sdcard/book.pdf"));
+    }
+
+    #[test]
+    fn test_render_threads() {
+        assert_eq!(render_threads(0, false), 0);
+        assert_eq!(render_threads(8, false), 8);
+        assert_eq!(render_threads(0, true), MOBILE_RENDER_THREADS);
+        assert_eq!(render_threads(8, true), MOBILE_RENDER_THREADS);
+        assert_eq!(render_threads(1, true), 1);
+    }
+
+    #[test]
+    fn test_content_uri_name() {
+        assert_eq!(
+            content_uri_name("content://com.android.externalstorage.documents/document/primary%3ABooks%2F%D9%83%D8%AA%D8%A7%D8%A8.pdf"),
+            "كتاب.pdf"
+        );
+        assert_eq!(
+            content_uri_name(
+                "content://com.android.providers.downloads.documents/document/msf%3A1234"
+            ),
+            "1234"
+        );
+        assert_eq!(content_uri_name("content://authority/"), "input");
+        assert_eq!(
+            content_uri_name("content://authority/document/..%2F.."),
+            "input"
+        );
+    }
+
+    #[test]
+    fn test_stage_reader_keeps_supported_name() {
+        let workspace = tempdir().unwrap();
+        let staged = stage_reader(&b"%PDF-1.7\n"[..], "book.pdf", workspace.path()).unwrap();
+
+        assert_eq!(staged.file_name().unwrap(), "book.pdf");
+        assert!(staged.starts_with(workspace.path()));
+        assert_eq!(fs::read(&staged).unwrap(), b"%PDF-1.7\n");
+    }
+
+    #[test]
+    fn test_stage_reader_adds_sniffed_extension() {
+        let workspace = tempdir().unwrap();
+        let staged = stage_reader(&b"%PDF-1.7\n"[..], "1234", workspace.path()).unwrap();
+        assert_eq!(staged.file_name().unwrap(), "1234.pdf");
+
+        let unknown = stage_reader(&b"plain"[..], "notes", workspace.path()).unwrap();
+        assert_eq!(unknown.file_name().unwrap(), "notes");
+    }
+
+    #[test]
+    fn test_stage_reader_uses_separate_folders() {
+        let workspace = tempdir().unwrap();
+        let a = stage_reader(&b"a"[..], "scan.png", workspace.path()).unwrap();
+        let b = stage_reader(&b"b"[..], "scan.png", workspace.path()).unwrap();
+        assert_ne!(a.parent(), b.parent());
+    }
+}
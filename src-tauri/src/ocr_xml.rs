@@ -0,0 +1,491 @@
+use crate::layout::{BoundingBox, OcrWord, PageLayout};
+use crate::output::is_arabic_text;
+use serde::Deserialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Positional OCR formats supported for archival output
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum XmlFormat {
+    Hocr,
+    Alto,
+}
+
+impl XmlFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            XmlFormat::Hocr => "hocr",
+            XmlFormat::Alto => "xml",
+        }
+    }
+
+    fn directory_suffix(self) -> &'static str {
+        match self {
+            XmlFormat::Hocr => "hocr",
+            XmlFormat::Alto => "alto",
+        }
+    }
+}
+
+/// Page input for positional output. Width/height are read from the page image
+/// when not supplied; `words` is empty for backends without geometry.
+#[derive(Debug, Clone, Deserialize)]
+pub struct XmlPageInput {
+    pub text: String,
+    #[serde(rename = "imagePath", default)]
+    pub image_path: Option<String>,
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+    #[serde(default)]
+    pub words: Vec<OcrWord>,
+}
+
+/// Escape text for inclusion in XML content or attribute values
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if (c as u32) < 0x20 && c != '\n' && c != '\t' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn hocr_bbox(b: &BoundingBox) -> String {
+    format!("bbox {} {} {} {}", b.x, b.y, b.right(), b.bottom())
+}
+
+fn page_box(layout: &PageLayout) -> BoundingBox {
+    BoundingBox {
+        x: 0,
+        y: 0,
+        width: layout.width,
+        height: layout.height,
+    }
+}
+
+/// Render a single page as an hOCR document
+pub fn render_hocr(layout: &PageLayout, page_number: u32, image_name: &str) -> String {
+    let rtl = is_arabic_text(&layout.text);
+    let lang = if rtl { "ar" } else { "en" };
+    let dir = if rtl { "rtl" } else { "ltr" };
+    let capabilities = if layout.has_geometry() {
+        "ocr_page ocr_carea ocr_par ocr_line ocrx_word"
+    } else {
+        "ocr_page ocr_carea ocr_par ocr_line"
+    };
+    let page_bbox = hocr_bbox(&page_box(layout));
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\" \"http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd\">\n");
+    let _ = writeln!(
+        out,
+        "<html xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"{lang}\" lang=\"{lang}\">"
+    );
+    out.push_str("<head>\n<title></title>\n");
+    out.push_str("<meta http-equiv=\"Content-Type\" content=\"text/html;charset=utf-8\"/>\n");
+    out.push_str("<meta name=\"ocr-system\" content=\"tahweel\"/>\n");
+    let _ = writeln!(
+        out,
+        "<meta name=\"ocr-capabilities\" content=\"{capabilities}\"/>"
+    );
+    out.push_str("</head>\n<body>\n");
+    let _ = writeln!(
+        out,
+        "<div class=\"ocr_page\" id=\"page_{page_number}\" title=\"image &quot;{}&quot;; {page_bbox}; ppageno {}\">",
+        escape_xml(image_name),
+        page_number.saturating_sub(1)
+    );
+    let _ = writeln!(
+        out,
+        "<div class=\"ocr_carea\" id=\"block_{page_number}_1\" title=\"{page_bbox}\">"
+    );
+
+    if layout.has_geometry() {
+        let lines = layout.lines(rtl);
+        let par_box = BoundingBox::union(layout.words.iter().map(|w| &w.bbox))
+            .unwrap_or_else(|| page_box(layout));
+        let _ = writeln!(
+            out,
+            "<p class=\"ocr_par\" id=\"par_{page_number}_1\" dir=\"{dir}\" title=\"{}\">",
+            hocr_bbox(&par_box)
+        );
+        let mut word_index = 0;
+        for (line_index, line) in lines.iter().enumerate() {
+            let line_box = BoundingBox::union(line.iter().map(|w| &w.bbox)).unwrap_or(par_box);
+            let _ = write!(
+                out,
+                "<span class=\"ocr_line\" id=\"line_{page_number}_{}\" title=\"{}\">",
+                line_index + 1,
+                hocr_bbox(&line_box)
+            );
+            for word in line {
+                word_index += 1;
+                let confidence = word
+                    .confidence
+                    .map(|c| format!("; x_wconf {}", (c * 100.0).round() as u32))
+                    .unwrap_or_default();
+                let _ = write!(
+                    out,
+                    "<span class=\"ocrx_word\" id=\"word_{page_number}_{word_index}\" title=\"{}{confidence}\">{}</span> ",
+                    hocr_bbox(&word.bbox),
+                    escape_xml(&word.text)
+                );
+            }
+            out.push_str("</span>\n");
+        }
+        out.push_str("</p>\n");
+    } else {
+        // Degraded variant: page-level text only, no line/word coordinates
+        let _ = writeln!(
+            out,
+            "<p class=\"ocr_par\" id=\"par_{page_number}_1\" dir=\"{dir}\" title=\"{page_bbox}\">"
+        );
+        for (line_index, line) in layout
+            .text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .enumerate()
+        {
+            let _ = writeln!(
+                out,
+                "<span class=\"ocr_line\" id=\"line_{page_number}_{}\">{}</span>",
+                line_index + 1,
+                escape_xml(line.trim())
+            );
+        }
+        out.push_str("</p>\n");
+    }
+
+    out.push_str("</div>\n</div>\n</body>\n</html>\n");
+    out
+}
+
+fn alto_position(b: &BoundingBox) -> String {
+    format!(
+        "HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\"",
+        b.x, b.y, b.width, b.height
+    )
+}
+
+/// Render a single page as an ALTO v4 document
+pub fn render_alto(layout: &PageLayout, page_number: u32, image_name: &str) -> String {
+    let rtl = is_arabic_text(&layout.text);
+    let page_position = alto_position(&page_box(layout));
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<alto xmlns=\"http://www.loc.gov/standards/alto/ns-v4#\">\n");
+    out.push_str("<Description>\n<MeasurementUnit>pixel</MeasurementUnit>\n");
+    let _ = writeln!(
+        out,
+        "<sourceImageInformation><fileName>{}</fileName></sourceImageInformation>",
+        escape_xml(image_name)
+    );
+    out.push_str("<OCRProcessing ID=\"OCR_0\"><ocrProcessingStep><processingSoftware><softwareName>Tahweel</softwareName></processingSoftware></ocrProcessingStep></OCRProcessing>\n");
+    out.push_str("</Description>\n<Layout>\n");
+    let _ = writeln!(
+        out,
+        "<Page ID=\"page_{page_number}\" PHYSICAL_IMG_NR=\"{page_number}\" WIDTH=\"{}\" HEIGHT=\"{}\">",
+        layout.width, layout.height
+    );
+    let _ = writeln!(out, "<PrintSpace {page_position}>");
+    let lang = if rtl { "ar" } else { "en" };
+    let _ = writeln!(
+        out,
+        "<TextBlock ID=\"block_{page_number}_1\" LANG=\"{lang}\">"
+    );
+
+    if layout.has_geometry() {
+        let mut word_index = 0;
+        for (line_index, line) in layout.lines(rtl).iter().enumerate() {
+            let line_box =
+                BoundingBox::union(line.iter().map(|w| &w.bbox)).unwrap_or(page_box(layout));
+            let _ = write!(
+                out,
+                "<TextLine ID=\"line_{page_number}_{}\" {}>",
+                line_index + 1,
+                alto_position(&line_box)
+            );
+            for (i, word) in line.iter().enumerate() {
+                word_index += 1;
+                if i > 0 {
+                    out.push_str("<SP/>");
+                }
+                let confidence = word
+                    .confidence
+                    .map(|c| format!(" WC=\"{:.2}\"", c))
+                    .unwrap_or_default();
+                let _ = write!(
+                    out,
+                    "<String ID=\"string_{page_number}_{word_index}\" CONTENT=\"{}\" {}{confidence}/>",
+                    escape_xml(&word.text),
+                    alto_position(&word.bbox)
+                );
+            }
+            out.push_str("</TextLine>\n");
+        }
+    } else {
+        // Degraded variant: one TextLine per text line, without coordinates
+        for (line_index, line) in layout
+            .text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .enumerate()
+        {
+            let _ = write!(
+                out,
+                "<TextLine ID=\"line_{page_number}_{}\">",
+                line_index + 1
+            );
+            for (i, token) in line.split_whitespace().enumerate() {
+                if i > 0 {
+                    out.push_str("<SP/>");
+                }
+                let _ = write!(out, "<String CONTENT=\"{}\"/>", escape_xml(token));
+            }
+            out.push_str("</TextLine>\n");
+        }
+    }
+
+    out.push_str("</TextBlock>\n</PrintSpace>\n</Page>\n</Layout>\n</alto>\n");
+    out
+}
+
+/// Resolve the page dimensions, reading them from the page image if needed
+fn resolve_layout(input: XmlPageInput) -> Result<(PageLayout, String), String> {
+    let image_name = input
+        .image_path
+        .as_deref()
+        .and_then(|p| Path::new(p).file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let (width, height) = match (input.width, input.height) {
+        (Some(w), Some(h)) => (w, h),
+        _ => match input.image_path.as_deref() {
+            Some(path) => image::image_dimensions(path)
+                .map_err(|e| format!("Failed to read image dimensions: {}", e))?,
+            None => (0, 0),
+        },
+    };
+
+    Ok((
+        PageLayout {
+            width,
+            height,
+            text: input.text,
+            words: input.words,
+        },
+        image_name,
+    ))
+}
+
+/// Write one hOCR or ALTO file per page into `<output_path>_hocr/` or `<output_path>_alto/`.
+/// Returns the written file paths in page order.
+#[tauri::command]
+pub async fn write_xml_output(
+    output_path: String,
+    format: XmlFormat,
+    pages: Vec<XmlPageInput>,
+) -> Result<Vec<String>, String> {
+    let dir = PathBuf::from(format!("{}_{}", output_path, format.directory_suffix()));
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let mut written = Vec::with_capacity(pages.len());
+    for (index, input) in pages.into_iter().enumerate() {
+        let page_number = index as u32 + 1;
+        let (layout, image_name) = resolve_layout(input)?;
+        let content = match format {
+            XmlFormat::Hocr => render_hocr(&layout, page_number, &image_name),
+            XmlFormat::Alto => render_alto(&layout, page_number, &image_name),
+        };
+
+        let path = dir.join(format!("page-{:04}.{}", page_number, format.extension()));
+        fs::write(&path, content).map_err(|e| format!("Failed to write XML output: {}", e))?;
+        written.push(path.to_string_lossy().to_string());
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn drive_page(text: &str) -> PageLayout {
+        PageLayout {
+            width: 1200,
+            height: 1800,
+            text: text.to_string(),
+            words: vec![],
+        }
+    }
+
+    fn geometry_page() -> PageLayout {
+        PageLayout {
+            width: 1200,
+            height: 1800,
+            text: "hello world".to_string(),
+            words: vec![
+                OcrWord {
+                    text: "hello".to_string(),
+                    bbox: BoundingBox {
+                        x: 100,
+                        y: 100,
+                        width: 200,
+                        height: 40,
+                    },
+                    confidence: Some(0.93),
+                },
+                OcrWord {
+                    text: "world".to_string(),
+                    bbox: BoundingBox {
+                        x: 320,
+                        y: 105,
+                        width: 200,
+                        height: 40,
+                    },
+                    confidence: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml("a < b & \"c\" > 'd'"),
+            "a &lt; b &amp; &quot;c&quot; &gt; &apos;d&apos;"
+        );
+        assert_eq!(escape_xml("bell\u{7}"), "bell");
+    }
+
+    #[test]
+    fn test_hocr_degraded_page_level_text() {
+        let hocr = render_hocr(
+            &drive_page("السطر الأول\n\nالسطر الثاني"),
+            1,
+            "page-0001.png",
+        );
+
+        assert!(hocr.contains("class=\"ocr_page\""));
+        assert!(hocr.contains("bbox 0 0 1200 1800"));
+        assert!(hocr.contains("dir=\"rtl\""));
+        assert!(hocr.contains("lang=\"ar\""));
+        assert!(hocr.contains(">السطر الأول</span>"));
+        assert!(hocr.contains("id=\"line_1_2\""));
+        assert!(!hocr.contains("ocrx_word"));
+    }
+
+    #[test]
+    fn test_hocr_with_geometry() {
+        let hocr = render_hocr(&geometry_page(), 3, "page-0003.png");
+
+        assert!(hocr.contains("ocrx_word"));
+        assert!(hocr.contains("bbox 100 100 300 140; x_wconf 93"));
+        assert!(hocr.contains("bbox 100 100 520 145"));
+        assert!(hocr.contains("ppageno 2"));
+        assert!(hocr.find(">hello<").unwrap() < hocr.find(">world<").unwrap());
+    }
+
+    #[test]
+    fn test_alto_degraded_page_level_text() {
+        let alto = render_alto(&drive_page("one two\nthree"), 1, "page-0001.png");
+
+        assert!(alto.contains("ns-v4"));
+        assert!(alto.contains("WIDTH=\"1200\" HEIGHT=\"1800\""));
+        assert!(alto.contains("<String CONTENT=\"one\"/><SP/><String CONTENT=\"two\"/>"));
+        assert!(alto.contains("<TextLine ID=\"line_1_2\"><String CONTENT=\"three\"/>"));
+        assert!(alto.contains("<fileName>page-0001.png</fileName>"));
+    }
+
+    #[test]
+    fn test_alto_with_geometry() {
+        let alto = render_alto(&geometry_page(), 1, "page-0001.png");
+
+        assert!(alto.contains(
+            "CONTENT=\"hello\" HPOS=\"100\" VPOS=\"100\" WIDTH=\"200\" HEIGHT=\"40\" WC=\"0.93\""
+        ));
+        assert!(alto.contains("<SP/>"));
+        assert!(alto.contains("LANG=\"en\""));
+    }
+
+    #[test]
+    fn test_xml_format_deserialization() {
+        let hocr: XmlFormat = serde_json::from_str("\"hocr\"").unwrap();
+        let alto: XmlFormat = serde_json::from_str("\"alto\"").unwrap();
+        assert_eq!(hocr, XmlFormat::Hocr);
+        assert_eq!(alto, XmlFormat::Alto);
+        assert!(serde_json::from_str::<XmlFormat>("\"pdf\"").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_xml_output_per_page_files() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("book").to_string_lossy().to_string();
+
+        let pages = vec![
+            XmlPageInput {
+                text: "one".to_string(),
+                image_path: None,
+                width: Some(100),
+                height: Some(200),
+                words: vec![],
+            },
+            XmlPageInput {
+                text: "two".to_string(),
+                image_path: None,
+                width: Some(100),
+                height: Some(200),
+                words: vec![],
+            },
+        ];
+
+        let paths = write_xml_output(base.clone(), XmlFormat::Alto, pages)
+            .await
+            .unwrap();
+
+        assert_eq!(paths.len(), 2);
+        assert!(paths[0].ends_with("page-0001.xml"));
+        assert!(paths[1].contains("book_alto"));
+        assert!(fs::read_to_string(&paths[1])
+            .unwrap()
+            .contains("CONTENT=\"two\""));
+    }
+
+    #[tokio::test]
+    async fn test_write_xml_output_reads_image_dimensions() {
+        let temp = tempdir().unwrap();
+        let image_path = temp.path().join("page-0001.png");
+        image::RgbImage::new(40, 60).save(&image_path).unwrap();
+
+        let pages = vec![XmlPageInput {
+            text: "text".to_string(),
+            image_path: Some(image_path.to_string_lossy().to_string()),
+            width: None,
+            height: None,
+            words: vec![],
+        }];
+
+        let base = temp.path().join("doc").to_string_lossy().to_string();
+        let paths = write_xml_output(base, XmlFormat::Hocr, pages)
+            .await
+            .unwrap();
+        let hocr = fs::read_to_string(&paths[0]).unwrap();
+        assert!(hocr.contains("bbox 0 0 40 60"));
+        assert!(hocr.contains("image &quot;page-0001.png&quot;"));
+    }
+}
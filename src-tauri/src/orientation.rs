@@ -0,0 +1,249 @@
+use crate::encryption;
+use crate::error::TahweelError;
+use crate::settings;
+use image::{DynamicImage, GrayImage};
+use serde::Serialize;
+use std::path::Path;
+
+/// Luma below which a pixel counts as ink
+pub(crate) const INK_THRESHOLD: u8 = 128;
+/// Longer side of the downscaled copy the orientation is detected on
+const ANALYSIS_SIZE: u32 = 800;
+/// How much sharper the column profile must be than the row profile before a
+/// page is considered sideways
+const SIDEWAYS_MARGIN: f64 = 1.5;
+/// Text lines needed before the baseline vote is trusted
+const MIN_LINES: usize = 3;
+/// A line's baseline must sit this far from its middle (as a share of the line
+/// height) to vote
+const BASELINE_OFFSET: f64 = 0.1;
+
+/// Rotation applied to a page image before OCR
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct OrientationFix {
+    /// Clockwise degrees the page was turned (0, 90, 180 or 270)
+    pub rotation: u32,
+    /// Corrected image, or the original when the page was already upright
+    pub path: String,
+}
+
+/// Ink pixels per row (`rows`) or per column
+pub(crate) fn ink_profile(gray: &GrayImage, rows: bool) -> Vec<u32> {
+    let (width, height) = gray.dimensions();
+    let mut profile = vec![0u32; if rows { height } else { width } as usize];
+    for (x, y, pixel) in gray.enumerate_pixels() {
+        if pixel.0[0] < INK_THRESHOLD {
+            profile[if rows { y } else { x } as usize] += 1;
+        }
+    }
+    profile
+}
+
+/// Squared coefficient of variation: high when ink comes in bands separated by
+/// gaps (text lines across the profile), low when it is spread evenly
+pub(crate) fn sharpness(profile: &[u32]) -> f64 {
+    let n = profile.len() as f64;
+    let mean = profile.iter().map(|&v| v as f64).sum::<f64>() / n;
+    if mean == 0.0 {
+        return 0.0;
+    }
+    let variance = profile
+        .iter()
+        .map(|&v| (v as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    variance / (mean * mean)
+}
+
+/// Votes of horizontal text lines for `(upright, upside_down)`. The densest row
+/// of a line is its baseline, which sits in the lower half of upright Arabic
+/// and Latin lines.
+fn baseline_votes(gray: &GrayImage) -> (usize, usize) {
+    let profile = ink_profile(gray, true);
+    let max = profile.iter().copied().max().unwrap_or(0);
+    let floor = max / 10;
+    let (mut upright, mut upside_down) = (0, 0);
+    let mut row = 0;
+    while row < profile.len() {
+        if profile[row] <= floor {
+            row += 1;
+            continue;
+        }
+        let start = row;
+        while row < profile.len() && profile[row] > floor {
+            row += 1;
+        }
+        let line = &profile[start..row];
+        if line.len() < 4 {
+            continue;
+        }
+        let peak = line
+            .iter()
+            .enumerate()
+            .max_by_key(|&(i, &v)| (v, std::cmp::Reverse(i)))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        let position = peak as f64 / (line.len() - 1) as f64;
+        if position > 0.5 + BASELINE_OFFSET {
+            upright += 1;
+        } else if position < 0.5 - BASELINE_OFFSET {
+            upside_down += 1;
+        }
+    }
+    (upright, upside_down)
+}
+
+/// Clockwise rotation (0, 90, 180 or 270) that makes a page's text upright.
+/// Sideways pages are told apart by which ink profile shows text lines, and
+/// upside-down ones by where the lines' baselines sit. Pages with too little
+/// text to tell are left as they are.
+pub fn detect_rotation(image: &DynamicImage) -> u32 {
+    let gray = if image.width().max(image.height()) > ANALYSIS_SIZE {
+        image.thumbnail(ANALYSIS_SIZE, ANALYSIS_SIZE).to_luma8()
+    } else {
+        image.to_luma8()
+    };
+    let rows = sharpness(&ink_profile(&gray, true));
+    let columns = sharpness(&ink_profile(&gray, false));
+
+    if columns > rows * SIDEWAYS_MARGIN {
+        // Lines run vertically: turn them horizontal, then pick the upright side
+        let turned = image::imageops::rotate90(&gray);
+        let (upright, upside_down) = baseline_votes(&turned);
+        if upright + upside_down < MIN_LINES {
+            return 0;
+        }
+        return if upright >= upside_down { 90 } else { 270 };
+    }
+
+    let (upright, upside_down) = baseline_votes(&gray);
+    if upside_down >= MIN_LINES && upside_down > upright * 2 {
+        180
+    } else {
+        0
+    }
+}
+
+/// Turn a rendered page image upright before OCR when the `autoRotatePages`
+/// option is on, writing `page-0001-rotated.png` next to it. Returns the
+/// original path when the option is off or the page is already upright.
+#[tauri::command]
+pub async fn correct_page_orientation(image_path: String) -> Result<OrientationFix, TahweelError> {
+    if !settings::current().auto_rotate_pages {
+        return Ok(OrientationFix {
+            rotation: 0,
+            path: image_path,
+        });
+    }
+    orient_image(&image_path)
+}
+
+/// Write an upright copy of a page image if it needs one (see `correct_page_orientation`)
+pub fn orient_image(image_path: &str) -> Result<OrientationFix, TahweelError> {
+    let path = Path::new(image_path);
+    let (image, key) = encryption::open_image(path)?;
+    let rotation = detect_rotation(&image);
+    let rotated = match rotation {
+        90 => image.rotate90(),
+        180 => image.rotate180(),
+        270 => image.rotate270(),
+        _ => {
+            return Ok(OrientationFix {
+                rotation: 0,
+                path: image_path.to_string(),
+            })
+        }
+    };
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let corrected = path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(format!("{}-rotated.png", stem));
+    encryption::save_png(&rotated, &corrected, key)?;
+    Ok(OrientationFix {
+        rotation,
+        path: corrected.to_string_lossy().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+    use tempfile::tempdir;
+
+    /// An upright page of text-like lines: sparse strokes above a dense baseline
+    fn text_page() -> DynamicImage {
+        let mut page = GrayImage::from_pixel(300, 400, Luma([255]));
+        for line in 0..12 {
+            let top = 20 + line * 30;
+            for y in top..top + 14 {
+                for x in 20..280 {
+                    let baseline = y >= top + 11;
+                    let stroke = (x * 7 + y * 13) % 5 == 0;
+                    if baseline || stroke {
+                        page.put_pixel(x, y, Luma([0]));
+                    }
+                }
+            }
+        }
+        DynamicImage::ImageLuma8(page)
+    }
+
+    #[test]
+    fn test_detect_rotation() {
+        let page = text_page();
+        assert_eq!(detect_rotation(&page), 0);
+        assert_eq!(detect_rotation(&page.rotate180()), 180);
+        assert_eq!(detect_rotation(&page.rotate90()), 270);
+        assert_eq!(detect_rotation(&page.rotate270()), 90);
+    }
+
+    #[test]
+    fn test_blank_page_is_left_alone() {
+        let blank = DynamicImage::ImageLuma8(GrayImage::from_pixel(100, 100, Luma([255])));
+        assert_eq!(detect_rotation(&blank), 0);
+    }
+
+    #[test]
+    fn test_orient_image() {
+        let temp = tempdir().unwrap();
+        let upright = temp.path().join("page-0001.png");
+        let flipped = temp.path().join("page-0002.png");
+        text_page().save(&upright).unwrap();
+        text_page().rotate180().save(&flipped).unwrap();
+
+        let upright_path = upright.to_string_lossy().to_string();
+        let fix = orient_image(&upright_path).unwrap();
+        assert_eq!(
+            fix,
+            OrientationFix {
+                rotation: 0,
+                path: upright_path
+            }
+        );
+
+        let fix = orient_image(&flipped.to_string_lossy()).unwrap();
+        assert_eq!(fix.rotation, 180);
+        assert!(fix.path.ends_with("page-0002-rotated.png"));
+        assert_eq!(
+            image::open(&fix.path).unwrap().to_luma8(),
+            text_page().to_luma8()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_correct_page_orientation_disabled_by_default() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("page-0001.png");
+        text_page().rotate180().save(&path).unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let fix = correct_page_orientation(path.clone()).await.unwrap();
+        assert_eq!(fix, OrientationFix { rotation: 0, path });
+    }
+}
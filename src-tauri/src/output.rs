@@ -0,0 +1,791 @@
+use crate::docx::{write_docx, DocxPage, DocxSection};
+use crate::error::TahweelError;
+use crate::footnotes::page_text_with_footnotes;
+use crate::long_path;
+use crate::path_policy;
+use crate::quran::QuranSubstitution;
+use crate::settings;
+use crate::tables::TableRegion;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Version of the structured JSON output schema
+pub(crate) const JSON_OUTPUT_VERSION: u32 = 1;
+/// OCR backend recorded when the frontend does not specify one
+const DEFAULT_OCR_BACKEND: &str = "google-drive";
+
+/// Per-stage timing for a single page, in milliseconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PageTiming {
+    #[serde(rename = "renderMs", default)]
+    pub render_ms: Option<u64>,
+    #[serde(rename = "uploadMs", default)]
+    pub upload_ms: Option<u64>,
+    #[serde(rename = "exportMs", default)]
+    pub export_ms: Option<u64>,
+}
+
+/// Page result as collected by the frontend during OCR
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageInput {
+    pub text: String,
+    #[serde(rename = "sourcePage", default)]
+    pub source_page: Option<u32>,
+    #[serde(rename = "imagePath", default)]
+    pub image_path: Option<String>,
+    #[serde(default)]
+    pub backend: Option<String>,
+    #[serde(default)]
+    pub retries: u32,
+    #[serde(default)]
+    pub timing: PageTiming,
+    /// Table regions detected on this page (geometry-aware backends only)
+    #[serde(default)]
+    pub tables: Vec<TableRegion>,
+    /// Quranic verses substituted by post-processing
+    #[serde(default)]
+    pub quran: Vec<QuranSubstitution>,
+    /// Detected language (ISO 639-1) of the page
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Footnotes separated from the body text (see footnotes.rs)
+    #[serde(default)]
+    pub footnotes: Option<String>,
+}
+
+/// Descriptive metadata carried from the source PDF into the outputs (DOCX core
+/// properties and the job report). Empty fields are left out.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DocumentMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+}
+
+impl DocumentMetadata {
+    /// Trimmed copy with blank fields dropped
+    pub fn normalized(&self) -> Self {
+        let field = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        Self {
+            title: field(&self.title),
+            author: field(&self.author),
+            subject: field(&self.subject),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.normalized() == Self::default()
+    }
+}
+
+/// Page entry written to the structured JSON output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageRecord {
+    pub page: u32,
+    #[serde(rename = "sourcePage")]
+    pub source_page: u32,
+    pub text: String,
+    #[serde(rename = "imageChecksum")]
+    pub image_checksum: Option<String>,
+    pub backend: String,
+    pub retries: u32,
+    pub timing: PageTiming,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<TableRegion>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quran: Vec<QuranSubstitution>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footnotes: Option<String>,
+}
+
+/// Top-level structure of the JSON output file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonOutput {
+    pub version: u32,
+    pub source: String,
+    #[serde(rename = "pageCount")]
+    pub page_count: u32,
+    pub pages: Vec<PageRecord>,
+}
+
+/// Output document kinds (mirrors the frontend `OutputFormat`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Txt,
+    Json,
+    Docx,
+}
+
+/// OCR results for one source file of a combined book, in reading order
+#[derive(Debug, Clone, Deserialize)]
+pub struct BookSection {
+    pub title: String,
+    #[serde(rename = "sourcePath")]
+    pub source_path: String,
+    pub pages: Vec<PageInput>,
+}
+
+/// Section entry in the combined JSON output
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SectionRecord {
+    pub title: String,
+    pub source: String,
+    #[serde(rename = "firstPage")]
+    pub first_page: u32,
+    pub pages: Vec<PageRecord>,
+}
+
+/// Top-level structure of the combined JSON output file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CombinedJsonOutput {
+    pub version: u32,
+    #[serde(rename = "pageCount")]
+    pub page_count: u32,
+    pub sections: Vec<SectionRecord>,
+}
+
+/// Check whether a character is in the Arabic Unicode block (U+0600–U+06FF)
+pub fn is_arabic_char(c: char) -> bool {
+    ('\u{0600}'..='\u{06FF}').contains(&c)
+}
+
+/// Detect if text is predominantly Arabic (for RTL alignment).
+/// Mirrors the frontend heuristic: Arabic letters must not be outnumbered by other letters.
+pub fn is_arabic_text(text: &str) -> bool {
+    let arabic = text.chars().filter(|&c| is_arabic_char(c)).count();
+    let other = text
+        .chars()
+        .filter(|&c| c.is_alphabetic() && !is_arabic_char(c))
+        .count();
+    arabic >= other
+}
+
+/// Compute the SHA-256 checksum of a file as a lowercase hex string
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = fs::File::open(path)
+        .map_err(|e| format!("Failed to open {} for hashing: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to hash {}: {}", path.display(), e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Build page records from frontend input, hashing the rendered page images.
+/// Missing images (e.g. already cleaned up) produce a `null` checksum rather than an error.
+pub fn build_page_records(pages: Vec<PageInput>) -> Vec<PageRecord> {
+    pages
+        .into_iter()
+        .enumerate()
+        .map(|(index, input)| {
+            let page = index as u32 + 1;
+            let image_checksum = input
+                .image_path
+                .as_deref()
+                .map(Path::new)
+                .filter(|path| path.is_file())
+                .and_then(|path| sha256_file(path).ok());
+
+            PageRecord {
+                page,
+                source_page: input.source_page.unwrap_or(page),
+                text: input.text.trim().to_string(),
+                image_checksum,
+                backend: input
+                    .backend
+                    .unwrap_or_else(|| DEFAULT_OCR_BACKEND.to_string()),
+                retries: input.retries,
+                timing: input.timing,
+                tables: input.tables,
+                quran: input.quran,
+                language: input.language,
+                footnotes: input
+                    .footnotes
+                    .map(|notes| notes.trim().to_string())
+                    .filter(|notes| !notes.is_empty()),
+            }
+        })
+        .collect()
+}
+
+/// Write structured JSON output with per-page metadata to `<output_path>.json`.
+/// Returns the path of the written file.
+#[tauri::command]
+pub async fn write_json_output(
+    output_path: String,
+    source_path: String,
+    pages: Vec<PageInput>,
+) -> Result<String, String> {
+    let records = build_page_records(pages);
+    let output = JsonOutput {
+        version: JSON_OUTPUT_VERSION,
+        source: source_path,
+        page_count: records.len() as u32,
+        pages: records,
+    };
+
+    let json = serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?;
+    let final_path = format!("{}.json", output_path);
+    fs::write(&final_path, json).map_err(|e| format!("Failed to write JSON output: {}", e))?;
+
+    Ok(final_path)
+}
+
+/// Assemble the combined TXT body: each section starts with its title,
+/// followed by its pages joined by the page separator.
+pub fn build_combined_text(sections: &[BookSection], page_separator: &str) -> String {
+    sections
+        .iter()
+        .map(|section| {
+            let pages: Vec<String> = section
+                .pages
+                .iter()
+                .map(|p| page_text_with_footnotes(&p.text, p.footnotes.as_deref()))
+                .collect();
+            format!("{}\n\n{}", section.title.trim(), pages.join(page_separator))
+        })
+        .collect::<Vec<_>>()
+        .join(page_separator)
+}
+
+/// Build combined JSON sections. Page numbers run continuously across sections.
+pub fn build_section_records(sections: Vec<BookSection>) -> Vec<SectionRecord> {
+    let mut offset = 0;
+    sections
+        .into_iter()
+        .map(|section| {
+            let mut pages = build_page_records(section.pages);
+            for record in &mut pages {
+                record.page += offset;
+            }
+            let first_page = offset + 1;
+            offset += pages.len() as u32;
+            SectionRecord {
+                title: section.title,
+                source: section.source_path,
+                first_page,
+                pages,
+            }
+        })
+        .collect()
+}
+
+/// Write a single set of output documents for several sources, in the given order,
+/// with a heading per source. Returns the paths of the written files.
+#[tauri::command]
+pub async fn write_combined_output(
+    output_path: String,
+    sections: Vec<BookSection>,
+    formats: Vec<OutputFormat>,
+    page_separator: String,
+) -> Result<Vec<String>, String> {
+    if sections.is_empty() {
+        return Err("No sections to combine".to_string());
+    }
+
+    let mut written = Vec::new();
+
+    if formats.contains(&OutputFormat::Txt) {
+        let path = format!("{}.txt", output_path);
+        fs::write(&path, build_combined_text(&sections, &page_separator))
+            .map_err(|e| format!("Failed to write TXT output: {}", e))?;
+        written.push(path);
+    }
+
+    if formats.contains(&OutputFormat::Docx) {
+        let docx_sections: Vec<DocxSection> = sections
+            .iter()
+            .map(|section| DocxSection {
+                heading: Some(section.title.clone()),
+                pages: section
+                    .pages
+                    .iter()
+                    .map(|p| DocxPage::with_footnotes(p.text.clone(), p.footnotes.clone()))
+                    .collect(),
+            })
+            .collect();
+        let path = format!("{}.docx", output_path);
+        write_docx(&path, &docx_sections, &DocumentMetadata::default())?;
+        written.push(path);
+    }
+
+    if formats.contains(&OutputFormat::Json) {
+        let records = build_section_records(sections);
+        let output = CombinedJsonOutput {
+            version: JSON_OUTPUT_VERSION,
+            page_count: records.iter().map(|s| s.pages.len() as u32).sum(),
+            sections: records,
+        };
+        let json = serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?;
+        let path = format!("{}.json", output_path);
+        fs::write(&path, json).map_err(|e| format!("Failed to write JSON output: {}", e))?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// Where outputs go without a configured output directory: the app's own output
+/// directory on platforms that have one (Android), otherwise next to the source
+fn default_output_dir(source_path: &str, app_output_dir: Option<&Path>) -> String {
+    if let Some(dir) = app_output_dir {
+        return dir.to_string_lossy().to_string();
+    }
+    Path::new(source_path)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| ".".to_string())
+}
+
+/// Output directory for an input created in the workspace (a download or a scan),
+/// which has no folder of its own: `requested`, the configured output directory,
+/// the app's output directory, then `fallback`. The directory is created.
+pub(crate) fn workspace_input_output_dir(
+    requested: Option<String>,
+    fallback: Option<PathBuf>,
+) -> Result<String, TahweelError> {
+    let dir = requested
+        .filter(|dir| !dir.is_empty())
+        .or_else(|| settings::current().output_directory)
+        .map(PathBuf::from)
+        .or_else(|| path_policy::app_output_dir().map(Path::to_path_buf))
+        .or(fallback)
+        .ok_or_else(|| TahweelError::NotFound("No output directory available".to_string()))?;
+    fs::create_dir_all(&dir)?;
+    long_path::path_string(&dir)
+}
+
+/// Resolve and prepare the output directory for a source file: the configured
+/// output directory when set, otherwise the source file's own directory.
+#[tauri::command]
+pub async fn resolve_output_dir(source_path: String) -> Result<String, String> {
+    let dir = match settings::current().output_directory {
+        Some(dir) => dir,
+        None => default_output_dir(&source_path, path_policy::app_output_dir()),
+    };
+    prepare_output_dir(dir).await
+}
+
+/// Create the output directory if needed and verify it is writable by writing and
+/// removing a probe file. Returns the canonical directory path.
+#[tauri::command]
+pub async fn prepare_output_dir(path: String) -> Result<String, String> {
+    let dir = Path::new(&path);
+    if dir.exists() && !dir.is_dir() {
+        return Err(format!("Output path is not a directory: {}", path));
+    }
+
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create output directory {}: {}", path, e))?;
+
+    let probe = dir.join(format!(".tahweel-write-test-{}", uuid::Uuid::new_v4()));
+    fs::write(&probe, b"")
+        .map_err(|e| format!("Output directory is not writable {}: {}", path, e))?;
+    let _ = fs::remove_file(&probe);
+
+    let canonical = dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve output directory {}: {}", path, e))?;
+    Ok(canonical.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::{tempdir, NamedTempFile};
+
+    fn page(text: &str) -> PageInput {
+        PageInput {
+            text: text.to_string(),
+            source_page: None,
+            image_path: None,
+            backend: None,
+            retries: 0,
+            timing: PageTiming::default(),
+            tables: Vec::new(),
+            quran: Vec::new(),
+            language: None,
+            footnotes: None,
+        }
+    }
+
+    #[test]
+    fn test_is_arabic_text() {
+        assert!(is_arabic_text("مرحبا بالعالم"));
+        assert!(is_arabic_text("مرحبا hi"));
+        assert!(!is_arabic_text("Hello World"));
+        assert!(is_arabic_text("123 ..."));
+    }
+
+    #[test]
+    fn test_sha256_file_known_value() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"abc").unwrap();
+
+        let checksum = sha256_file(file.path()).unwrap();
+        assert_eq!(
+            checksum,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_sha256_file_missing() {
+        let result = sha256_file(Path::new("/nonexistent/tahweel/page-0001.png"));
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Failed to open"));
+    }
+
+    #[test]
+    fn test_build_page_records_defaults() {
+        let records = build_page_records(vec![page("  first  "), page("second")]);
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].page, 1);
+        assert_eq!(records[0].source_page, 1);
+        assert_eq!(records[0].text, "first");
+        assert_eq!(records[0].backend, DEFAULT_OCR_BACKEND);
+        assert!(records[0].image_checksum.is_none());
+        assert_eq!(records[1].page, 2);
+        assert_eq!(records[1].source_page, 2);
+    }
+
+    #[test]
+    fn test_build_page_records_with_metadata() {
+        let mut image = NamedTempFile::with_suffix(".png").unwrap();
+        image.write_all(b"fake png").unwrap();
+
+        let mut input = page("نص عربي");
+        input.source_page = Some(7);
+        input.image_path = Some(image.path().to_string_lossy().to_string());
+        input.backend = Some("custom".to_string());
+        input.retries = 2;
+        input.timing = PageTiming {
+            render_ms: Some(120),
+            upload_ms: Some(800),
+            export_ms: Some(300),
+        };
+
+        let records = build_page_records(vec![input]);
+        assert_eq!(records[0].source_page, 7);
+        assert_eq!(records[0].backend, "custom");
+        assert_eq!(records[0].retries, 2);
+        assert_eq!(records[0].timing.upload_ms, Some(800));
+        assert_eq!(
+            records[0].image_checksum,
+            Some(sha256_file(image.path()).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_build_page_records_missing_image_has_no_checksum() {
+        let mut input = page("text");
+        input.image_path = Some("/nonexistent/page-0001.png".to_string());
+
+        let records = build_page_records(vec![input]);
+        assert!(records[0].image_checksum.is_none());
+    }
+
+    #[test]
+    fn test_page_input_deserializes_camel_case() {
+        let json = r#"{
+            "text": "hello",
+            "sourcePage": 3,
+            "imagePath": "/tmp/page-0003.png",
+            "retries": 1,
+            "timing": { "renderMs": 10, "uploadMs": 20 }
+        }"#;
+
+        let input: PageInput = serde_json::from_str(json).unwrap();
+        assert_eq!(input.source_page, Some(3));
+        assert_eq!(input.image_path.as_deref(), Some("/tmp/page-0003.png"));
+        assert_eq!(input.retries, 1);
+        assert_eq!(input.timing.render_ms, Some(10));
+        assert_eq!(input.timing.export_ms, None);
+    }
+
+    #[test]
+    fn test_page_input_deserializes_text_only() {
+        let input: PageInput = serde_json::from_str(r#"{"text": "only text"}"#).unwrap();
+        assert_eq!(input.retries, 0);
+        assert!(input.backend.is_none());
+        assert_eq!(input.timing, PageTiming::default());
+    }
+
+    #[test]
+    fn test_document_metadata_normalized() {
+        let metadata: DocumentMetadata = serde_json::from_str(
+            r#"{"title": " كتاب التوحيد ", "author": "ابن خزيمة", "subject": ""}"#,
+        )
+        .unwrap();
+
+        let normalized = metadata.normalized();
+        assert_eq!(normalized.title.as_deref(), Some("كتاب التوحيد"));
+        assert_eq!(normalized.subject, None);
+        assert_eq!(
+            serde_json::to_value(&normalized).unwrap(),
+            serde_json::json!({"title": "كتاب التوحيد", "author": "ابن خزيمة"})
+        );
+        assert!(DocumentMetadata {
+            subject: Some(" ".to_string()),
+            ..Default::default()
+        }
+        .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_write_json_output_structure() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("book").to_string_lossy().to_string();
+
+        let path = write_json_output(
+            base.clone(),
+            "/docs/book.pdf".to_string(),
+            vec![page("one"), page("two")],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(path, format!("{}.json", base));
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(parsed["version"], JSON_OUTPUT_VERSION);
+        assert_eq!(parsed["source"], "/docs/book.pdf");
+        assert_eq!(parsed["pageCount"], 2);
+        assert_eq!(parsed["pages"][1]["text"], "two");
+        assert_eq!(parsed["pages"][1]["sourcePage"], 2);
+        assert!(parsed["pages"][0]["imageChecksum"].is_null());
+        assert!(parsed["pages"][0]["timing"]["renderMs"].is_null());
+        assert!(parsed["pages"][0].get("tables").is_none());
+    }
+
+    #[test]
+    fn test_build_page_records_keeps_table_regions() {
+        let mut input = page("table page");
+        input.tables = vec![serde_json::from_value(serde_json::json!({
+            "page": 1,
+            "bbox": { "x": 1, "y": 2, "width": 3, "height": 4 },
+            "rows": 3,
+            "columns": 2,
+            "path": "/out/book_tables/page-0001-table-1.csv"
+        }))
+        .unwrap()];
+
+        let records = build_page_records(vec![input]);
+        let json = serde_json::to_value(&records[0]).unwrap();
+        assert_eq!(json["tables"][0]["columns"], 2);
+        assert_eq!(json["tables"][0]["bbox"]["height"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_write_json_output_invalid_path() {
+        let result = write_json_output(
+            "/nonexistent/dir/book".to_string(),
+            "book.pdf".to_string(),
+            vec![page("text")],
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Failed to write JSON output"));
+    }
+
+    fn section(title: &str, texts: &[&str]) -> BookSection {
+        BookSection {
+            title: title.to_string(),
+            source_path: format!("/docs/{}.pdf", title),
+            pages: texts.iter().map(|t| page(t)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_combined_format_deserializes_lowercase() {
+        let formats: Vec<OutputFormat> = serde_json::from_str(r#"["txt", "docx"]"#).unwrap();
+        assert_eq!(formats, vec![OutputFormat::Txt, OutputFormat::Docx]);
+    }
+
+    #[test]
+    fn test_build_combined_text_orders_sections() {
+        let text = build_combined_text(
+            &[
+                section("chapter-01", &["one ", "two"]),
+                section("chapter-02", &["three"]),
+            ],
+            "\n\nPAGE_SEPARATOR\n\n",
+        );
+        assert_eq!(
+            text,
+            "chapter-01\n\none\n\nPAGE_SEPARATOR\n\ntwo\n\nPAGE_SEPARATOR\n\nchapter-02\n\nthree"
+        );
+    }
+
+    #[test]
+    fn test_build_section_records_continuous_numbering() {
+        let records = build_section_records(vec![section("a", &["1", "2"]), section("b", &["3"])]);
+        assert_eq!(records[0].first_page, 1);
+        assert_eq!(records[1].first_page, 3);
+        assert_eq!(records[1].pages[0].page, 3);
+        assert_eq!(records[1].pages[0].source_page, 1);
+        assert_eq!(records[1].source, "/docs/b.pdf");
+    }
+
+    #[tokio::test]
+    async fn test_write_combined_output_all_formats() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("book").to_string_lossy().to_string();
+
+        let paths = write_combined_output(
+            base.clone(),
+            vec![
+                section("الباب الأول", &["نص"]),
+                section("الباب الثاني", &["نص آخر"]),
+            ],
+            vec![OutputFormat::Txt, OutputFormat::Json, OutputFormat::Docx],
+            "\n".to_string(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            paths,
+            vec![
+                format!("{}.txt", base),
+                format!("{}.docx", base),
+                format!("{}.json", base)
+            ]
+        );
+        let txt = fs::read_to_string(&paths[0]).unwrap();
+        assert!(txt.find("الباب الأول").unwrap() < txt.find("الباب الثاني").unwrap());
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&paths[2]).unwrap()).unwrap();
+        assert_eq!(parsed["pageCount"], 2);
+        assert_eq!(parsed["sections"][1]["title"], "الباب الثاني");
+        assert_eq!(parsed["sections"][1]["firstPage"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_write_combined_output_requires_sections() {
+        let result = write_combined_output(
+            "book".to_string(),
+            vec![],
+            vec![OutputFormat::Txt],
+            "\n".to_string(),
+        )
+        .await;
+        assert_eq!(result.unwrap_err(), "No sections to combine");
+    }
+
+    #[test]
+    fn test_default_output_dir() {
+        assert_eq!(default_output_dir("/books/a.pdf", None), "/books");
+        assert_eq!(default_output_dir("a.pdf", None), ".");
+        assert_eq!(
+            default_output_dir(
+                "content://com.android.providers/document/1",
+                Some(Path::new("/data/out"))
+            ),
+            "/data/out"
+        );
+    }
+
+    #[test]
+    fn test_workspace_input_output_dir() {
+        let temp = tempdir().unwrap();
+        let requested = temp.path().join("requested");
+        let fallback = temp.path().join("fallback");
+
+        let dir = workspace_input_output_dir(
+            Some(requested.to_string_lossy().to_string()),
+            Some(fallback.clone()),
+        )
+        .unwrap();
+        assert_eq!(dir, requested.to_string_lossy());
+        assert!(requested.is_dir());
+
+        let dir = workspace_input_output_dir(Some(String::new()), Some(fallback.clone())).unwrap();
+        assert_eq!(dir, fallback.to_string_lossy());
+        assert!(fallback.is_dir());
+
+        let err = workspace_input_output_dir(None, None).unwrap_err();
+        assert_eq!(err.kind(), "notFound");
+    }
+
+    #[tokio::test]
+    async fn test_prepare_output_dir_creates_nested_directory() {
+        let temp = tempdir().unwrap();
+        let target = temp.path().join("out").join("nested");
+
+        let resolved = prepare_output_dir(target.to_string_lossy().to_string())
+            .await
+            .unwrap();
+
+        assert!(target.is_dir());
+        assert_eq!(Path::new(&resolved), target.canonicalize().unwrap());
+        assert_eq!(fs::read_dir(&target).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_prepare_output_dir_existing_directory() {
+        let temp = tempdir().unwrap();
+        let result = prepare_output_dir(temp.path().to_string_lossy().to_string()).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_prepare_output_dir_rejects_file() {
+        let file = NamedTempFile::new().unwrap();
+        let result = prepare_output_dir(file.path().to_string_lossy().to_string()).await;
+        assert!(result.unwrap_err().contains("not a directory"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_prepare_output_dir_read_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempdir().unwrap();
+        let target = temp.path().join("readonly");
+        fs::create_dir(&target).unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o555)).unwrap();
+
+        let probe = target.join("probe");
+        if fs::write(&probe, b"").is_ok() {
+            // Running with elevated privileges; permissions are not enforced
+            let _ = fs::remove_file(&probe);
+            return;
+        }
+
+        let result = prepare_output_dir(target.to_string_lossy().to_string()).await;
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(result.unwrap_err().contains("not writable"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_output_dir_defaults_to_source_directory() {
+        let temp = tempdir().unwrap();
+        let source = temp.path().join("book.pdf");
+
+        let resolved = resolve_output_dir(source.to_string_lossy().to_string())
+            .await
+            .unwrap();
+        assert_eq!(Path::new(&resolved), temp.path().canonicalize().unwrap());
+    }
+}
@@ -0,0 +1,296 @@
+use crate::app_paths::{self, Area};
+use crate::error::TahweelError;
+use crate::settings;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Answers whether a path was granted by the user (dialog selections land in the fs scope)
+type ScopeCheck = Box<dyn Fn(&Path) -> bool + Send + Sync>;
+
+static SCOPE_CHECK: OnceLock<ScopeCheck> = OnceLock::new();
+/// Parent of the workspace on mobile, where it lives in the app cache
+static WORKSPACE_ROOT: OnceLock<PathBuf> = OnceLock::new();
+/// App-owned output directory used when inputs have no folder of their own (Android)
+static APP_OUTPUT_DIR: OnceLock<PathBuf> = OnceLock::new();
+/// App-owned output directory used by archival mode, which never writes beside sources
+static ARCHIVAL_OUTPUT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// What a command wants to do with a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathAccess {
+    Write,
+    Delete,
+    Open,
+    /// Reading a file to send it off the machine (email attachments)
+    Read,
+}
+
+/// Directory holding the app's temporary files (rendered pages, scratch output):
+/// the `workspaces` area of the app-data layout. Tests use the system temp dir
+/// itself so `tempdir()` paths are inside it.
+pub fn workspace() -> PathBuf {
+    if cfg!(test) {
+        std::env::temp_dir()
+    } else {
+        WORKSPACE_ROOT
+            .get()
+            .map(|root| root.join("tahweel"))
+            .unwrap_or_else(|| app_paths::dir(Area::Workspaces))
+    }
+}
+
+/// Keep the workspace under `root` instead of the app-data layout
+pub fn set_workspace_root(root: PathBuf) {
+    let _ = WORKSPACE_ROOT.set(root);
+}
+
+/// Write outputs to `dir` by default; it is always allowed for writes and opens
+pub fn set_app_output_dir(dir: PathBuf) {
+    let _ = APP_OUTPUT_DIR.set(dir);
+}
+
+/// The app-owned output directory, when the platform has one
+pub fn app_output_dir() -> Option<&'static Path> {
+    APP_OUTPUT_DIR.get().map(PathBuf::as_path)
+}
+
+/// Write archival-mode outputs to `dir`; it is always allowed for writes and opens
+pub fn set_archival_output_dir(dir: PathBuf) {
+    let _ = ARCHIVAL_OUTPUT_DIR.set(dir);
+}
+
+/// The output directory for archival mode: the app's output directory when the
+/// platform has one, otherwise the directory set at startup
+pub fn archival_output_dir() -> Option<&'static Path> {
+    app_output_dir().or_else(|| ARCHIVAL_OUTPUT_DIR.get().map(PathBuf::as_path))
+}
+
+/// Use the fs plugin scope to recognize paths picked through the dialog plugin
+pub fn set_scope_check<F: Fn(&Path) -> bool + Send + Sync + 'static>(check: F) {
+    let _ = SCOPE_CHECK.set(Box::new(check));
+}
+
+/// Canonicalize `path`, resolving symlinks in its nearest existing ancestor.
+/// The non-existent remainder must not contain `..`, so it cannot escape.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() {
+        return None;
+    }
+    let mut existing = path;
+    let mut remainder = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            let mut resolved = canonical;
+            for component in remainder.iter().rev() {
+                resolved.push(component);
+            }
+            return Some(resolved);
+        }
+        match existing.components().next_back() {
+            Some(Component::Normal(name)) => remainder.push(name.to_os_string()),
+            _ => return None,
+        }
+        existing = existing.parent()?;
+    }
+}
+
+/// An output like `<dir>/book.docx` is allowed when the user picked `<dir>/book.pdf`
+fn sibling_input_allowed(path: &Path, scope_allows: &dyn Fn(&Path) -> bool) -> bool {
+    let (Some(dir), Some(stem)) = (path.parent(), path.file_stem()) else {
+        return false;
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .any(|candidate| candidate.file_stem() == Some(stem) && scope_allows(&candidate))
+}
+
+/// A folder can be opened when it directly contains something the user picked
+fn contains_allowed_entry(dir: &Path, scope_allows: &dyn Fn(&Path) -> bool) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .any(|entry| scope_allows(&entry))
+}
+
+/// Decide whether `path` may be used for `access`. Deletes are confined to the
+/// workspace; writes, reads and opens are also allowed in output directories the
+/// user chose (settings or dialog) and next to inputs the user picked.
+pub fn check_with(
+    path: &Path,
+    access: PathAccess,
+    workspace: &Path,
+    output_dirs: &[PathBuf],
+    scope_allows: &dyn Fn(&Path) -> bool,
+) -> Result<PathBuf, TahweelError> {
+    let denied = || TahweelError::PathNotAllowed(format!("Path not allowed: {}", path.display()));
+    let resolved = resolve(path).ok_or_else(denied)?;
+
+    let in_workspace = resolve(workspace)
+        .map(|ws| resolved.starts_with(&ws) && resolved != ws)
+        .unwrap_or(false);
+    if in_workspace {
+        return Ok(resolved);
+    }
+    if access == PathAccess::Delete {
+        return Err(denied());
+    }
+
+    let in_output_dir = output_dirs
+        .iter()
+        .filter_map(|dir| resolve(dir))
+        .any(|dir| resolved.starts_with(dir));
+    let allowed = in_output_dir
+        || scope_allows(&resolved)
+        || sibling_input_allowed(&resolved, scope_allows)
+        || (access == PathAccess::Open && contains_allowed_entry(&resolved, scope_allows));
+
+    if allowed {
+        Ok(resolved)
+    } else {
+        Err(denied())
+    }
+}
+
+/// Check `path` against the app's policy, returning the resolved path to operate on
+pub fn check(path: &Path, access: PathAccess) -> Result<PathBuf, TahweelError> {
+    let output_dirs: Vec<PathBuf> = settings::current()
+        .output_directory
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(app_output_dir().map(Path::to_path_buf))
+        .chain(archival_output_dir().map(Path::to_path_buf))
+        .collect();
+    let scope_allows = |p: &Path| SCOPE_CHECK.get().map(|check| check(p)).unwrap_or(false);
+    check_with(path, access, &workspace(), &output_dirs, &scope_allows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn deny_all(_: &Path) -> bool {
+        false
+    }
+
+    #[test]
+    fn test_workspace_allows_all_access() {
+        let ws = tempdir().unwrap();
+        let target = ws.path().join("pages");
+        fs::create_dir(&target).unwrap();
+
+        for access in [PathAccess::Write, PathAccess::Delete, PathAccess::Open] {
+            assert!(check_with(&target, access, ws.path(), &[], &deny_all).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_workspace_root_cannot_be_deleted() {
+        let ws = tempdir().unwrap();
+        let result = check_with(ws.path(), PathAccess::Delete, ws.path(), &[], &deny_all);
+        assert_eq!(result.unwrap_err().kind(), "pathNotAllowed");
+    }
+
+    #[test]
+    fn test_delete_outside_workspace_rejected() {
+        let ws = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        let result = check_with(
+            outside.path(),
+            PathAccess::Delete,
+            ws.path(),
+            &[outside.path().to_path_buf()],
+            &|_: &Path| true,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parent_components_cannot_escape_workspace() {
+        let ws = tempdir().unwrap();
+        let sneaky = ws.path().join("missing").join("..").join("..").join("etc");
+        let result = check_with(&sneaky, PathAccess::Delete, ws.path(), &[], &deny_all);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_relative_path_rejected() {
+        let ws = tempdir().unwrap();
+        let result = check_with(
+            Path::new("out.docx"),
+            PathAccess::Write,
+            ws.path(),
+            &[],
+            &deny_all,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_write_in_output_dir() {
+        let ws = tempdir().unwrap();
+        let out = tempdir().unwrap();
+        let target = out.path().join("book.docx");
+
+        assert!(check_with(&target, PathAccess::Write, ws.path(), &[], &deny_all).is_err());
+        let resolved = check_with(
+            &target,
+            PathAccess::Write,
+            ws.path(),
+            &[out.path().to_path_buf()],
+            &deny_all,
+        )
+        .unwrap();
+        assert!(resolved.ends_with("book.docx"));
+    }
+
+    #[test]
+    fn test_write_next_to_picked_input() {
+        let ws = tempdir().unwrap();
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("book.pdf");
+        fs::write(&input, b"%PDF").unwrap();
+        let picked = input.canonicalize().unwrap();
+        let scope = move |p: &Path| p == picked;
+
+        let output = dir.path().join("book.docx");
+        assert!(check_with(&output, PathAccess::Write, ws.path(), &[], &scope).is_ok());
+        let other = dir.path().join("other.docx");
+        assert!(check_with(&other, PathAccess::Write, ws.path(), &[], &scope).is_err());
+    }
+
+    #[test]
+    fn test_read_follows_the_write_rules() {
+        let ws = tempdir().unwrap();
+        let out = tempdir().unwrap();
+        let output = out.path().join("book.txt");
+        fs::write(&output, b"text").unwrap();
+        let outside = tempdir().unwrap();
+        let secret = outside.path().join("id_rsa");
+        fs::write(&secret, b"key").unwrap();
+        let dirs = [out.path().to_path_buf()];
+
+        assert!(check_with(&output, PathAccess::Read, ws.path(), &dirs, &deny_all).is_ok());
+        let err = check_with(&secret, PathAccess::Read, ws.path(), &dirs, &deny_all).unwrap_err();
+        assert_eq!(err.kind(), "pathNotAllowed");
+    }
+
+    #[test]
+    fn test_open_folder_containing_picked_input() {
+        let ws = tempdir().unwrap();
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("scan.png");
+        fs::write(&input, b"png").unwrap();
+        let picked = input.canonicalize().unwrap();
+        let scope = move |p: &Path| p == picked;
+
+        assert!(check_with(dir.path(), PathAccess::Open, ws.path(), &[], &scope).is_ok());
+        assert!(check_with(dir.path(), PathAccess::Write, ws.path(), &[], &scope).is_err());
+    }
+}
@@ -1,18 +1,85 @@
-use image::ImageFormat;
+use crate::drive_safe::{self, DownscaledPage};
+use crate::encryption::{self, KeyId};
+use crate::error::TahweelError;
+use crate::events::{ThrottledEmitter, PROGRESS_INTERVAL};
+use crate::job_usage;
+use crate::jobs::{self, JobEventKind};
+use crate::long_path;
+use crate::memory;
+use crate::mobile;
+use crate::output::DocumentMetadata;
+use crate::path_policy::{self, PathAccess};
+use crate::perf::{self, Stage};
+use crate::power;
+use crate::settings::{self, ColorMode, Settings};
+use crate::trash;
+use image::{DynamicImage, GrayImage, ImageFormat, RgbImage};
 use pdfium_render::prelude::*;
 use rayon::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tauri::{AppHandle, Emitter, Manager};
-use tempfile::TempDir;
 
 /// Standard US Letter page width in inches (used for DPI calculation)
-const PAGE_WIDTH_INCHES: i32 = 8;
+pub(crate) const PAGE_WIDTH_INCHES: i32 = 8;
 /// Standard US Letter page height in inches (used for DPI calculation)
-const PAGE_HEIGHT_INCHES: i32 = 12;
+pub(crate) const PAGE_HEIGHT_INCHES: i32 = 12;
+/// Effective resolution auto DPI aims for
+const AUTO_TARGET_DPI: f32 = 300.0;
+/// Lowest resolution auto DPI renders at, even for low-resolution scans
+const AUTO_MIN_DPI: f32 = 150.0;
+/// Largest page auto DPI renders, in pixels: what a fixed 300 DPI render of the
+/// standard page produces, so uploads stay within the size that setting already sends
+const AUTO_MAX_PIXELS: f32 = (PAGE_WIDTH_INCHES * PAGE_HEIGHT_INCHES) as f32 * 300.0 * 300.0;
+
+/// Cancel flags of the splits running under a job id (see `cancel_split`)
+static SPLIT_CANCELS: Mutex<Option<HashMap<String, Arc<AtomicBool>>>> = Mutex::new(None);
+
+/// A split's entry in `SPLIT_CANCELS`, removed when the split returns
+struct SplitCancel {
+    job_id: Option<String>,
+    flag: Arc<AtomicBool>,
+}
+
+impl SplitCancel {
+    fn register(job_id: Option<&str>) -> Self {
+        let flag = Arc::new(AtomicBool::new(false));
+        if let Some(job_id) = job_id {
+            SPLIT_CANCELS
+                .lock()
+                .unwrap_or_else(|p| p.into_inner())
+                .get_or_insert_with(HashMap::new)
+                .insert(job_id.to_string(), flag.clone());
+        }
+        Self {
+            job_id: job_id.map(str::to_string),
+            flag,
+        }
+    }
+
+    fn is_set(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for SplitCancel {
+    fn drop(&mut self) {
+        if let Some(job_id) = &self.job_id {
+            if let Some(cancels) = SPLIT_CANCELS
+                .lock()
+                .unwrap_or_else(|p| p.into_inner())
+                .as_mut()
+            {
+                cancels.remove(job_id);
+            }
+        }
+    }
+}
 
 #[derive(Debug, Serialize)]
 pub struct SplitResult {
@@ -20,10 +87,40 @@ pub struct SplitResult {
     pub image_paths: Vec<String>,
     #[serde(rename = "tempDir")]
     pub temp_dir: String,
+    /// Render duration in milliseconds for each page, in page order
+    #[serde(rename = "renderTimesMs")]
+    pub render_times_ms: Vec<u64>,
+    /// Render resolution of each page, in page order (varies per page under auto DPI)
+    #[serde(rename = "pageDpis")]
+    pub page_dpis: Vec<u32>,
+    /// Pages that only rendered on a retry, usually at a lower DPI
+    #[serde(rename = "degradedPages")]
+    pub degraded_pages: Vec<DegradedPage>,
+    /// Pages scaled down to fit Drive's image limits (`driveSafeImages` setting)
+    #[serde(rename = "downscaledPages")]
+    pub downscaled_pages: Vec<DownscaledPage>,
+}
+
+/// Path, render time (ms), DPI and Drive-safe downscaling of a rendered page
+type RenderedPage = (String, u64, u32, Option<DownscaledPage>);
+
+/// A page whose first render failed and that was rendered again on its own
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DegradedPage {
+    /// 1-based page number
+    pub page: u32,
+    /// Resolution the page was finally rendered at
+    pub dpi: u32,
+    /// Why the first render failed
+    pub error: String,
 }
 
+/// Payload of the `split-progress` event
 #[derive(Clone, Serialize)]
-struct SplitProgress {
+pub(crate) struct SplitProgress {
+    /// Job being split, when `split_pdf` was given one
+    #[serde(rename = "jobId", skip_serializing_if = "Option::is_none")]
+    job_id: Option<String>,
     #[serde(rename = "currentPage")]
     current_page: u32,
     #[serde(rename = "totalPages")]
@@ -31,8 +128,20 @@ struct SplitProgress {
     percentage: f32,
 }
 
+impl SplitProgress {
+    /// `done` of `total` pages written
+    pub(crate) fn new(job_id: Option<String>, done: u32, total: u32) -> Self {
+        Self {
+            job_id,
+            current_page: done,
+            total_pages: total,
+            percentage: ((done as f32 / total as f32) * 100.0).round(),
+        }
+    }
+}
+
 /// Find the PDFium library path
-fn find_pdfium_library(app: &AppHandle) -> Result<PathBuf, String> {
+pub(crate) fn find_pdfium_library(app: &AppHandle) -> Result<PathBuf, TahweelError> {
     let lib_name = if cfg!(target_os = "windows") {
         "pdfium.dll"
     } else if cfg!(target_os = "macos") {
@@ -69,34 +178,339 @@ fn find_pdfium_library(app: &AppHandle) -> Result<PathBuf, String> {
         }
     }
 
-    Err(format!(
+    Err(TahweelError::PdfiumUnavailable(format!(
         "PDFium library '{}' not found. Searched: {:?}",
         lib_name, search_paths
-    ))
+    )))
 }
 
 /// Create a PDFium instance
-fn create_pdfium(app: &AppHandle) -> Result<Pdfium, String> {
+fn create_pdfium(app: &AppHandle) -> Result<Pdfium, TahweelError> {
     let lib_path = find_pdfium_library(app)?;
 
-    let bindings = Pdfium::bind_to_library(lib_path.to_str().unwrap())
-        .map_err(|e| format!("Failed to bind to PDFium library: {}", e))?;
+    let bindings = Pdfium::bind_to_library(&lib_path).map_err(|e| {
+        TahweelError::PdfiumUnavailable(format!("Failed to bind to PDFium library: {}", e))
+    })?;
 
     Ok(Pdfium::new(bindings))
 }
 
 /// Get the total number of pages in a PDF file
 #[tauri::command]
-pub async fn get_pdf_page_count(pdf_path: String, app: AppHandle) -> Result<u32, String> {
+#[tracing::instrument(skip(app), err)]
+pub async fn get_pdf_page_count(pdf_path: String, app: AppHandle) -> Result<u32, TahweelError> {
+    page_count(&pdf_path, &app)
+}
+
+/// Page count and descriptive metadata of a PDF
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PdfInfo {
+    #[serde(rename = "pageCount")]
+    pub page_count: u32,
+    #[serde(flatten)]
+    pub metadata: DocumentMetadata,
+}
+
+/// Get the page count and the title, author and subject of a PDF (blank
+/// fields are left out), to carry into the outputs of its job
+#[tauri::command]
+#[tracing::instrument(skip(app), err)]
+pub async fn get_pdf_info(pdf_path: String, app: AppHandle) -> Result<PdfInfo, TahweelError> {
     let pdfium = create_pdfium(&app)?;
+    let document = pdfium
+        .load_pdf_from_file(&long_path::extended(Path::new(&pdf_path)), None)
+        .map_err(|e| TahweelError::Pdf(format!("Failed to load PDF: {}", e)))?;
+
+    let tags = document.metadata();
+    let tag = |kind| tags.get(kind).map(|tag| tag.value().to_string());
+    Ok(PdfInfo {
+        page_count: document.pages().len() as u32,
+        metadata: DocumentMetadata {
+            title: tag(PdfDocumentMetadataTagType::Title),
+            author: tag(PdfDocumentMetadataTagType::Author),
+            subject: tag(PdfDocumentMetadataTagType::Subject),
+        }
+        .normalized(),
+    })
+}
+
+/// Number of pages in a PDF
+pub(crate) fn page_count(pdf_path: &str, app: &AppHandle) -> Result<u32, TahweelError> {
+    let pdfium = create_pdfium(app)?;
 
     let document = pdfium
-        .load_pdf_from_file(&pdf_path, None)
-        .map_err(|e| format!("Failed to load PDF: {}", e))?;
+        .load_pdf_from_file(&long_path::extended(Path::new(pdf_path)), None)
+        .map_err(|e| TahweelError::Pdf(format!("Failed to load PDF: {}", e)))?;
 
     Ok(document.pages().len() as u32)
 }
 
+/// Render resolution for a page `width_pt` x `height_pt` points in size under auto DPI.
+/// Targets ~300 effective DPI; scanned pages (`image_dpi` is the resolution of their
+/// page image) are not rendered above the scan's own resolution, which adds no detail.
+/// Large pages are scaled down to stay within `AUTO_MAX_PIXELS`.
+pub(crate) fn auto_page_dpi(width_pt: f32, height_pt: f32, image_dpi: Option<f32>) -> u32 {
+    let target = match image_dpi {
+        Some(dpi) if dpi.is_finite() && dpi > 0.0 => dpi.clamp(AUTO_MIN_DPI, AUTO_TARGET_DPI),
+        _ => AUTO_TARGET_DPI,
+    };
+    let area_sq_in = (width_pt / 72.0) * (height_pt / 72.0);
+    let cap = if area_sq_in > 0.0 {
+        (AUTO_MAX_PIXELS / area_sq_in).sqrt()
+    } else {
+        target
+    };
+    target.min(cap).max(72.0).round() as u32
+}
+
+/// Resolution of a scanned page's image: the DPI of the widest image object
+/// covering at least half the page width, if any
+fn scanned_image_dpi(page: &PdfPage) -> Option<f32> {
+    let page_width = page.width().value;
+    page.objects()
+        .iter()
+        .filter_map(|object| {
+            let width = object.width().ok()?.value;
+            if width < page_width / 2.0 {
+                return None;
+            }
+            Some((width, object.as_image_object()?.horizontal_dpi().ok()?))
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, dpi)| dpi)
+}
+
+/// Render config and resolution for `page`: the fixed `dpi`, or the page's own
+/// resolution from `auto_page_dpi` when `auto` is set
+fn page_render_config(page: &PdfPage, dpi: u32, auto: bool) -> (PdfRenderConfig, u32) {
+    if auto {
+        let dpi = auto_page_dpi(
+            page.width().value,
+            page.height().value,
+            scanned_image_dpi(page),
+        );
+        let config = PdfRenderConfig::new()
+            .scale_page_by_factor(dpi as f32 / 72.0)
+            .rotate_if_landscape(PdfPageRenderRotation::None, false);
+        (config, dpi)
+    } else {
+        let config = PdfRenderConfig::new()
+            .set_target_width((dpi as i32) * PAGE_WIDTH_INCHES)
+            .set_maximum_height((dpi as i32) * PAGE_HEIGHT_INCHES)
+            .rotate_if_landscape(PdfPageRenderRotation::None, false);
+        (config, dpi)
+    }
+}
+
+/// Resolutions a failed page is retried at, in order: the original DPI, then
+/// two thirds and half of it (never below 72)
+pub(crate) fn retry_dpis(dpi: u32) -> Vec<u32> {
+    let mut dpis: Vec<u32> = [dpi, dpi * 2 / 3, dpi / 2]
+        .into_iter()
+        .map(|d| d.max(72))
+        .collect();
+    dpis.dedup();
+    dpis
+}
+
+/// A pixel counts as colored when its channels differ by more than this
+/// (tinted paper stays below it)
+const COLOR_CHROMA_MIN: u8 = 40;
+/// In `auto` mode pages keep RGB when at least this fraction of pixels is colored
+const COLOR_PIXELS_MIN_FRACTION: f64 = 0.005;
+
+/// Whether enough of the page is in color to be worth keeping RGB
+fn has_color(image: &RgbImage) -> bool {
+    let colored = image
+        .pixels()
+        .filter(|p| {
+            let [r, g, b] = p.0;
+            r.max(g).max(b) - r.min(g).min(b) > COLOR_CHROMA_MIN
+        })
+        .count();
+    colored as f64 >= image.pixels().len() as f64 * COLOR_PIXELS_MIN_FRACTION
+}
+
+/// Otsu's threshold: the gray level that best separates ink from paper
+fn otsu_threshold(image: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        histogram[pixel.0[0] as usize] += 1;
+    }
+    let total: u64 = histogram.iter().sum();
+    let sum_all: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(level, &count)| level as f64 * count as f64)
+        .sum();
+
+    let (mut weight_dark, mut sum_dark) = (0u64, 0.0);
+    let (mut best, mut best_variance) = (127u8, 0.0);
+    for (level, &count) in histogram.iter().enumerate() {
+        weight_dark += count;
+        sum_dark += level as f64 * count as f64;
+        let weight_light = total - weight_dark;
+        if weight_dark == 0 || weight_light == 0 {
+            continue;
+        }
+        let mean_dark = sum_dark / weight_dark as f64;
+        let mean_light = (sum_all - sum_dark) / weight_light as f64;
+        let variance = weight_dark as f64 * weight_light as f64 * (mean_dark - mean_light).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best = level as u8;
+        }
+    }
+    best
+}
+
+/// Convert a rendered page to the pixel format of the `colorMode` setting
+/// before it is saved
+pub(crate) fn apply_color_mode(image: DynamicImage, mode: ColorMode) -> DynamicImage {
+    match mode {
+        ColorMode::Color => DynamicImage::ImageRgb8(image.into_rgb8()),
+        ColorMode::Grayscale => DynamicImage::ImageLuma8(image.into_luma8()),
+        ColorMode::Bitonal => {
+            let mut gray = image.into_luma8();
+            let threshold = otsu_threshold(&gray);
+            for pixel in gray.pixels_mut() {
+                pixel.0[0] = if pixel.0[0] > threshold { 255 } else { 0 };
+            }
+            DynamicImage::ImageLuma8(gray)
+        }
+        ColorMode::Auto => {
+            let rgb = image.into_rgb8();
+            if has_color(&rgb) {
+                DynamicImage::ImageRgb8(rgb)
+            } else {
+                DynamicImage::ImageLuma8(DynamicImage::ImageRgb8(rgb).into_luma8())
+            }
+        }
+    }
+}
+
+/// How rendered pages are saved: pixel format, Drive-safe sizing, and the
+/// workspace key (if encrypted)
+#[derive(Clone, Copy)]
+pub(crate) struct PageOutput {
+    color_mode: ColorMode,
+    drive_safe: bool,
+    key: Option<KeyId>,
+}
+
+impl PageOutput {
+    /// Pages saved as `settings` ask, encrypted with `key`
+    pub(crate) fn new(settings: &Settings, key: Option<KeyId>) -> Self {
+        Self {
+            color_mode: settings.color_mode,
+            drive_safe: settings.drive_safe_images,
+            key,
+        }
+    }
+}
+
+/// Save rendered page `page_number` (1-based) as a PNG at `output_path`. In
+/// Drive-safe mode the page is scaled down to fit `drive_safe::fit_png`'s limits,
+/// and returned when it had to be.
+pub(crate) fn save_page(
+    image: DynamicImage,
+    page_number: u32,
+    output_path: &Path,
+    output: PageOutput,
+) -> Result<Option<DownscaledPage>, TahweelError> {
+    let image = apply_color_mode(image, output.color_mode);
+    if !output.drive_safe {
+        encryption::save_png(&image, output_path, output.key)?;
+        return Ok(None);
+    }
+    let (png, downscaled) = drive_safe::fit_png(&image, page_number)?;
+    encryption::write_file(output_path, &png, output.key)?;
+    if let Some(page) = &downscaled {
+        tracing::info!(
+            page = page_number,
+            width = page.width,
+            height = page.height,
+            bytes = page.bytes,
+            "Downscaled page to fit Drive limits"
+        );
+    }
+    Ok(downscaled)
+}
+
+fn bind_pdfium(lib_path: &Path) -> Result<Pdfium, TahweelError> {
+    let bindings = Pdfium::bind_to_library(lib_path).map_err(|e| {
+        TahweelError::PdfiumUnavailable(format!("Failed to bind to PDFium library: {}", e))
+    })?;
+    Ok(Pdfium::new(bindings))
+}
+
+/// Render page `page_num` (0-based) of `pdf_path` to a PNG at `output_path` with the
+/// config from `configure`, saved as `output` says, returning the DPI it was
+/// rendered at and whether it had to be downscaled
+fn render_page_png(
+    pdfium: &Pdfium,
+    pdf_path: &Path,
+    page_num: u32,
+    configure: impl FnOnce(&PdfPage) -> (PdfRenderConfig, u32),
+    output_path: &Path,
+    output: PageOutput,
+) -> Result<(u32, Option<DownscaledPage>), TahweelError> {
+    let document = pdfium
+        .load_pdf_from_file(pdf_path, None)
+        .map_err(|e| TahweelError::Pdf(format!("Failed to load PDF: {}", e)))?;
+    let page = document
+        .pages()
+        .get(page_num as u16)
+        .map_err(|e| TahweelError::Pdf(format!("Failed to get page {}: {}", page_num + 1, e)))?;
+    let (render_config, dpi) = configure(&page);
+
+    let image = page
+        .render_with_config(&render_config)
+        .map_err(|e| TahweelError::Pdf(format!("Failed to render page {}: {}", page_num + 1, e)))?
+        .as_image();
+
+    // Save as PNG (lossless, better for OCR quality)
+    let downscaled = save_page(image, page_num + 1, output_path, output).map_err(|e| {
+        TahweelError::Io(format!(
+            "Failed to save page {} as PNG: {}",
+            page_num + 1,
+            e.message()
+        ))
+    })?;
+    Ok((dpi, downscaled))
+}
+
+/// Render a page that failed in the parallel pass again, on this thread with a
+/// fresh PDFium instance, at each of `retry_dpis(dpi)` until one succeeds
+fn retry_render(
+    lib_path: &Path,
+    pdf_path: &Path,
+    page_num: u32,
+    dpi: u32,
+    output_path: &Path,
+    output: PageOutput,
+) -> Result<(u32, Option<DownscaledPage>), TahweelError> {
+    let pdfium = bind_pdfium(lib_path)?;
+    let mut last_error = None;
+    for retry_dpi in retry_dpis(dpi) {
+        match render_page_png(
+            &pdfium,
+            pdf_path,
+            page_num,
+            |page| page_render_config(page, retry_dpi, false),
+            output_path,
+            output,
+        ) {
+            Ok(rendered) => return Ok(rendered),
+            Err(error) => {
+                tracing::warn!(page = page_num + 1, dpi = retry_dpi, %error, "Render retry failed");
+                last_error = Some(error);
+            }
+        }
+    }
+    Err(last_error.expect("retry_dpis is never empty"))
+}
+
 /// Split a PDF into individual page images with progress events (parallel PNG processing).
 ///
 /// # Memory Considerations
@@ -105,122 +519,318 @@ pub async fn get_pdf_page_count(pdf_path: String, app: AppHandle) -> Result<u32,
 /// - **Higher memory usage**: Each thread holds a copy of the PDF in memory
 /// - **Faster processing**: Parallel rendering significantly reduces total time
 ///
-/// Rayon automatically sizes the thread pool to the number of CPU cores, which is
-/// reasonable for most user devices. For extremely large PDFs on low-memory devices,
-/// consider reducing DPI or processing fewer pages at once.
+/// The thread pool size comes from the `renderThreads` setting; the default (0) lets
+/// Rayon size it to the number of CPU cores. Mobile builds cap it at
+/// `MOBILE_RENDER_THREADS`. Each page reserves its estimated memory
+/// with the pipeline's accountant first; when the `memoryBudgetMb` budget is reached,
+/// workers wait for other pages to finish and a `memory-pressure` event is emitted.
+///
+/// `dpi` falls back to the DPI setting when not provided. With `auto_dpi` (default: the
+/// `autoDpi` setting) each page gets its own resolution from `auto_page_dpi` instead,
+/// reported in `pageDpis`. A page that fails to render is retried on its own at
+/// decreasing DPI (`retry_dpis`) and listed in `degradedPages` instead of failing
+/// the whole split. Pages are saved in the pixel format of the `colorMode` setting
+/// (`apply_color_mode`); with the `driveSafeImages` setting, pages beyond Drive's
+/// pixel or byte limits are scaled down and listed in `downscaledPages`. `job_id` tags the log span
+/// so a job's render and upload entries can be traced together, files per-page
+/// render times under the job for `get_performance_summary`, and tags the
+/// `split-progress` events, which are also sent to the job's channels (`job_channels`).
+///
+/// `total_pages` pages are rendered starting at `first_page` (1-based, default 1), so
+/// a very large PDF can be split one shard at a time; page files keep their
+/// document page numbers.
+///
+/// Rendering runs on the blocking thread pool. `cancel_split(job_id)` stops it:
+/// pages not yet started (including those held back on low battery) are
+/// skipped, the page directory is removed and the split fails.
 #[tauri::command]
+#[tracing::instrument(skip(app, job_id), err, fields(job = job_id.as_deref()))]
 pub async fn split_pdf(
     pdf_path: String,
-    dpi: u32,
+    dpi: Option<u32>,
+    auto_dpi: Option<bool>,
     total_pages: u32,
+    first_page: Option<u32>,
+    job_id: Option<String>,
     app: AppHandle,
-) -> Result<SplitResult, String> {
+) -> Result<SplitResult, TahweelError> {
+    let page_indices: Vec<u32> = page_range(first_page, total_pages)?.collect();
+    let settings = settings::current();
+    let dpi = dpi.unwrap_or(settings.dpi);
+    let auto_dpi = auto_dpi.unwrap_or(settings.auto_dpi);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(mobile::render_threads(settings.render_threads, cfg!(mobile)) as usize)
+        .build()
+        .map_err(|e| {
+            TahweelError::Internal(format!("Failed to create render thread pool: {}", e))
+        })?;
+
     // Find library path first (before parallel processing)
     let lib_path = find_pdfium_library(&app)?;
-    let lib_path_str = lib_path.to_str().ok_or("Invalid library path")?.to_string();
 
     // Create temp directory for rendered page images
-    let temp_dir = TempDir::new().map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let workspace = path_policy::workspace();
+    fs::create_dir_all(&workspace)
+        .map_err(|e| TahweelError::Io(format!("Failed to create temp directory: {}", e)))?;
+    let temp_dir = tempfile::Builder::new()
+        .prefix("tahweel-")
+        .tempdir_in(&workspace)
+        .map_err(|e| TahweelError::Io(format!("Failed to create temp directory: {}", e)))?;
     let temp_path_owned = temp_dir.keep();
-    let temp_path_str = temp_path_owned.to_string_lossy().to_string();
+    let temp_path_str = long_path::path_string(&temp_path_owned)?;
+    // One key per render workspace, dropped by `cleanup_temp_dir`
+    let key = encryption::key_if_enabled(&temp_path_str)?;
+    let output = PageOutput::new(&settings, key);
 
     // Atomic counter for progress tracking across threads
     let processed_count = Arc::new(AtomicU32::new(0));
 
-    // Generate page indices for parallel processing
-    let page_indices: Vec<u32> = (0..total_pages).collect();
-
     // Wrap shared values in Arc for thread-safe sharing
-    let pdf_path_arc = Arc::new(pdf_path);
-    let lib_path_arc = Arc::new(lib_path_str);
-    let temp_path_arc = Arc::new(temp_path_str.clone());
-
-    // Parallel page rendering using rayon's work-stealing scheduler
-    let results: Vec<Result<String, String>> = page_indices
-        .par_iter()
-        .map(|&page_num| {
-            // Each thread needs its own PDFium instance (PDFium is not thread-safe)
-            let bindings = Pdfium::bind_to_library(lib_path_arc.as_str())
-                .map_err(|e| format!("Failed to bind to PDFium library: {}", e))?;
-            let pdfium = Pdfium::new(bindings);
-
-            let document = pdfium
-                .load_pdf_from_file(pdf_path_arc.as_str(), None)
-                .map_err(|e| format!("Failed to load PDF: {}", e))?;
-
-            let page = document
-                .pages()
-                .get(page_num as u16)
-                .map_err(|e| format!("Failed to get page {}: {}", page_num + 1, e))?;
-
-            // Configure rendering based on DPI
-            let render_config = PdfRenderConfig::new()
-                .set_target_width((dpi as i32) * PAGE_WIDTH_INCHES)
-                .set_maximum_height((dpi as i32) * PAGE_HEIGHT_INCHES)
-                .rotate_if_landscape(PdfPageRenderRotation::None, false);
-
-            let image = page
-                .render_with_config(&render_config)
-                .map_err(|e| format!("Failed to render page {}: {}", page_num + 1, e))?
-                .as_image();
-
-            // Save as PNG (lossless, better for OCR quality)
-            let output_path =
-                PathBuf::from(temp_path_arc.as_str()).join(format!("page-{:04}.png", page_num + 1));
-            image
-                .into_rgb8()
-                .save_with_format(&output_path, ImageFormat::Png)
-                .map_err(|e| format!("Failed to save page {} as PNG: {}", page_num + 1, e))?;
-
-            // Update progress counter
-            let count = processed_count.fetch_add(1, Ordering::Relaxed) + 1;
-
-            // Emit approximate progress (may be out of order due to parallelism)
-            let _ = app.emit(
-                "split-progress",
-                SplitProgress {
-                    current_page: count,
-                    total_pages,
-                    percentage: ((count as f32 / total_pages as f32) * 100.0).round(),
-                },
-            );
-
-            Ok(output_path.to_string_lossy().to_string())
-        })
-        .collect();
+    // Long paths get the `\\?\` prefix on Windows; page paths are built from it too
+    let pdf_path_arc = Arc::new(long_path::extended(Path::new(&pdf_path)));
+    let lib_path_arc = Arc::new(lib_path);
+    let temp_path_arc = Arc::new(long_path::extended(&temp_path_owned));
+
+    // Estimated memory per in-flight page, held until its PNG is written
+    let page_bytes = memory::render_page_bytes(
+        if auto_dpi {
+            AUTO_TARGET_DPI as u32
+        } else {
+            dpi
+        },
+        fs::metadata(pdf_path_arc.as_path())
+            .map(|m| m.len())
+            .unwrap_or(0),
+    );
+    let budget = memory::budget_bytes(settings.memory_budget_mb);
+    let cancel = SplitCancel::register(job_id.as_deref());
+    // Background mode renders fewer pages at once, and none while on low battery
+    let gate = power::RenderGate::new(pool.current_num_threads() as u32, cancel.flag.clone());
+
+    // Rate-limited: a long PDF would otherwise send one event per page
+    let progress = Arc::new(ThrottledEmitter::for_job_event(
+        &app,
+        "split-progress",
+        job_id.clone(),
+        PROGRESS_INTERVAL,
+    ));
+    let emit_progress = {
+        let progress = progress.clone();
+        let processed_count = processed_count.clone();
+        let job_id = job_id.clone();
+        move || {
+            progress.emit_with(|| {
+                let count = processed_count.fetch_add(1, Ordering::Relaxed) + 1;
+                SplitProgress::new(job_id.clone(), count, total_pages)
+            });
+        }
+    };
 
-    // Collect results, propagating any errors
-    let mut image_paths: Vec<String> = results.into_iter().collect::<Result<Vec<_>, _>>()?;
+    // Rayon workers don't inherit the current span, so page spans name it explicitly
+    let job_span = tracing::Span::current();
+
+    // Parallel page rendering using rayon's work-stealing scheduler, on the
+    // blocking pool: pages may wait in the gate for as long as a pause lasts
+    let render = {
+        let page_indices = page_indices.clone();
+        let app = app.clone();
+        let job_id = job_id.clone();
+        let lib_path_arc = lib_path_arc.clone();
+        let pdf_path_arc = pdf_path_arc.clone();
+        let temp_path_arc = temp_path_arc.clone();
+        let emit_progress = emit_progress.clone();
+        move || -> Vec<Result<RenderedPage, TahweelError>> {
+            pool.install(|| {
+                page_indices
+                    .par_iter()
+                    .map(|&page_num| {
+                        let page = page_num + 1;
+                        let _span =
+                            tracing::debug_span!(parent: &job_span, "render_page", page).entered();
+                        let _slot = gate.enter()?;
+                        let _memory = memory::ACCOUNTANT.reserve(page_bytes, budget, |pressure| {
+                            tracing::warn!(
+                                in_use_bytes = pressure.in_use_bytes,
+                                budget_bytes = pressure.budget_bytes,
+                                "Memory budget reached; throttling rendering"
+                            );
+                            let _ = app.emit("memory-pressure", pressure);
+                        });
+                        let started = Instant::now();
+                        let cpu = job_usage::CpuTimer::start();
+
+                        // Each thread needs its own PDFium instance (PDFium is not thread-safe)
+                        let pdfium = bind_pdfium(lib_path_arc.as_path())?;
+                        let output_path =
+                            temp_path_arc.join(format!("page-{:04}.png", page_num + 1));
+                        let (page_dpi, downscaled) = render_page_png(
+                            &pdfium,
+                            pdf_path_arc.as_path(),
+                            page_num,
+                            |page| page_render_config(page, dpi, auto_dpi),
+                            &output_path,
+                            output,
+                        )?;
+                        emit_progress();
+
+                        let elapsed_ms = started.elapsed().as_millis() as u64;
+                        tracing::debug!(elapsed_ms, "Rendered page");
+                        if let Some(job_id) = job_id.as_deref() {
+                            perf::record(job_id, page_num + 1, Stage::Render, elapsed_ms);
+                            job_usage::add_cpu(job_id, cpu.elapsed_ms());
+                            job_usage::observe_memory(job_id, memory::ACCOUNTANT.in_use());
+                        }
+
+                        Ok((
+                            long_path::path_string(&output_path)?,
+                            elapsed_ms,
+                            page_dpi,
+                            downscaled,
+                        ))
+                    })
+                    .collect()
+            })
+        }
+    };
+    let results = tauri::async_runtime::spawn_blocking(render)
+        .await
+        .map_err(|e| TahweelError::Internal(format!("Failed to render pages: {}", e)))?;
+    if cancel.is_set() {
+        let _ = fs::remove_dir_all(&temp_path_owned);
+        encryption::forget(&temp_path_str);
+        tracing::info!("Split cancelled");
+        return Err(TahweelError::Internal("Rendering cancelled".to_string()));
+    }
 
-    // Sort paths to ensure correct page order
-    image_paths.sort();
+    // Pages that failed are retried one at a time at decreasing DPI; only a page
+    // that fails at every resolution aborts the split
+    let mut image_paths = Vec::with_capacity(results.len());
+    let mut render_times_ms = Vec::with_capacity(results.len());
+    let mut page_dpis = Vec::with_capacity(results.len());
+    let mut degraded_pages = Vec::new();
+    let mut downscaled_pages = Vec::new();
+    for (page_num, result) in page_indices.iter().copied().zip(results) {
+        let (path, elapsed_ms, page_dpi, downscaled) = match result {
+            Ok(page) => page,
+            Err(error) => {
+                tracing::warn!(page = page_num + 1, %error, "Page render failed; retrying");
+                let started = Instant::now();
+                let cpu = job_usage::CpuTimer::start();
+                let output_path = temp_path_arc.join(format!("page-{:04}.png", page_num + 1));
+                let base_dpi = if auto_dpi {
+                    AUTO_TARGET_DPI as u32
+                } else {
+                    dpi
+                };
+                jobs::log_event(
+                    job_id.as_deref(),
+                    JobEventKind::Retry,
+                    Some(page_num + 1),
+                    format!("Render failed, retrying at lower DPI: {}", error.message()),
+                );
+                let (page_dpi, downscaled) = retry_render(
+                    lib_path_arc.as_path(),
+                    pdf_path_arc.as_path(),
+                    page_num,
+                    base_dpi,
+                    &output_path,
+                    output,
+                )
+                .map_err(|e| {
+                    let message = format!("Render failed at every DPI: {}", e.message());
+                    jobs::log_event(
+                        job_id.as_deref(),
+                        JobEventKind::Error,
+                        Some(page_num + 1),
+                        message,
+                    );
+                    e
+                })?;
+                emit_progress();
+                if let Some(job_id) = job_id.as_deref() {
+                    job_usage::add_cpu(job_id, cpu.elapsed_ms());
+                }
+                degraded_pages.push(DegradedPage {
+                    page: page_num + 1,
+                    dpi: page_dpi,
+                    error: error.message().to_string(),
+                });
+                let elapsed_ms = started.elapsed().as_millis() as u64;
+                (
+                    long_path::path_string(&output_path)?,
+                    elapsed_ms,
+                    page_dpi,
+                    downscaled,
+                )
+            }
+        };
+        downscaled_pages.extend(downscaled);
+        image_paths.push(path);
+        render_times_ms.push(elapsed_ms);
+        page_dpis.push(page_dpi);
+    }
+    progress.flush();
+    tracing::info!(dpi, auto_dpi, "Split PDF into page images");
 
     Ok(SplitResult {
         image_paths,
         temp_dir: temp_path_str,
+        render_times_ms,
+        page_dpis,
+        degraded_pages,
+        downscaled_pages,
     })
 }
 
-/// Extract a single page from a PDF as an image
+/// Stop the split running under `job_id`, if any; it then fails with
+/// "Rendering cancelled" and removes its page directory
 #[tauri::command]
+pub fn cancel_split(job_id: String) {
+    let cancels = SPLIT_CANCELS.lock().unwrap_or_else(|p| p.into_inner());
+    if let Some(flag) = cancels.as_ref().and_then(|c| c.get(&job_id)) {
+        flag.store(true, Ordering::Relaxed);
+        tracing::info!(job = %job_id, "Cancelling split");
+    }
+}
+
+/// 0-based indices of the `count` pages starting at 1-based `first_page`
+fn page_range(first_page: Option<u32>, count: u32) -> Result<std::ops::Range<u32>, TahweelError> {
+    let first = first_page.unwrap_or(1);
+    if first == 0 {
+        return Err(TahweelError::InvalidInput(
+            "Page numbers start at 1".to_string(),
+        ));
+    }
+    let start = first - 1;
+    let end = start
+        .checked_add(count)
+        .ok_or_else(|| TahweelError::InvalidInput("Page range is too large".to_string()))?;
+    Ok(start..end)
+}
+
+/// Extract a single page from a PDF as an image (`dpi` defaults to the DPI setting),
+/// scaled down to Drive's limits with the `driveSafeImages` setting
+#[tauri::command]
+#[tracing::instrument(skip(app), err)]
 pub async fn extract_pdf_page(
     pdf_path: String,
     page_number: u32,
-    dpi: u32,
+    dpi: Option<u32>,
     output_path: String,
     app: AppHandle,
-) -> Result<String, String> {
+) -> Result<String, TahweelError> {
+    let dpi = dpi.unwrap_or_else(|| settings::current().dpi);
     let pdfium = create_pdfium(&app)?;
 
     let document = pdfium
-        .load_pdf_from_file(&pdf_path, None)
-        .map_err(|e| format!("Failed to load PDF: {}", e))?;
+        .load_pdf_from_file(&long_path::extended(Path::new(&pdf_path)), None)
+        .map_err(|e| TahweelError::Pdf(format!("Failed to load PDF: {}", e)))?;
 
     // Get the specific page (0-indexed)
     let page = document
         .pages()
         .get((page_number - 1) as u16)
-        .map_err(|e| format!("Failed to get page {}: {}", page_number, e))?;
+        .map_err(|e| TahweelError::Pdf(format!("Failed to get page {}: {}", page_number, e)))?;
 
     // Configure rendering
     let render_config = PdfRenderConfig::new()
@@ -231,7 +841,7 @@ pub async fn extract_pdf_page(
     // Render page to image
     let image = page
         .render_with_config(&render_config)
-        .map_err(|e| format!("Failed to render page {}: {}", page_number, e))?
+        .map_err(|e| TahweelError::Pdf(format!("Failed to render page {}: {}", page_number, e)))?
         .as_image();
 
     // Save as PNG (lossless, better for OCR quality)
@@ -241,28 +851,155 @@ pub async fn extract_pdf_page(
         format!("{}.png", output_path)
     };
 
-    image
-        .into_rgb8()
-        .save_with_format(&final_path, ImageFormat::Png)
-        .map_err(|e| format!("Failed to save page as PNG: {}", e))?;
+    // Pages rendered into an encrypted workspace are encrypted with its key
+    let key = Path::new(&final_path)
+        .parent()
+        .and_then(|dir| encryption::existing_key(&dir.to_string_lossy()));
+    let settings = settings::current();
+    let output = PageOutput::new(&settings, key);
+    save_page(
+        image,
+        page_number,
+        &long_path::extended(Path::new(&final_path)),
+        output,
+    )
+    .map_err(|e| TahweelError::Io(format!("Failed to save page as PNG: {}", e.message())))?;
 
     Ok(final_path)
 }
 
-/// Clean up a temporary directory
+/// Render every page of a PDF to `page-NNNN.png` in `dir`, one page at a time
+/// (for exports, where speed matters less than memory)
+pub(crate) fn render_pages(
+    app: &AppHandle,
+    pdf_path: &Path,
+    dpi: u32,
+    dir: &Path,
+) -> Result<Vec<PathBuf>, TahweelError> {
+    let pdfium = create_pdfium(app)?;
+    let document = pdfium
+        .load_pdf_from_file(&long_path::extended(pdf_path), None)
+        .map_err(|e| TahweelError::Pdf(format!("Failed to load PDF: {}", e)))?;
+    let render_config = PdfRenderConfig::new()
+        .set_target_width((dpi as i32) * PAGE_WIDTH_INCHES)
+        .set_maximum_height((dpi as i32) * PAGE_HEIGHT_INCHES)
+        .rotate_if_landscape(PdfPageRenderRotation::None, false);
+
+    let color_mode = settings::current().color_mode;
+    let mut paths = Vec::new();
+    for (index, page) in document.pages().iter().enumerate() {
+        let image = page
+            .render_with_config(&render_config)
+            .map_err(|e| TahweelError::Pdf(format!("Failed to render page {}: {}", index + 1, e)))?
+            .as_image();
+        let path = dir.join(format!("page-{:04}.png", index + 1));
+        apply_color_mode(image, color_mode)
+            .save_with_format(long_path::extended(&path), ImageFormat::Png)
+            .map_err(|e| {
+                TahweelError::Io(format!("Failed to save page {} as PNG: {}", index + 1, e))
+            })?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Where `cleanup_temp_dir` moves a job's rendered pages instead of deleting them
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeepImages {
+    /// The job's output base path (`<dir>/<name>`, as given to the writers)
+    #[serde(rename = "outputPath")]
+    pub output_path: String,
+    /// Subfolder of the output directory; pages go to `<dir>/<folder>/<name>/`
+    pub folder: String,
+}
+
+/// A page rendered by `split_pdf` (`page-NNNN.png`), not a crop derived from one
+fn is_page_image(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    name.strip_prefix("page-")
+        .and_then(|rest| rest.strip_suffix(".png"))
+        .is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Move the rendered pages in `dir` to the folder `keep` names, decrypting them
+/// when the workspace is encrypted; returns the folder
+fn keep_page_images(dir: &Path, keep: &KeepImages) -> Result<PathBuf, TahweelError> {
+    let mut folder = Path::new(&keep.folder).components();
+    if !matches!(
+        (folder.next(), folder.next()),
+        (Some(std::path::Component::Normal(_)), None)
+    ) {
+        return Err(TahweelError::InvalidInput(format!(
+            "Invalid page images folder: {}",
+            keep.folder
+        )));
+    }
+    let base = path_policy::check(Path::new(&keep.output_path), PathAccess::Write)?;
+    let (Some(parent), Some(name)) = (base.parent(), base.file_name()) else {
+        return Err(TahweelError::InvalidInput(format!(
+            "Invalid output path: {}",
+            keep.output_path
+        )));
+    };
+    let target_dir = parent.join(&keep.folder).join(name);
+    fs::create_dir_all(&target_dir)?;
+
+    let mut pages: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_page_image(path))
+        .collect();
+    pages.sort();
+    for page in pages {
+        let target = target_dir.join(page.file_name().unwrap_or_default());
+        let data = fs::read(&page)?;
+        if encryption::is_encrypted(&data) || fs::rename(&page, &target).is_err() {
+            // Encrypted pages are kept as plain PNGs; a rename fails across volumes
+            fs::write(&target, encryption::decrypt(data)?.0)?;
+        }
+    }
+    Ok(target_dir)
+}
+
+/// Clean up a temporary directory (only inside the app's temp workspace),
+/// discarding its encryption key. With `keep_images` (the `keepPageImages`
+/// option), the rendered pages are moved to the output folder first; if that
+/// fails the directory is left in place so no page is lost.
+/// The directory is moved to the workspace trash, where `undo_cleanup(job_id)`
+/// can restore it for a while; encrypted directories are deleted outright, as
+/// nothing in them can be read once their key is gone.
 #[tauri::command]
-pub async fn cleanup_temp_dir(path: String) -> Result<(), String> {
+pub async fn cleanup_temp_dir(
+    path: String,
+    keep_images: Option<KeepImages>,
+    job_id: Option<String>,
+) -> Result<(), TahweelError> {
+    if let Some(keep) = keep_images.filter(|_| Path::new(&path).is_dir()) {
+        let dir = path_policy::check(Path::new(&path), PathAccess::Delete)?;
+        keep_page_images(&dir, &keep)?;
+    }
+    let encrypted = encryption::existing_key(&path).is_some();
+    encryption::forget(&path);
     let path = std::path::Path::new(&path);
     if path.exists() && path.is_dir() {
-        fs::remove_dir_all(path).map_err(|e| format!("Failed to remove temp directory: {}", e))?;
+        let path = path_policy::check(path, PathAccess::Delete)?;
+        if encrypted {
+            fs::remove_dir_all(path)
+                .map_err(|e| TahweelError::Io(format!("Failed to remove temp directory: {}", e)))?;
+        } else {
+            trash::soft_delete(&path, job_id.as_deref())?;
+        }
     }
     Ok(())
 }
 
-/// Write binary data to a file (used for DOCX output)
+/// Write binary data to a file (used for DOCX output); the path must pass the path policy
 #[tauri::command]
-pub async fn write_binary_file(path: String, data: Vec<u8>) -> Result<(), String> {
-    fs::write(&path, &data).map_err(|e| format!("Failed to write file: {}", e))
+pub async fn write_binary_file(path: String, data: Vec<u8>) -> Result<(), TahweelError> {
+    let path = path_policy::check(std::path::Path::new(&path), PathAccess::Write)?;
+    fs::write(&path, &data).map_err(|e| TahweelError::Io(format!("Failed to write file: {}", e)))
 }
 
 #[cfg(test)]
@@ -280,6 +1017,10 @@ mod tests {
                 "/tmp/page-0002.png".to_string(),
             ],
             temp_dir: "/tmp/tahweel-123".to_string(),
+            render_times_ms: vec![],
+            page_dpis: vec![],
+            degraded_pages: vec![],
+            downscaled_pages: vec![],
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -288,15 +1029,25 @@ mod tests {
         assert!(json.contains("page-0001.png"));
     }
 
+    #[test]
+    fn test_page_range() {
+        assert_eq!(page_range(None, 3).unwrap(), 0..3);
+        assert_eq!(page_range(Some(501), 500).unwrap(), 500..1000);
+        assert!(page_range(Some(0), 1).is_err());
+        assert!(page_range(Some(u32::MAX), 2).is_err());
+    }
+
     #[test]
     fn test_split_progress_serialization() {
         let progress = SplitProgress {
+            job_id: Some("job-1".to_string()),
             current_page: 5,
             total_pages: 10,
             percentage: 50.0,
         };
 
         let json = serde_json::to_string(&progress).unwrap();
+        assert!(json.contains("\"jobId\":\"job-1\""));
         assert!(json.contains("currentPage"));
         assert!(json.contains("totalPages"));
         assert!(json.contains("percentage"));
@@ -305,6 +1056,120 @@ mod tests {
         assert!(json.contains("50"));
     }
 
+    #[test]
+    fn test_auto_page_dpi_targets_300() {
+        // US Letter (8.5 x 11 in) fits within the pixel cap
+        assert_eq!(auto_page_dpi(612.0, 792.0, None), 300);
+        // A small page (A5) still renders at the target, not larger
+        assert_eq!(auto_page_dpi(420.0, 595.0, None), 300);
+    }
+
+    #[test]
+    fn test_auto_page_dpi_follows_scan_resolution() {
+        assert_eq!(auto_page_dpi(612.0, 792.0, Some(600.0)), 300);
+        assert_eq!(auto_page_dpi(612.0, 792.0, Some(200.0)), 200);
+        // Low-resolution scans are still rendered at a readable minimum
+        assert_eq!(auto_page_dpi(612.0, 792.0, Some(96.0)), 150);
+        assert_eq!(auto_page_dpi(612.0, 792.0, Some(f32::NAN)), 300);
+        assert_eq!(auto_page_dpi(612.0, 792.0, Some(0.0)), 300);
+    }
+
+    #[test]
+    fn test_auto_page_dpi_caps_large_pages() {
+        // A3 (11.7 x 16.5 in) at 300 DPI would exceed the pixel cap
+        let dpi = auto_page_dpi(842.0, 1191.0, None);
+        assert_eq!(dpi, 211);
+        let pixels = (842.0 / 72.0 * dpi as f32) * (1191.0 / 72.0 * dpi as f32);
+        assert!(pixels <= AUTO_MAX_PIXELS);
+        // Huge sheets never drop below 72 DPI
+        assert_eq!(auto_page_dpi(7200.0, 7200.0, None), 72);
+        // Degenerate sizes fall back to the target
+        assert_eq!(auto_page_dpi(0.0, 0.0, None), 300);
+    }
+
+    /// A white page with a dark text block and, optionally, a red stamp
+    fn scanned_page(stamp: bool) -> DynamicImage {
+        let mut image = RgbImage::from_pixel(100, 100, image::Rgb([245, 240, 230]));
+        for y in 10..30 {
+            for x in 10..90 {
+                image.put_pixel(x, y, image::Rgb([30, 30, 35]));
+            }
+        }
+        if stamp {
+            for y in 60..80 {
+                for x in 60..80 {
+                    image.put_pixel(x, y, image::Rgb([200, 20, 20]));
+                }
+            }
+        }
+        DynamicImage::ImageRgb8(image)
+    }
+
+    #[test]
+    fn test_color_mode_auto_detects_color_content() {
+        assert!(matches!(
+            apply_color_mode(scanned_page(false), ColorMode::Auto),
+            DynamicImage::ImageLuma8(_)
+        ));
+        assert!(matches!(
+            apply_color_mode(scanned_page(true), ColorMode::Auto),
+            DynamicImage::ImageRgb8(_)
+        ));
+    }
+
+    #[test]
+    fn test_color_mode_forced() {
+        assert!(matches!(
+            apply_color_mode(scanned_page(false), ColorMode::Color),
+            DynamicImage::ImageRgb8(_)
+        ));
+        assert!(matches!(
+            apply_color_mode(scanned_page(true), ColorMode::Grayscale),
+            DynamicImage::ImageLuma8(_)
+        ));
+    }
+
+    #[test]
+    fn test_color_mode_bitonal_separates_ink_from_paper() {
+        let DynamicImage::ImageLuma8(page) =
+            apply_color_mode(scanned_page(true), ColorMode::Bitonal)
+        else {
+            panic!("bitonal pages are grayscale");
+        };
+        assert!(page.pixels().all(|p| p.0[0] == 0 || p.0[0] == 255));
+        assert_eq!(page.get_pixel(20, 20).0[0], 0);
+        assert_eq!(page.get_pixel(50, 50).0[0], 255);
+
+        let blank = GrayImage::from_pixel(10, 10, image::Luma([250]));
+        let DynamicImage::ImageLuma8(blank) =
+            apply_color_mode(DynamicImage::ImageLuma8(blank), ColorMode::Bitonal)
+        else {
+            panic!("bitonal pages are grayscale");
+        };
+        assert!(blank.pixels().all(|p| p.0[0] == 255));
+    }
+
+    #[test]
+    fn test_retry_dpis_decrease() {
+        assert_eq!(retry_dpis(300), vec![300, 200, 150]);
+        assert_eq!(retry_dpis(150), vec![150, 100, 75]);
+        // Never below 72, without repeating a resolution
+        assert_eq!(retry_dpis(100), vec![100, 72]);
+        assert_eq!(retry_dpis(72), vec![72]);
+    }
+
+    #[test]
+    fn test_degraded_page_serialization() {
+        let page = DegradedPage {
+            page: 7,
+            dpi: 200,
+            error: "Failed to render page 7".to_string(),
+        };
+        let json = serde_json::to_value(&page).unwrap();
+        assert_eq!(json["page"], 7);
+        assert_eq!(json["dpi"], 200);
+    }
+
     #[test]
     fn test_render_config_dimensions() {
         // Test DPI calculation for different values
@@ -393,10 +1258,15 @@ mod tests {
 
     #[tokio::test]
     async fn test_write_binary_file_invalid_path() {
+        let temp = tempdir().unwrap();
+        let file_path = temp.path().join("missing").join("file.bin");
         let result =
-            write_binary_file("/nonexistent/path/file.bin".to_string(), vec![1, 2, 3]).await;
+            write_binary_file(file_path.to_string_lossy().to_string(), vec![1, 2, 3]).await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Failed to write file"));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Failed to write file"));
     }
 
     #[test]
@@ -469,14 +1339,119 @@ mod tests {
         assert!(file2.exists());
 
         // Clean up
-        let result = cleanup_temp_dir(temp_path.to_string_lossy().to_string()).await;
+        let result = cleanup_temp_dir(temp_path.to_string_lossy().to_string(), None, None).await;
         assert!(result.is_ok());
         assert!(!temp_path.exists());
     }
 
+    #[tokio::test]
+    async fn test_cleanup_temp_dir_can_be_undone() {
+        let temp_path =
+            std::env::temp_dir().join(format!("tahweel_test_undo_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&temp_path).unwrap();
+        fs::write(temp_path.join("page-0001.png"), b"png").unwrap();
+        let job_id = uuid::Uuid::new_v4().to_string();
+
+        cleanup_temp_dir(
+            temp_path.to_string_lossy().to_string(),
+            None,
+            Some(job_id.clone()),
+        )
+        .await
+        .unwrap();
+        assert!(!temp_path.exists());
+
+        let restored = trash::undo_cleanup(job_id).await.unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(fs::read(temp_path.join("page-0001.png")).unwrap(), b"png");
+        fs::remove_dir_all(&temp_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_temp_dir_deletes_encrypted_dir() {
+        let temp_path =
+            std::env::temp_dir().join(format!("tahweel_test_enc_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&temp_path).unwrap();
+        let scope = temp_path.to_string_lossy().to_string();
+        encryption::scope_key(&scope).unwrap();
+        let job_id = uuid::Uuid::new_v4().to_string();
+
+        cleanup_temp_dir(scope, None, Some(job_id.clone()))
+            .await
+            .unwrap();
+        assert!(!temp_path.exists());
+        assert!(trash::undo_cleanup(job_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_temp_dir_keeps_page_images() {
+        let pages_dir = tempdir().unwrap();
+        let output = tempdir().unwrap();
+        let key = encryption::scope_key("test-keep-pages").unwrap();
+        fs::write(pages_dir.path().join("page-0001.png"), b"png 1").unwrap();
+        encryption::write_file(&pages_dir.path().join("page-0002.png"), b"png 2", Some(key))
+            .unwrap();
+        fs::write(pages_dir.path().join("page-0001-body.png"), b"crop").unwrap();
+        fs::write(pages_dir.path().join("second_pass_2.png"), b"second").unwrap();
+
+        let keep = KeepImages {
+            output_path: output.path().join("book").to_string_lossy().to_string(),
+            folder: "pages".to_string(),
+        };
+        let dir = pages_dir.path().to_string_lossy().to_string();
+        cleanup_temp_dir(dir, Some(keep), None).await.unwrap();
+
+        let kept = output.path().join("pages").join("book");
+        assert_eq!(fs::read(kept.join("page-0001.png")).unwrap(), b"png 1");
+        assert_eq!(fs::read(kept.join("page-0002.png")).unwrap(), b"png 2");
+        assert_eq!(fs::read_dir(&kept).unwrap().count(), 2);
+        assert!(!pages_dir.path().exists());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_temp_dir_keeps_workspace_when_pages_cannot_move() {
+        let pages_dir = tempdir().unwrap();
+        fs::write(pages_dir.path().join("page-0001.png"), b"png 1").unwrap();
+
+        let keep = KeepImages {
+            output_path: pages_dir.path().join("book").to_string_lossy().to_string(),
+            folder: "../escape".to_string(),
+        };
+        let dir = pages_dir.path().to_string_lossy().to_string();
+        let err = cleanup_temp_dir(dir, Some(keep), None).await.unwrap_err();
+
+        assert_eq!(err.kind(), "invalidInput");
+        assert!(pages_dir.path().join("page-0001.png").exists());
+    }
+
+    #[test]
+    fn test_is_page_image() {
+        assert!(is_page_image(Path::new("/tmp/x/page-0001.png")));
+        assert!(is_page_image(Path::new("page-10000.png")));
+        assert!(!is_page_image(Path::new("page-0001-body.png")));
+        assert!(!is_page_image(Path::new("page-.png")));
+        assert!(!is_page_image(Path::new("second_pass_3.png")));
+    }
+
+    #[tokio::test]
+    async fn test_write_binary_file_outside_allowed_paths() {
+        let result =
+            write_binary_file("/nonexistent/path/file.bin".to_string(), vec![1, 2, 3]).await;
+        assert_eq!(result.unwrap_err().kind(), "pathNotAllowed");
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_temp_dir_rejects_outside_workspace() {
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        let result = cleanup_temp_dir(home.to_string_lossy().to_string(), None, None).await;
+        assert_eq!(result.unwrap_err().kind(), "pathNotAllowed");
+        assert!(home.exists());
+    }
+
     #[tokio::test]
     async fn test_cleanup_temp_dir_nonexistent_path() {
-        let result = cleanup_temp_dir("/nonexistent/path/tahweel_test".to_string()).await;
+        let result =
+            cleanup_temp_dir("/nonexistent/path/tahweel_test".to_string(), None, None).await;
         // Should succeed - no error for nonexistent paths
         assert!(result.is_ok());
     }
@@ -496,7 +1471,7 @@ mod tests {
 
         assert!(nested.exists());
 
-        let result = cleanup_temp_dir(temp_path.to_string_lossy().to_string()).await;
+        let result = cleanup_temp_dir(temp_path.to_string_lossy().to_string(), None, None).await;
         assert!(result.is_ok());
         assert!(!temp_path.exists());
     }
@@ -576,6 +1551,10 @@ mod tests {
         let result = SplitResult {
             image_paths: vec![],
             temp_dir: "/tmp/empty".to_string(),
+            render_times_ms: vec![],
+            page_dpis: vec![],
+            degraded_pages: vec![],
+            downscaled_pages: vec![],
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -591,6 +1570,10 @@ mod tests {
         let result = SplitResult {
             image_paths: paths.clone(),
             temp_dir: "/tmp/many".to_string(),
+            render_times_ms: vec![],
+            page_dpis: vec![],
+            degraded_pages: vec![],
+            downscaled_pages: vec![],
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -601,6 +1584,7 @@ mod tests {
     #[test]
     fn test_split_progress_at_start() {
         let progress = SplitProgress {
+            job_id: None,
             current_page: 0,
             total_pages: 50,
             percentage: 0.0,
@@ -617,6 +1601,7 @@ mod tests {
     #[test]
     fn test_split_progress_at_end() {
         let progress = SplitProgress {
+            job_id: None,
             current_page: 100,
             total_pages: 100,
             percentage: 100.0,
@@ -700,6 +1685,10 @@ mod tests {
         let result = SplitResult {
             image_paths: vec!["/tmp/مستند/page-0001.png".to_string()],
             temp_dir: "/tmp/مستند".to_string(),
+            render_times_ms: vec![],
+            page_dpis: vec![],
+            degraded_pages: vec![],
+            downscaled_pages: vec![],
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -711,6 +1700,10 @@ mod tests {
         let result = SplitResult {
             image_paths: vec!["/tmp/my documents/page-0001.png".to_string()],
             temp_dir: "/tmp/my documents".to_string(),
+            render_times_ms: vec![],
+            page_dpis: vec![],
+            degraded_pages: vec![],
+            downscaled_pages: vec![],
         };
 
         let json = serde_json::to_string(&result).unwrap();
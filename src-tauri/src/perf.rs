@@ -0,0 +1,301 @@
+use crate::error::TahweelError;
+use crate::job_channels;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
+
+/// Jobs whose timings are kept; older jobs are forgotten first
+const MAX_JOBS: usize = 20;
+
+/// Pipeline stage a timing belongs to
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    Render,
+    Upload,
+    Export,
+}
+
+impl Stage {
+    const ALL: [Stage; 3] = [Stage::Render, Stage::Upload, Stage::Export];
+}
+
+/// Payload of the `stage-timing` event
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StageTiming {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    pub page: u32,
+    pub stage: Stage,
+    pub ms: u64,
+}
+
+/// Timings of one page; a stage is `None` until it has run
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct PageTimings {
+    pub page: u32,
+    #[serde(rename = "renderMs")]
+    pub render_ms: Option<u64>,
+    #[serde(rename = "uploadMs")]
+    pub upload_ms: Option<u64>,
+    #[serde(rename = "exportMs")]
+    pub export_ms: Option<u64>,
+}
+
+impl PageTimings {
+    fn get(&self, stage: Stage) -> Option<u64> {
+        match stage {
+            Stage::Render => self.render_ms,
+            Stage::Upload => self.upload_ms,
+            Stage::Export => self.export_ms,
+        }
+    }
+
+    fn set(&mut self, stage: Stage, ms: u64) {
+        let slot = match stage {
+            Stage::Render => &mut self.render_ms,
+            Stage::Upload => &mut self.upload_ms,
+            Stage::Export => &mut self.export_ms,
+        };
+        *slot = Some(ms);
+    }
+}
+
+/// Totals for one stage across a job's pages
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StageSummary {
+    pub stage: Stage,
+    pub count: u32,
+    #[serde(rename = "totalMs")]
+    pub total_ms: u64,
+    #[serde(rename = "averageMs")]
+    pub average_ms: u64,
+    #[serde(rename = "maxMs")]
+    pub max_ms: u64,
+    #[serde(rename = "slowestPage")]
+    pub slowest_page: Option<u32>,
+}
+
+/// Where a job spent its time, stage by stage and page by page
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PerformanceSummary {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    pub stages: Vec<StageSummary>,
+    /// Stage with the largest total time
+    #[serde(rename = "slowestStage")]
+    pub slowest_stage: Option<Stage>,
+    pub pages: Vec<PageTimings>,
+}
+
+/// Per-job page timings, oldest job first
+#[derive(Debug, Default)]
+struct Timings {
+    order: VecDeque<String>,
+    jobs: BTreeMap<String, BTreeMap<u32, PageTimings>>,
+}
+
+impl Timings {
+    fn record(&mut self, job_id: &str, page: u32, stage: Stage, ms: u64) {
+        if !self.jobs.contains_key(job_id) {
+            if self.order.len() >= MAX_JOBS {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.jobs.remove(&oldest);
+                }
+            }
+            self.order.push_back(job_id.to_string());
+        }
+        self.jobs
+            .entry(job_id.to_string())
+            .or_default()
+            .entry(page)
+            .or_insert_with(|| PageTimings {
+                page,
+                ..Default::default()
+            })
+            .set(stage, ms);
+    }
+}
+
+static TIMINGS: Mutex<Option<Timings>> = Mutex::new(None);
+/// Set in `setup`; timings are still recorded (but not emitted) without it
+static EMITTER: OnceLock<AppHandle> = OnceLock::new();
+
+/// Emit `stage-timing` events through `app`
+pub fn init(app: AppHandle) {
+    let _ = EMITTER.set(app);
+}
+
+/// Record how long `stage` took for `page` of `job_id` and emit `stage-timing`,
+/// globally and to the job's subscribers
+pub fn record(job_id: &str, page: u32, stage: Stage, ms: u64) {
+    if let Ok(mut timings) = TIMINGS.lock() {
+        timings
+            .get_or_insert_with(Timings::default)
+            .record(job_id, page, stage, ms);
+    }
+    let timing = StageTiming {
+        job_id: job_id.to_string(),
+        page,
+        stage,
+        ms,
+    };
+    job_channels::send(job_id, "stage-timing", &timing);
+    if let Some(app) = EMITTER.get() {
+        let _ = app.emit("stage-timing", timing);
+    }
+}
+
+/// Summarize page timings for a job
+pub fn summarize(job_id: &str, pages: Vec<PageTimings>) -> PerformanceSummary {
+    let stages: Vec<StageSummary> = Stage::ALL
+        .iter()
+        .map(|&stage| {
+            let times: Vec<(u32, u64)> = pages
+                .iter()
+                .filter_map(|p| p.get(stage).map(|ms| (p.page, ms)))
+                .collect();
+            let total_ms: u64 = times.iter().map(|(_, ms)| ms).sum();
+            let slowest = times.iter().max_by_key(|(_, ms)| *ms);
+            StageSummary {
+                stage,
+                count: times.len() as u32,
+                total_ms,
+                average_ms: if times.is_empty() {
+                    0
+                } else {
+                    total_ms / times.len() as u64
+                },
+                max_ms: slowest.map(|(_, ms)| *ms).unwrap_or(0),
+                slowest_page: slowest.map(|(page, _)| *page),
+            }
+        })
+        .collect();
+    let slowest_stage = stages
+        .iter()
+        .filter(|s| s.count > 0)
+        .max_by_key(|s| s.total_ms)
+        .map(|s| s.stage);
+    PerformanceSummary {
+        job_id: job_id.to_string(),
+        stages,
+        slowest_stage,
+        pages,
+    }
+}
+
+/// Per-stage timing summary of a recent job
+#[tauri::command]
+pub async fn get_performance_summary(job_id: String) -> Result<PerformanceSummary, TahweelError> {
+    let pages = TIMINGS
+        .lock()
+        .map_err(|_| TahweelError::Internal("Timings lock poisoned".to_string()))?
+        .as_ref()
+        .and_then(|t| t.jobs.get(&job_id))
+        .map(|pages| pages.values().cloned().collect::<Vec<_>>())
+        .ok_or_else(|| TahweelError::NotFound(format!("No timings for job {}", job_id)))?;
+    Ok(summarize(&job_id, pages))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(page: u32, render: u64, upload: u64, export: Option<u64>) -> PageTimings {
+        PageTimings {
+            page,
+            render_ms: Some(render),
+            upload_ms: Some(upload),
+            export_ms: export,
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(
+            "job",
+            vec![page(1, 100, 900, Some(300)), page(2, 300, 1100, None)],
+        );
+        let render = &summary.stages[0];
+        assert_eq!(render.stage, Stage::Render);
+        assert_eq!(render.count, 2);
+        assert_eq!(render.total_ms, 400);
+        assert_eq!(render.average_ms, 200);
+        assert_eq!(render.max_ms, 300);
+        assert_eq!(render.slowest_page, Some(2));
+
+        let export = &summary.stages[2];
+        assert_eq!(export.count, 1);
+        assert_eq!(export.average_ms, 300);
+        assert_eq!(summary.slowest_stage, Some(Stage::Upload));
+    }
+
+    #[test]
+    fn test_summarize_empty() {
+        let summary = summarize("job", vec![]);
+        assert!(summary
+            .stages
+            .iter()
+            .all(|s| s.count == 0 && s.average_ms == 0));
+        assert!(summary.slowest_stage.is_none());
+    }
+
+    #[test]
+    fn test_timings_record_merges_stages_per_page() {
+        let mut timings = Timings::default();
+        timings.record("job", 2, Stage::Render, 10);
+        timings.record("job", 2, Stage::Upload, 20);
+        timings.record("job", 1, Stage::Render, 5);
+
+        let pages: Vec<_> = timings.jobs["job"].values().cloned().collect();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].page, 1);
+        assert_eq!(pages[1].render_ms, Some(10));
+        assert_eq!(pages[1].upload_ms, Some(20));
+        assert_eq!(pages[1].export_ms, None);
+    }
+
+    #[test]
+    fn test_timings_forget_oldest_jobs() {
+        let mut timings = Timings::default();
+        for i in 0..(MAX_JOBS + 2) {
+            timings.record(&format!("job-{}", i), 1, Stage::Render, 1);
+        }
+        assert_eq!(timings.jobs.len(), MAX_JOBS);
+        assert!(!timings.jobs.contains_key("job-0"));
+        assert!(!timings.jobs.contains_key("job-1"));
+        assert!(timings.jobs.contains_key(&format!("job-{}", MAX_JOBS + 1)));
+    }
+
+    #[tokio::test]
+    async fn test_get_performance_summary() {
+        record("perf-test-job", 1, Stage::Upload, 42);
+        let summary = get_performance_summary("perf-test-job".to_string())
+            .await
+            .unwrap();
+        assert_eq!(summary.stages[1].total_ms, 42);
+
+        let err = get_performance_summary("perf-missing-job".to_string())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), "notFound");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let json = serde_json::to_value(StageTiming {
+            job_id: "j".to_string(),
+            page: 3,
+            stage: Stage::Export,
+            ms: 12,
+        })
+        .unwrap();
+        assert_eq!(json["jobId"], "j");
+        assert_eq!(json["stage"], "export");
+
+        let json = serde_json::to_value(page(1, 1, 2, None)).unwrap();
+        assert_eq!(json["renderMs"], 1);
+        assert!(json["exportMs"].is_null());
+    }
+}
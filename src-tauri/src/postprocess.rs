@@ -0,0 +1,880 @@
+use crate::error::TahweelError;
+use crate::quran::{self, QuranScript, QuranSubstitution};
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Characters that end a sentence in Arabic or Latin text
+const SENTENCE_TERMINATORS: &[char] = &['.', '!', '?', '؟', ':', '…', '۔', '»', '"', ')'];
+/// A line shorter than this fraction of the page's typical line length ends a paragraph
+const SHORT_LINE_RATIO: f32 = 0.6;
+/// Number of non-empty lines at the top/bottom of a page searched for headers/footers
+const EDGE_LINES: usize = 2;
+/// Minimum fraction of pages a line must repeat on to be treated as a running header/footer
+const REPEAT_RATIO: f32 = 0.4;
+/// Minimum number of pages a line must repeat on (avoids false positives in short documents)
+const MIN_REPEAT_PAGES: usize = 3;
+/// Most steps a configured pipeline keeps
+pub const MAX_PIPELINE_STEPS: usize = 32;
+
+/// How hard line breaks returned by OCR are treated
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LineBreakMode {
+    /// Keep the original line breaks (escape hatch for poetry, tables, lists)
+    #[default]
+    Keep,
+    /// Merge lines into paragraphs using punctuation and line-length heuristics
+    Reflow,
+}
+
+/// Post-processing options applied to each page's text before output
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PostprocessOptions {
+    #[serde(rename = "lineBreaks", default)]
+    pub line_breaks: LineBreakMode,
+    #[serde(rename = "removeHeadersFooters", default)]
+    pub remove_headers_footers: bool,
+    /// Replace detected Quranic verses with their canonical text in this script
+    #[serde(rename = "quranVerses", default)]
+    pub quran_verses: Option<QuranScript>,
+}
+
+/// Why a line was stripped from a page
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StripReason {
+    Header,
+    Footer,
+    PageNumber,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StrippedLine {
+    pub page: u32,
+    pub text: String,
+    pub reason: StripReason,
+}
+
+/// Preview of what header/footer removal stripped, for display to the user
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StripReport {
+    /// Distinct repeated header/footer lines that were detected (normalized form)
+    #[serde(rename = "repeatedLines")]
+    pub repeated_lines: Vec<String>,
+    pub stripped: Vec<StrippedLine>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostprocessResult {
+    pub pages: Vec<String>,
+    #[serde(rename = "stripReport")]
+    pub strip_report: Option<StripReport>,
+    /// Verse substitutions, when `quranVerses` is set
+    #[serde(rename = "quranSubstitutions")]
+    pub quran_substitutions: Option<Vec<QuranSubstitution>>,
+}
+
+/// One named step of a post-processing pipeline, with its parameters.
+/// A pipeline is an ordered list of steps; each one sees the text the previous
+/// one produced.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(
+    tag = "step",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum PostprocessStep {
+    /// Unify line endings, collapse runs of spaces, drop trailing spaces and
+    /// keep at most one blank line between paragraphs
+    NormalizeWhitespace,
+    /// Remove Arabic diacritics (harakat) and tatweel
+    StripDiacritics {
+        /// Keep the shadda, which changes a word's reading
+        #[serde(default)]
+        keep_shadda: bool,
+    },
+    /// Unify alef forms (أ إ آ ٱ) to ا and alef maqsura (ى) to ي
+    UnifyLetters,
+    /// Remove running headers/footers and page numbers (needs the whole document)
+    RemoveHeadersFooters,
+    /// Merge hard-wrapped lines into paragraphs
+    ReflowParagraphs,
+    /// Replace detected Quranic verses with their canonical text
+    QuranVerses { script: QuranScript },
+    /// Replace every occurrence of `find` with `replace`
+    Replace { find: String, replace: String },
+}
+
+impl PostprocessStep {
+    /// Steps that compare pages run only when the whole document is at hand
+    pub fn is_document_level(&self) -> bool {
+        matches!(self, PostprocessStep::RemoveHeadersFooters)
+    }
+
+    /// Name of the step as written in settings
+    pub fn name(&self) -> &'static str {
+        match self {
+            PostprocessStep::NormalizeWhitespace => "normalizeWhitespace",
+            PostprocessStep::StripDiacritics { .. } => "stripDiacritics",
+            PostprocessStep::UnifyLetters => "unifyLetters",
+            PostprocessStep::RemoveHeadersFooters => "removeHeadersFooters",
+            PostprocessStep::ReflowParagraphs => "reflowParagraphs",
+            PostprocessStep::QuranVerses { .. } => "quranVerses",
+            PostprocessStep::Replace { .. } => "replace",
+        }
+    }
+}
+
+/// A pipeline step applied to a sample page
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PreviewStage {
+    pub step: String,
+    /// Text after this step
+    pub text: String,
+    /// Document-level step, which a single page cannot show
+    pub skipped: bool,
+}
+
+/// Result of `preview_postprocessing`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PostprocessPreview {
+    pub text: String,
+    pub stages: Vec<PreviewStage>,
+}
+
+/// Typical line length of a page: the 75th percentile of non-empty line lengths,
+/// which ignores short headings and last lines of paragraphs.
+fn typical_line_length(lines: &[&str]) -> usize {
+    let mut lengths: Vec<usize> = lines
+        .iter()
+        .map(|l| l.trim().chars().count())
+        .filter(|&len| len > 0)
+        .collect();
+    if lengths.is_empty() {
+        return 0;
+    }
+    lengths.sort_unstable();
+    lengths[(lengths.len() * 3 / 4).min(lengths.len() - 1)]
+}
+
+/// Whether a line looks like the start of a list item (e.g. "1.", "٢)", "-", "•")
+fn starts_list_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with(['-', '•', '*', '–']) {
+        return true;
+    }
+    let digits: String = trimmed
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || ('\u{0660}'..='\u{0669}').contains(c))
+        .collect();
+    !digits.is_empty() && trimmed[digits.len()..].starts_with(['.', ')', '-', '،'])
+}
+
+/// Merge hard-wrapped lines into paragraphs.
+///
+/// A line is joined with the next one unless it ends with sentence punctuation,
+/// is noticeably shorter than the page's typical line, or the next line is blank
+/// or starts a list item. Latin words hyphenated across lines are rejoined.
+pub fn reflow_paragraphs(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let typical = typical_line_length(&lines);
+    let short_threshold = (typical as f32 * SHORT_LINE_RATIO) as usize;
+
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for (i, raw) in lines.iter().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if current.is_empty() {
+            current.push_str(line);
+        } else if current.ends_with('-')
+            && current
+                .chars()
+                .rev()
+                .nth(1)
+                .is_some_and(|c| c.is_ascii_alphabetic())
+        {
+            current.pop();
+            current.push_str(line);
+        } else {
+            current.push(' ');
+            current.push_str(line);
+        }
+
+        let next_starts_item = lines.get(i + 1).is_some_and(|next| starts_list_item(next));
+        let ends_sentence = line.ends_with(SENTENCE_TERMINATORS);
+        let is_short = line.chars().count() < short_threshold;
+
+        if ends_sentence || is_short || next_starts_item {
+            paragraphs.push(std::mem::take(&mut current));
+        }
+    }
+
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+
+    paragraphs.join("\n\n")
+}
+
+/// Whether a line consists only of a page number, optionally decorated
+/// (e.g. "12", "- ١٢ -", "(12)", "[ 7 ]")
+fn is_page_number(line: &str) -> bool {
+    let trimmed = line.trim();
+    let core = trimmed.trim_matches(|c: char| c.is_whitespace() || "-–—()[]{}|.«»".contains(c));
+    !core.is_empty()
+        && core.chars().count() <= 5
+        && core
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('\u{0660}'..='\u{0669}').contains(&c))
+}
+
+/// Normalize a line for repetition detection: digits are replaced so running
+/// headers that include the page number still match across pages.
+fn normalize_edge_line(line: &str) -> String {
+    line.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_digit() || ('\u{0660}'..='\u{0669}').contains(&c) {
+                '#'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Indices of the first and last few non-empty lines of a page
+fn edge_line_indices(lines: &[&str]) -> (Vec<usize>, Vec<usize>) {
+    let non_empty: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, _)| i)
+        .collect();
+    let top: Vec<usize> = non_empty.iter().take(EDGE_LINES).copied().collect();
+    let bottom: Vec<usize> = non_empty
+        .iter()
+        .rev()
+        .take(EDGE_LINES)
+        .filter(|i| !top.contains(i))
+        .copied()
+        .collect();
+    (top, bottom)
+}
+
+/// Detect and remove running headers/footers and standalone page numbers.
+///
+/// A line at the top or bottom of a page is considered a running header/footer when
+/// its normalized form appears in the same zone on enough pages. Returns the cleaned
+/// pages together with a report of every stripped line.
+pub fn remove_headers_footers(pages: &[String]) -> (Vec<String>, StripReport) {
+    let split: Vec<Vec<&str>> = pages.iter().map(|p| p.lines().collect()).collect();
+    let threshold = MIN_REPEAT_PAGES.max((pages.len() as f32 * REPEAT_RATIO).ceil() as usize);
+
+    // Count on how many pages each normalized edge line appears (once per page per zone)
+    let mut top_counts: HashMap<String, usize> = HashMap::new();
+    let mut bottom_counts: HashMap<String, usize> = HashMap::new();
+    for lines in &split {
+        let (top, bottom) = edge_line_indices(lines);
+        let top_set: HashSet<String> = top.iter().map(|&i| normalize_edge_line(lines[i])).collect();
+        let bottom_set: HashSet<String> = bottom
+            .iter()
+            .map(|&i| normalize_edge_line(lines[i]))
+            .collect();
+        for key in top_set {
+            *top_counts.entry(key).or_default() += 1;
+        }
+        for key in bottom_set {
+            *bottom_counts.entry(key).or_default() += 1;
+        }
+    }
+
+    let repeated_top: HashSet<&String> = top_counts
+        .iter()
+        .filter(|(_, &count)| count >= threshold)
+        .map(|(key, _)| key)
+        .collect();
+    let repeated_bottom: HashSet<&String> = bottom_counts
+        .iter()
+        .filter(|(_, &count)| count >= threshold)
+        .map(|(key, _)| key)
+        .collect();
+
+    let mut report = StripReport::default();
+    let mut repeated: Vec<String> = repeated_top
+        .iter()
+        .chain(repeated_bottom.iter())
+        .map(|s| s.to_string())
+        .collect();
+    repeated.sort();
+    repeated.dedup();
+    report.repeated_lines = repeated;
+
+    let cleaned = split
+        .iter()
+        .enumerate()
+        .map(|(page_index, lines)| {
+            let (top, bottom) = edge_line_indices(lines);
+            let mut removed: HashSet<usize> = HashSet::new();
+
+            for (indices, repeated_set, reason) in [
+                (&top, &repeated_top, StripReason::Header),
+                (&bottom, &repeated_bottom, StripReason::Footer),
+            ] {
+                for &i in indices {
+                    let reason = if is_page_number(lines[i]) {
+                        StripReason::PageNumber
+                    } else if repeated_set.contains(&normalize_edge_line(lines[i])) {
+                        reason
+                    } else {
+                        continue;
+                    };
+                    removed.insert(i);
+                    report.stripped.push(StrippedLine {
+                        page: page_index as u32 + 1,
+                        text: lines[i].trim().to_string(),
+                        reason,
+                    });
+                }
+            }
+
+            lines
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !removed.contains(i))
+                .map(|(_, l)| *l)
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim()
+                .to_string()
+        })
+        .collect();
+
+    report.stripped.sort_by_key(|s| s.page);
+    (cleaned, report)
+}
+
+/// Collapse runs of spaces and tabs, drop trailing spaces and keep at most one
+/// blank line between paragraphs
+pub fn normalize_whitespace(text: &str) -> String {
+    let unified = text.replace("\r\n", "\n").replace('\r', "\n");
+    let mut lines: Vec<String> = Vec::new();
+    for line in unified.lines() {
+        let line = line
+            .split([' ', '\t'])
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if line.is_empty() && lines.last().is_some_and(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+/// Remove harakat (fathatan through sukun, superscript alef) and tatweel
+pub fn strip_diacritics(text: &str, keep_shadda: bool) -> String {
+    text.chars()
+        .filter(|&c| {
+            let diacritic = matches!(c, '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{0640}');
+            !diacritic || (keep_shadda && c == '\u{0651}')
+        })
+        .collect()
+}
+
+/// Unify alef forms to a bare alef and alef maqsura to yaa
+pub fn unify_letters(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'أ' | 'إ' | 'آ' | 'ٱ' => 'ا',
+            'ى' => 'ي',
+            _ => c,
+        })
+        .collect()
+}
+
+/// Apply a page-level step to page `page` (1-based). Returns the new text and,
+/// for `quranVerses`, the substitutions made.
+fn apply_page_step(
+    text: &str,
+    page: u32,
+    step: &PostprocessStep,
+) -> Result<(String, Vec<QuranSubstitution>), TahweelError> {
+    let text = match step {
+        PostprocessStep::NormalizeWhitespace => normalize_whitespace(text),
+        PostprocessStep::StripDiacritics { keep_shadda } => strip_diacritics(text, *keep_shadda),
+        PostprocessStep::UnifyLetters => unify_letters(text),
+        PostprocessStep::ReflowParagraphs => reflow_paragraphs(text),
+        PostprocessStep::Replace { find, replace } if !find.is_empty() => {
+            text.replace(find.as_str(), replace)
+        }
+        PostprocessStep::QuranVerses { script } => {
+            let index = quran::index().ok_or_else(|| {
+                TahweelError::NotFound("Quran text index is not installed".to_string())
+            })?;
+            return Ok(index.substitute(page, text, *script));
+        }
+        PostprocessStep::Replace { .. } | PostprocessStep::RemoveHeadersFooters => text.to_string(),
+    };
+    Ok((text, Vec::new()))
+}
+
+/// Run `steps` over all pages of a document, in order
+pub fn run_pipeline(
+    pages: Vec<String>,
+    steps: &[PostprocessStep],
+) -> Result<PostprocessResult, TahweelError> {
+    let mut result = PostprocessResult {
+        pages,
+        strip_report: None,
+        quran_substitutions: None,
+    };
+    for step in steps {
+        if step.is_document_level() {
+            let (cleaned, report) = remove_headers_footers(&result.pages);
+            result.pages = cleaned;
+            let strip_report = result.strip_report.get_or_insert_with(StripReport::default);
+            strip_report.repeated_lines.extend(report.repeated_lines);
+            strip_report.stripped.extend(report.stripped);
+            continue;
+        }
+        let mut substitutions = Vec::new();
+        for (i, text) in result.pages.iter_mut().enumerate() {
+            let (processed, found) = apply_page_step(text, i as u32 + 1, step)?;
+            *text = processed;
+            substitutions.extend(found);
+        }
+        if matches!(step, PostprocessStep::QuranVerses { .. }) {
+            result
+                .quran_substitutions
+                .get_or_insert_with(Vec::new)
+                .extend(substitutions);
+        }
+    }
+    Ok(result)
+}
+
+/// Run the page-level steps of `steps` over one page, for outputs streamed a
+/// page at a time; document-level steps are left out
+pub fn run_page_steps(
+    text: &str,
+    page: u32,
+    steps: &[PostprocessStep],
+) -> Result<String, TahweelError> {
+    let mut text = text.to_string();
+    for step in steps.iter().filter(|step| !step.is_document_level()) {
+        text = apply_page_step(&text, page, step)?.0;
+    }
+    Ok(text)
+}
+
+/// Run `steps` over a sample page, keeping the text after each step
+pub fn preview(
+    page_text: &str,
+    steps: &[PostprocessStep],
+) -> Result<PostprocessPreview, TahweelError> {
+    let mut text = page_text.to_string();
+    let mut stages = Vec::with_capacity(steps.len());
+    for step in steps {
+        let skipped = step.is_document_level();
+        if !skipped {
+            text = apply_page_step(&text, 1, step)?.0;
+        }
+        stages.push(PreviewStage {
+            step: step.name().to_string(),
+            text: text.clone(),
+            skipped,
+        });
+    }
+    Ok(PostprocessPreview { text, stages })
+}
+
+impl PostprocessOptions {
+    /// The options as a pipeline: header/footer removal, line breaks, then
+    /// Quranic verse substitution on the final text
+    pub fn steps(&self) -> Vec<PostprocessStep> {
+        let mut steps = Vec::new();
+        if self.remove_headers_footers {
+            steps.push(PostprocessStep::RemoveHeadersFooters);
+        }
+        if self.line_breaks == LineBreakMode::Reflow {
+            steps.push(PostprocessStep::ReflowParagraphs);
+        }
+        if let Some(script) = self.quran_verses {
+            steps.push(PostprocessStep::QuranVerses { script });
+        }
+        steps
+    }
+}
+
+/// Apply post-processing to a single page's text
+pub fn postprocess_page(text: &str, options: &PostprocessOptions) -> String {
+    match options.line_breaks {
+        LineBreakMode::Keep => text.to_string(),
+        LineBreakMode::Reflow => reflow_paragraphs(text),
+    }
+}
+
+/// Apply post-processing to all pages of a document.
+/// Document-level passes (header/footer removal) run before per-page passes;
+/// Quranic verse substitution runs last, on the final text.
+#[tauri::command]
+pub async fn postprocess_pages(
+    pages: Vec<String>,
+    options: PostprocessOptions,
+) -> Result<PostprocessResult, TahweelError> {
+    run_pipeline(pages, &options.steps())
+}
+
+/// Run a post-processing pipeline over all pages of a document before its
+/// outputs are written; without `pipeline`, the one configured in settings
+#[tauri::command]
+pub async fn apply_postprocessing(
+    pages: Vec<String>,
+    pipeline: Option<Vec<PostprocessStep>>,
+) -> Result<PostprocessResult, TahweelError> {
+    let steps = pipeline.unwrap_or_else(|| settings::current().postprocessing);
+    tauri::async_runtime::spawn_blocking(move || run_pipeline(pages, &steps))
+        .await
+        .map_err(|e| TahweelError::Internal(format!("Post-processing failed: {}", e)))?
+}
+
+/// Show what `config` does to one page, step by step, for a live preview
+#[tauri::command]
+pub async fn preview_postprocessing(
+    page_text: String,
+    config: Vec<PostprocessStep>,
+) -> Result<PostprocessPreview, TahweelError> {
+    preview(&page_text, &config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WRAPPED_ARABIC: &str = "الحمد لله رب العالمين والصلاة والسلام على أشرف\n\
+        الأنبياء والمرسلين نبينا محمد وعلى آله وصحبه\n\
+        أجمعين.\n\
+        أما بعد فهذا كتاب في بيان أحكام الطهارة وما\n\
+        يتعلق بها من المسائل";
+
+    #[test]
+    fn test_reflow_merges_wrapped_lines() {
+        let result = reflow_paragraphs(WRAPPED_ARABIC);
+        let paragraphs: Vec<&str> = result.split("\n\n").collect();
+
+        assert_eq!(paragraphs.len(), 2);
+        assert!(paragraphs[0].starts_with("الحمد لله"));
+        assert!(paragraphs[0].ends_with("أجمعين."));
+        assert!(paragraphs[1].contains("وما يتعلق"));
+    }
+
+    #[test]
+    fn test_reflow_breaks_on_arabic_question_mark() {
+        let text =
+            "هل هذا سؤال طويل بما يكفي ليكون سطرا كاملا؟\nنعم هذا هو الجواب الطويل بما يكفي أيضا";
+        let result = reflow_paragraphs(text);
+        assert_eq!(result.split("\n\n").count(), 2);
+    }
+
+    #[test]
+    fn test_reflow_keeps_blank_line_paragraphs() {
+        let text = "first paragraph line one that is long enough\n\nsecond paragraph";
+        let result = reflow_paragraphs(text);
+        assert_eq!(
+            result,
+            "first paragraph line one that is long enough\n\nsecond paragraph"
+        );
+    }
+
+    #[test]
+    fn test_reflow_short_line_ends_paragraph() {
+        let text = "Chapter One\nThis is a long line of body text that keeps going\nand continues here until the end";
+        let result = reflow_paragraphs(text);
+        assert!(result.starts_with("Chapter One\n\n"));
+        assert!(result.contains("going and continues"));
+    }
+
+    #[test]
+    fn test_reflow_rejoins_hyphenated_latin_words() {
+        let text = "This sentence contains a hyphen-\nated word that should be rejoined properly";
+        let result = reflow_paragraphs(text);
+        assert!(result.contains("hyphenated"));
+    }
+
+    #[test]
+    fn test_reflow_keeps_list_items_separate() {
+        let text =
+            "The following items are required for the procedure\n1. first item\n2. second item";
+        let result = reflow_paragraphs(text);
+        assert_eq!(result.split("\n\n").count(), 3);
+    }
+
+    #[test]
+    fn test_starts_list_item_arabic_indic_digits() {
+        assert!(starts_list_item("١) البند الأول"));
+        assert!(starts_list_item("• bullet"));
+        assert!(!starts_list_item("2024 was a year"));
+        assert!(!starts_list_item("plain text"));
+    }
+
+    #[test]
+    fn test_reflow_empty_text() {
+        assert_eq!(reflow_paragraphs(""), "");
+        assert_eq!(reflow_paragraphs("\n\n\n"), "");
+    }
+
+    #[test]
+    fn test_postprocess_keep_mode_is_identity() {
+        let options = PostprocessOptions::default();
+        assert_eq!(postprocess_page(WRAPPED_ARABIC, &options), WRAPPED_ARABIC);
+    }
+
+    #[test]
+    fn test_postprocess_options_deserialization() {
+        let options: PostprocessOptions =
+            serde_json::from_str(r#"{"lineBreaks": "reflow"}"#).unwrap();
+        assert_eq!(options.line_breaks, LineBreakMode::Reflow);
+
+        let defaults: PostprocessOptions = serde_json::from_str("{}").unwrap();
+        assert_eq!(defaults.line_breaks, LineBreakMode::Keep);
+        assert!(!defaults.remove_headers_footers);
+    }
+
+    fn book_pages() -> Vec<String> {
+        (1..=6)
+            .map(|n| {
+                format!(
+                    "كتاب الطهارة - الجزء الأول\nنص الصفحة {} في متن الكتاب\nسطر آخر مختلف {}\n- {} -",
+                    "ت".repeat(n),
+                    "ب".repeat(n),
+                    n
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_is_page_number() {
+        assert!(is_page_number("12"));
+        assert!(is_page_number("- ١٢ -"));
+        assert!(is_page_number("(7)"));
+        assert!(is_page_number("[ 104 ]"));
+        assert!(!is_page_number("12 رجلا"));
+        assert!(!is_page_number("2024-01-01"));
+        assert!(!is_page_number(""));
+    }
+
+    #[test]
+    fn test_normalize_edge_line_masks_digits() {
+        assert_eq!(
+            normalize_edge_line("Chapter  Two   - page 12"),
+            "Chapter Two - page ##"
+        );
+        assert_eq!(normalize_edge_line("صفحة ١٢"), "صفحة ##");
+    }
+
+    #[test]
+    fn test_remove_headers_footers_strips_running_header_and_page_numbers() {
+        let (cleaned, report) = remove_headers_footers(&book_pages());
+
+        for page in &cleaned {
+            assert!(!page.contains("كتاب الطهارة"));
+            assert!(page.starts_with("نص الصفحة"));
+            assert!(page.contains("سطر آخر"));
+        }
+        assert!(!cleaned[2].contains("- 3 -"));
+        assert_eq!(
+            report.repeated_lines,
+            vec!["- # -", "كتاب الطهارة - الجزء الأول"]
+        );
+        assert_eq!(report.stripped.len(), 12);
+        assert!(report
+            .stripped
+            .iter()
+            .any(|s| s.page == 1 && s.reason == StripReason::PageNumber && s.text == "- 1 -"));
+        assert!(report
+            .stripped
+            .iter()
+            .any(|s| s.page == 6 && s.reason == StripReason::Header));
+    }
+
+    #[test]
+    fn test_remove_headers_footers_header_with_page_number() {
+        let pages: Vec<String> = (1..=5)
+            .map(|n| format!("Running Title {}\nBody text number {}", n, "x".repeat(n)))
+            .collect();
+
+        let (cleaned, report) = remove_headers_footers(&pages);
+        assert!(cleaned.iter().all(|p| p.starts_with("Body text")));
+        assert_eq!(report.repeated_lines, vec!["Running Title #"]);
+    }
+
+    #[test]
+    fn test_remove_headers_footers_keeps_body_in_short_documents() {
+        let pages = vec![
+            "Same line\nbody one".to_string(),
+            "Same line\nbody two".to_string(),
+        ];
+        let (cleaned, report) = remove_headers_footers(&pages);
+
+        assert_eq!(cleaned, pages);
+        assert!(report.stripped.is_empty());
+    }
+
+    #[test]
+    fn test_remove_headers_footers_middle_lines_untouched() {
+        let pages: Vec<String> = (1..=4)
+            .map(|n| {
+                format!(
+                    "top {}\nrepeated middle\nrepeated middle 2\nunique {}\nend {}",
+                    "a".repeat(n),
+                    n,
+                    "z".repeat(n)
+                )
+            })
+            .collect();
+
+        let (cleaned, _) = remove_headers_footers(&pages);
+        assert!(cleaned.iter().all(|p| p.contains("repeated middle")));
+    }
+
+    #[tokio::test]
+    async fn test_postprocess_pages_with_header_removal_report() {
+        let options = PostprocessOptions {
+            line_breaks: LineBreakMode::Keep,
+            remove_headers_footers: true,
+            quran_verses: None,
+        };
+
+        let result = postprocess_pages(book_pages(), options).await.unwrap();
+        assert_eq!(result.pages.len(), 6);
+        assert!(result.strip_report.is_some());
+        assert!(!result.pages[0].contains("كتاب الطهارة"));
+    }
+
+    #[tokio::test]
+    async fn test_postprocess_pages_command() {
+        let options = PostprocessOptions {
+            line_breaks: LineBreakMode::Reflow,
+            remove_headers_footers: false,
+            quran_verses: None,
+        };
+        let result = postprocess_pages(vec![WRAPPED_ARABIC.to_string(), String::new()], options)
+            .await
+            .unwrap();
+        assert_eq!(result.pages.len(), 2);
+        assert!(!result.pages[0].contains("أشرف\nالأنبياء"));
+        assert_eq!(result.pages[1], "");
+        assert!(result.strip_report.is_none());
+        assert!(result.quran_substitutions.is_none());
+    }
+
+    #[test]
+    fn test_pipeline_step_deserialization() {
+        let steps: Vec<PostprocessStep> = serde_json::from_str(
+            r#"[{"step":"normalizeWhitespace"},
+                {"step":"stripDiacritics","keepShadda":true},
+                {"step":"replace","find":"ـ","replace":""},
+                {"step":"quranVerses","script":"uthmani"}]"#,
+        )
+        .unwrap();
+        assert_eq!(steps[0], PostprocessStep::NormalizeWhitespace);
+        assert_eq!(
+            steps[1],
+            PostprocessStep::StripDiacritics { keep_shadda: true }
+        );
+        assert_eq!(steps[3].name(), "quranVerses");
+        let json = serde_json::to_value(&steps[1]).unwrap();
+        assert_eq!(json["step"], "stripDiacritics");
+        assert_eq!(json["keepShadda"], true);
+        assert!(serde_json::from_str::<PostprocessStep>(r#"{"step":"unknown"}"#).is_err());
+    }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        assert_eq!(
+            normalize_whitespace("  one   two\t \r\n\n\n\nthree  "),
+            "one two\n\nthree"
+        );
+    }
+
+    #[test]
+    fn test_strip_diacritics_and_unify_letters() {
+        assert_eq!(strip_diacritics("بِسْمِ اللَّهِ", false), "بسم الله");
+        assert_eq!(strip_diacritics("اللَّـهِ", true), "اللّه");
+        assert_eq!(unify_letters("إلى أن آمن"), "الي ان امن");
+    }
+
+    #[test]
+    fn test_pipeline_runs_steps_in_order() {
+        let replace = PostprocessStep::Replace {
+            find: "  ".to_string(),
+            replace: "|".to_string(),
+        };
+        let pages = vec!["a  b".to_string()];
+        let replaced_first = run_pipeline(
+            pages.clone(),
+            &[replace.clone(), PostprocessStep::NormalizeWhitespace],
+        )
+        .unwrap();
+        assert_eq!(replaced_first.pages, vec!["a|b"]);
+        let normalized_first =
+            run_pipeline(pages, &[PostprocessStep::NormalizeWhitespace, replace]).unwrap();
+        assert_eq!(normalized_first.pages, vec!["a b"]);
+    }
+
+    #[test]
+    fn test_options_match_equivalent_pipeline() {
+        let options = PostprocessOptions {
+            line_breaks: LineBreakMode::Reflow,
+            remove_headers_footers: true,
+            quran_verses: None,
+        };
+        assert_eq!(
+            options.steps(),
+            vec![
+                PostprocessStep::RemoveHeadersFooters,
+                PostprocessStep::ReflowParagraphs
+            ]
+        );
+        let result = run_pipeline(book_pages(), &options.steps()).unwrap();
+        assert!(!result.strip_report.unwrap().stripped.is_empty());
+    }
+
+    #[test]
+    fn test_page_steps_skip_document_level_steps() {
+        let steps = [
+            PostprocessStep::RemoveHeadersFooters,
+            PostprocessStep::UnifyLetters,
+        ];
+        assert_eq!(run_page_steps("أ\nب", 1, &steps).unwrap(), "ا\nب");
+    }
+
+    #[test]
+    fn test_preview_keeps_each_stage() {
+        let steps = [
+            PostprocessStep::StripDiacritics { keep_shadda: false },
+            PostprocessStep::RemoveHeadersFooters,
+            PostprocessStep::UnifyLetters,
+        ];
+        let preview = preview("أَحْمَد", &steps).unwrap();
+        assert_eq!(preview.text, "احمد");
+        assert_eq!(preview.stages.len(), 3);
+        assert_eq!(preview.stages[0].text, "أحمد");
+        assert!(preview.stages[1].skipped);
+        assert_eq!(preview.stages[2].step, "unifyLetters");
+    }
+}
@@ -0,0 +1,457 @@
+use crate::error::TahweelError;
+use crate::settings::{self, Settings};
+use serde::Serialize;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// How often the power source is read
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// How long a waiting render sleeps before looking at the mode and pause again
+const RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Nice value of render threads in background mode (Linux)
+#[cfg(all(unix, not(target_vendor = "apple")))]
+const BACKGROUND_NICE: libc::c_int = 10;
+
+/// Where the machine draws power from
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct PowerSource {
+    #[serde(rename = "onBattery")]
+    pub on_battery: bool,
+    /// Charge of the battery (0-100), when the machine has one
+    #[serde(rename = "batteryPercent")]
+    pub battery_percent: Option<u8>,
+}
+
+/// Payload of the `power-status` event and `get_power_status`
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct PowerStatus {
+    #[serde(flatten)]
+    pub source: PowerSource,
+    #[serde(rename = "backgroundMode")]
+    pub background_mode: bool,
+    /// Work is held back until the machine is plugged in or charged
+    pub paused: bool,
+}
+
+static BACKGROUND: AtomicBool = AtomicBool::new(false);
+static PAUSED: AtomicBool = AtomicBool::new(false);
+static LAST_STATUS: Mutex<Option<PowerStatus>> = Mutex::new(None);
+
+/// Background mode pauses work on battery below the configured charge
+pub fn should_pause(source: PowerSource, settings: &Settings) -> bool {
+    settings.background_mode
+        && source.on_battery
+        && source
+            .battery_percent
+            .is_some_and(|percent| percent < settings.battery_pause_percent)
+}
+
+/// Pages rendered at once: half the pool in background mode, at least one
+pub fn render_limit(threads: u32, background: bool) -> u32 {
+    if background {
+        (threads / 2).max(1)
+    } else {
+        threads.max(1)
+    }
+}
+
+/// Power source from Linux's `/sys/class/power_supply`: on battery when a
+/// battery is discharging; the charge is the mean over all batteries
+pub fn source_from_power_supply(dir: &std::path::Path) -> PowerSource {
+    let read = |path: std::path::PathBuf| {
+        std::fs::read_to_string(path)
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default()
+    };
+    let mut on_battery = false;
+    let mut charges = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return PowerSource::default();
+    };
+    for entry in entries.flatten() {
+        let supply = entry.path();
+        if read(supply.join("type")) != "Battery" {
+            continue;
+        }
+        if read(supply.join("status")) == "Discharging" {
+            on_battery = true;
+        }
+        if let Ok(capacity) = read(supply.join("capacity")).parse::<u32>() {
+            charges.push(capacity.min(100));
+        }
+    }
+    let battery_percent =
+        (!charges.is_empty()).then(|| (charges.iter().sum::<u32>() / charges.len() as u32) as u8);
+    PowerSource {
+        on_battery,
+        battery_percent,
+    }
+}
+
+/// Power source from macOS's `pmset -g batt`, e.g.
+/// `Now drawing from 'Battery Power'` / ` -InternalBattery-0 (id=1)\t85%; discharging; ...`
+pub fn parse_pmset(output: &str) -> PowerSource {
+    let on_battery = output.contains("'Battery Power'");
+    let battery_percent = output
+        .split(|c: char| c.is_whitespace() || c == ';')
+        .find_map(|token| token.strip_suffix('%')?.parse::<u8>().ok())
+        .map(|percent| percent.min(100));
+    PowerSource {
+        on_battery,
+        battery_percent,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_source() -> PowerSource {
+    source_from_power_supply(std::path::Path::new("/sys/class/power_supply"))
+}
+
+#[cfg(target_os = "macos")]
+fn read_source() -> PowerSource {
+    std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .map(|output| parse_pmset(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+#[cfg(windows)]
+fn read_source() -> PowerSource {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    // SAFETY: `status` is a valid out-pointer
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return PowerSource::default();
+    }
+    PowerSource {
+        // 0 = offline, 1 = online, 255 = unknown
+        on_battery: status.ACLineStatus == 0,
+        battery_percent: (status.BatteryLifePercent <= 100).then_some(status.BatteryLifePercent),
+    }
+}
+
+/// Phones and tablets manage background work themselves
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn read_source() -> PowerSource {
+    PowerSource::default()
+}
+
+/// Read the power source and apply `settings`; emits `power-status` when the
+/// pause or mode changed
+pub fn refresh(app: &AppHandle, settings: &Settings) -> PowerStatus {
+    let source = read_source();
+    let status = PowerStatus {
+        source,
+        background_mode: settings.background_mode,
+        paused: should_pause(source, settings),
+    };
+    BACKGROUND.store(status.background_mode, Ordering::Relaxed);
+    PAUSED.store(status.paused, Ordering::Relaxed);
+
+    let mut last = LAST_STATUS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let changed = !matches!(*last, Some(last)
+        if last.paused == status.paused && last.background_mode == status.background_mode);
+    *last = Some(status);
+    drop(last);
+    if changed {
+        if status.paused {
+            tracing::info!(
+                battery_percent = source.battery_percent,
+                "On low battery; pausing work until plugged in"
+            );
+        }
+        let _ = app.emit("power-status", status);
+    }
+    status
+}
+
+/// Read the power source now and then every `POLL_INTERVAL`; call once from `setup`
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name("power-monitor".to_string())
+        .spawn(move || loop {
+            refresh(&app, &settings::current());
+            std::thread::sleep(POLL_INTERVAL);
+        });
+    if let Err(error) = spawned {
+        tracing::warn!(%error, "Failed to start the power monitor");
+    }
+}
+
+thread_local! {
+    /// Whether this thread currently runs at background priority
+    static LOWERED: Cell<bool> = const { Cell::new(false) };
+}
+
+#[cfg(target_vendor = "apple")]
+fn set_thread_priority(low: bool) -> bool {
+    let value = if low { libc::PRIO_DARWIN_BG } else { 0 };
+    // SAFETY: plain syscall on the calling thread
+    unsafe { libc::setpriority(libc::PRIO_DARWIN_THREAD, 0, value) == 0 }
+}
+
+#[cfg(all(unix, not(target_vendor = "apple")))]
+fn set_thread_priority(low: bool) -> bool {
+    // Linux keeps a nice value per thread; raising it back needs privileges,
+    // so a thread lowered once stays lowered until its pool is dropped
+    let value = if low { BACKGROUND_NICE } else { 0 };
+    // SAFETY: plain syscall on the calling thread
+    unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, value) == 0 }
+}
+
+#[cfg(windows)]
+fn set_thread_priority(low: bool) -> bool {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentThread, SetThreadPriority, THREAD_PRIORITY_BELOW_NORMAL, THREAD_PRIORITY_NORMAL,
+    };
+
+    let value = if low {
+        THREAD_PRIORITY_BELOW_NORMAL
+    } else {
+        THREAD_PRIORITY_NORMAL
+    };
+    // SAFETY: the pseudo handle of the calling thread needs no closing
+    unsafe { SetThreadPriority(GetCurrentThread(), value) != 0 }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn set_thread_priority(_low: bool) -> bool {
+    false
+}
+
+/// Lower or restore the calling thread's scheduling priority, once per change
+fn apply_thread_priority(low: bool) {
+    LOWERED.with(|lowered| {
+        if lowered.get() != low && set_thread_priority(low) {
+            lowered.set(low);
+        }
+    });
+}
+
+/// Limits how many pages of one split render at once, following background mode
+pub struct RenderGate {
+    threads: u32,
+    active: Mutex<u32>,
+    released: Condvar,
+    /// Set when the split is stopped; pages not yet let in give up
+    cancel: Arc<AtomicBool>,
+}
+
+/// A page being rendered; frees its place in the gate on drop
+pub struct RenderSlot<'a> {
+    gate: &'a RenderGate,
+}
+
+impl Drop for RenderSlot<'_> {
+    fn drop(&mut self) {
+        let mut active = self
+            .gate
+            .active
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        *active = active.saturating_sub(1);
+        self.gate.released.notify_all();
+    }
+}
+
+impl RenderGate {
+    /// Gate for a render pool of `threads` threads, stopped by `cancel`
+    pub fn new(threads: u32, cancel: Arc<AtomicBool>) -> Self {
+        Self {
+            threads,
+            active: Mutex::new(0),
+            released: Condvar::new(),
+            cancel,
+        }
+    }
+
+    /// Wait for a place to render a page: while paused on low battery, or while
+    /// background mode's limit is reached. The calling thread's priority follows
+    /// the mode. The mode and the cancel flag may change at any time, so waits
+    /// are re-checked every `RECHECK_INTERVAL`; once cancelled, this fails
+    /// instead of waiting on.
+    pub fn enter(&self) -> Result<RenderSlot<'_>, TahweelError> {
+        let mut active = self
+            .active
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        loop {
+            if self.cancel.load(Ordering::Relaxed) {
+                return Err(TahweelError::Internal("Rendering cancelled".to_string()));
+            }
+            let background = BACKGROUND.load(Ordering::Relaxed);
+            if !PAUSED.load(Ordering::Relaxed) && *active < render_limit(self.threads, background) {
+                break;
+            }
+            active = self
+                .released
+                .wait_timeout(active, RECHECK_INTERVAL)
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+        *active += 1;
+        drop(active);
+        apply_thread_priority(BACKGROUND.load(Ordering::Relaxed));
+        Ok(RenderSlot { gate: self })
+    }
+}
+
+/// Current power source and whether background mode holds work back
+#[tauri::command]
+pub async fn get_power_status(app: AppHandle) -> PowerStatus {
+    refresh(&app, &settings::current())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn settings(background_mode: bool, battery_pause_percent: u8) -> Settings {
+        Settings {
+            background_mode,
+            battery_pause_percent,
+            ..Settings::default()
+        }
+    }
+
+    fn on_battery(percent: u8) -> PowerSource {
+        PowerSource {
+            on_battery: true,
+            battery_percent: Some(percent),
+        }
+    }
+
+    #[test]
+    fn test_should_pause_only_in_background_mode_on_low_battery() {
+        assert!(should_pause(on_battery(15), &settings(true, 20)));
+        assert!(!should_pause(on_battery(25), &settings(true, 20)));
+        assert!(!should_pause(on_battery(15), &settings(false, 20)));
+        let plugged_in = PowerSource {
+            on_battery: false,
+            battery_percent: Some(5),
+        };
+        assert!(!should_pause(plugged_in, &settings(true, 20)));
+        let unknown_charge = PowerSource {
+            on_battery: true,
+            battery_percent: None,
+        };
+        assert!(!should_pause(unknown_charge, &settings(true, 20)));
+    }
+
+    #[test]
+    fn test_render_limit_halves_in_background_mode() {
+        assert_eq!(render_limit(8, false), 8);
+        assert_eq!(render_limit(8, true), 4);
+        assert_eq!(render_limit(3, true), 1);
+        assert_eq!(render_limit(1, true), 1);
+    }
+
+    #[test]
+    fn test_source_from_power_supply() {
+        let dir = tempdir().unwrap();
+        let write = |name: &str, files: &[(&str, &str)]| {
+            let supply = dir.path().join(name);
+            fs::create_dir(&supply).unwrap();
+            for (file, value) in files {
+                fs::write(supply.join(file), format!("{}\n", value)).unwrap();
+            }
+        };
+        write("AC", &[("type", "Mains"), ("online", "0")]);
+        write(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "40"),
+            ],
+        );
+        write(
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "20"),
+            ],
+        );
+        assert_eq!(source_from_power_supply(dir.path()), on_battery(30));
+    }
+
+    #[test]
+    fn test_desktop_without_battery() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("AC")).unwrap();
+        fs::write(dir.path().join("AC").join("type"), "Mains\n").unwrap();
+        assert_eq!(source_from_power_supply(dir.path()), PowerSource::default());
+        assert_eq!(
+            source_from_power_supply(&dir.path().join("missing")),
+            PowerSource::default()
+        );
+    }
+
+    #[test]
+    fn test_parse_pmset() {
+        let battery = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=4653155)\t85%; discharging; 4:20 remaining present: true\n";
+        assert_eq!(parse_pmset(battery), on_battery(85));
+        let charging = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=4653155)\t100%; charged; 0:00 remaining present: true\n";
+        assert_eq!(
+            parse_pmset(charging),
+            PowerSource {
+                on_battery: false,
+                battery_percent: Some(100),
+            }
+        );
+        assert_eq!(
+            parse_pmset("Now drawing from 'AC Power'\n"),
+            PowerSource::default()
+        );
+    }
+
+    #[test]
+    fn test_render_gate_limits_active_pages() {
+        let gate = RenderGate::new(2, Arc::default());
+        let first = gate.enter().unwrap();
+        let _second = gate.enter().unwrap();
+        assert_eq!(*gate.active.lock().unwrap(), 2);
+        drop(first);
+        assert_eq!(*gate.active.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_cancel_releases_a_waiting_render() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let gate = Arc::new(RenderGate::new(1, cancel.clone()));
+        let _held = gate.enter().unwrap();
+
+        // The only place is taken, so this waits until the split is cancelled
+        let waiting = std::thread::spawn({
+            let gate = gate.clone();
+            move || gate.enter().map(|_| ()).unwrap_err().message().to_string()
+        });
+        cancel.store(true, Ordering::Relaxed);
+        assert_eq!(waiting.join().unwrap(), "Rendering cancelled");
+    }
+
+    #[test]
+    fn test_power_status_serialization() {
+        let status = PowerStatus {
+            source: on_battery(12),
+            background_mode: true,
+            paused: true,
+        };
+        let json = serde_json::to_value(status).unwrap();
+        assert_eq!(json["onBattery"], true);
+        assert_eq!(json["batteryPercent"], 12);
+        assert_eq!(json["backgroundMode"], true);
+        assert_eq!(json["paused"], true);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::app_paths::{self, Area};
+use crate::error::TahweelError;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter};
+
+/// Profile using the original (unscoped) settings, token and history files
+pub const DEFAULT_PROFILE: &str = "default";
+/// Longest profile name accepted
+const MAX_NAME_CHARS: usize = 40;
+/// Folder under each area of the app-data layout holding the other profiles
+const PROFILES_DIR: &str = "profiles";
+
+/// Profile this process reads and writes; None until `init` or `switch_profile`
+static ACTIVE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Profiles on this machine, as returned to the UI
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProfileList {
+    pub profiles: Vec<String>,
+    pub active: String,
+}
+
+/// `profiles.json`: the profile the next launch starts with
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Registry {
+    #[serde(default)]
+    active: Option<String>,
+}
+
+/// Letters, digits, `-` and `_` (Arabic names included), at most 40 characters
+pub fn is_valid_name(name: &str) -> bool {
+    let chars = name.chars().count();
+    chars > 0
+        && chars <= MAX_NAME_CHARS
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+fn config_root() -> PathBuf {
+    app_paths::dir(Area::Config)
+}
+
+fn registry_path() -> PathBuf {
+    config_root().join("profiles.json")
+}
+
+/// `dir` (an app `tahweel` folder) as seen by `profile`: the default profile
+/// keeps the folder itself, others get `<dir>/profiles/<name>`
+pub fn scoped_to(dir: &Path, profile: &str) -> PathBuf {
+    if profile == DEFAULT_PROFILE {
+        dir.to_path_buf()
+    } else {
+        dir.join(PROFILES_DIR).join(profile)
+    }
+}
+
+/// `dir` as seen by the active profile; used for settings, tokens and history
+pub fn scoped(dir: PathBuf) -> PathBuf {
+    scoped_to(&dir, &active())
+}
+
+/// Name of the active profile
+pub fn active() -> String {
+    ACTIVE
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+fn set_active(profile: &str) {
+    *ACTIVE
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(profile.to_string());
+}
+
+/// Profiles under the config folder `root`, the default one first
+pub fn list_in(root: &Path) -> Vec<String> {
+    let mut profiles: Vec<String> = fs::read_dir(root.join(PROFILES_DIR))
+        .map(|dirs| {
+            dirs.filter_map(|dir| dir.ok())
+                .filter(|dir| dir.path().is_dir())
+                .map(|dir| dir.file_name().to_string_lossy().to_string())
+                .filter(|name| is_valid_name(name) && name != DEFAULT_PROFILE)
+                .collect()
+        })
+        .unwrap_or_default();
+    profiles.sort();
+    profiles.insert(0, DEFAULT_PROFILE.to_string());
+    profiles
+}
+
+/// Create the config folder of a new profile under `root`
+pub fn create_in(root: &Path, name: &str) -> Result<(), TahweelError> {
+    if !is_valid_name(name) {
+        return Err(TahweelError::InvalidInput(format!(
+            "Invalid profile name: {}",
+            name
+        )));
+    }
+    if list_in(root).iter().any(|profile| profile == name) {
+        return Err(TahweelError::InvalidInput(format!(
+            "Profile already exists: {}",
+            name
+        )));
+    }
+    fs::create_dir_all(scoped_to(root, name))?;
+    Ok(())
+}
+
+/// Profile named by `--profile <name>` or `--profile=<name>` among launch arguments
+pub fn profile_arg(args: &[String]) -> Option<String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            return args.next().cloned();
+        }
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            return Some(name.to_string());
+        }
+    }
+    None
+}
+
+/// Pick the profile at startup: `--profile` when it names an existing profile,
+/// otherwise the one used last. Call from `setup` before anything reads settings.
+pub fn init(args: &[String]) {
+    let root = config_root();
+    let profiles = list_in(&root);
+    let last = fs::read(registry_path())
+        .ok()
+        .and_then(|data| serde_json::from_slice::<Registry>(&data).ok())
+        .and_then(|registry| registry.active);
+    let requested = profile_arg(args);
+    if let Some(name) = requested.as_ref().filter(|name| !profiles.contains(name)) {
+        eprintln!("Unknown profile {}, using the last one", name);
+    }
+    let profile = requested
+        .into_iter()
+        .chain(last)
+        .find(|name| profiles.contains(name))
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+    set_active(&profile);
+}
+
+/// List the profiles and the active one
+#[tauri::command]
+pub async fn list_profiles() -> ProfileList {
+    ProfileList {
+        profiles: list_in(&config_root()),
+        active: active(),
+    }
+}
+
+/// Create a profile with its own settings, sign-in and history. It starts
+/// with default settings and is not switched to.
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn create_profile(name: String) -> Result<ProfileList, TahweelError> {
+    create_in(&config_root(), &name)?;
+    Ok(list_profiles().await)
+}
+
+/// Make `name` the active profile (and the one the next launch starts with),
+/// reload its settings and emit `profile-changed` and `settings-changed`.
+/// The UI reloads to pick up the profile's sign-in and history.
+#[tauri::command]
+#[tracing::instrument(skip(app), err)]
+pub async fn switch_profile(name: String, app: AppHandle) -> Result<ProfileList, TahweelError> {
+    if !list_in(&config_root()).contains(&name) {
+        return Err(TahweelError::NotFound(format!("No profile named {}", name)));
+    }
+    let registry = Registry {
+        active: Some(name.clone()),
+    };
+    app_paths::write_atomic(&registry_path(), &serde_json::to_vec_pretty(&registry)?)?;
+    set_active(&name);
+    let settings = settings::reload();
+    tracing::info!(profile = %name, "Switched profile");
+    let _ = app.emit("profile-changed", &name);
+    let _ = app.emit("settings-changed", &settings);
+    Ok(list_profiles().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_is_valid_name() {
+        assert!(is_valid_name("reading-room_2"));
+        assert!(is_valid_name("المكتبة"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("../other"));
+        assert!(!is_valid_name("a b"));
+        assert!(!is_valid_name(&"a".repeat(41)));
+    }
+
+    #[test]
+    fn test_scoped_to() {
+        let dir = Path::new("/data/tahweel");
+        assert_eq!(scoped_to(dir, DEFAULT_PROFILE), dir);
+        assert_eq!(
+            scoped_to(dir, "staff"),
+            Path::new("/data/tahweel/profiles/staff")
+        );
+    }
+
+    #[test]
+    fn test_create_and_list() {
+        let root = tempdir().unwrap();
+        assert_eq!(list_in(root.path()), vec![DEFAULT_PROFILE]);
+
+        create_in(root.path(), "visitors").unwrap();
+        create_in(root.path(), "staff").unwrap();
+        assert!(root.path().join("profiles").join("staff").is_dir());
+        assert_eq!(
+            list_in(root.path()),
+            vec![DEFAULT_PROFILE, "staff", "visitors"]
+        );
+
+        for name in ["staff", DEFAULT_PROFILE, "a/b"] {
+            let err = create_in(root.path(), name).unwrap_err();
+            assert_eq!(err.kind(), "invalidInput");
+        }
+    }
+
+    #[test]
+    fn test_profile_arg() {
+        let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            profile_arg(&args(&["tahweel", "--profile", "staff", "book.pdf"])),
+            Some("staff".to_string())
+        );
+        assert_eq!(
+            profile_arg(&args(&["tahweel", "--profile=staff"])),
+            Some("staff".to_string())
+        );
+        assert_eq!(profile_arg(&args(&["tahweel", "book.pdf"])), None);
+    }
+}
@@ -0,0 +1,316 @@
+use crate::backend_health::GOOGLE_DRIVE;
+use crate::error::TahweelError;
+use crate::jobs;
+use crate::settings::{self, DailyBudget, QuotaSettings};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+/// How often budgets are checked for a reset (or a changed limit)
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// Share of a budget at which `quota-warning` is sent
+const WARNING_SHARE: f64 = 0.8;
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// Backends with a daily budget
+pub const BACKENDS: &[&str] = &[GOOGLE_DRIVE];
+
+/// Payload of the `quota-status` and `quota-warning` events and `get_quota_status`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct QuotaStatus {
+    pub backend: String,
+    pub budget: DailyBudget,
+    /// Pages sent since `periodStart`
+    pub pages: u64,
+    #[serde(rename = "apiCalls")]
+    pub api_calls: u64,
+    /// Seconds since the Unix epoch the current day started and ends
+    #[serde(rename = "periodStart")]
+    pub period_start: i64,
+    #[serde(rename = "resetsAt")]
+    pub resets_at: i64,
+    /// Largest share of a limit used (0 without limits)
+    pub used: f64,
+    /// `WARNING_SHARE` of a limit is used
+    pub warning: bool,
+    /// A limit is used up: work on this backend waits for `resetsAt`
+    pub paused: bool,
+}
+
+/// What was last sent about a backend, so each change is sent once
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Sent {
+    period_start: i64,
+    warned: bool,
+    paused: bool,
+}
+
+impl Sent {
+    /// Take in `status`; returns whether to send a warning and whether the
+    /// pause changed
+    fn update(&mut self, status: &QuotaStatus) -> (bool, bool) {
+        if self.period_start != status.period_start {
+            self.period_start = status.period_start;
+            self.warned = false;
+        }
+        let warn = status.warning && !self.warned;
+        self.warned |= status.warning;
+        let pause_changed = self.paused != status.paused;
+        self.paused = status.paused;
+        (warn, pause_changed)
+    }
+}
+
+static SENT: Mutex<Option<HashMap<String, Sent>>> = Mutex::new(None);
+/// Set in `init`; nothing is counted before (or, in tests, without) it
+static EMITTER: OnceLock<AppHandle> = OnceLock::new();
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Start of the budget day holding `now_secs`, for days starting at `reset_hour` UTC
+pub fn period_start(now_secs: i64, reset_hour: u8) -> i64 {
+    let offset = i64::from(reset_hour) * 60 * 60;
+    (now_secs - offset).div_euclid(DAY_SECS) * DAY_SECS + offset
+}
+
+fn budget_of(settings: &QuotaSettings, backend: &str) -> DailyBudget {
+    match backend {
+        GOOGLE_DRIVE => settings.google_drive,
+        _ => DailyBudget::default(),
+    }
+}
+
+/// Where `backend` stands against `budget` after `pages` and `api_calls`
+pub fn status(
+    backend: &str,
+    budget: DailyBudget,
+    pages: u64,
+    api_calls: u64,
+    period_start: i64,
+) -> QuotaStatus {
+    let share = |used: u64, limit: u32| {
+        if limit == 0 {
+            0.0
+        } else {
+            used as f64 / f64::from(limit)
+        }
+    };
+    let used = share(pages, budget.max_pages).max(share(api_calls, budget.max_api_calls));
+    QuotaStatus {
+        backend: backend.to_string(),
+        budget,
+        pages,
+        api_calls,
+        period_start,
+        resets_at: period_start + DAY_SECS,
+        used,
+        warning: used >= WARNING_SHARE,
+        paused: used >= 1.0,
+    }
+}
+
+/// Today's status of `backend` under `settings`
+async fn current(backend: &str, settings: &QuotaSettings) -> Result<QuotaStatus, TahweelError> {
+    let start = period_start(now_secs(), settings.reset_hour_utc);
+    let key = backend.to_string();
+    let (pages, api_calls) = jobs::db()?
+        .read_async(move |conn| jobs::load_quota_usage(conn, &key, start))
+        .await?;
+    Ok(status(
+        backend,
+        budget_of(settings, backend),
+        pages,
+        api_calls,
+        start,
+    ))
+}
+
+/// Send `quota-warning` the first time a day's usage reaches `WARNING_SHARE`
+/// and `quota-status` when the backend pauses or resumes
+fn publish(status: &QuotaStatus) {
+    let (warn, pause_changed) = SENT
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .entry(status.backend.clone())
+        .or_default()
+        .update(status);
+    if pause_changed {
+        if status.paused {
+            tracing::warn!(
+                backend = %status.backend,
+                pages = status.pages,
+                api_calls = status.api_calls,
+                resets_at = status.resets_at,
+                "Daily budget used up; pausing until it resets"
+            );
+        } else {
+            tracing::info!(backend = %status.backend, "Daily budget available again");
+        }
+    }
+    let Some(app) = EMITTER.get() else {
+        return;
+    };
+    if warn {
+        let _ = app.emit("quota-warning", status);
+    }
+    if pause_changed {
+        let _ = app.emit("quota-status", status);
+    }
+}
+
+/// Count `pages` and `api_calls` against today's budget of `backend`. Best
+/// effort: queued without waiting, and a failure is logged and never fails the
+/// request it counts.
+pub fn consume(backend: &str, pages: u64, api_calls: u64) {
+    if EMITTER.get().is_none() {
+        return;
+    }
+    let settings = settings::current().quota;
+    let start = period_start(now_secs(), settings.reset_hour_utc);
+    let backend = backend.to_string();
+    let queued = jobs::db().and_then(|db| {
+        db.write_detached(move |conn| {
+            match jobs::add_quota_usage(conn, &backend, start, pages, api_calls) {
+                Ok((pages, api_calls)) => publish(&status(
+                    &backend,
+                    budget_of(&settings, &backend),
+                    pages,
+                    api_calls,
+                    start,
+                )),
+                Err(error) => tracing::warn!(%error, "Failed to count quota usage"),
+            }
+        })
+    });
+    if let Err(error) = queued {
+        tracing::warn!(%error, "Failed to count quota usage");
+    }
+}
+
+/// Check every backend against `settings`, announcing warnings, pauses and
+/// resumes (after a reset or a raised limit)
+pub async fn refresh(settings: &QuotaSettings) {
+    for backend in BACKENDS {
+        match current(backend, settings).await {
+            Ok(status) => publish(&status),
+            Err(error) => tracing::warn!(%error, "Failed to check the daily budget"),
+        }
+    }
+}
+
+/// Check the budgets now and then every `POLL_INTERVAL`; call once from `setup`
+pub fn init(app: &AppHandle) {
+    let _ = EMITTER.set(app.clone());
+    tauri::async_runtime::spawn(async {
+        loop {
+            refresh(&settings::current().quota).await;
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+/// Today's usage and budget of every backend
+#[tauri::command]
+pub async fn get_quota_status() -> Result<Vec<QuotaStatus>, TahweelError> {
+    let settings = settings::current().quota;
+    let mut statuses = Vec::with_capacity(BACKENDS.len());
+    for backend in BACKENDS {
+        statuses.push(current(backend, &settings).await?);
+    }
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 60 * 60;
+    // 2024-03-10 00:00 UTC
+    const MIDNIGHT: i64 = 1_710_028_800;
+
+    fn budget(max_pages: u32, max_api_calls: u32) -> DailyBudget {
+        DailyBudget {
+            max_pages,
+            max_api_calls,
+        }
+    }
+
+    #[test]
+    fn test_period_start_follows_reset_hour() {
+        assert_eq!(period_start(MIDNIGHT + 5 * HOUR, 0), MIDNIGHT);
+        assert_eq!(period_start(MIDNIGHT + 9 * HOUR, 8), MIDNIGHT + 8 * HOUR);
+        // Before today's reset the day started yesterday
+        assert_eq!(period_start(MIDNIGHT + 7 * HOUR, 8), MIDNIGHT - 16 * HOUR);
+        assert_eq!(period_start(MIDNIGHT + 8 * HOUR, 8), MIDNIGHT + 8 * HOUR);
+    }
+
+    #[test]
+    fn test_status_uses_the_tighter_limit() {
+        let status = status(GOOGLE_DRIVE, budget(100, 1000), 40, 850, MIDNIGHT);
+        assert!((status.used - 0.85).abs() < 1e-9);
+        assert!(status.warning);
+        assert!(!status.paused);
+        assert_eq!(status.resets_at, MIDNIGHT + DAY_SECS);
+
+        assert!(super::status(GOOGLE_DRIVE, budget(100, 0), 100, 5000, MIDNIGHT).paused);
+        let unlimited = super::status(GOOGLE_DRIVE, budget(0, 0), 100_000, 1_000_000, MIDNIGHT);
+        assert_eq!(unlimited.used, 0.0);
+        assert!(!unlimited.warning && !unlimited.paused);
+    }
+
+    #[test]
+    fn test_warning_and_pause_are_sent_once() {
+        let mut sent = Sent::default();
+        let b = budget(10, 0);
+        assert_eq!(
+            sent.update(&status(GOOGLE_DRIVE, b, 5, 0, MIDNIGHT)),
+            (false, false)
+        );
+        assert_eq!(
+            sent.update(&status(GOOGLE_DRIVE, b, 8, 0, MIDNIGHT)),
+            (true, false)
+        );
+        assert_eq!(
+            sent.update(&status(GOOGLE_DRIVE, b, 9, 0, MIDNIGHT)),
+            (false, false)
+        );
+        assert_eq!(
+            sent.update(&status(GOOGLE_DRIVE, b, 10, 0, MIDNIGHT)),
+            (false, true)
+        );
+        assert_eq!(
+            sent.update(&status(GOOGLE_DRIVE, b, 11, 0, MIDNIGHT)),
+            (false, false)
+        );
+
+        // The next day resumes and may warn again
+        let next = MIDNIGHT + DAY_SECS;
+        assert_eq!(
+            sent.update(&status(GOOGLE_DRIVE, b, 0, 0, next)),
+            (false, true)
+        );
+        assert_eq!(
+            sent.update(&status(GOOGLE_DRIVE, b, 8, 0, next)),
+            (true, false)
+        );
+    }
+
+    #[test]
+    fn test_status_serialization() {
+        let json =
+            serde_json::to_value(status(GOOGLE_DRIVE, budget(100, 0), 20, 60, MIDNIGHT)).unwrap();
+        assert_eq!(json["backend"], "googleDrive");
+        assert_eq!(json["budget"]["maxPages"], 100);
+        assert_eq!(json["apiCalls"], 60);
+        assert_eq!(json["resetsAt"], MIDNIGHT + DAY_SECS);
+        assert_eq!(json["paused"], false);
+    }
+}
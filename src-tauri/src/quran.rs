@@ -0,0 +1,460 @@
+use crate::search::normalize_for_search;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Imlaei (standard orthography) text in Tanzil's `sura|aya|text` format
+const IMLAEI_FILE: &str = "quran-simple.txt";
+/// Uthmani text in the same format
+const UTHMANI_FILE: &str = "quran-uthmani.txt";
+/// Shorter verses (e.g. "الرحمن الرحيم") occur in ordinary prose too often to substitute
+const MIN_VERSE_WORDS: usize = 3;
+/// Character similarity a span needs to be taken as a verse
+const MIN_SIMILARITY: f64 = 0.8;
+/// Words a span may differ from the verse by (OCR splits and merges words)
+const WORD_SLACK: usize = 2;
+
+static RESOURCE_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+static INDEX: OnceLock<Option<QuranIndex>> = OnceLock::new();
+
+/// Script of the substituted verse text
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QuranScript {
+    Uthmani,
+    Imlaei,
+}
+
+/// A span of OCR text replaced by the canonical text of a verse
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuranSubstitution {
+    pub page: u32,
+    pub surah: u16,
+    pub ayah: u16,
+    /// Text as OCRed
+    pub original: String,
+    /// Canonical text it was replaced with
+    pub text: String,
+    pub similarity: f64,
+}
+
+#[derive(Debug)]
+struct Verse {
+    surah: u16,
+    ayah: u16,
+    imlaei: String,
+    uthmani: String,
+    /// Normalized words, for matching
+    words: Vec<String>,
+}
+
+/// Verse texts with a word-bigram index for finding quotations
+#[derive(Debug, Default)]
+pub struct QuranIndex {
+    verses: Vec<Verse>,
+    /// "word word" -> (verse, position of the first word)
+    bigrams: HashMap<String, Vec<(usize, usize)>>,
+}
+
+/// A word of the page: byte range of its Arabic core and its normalized form
+#[derive(Debug)]
+struct Token {
+    start: usize,
+    end: usize,
+    norm: String,
+}
+
+/// Best alignment of a verse against a run of page tokens
+#[derive(Debug)]
+struct Match {
+    verse: usize,
+    first: usize,
+    last: usize,
+    similarity: f64,
+}
+
+/// Arabic letters, harakat and Quranic annotation marks (not digits or punctuation)
+fn is_verse_char(c: char) -> bool {
+    matches!(c,
+        '\u{0610}'..='\u{061A}'
+        | '\u{0620}'..='\u{065F}'
+        | '\u{0670}'..='\u{06D3}'
+        | '\u{06D6}'..='\u{06ED}')
+}
+
+/// Base letters only, with the variant folding used by search
+fn normalize_word(word: &str) -> String {
+    normalize_for_search(word)
+        .chars()
+        .filter(|c| ('\u{0621}'..='\u{064A}').contains(c))
+        .collect()
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word_start = None;
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (c.is_whitespace(), word_start) {
+            (false, None) => word_start = Some(i),
+            (true, Some(start)) => {
+                word_start = None;
+                let word = &text[start..i];
+                let Some(core_start) = word.find(is_verse_char) else {
+                    continue;
+                };
+                let core_end = word
+                    .char_indices()
+                    .rev()
+                    .find(|(_, c)| is_verse_char(*c))
+                    .map(|(j, c)| j + c.len_utf8())
+                    .unwrap_or(word.len());
+                let norm = normalize_word(&word[core_start..core_end]);
+                if !norm.is_empty() {
+                    tokens.push(Token {
+                        start: start + core_start,
+                        end: start + core_end,
+                        norm,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+/// Parse Tanzil's `sura|aya|text` export, skipping the license comments
+fn parse_tanzil(content: &str) -> Vec<(u16, u16, String)> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '|');
+            let surah = parts.next()?.trim().parse().ok()?;
+            let ayah = parts.next()?.trim().parse().ok()?;
+            Some((surah, ayah, parts.next()?.trim().to_string()))
+        })
+        .collect()
+}
+
+impl QuranIndex {
+    /// Build the index from the Imlaei and Uthmani texts (Tanzil format). Verses
+    /// missing from the Uthmani text fall back to the Imlaei one.
+    pub fn from_tanzil(imlaei: &str, uthmani: &str) -> Self {
+        let uthmani: HashMap<(u16, u16), String> = parse_tanzil(uthmani)
+            .into_iter()
+            .map(|(surah, ayah, text)| ((surah, ayah), text))
+            .collect();
+
+        let mut index = QuranIndex::default();
+        for (surah, ayah, imlaei) in parse_tanzil(imlaei) {
+            let words: Vec<String> = imlaei
+                .split_whitespace()
+                .map(normalize_word)
+                .filter(|w| !w.is_empty())
+                .collect();
+            let id = index.verses.len();
+            if words.len() >= MIN_VERSE_WORDS {
+                for (position, pair) in words.windows(2).enumerate() {
+                    index
+                        .bigrams
+                        .entry(format!("{} {}", pair[0], pair[1]))
+                        .or_default()
+                        .push((id, position));
+                }
+            }
+            index.verses.push(Verse {
+                surah,
+                ayah,
+                uthmani: uthmani
+                    .get(&(surah, ayah))
+                    .cloned()
+                    .unwrap_or_else(|| imlaei.clone()),
+                imlaei,
+                words,
+            });
+        }
+        index
+    }
+
+    /// Align `verse` against the tokens around `offset`, trying nearby starts and
+    /// lengths. Spaces are ignored so split or merged words still line up.
+    fn align(&self, verse: usize, offset: isize, tokens: &[Token]) -> Option<Match> {
+        let target: Vec<char> = self.verses[verse].words.concat().chars().collect();
+        let length = self.verses[verse].words.len();
+        let mut best: Option<Match> = None;
+
+        for shift in -(WORD_SLACK as isize)..=WORD_SLACK as isize {
+            let Ok(first) = usize::try_from(offset + shift) else {
+                continue;
+            };
+            if first >= tokens.len() {
+                continue;
+            }
+            let last_max = (first + length + WORD_SLACK).min(tokens.len());
+
+            // Span characters and the character count at the end of each word
+            let mut span: Vec<char> = Vec::new();
+            let mut ends = Vec::new();
+            for token in &tokens[first..last_max] {
+                span.extend(token.norm.chars());
+                ends.push(span.len());
+            }
+
+            // Edit distance of the whole verse against every prefix of the span
+            let mut previous: Vec<usize> = (0..=span.len()).collect();
+            for (i, &t) in target.iter().enumerate() {
+                let mut current = vec![i + 1; span.len() + 1];
+                for (j, &s) in span.iter().enumerate() {
+                    let cost = usize::from(t != s);
+                    current[j + 1] = (previous[j] + cost)
+                        .min(previous[j + 1] + 1)
+                        .min(current[j] + 1);
+                }
+                previous = current;
+            }
+
+            let min_words = length.saturating_sub(WORD_SLACK).max(1);
+            for (words, &end) in ends.iter().enumerate().skip(min_words - 1) {
+                let similarity = 1.0 - previous[end] as f64 / target.len().max(end).max(1) as f64;
+                if best.as_ref().map_or(true, |b| similarity > b.similarity) {
+                    best = Some(Match {
+                        verse,
+                        first,
+                        last: first + words,
+                        similarity,
+                    });
+                }
+            }
+        }
+        best.filter(|m| m.similarity >= MIN_SIMILARITY)
+    }
+
+    /// Non-overlapping verse quotations in `tokens`, in text order
+    fn find(&self, tokens: &[Token]) -> Vec<Match> {
+        let mut votes: HashMap<(usize, isize), usize> = HashMap::new();
+        for (i, pair) in tokens.windows(2).enumerate() {
+            let key = format!("{} {}", pair[0].norm, pair[1].norm);
+            for &(verse, position) in self.bigrams.get(&key).into_iter().flatten() {
+                *votes
+                    .entry((verse, i as isize - position as isize))
+                    .or_default() += 1;
+            }
+        }
+
+        let mut matches: Vec<Match> = votes
+            .into_iter()
+            .filter(|&((verse, _), count)| {
+                // A short verse has few bigrams left once OCR garbles a word
+                let required = if self.verses[verse].words.len() <= 4 {
+                    1
+                } else {
+                    2
+                };
+                count >= required
+            })
+            .filter_map(|((verse, offset), _)| self.align(verse, offset, tokens))
+            .collect();
+
+        // Prefer long, close matches; drop anything overlapping a better one
+        matches.sort_by(|a, b| {
+            let weight = |m: &Match| m.similarity * (m.last - m.first + 1) as f64;
+            weight(b).total_cmp(&weight(a)).then(a.first.cmp(&b.first))
+        });
+        let mut kept: Vec<Match> = Vec::new();
+        for candidate in matches {
+            if kept
+                .iter()
+                .all(|k| candidate.last < k.first || candidate.first > k.last)
+            {
+                kept.push(candidate);
+            }
+        }
+        kept.sort_by_key(|m| m.first);
+        kept
+    }
+
+    /// Replace verse quotations on a page with the canonical text in `script`
+    pub fn substitute(
+        &self,
+        page: u32,
+        text: &str,
+        script: QuranScript,
+    ) -> (String, Vec<QuranSubstitution>) {
+        let tokens = tokenize(text);
+        let mut result = String::with_capacity(text.len());
+        let mut substitutions = Vec::new();
+        let mut copied = 0;
+
+        for m in self.find(&tokens) {
+            let verse = &self.verses[m.verse];
+            let (start, end) = (tokens[m.first].start, tokens[m.last].end);
+            let canonical = match script {
+                QuranScript::Uthmani => &verse.uthmani,
+                QuranScript::Imlaei => &verse.imlaei,
+            };
+            let original = &text[start..end];
+            if original == canonical {
+                continue;
+            }
+            result.push_str(&text[copied..start]);
+            result.push_str(canonical);
+            copied = end;
+            substitutions.push(QuranSubstitution {
+                page,
+                surah: verse.surah,
+                ayah: verse.ayah,
+                original: original.to_string(),
+                text: canonical.clone(),
+                similarity: m.similarity,
+            });
+        }
+        result.push_str(&text[copied..]);
+        (result, substitutions)
+    }
+}
+
+/// Remember where bundled resources live; the index itself is loaded on first use
+pub fn init(resource_dir: Option<PathBuf>) {
+    let _ = RESOURCE_DIR.set(resource_dir);
+}
+
+fn read_resource(name: &str) -> Option<String> {
+    let mut search_paths = Vec::new();
+    if let Some(Some(dir)) = RESOURCE_DIR.get() {
+        search_paths.push(dir.join(name));
+        search_paths.push(dir.join("resources").join(name));
+    }
+    // Development
+    search_paths.push(PathBuf::from("resources").join(name));
+    search_paths.push(PathBuf::from("src-tauri/resources").join(name));
+    search_paths
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+}
+
+/// The bundled Quran text index (see `scripts/download-quran.sh`), or `None`
+/// if it is not installed
+pub fn index() -> Option<&'static QuranIndex> {
+    INDEX
+        .get_or_init(|| {
+            let Some(imlaei) = read_resource(IMLAEI_FILE) else {
+                tracing::warn!("Quran text index not found");
+                return None;
+            };
+            let uthmani = read_resource(UTHMANI_FILE).unwrap_or_default();
+            let index = QuranIndex::from_tanzil(&imlaei, &uthmani);
+            tracing::info!(verses = index.verses.len(), "Loaded Quran text index");
+            Some(index)
+        })
+        .as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMLAEI: &str = "1|1|بِسْمِ اللَّهِ الرَّحْمَٰنِ الرَّحِيمِ
+1|2|الْحَمْدُ لِلَّهِ رَبِّ الْعَالَمِينَ
+1|3|الرَّحْمَٰنِ الرَّحِيمِ
+1|4|مَالِكِ يَوْمِ الدِّينِ
+1|5|إِيَّاكَ نَعْبُدُ وَإِيَّاكَ نَسْتَعِينُ
+1|6|اهْدِنَا الصِّرَاطَ الْمُسْتَقِيمَ
+1|7|صِرَاطَ الَّذِينَ أَنْعَمْتَ عَلَيْهِمْ غَيْرِ الْمَغْضُوبِ عَلَيْهِمْ وَلَا الضَّالِّينَ
+112|1|قُلْ هُوَ اللَّهُ أَحَدٌ
+
+# Tanzil license text
+";
+
+    const UTHMANI: &str = "1|2|ٱلْحَمْدُ لِلَّهِ رَبِّ ٱلْعَـٰلَمِينَ
+1|7|صِرَٰطَ ٱلَّذِينَ أَنْعَمْتَ عَلَيْهِمْ غَيْرِ ٱلْمَغْضُوبِ عَلَيْهِمْ وَلَا ٱلضَّآلِّينَ
+";
+
+    fn index() -> QuranIndex {
+        QuranIndex::from_tanzil(IMLAEI, UTHMANI)
+    }
+
+    #[test]
+    fn test_parse_tanzil_skips_comments() {
+        let verses = parse_tanzil(IMLAEI);
+        assert_eq!(verses.len(), 8);
+        assert_eq!(verses[7].0, 112);
+        assert_eq!(verses[7].1, 1);
+    }
+
+    #[test]
+    fn test_tokenize_keeps_brackets_and_numbers_outside() {
+        let text = "قال تعالى: ﴿الحمد لله﴾ (٢)";
+        let tokens = tokenize(text);
+        let words: Vec<&str> = tokens.iter().map(|t| &text[t.start..t.end]).collect();
+        assert_eq!(words, vec!["قال", "تعالى", "الحمد", "لله"]);
+    }
+
+    #[test]
+    fn test_substitutes_garbled_verse() {
+        let index = index();
+        let page = "قال تعالى: ﴿صراط الذين انعمت عليهم غير المغضوب عليهم ولا الضالبن﴾ وهذا دعاء";
+        let (text, substitutions) = index.substitute(3, page, QuranScript::Imlaei);
+
+        assert_eq!(substitutions.len(), 1);
+        let s = &substitutions[0];
+        assert_eq!((s.page, s.surah, s.ayah), (3, 1, 7));
+        assert!(s.similarity >= MIN_SIMILARITY && s.similarity < 1.0);
+        assert_eq!(
+            text,
+            "قال تعالى: ﴿صِرَاطَ الَّذِينَ أَنْعَمْتَ عَلَيْهِمْ غَيْرِ الْمَغْضُوبِ عَلَيْهِمْ وَلَا الضَّالِّينَ﴾ وهذا دعاء"
+        );
+    }
+
+    #[test]
+    fn test_uthmani_script_and_split_words() {
+        let index = index();
+        // OCR split "العالمين" in two
+        let page = "الحمد لله رب العا لمين\nثم بدأ الكلام";
+        let (text, substitutions) = index.substitute(1, page, QuranScript::Uthmani);
+        assert_eq!(substitutions.len(), 1);
+        assert_eq!(substitutions[0].ayah, 2);
+        assert_eq!(substitutions[0].original, "الحمد لله رب العا لمين");
+        assert_eq!(text, "ٱلْحَمْدُ لِلَّهِ رَبِّ ٱلْعَـٰلَمِينَ\nثم بدأ الكلام");
+    }
+
+    #[test]
+    fn test_uthmani_falls_back_to_imlaei() {
+        let index = index();
+        let (text, _) = index.substitute(1, "قل هو الله احد", QuranScript::Uthmani);
+        assert_eq!(text, "قُلْ هُوَ اللَّهُ أَحَدٌ");
+    }
+
+    #[test]
+    fn test_consecutive_verses() {
+        let index = index();
+        let page = "مالك يوم الدين (٤) اياك نعبد واياك نستعين (٥)";
+        let (text, substitutions) = index.substitute(1, page, QuranScript::Imlaei);
+        let ayahs: Vec<u16> = substitutions.iter().map(|s| s.ayah).collect();
+        assert_eq!(ayahs, vec![4, 5]);
+        assert_eq!(text, "مَالِكِ يَوْمِ الدِّينِ (٤) إِيَّاكَ نَعْبُدُ وَإِيَّاكَ نَسْتَعِينُ (٥)");
+    }
+
+    #[test]
+    fn test_prose_and_short_verses_are_left_alone() {
+        let index = index();
+        let page = "الرحمن الرحيم من أسماء الله، والحمد لله على كل حال";
+        let (text, substitutions) = index.substitute(1, page, QuranScript::Imlaei);
+        assert!(substitutions.is_empty());
+        assert_eq!(text, page);
+    }
+
+    #[test]
+    fn test_canonical_text_is_not_reported() {
+        let index = index();
+        let page = "مَالِكِ يَوْمِ الدِّينِ";
+        let (text, substitutions) = index.substitute(1, page, QuranScript::Imlaei);
+        assert!(substitutions.is_empty());
+        assert_eq!(text, page);
+    }
+}
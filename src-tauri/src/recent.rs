@@ -0,0 +1,383 @@
+use crate::app_paths::{self, Area};
+use crate::error::TahweelError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current version of the recent-files schema
+pub const RECENT_VERSION: u32 = 1;
+
+/// Unpinned entries kept; pinned entries are never dropped for space
+const MAX_UNPINNED: usize = 20;
+
+/// Serializes load-modify-save cycles on the recent-files file
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// An input file processed before
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecentFile {
+    pub path: String,
+    pub name: String,
+    /// Seconds since the Unix epoch
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: u64,
+    #[serde(default)]
+    pub pinned: bool,
+    /// Processing options of the last run, as sent by the frontend
+    #[serde(default)]
+    pub options: Value,
+    /// Output files of the last run that still exist
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    /// Whether the input still exists (only pinned entries can be missing)
+    #[serde(default = "default_exists")]
+    pub exists: bool,
+}
+
+fn default_exists() -> bool {
+    true
+}
+
+/// The persisted list, most recently used first
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecentFiles {
+    pub version: u32,
+    pub files: Vec<RecentFile>,
+}
+
+impl Default for RecentFiles {
+    fn default() -> Self {
+        Self {
+            version: RECENT_VERSION,
+            files: Vec::new(),
+        }
+    }
+}
+
+impl RecentFiles {
+    /// Move `path` to the top with the options and outputs of its latest run
+    pub fn record(&mut self, path: &str, options: Value, outputs: Vec<String>, now: u64) {
+        let pinned = self
+            .files
+            .iter()
+            .find(|f| f.path == path)
+            .map(|f| f.pinned)
+            .unwrap_or(false);
+        self.files.retain(|f| f.path != path);
+        self.files.insert(
+            0,
+            RecentFile {
+                path: path.to_string(),
+                name: Path::new(path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                last_used_at: now,
+                pinned,
+                options,
+                outputs,
+                exists: true,
+            },
+        );
+        self.trim();
+    }
+
+    /// Pin or unpin `path`; returns whether it was in the list
+    pub fn set_pinned(&mut self, path: &str, pinned: bool) -> bool {
+        let Some(file) = self.files.iter_mut().find(|f| f.path == path) else {
+            return false;
+        };
+        file.pinned = pinned;
+        self.trim();
+        true
+    }
+
+    /// Remove unpinned entries, or every entry when `include_pinned` is set
+    pub fn clear(&mut self, include_pinned: bool) {
+        self.files.retain(|f| f.pinned && !include_pinned);
+    }
+
+    /// Drop unpinned inputs that no longer exist, flag missing pinned ones, and
+    /// forget outputs that were deleted. Returns whether anything changed.
+    pub fn validate(&mut self) -> bool {
+        let before = self.files.clone();
+        self.files.retain_mut(|file| {
+            file.exists = Path::new(&file.path).is_file();
+            file.outputs.retain(|output| Path::new(output).is_file());
+            file.exists || file.pinned
+        });
+        self.files != before
+    }
+
+    /// Keep at most `MAX_UNPINNED` unpinned entries (the most recent ones)
+    fn trim(&mut self) {
+        let mut unpinned = 0;
+        self.files.retain(|f| {
+            if f.pinned {
+                return true;
+            }
+            unpinned += 1;
+            unpinned <= MAX_UNPINNED
+        });
+    }
+}
+
+/// Location of the recent-files list
+pub fn recent_path() -> PathBuf {
+    app_paths::scoped_dir(Area::Config).join("recent.json")
+}
+
+/// Load the list from `path`. A missing or unreadable file yields an empty list:
+/// losing history is preferable to failing the UI.
+pub fn load_from(path: &Path) -> RecentFiles {
+    let Ok(content) = fs::read_to_string(path) else {
+        return RecentFiles::default();
+    };
+    match serde_json::from_str::<RecentFiles>(&content) {
+        Ok(recent) if recent.version == RECENT_VERSION => recent,
+        Ok(recent) => {
+            tracing::warn!(
+                version = recent.version,
+                "Ignoring recent files of unknown version"
+            );
+            RecentFiles::default()
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Ignoring corrupt recent files list");
+            RecentFiles::default()
+        }
+    }
+}
+
+/// Write the list atomically (`app_paths::write_atomic`)
+pub fn save_to(path: &Path, recent: &RecentFiles) -> Result<(), TahweelError> {
+    let json = serde_json::to_string_pretty(recent)?;
+    app_paths::write_atomic(path, json.as_bytes())
+        .map_err(|e| TahweelError::Io(format!("Failed to write recent files: {}", e)))
+}
+
+/// Load, change and save the list under the lock
+fn update<T>(
+    path: &Path,
+    change: impl FnOnce(&mut RecentFiles) -> T,
+) -> Result<(T, RecentFiles), TahweelError> {
+    let _guard = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut recent = load_from(path);
+    let result = change(&mut recent);
+    save_to(path, &recent)?;
+    Ok((result, recent))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Recent inputs, most recent first, after dropping files that no longer exist
+#[tauri::command]
+pub async fn get_recent_files() -> Result<Vec<RecentFile>, TahweelError> {
+    let path = recent_path();
+    let _guard = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut recent = load_from(&path);
+    if recent.validate() {
+        save_to(&path, &recent)?;
+    }
+    Ok(recent.files)
+}
+
+/// Remember a processed input with the options and outputs of this run
+#[tauri::command]
+pub async fn record_recent_file(
+    path: String,
+    options: Option<Value>,
+    outputs: Option<Vec<String>>,
+) -> Result<(), TahweelError> {
+    if !Path::new(&path).is_file() {
+        return Err(TahweelError::NotFound(format!("File not found: {}", path)));
+    }
+    update(&recent_path(), |recent| {
+        recent.record(
+            &path,
+            options.unwrap_or(Value::Null),
+            outputs.unwrap_or_default(),
+            now(),
+        )
+    })?;
+    Ok(())
+}
+
+/// Pin (or unpin) a recent file so it is kept regardless of age
+#[tauri::command]
+pub async fn pin_recent_file(path: String, pinned: bool) -> Result<Vec<RecentFile>, TahweelError> {
+    let (found, recent) = update(&recent_path(), |recent| recent.set_pinned(&path, pinned))?;
+    if !found {
+        return Err(TahweelError::NotFound(format!(
+            "Not a recent file: {}",
+            path
+        )));
+    }
+    Ok(recent.files)
+}
+
+/// Forget recent files; pinned ones are kept unless `include_pinned` is set
+#[tauri::command]
+pub async fn clear_recent_files(
+    include_pinned: Option<bool>,
+) -> Result<Vec<RecentFile>, TahweelError> {
+    let (_, recent) = update(&recent_path(), |recent| {
+        recent.clear(include_pinned.unwrap_or(false))
+    })?;
+    Ok(recent.files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn paths(recent: &RecentFiles) -> Vec<&str> {
+        recent.files.iter().map(|f| f.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_record_moves_to_top_and_keeps_pin() {
+        let mut recent = RecentFiles::default();
+        recent.record("/a/one.pdf", json!({ "dpi": 150 }), vec![], 1);
+        recent.record("/a/two.pdf", Value::Null, vec![], 2);
+        recent.set_pinned("/a/one.pdf", true);
+        recent.record(
+            "/a/one.pdf",
+            json!({ "dpi": 300 }),
+            vec!["/a/one.txt".to_string()],
+            3,
+        );
+
+        assert_eq!(paths(&recent), vec!["/a/one.pdf", "/a/two.pdf"]);
+        let first = &recent.files[0];
+        assert_eq!(first.name, "one.pdf");
+        assert_eq!(first.last_used_at, 3);
+        assert!(first.pinned);
+        assert_eq!(first.options["dpi"], 300);
+        assert_eq!(first.outputs, vec!["/a/one.txt"]);
+    }
+
+    #[test]
+    fn test_trim_keeps_pinned_entries() {
+        let mut recent = RecentFiles::default();
+        recent.record("/pinned.pdf", Value::Null, vec![], 0);
+        recent.set_pinned("/pinned.pdf", true);
+        for i in 0..(MAX_UNPINNED + 5) {
+            recent.record(&format!("/f{}.pdf", i), Value::Null, vec![], i as u64);
+        }
+
+        assert_eq!(recent.files.len(), MAX_UNPINNED + 1);
+        assert!(recent.files.iter().any(|f| f.path == "/pinned.pdf"));
+        assert_eq!(recent.files[0].path, format!("/f{}.pdf", MAX_UNPINNED + 4));
+        assert!(!recent.files.iter().any(|f| f.path == "/f0.pdf"));
+    }
+
+    #[test]
+    fn test_set_pinned_unknown_path() {
+        let mut recent = RecentFiles::default();
+        assert!(!recent.set_pinned("/missing.pdf", true));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut recent = RecentFiles::default();
+        recent.record("/a.pdf", Value::Null, vec![], 1);
+        recent.record("/b.pdf", Value::Null, vec![], 2);
+        recent.set_pinned("/a.pdf", true);
+
+        recent.clear(false);
+        assert_eq!(paths(&recent), vec!["/a.pdf"]);
+        recent.clear(true);
+        assert!(recent.files.is_empty());
+    }
+
+    #[test]
+    fn test_validate_drops_missing_files() {
+        let dir = tempdir().unwrap();
+        let kept = dir.path().join("kept.pdf");
+        let output = dir.path().join("kept.txt");
+        fs::write(&kept, b"%PDF").unwrap();
+        fs::write(&output, b"text").unwrap();
+        let kept = kept.to_string_lossy().to_string();
+        let output = output.to_string_lossy().to_string();
+        let gone = dir.path().join("gone.pdf").to_string_lossy().to_string();
+        let gone_pinned = dir.path().join("pinned.pdf").to_string_lossy().to_string();
+
+        let mut recent = RecentFiles::default();
+        recent.record(&gone_pinned, Value::Null, vec![], 1);
+        recent.set_pinned(&gone_pinned, true);
+        recent.record(&gone, Value::Null, vec![], 2);
+        recent.record(
+            &kept,
+            Value::Null,
+            vec![output.clone(), "/deleted/out.docx".to_string()],
+            3,
+        );
+
+        assert!(recent.validate());
+        assert_eq!(paths(&recent), vec![kept.as_str(), gone_pinned.as_str()]);
+        assert!(recent.files[0].exists);
+        assert_eq!(recent.files[0].outputs, vec![output]);
+        assert!(!recent.files[1].exists);
+        assert!(!recent.validate());
+    }
+
+    #[test]
+    fn test_failed_save_is_an_io_error() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("not-a-folder");
+        fs::write(&file, b"").unwrap();
+
+        let error = save_to(&file.join("recent.json"), &RecentFiles::default()).unwrap_err();
+        assert_eq!(error.kind(), "io");
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tahweel").join("recent.json");
+        let mut recent = RecentFiles::default();
+        recent.record("/a.pdf", json!({ "formats": ["txt"] }), vec![], 5);
+
+        save_to(&path, &recent).unwrap();
+        assert_eq!(load_from(&path), recent);
+
+        let json: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["files"][0]["lastUsedAt"], 5);
+    }
+
+    #[test]
+    fn test_load_from_missing_or_corrupt_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("recent.json");
+        assert_eq!(load_from(&path), RecentFiles::default());
+
+        fs::write(&path, "{not json").unwrap();
+        assert_eq!(load_from(&path), RecentFiles::default());
+
+        fs::write(&path, r#"{"version": 99, "files": []}"#).unwrap();
+        assert_eq!(load_from(&path), RecentFiles::default());
+    }
+
+    #[test]
+    fn test_update_persists_changes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("recent.json");
+
+        let (_, recent) = update(&path, |r| r.record("/a.pdf", Value::Null, vec![], 1)).unwrap();
+        assert_eq!(paths(&recent), vec!["/a.pdf"]);
+        let (found, _) = update(&path, |r| r.set_pinned("/a.pdf", true)).unwrap();
+        assert!(found);
+        assert!(load_from(&path).files[0].pinned);
+    }
+}
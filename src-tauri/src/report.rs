@@ -0,0 +1,441 @@
+use crate::confidence::{self, SecondPassText};
+use crate::drive_safe::DownscaledPage;
+use crate::duplicates::DuplicatePage;
+use crate::job_channels;
+use crate::logging;
+use crate::output::{is_arabic_char, DocumentMetadata};
+use crate::pdf::DegradedPage;
+use crate::storage::StoredObject;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::{AppHandle, Emitter};
+
+/// Pages with a larger share of unexpected characters are flagged for proofreading
+const GARBAGE_RATIO_THRESHOLD: f64 = 0.3;
+/// Non-empty pages with fewer words than this are flagged for proofreading
+const MIN_WORDS_PER_PAGE: u32 = 5;
+/// Copy of the most recent report kept in the log dir for diagnostics bundles
+pub(crate) const LAST_REPORT_FILE: &str = "last-job-report.json";
+
+/// Why a page was flagged as likely bad OCR
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QualityFlag {
+    Empty,
+    HighGarbageRatio,
+    FewWords,
+    /// The two passes of the `dualPassOcr` quality mode disagree
+    LowAgreement,
+    /// The page only rendered on a retry, usually at a lower DPI
+    DegradedRender,
+    /// The page's text was copied from a near-identical earlier page without OCR
+    NearDuplicate,
+}
+
+/// Statistics for a single page
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PageStats {
+    pub page: u32,
+    pub words: u32,
+    pub characters: u32,
+    /// Share of non-whitespace characters that are neither Arabic, digits, nor punctuation
+    #[serde(rename = "garbageRatio")]
+    pub garbage_ratio: f64,
+    /// Word agreement with the second OCR pass, for pages that had one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agreement: Option<f64>,
+    pub flags: Vec<QualityFlag>,
+}
+
+/// Quality report for a whole job, emitted as `job-report` and saved as JSON
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobReport {
+    /// Job the report belongs to, when `generate_job_report` was given one
+    #[serde(rename = "jobId", default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(rename = "pageCount")]
+    pub page_count: u32,
+    #[serde(rename = "totalWords")]
+    pub total_words: u32,
+    #[serde(rename = "totalCharacters")]
+    pub total_characters: u32,
+    #[serde(rename = "emptyPages")]
+    pub empty_pages: u32,
+    #[serde(rename = "garbageRatio")]
+    pub garbage_ratio: f64,
+    /// 1-based numbers of pages that should be proofread
+    #[serde(rename = "flaggedPages")]
+    pub flagged_pages: Vec<u32>,
+    pub pages: Vec<PageStats>,
+    /// Output files uploaded to S3-compatible storage, with their object URLs
+    #[serde(
+        rename = "storedObjects",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub stored_objects: Vec<StoredObject>,
+    /// Pages `split_pdf` had to render again after a failure
+    #[serde(
+        rename = "degradedPages",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub degraded_pages: Vec<DegradedPage>,
+    /// Pages scaled down to fit Drive's image limits (`driveSafeImages` setting)
+    #[serde(
+        rename = "downscaledPages",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub downscaled_pages: Vec<DownscaledPage>,
+    /// Pages that repeat an earlier page, and whether their OCR was skipped
+    #[serde(
+        rename = "duplicatePages",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub duplicate_pages: Vec<DuplicatePage>,
+    /// Title, author and subject of the source, as written to the outputs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<DocumentMetadata>,
+}
+
+/// Characters expected in Arabic OCR output besides Arabic letters
+fn is_expected_char(c: char) -> bool {
+    is_arabic_char(c)
+        || c.is_ascii_digit()
+        || c.is_ascii_punctuation()
+        || matches!(
+            c,
+            '«' | '»' | '–' | '—' | '…' | '“' | '”' | '‘' | '’' | '\u{200C}' | '\u{200D}'
+        )
+}
+
+/// Compute statistics and quality flags for one page of text
+pub fn page_stats(page: u32, text: &str) -> PageStats {
+    let words = text.split_whitespace().count() as u32;
+    let non_whitespace: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let characters = non_whitespace.len() as u32;
+    let garbage = non_whitespace
+        .iter()
+        .filter(|&&c| !is_expected_char(c))
+        .count();
+    let garbage_ratio = if characters == 0 {
+        0.0
+    } else {
+        garbage as f64 / characters as f64
+    };
+
+    let mut flags = Vec::new();
+    if characters == 0 {
+        flags.push(QualityFlag::Empty);
+    } else {
+        if garbage_ratio > GARBAGE_RATIO_THRESHOLD {
+            flags.push(QualityFlag::HighGarbageRatio);
+        }
+        if words < MIN_WORDS_PER_PAGE {
+            flags.push(QualityFlag::FewWords);
+        }
+    }
+
+    PageStats {
+        page,
+        words,
+        characters,
+        garbage_ratio,
+        agreement: None,
+        flags,
+    }
+}
+
+/// Build the job report from the final page texts (in page order). Pages in
+/// `second_pass` are compared with their first pass and flagged on low agreement;
+/// pages in `degraded` are flagged as degraded renders.
+pub fn build_job_report(
+    pages: &[String],
+    second_pass: &[SecondPassText],
+    degraded: &[DegradedPage],
+    duplicates: &[DuplicatePage],
+) -> JobReport {
+    let stats: Vec<PageStats> = pages
+        .iter()
+        .enumerate()
+        .map(|(i, text)| {
+            let mut stats = page_stats(i as u32 + 1, text);
+            if let Some(second) = second_pass.iter().find(|p| p.page == stats.page) {
+                let agreement = confidence::agreement(text, &second.text);
+                stats.agreement = Some(agreement);
+                if agreement < confidence::LOW_AGREEMENT_THRESHOLD {
+                    stats.flags.push(QualityFlag::LowAgreement);
+                }
+            }
+            if degraded.iter().any(|p| p.page == stats.page) {
+                stats.flags.push(QualityFlag::DegradedRender);
+            }
+            if duplicates
+                .iter()
+                .any(|p| p.page == stats.page && p.skipped && !p.exact)
+            {
+                stats.flags.push(QualityFlag::NearDuplicate);
+            }
+            stats
+        })
+        .collect();
+
+    let total_characters: u32 = stats.iter().map(|s| s.characters).sum();
+    let total_garbage: f64 = stats
+        .iter()
+        .map(|s| s.garbage_ratio * s.characters as f64)
+        .sum();
+
+    JobReport {
+        job_id: None,
+        page_count: stats.len() as u32,
+        total_words: stats.iter().map(|s| s.words).sum(),
+        total_characters,
+        empty_pages: stats
+            .iter()
+            .filter(|s| s.flags.contains(&QualityFlag::Empty))
+            .count() as u32,
+        garbage_ratio: if total_characters == 0 {
+            0.0
+        } else {
+            total_garbage / total_characters as f64
+        },
+        flagged_pages: stats
+            .iter()
+            .filter(|s| !s.flags.is_empty())
+            .map(|s| s.page)
+            .collect(),
+        pages: stats,
+        stored_objects: Vec::new(),
+        degraded_pages: degraded.to_vec(),
+        downscaled_pages: Vec::new(),
+        duplicate_pages: duplicates.to_vec(),
+        metadata: None,
+    }
+}
+
+/// Compute the quality report, save it to `<output_path>.report.json`,
+/// and emit it as a `job-report` event (also sent to the subscribers of
+/// `job_id`, see `job_channels`). `second_pass` holds the texts of pages
+/// OCRed twice in the `dualPassOcr` quality mode; `stored_objects` the outputs
+/// uploaded by `upload_outputs_to_storage`; `degraded_pages` the pages `split_pdf`
+/// rendered on a retry; `downscaled_pages` the pages it scaled down to Drive's
+/// limits; `metadata` the source's title, author and subject.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_job_report(
+    output_path: String,
+    pages: Vec<String>,
+    second_pass: Option<Vec<SecondPassText>>,
+    stored_objects: Option<Vec<StoredObject>>,
+    degraded_pages: Option<Vec<DegradedPage>>,
+    downscaled_pages: Option<Vec<DownscaledPage>>,
+    duplicate_pages: Option<Vec<DuplicatePage>>,
+    metadata: Option<DocumentMetadata>,
+    job_id: Option<String>,
+    app: AppHandle,
+) -> Result<JobReport, String> {
+    let report = JobReport {
+        job_id,
+        stored_objects: stored_objects.unwrap_or_default(),
+        downscaled_pages: downscaled_pages.unwrap_or_default(),
+        metadata: metadata
+            .map(|metadata| metadata.normalized())
+            .filter(|metadata| !metadata.is_empty()),
+        ..build_job_report(
+            &pages,
+            &second_pass.unwrap_or_default(),
+            &degraded_pages.unwrap_or_default(),
+            &duplicate_pages.unwrap_or_default(),
+        )
+    };
+
+    let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    fs::write(format!("{}.report.json", output_path), &json)
+        .map_err(|e| format!("Failed to write job report: {}", e))?;
+    if let Some(dir) = logging::log_dir() {
+        let _ = fs::write(dir.join(LAST_REPORT_FILE), &json);
+    }
+
+    if let Some(job_id) = report.job_id.as_deref() {
+        job_channels::send(job_id, "job-report", &report);
+    }
+    let _ = app.emit("job-report", &report);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_stats_clean_arabic_page() {
+        let stats = page_stats(1, "بسم الله الرحمن الرحيم، الحمد لله رب العالمين (1)");
+        assert_eq!(stats.words, 9);
+        assert_eq!(stats.garbage_ratio, 0.0);
+        assert!(stats.flags.is_empty());
+    }
+
+    #[test]
+    fn test_page_stats_empty_page() {
+        let stats = page_stats(3, "  \n\n ");
+        assert_eq!(stats.words, 0);
+        assert_eq!(stats.characters, 0);
+        assert_eq!(stats.flags, vec![QualityFlag::Empty]);
+    }
+
+    #[test]
+    fn test_page_stats_garbage_page() {
+        let stats = page_stats(2, "ﾟ∂ ¶§ xx ©® qq lll ¤¤ ب");
+        assert!(stats.garbage_ratio > GARBAGE_RATIO_THRESHOLD);
+        assert!(stats.flags.contains(&QualityFlag::HighGarbageRatio));
+        assert!(!stats.flags.contains(&QualityFlag::FewWords));
+    }
+
+    #[test]
+    fn test_page_stats_few_words() {
+        let stats = page_stats(1, "الفصل الأول");
+        assert_eq!(stats.flags, vec![QualityFlag::FewWords]);
+    }
+
+    #[test]
+    fn test_build_job_report_totals() {
+        let report = build_job_report(
+            &[
+                "هذا نص سليم من خمس كلمات".to_string(),
+                String::new(),
+                "abc".to_string(),
+            ],
+            &[],
+            &[],
+            &[],
+        );
+
+        assert_eq!(report.page_count, 3);
+        assert_eq!(report.total_words, 7);
+        assert_eq!(report.empty_pages, 1);
+        assert_eq!(report.flagged_pages, vec![2, 3]);
+        assert!(report.garbage_ratio > 0.0 && report.garbage_ratio < 1.0);
+    }
+
+    #[test]
+    fn test_job_report_serializes_camel_case() {
+        let report = build_job_report(&[String::new()], &[], &[], &[]);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["emptyPages"], 1);
+        assert_eq!(json["flaggedPages"][0], 1);
+        assert_eq!(json["pages"][0]["flags"][0], "empty");
+        assert!(json["pages"][0].get("agreement").is_none());
+        assert!(json.get("storedObjects").is_none());
+        assert!(json.get("degradedPages").is_none());
+        assert!(json.get("duplicatePages").is_none());
+        assert!(json.get("metadata").is_none());
+
+        let report = JobReport {
+            stored_objects: vec![StoredObject {
+                path: "/out/book.txt".to_string(),
+                key: "ocr/book.txt".to_string(),
+                url: "http://minio.local:9000/books/ocr/book.txt".to_string(),
+            }],
+            ..report
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["storedObjects"][0]["key"], "ocr/book.txt");
+
+        let report = JobReport {
+            metadata: Some(DocumentMetadata {
+                title: Some("الرسالة".to_string()),
+                ..Default::default()
+            }),
+            ..report
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["metadata"], serde_json::json!({"title": "الرسالة"}));
+    }
+
+    #[test]
+    fn test_build_job_report_flags_low_agreement() {
+        let clean = "بسم الله الرحمن الرحيم، الحمد لله رب العالمين";
+        let report = build_job_report(
+            &[clean.to_string(), clean.to_string(), clean.to_string()],
+            &[
+                SecondPassText {
+                    page: 1,
+                    text: clean.to_string(),
+                },
+                SecondPassText {
+                    page: 3,
+                    text: "بسم الله الرحمان الرحيم".to_string(),
+                },
+            ],
+            &[],
+            &[],
+        );
+
+        assert_eq!(report.pages[0].agreement, Some(1.0));
+        assert!(report.pages[0].flags.is_empty());
+        assert_eq!(report.pages[1].agreement, None);
+        assert!(report.pages[2].agreement.unwrap() < confidence::LOW_AGREEMENT_THRESHOLD);
+        assert_eq!(report.pages[2].flags, vec![QualityFlag::LowAgreement]);
+        assert_eq!(report.flagged_pages, vec![3]);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["pages"][2]["flags"][0], "lowAgreement");
+    }
+
+    #[test]
+    fn test_build_job_report_flags_degraded_renders() {
+        let clean = "بسم الله الرحمن الرحيم، الحمد لله رب العالمين";
+        let degraded = DegradedPage {
+            page: 2,
+            dpi: 100,
+            error: "Failed to render page 2".to_string(),
+        };
+        let report = build_job_report(
+            &[clean.to_string(), clean.to_string()],
+            &[],
+            std::slice::from_ref(&degraded),
+            &[],
+        );
+
+        assert!(report.pages[0].flags.is_empty());
+        assert_eq!(report.pages[1].flags, vec![QualityFlag::DegradedRender]);
+        assert_eq!(report.flagged_pages, vec![2]);
+        assert_eq!(report.degraded_pages, vec![degraded]);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["pages"][1]["flags"][0], "degradedRender");
+        assert_eq!(json["degradedPages"][0]["dpi"], 100);
+    }
+
+    #[test]
+    fn test_build_job_report_records_duplicates() {
+        let clean = "بسم الله الرحمن الرحيم، الحمد لله رب العالمين";
+        let duplicates = vec![
+            DuplicatePage {
+                page: 2,
+                duplicate_of: 1,
+                exact: true,
+                skipped: true,
+            },
+            DuplicatePage {
+                page: 3,
+                duplicate_of: 1,
+                exact: false,
+                skipped: true,
+            },
+        ];
+        let report = build_job_report(&vec![clean.to_string(); 3], &[], &[], &duplicates);
+
+        assert!(report.pages[1].flags.is_empty());
+        assert_eq!(report.pages[2].flags, vec![QualityFlag::NearDuplicate]);
+        assert_eq!(report.flagged_pages, vec![3]);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["duplicatePages"][0]["duplicateOf"], 1);
+        assert_eq!(json["duplicatePages"][1]["exact"], false);
+        assert_eq!(json["pages"][2]["flags"][0], "nearDuplicate");
+    }
+}
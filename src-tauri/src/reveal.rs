@@ -0,0 +1,176 @@
+use crate::error::TahweelError;
+use crate::path_policy::{self, PathAccess};
+use std::path::Path;
+use std::process::Command;
+
+/// Command that shows `path` selected in the file manager of `os`
+/// (a `std::env::consts::OS` value). `None` where no selecting file manager is known.
+pub fn reveal_command(path: &Path, os: &str) -> Option<Command> {
+    match os {
+        "windows" => {
+            let mut command = Command::new("explorer");
+            #[cfg(windows)]
+            {
+                // Explorer parses `/select,` itself and rejects the quoting `arg` would add
+                use std::os::windows::process::CommandExt;
+                command.raw_arg(format!("/select,\"{}\"", path.display()));
+            }
+            #[cfg(not(windows))]
+            command.arg(format!("/select,{}", path.display()));
+            Some(command)
+        }
+        "macos" => {
+            let mut command = Command::new("open");
+            command.arg("-R").arg(path);
+            Some(command)
+        }
+        "linux" | "freebsd" | "openbsd" | "netbsd" | "dragonfly" => {
+            // Nautilus, Dolphin, Nemo, Caja, and Thunar implement the FileManager1 D-Bus interface
+            let uri = url::Url::from_file_path(path).ok()?;
+            let mut command = Command::new("dbus-send");
+            command.args([
+                "--session",
+                "--print-reply",
+                "--dest=org.freedesktop.FileManager1",
+                "--type=method_call",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+            ]);
+            command.arg(format!("array:string:{}", uri));
+            command.arg("string:");
+            Some(command)
+        }
+        _ => None,
+    }
+}
+
+/// Run the reveal command; returns false if it could not be started or reported failure
+fn run_reveal(path: &Path) -> bool {
+    let Some(mut command) = reveal_command(path, std::env::consts::OS) else {
+        return false;
+    };
+    if cfg!(windows) {
+        // Explorer exits with 1 even when it succeeds
+        return command.spawn().is_ok();
+    }
+    command.status().map(|s| s.success()).unwrap_or(false)
+}
+
+/// Open the file manager with `path` selected. Falls back to opening the
+/// containing folder when the platform has no way to select an item.
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn reveal_in_folder(path: String) -> Result<(), TahweelError> {
+    let path = path_policy::check(Path::new(&path), PathAccess::Open)?;
+    if !path.exists() {
+        return Err(TahweelError::NotFound(format!(
+            "File not found: {}",
+            path.display()
+        )));
+    }
+
+    if run_reveal(&path) {
+        return Ok(());
+    }
+    let folder = if path.is_dir() {
+        path.as_path()
+    } else {
+        path.parent().unwrap_or(&path)
+    };
+    open::that(folder).map_err(|e| TahweelError::Io(format!("Failed to open folder: {}", e)))
+}
+
+/// Open a file (e.g. a DOCX output) in the default app for its type
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn open_file(path: String) -> Result<(), TahweelError> {
+    let path = path_policy::check(Path::new(&path), PathAccess::Open)?;
+    if !path.exists() {
+        return Err(TahweelError::NotFound(format!(
+            "File not found: {}",
+            path.display()
+        )));
+    }
+    if !path.is_file() {
+        return Err(TahweelError::InvalidInput(format!(
+            "Not a file: {}",
+            path.display()
+        )));
+    }
+    open::that(&path).map_err(|e| TahweelError::Io(format!("Failed to open file: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn args(command: &Command) -> Vec<String> {
+        command
+            .get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_reveal_command_macos() {
+        let command = reveal_command(Path::new("/Users/a/out/book.docx"), "macos").unwrap();
+        assert_eq!(command.get_program(), OsStr::new("open"));
+        assert_eq!(args(&command), vec!["-R", "/Users/a/out/book.docx"]);
+    }
+
+    #[test]
+    fn test_reveal_command_linux_uses_file_uri() {
+        let command = reveal_command(Path::new("/home/a/my books/book.docx"), "linux").unwrap();
+        assert_eq!(command.get_program(), OsStr::new("dbus-send"));
+        let args = args(&command);
+        assert!(args.contains(&"org.freedesktop.FileManager1.ShowItems".to_string()));
+        assert!(args.contains(&"array:string:file:///home/a/my%20books/book.docx".to_string()));
+        assert_eq!(args.last().unwrap(), "string:");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_reveal_command_windows() {
+        let command = reveal_command(Path::new("/out/book.docx"), "windows").unwrap();
+        assert_eq!(command.get_program(), OsStr::new("explorer"));
+        assert_eq!(args(&command), vec!["/select,/out/book.docx"]);
+    }
+
+    #[test]
+    fn test_reveal_command_unknown_os() {
+        assert!(reveal_command(Path::new("/out/book.docx"), "android").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_open_file_rejects_directory() {
+        let dir = tempdir().unwrap();
+        let result = open_file(dir.path().to_string_lossy().to_string()).await;
+        assert_eq!(result.unwrap_err().kind(), "invalidInput");
+    }
+
+    #[tokio::test]
+    async fn test_open_file_missing() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("missing.docx");
+        let result = open_file(path.to_string_lossy().to_string()).await;
+        assert_eq!(result.unwrap_err().kind(), "notFound");
+    }
+
+    #[tokio::test]
+    async fn test_reveal_in_folder_outside_allowed_paths() {
+        let result = reveal_in_folder("/nonexistent/out/book.docx".to_string()).await;
+        assert_eq!(result.unwrap_err().kind(), "pathNotAllowed");
+    }
+
+    #[tokio::test]
+    async fn test_reveal_in_folder_missing_file() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("out")).unwrap();
+        let path = dir.path().join("out").join("book.docx");
+        let result = reveal_in_folder(path.to_string_lossy().to_string()).await;
+        assert_eq!(result.unwrap_err().kind(), "notFound");
+    }
+}
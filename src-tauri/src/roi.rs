@@ -0,0 +1,115 @@
+use crate::encryption;
+use crate::error::TahweelError;
+use crate::masking::MaskRegion;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A named region of a form page; only these regions are OCRed when any are set.
+/// The area uses the same percent geometry as mask regions.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoiRegion {
+    pub label: String,
+    #[serde(flatten)]
+    pub area: MaskRegion,
+}
+
+/// A region cropped out of a page image
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct RegionCrop {
+    pub label: String,
+    pub path: String,
+}
+
+/// Crop the regions that apply to `page` out of a rendered page image into
+/// `page-0001-roi-1.png`, ... next to it (numbered by position in `regions`),
+/// in the order of `regions`. Regions outside the image are left out.
+#[tauri::command]
+pub async fn crop_page_regions(
+    image_path: String,
+    page: u32,
+    regions: Vec<RoiRegion>,
+) -> Result<Vec<RegionCrop>, TahweelError> {
+    let path = Path::new(&image_path);
+    let (image, key) = encryption::open_image(path)?;
+    let (width, height) = (image.width(), image.height());
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut crops = Vec::new();
+    for (index, region) in regions.iter().enumerate() {
+        if !region.area.applies_to(page) {
+            continue;
+        }
+        let Some((left, top, right, bottom)) = region.area.pixel_bounds(width, height) else {
+            continue;
+        };
+        let crop_path = parent.join(format!("{}-roi-{}.png", stem, index + 1));
+        encryption::save_png(
+            &image.crop_imm(left, top, right - left, bottom - top),
+            &crop_path,
+            key,
+        )?;
+        crops.push(RegionCrop {
+            label: region.label.clone(),
+            path: crop_path.to_string_lossy().to_string(),
+        });
+    }
+    Ok(crops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+    use tempfile::tempdir;
+
+    fn region(label: &str, page: Option<u32>, x: f64, width: f64) -> RoiRegion {
+        RoiRegion {
+            label: label.to_string(),
+            area: MaskRegion {
+                page,
+                x,
+                y: 0.0,
+                width,
+                height: 50.0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_crop_page_regions() {
+        let temp = tempdir().unwrap();
+        let page = temp.path().join("page-0001.png");
+        RgbImage::new(40, 20).save(&page).unwrap();
+        let regions = vec![
+            region("name", None, 50.0, 50.0),
+            region("date", Some(2), 0.0, 25.0),
+            region("id", Some(1), 0.0, 25.0),
+            region("outside", None, 100.0, 10.0),
+        ];
+
+        let crops = crop_page_regions(page.to_string_lossy().to_string(), 1, regions)
+            .await
+            .unwrap();
+        let labels: Vec<&str> = crops.iter().map(|c| c.label.as_str()).collect();
+        assert_eq!(labels, vec!["name", "id"]);
+        assert!(crops[0].path.ends_with("page-0001-roi-1.png"));
+        assert!(crops[1].path.ends_with("page-0001-roi-3.png"));
+        let name = image::open(&crops[0].path).unwrap();
+        assert_eq!((name.width(), name.height()), (20, 10));
+    }
+
+    #[test]
+    fn test_region_deserialization() {
+        let region: RoiRegion = serde_json::from_value(serde_json::json!({
+            "label": "الاسم", "page": 1, "x": 10, "y": 20, "width": 30, "height": 5
+        }))
+        .unwrap();
+        assert_eq!(region.label, "الاسم");
+        assert_eq!(region.area.page, Some(1));
+        assert_eq!(region.area.height, 5.0);
+    }
+}
@@ -0,0 +1,512 @@
+use crate::error::TahweelError;
+use crate::long_path;
+use crate::output;
+use crate::path_policy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const SCAN_DPI_MIN: u32 = 75;
+const SCAN_DPI_MAX: u32 = 1200;
+/// Scanned pages are written as `page-0001.png`, `page-0002.png`, ...
+const PAGE_PREFIX: &str = "page-";
+
+/// Lists WIA scanners as `id<TAB>name` lines
+const WIA_LIST_SCRIPT: &str = r#"
+$manager = New-Object -ComObject WIA.DeviceManager
+foreach ($info in $manager.DeviceInfos) {
+  if ($info.Type -eq 1) { "{0}`t{1}" -f $info.DeviceID, $info.Properties.Item('Name').Value }
+}
+"#;
+
+/// Scans with WIA into `TAHWEEL_SCAN_DIR`; parameters come from the environment
+/// so device IDs never pass through the script text
+const WIA_SCAN_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+$manager = New-Object -ComObject WIA.DeviceManager
+$info = $manager.DeviceInfos | Where-Object { $_.DeviceID -eq $env:TAHWEEL_SCAN_DEVICE } | Select-Object -First 1
+if (-not $info) { Write-Error 'Scanner not found'; exit 2 }
+$device = $info.Connect()
+if ($env:TAHWEEL_SCAN_FEEDER -eq '1') { $device.Properties.Item('3088').Value = 1 }
+$item = $device.Items.Item(1)
+$item.Properties.Item('6147').Value = [int]$env:TAHWEEL_SCAN_DPI
+$item.Properties.Item('6148').Value = [int]$env:TAHWEEL_SCAN_DPI
+$item.Properties.Item('6146').Value = [int]$env:TAHWEEL_SCAN_INTENT
+$max = [int]$env:TAHWEEL_SCAN_MAX_PAGES
+$page = 0
+do {
+  try { $image = $item.Transfer('{B96B3CAF-0728-11D3-9D7B-0000F81EF32E}') }
+  catch { if ($page -gt 0) { break } else { throw } }
+  $page++
+  $image.SaveFile((Join-Path $env:TAHWEEL_SCAN_DIR ('page-{0:D4}.png' -f $page)))
+} while ($env:TAHWEEL_SCAN_FEEDER -eq '1' -and ($max -eq 0 -or $page -lt $max))
+"#;
+
+/// Platform scanning interface
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ScannerBackend {
+    /// SANE's `scanimage` (Linux, BSD, and macOS with Homebrew's sane-backends)
+    Sane,
+    /// Windows Image Acquisition through PowerShell
+    Wia,
+}
+
+/// A connected scanner
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Scanner {
+    /// Device name (SANE) or device ID (WIA), passed back to `scan_pages`
+    pub id: String,
+    pub name: String,
+    pub backend: ScannerBackend,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ColorMode {
+    Color,
+    Gray,
+    Lineart,
+}
+
+/// Acquisition options for `scan_pages`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ScanSettings {
+    pub dpi: u32,
+    #[serde(rename = "colorMode")]
+    pub color_mode: ColorMode,
+    /// Scan every sheet in the document feeder instead of one page from the flatbed
+    pub feeder: bool,
+    /// Most pages to take from the feeder; 0 scans until it is empty
+    #[serde(rename = "maxPages")]
+    pub max_pages: u32,
+}
+
+impl Default for ScanSettings {
+    fn default() -> Self {
+        Self {
+            dpi: 300,
+            color_mode: ColorMode::Gray,
+            feeder: false,
+            max_pages: 0,
+        }
+    }
+}
+
+impl ScanSettings {
+    pub fn validated(mut self) -> Self {
+        self.dpi = self.dpi.clamp(SCAN_DPI_MIN, SCAN_DPI_MAX);
+        self
+    }
+}
+
+/// Pages acquired by `scan_pages`, in order
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ScanResult {
+    /// Name for the job's outputs
+    pub name: String,
+    /// Workspace folder holding the pages (delete it when done)
+    pub dir: String,
+    pub pages: Vec<String>,
+    #[serde(rename = "outputDirectory")]
+    pub output_directory: String,
+}
+
+/// Scanning interface for `os` (a `std::env::consts::OS` value)
+pub fn backend_for_os(os: &str) -> Option<ScannerBackend> {
+    match os {
+        "windows" => Some(ScannerBackend::Wia),
+        "linux" | "macos" | "freebsd" | "openbsd" | "netbsd" | "dragonfly" => {
+            Some(ScannerBackend::Sane)
+        }
+        _ => None,
+    }
+}
+
+fn powershell(script: &str) -> Command {
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", script]);
+    command
+}
+
+/// Command that lists scanners as `id<TAB>name` lines
+pub fn list_command(backend: ScannerBackend) -> Command {
+    match backend {
+        ScannerBackend::Sane => {
+            let mut command = Command::new("scanimage");
+            command.arg("--formatted-device-list=%d\t%v %m%n");
+            command
+        }
+        ScannerBackend::Wia => powershell(WIA_LIST_SCRIPT),
+    }
+}
+
+/// Parse `id<TAB>name` lines into scanners
+pub fn parse_device_list(output: &str, backend: ScannerBackend) -> Vec<Scanner> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (id, name) = line.split_once('\t')?;
+            let id = id.trim();
+            if id.is_empty() {
+                return None;
+            }
+            let name = name.trim();
+            Some(Scanner {
+                id: id.to_string(),
+                name: if name.is_empty() { id } else { name }.to_string(),
+                backend,
+            })
+        })
+        .collect()
+}
+
+/// Command that scans pages from `device` into `dir` as `page-NNNN.png`
+pub fn scan_command(
+    backend: ScannerBackend,
+    device: &str,
+    settings: &ScanSettings,
+    dir: &Path,
+) -> Command {
+    match backend {
+        ScannerBackend::Sane => {
+            let mut command = Command::new("scanimage");
+            command
+                .arg("--device-name")
+                .arg(device)
+                .arg("--format=png")
+                .arg(format!("--resolution={}", settings.dpi))
+                .arg(format!(
+                    "--mode={}",
+                    match settings.color_mode {
+                        ColorMode::Color => "Color",
+                        ColorMode::Gray => "Gray",
+                        ColorMode::Lineart => "Lineart",
+                    }
+                ));
+            if settings.feeder {
+                command.arg("--source=ADF");
+            }
+            let mut batch = std::ffi::OsString::from("--batch=");
+            batch.push(dir.join(format!("{}%04d.png", PAGE_PREFIX)));
+            command.arg(batch);
+            match (settings.feeder, settings.max_pages) {
+                (false, _) => {
+                    command.arg("--batch-count=1");
+                }
+                (true, 0) => {}
+                (true, max) => {
+                    command.arg(format!("--batch-count={}", max));
+                }
+            }
+            command
+        }
+        ScannerBackend::Wia => {
+            let mut command = powershell(WIA_SCAN_SCRIPT);
+            let intent = match settings.color_mode {
+                ColorMode::Color => "1",
+                ColorMode::Gray => "2",
+                ColorMode::Lineart => "4",
+            };
+            command
+                .env("TAHWEEL_SCAN_DEVICE", device)
+                .env("TAHWEEL_SCAN_DIR", dir)
+                .env("TAHWEEL_SCAN_DPI", settings.dpi.to_string())
+                .env("TAHWEEL_SCAN_INTENT", intent)
+                .env(
+                    "TAHWEEL_SCAN_FEEDER",
+                    if settings.feeder { "1" } else { "0" },
+                )
+                .env("TAHWEEL_SCAN_MAX_PAGES", settings.max_pages.to_string());
+            command
+        }
+    }
+}
+
+/// Scanned pages in `dir`, in page order
+pub fn scanned_pages(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut pages: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(PAGE_PREFIX) && n.ends_with(".png"))
+                .unwrap_or(false)
+        })
+        .collect();
+    // Zero-padded numbers sort correctly as strings
+    pages.sort();
+    Ok(pages)
+}
+
+fn current_backend() -> Result<ScannerBackend, TahweelError> {
+    backend_for_os(std::env::consts::OS).ok_or_else(|| {
+        TahweelError::NotFound("Scanning is not supported on this platform".to_string())
+    })
+}
+
+/// Error for a missing scanning tool on `os`. macOS scanners are normally
+/// reached through Image Capture, which is not supported, so without SANE
+/// scanning is unavailable there rather than merely showing no scanners.
+pub fn missing_tool_error(os: &str) -> TahweelError {
+    match os {
+        "macos" => TahweelError::NotFound(
+            "Scanning on macOS needs SANE (brew install sane-backends); Image Capture scanners are not supported".to_string(),
+        ),
+        _ => TahweelError::NotFound("No scanning tool found".to_string()),
+    }
+}
+
+/// Scanners connected to this machine. Empty when the platform's scanning tool
+/// (`scanimage`, PowerShell) is not installed, except on macOS, where that
+/// means scanning is unavailable (`missing_tool_error`).
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn list_scanners() -> Result<Vec<Scanner>, TahweelError> {
+    let backend = current_backend()?;
+    let output = tauri::async_runtime::spawn_blocking(move || list_command(backend).output())
+        .await
+        .map_err(|e| TahweelError::Internal(format!("Failed to list scanners: {}", e)))?;
+    match output {
+        Ok(output) => Ok(parse_device_list(
+            &String::from_utf8_lossy(&output.stdout),
+            backend,
+        )),
+        Err(e) if e.kind() == io::ErrorKind::NotFound && std::env::consts::OS == "macos" => {
+            Err(missing_tool_error("macos"))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(TahweelError::Io(format!("Failed to list scanners: {}", e))),
+    }
+}
+
+/// Acquire pages from `device` into a new workspace folder, ready for OCR as one
+/// job. Outputs go to `output_directory`, the configured output directory, or the
+/// documents folder.
+#[tauri::command]
+#[tracing::instrument(skip(settings, app), err)]
+pub async fn scan_pages(
+    device: String,
+    settings: Option<ScanSettings>,
+    output_directory: Option<String>,
+    app: AppHandle,
+) -> Result<ScanResult, TahweelError> {
+    let backend = current_backend()?;
+    let settings = settings.unwrap_or_default().validated();
+    let output_directory =
+        output::workspace_input_output_dir(output_directory, app.path().document_dir().ok())?;
+
+    let workspace = path_policy::workspace();
+    fs::create_dir_all(&workspace)?;
+    let dir = tempfile::Builder::new()
+        .prefix("tahweel-scan-")
+        .tempdir_in(&workspace)
+        .map_err(|e| TahweelError::Io(format!("Failed to create scan directory: {}", e)))?
+        .keep();
+
+    let mut command = scan_command(backend, &device, &settings, &dir);
+    let output = tauri::async_runtime::spawn_blocking(move || command.output())
+        .await
+        .map_err(|e| TahweelError::Internal(format!("Scan failed: {}", e)))?
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => missing_tool_error(std::env::consts::OS),
+            _ => TahweelError::Io(format!("Failed to start scan: {}", e)),
+        })?;
+
+    // A feeder scan ends with an error status once the feeder is empty, so any
+    // acquired page counts as success
+    let pages = scanned_pages(&dir)?;
+    if pages.is_empty() {
+        let _ = fs::remove_dir_all(&dir);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(TahweelError::Io(format!(
+            "Scan produced no pages: {}",
+            stderr.trim()
+        )));
+    }
+    tracing::info!(pages = pages.len(), "Scanned pages");
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(ScanResult {
+        name: format!("scan-{}", stamp),
+        dir: long_path::path_string(&dir)?,
+        pages: pages
+            .iter()
+            .map(|page| long_path::path_string(page))
+            .collect::<Result<_, _>>()?,
+        output_directory,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn args(command: &Command) -> Vec<String> {
+        command
+            .get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_backend_for_os() {
+        assert_eq!(backend_for_os("windows"), Some(ScannerBackend::Wia));
+        assert_eq!(backend_for_os("linux"), Some(ScannerBackend::Sane));
+        assert_eq!(backend_for_os("macos"), Some(ScannerBackend::Sane));
+        assert_eq!(backend_for_os("android"), None);
+    }
+
+    #[test]
+    fn test_macos_without_sane_reports_scanning_unavailable() {
+        let error = missing_tool_error("macos");
+        assert_eq!(error.kind(), "notFound");
+        assert!(error.message().contains("sane-backends"));
+        assert_eq!(
+            missing_tool_error("linux").message(),
+            "No scanning tool found"
+        );
+    }
+
+    #[test]
+    fn test_parse_device_list() {
+        let output =
+            "epson2:net:192.168.1.5\tEpson WF-3620\nbad line\n\tmissing id\nhpaio:/usb/x\t \n";
+        let scanners = parse_device_list(output, ScannerBackend::Sane);
+        assert_eq!(
+            scanners,
+            vec![
+                Scanner {
+                    id: "epson2:net:192.168.1.5".to_string(),
+                    name: "Epson WF-3620".to_string(),
+                    backend: ScannerBackend::Sane,
+                },
+                Scanner {
+                    id: "hpaio:/usb/x".to_string(),
+                    name: "hpaio:/usb/x".to_string(),
+                    backend: ScannerBackend::Sane,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sane_flatbed_scan_command() {
+        let dir = Path::new("/ws/tahweel-scan-1");
+        let command = scan_command(
+            ScannerBackend::Sane,
+            "epson2:libusb:001:004",
+            &ScanSettings::default(),
+            dir,
+        );
+        assert_eq!(command.get_program(), "scanimage");
+        let args = args(&command);
+        assert_eq!(args[..2], ["--device-name", "epson2:libusb:001:004"]);
+        assert!(args.contains(&"--resolution=300".to_string()));
+        assert!(args.contains(&"--mode=Gray".to_string()));
+        assert!(args.contains(&"--batch-count=1".to_string()));
+        assert!(!args.iter().any(|a| a.starts_with("--source")));
+        assert!(args.contains(&"--batch=/ws/tahweel-scan-1/page-%04d.png".to_string()));
+    }
+
+    #[test]
+    fn test_sane_feeder_scan_command() {
+        let settings = ScanSettings {
+            feeder: true,
+            max_pages: 10,
+            color_mode: ColorMode::Color,
+            ..ScanSettings::default()
+        };
+        let feeder = args(&scan_command(
+            ScannerBackend::Sane,
+            "dev",
+            &settings,
+            Path::new("/ws"),
+        ));
+        assert!(feeder.contains(&"--source=ADF".to_string()));
+        assert!(feeder.contains(&"--batch-count=10".to_string()));
+        assert!(feeder.contains(&"--mode=Color".to_string()));
+
+        let unlimited = ScanSettings {
+            feeder: true,
+            ..ScanSettings::default()
+        };
+        let unlimited = args(&scan_command(
+            ScannerBackend::Sane,
+            "dev",
+            &unlimited,
+            Path::new("/ws"),
+        ));
+        assert!(!unlimited.iter().any(|a| a.starts_with("--batch-count")));
+    }
+
+    #[test]
+    fn test_wia_scan_passes_parameters_in_environment() {
+        let device = "{6BDD1FC6-810F-11D0-BEC7-08002BE2092F}\\0000'; Remove-Item C:\\";
+        let command = scan_command(
+            ScannerBackend::Wia,
+            device,
+            &ScanSettings::default(),
+            Path::new("C:\\ws"),
+        );
+        assert_eq!(command.get_program(), "powershell");
+        assert!(!args(&command).iter().any(|a| a.contains("Remove-Item")));
+        let env: Vec<_> = command
+            .get_envs()
+            .map(|(k, v)| {
+                (
+                    k.to_string_lossy().to_string(),
+                    v.map(|v| v.to_string_lossy().to_string()),
+                )
+            })
+            .collect();
+        assert!(env.contains(&("TAHWEEL_SCAN_DEVICE".to_string(), Some(device.to_string()))));
+        assert!(env.contains(&("TAHWEEL_SCAN_INTENT".to_string(), Some("2".to_string()))));
+    }
+
+    #[test]
+    fn test_scan_settings_validated() {
+        let settings = ScanSettings {
+            dpi: 10_000,
+            ..ScanSettings::default()
+        }
+        .validated();
+        assert_eq!(settings.dpi, SCAN_DPI_MAX);
+
+        let parsed: ScanSettings =
+            serde_json::from_value(serde_json::json!({ "colorMode": "lineart" })).unwrap();
+        assert_eq!(parsed.color_mode, ColorMode::Lineart);
+        assert_eq!(parsed.dpi, 300);
+    }
+
+    #[test]
+    fn test_scanned_pages_in_order() {
+        let dir = tempdir().unwrap();
+        for name in [
+            "page-0010.png",
+            "page-0002.png",
+            "page-0001.png",
+            "notes.txt",
+        ] {
+            fs::write(dir.path().join(name), b"").unwrap();
+        }
+        let pages: Vec<_> = scanned_pages(dir.path())
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            pages,
+            vec!["page-0001.png", "page-0002.png", "page-0010.png"]
+        );
+    }
+}
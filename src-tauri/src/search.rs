@@ -0,0 +1,313 @@
+use crate::app_paths::{self, Area};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Maximum number of search hits returned when the caller does not specify a limit
+const DEFAULT_SEARCH_LIMIT: u32 = 50;
+/// Number of tokens shown around a match in snippets
+const SNIPPET_TOKENS: u32 = 12;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS documents (
+    id INTEGER PRIMARY KEY,
+    source_path TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    page_count INTEGER NOT NULL,
+    indexed_at INTEGER NOT NULL
+);
+CREATE VIRTUAL TABLE IF NOT EXISTS pages USING fts5(
+    text,
+    document_id UNINDEXED,
+    page UNINDEXED,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+";
+
+/// A page matching a search query
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SearchHit {
+    #[serde(rename = "documentId")]
+    pub document_id: i64,
+    pub title: String,
+    #[serde(rename = "sourcePath")]
+    pub source_path: String,
+    pub page: u32,
+    pub snippet: String,
+}
+
+/// Location of the search index database
+fn search_db_path() -> PathBuf {
+    app_paths::scoped_dir(Area::Db).join("search.db")
+}
+
+/// Open (and create if needed) the search index
+pub fn open_index(path: &std::path::Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| format!("Failed to open search index: {}", e))?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+pub fn init_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to initialize search index: {}", e))
+}
+
+/// Normalize Arabic text for indexing and querying: drop harakat and tatweel and
+/// unify alef/yaa/taa-marbuta variants, so searches match regardless of vocalization.
+/// SQLite's tokenizer would otherwise split words at combining marks.
+pub fn normalize_for_search(text: &str) -> String {
+    text.chars()
+        .filter(|c| !matches!(c, '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{0640}'))
+        .map(|c| match c {
+            'أ' | 'إ' | 'آ' | 'ٱ' => 'ا',
+            'ى' => 'ي',
+            'ة' => 'ه',
+            _ => c,
+        })
+        .collect()
+}
+
+/// Turn free-form user input into an FTS5 query: every term is quoted
+/// (so punctuation cannot produce syntax errors) and all terms must match.
+pub fn build_fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = normalize_for_search(query)
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Index (or re-index) a document's pages, replacing any previous entry for the same source
+pub fn index_pages(
+    conn: &mut Connection,
+    source_path: &str,
+    title: &str,
+    pages: &[String],
+) -> Result<i64, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to index document: {}", e))?;
+    remove_document(&tx, source_path)?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    tx.execute(
+        "INSERT INTO documents (source_path, title, page_count, indexed_at) VALUES (?1, ?2, ?3, ?4)",
+        params![source_path, title, pages.len() as i64, now],
+    )
+    .map_err(|e| format!("Failed to index document: {}", e))?;
+    let document_id = tx.last_insert_rowid();
+
+    {
+        let mut insert = tx
+            .prepare("INSERT INTO pages (text, document_id, page) VALUES (?1, ?2, ?3)")
+            .map_err(|e| format!("Failed to index document: {}", e))?;
+        for (i, text) in pages.iter().enumerate() {
+            insert
+                .execute(params![
+                    normalize_for_search(text),
+                    document_id,
+                    i as i64 + 1
+                ])
+                .map_err(|e| format!("Failed to index page {}: {}", i + 1, e))?;
+        }
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to index document: {}", e))?;
+    Ok(document_id)
+}
+
+/// Remove a document and its pages from the index. Returns whether it existed.
+pub fn remove_document(conn: &Connection, source_path: &str) -> Result<bool, String> {
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM documents WHERE source_path = ?1",
+            params![source_path],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to query search index: {}", e))?;
+
+    let Some(id) = existing else {
+        return Ok(false);
+    };
+    conn.execute("DELETE FROM pages WHERE document_id = ?1", params![id])
+        .and_then(|_| conn.execute("DELETE FROM documents WHERE id = ?1", params![id]))
+        .map_err(|e| format!("Failed to remove document from index: {}", e))?;
+    Ok(true)
+}
+
+/// Search indexed pages, best matches first
+pub fn search(conn: &Connection, query: &str, limit: u32) -> Result<Vec<SearchHit>, String> {
+    let Some(fts_query) = build_fts_query(query) else {
+        return Ok(Vec::new());
+    };
+
+    let mut statement = conn
+        .prepare(&format!(
+            "SELECT d.id, d.title, d.source_path, p.page,
+                    snippet(pages, 0, '[', ']', '…', {})
+             FROM pages p JOIN documents d ON d.id = p.document_id
+             WHERE pages MATCH ?1
+             ORDER BY rank
+             LIMIT ?2",
+            SNIPPET_TOKENS
+        ))
+        .map_err(|e| format!("Failed to search: {}", e))?;
+
+    let hits = statement
+        .query_map(params![fts_query, limit], |row| {
+            Ok(SearchHit {
+                document_id: row.get(0)?,
+                title: row.get(1)?,
+                source_path: row.get(2)?,
+                page: row.get(3)?,
+                snippet: row.get(4)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Failed to search: {}", e))?;
+    Ok(hits)
+}
+
+/// Add a completed job's text to the local search index
+#[tauri::command]
+pub async fn index_document(
+    source_path: String,
+    title: String,
+    pages: Vec<String>,
+) -> Result<i64, String> {
+    let mut conn = open_index(&search_db_path())?;
+    index_pages(&mut conn, &source_path, &title, &pages)
+}
+
+/// Search everything Tahweel has processed; returns document, page, and snippet
+#[tauri::command]
+pub async fn search_documents(query: String, limit: Option<u32>) -> Result<Vec<SearchHit>, String> {
+    let conn = open_index(&search_db_path())?;
+    search(&conn, &query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+}
+
+/// Remove a document from the search index
+#[tauri::command]
+pub async fn remove_indexed_document(source_path: String) -> Result<bool, String> {
+    let conn = open_index(&search_db_path())?;
+    remove_document(&conn, &source_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_normalize_for_search() {
+        assert_eq!(normalize_for_search("كَتَبَ"), "كتب");
+        assert_eq!(normalize_for_search("إسلام أحمد آمن"), "اسلام احمد امن");
+        assert_eq!(normalize_for_search("مدرسة على"), "مدرسه علي");
+        assert_eq!(normalize_for_search("كـــتاب"), "كتاب");
+    }
+
+    #[test]
+    fn test_build_fts_query_quotes_terms() {
+        assert_eq!(
+            build_fts_query("الصلاة AND \"x"),
+            Some("\"الصلاه\" \"AND\" \"\"\"x\"".to_string())
+        );
+        assert_eq!(build_fts_query("   "), None);
+    }
+
+    #[test]
+    fn test_search_finds_page_with_snippet() {
+        let mut conn = index();
+        index_pages(
+            &mut conn,
+            "/books/fiqh.pdf",
+            "fiqh",
+            &["كتاب الطهارة".to_string(), "بابُ الصَّلاةِ وأحكامها".to_string()],
+        )
+        .unwrap();
+
+        let hits = search(&conn, "الصلاة", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "fiqh");
+        assert_eq!(hits[0].source_path, "/books/fiqh.pdf");
+        assert_eq!(hits[0].page, 2);
+        assert!(hits[0].snippet.contains("[الصلاه]"));
+    }
+
+    #[test]
+    fn test_search_requires_all_terms() {
+        let mut conn = index();
+        index_pages(
+            &mut conn,
+            "a.pdf",
+            "a",
+            &["alpha beta".to_string(), "alpha".to_string()],
+        )
+        .unwrap();
+
+        let hits = search(&conn, "alpha beta", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].page, 1);
+    }
+
+    #[test]
+    fn test_reindex_replaces_document() {
+        let mut conn = index();
+        index_pages(&mut conn, "a.pdf", "a", &["old text".to_string()]).unwrap();
+        let second = index_pages(&mut conn, "a.pdf", "a", &["new text".to_string()]).unwrap();
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM documents", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(search(&conn, "old", 10).unwrap().is_empty());
+        assert_eq!(search(&conn, "new", 10).unwrap()[0].document_id, second);
+    }
+
+    #[test]
+    fn test_remove_document() {
+        let mut conn = index();
+        index_pages(&mut conn, "a.pdf", "a", &["text".to_string()]).unwrap();
+
+        assert!(remove_document(&conn, "a.pdf").unwrap());
+        assert!(!remove_document(&conn, "a.pdf").unwrap());
+        assert!(search(&conn, "text", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_limit() {
+        let mut conn = index();
+        let pages: Vec<String> = (0..5).map(|i| format!("word {}", i)).collect();
+        index_pages(&mut conn, "a.pdf", "a", &pages).unwrap();
+
+        assert_eq!(search(&conn, "word", 3).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_open_index_on_disk() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("search.db");
+        {
+            let mut conn = open_index(&path).unwrap();
+            index_pages(&mut conn, "a.pdf", "a", &["persisted".to_string()]).unwrap();
+        }
+        let conn = open_index(&path).unwrap();
+        assert_eq!(search(&conn, "persisted", 10).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,1136 @@
+use crate::app_paths::{self, Area};
+use crate::error_catalog;
+use crate::output::OutputFormat;
+use crate::postprocess::{PostprocessStep, MAX_PIPELINE_STEPS};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter};
+
+/// Current version of the settings file schema
+pub const SETTINGS_VERSION: u32 = 1;
+
+// Validation ranges (DPI and OCR concurrency mirror the frontend store)
+const DPI_MIN: u32 = 72;
+const DPI_MAX: u32 = 300;
+const CONCURRENCY_MIN: u32 = 1;
+const CONCURRENCY_MAX: u32 = 20;
+const RENDER_THREADS_MAX: u32 = 64;
+const MEMORY_BUDGET_MIN_MB: u32 = 256;
+const MEMORY_BUDGET_MAX_MB: u32 = 65536;
+const MAX_RETRIES_LIMIT: u32 = 10;
+const RETRY_DELAY_MIN_SECS: f64 = 1.0;
+const RETRY_DELAY_MAX_SECS: f64 = 120.0;
+const LOGIN_TIMEOUT_MIN_SECS: u64 = 30;
+const LOGIN_TIMEOUT_MAX_SECS: u64 = 3600;
+const HTTP_TIMEOUT_MIN_SECS: u64 = 1;
+const HTTP_TIMEOUT_MAX_SECS: u64 = 3600;
+const WEBDAV_CHUNK_MAX_MB: u32 = 1024;
+const EMAIL_ATTACHMENT_MAX_MB: u32 = 100;
+const SIMULATED_DELAY_MAX_MS: u64 = 60_000;
+const BATTERY_PAUSE_MIN_PERCENT: u8 = 5;
+const BATTERY_PAUSE_MAX_PERCENT: u8 = 95;
+const RETENTION_MAX_AGE_DAYS: u32 = 3650;
+const RETENTION_MAX_SIZE_MB: u32 = 1024 * 1024;
+const QUOTA_RESET_HOUR_MAX: u8 = 23;
+
+/// Pixel format rendered pages are saved in
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// Grayscale unless the page has color content
+    #[default]
+    Auto,
+    /// Always 24-bit RGB
+    Color,
+    /// 8-bit grayscale
+    Grayscale,
+    /// Black and white only (stored as 8-bit grayscale)
+    Bitonal,
+}
+
+/// What the quick OCR shortcut reads the image from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum QuickOcrSource {
+    /// A screen region selected after pressing the shortcut
+    #[default]
+    Region,
+    /// The image on the system clipboard
+    Clipboard,
+}
+
+/// Global keyboard shortcut that runs a quick OCR while the app is in the background
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct QuickOcrSettings {
+    pub enabled: bool,
+    /// Accelerator such as `CommandOrControl+Shift+O`
+    pub shortcut: String,
+    pub source: QuickOcrSource,
+}
+
+impl Default for QuickOcrSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            shortcut: "CommandOrControl+Shift+O".to_string(),
+            source: QuickOcrSource::Region,
+        }
+    }
+}
+
+/// Retry policy for Google API calls
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RetrySettings {
+    #[serde(rename = "maxRetries")]
+    pub max_retries: u32,
+    #[serde(rename = "maxDelaySecs")]
+    pub max_delay_secs: f64,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            max_delay_secs: 15.0,
+        }
+    }
+}
+
+/// HTTP timeouts per operation class. The read timeout bounds each wait for
+/// data, so slow but progressing transfers are not cut off.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TimeoutSettings {
+    #[serde(rename = "apiConnectSecs")]
+    pub api_connect_secs: u64,
+    #[serde(rename = "apiReadSecs")]
+    pub api_read_secs: u64,
+    #[serde(rename = "uploadConnectSecs")]
+    pub upload_connect_secs: u64,
+    #[serde(rename = "uploadReadSecs")]
+    pub upload_read_secs: u64,
+}
+
+impl Default for TimeoutSettings {
+    fn default() -> Self {
+        Self {
+            api_connect_secs: 10,
+            api_read_secs: 60,
+            upload_connect_secs: 15,
+            upload_read_secs: 300,
+        }
+    }
+}
+
+/// Retention limits of one store; 0 turns a limit off
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Entries not touched for this many days are removed
+    #[serde(rename = "maxAgeDays")]
+    pub max_age_days: u32,
+    /// Oldest entries are removed while the store is larger than this, in MiB
+    #[serde(rename = "maxSizeMb")]
+    pub max_size_mb: u32,
+}
+
+impl RetentionPolicy {
+    pub const fn new(max_age_days: u32, max_size_mb: u32) -> Self {
+        Self {
+            max_age_days,
+            max_size_mb,
+        }
+    }
+}
+
+/// Retention policies the maintenance task enforces (see maintenance.rs)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RetentionSettings {
+    /// Leftover rendered pages, downloads, spools and captures in the workspace
+    pub workspace: RetentionPolicy,
+    /// The layout's `cache` area
+    pub cache: RetentionPolicy,
+    /// Completed jobs in the job database; kept forever by default
+    pub history: RetentionPolicy,
+    pub logs: RetentionPolicy,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            workspace: RetentionPolicy::new(7, 4096),
+            cache: RetentionPolicy::new(30, 512),
+            history: RetentionPolicy::new(0, 0),
+            logs: RetentionPolicy::new(30, 200),
+        }
+    }
+}
+
+/// Daily limits of one OCR backend; 0 turns a limit off
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct DailyBudget {
+    /// Pages sent for OCR
+    #[serde(rename = "maxPages")]
+    pub max_pages: u32,
+    /// API requests (uploads, conversion polls, exports, retries included)
+    #[serde(rename = "maxApiCalls")]
+    pub max_api_calls: u32,
+}
+
+/// Daily budgets per OCR backend (see quota.rs)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct QuotaSettings {
+    #[serde(rename = "googleDrive")]
+    pub google_drive: DailyBudget,
+    /// Hour (UTC) the daily counts start over; 8 is midnight Pacific standard
+    /// time, when Google's daily quotas reset
+    #[serde(rename = "resetHourUtc")]
+    pub reset_hour_utc: u8,
+}
+
+impl Default for QuotaSettings {
+    fn default() -> Self {
+        Self {
+            google_drive: DailyBudget::default(),
+            reset_hour_utc: 8,
+        }
+    }
+}
+
+/// Developer mode, not shown in the UI: every Google API request attempt is
+/// delayed by `latencyMs` plus up to `jitterMs`, and fails with a transient
+/// error (network, timeout, 429 or 5xx) with probability `failureRate`, so
+/// retries, resume and backoff can be exercised without a flaky network.
+/// Set it in the settings file or through `update_settings`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct NetworkSimulationSettings {
+    pub enabled: bool,
+    #[serde(rename = "latencyMs")]
+    pub latency_ms: u64,
+    #[serde(rename = "jitterMs")]
+    pub jitter_ms: u64,
+    /// Share of attempts that fail, from 0 to 1
+    #[serde(rename = "failureRate")]
+    pub failure_rate: f64,
+}
+
+/// Sign-in options
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AuthSettings {
+    /// How long to wait for the browser sign-in to complete
+    #[serde(rename = "loginTimeoutSecs")]
+    pub login_timeout_secs: u64,
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        Self {
+            login_timeout_secs: 300,
+        }
+    }
+}
+
+/// S3-compatible bucket (AWS S3, MinIO, ...) that job outputs are uploaded to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StorageSettings {
+    pub enabled: bool,
+    /// Service URL, e.g. `https://s3.us-east-1.amazonaws.com` or `http://minio.local:9000`
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    /// Key prefix ("folder") for uploaded objects
+    pub prefix: String,
+    #[serde(rename = "accessKeyId")]
+    pub access_key_id: String,
+    #[serde(rename = "secretAccessKey")]
+    pub secret_access_key: String,
+    /// Address objects as `<endpoint>/<bucket>/<key>` (MinIO) rather than
+    /// `<bucket>.<endpoint host>/<key>`
+    #[serde(rename = "pathStyle")]
+    pub path_style: bool,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            bucket: String::new(),
+            region: "us-east-1".to_string(),
+            prefix: String::new(),
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            path_style: true,
+        }
+    }
+}
+
+/// What to do when a file with the same name already exists on the WebDAV server
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Upload as `name (1).ext`, `name (2).ext`, ...
+    #[default]
+    Rename,
+    Overwrite,
+    Skip,
+}
+
+/// WebDAV folder (Nextcloud, ownCloud, ...) that job outputs are uploaded to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct WebDavSettings {
+    pub enabled: bool,
+    /// Folder URL, e.g. `https://cloud.example.com/remote.php/dav/files/amina/Books`
+    pub url: String,
+    pub username: String,
+    /// Password or Nextcloud app password
+    pub password: String,
+    /// Files larger than this are sent in chunks when the server is Nextcloud;
+    /// 0 always uploads in one request
+    #[serde(rename = "chunkSizeMb")]
+    pub chunk_size_mb: u32,
+    pub conflict: ConflictPolicy,
+}
+
+impl Default for WebDavSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            username: String::new(),
+            password: String::new(),
+            chunk_size_mb: 10,
+            conflict: ConflictPolicy::Rename,
+        }
+    }
+}
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with `STARTTLS` (port 587)
+    #[default]
+    StartTls,
+    /// TLS from the first byte (port 465)
+    Tls,
+    /// Unencrypted; only for local relays
+    None,
+}
+
+/// SMTP account used to email job results
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EmailSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: String,
+    pub password: String,
+    /// Sender address; the username is used when empty
+    pub from: String,
+    /// Address the results are sent to
+    pub to: String,
+    /// Outputs beyond this total size are sent as links instead of attachments
+    #[serde(rename = "maxAttachmentMb")]
+    pub max_attachment_mb: u32,
+}
+
+impl Default for EmailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 587,
+            security: SmtpSecurity::StartTls,
+            username: String::new(),
+            password: String::new(),
+            from: String::new(),
+            to: String::new(),
+            max_attachment_mb: 10,
+        }
+    }
+}
+
+/// Application settings persisted as JSON in the app config directory
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub dpi: u32,
+    /// Pick each page's render resolution from its size and scan resolution
+    /// (targeting ~300 effective DPI) instead of using `dpi`
+    #[serde(rename = "autoDpi")]
+    pub auto_dpi: bool,
+    /// Pixel format of rendered pages; grayscale is a third the size of RGB
+    #[serde(rename = "colorMode")]
+    pub color_mode: ColorMode,
+    /// Scale rendered pages down to Google Drive's image limits (25 MP, 2 MB)
+    /// so none is rejected on upload
+    #[serde(rename = "driveSafeImages")]
+    pub drive_safe_images: bool,
+    pub formats: Vec<OutputFormat>,
+    #[serde(rename = "ocrConcurrency")]
+    pub ocr_concurrency: u32,
+    /// Threads used to render PDF pages; 0 sizes the pool to the CPU count
+    #[serde(rename = "renderThreads")]
+    pub render_threads: u32,
+    /// Memory the render and upload stages may hold, in MiB; 0 disables the limit
+    #[serde(rename = "memoryBudgetMb")]
+    pub memory_budget_mb: u32,
+    /// Low-power mode: render threads run at lower priority, half of them at
+    /// once, and work pauses on battery below `battery_pause_percent`
+    #[serde(rename = "backgroundMode")]
+    pub background_mode: bool,
+    /// Battery charge (percent) below which background mode pauses work
+    #[serde(rename = "batteryPausePercent")]
+    pub battery_pause_percent: u8,
+    #[serde(rename = "pageSeparator")]
+    pub page_separator: String,
+    /// Post-processing steps run, in order, on each document's text before its
+    /// outputs are written
+    pub postprocessing: Vec<PostprocessStep>,
+    /// Quality mode: OCR ambiguous pages a second time at another DPI and flag
+    /// pages where the two passes disagree
+    #[serde(rename = "dualPassOcr")]
+    pub dual_pass_ocr: bool,
+    /// Detect footnotes below a rule line and emit them separately from the body
+    #[serde(rename = "separateFootnotes")]
+    pub separate_footnotes: bool,
+    /// Skip OCR of pages that repeat an earlier page and reuse its text
+    #[serde(rename = "skipDuplicatePages")]
+    pub skip_duplicate_pages: bool,
+    /// Turn sideways and upside-down rendered pages upright before OCR
+    #[serde(rename = "autoRotatePages")]
+    pub auto_rotate_pages: bool,
+    /// Keep rendered page images and partial OCR text encrypted at rest with
+    /// per-job keys held only in memory
+    #[serde(rename = "encryptWorkspace")]
+    pub encrypt_workspace: bool,
+    /// Output directory; `None` writes next to the source file
+    #[serde(rename = "outputDirectory")]
+    pub output_directory: Option<String>,
+    pub retry: RetrySettings,
+    pub timeouts: TimeoutSettings,
+    /// How long and how much the caches, history and logs may keep
+    pub retention: RetentionSettings,
+    /// Daily page and API call budgets per OCR backend
+    pub quota: QuotaSettings,
+    pub auth: AuthSettings,
+    /// Injected latency and failures for testing (see `NetworkSimulationSettings`)
+    #[serde(rename = "networkSimulation")]
+    pub network_simulation: NetworkSimulationSettings,
+    /// Optional upload of job outputs to S3-compatible storage
+    pub storage: StorageSettings,
+    /// Optional upload of job outputs to a WebDAV folder
+    pub webdav: WebDavSettings,
+    /// Optional email with the outputs when a job finishes
+    pub email: EmailSettings,
+    /// Global shortcut for screen-region or clipboard OCR
+    #[serde(rename = "quickOcr")]
+    pub quick_ocr: QuickOcrSettings,
+    /// UI language (`ar` or `en`); selects the language of error messages
+    pub language: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            dpi: 150,
+            auto_dpi: false,
+            color_mode: ColorMode::Auto,
+            drive_safe_images: false,
+            formats: vec![OutputFormat::Txt, OutputFormat::Docx],
+            ocr_concurrency: 12,
+            render_threads: 0,
+            memory_budget_mb: 1024,
+            background_mode: false,
+            battery_pause_percent: 20,
+            page_separator: "\n\nPAGE_SEPARATOR\n\n".to_string(),
+            postprocessing: Vec::new(),
+            dual_pass_ocr: false,
+            separate_footnotes: false,
+            skip_duplicate_pages: false,
+            auto_rotate_pages: false,
+            encrypt_workspace: false,
+            output_directory: None,
+            retry: RetrySettings::default(),
+            timeouts: TimeoutSettings::default(),
+            retention: RetentionSettings::default(),
+            quota: QuotaSettings::default(),
+            auth: AuthSettings::default(),
+            network_simulation: NetworkSimulationSettings::default(),
+            storage: StorageSettings::default(),
+            webdav: WebDavSettings::default(),
+            email: EmailSettings::default(),
+            quick_ocr: QuickOcrSettings::default(),
+            language: "ar".to_string(),
+        }
+    }
+}
+
+impl Settings {
+    /// Copy with passwords and secret keys replaced, for files that leave the machine
+    pub fn redacted(&self) -> Self {
+        let mut settings = self.clone();
+        for secret in [
+            &mut settings.storage.secret_access_key,
+            &mut settings.webdav.password,
+            &mut settings.email.password,
+        ] {
+            if !secret.is_empty() {
+                *secret = "[REDACTED]".to_string();
+            }
+        }
+        settings
+    }
+
+    /// Clamp values into their valid ranges (protects against hand-edited files)
+    pub fn validated(mut self) -> Self {
+        self.version = SETTINGS_VERSION;
+        self.dpi = self.dpi.clamp(DPI_MIN, DPI_MAX);
+        self.ocr_concurrency = self.ocr_concurrency.clamp(CONCURRENCY_MIN, CONCURRENCY_MAX);
+        self.render_threads = self.render_threads.min(RENDER_THREADS_MAX);
+        if self.memory_budget_mb != 0 {
+            self.memory_budget_mb = self
+                .memory_budget_mb
+                .clamp(MEMORY_BUDGET_MIN_MB, MEMORY_BUDGET_MAX_MB);
+        }
+        self.battery_pause_percent = self
+            .battery_pause_percent
+            .clamp(BATTERY_PAUSE_MIN_PERCENT, BATTERY_PAUSE_MAX_PERCENT);
+        self.retry.max_retries = self.retry.max_retries.min(MAX_RETRIES_LIMIT);
+        self.retry.max_delay_secs = if self.retry.max_delay_secs.is_finite() {
+            self.retry
+                .max_delay_secs
+                .clamp(RETRY_DELAY_MIN_SECS, RETRY_DELAY_MAX_SECS)
+        } else {
+            RetrySettings::default().max_delay_secs
+        };
+        for secs in [
+            &mut self.timeouts.api_connect_secs,
+            &mut self.timeouts.api_read_secs,
+            &mut self.timeouts.upload_connect_secs,
+            &mut self.timeouts.upload_read_secs,
+        ] {
+            *secs = (*secs).clamp(HTTP_TIMEOUT_MIN_SECS, HTTP_TIMEOUT_MAX_SECS);
+        }
+        for policy in [
+            &mut self.retention.workspace,
+            &mut self.retention.cache,
+            &mut self.retention.history,
+            &mut self.retention.logs,
+        ] {
+            policy.max_age_days = policy.max_age_days.min(RETENTION_MAX_AGE_DAYS);
+            policy.max_size_mb = policy.max_size_mb.min(RETENTION_MAX_SIZE_MB);
+        }
+        self.quota.reset_hour_utc = self.quota.reset_hour_utc.min(QUOTA_RESET_HOUR_MAX);
+        self.auth.login_timeout_secs = self
+            .auth
+            .login_timeout_secs
+            .clamp(LOGIN_TIMEOUT_MIN_SECS, LOGIN_TIMEOUT_MAX_SECS);
+        let simulation = &mut self.network_simulation;
+        simulation.latency_ms = simulation.latency_ms.min(SIMULATED_DELAY_MAX_MS);
+        simulation.jitter_ms = simulation.jitter_ms.min(SIMULATED_DELAY_MAX_MS);
+        simulation.failure_rate = if simulation.failure_rate.is_finite() {
+            simulation.failure_rate.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        self.postprocessing.retain(
+            |step| !matches!(step, PostprocessStep::Replace { find, .. } if find.is_empty()),
+        );
+        self.postprocessing.truncate(MAX_PIPELINE_STEPS);
+        let mut seen = Vec::new();
+        self.formats.retain(|format| {
+            let first = !seen.contains(format);
+            seen.push(*format);
+            first
+        });
+        if self.formats.is_empty() {
+            self.formats = Settings::default().formats;
+        }
+        if self.output_directory.as_deref() == Some("") {
+            self.output_directory = None;
+        }
+        self.storage.endpoint = self
+            .storage
+            .endpoint
+            .trim()
+            .trim_end_matches('/')
+            .to_string();
+        self.storage.bucket = self.storage.bucket.trim().to_string();
+        self.storage.prefix = self.storage.prefix.trim().trim_matches('/').to_string();
+        if self.storage.region.trim().is_empty() {
+            self.storage.region = StorageSettings::default().region;
+        }
+        self.webdav.url = self.webdav.url.trim().trim_end_matches('/').to_string();
+        self.webdav.chunk_size_mb = self.webdav.chunk_size_mb.min(WEBDAV_CHUNK_MAX_MB);
+        for field in [
+            &mut self.email.host,
+            &mut self.email.from,
+            &mut self.email.to,
+        ] {
+            *field = field.trim().to_string();
+        }
+        if self.email.port == 0 {
+            self.email.port = EmailSettings::default().port;
+        }
+        self.email.max_attachment_mb = self.email.max_attachment_mb.min(EMAIL_ATTACHMENT_MAX_MB);
+        self.quick_ocr.shortcut = self.quick_ocr.shortcut.trim().to_string();
+        if self.quick_ocr.shortcut.is_empty() {
+            self.quick_ocr.shortcut = QuickOcrSettings::default().shortcut;
+        }
+        if !error_catalog::is_supported_language(&self.language) {
+            self.language = Settings::default().language;
+        }
+        self
+    }
+}
+
+/// Migration steps, indexed by the version they upgrade from
+const MIGRATIONS: &[fn(&mut Value)] = &[migrate_v0_to_v1];
+
+/// v0 is the unversioned shape previously kept in the webview's localStorage
+fn migrate_v0_to_v1(value: &mut Value) {
+    if let Some(object) = value.as_object_mut() {
+        // The frontend stored an empty string when no output folder was chosen
+        if object.get("outputDirectory") == Some(&Value::String(String::new())) {
+            object.insert("outputDirectory".to_string(), Value::Null);
+        }
+        object.insert("version".to_string(), Value::from(1));
+    }
+}
+
+/// Upgrade a raw settings document to the current version.
+/// Returns the migrated settings and whether any migration ran.
+pub fn migrate(mut value: Value) -> Result<(Settings, bool), String> {
+    if !value.is_object() {
+        return Err("Settings file is not a JSON object".to_string());
+    }
+
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let mut migrated = false;
+    for step in MIGRATIONS.iter().skip(version) {
+        step(&mut value);
+        migrated = true;
+    }
+
+    let settings: Settings =
+        serde_json::from_value(value).map_err(|e| format!("Invalid settings: {}", e))?;
+    Ok((settings.validated(), migrated))
+}
+
+/// Recursively merge `patch` into `target` (objects merge, other values replace)
+fn merge_json(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge_json(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+/// Apply a partial update to the given settings
+pub fn apply_update(current: &Settings, patch: Value) -> Result<Settings, String> {
+    if !patch.is_object() {
+        return Err("Settings update must be a JSON object".to_string());
+    }
+    let mut value = serde_json::to_value(current).map_err(|e| e.to_string())?;
+    merge_json(&mut value, patch);
+    let settings: Settings =
+        serde_json::from_value(value).map_err(|e| format!("Invalid settings: {}", e))?;
+    Ok(settings.validated())
+}
+
+/// Location of the settings file
+pub fn settings_path() -> PathBuf {
+    app_paths::scoped_dir(Area::Config).join("settings.json")
+}
+
+/// Load settings from `path`, migrating (and rewriting) older files.
+/// A missing file yields the defaults.
+pub fn load_from(path: &Path) -> Result<Settings, String> {
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read settings: {}", e))?;
+    let value: Value =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    let (settings, migrated) = migrate(value)?;
+    if migrated {
+        save_to(path, &settings)?;
+    }
+    Ok(settings)
+}
+
+/// Write settings atomically (`app_paths::write_atomic`)
+pub fn save_to(path: &Path, settings: &Settings) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    app_paths::write_atomic(path, json.as_bytes())
+        .map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+/// Process-wide settings snapshot, loaded on first use
+static SETTINGS: RwLock<Option<Settings>> = RwLock::new(None);
+
+fn load_initial() -> Settings {
+    // Unit tests never touch the user's real settings file
+    if cfg!(test) {
+        return Settings::default();
+    }
+    load_from(&settings_path()).unwrap_or_default()
+}
+
+/// Current settings, for backend code that needs configuration values
+pub fn current() -> Settings {
+    if let Some(settings) = SETTINGS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_ref()
+    {
+        return settings.clone();
+    }
+    let mut guard = SETTINGS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    guard.get_or_insert_with(load_initial).clone()
+}
+
+/// Re-read the settings file, e.g. after the active profile changed
+pub fn reload() -> Settings {
+    let settings = load_initial();
+    replace_current(settings.clone());
+    settings
+}
+
+fn replace_current(settings: Settings) {
+    *SETTINGS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(settings);
+}
+
+/// Get the current settings
+#[tauri::command]
+pub async fn get_settings() -> Settings {
+    current()
+}
+
+/// Apply a partial settings update, persist it, and emit `settings-changed`.
+/// A changed quick OCR shortcut is re-registered first; if that fails nothing is saved.
+#[tauri::command]
+pub async fn update_settings(patch: Value, app: AppHandle) -> Result<Settings, String> {
+    let previous = current();
+    let settings = apply_update(&previous, patch)?;
+    #[cfg(desktop)]
+    if settings.quick_ocr != previous.quick_ocr {
+        if let Err(e) = crate::shortcut::apply(&app, &settings.quick_ocr) {
+            // Keep the shortcut that was working
+            let _ = crate::shortcut::apply(&app, &previous.quick_ocr);
+            return Err(e.to_string());
+        }
+    }
+    save_to(&settings_path(), &settings)?;
+    replace_current(settings.clone());
+    if settings.background_mode != previous.background_mode
+        || settings.battery_pause_percent != previous.battery_pause_percent
+    {
+        crate::power::refresh(&app, &settings);
+    }
+    if settings.quota != previous.quota {
+        crate::quota::refresh(&settings.quota).await;
+    }
+    let _ = app.emit("settings-changed", &settings);
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_defaults_match_frontend() {
+        let settings = Settings::default();
+        assert_eq!(settings.dpi, 150);
+        assert_eq!(settings.ocr_concurrency, 12);
+        assert_eq!(
+            settings.formats,
+            vec![OutputFormat::Txt, OutputFormat::Docx]
+        );
+        assert_eq!(settings.page_separator, "\n\nPAGE_SEPARATOR\n\n");
+        assert!(settings.output_directory.is_none());
+        assert_eq!(settings.language, "ar");
+        assert_eq!(settings.color_mode, ColorMode::Auto);
+        assert!(!settings.drive_safe_images);
+        assert!(!settings.background_mode);
+        assert_eq!(settings.battery_pause_percent, 20);
+    }
+
+    #[test]
+    fn test_quota_defaults_and_clamping() {
+        let settings: Settings = serde_json::from_value(
+            json!({"quota": {"googleDrive": {"maxPages": 500}, "resetHourUtc": 30}}),
+        )
+        .unwrap();
+        let quota = settings.validated().quota;
+        assert_eq!(quota.google_drive.max_pages, 500);
+        assert_eq!(quota.google_drive.max_api_calls, 0);
+        assert_eq!(quota.reset_hour_utc, 23);
+        assert_eq!(Settings::default().quota.reset_hour_utc, 8);
+    }
+
+    #[test]
+    fn test_color_mode_serialization() {
+        let settings: Settings = serde_json::from_value(json!({"colorMode": "bitonal"})).unwrap();
+        assert_eq!(settings.color_mode, ColorMode::Bitonal);
+        assert!(serde_json::from_value::<Settings>(json!({"colorMode": "sepia"})).is_err());
+    }
+
+    #[test]
+    fn test_validated_normalizes_storage() {
+        let mut settings = Settings::default();
+        settings.storage.endpoint = " http://minio.local:9000/ ".to_string();
+        settings.storage.prefix = "/archive/ocr/".to_string();
+        settings.storage.region = " ".to_string();
+        let storage = settings.validated().storage;
+        assert_eq!(storage.endpoint, "http://minio.local:9000");
+        assert_eq!(storage.prefix, "archive/ocr");
+        assert_eq!(storage.region, "us-east-1");
+    }
+
+    #[test]
+    fn test_redacted_hides_secrets() {
+        let mut settings = Settings::default();
+        settings.storage.secret_access_key = "s3-secret".to_string();
+        settings.email.password = "smtp".to_string();
+        let redacted = settings.redacted();
+        assert_eq!(redacted.storage.secret_access_key, "[REDACTED]");
+        assert_eq!(redacted.email.password, "[REDACTED]");
+        assert_eq!(redacted.webdav.password, "");
+        assert_eq!(settings.email.password, "smtp");
+    }
+
+    #[test]
+    fn test_validated_normalizes_webdav() {
+        let mut settings = Settings::default();
+        settings.webdav.url = " https://cloud.example.com/remote.php/dav/files/amina/ ".to_string();
+        settings.webdav.chunk_size_mb = 100_000;
+        let webdav = settings.validated().webdav;
+        assert_eq!(
+            webdav.url,
+            "https://cloud.example.com/remote.php/dav/files/amina"
+        );
+        assert_eq!(webdav.chunk_size_mb, WEBDAV_CHUNK_MAX_MB);
+        assert_eq!(webdav.conflict, ConflictPolicy::Rename);
+    }
+
+    #[test]
+    fn test_validated_normalizes_email() {
+        let mut settings = Settings::default();
+        settings.email.host = " smtp.example.com ".to_string();
+        settings.email.to = "amina@example.com ".to_string();
+        settings.email.port = 0;
+        settings.email.max_attachment_mb = 5000;
+        let email = settings.validated().email;
+        assert_eq!(email.host, "smtp.example.com");
+        assert_eq!(email.to, "amina@example.com");
+        assert_eq!(email.port, 587);
+        assert_eq!(email.max_attachment_mb, EMAIL_ATTACHMENT_MAX_MB);
+        assert_eq!(email.security, SmtpSecurity::StartTls);
+    }
+
+    #[test]
+    fn test_validated_normalizes_quick_ocr() {
+        let mut settings = Settings::default();
+        settings.quick_ocr.shortcut = " Alt+Shift+T ".to_string();
+        assert_eq!(
+            settings.clone().validated().quick_ocr.shortcut,
+            "Alt+Shift+T"
+        );
+        settings.quick_ocr.shortcut = "  ".to_string();
+        assert_eq!(settings.validated().quick_ocr, QuickOcrSettings::default());
+
+        let parsed: Settings =
+            serde_json::from_value(json!({"quickOcr": {"enabled": true, "source": "clipboard"}}))
+                .unwrap();
+        assert!(parsed.quick_ocr.enabled);
+        assert_eq!(parsed.quick_ocr.source, QuickOcrSource::Clipboard);
+        assert_eq!(parsed.quick_ocr.shortcut, "CommandOrControl+Shift+O");
+    }
+
+    #[test]
+    fn test_validated_clamps_memory_budget() {
+        let clamp = |memory_budget_mb| {
+            Settings {
+                memory_budget_mb,
+                ..Settings::default()
+            }
+            .validated()
+            .memory_budget_mb
+        };
+        assert_eq!(clamp(0), 0);
+        assert_eq!(clamp(1), MEMORY_BUDGET_MIN_MB);
+        assert_eq!(clamp(4096), 4096);
+        assert_eq!(clamp(u32::MAX), MEMORY_BUDGET_MAX_MB);
+    }
+
+    #[test]
+    fn test_retention_defaults_and_clamping() {
+        let settings: Settings =
+            serde_json::from_value(json!({"retention": {"history": {"maxAgeDays": 99999}}}))
+                .unwrap();
+        let retention = settings.validated().retention;
+        assert_eq!(retention.history.max_age_days, RETENTION_MAX_AGE_DAYS);
+        assert_eq!(retention.history.max_size_mb, 0);
+        assert_eq!(retention.workspace, RetentionSettings::default().workspace);
+    }
+
+    #[test]
+    fn test_postprocessing_pipeline_is_validated() {
+        let settings: Settings = serde_json::from_value(json!({
+            "postprocessing": [
+                {"step": "replace", "find": "", "replace": "x"},
+                {"step": "reflowParagraphs"},
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            settings.validated().postprocessing,
+            vec![PostprocessStep::ReflowParagraphs]
+        );
+
+        let long = Settings {
+            postprocessing: vec![PostprocessStep::UnifyLetters; MAX_PIPELINE_STEPS + 5],
+            ..Settings::default()
+        };
+        assert_eq!(long.validated().postprocessing.len(), MAX_PIPELINE_STEPS);
+        assert!(Settings::default().postprocessing.is_empty());
+    }
+
+    #[test]
+    fn test_validated_clamps_battery_pause_percent() {
+        let clamp = |battery_pause_percent| {
+            Settings {
+                battery_pause_percent,
+                ..Settings::default()
+            }
+            .validated()
+            .battery_pause_percent
+        };
+        assert_eq!(clamp(0), BATTERY_PAUSE_MIN_PERCENT);
+        assert_eq!(clamp(30), 30);
+        assert_eq!(clamp(100), BATTERY_PAUSE_MAX_PERCENT);
+    }
+
+    #[test]
+    fn test_validated_resets_unknown_language() {
+        let settings = Settings {
+            language: "fr".to_string(),
+            ..Settings::default()
+        }
+        .validated();
+        assert_eq!(settings.language, "ar");
+
+        let updated = apply_update(&settings, json!({ "language": "en" })).unwrap();
+        assert_eq!(updated.language, "en");
+    }
+
+    #[test]
+    fn test_validated_clamps_values() {
+        let settings = Settings {
+            dpi: 1000,
+            ocr_concurrency: 0,
+            formats: vec![],
+            retry: RetrySettings {
+                max_retries: 50,
+                max_delay_secs: f64::NAN,
+            },
+            auth: AuthSettings {
+                login_timeout_secs: 1,
+            },
+            ..Settings::default()
+        }
+        .validated();
+        assert_eq!(settings.dpi, DPI_MAX);
+        assert_eq!(settings.ocr_concurrency, CONCURRENCY_MIN);
+        assert_eq!(settings.retry.max_retries, MAX_RETRIES_LIMIT);
+        assert_eq!(settings.retry.max_delay_secs, 15.0);
+        assert_eq!(settings.auth.login_timeout_secs, LOGIN_TIMEOUT_MIN_SECS);
+        assert!(!settings.formats.is_empty());
+    }
+
+    #[test]
+    fn test_validated_clamps_network_simulation() {
+        let mut settings = Settings::default();
+        assert!(!settings.network_simulation.enabled);
+        settings.network_simulation = NetworkSimulationSettings {
+            enabled: true,
+            latency_ms: 10_000_000,
+            jitter_ms: 50,
+            failure_rate: 3.0,
+        };
+        let simulation = settings.clone().validated().network_simulation;
+        assert_eq!(simulation.latency_ms, SIMULATED_DELAY_MAX_MS);
+        assert_eq!(simulation.jitter_ms, 50);
+        assert_eq!(simulation.failure_rate, 1.0);
+
+        settings.network_simulation.failure_rate = f64::NAN;
+        assert_eq!(settings.validated().network_simulation.failure_rate, 0.0);
+    }
+
+    #[test]
+    fn test_validated_clamps_timeouts() {
+        let settings = Settings {
+            timeouts: TimeoutSettings {
+                api_connect_secs: 0,
+                api_read_secs: 30,
+                upload_connect_secs: 0,
+                upload_read_secs: u64::MAX,
+            },
+            ..Settings::default()
+        }
+        .validated();
+        assert_eq!(settings.timeouts.api_connect_secs, HTTP_TIMEOUT_MIN_SECS);
+        assert_eq!(settings.timeouts.api_read_secs, 30);
+        assert_eq!(settings.timeouts.upload_connect_secs, HTTP_TIMEOUT_MIN_SECS);
+        assert_eq!(settings.timeouts.upload_read_secs, HTTP_TIMEOUT_MAX_SECS);
+    }
+
+    #[test]
+    fn test_migrate_unversioned_local_storage_shape() {
+        let legacy = json!({
+            "dpi": 200,
+            "formats": ["txt", "json"],
+            "ocrConcurrency": 8,
+            "pageSeparator": "---",
+            "outputDirectory": ""
+        });
+
+        let (settings, migrated) = migrate(legacy).unwrap();
+        assert!(migrated);
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.dpi, 200);
+        assert_eq!(
+            settings.formats,
+            vec![OutputFormat::Txt, OutputFormat::Json]
+        );
+        assert_eq!(settings.ocr_concurrency, 8);
+        assert!(settings.output_directory.is_none());
+        assert_eq!(settings.retry, RetrySettings::default());
+        assert_eq!(settings.timeouts, TimeoutSettings::default());
+    }
+
+    #[test]
+    fn test_migrate_current_version_is_noop() {
+        let value = serde_json::to_value(Settings::default()).unwrap();
+        let (settings, migrated) = migrate(value).unwrap();
+        assert!(!migrated);
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn test_migrate_rejects_non_object() {
+        assert!(migrate(json!([1, 2])).is_err());
+    }
+
+    #[test]
+    fn test_apply_update_merges_nested() {
+        let updated = apply_update(
+            &Settings::default(),
+            json!({ "dpi": 300, "retry": { "maxRetries": 2 } }),
+        )
+        .unwrap();
+
+        assert_eq!(updated.dpi, 300);
+        assert_eq!(updated.retry.max_retries, 2);
+        assert_eq!(updated.retry.max_delay_secs, 15.0);
+        assert_eq!(updated.ocr_concurrency, 12);
+    }
+
+    #[test]
+    fn test_apply_update_rejects_invalid_types() {
+        let result = apply_update(&Settings::default(), json!({ "dpi": "high" }));
+        assert!(result.unwrap_err().contains("Invalid settings"));
+        assert!(apply_update(&Settings::default(), json!("dpi")).is_err());
+    }
+
+    #[test]
+    fn test_apply_update_clears_output_directory() {
+        let settings = Settings {
+            output_directory: Some("/out".to_string()),
+            ..Settings::default()
+        };
+        let updated = apply_update(&settings, json!({ "outputDirectory": null })).unwrap();
+        assert!(updated.output_directory.is_none());
+    }
+
+    #[test]
+    fn test_load_from_missing_file_returns_defaults() {
+        let temp = tempdir().unwrap();
+        let settings = load_from(&temp.path().join("settings.json")).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn test_load_from_migrates_and_rewrites_file() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("settings.json");
+        fs::write(&path, r#"{"dpi": 96}"#).unwrap();
+
+        let settings = load_from(&path).unwrap();
+        assert_eq!(settings.dpi, 96);
+
+        let rewritten: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(rewritten["version"], SETTINGS_VERSION);
+        assert_eq!(rewritten["retry"]["maxRetries"], 5);
+    }
+
+    #[test]
+    fn test_load_from_corrupt_file() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("settings.json");
+        fs::write(&path, "{not json").unwrap();
+        assert!(load_from(&path)
+            .unwrap_err()
+            .contains("Failed to parse settings"));
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("nested").join("settings.json");
+        let settings = Settings {
+            output_directory: Some("/books/out".to_string()),
+            ..Settings::default()
+        };
+
+        save_to(&path, &settings).unwrap();
+        assert_eq!(load_from(&path).unwrap(), settings);
+        // No temporary file is left next to it
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_current_uses_defaults_in_tests() {
+        assert_eq!(current().dpi, Settings::default().dpi);
+    }
+}
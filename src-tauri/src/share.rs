@@ -0,0 +1,163 @@
+use crate::error::TahweelError;
+use crate::path_policy::{self, PathAccess};
+use serde::Deserialize;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Where the share sheet points, in webview CSS pixels (usually the Share
+/// button's bounding rect)
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+pub struct ShareAnchor {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Whether `os` (a `std::env::consts::OS` value) has a native share sheet we can show
+pub fn share_supported(os: &str) -> bool {
+    matches!(os, "macos" | "ios")
+}
+
+/// Show the macOS sharing service picker (AirDrop, Messages, Mail, ...) for `path`
+#[cfg(target_os = "macos")]
+fn show_share_sheet(
+    app: &AppHandle,
+    path: &Path,
+    anchor: Option<ShareAnchor>,
+) -> Result<(), TahweelError> {
+    use objc2::runtime::AnyObject;
+    use objc2::AnyThread;
+    use objc2_app_kit::{NSSharingServicePicker, NSView};
+    use objc2_foundation::{NSArray, NSPoint, NSRect, NSRectEdge, NSSize, NSString, NSURL};
+    use tauri::Manager;
+
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| TahweelError::Internal("Main window not found".to_string()))?;
+    let path = path.to_string_lossy().to_string();
+    window
+        .with_webview(move |webview| {
+            // Runs on the main thread; `inner` is the window's live WKWebView
+            let view = unsafe { &*(webview.inner() as *const NSView) };
+            let url = NSURL::fileURLWithPath(&NSString::from_str(&path));
+            let item: &AnyObject = &url;
+            let items = NSArray::from_slice(&[item]);
+            let rect = match anchor {
+                Some(a) => NSRect::new(NSPoint::new(a.x, a.y), NSSize::new(a.width, a.height)),
+                None => view.bounds(),
+            };
+            let picker = unsafe {
+                NSSharingServicePicker::initWithItems(NSSharingServicePicker::alloc(), &items)
+            };
+            picker.showRelativeToRect_ofView_preferredEdge(rect, view, NSRectEdge::MinY);
+        })
+        .map_err(|e| TahweelError::Internal(format!("Failed to show share sheet: {}", e)))
+}
+
+/// Present `UIActivityViewController` (AirDrop, WhatsApp, Telegram, ...) for `path`
+#[cfg(target_os = "ios")]
+fn show_share_sheet(
+    app: &AppHandle,
+    path: &Path,
+    anchor: Option<ShareAnchor>,
+) -> Result<(), TahweelError> {
+    use objc2::runtime::AnyObject;
+    use objc2::MainThreadMarker;
+    use objc2_core_foundation::{CGPoint, CGRect, CGSize};
+    use objc2_foundation::{NSArray, NSString, NSURL};
+    use objc2_ui_kit::{UIActivityViewController, UIViewController};
+    use tauri::Manager;
+
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| TahweelError::Internal("Main window not found".to_string()))?;
+    let path = path.to_string_lossy().to_string();
+    window
+        .with_webview(move |webview| {
+            let mtm = MainThreadMarker::new().expect("with_webview runs on the main thread");
+            let presenter = unsafe { &*(webview.view_controller() as *const UIViewController) };
+            let url = NSURL::fileURLWithPath(&NSString::from_str(&path));
+            let item: &AnyObject = &url;
+            let items = NSArray::from_slice(&[item]);
+            let sheet = unsafe {
+                UIActivityViewController::initWithActivityItems_applicationActivities(
+                    UIActivityViewController::alloc(mtm),
+                    &items,
+                    None,
+                )
+            };
+            // iPad shows the sheet as a popover, which needs a source
+            if let (Some(popover), Some(view)) =
+                (sheet.popoverPresentationController(), presenter.view())
+            {
+                popover.setSourceView(Some(&*view));
+                if let Some(a) = anchor {
+                    popover.setSourceRect(CGRect::new(
+                        CGPoint::new(a.x, a.y),
+                        CGSize::new(a.width, a.height),
+                    ));
+                }
+            }
+            presenter.presentViewController_animated_completion(&sheet, true, None);
+        })
+        .map_err(|e| TahweelError::Internal(format!("Failed to show share sheet: {}", e)))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+fn show_share_sheet(
+    _app: &AppHandle,
+    _path: &Path,
+    _anchor: Option<ShareAnchor>,
+) -> Result<(), TahweelError> {
+    Err(TahweelError::InvalidInput(
+        "Sharing is not available on this platform".to_string(),
+    ))
+}
+
+/// Whether `share_output` works on this build; the UI hides its Share button otherwise
+#[tauri::command]
+pub fn can_share_output() -> bool {
+    share_supported(std::env::consts::OS)
+}
+
+/// Open the native share sheet for an output file so it can be sent
+/// straight to AirDrop, messaging apps, or mail
+#[tauri::command]
+#[tracing::instrument(skip(app), err)]
+pub async fn share_output(
+    app: AppHandle,
+    path: String,
+    anchor: Option<ShareAnchor>,
+) -> Result<(), TahweelError> {
+    let path = path_policy::check(Path::new(&path), PathAccess::Open)?;
+    if !path.is_file() {
+        return Err(TahweelError::NotFound(format!(
+            "File not found: {}",
+            path.display()
+        )));
+    }
+    show_share_sheet(&app, &path, anchor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_supported() {
+        assert!(share_supported("macos"));
+        assert!(share_supported("ios"));
+        assert!(!share_supported("android"));
+        assert!(!share_supported("windows"));
+        assert!(!share_supported("linux"));
+    }
+
+    #[test]
+    fn test_anchor_deserializes_from_dom_rect() {
+        let anchor: ShareAnchor =
+            serde_json::from_str(r#"{"x": 10.5, "y": 20, "width": 80, "height": 32}"#).unwrap();
+        assert_eq!(anchor.x, 10.5);
+        assert_eq!(anchor.height, 32.0);
+    }
+}
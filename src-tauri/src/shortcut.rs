@@ -0,0 +1,90 @@
+use crate::error::TahweelError;
+use crate::settings::{self, QuickOcrSettings, QuickOcrSource};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_global_shortcut::{
+    Builder, GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState,
+};
+
+/// Payload of the `quick-ocr` event; the webview holds the access token, so it
+/// runs the capture or clipboard OCR itself
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct QuickOcrRequest {
+    pub source: QuickOcrSource,
+}
+
+/// Whether a shortcut event should start a quick OCR (only on key down)
+pub fn should_trigger(state: ShortcutState) -> bool {
+    state == ShortcutState::Pressed
+}
+
+/// Check that `accelerator` is a shortcut the plugin can register
+pub fn parse(accelerator: &str) -> Result<Shortcut, TahweelError> {
+    accelerator.parse::<Shortcut>().map_err(|e| {
+        TahweelError::InvalidInput(format!("Invalid shortcut '{}': {}", accelerator, e))
+    })
+}
+
+fn handle_shortcut(app: &AppHandle, _shortcut: &Shortcut, event: ShortcutEvent) {
+    if !should_trigger(event.state) {
+        return;
+    }
+    let request = QuickOcrRequest {
+        source: settings::current().quick_ocr.source,
+    };
+    tracing::info!(source = ?request.source, "Quick OCR shortcut pressed");
+    let _ = app.emit("quick-ocr", request);
+}
+
+/// Replace the registered shortcut with the one in `quick_ocr` (none when disabled)
+pub fn apply(app: &AppHandle, quick_ocr: &QuickOcrSettings) -> Result<(), TahweelError> {
+    let shortcuts = app.global_shortcut();
+    shortcuts
+        .unregister_all()
+        .map_err(|e| TahweelError::Internal(format!("Failed to clear shortcuts: {}", e)))?;
+    if !quick_ocr.enabled {
+        return Ok(());
+    }
+    let shortcut = parse(&quick_ocr.shortcut)?;
+    shortcuts.register(shortcut).map_err(|e| {
+        TahweelError::InvalidInput(format!(
+            "Shortcut '{}' could not be registered (it may be used by another app): {}",
+            quick_ocr.shortcut, e
+        ))
+    })
+}
+
+/// Install the plugin and register the configured shortcut; call once from `setup`
+pub fn init(app: &AppHandle) -> Result<(), TahweelError> {
+    app.plugin(Builder::new().with_handler(handle_shortcut).build())
+        .map_err(|e| TahweelError::Internal(format!("Failed to load shortcuts: {}", e)))?;
+    apply(app, &settings::current().quick_ocr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triggers_on_press_only() {
+        assert!(should_trigger(ShortcutState::Pressed));
+        assert!(!should_trigger(ShortcutState::Released));
+    }
+
+    #[test]
+    fn test_parse_default_shortcut() {
+        assert!(parse(&QuickOcrSettings::default().shortcut).is_ok());
+        assert!(matches!(parse(""), Err(TahweelError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_request_serialization() {
+        let request = QuickOcrRequest {
+            source: QuickOcrSource::Clipboard,
+        };
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            serde_json::json!({"source": "clipboard"})
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use crate::docx::write_docx_streamed;
+use crate::encryption;
+use crate::error::TahweelError;
+use crate::footnotes::page_text_with_footnotes;
+use crate::long_path;
+use crate::output::{DocumentMetadata, OutputFormat};
+use crate::path_policy::{self, PathAccess};
+use crate::postprocess::{self, PostprocessStep};
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Folder of the workspace holding one spool directory per job
+const SPOOL_DIR: &str = "spool";
+
+/// Recognized text of one page, kept on disk until the outputs are assembled
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SpooledPage {
+    pub text: String,
+    #[serde(default)]
+    pub footnotes: Option<String>,
+}
+
+/// Entry of the JSON output, in the frontend writer's shape
+#[derive(Serialize)]
+struct JsonPage<'a> {
+    page: usize,
+    content: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    footnotes: Option<&'a str>,
+}
+
+/// Spool directory of `job_id` under `workspace`. Job ids are generated by the
+/// frontend (UUIDs); anything that could leave the spool folder is rejected.
+pub fn spool_dir(workspace: &Path, job_id: &str) -> Result<PathBuf, TahweelError> {
+    let valid = !job_id.is_empty()
+        && job_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(TahweelError::InvalidInput(format!(
+            "Invalid job id: {}",
+            job_id
+        )));
+    }
+    Ok(workspace.join(SPOOL_DIR).join(job_id))
+}
+
+/// File of a page (1-based): indexed by page number, so pages can arrive in any order
+fn page_file(dir: &Path, page: usize) -> PathBuf {
+    dir.join(format!("{:06}.json", page))
+}
+
+/// Encryption scope of a spool directory
+fn key_scope(dir: &Path) -> String {
+    dir.to_string_lossy().to_string()
+}
+
+/// Store `pages` as pages `first_page`, `first_page + 1`, ... (1-based),
+/// encrypted when the workspace is
+pub fn spool(dir: &Path, first_page: usize, pages: &[SpooledPage]) -> Result<(), TahweelError> {
+    fs::create_dir_all(dir)?;
+    let key = encryption::key_if_enabled(&key_scope(dir))?;
+    for (offset, page) in pages.iter().enumerate() {
+        let data = serde_json::to_vec(page)?;
+        encryption::write_file(&page_file(dir, first_page + offset), &data, key)?;
+    }
+    Ok(())
+}
+
+/// Read a spooled page; a page that was never spooled is empty
+pub fn read_page(dir: &Path, page: usize) -> Result<SpooledPage, TahweelError> {
+    let path = page_file(dir, page);
+    if !path.exists() {
+        return Ok(SpooledPage::default());
+    }
+    Ok(serde_json::from_slice(&encryption::read_file(&path)?)?)
+}
+
+/// Read a spooled page with the page-level post-processing `steps` applied
+fn read_processed(
+    dir: &Path,
+    page: usize,
+    steps: &[PostprocessStep],
+) -> Result<SpooledPage, TahweelError> {
+    let mut spooled = read_page(dir, page)?;
+    if !steps.is_empty() {
+        spooled.text = postprocess::run_page_steps(&spooled.text, page as u32, steps)?;
+    }
+    Ok(spooled)
+}
+
+fn write_txt(
+    dir: &Path,
+    path: &str,
+    page_count: usize,
+    separator: &str,
+    steps: &[PostprocessStep],
+) -> Result<(), TahweelError> {
+    let mut out = BufWriter::new(fs::File::create(path)?);
+    for page in 1..=page_count {
+        let spooled = read_processed(dir, page, steps)?;
+        if page > 1 {
+            out.write_all(separator.as_bytes())?;
+        }
+        let text = page_text_with_footnotes(&spooled.text, spooled.footnotes.as_deref());
+        out.write_all(text.as_bytes())?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Same layout as the frontend's `JSON.stringify(pages, null, 2)`
+fn write_json(
+    dir: &Path,
+    path: &str,
+    page_count: usize,
+    languages: &[Option<String>],
+    steps: &[PostprocessStep],
+) -> Result<(), TahweelError> {
+    let mut out = BufWriter::new(fs::File::create(path)?);
+    if page_count == 0 {
+        out.write_all(b"[]")?;
+        out.flush()?;
+        return Ok(());
+    }
+    out.write_all(b"[\n")?;
+    for page in 1..=page_count {
+        let spooled = read_processed(dir, page, steps)?;
+        let record = JsonPage {
+            page,
+            content: spooled.text.trim(),
+            language: languages
+                .get(page - 1)
+                .and_then(|language| language.as_deref())
+                .filter(|language| !language.is_empty()),
+            footnotes: spooled
+                .footnotes
+                .as_deref()
+                .map(str::trim)
+                .filter(|notes| !notes.is_empty()),
+        };
+        let json = serde_json::to_string_pretty(&record)?;
+        for (index, line) in json.lines().enumerate() {
+            if index > 0 {
+                out.write_all(b"\n")?;
+            }
+            out.write_all(b"  ")?;
+            out.write_all(line.as_bytes())?;
+        }
+        if page < page_count {
+            out.write_all(b",")?;
+        }
+        out.write_all(b"\n")?;
+    }
+    out.write_all(b"]")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Write `<output_path>.<format>` from the spool, one page in memory at a time.
+/// Page-level post-processing `steps` run on each page as it is read;
+/// document-level ones cannot, as only one page is in memory.
+#[allow(clippy::too_many_arguments)]
+pub fn write_outputs(
+    dir: &Path,
+    output_path: &str,
+    formats: &[OutputFormat],
+    page_count: usize,
+    page_separator: &str,
+    languages: &[Option<String>],
+    metadata: &DocumentMetadata,
+    steps: &[PostprocessStep],
+) -> Result<Vec<String>, TahweelError> {
+    let mut paths = Vec::new();
+    for format in formats {
+        let path = match format {
+            OutputFormat::Txt => {
+                let path = format!("{}.txt", output_path);
+                write_txt(dir, &path, page_count, page_separator, steps)?;
+                path
+            }
+            OutputFormat::Json => {
+                let path = format!("{}.json", output_path);
+                write_json(dir, &path, page_count, languages, steps)?;
+                path
+            }
+            OutputFormat::Docx => {
+                let path = format!("{}.docx", output_path);
+                write_docx_streamed(&path, page_count, metadata, |index| {
+                    let page = read_processed(dir, index + 1, steps).map_err(String::from)?;
+                    Ok((page.text, page.footnotes))
+                })
+                .map_err(TahweelError::Io)?;
+                path
+            }
+        };
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Remove a job's spool and its encryption key
+pub fn discard(dir: &Path) {
+    let _ = fs::remove_dir_all(dir);
+    encryption::forget(&key_scope(dir));
+}
+
+/// Spool recognized pages of a large document to the workspace, starting at
+/// `first_page` (1-based)
+#[tauri::command]
+#[tracing::instrument(skip(pages), fields(count = pages.len()), err)]
+pub async fn spool_pages(
+    job_id: String,
+    first_page: usize,
+    pages: Vec<SpooledPage>,
+) -> Result<(), TahweelError> {
+    let dir = spool_dir(&path_policy::workspace(), &job_id)?;
+    tauri::async_runtime::spawn_blocking(move || spool(&dir, first_page.max(1), &pages))
+        .await
+        .map_err(|e| TahweelError::Internal(format!("Failed to spool pages: {}", e)))?
+}
+
+/// Assemble a job's outputs from its spooled pages, streaming them page by page.
+/// `languages` gives each page's detected language for the JSON output.
+#[tauri::command]
+#[tracing::instrument(skip(page_separator, languages, metadata), err)]
+pub async fn write_spooled_outputs(
+    job_id: String,
+    output_path: String,
+    formats: Vec<OutputFormat>,
+    page_count: usize,
+    page_separator: String,
+    languages: Option<Vec<Option<String>>>,
+    metadata: Option<DocumentMetadata>,
+) -> Result<Vec<String>, TahweelError> {
+    let dir = spool_dir(&path_policy::workspace(), &job_id)?;
+    let base = path_policy::check(Path::new(&output_path), PathAccess::Write)?;
+    let output_path = long_path::path_string(&base)?;
+    let steps = settings::current().postprocessing;
+    tauri::async_runtime::spawn_blocking(move || {
+        write_outputs(
+            &dir,
+            &output_path,
+            &formats,
+            page_count,
+            &page_separator,
+            &languages.unwrap_or_default(),
+            &metadata.unwrap_or_default(),
+            &steps,
+        )
+    })
+    .await
+    .map_err(|e| TahweelError::Internal(format!("Failed to write outputs: {}", e)))?
+}
+
+/// Remove a job's spooled pages (after its outputs are written, or when it fails)
+#[tauri::command]
+pub async fn discard_spool(job_id: String) -> Result<(), TahweelError> {
+    discard(&spool_dir(&path_policy::workspace(), &job_id)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    const SEPARATOR: &str = "\n\nPAGE_SEPARATOR\n\n";
+
+    fn page(text: &str, footnotes: Option<&str>) -> SpooledPage {
+        SpooledPage {
+            text: text.to_string(),
+            footnotes: footnotes.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_spool_dir_rejects_unsafe_job_ids() {
+        let workspace = Path::new("/work");
+        assert_eq!(
+            spool_dir(workspace, "job-1").unwrap(),
+            workspace.join("spool").join("job-1")
+        );
+        assert!(spool_dir(workspace, "../etc").is_err());
+        assert!(spool_dir(workspace, "").is_err());
+    }
+
+    #[test]
+    fn test_pages_arrive_in_any_order() {
+        let root = tempdir().unwrap();
+        let dir = spool_dir(root.path(), "job").unwrap();
+        spool(&dir, 3, &[page("three", None)]).unwrap();
+        spool(&dir, 1, &[page("one", Some("note")), page("two", None)]).unwrap();
+
+        assert_eq!(read_page(&dir, 1).unwrap(), page("one", Some("note")));
+        assert_eq!(read_page(&dir, 3).unwrap().text, "three");
+        assert_eq!(read_page(&dir, 4).unwrap(), SpooledPage::default());
+    }
+
+    #[test]
+    fn test_write_outputs_streams_all_formats() {
+        let root = tempdir().unwrap();
+        let dir = spool_dir(root.path(), "job").unwrap();
+        spool(&dir, 1, &[page(" one ", Some("note")), page("two", None)]).unwrap();
+        let base = root.path().join("book");
+        let base = base.to_str().unwrap();
+
+        let paths = write_outputs(
+            &dir,
+            base,
+            &[OutputFormat::Txt, OutputFormat::Json, OutputFormat::Docx],
+            3,
+            SEPARATOR,
+            &[Some("ar".to_string())],
+            &DocumentMetadata::default(),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(paths.len(), 3);
+
+        let txt = fs::read_to_string(format!("{}.txt", base)).unwrap();
+        assert_eq!(
+            txt,
+            format!(
+                "{}{}two{}",
+                page_text_with_footnotes("one", Some("note")),
+                SEPARATOR,
+                SEPARATOR
+            )
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(format!("{}.json", base)).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                { "page": 1, "content": "one", "language": "ar", "footnotes": "note" },
+                { "page": 2, "content": "two" },
+                { "page": 3, "content": "" }
+            ])
+        );
+
+        let file = fs::File::open(format!("{}.docx", base)).unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        let mut document = String::new();
+        archive
+            .by_name("word/document.xml")
+            .unwrap()
+            .read_to_string(&mut document)
+            .unwrap();
+        assert!(document.contains(">two<"));
+        assert_eq!(document.matches("w:type=\"page\"").count(), 2);
+    }
+
+    #[test]
+    fn test_json_layout_matches_frontend() {
+        let root = tempdir().unwrap();
+        let dir = spool_dir(root.path(), "job").unwrap();
+        spool(&dir, 1, &[page("a", None)]).unwrap();
+        let base = root.path().join("book");
+        let base = base.to_str().unwrap();
+
+        write_outputs(
+            &dir,
+            base,
+            &[OutputFormat::Json],
+            1,
+            SEPARATOR,
+            &[],
+            &DocumentMetadata::default(),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(format!("{}.json", base)).unwrap(),
+            "[\n  {\n    \"page\": 1,\n    \"content\": \"a\"\n  }\n]"
+        );
+    }
+
+    #[test]
+    fn test_write_outputs_applies_page_steps() {
+        let root = tempdir().unwrap();
+        let dir = spool_dir(root.path(), "job").unwrap();
+        spool(&dir, 1, &[page("أَحْمَد   قال", None)]).unwrap();
+        let base = root.path().join("book");
+        let base = base.to_str().unwrap();
+
+        write_outputs(
+            &dir,
+            base,
+            &[OutputFormat::Txt],
+            1,
+            SEPARATOR,
+            &[],
+            &DocumentMetadata::default(),
+            &[
+                PostprocessStep::RemoveHeadersFooters,
+                PostprocessStep::StripDiacritics { keep_shadda: false },
+                PostprocessStep::NormalizeWhitespace,
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(format!("{}.txt", base)).unwrap(),
+            "أحمد قال"
+        );
+    }
+
+    #[test]
+    fn test_discard_removes_spool() {
+        let root = tempdir().unwrap();
+        let dir = spool_dir(root.path(), "job").unwrap();
+        spool(&dir, 1, &[page("a", None)]).unwrap();
+        discard(&dir);
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,324 @@
+use crate::layout::{BoundingBox, OcrWord, PageLayout};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Horizontal gap (in multiples of the median word height) that separates table cells
+const CELL_GAP_FACTOR: f64 = 2.0;
+/// Minimum number of consecutive aligned lines to treat as a table
+const MIN_TABLE_ROWS: usize = 3;
+/// Minimum number of columns for a table
+const MIN_TABLE_COLUMNS: usize = 2;
+
+/// Delimited text format for extracted tables
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    #[default]
+    Csv,
+    Tsv,
+}
+
+impl TableFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            TableFormat::Csv => "csv",
+            TableFormat::Tsv => "tsv",
+        }
+    }
+
+    fn delimiter(self) -> char {
+        match self {
+            TableFormat::Csv => ',',
+            TableFormat::Tsv => '\t',
+        }
+    }
+}
+
+/// A table reconstructed from word geometry, cells in reading order
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedTable {
+    pub bbox: BoundingBox,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Table region recorded in the JSON output, pointing at its extracted file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TableRegion {
+    pub page: u32,
+    pub bbox: BoundingBox,
+    pub rows: u32,
+    pub columns: u32,
+    pub path: String,
+}
+
+fn median_word_height(words: &[OcrWord]) -> u32 {
+    let mut heights: Vec<u32> = words.iter().map(|w| w.bbox.height).collect();
+    heights.sort_unstable();
+    heights.get(heights.len() / 2).copied().unwrap_or(0)
+}
+
+/// Split a line (already in reading order) into cells wherever the gap between
+/// neighbouring words exceeds `min_gap`.
+fn split_cells<'a>(line: &[&'a OcrWord], min_gap: u32, rtl: bool) -> Vec<Vec<&'a OcrWord>> {
+    let mut cells: Vec<Vec<&'a OcrWord>> = Vec::new();
+    for word in line {
+        let starts_new_cell = match cells.last().and_then(|cell| cell.last()) {
+            None => true,
+            Some(previous) => {
+                let gap = if rtl {
+                    previous.bbox.x.saturating_sub(word.bbox.right())
+                } else {
+                    word.bbox.x.saturating_sub(previous.bbox.right())
+                };
+                gap > min_gap
+            }
+        };
+        if starts_new_cell {
+            cells.push(vec![word]);
+        } else if let Some(cell) = cells.last_mut() {
+            cell.push(word);
+        }
+    }
+    cells
+}
+
+fn cell_text(cell: &[&OcrWord]) -> String {
+    cell.iter()
+        .map(|w| w.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Detect tables as runs of consecutive lines that split into the same number
+/// (at least two) of widely separated cells. Only geometry-aware pages can have tables.
+pub fn detect_tables(layout: &PageLayout, rtl: bool) -> Vec<DetectedTable> {
+    if !layout.has_geometry() {
+        return Vec::new();
+    }
+
+    let min_gap = (median_word_height(&layout.words) as f64 * CELL_GAP_FACTOR) as u32;
+    let lines: Vec<Vec<Vec<&OcrWord>>> = layout
+        .lines(rtl)
+        .iter()
+        .map(|line| split_cells(line, min_gap, rtl))
+        .collect();
+
+    let mut tables = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let columns = lines[start].len();
+        let mut end = start + 1;
+        while end < lines.len() && lines[end].len() == columns {
+            end += 1;
+        }
+
+        if columns >= MIN_TABLE_COLUMNS && end - start >= MIN_TABLE_ROWS {
+            let run = &lines[start..end];
+            let bbox = BoundingBox::union(run.iter().flatten().flatten().map(|w| &w.bbox))
+                .unwrap_or(BoundingBox {
+                    x: 0,
+                    y: 0,
+                    width: 0,
+                    height: 0,
+                });
+            tables.push(DetectedTable {
+                bbox,
+                rows: run
+                    .iter()
+                    .map(|cells| cells.iter().map(|c| cell_text(c)).collect())
+                    .collect(),
+            });
+        }
+        start = end;
+    }
+
+    tables
+}
+
+fn escape_field(field: &str, delimiter: char) -> String {
+    if field.contains(delimiter) || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Render table rows as delimited text
+pub fn render_table(rows: &[Vec<String>], format: TableFormat) -> String {
+    let delimiter = format.delimiter();
+    let mut out = String::new();
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|f| escape_field(f, delimiter)).collect();
+        out.push_str(&fields.join(&delimiter.to_string()));
+        out.push('\n');
+    }
+    out
+}
+
+/// Detect tables on geometry-aware pages and write each one to
+/// `<output_path>_tables/page-0001-table-1.csv` (or `.tsv`).
+/// Returns the table regions so they can be recorded in the JSON output.
+#[tauri::command]
+pub async fn extract_tables(
+    output_path: String,
+    pages: Vec<PageLayout>,
+    format: Option<TableFormat>,
+    rtl: bool,
+) -> Result<Vec<TableRegion>, String> {
+    let format = format.unwrap_or_default();
+    let dir = PathBuf::from(format!("{}_tables", output_path));
+    let mut regions = Vec::new();
+
+    for (index, layout) in pages.iter().enumerate() {
+        let page = index as u32 + 1;
+        for (table_index, table) in detect_tables(layout, rtl).into_iter().enumerate() {
+            fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create tables directory: {}", e))?;
+            let path = dir.join(format!(
+                "page-{:04}-table-{}.{}",
+                page,
+                table_index + 1,
+                format.extension()
+            ));
+            fs::write(&path, render_table(&table.rows, format))
+                .map_err(|e| format!("Failed to write table: {}", e))?;
+
+            regions.push(TableRegion {
+                page,
+                bbox: table.bbox,
+                rows: table.rows.len() as u32,
+                columns: table.rows.first().map(|r| r.len()).unwrap_or(0) as u32,
+                path: path.to_string_lossy().to_string(),
+            });
+        }
+    }
+
+    Ok(regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn word(text: &str, x: u32, y: u32) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            bbox: BoundingBox {
+                x,
+                y,
+                width: 40,
+                height: 20,
+            },
+            confidence: None,
+        }
+    }
+
+    fn layout(words: Vec<OcrWord>) -> PageLayout {
+        PageLayout {
+            width: 1000,
+            height: 1000,
+            text: String::new(),
+            words,
+        }
+    }
+
+    /// A paragraph line followed by a 3x3 table with wide column gaps
+    fn page_with_table() -> PageLayout {
+        let mut words = vec![
+            word("intro", 10, 10),
+            word("text", 55, 10),
+            word("here", 100, 10),
+        ];
+        for (row, y) in [100, 140, 180].into_iter().enumerate() {
+            words.push(word(&format!("a{}", row), 10, y));
+            words.push(word(&format!("b{}", row), 300, y));
+            words.push(word(&format!("c{}", row), 600, y));
+            words.push(word("x", 645, y));
+        }
+        layout(words)
+    }
+
+    #[test]
+    fn test_detect_tables_finds_aligned_rows() {
+        let tables = detect_tables(&page_with_table(), false);
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].rows.len(), 3);
+        assert_eq!(tables[0].rows[0], vec!["a0", "b0", "c0 x"]);
+        assert_eq!(tables[0].bbox.y, 100);
+        assert_eq!(tables[0].bbox.x, 10);
+    }
+
+    #[test]
+    fn test_detect_tables_rtl_column_order() {
+        let tables = detect_tables(&page_with_table(), true);
+        assert_eq!(tables[0].rows[2], vec!["x c2", "b2", "a2"]);
+    }
+
+    #[test]
+    fn test_detect_tables_ignores_short_runs() {
+        let words = vec![
+            word("a", 10, 100),
+            word("b", 400, 100),
+            word("c", 10, 140),
+            word("d", 400, 140),
+        ];
+        assert!(detect_tables(&layout(words), false).is_empty());
+    }
+
+    #[test]
+    fn test_detect_tables_without_geometry() {
+        assert!(detect_tables(&layout(vec![]), false).is_empty());
+    }
+
+    #[test]
+    fn test_render_table_escapes_fields() {
+        let rows = vec![vec!["a,b".to_string(), "say \"hi\"".to_string()]];
+        assert_eq!(
+            render_table(&rows, TableFormat::Csv),
+            "\"a,b\",\"say \"\"hi\"\"\"\n"
+        );
+        assert_eq!(
+            render_table(&rows, TableFormat::Tsv),
+            "a,b\t\"say \"\"hi\"\"\"\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extract_tables_writes_files() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("book").to_string_lossy().to_string();
+
+        let regions = extract_tables(
+            base.clone(),
+            vec![layout(vec![]), page_with_table()],
+            Some(TableFormat::Tsv),
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].page, 2);
+        assert_eq!(regions[0].rows, 3);
+        assert_eq!(regions[0].columns, 3);
+        assert!(regions[0]
+            .path
+            .ends_with("book_tables/page-0002-table-1.tsv"));
+        let content = fs::read_to_string(&regions[0].path).unwrap();
+        assert_eq!(content.lines().next(), Some("a0\tb0\tc0 x"));
+    }
+
+    #[tokio::test]
+    async fn test_extract_tables_no_tables_creates_nothing() {
+        let temp = tempdir().unwrap();
+        let base = temp.path().join("book").to_string_lossy().to_string();
+
+        let regions = extract_tables(base.clone(), vec![layout(vec![])], None, false)
+            .await
+            .unwrap();
+        assert!(regions.is_empty());
+        assert!(!PathBuf::from(format!("{}_tables", base)).exists());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::error::TahweelError;
+use crate::path_policy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Folder of the workspace cleaned-up directories are moved to
+pub(crate) const TRASH_DIR: &str = "tahweel-trash";
+/// How long a cleaned-up directory can be restored with `undo_cleanup`
+const RETENTION_MS: u64 = 15 * 60 * 1000;
+/// Trash entry layout: `<entry>/entry.json` and the directory as `<entry>/contents`
+const MANIFEST_FILE: &str = "entry.json";
+const CONTENTS_DIR: &str = "contents";
+
+/// Where a trashed directory came from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct TrashEntry {
+    #[serde(rename = "jobId", default)]
+    job_id: Option<String>,
+    #[serde(rename = "originalPath")]
+    original_path: String,
+    #[serde(rename = "trashedAtMs")]
+    trashed_at_ms: u64,
+}
+
+/// The trash area of `workspace`
+pub fn trash_root(workspace: &Path) -> PathBuf {
+    workspace.join(TRASH_DIR)
+}
+
+/// The app's trash, resolved like the paths the path policy hands out
+fn workspace_trash() -> PathBuf {
+    let workspace = path_policy::workspace();
+    trash_root(&workspace.canonicalize().unwrap_or(workspace))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Move `dir` into the trash under `root`, recording the job it belonged to.
+/// The trash itself (or anything in it) cannot be trashed.
+pub fn move_to_trash(
+    root: &Path,
+    dir: &Path,
+    job_id: Option<&str>,
+    now_ms: u64,
+) -> Result<PathBuf, TahweelError> {
+    if dir.starts_with(root) || root.starts_with(dir) {
+        return Err(TahweelError::InvalidInput(format!(
+            "Cannot clean up the trash: {}",
+            dir.display()
+        )));
+    }
+    let entry = root.join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&entry)?;
+    let manifest = TrashEntry {
+        job_id: job_id.map(str::to_string),
+        original_path: dir.to_string_lossy().to_string(),
+        trashed_at_ms: now_ms,
+    };
+    fs::write(entry.join(MANIFEST_FILE), serde_json::to_vec(&manifest)?)?;
+    if let Err(e) = fs::rename(dir, entry.join(CONTENTS_DIR)) {
+        let _ = fs::remove_dir_all(&entry);
+        return Err(TahweelError::Io(format!(
+            "Failed to move {} to the trash: {}",
+            dir.display(),
+            e
+        )));
+    }
+    Ok(entry)
+}
+
+/// Trash entries under `root` with their manifests; unreadable ones are left out
+fn entries(root: &Path) -> Vec<(PathBuf, TrashEntry)> {
+    let Ok(dirs) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    dirs.filter_map(|dir| dir.ok().map(|d| d.path()))
+        .filter_map(|dir| {
+            let manifest = fs::read(dir.join(MANIFEST_FILE)).ok()?;
+            Some((dir, serde_json::from_slice(&manifest).ok()?))
+        })
+        .collect()
+}
+
+/// Permanently delete entries trashed more than `retention_ms` ago, and entries
+/// without a readable manifest; returns how many were deleted
+pub fn purge_expired(root: &Path, now_ms: u64, retention_ms: u64) -> usize {
+    let Ok(dirs) = fs::read_dir(root) else {
+        return 0;
+    };
+    let mut purged = 0;
+    for dir in dirs.filter_map(|dir| dir.ok().map(|d| d.path())) {
+        let expired = fs::read(dir.join(MANIFEST_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_slice::<TrashEntry>(&data).ok())
+            .map_or(true, |entry| {
+                now_ms.saturating_sub(entry.trashed_at_ms) > retention_ms
+            });
+        if expired && fs::remove_dir_all(&dir).is_ok() {
+            purged += 1;
+        }
+    }
+    purged
+}
+
+/// Move the directories trashed for `job_id` back where they were; returns the
+/// restored paths. A directory whose original path is taken again stays in the trash.
+pub fn restore(root: &Path, job_id: &str) -> Result<Vec<String>, TahweelError> {
+    let mut restored = Vec::new();
+    for (dir, entry) in entries(root) {
+        if entry.job_id.as_deref() != Some(job_id) {
+            continue;
+        }
+        let original = Path::new(&entry.original_path);
+        if original.exists() {
+            tracing::warn!(path = %original.display(), "Not restoring over an existing directory");
+            continue;
+        }
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(dir.join(CONTENTS_DIR), original).map_err(|e| {
+            TahweelError::Io(format!("Failed to restore {}: {}", original.display(), e))
+        })?;
+        let _ = fs::remove_dir_all(&dir);
+        restored.push(entry.original_path);
+    }
+    Ok(restored)
+}
+
+/// Soft-delete a workspace directory: move it to the trash and drop expired
+/// entries. Used by `cleanup_temp_dir` for directories that passed the path policy.
+pub fn soft_delete(dir: &Path, job_id: Option<&str>) -> Result<(), TahweelError> {
+    let root = workspace_trash();
+    let now = now_ms();
+    purge_expired(&root, now, RETENTION_MS);
+    move_to_trash(&root, dir, job_id, now)?;
+    Ok(())
+}
+
+/// Empty expired trash entries left by an earlier run; call once from `setup`
+pub fn init() {
+    let purged = purge_expired(&workspace_trash(), now_ms(), RETENTION_MS);
+    if purged > 0 {
+        tracing::info!(purged, "Emptied expired trash entries");
+    }
+}
+
+/// Restore the workspace directories cleaned up for `job_id` in the last 15
+/// minutes; returns the restored paths (none when nothing is left to restore)
+#[tauri::command]
+#[tracing::instrument(err)]
+pub async fn undo_cleanup(job_id: String) -> Result<Vec<String>, TahweelError> {
+    restore(&workspace_trash(), &job_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn job_dir(workspace: &Path, name: &str) -> PathBuf {
+        let dir = workspace.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("page-0001.png"), b"png").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_trash_and_restore() {
+        let workspace = tempdir().unwrap();
+        let root = trash_root(workspace.path());
+        let first = job_dir(workspace.path(), "tahweel-a");
+        let second = job_dir(workspace.path(), "tahweel-b");
+        let other = job_dir(workspace.path(), "tahweel-c");
+
+        move_to_trash(&root, &first, Some("job-1"), 1_000).unwrap();
+        move_to_trash(&root, &second, Some("job-1"), 1_000).unwrap();
+        move_to_trash(&root, &other, Some("job-2"), 1_000).unwrap();
+        assert!(!first.exists() && !second.exists() && !other.exists());
+
+        let mut restored = restore(&root, "job-1").unwrap();
+        restored.sort();
+        assert_eq!(
+            restored,
+            vec![
+                first.to_string_lossy().to_string(),
+                second.to_string_lossy().to_string()
+            ]
+        );
+        assert_eq!(fs::read(first.join("page-0001.png")).unwrap(), b"png");
+        assert!(!other.exists());
+        assert_eq!(entries(&root).len(), 1);
+        assert!(restore(&root, "job-1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_restore_keeps_entry_when_path_is_taken() {
+        let workspace = tempdir().unwrap();
+        let root = trash_root(workspace.path());
+        let dir = job_dir(workspace.path(), "tahweel-a");
+        move_to_trash(&root, &dir, Some("job-1"), 1_000).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+
+        assert!(restore(&root, "job-1").unwrap().is_empty());
+        assert_eq!(entries(&root).len(), 1);
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let workspace = tempdir().unwrap();
+        let root = trash_root(workspace.path());
+        let old = job_dir(workspace.path(), "tahweel-old");
+        let recent = job_dir(workspace.path(), "tahweel-new");
+        move_to_trash(&root, &old, Some("job-1"), 1_000).unwrap();
+        move_to_trash(&root, &recent, Some("job-2"), 9_000).unwrap();
+        fs::create_dir_all(root.join("stray")).unwrap();
+
+        assert_eq!(purge_expired(&root, 10_000, 5_000), 2);
+        let left: Vec<_> = entries(&root).into_iter().map(|(_, e)| e).collect();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].job_id.as_deref(), Some("job-2"));
+    }
+
+    #[test]
+    fn test_trash_cannot_be_trashed() {
+        let workspace = tempdir().unwrap();
+        let root = trash_root(workspace.path());
+        fs::create_dir_all(root.join("entry")).unwrap();
+
+        for dir in [
+            root.clone(),
+            root.join("entry"),
+            workspace.path().to_path_buf(),
+        ] {
+            let err = move_to_trash(&root, &dir, None, 0).unwrap_err();
+            assert_eq!(err.kind(), "invalidInput");
+        }
+        assert!(root.join("entry").exists());
+    }
+}
@@ -0,0 +1,261 @@
+use crate::error::TahweelError;
+use crate::path_policy::{self, PathAccess};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+const TRAY_ID: &str = "main";
+const MENU_STATUS: &str = "tray-status";
+const MENU_PAUSE: &str = "tray-pause";
+const MENU_OPEN_OUTPUT: &str = "tray-open-output";
+const MENU_QUIT: &str = "tray-quit";
+
+/// Queue snapshot the frontend pushes whenever processing progresses
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TrayStatus {
+    /// Files not finished yet, including the current one
+    pub remaining: u32,
+    #[serde(rename = "currentFile")]
+    pub current_file: Option<String>,
+    /// Progress of the current file (0-100)
+    pub percentage: u32,
+    pub paused: bool,
+    #[serde(rename = "outputFolder")]
+    pub output_folder: Option<String>,
+}
+
+/// Payload of the `queue-pause-changed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct PauseChanged {
+    pub paused: bool,
+}
+
+static STATUS: Mutex<Option<TrayStatus>> = Mutex::new(None);
+
+/// Menu items whose text follows the queue status
+struct TrayMenu {
+    status: MenuItem<Wry>,
+    pause: MenuItem<Wry>,
+    open_output: MenuItem<Wry>,
+}
+
+fn current_status() -> TrayStatus {
+    STATUS
+        .lock()
+        .map(|s| s.clone().unwrap_or_default())
+        .unwrap_or_default()
+}
+
+/// One-line summary used for the disabled status item
+pub fn status_text(status: &TrayStatus) -> String {
+    if status.remaining == 0 {
+        return "No files in queue".to_string();
+    }
+    let files = if status.remaining == 1 {
+        "1 file remaining".to_string()
+    } else {
+        format!("{} files remaining", status.remaining)
+    };
+    if status.paused {
+        return format!("Paused, {}", files);
+    }
+    match &status.current_file {
+        Some(name) => format!("{} ({}: {}%)", files, name, status.percentage.min(100)),
+        None => files,
+    }
+}
+
+/// Tooltip shown when hovering the tray icon
+pub fn tooltip_text(status: &TrayStatus) -> String {
+    format!("Tahweel - {}", status_text(status))
+}
+
+fn pause_text(status: &TrayStatus) -> &'static str {
+    if status.paused {
+        "Resume queue"
+    } else {
+        "Pause queue"
+    }
+}
+
+/// Create the tray icon and its menu; call once from `setup`
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let status = current_status();
+    let status_item =
+        MenuItem::with_id(app, MENU_STATUS, status_text(&status), false, None::<&str>)?;
+    let pause_item = MenuItem::with_id(
+        app,
+        MENU_PAUSE,
+        pause_text(&status),
+        status.remaining > 0,
+        None::<&str>,
+    )?;
+    let open_output_item = MenuItem::with_id(
+        app,
+        MENU_OPEN_OUTPUT,
+        "Open output folder",
+        status.output_folder.is_some(),
+        None::<&str>,
+    )?;
+    let quit_item = MenuItem::with_id(app, MENU_QUIT, "Quit Tahweel", true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status_item,
+            &separator,
+            &pause_item,
+            &open_output_item,
+            &quit_item,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(tooltip_text(&status))
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    app.manage(TrayMenu {
+        status: status_item,
+        pause: pause_item,
+        open_output: open_output_item,
+    });
+    Ok(())
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        MENU_PAUSE => {
+            let paused = {
+                let Ok(mut guard) = STATUS.lock() else {
+                    return;
+                };
+                let status = guard.get_or_insert_with(TrayStatus::default);
+                status.paused = !status.paused;
+                status.paused
+            };
+            tracing::info!(paused, "Queue pause toggled from tray");
+            let _ = app.emit("queue-pause-changed", PauseChanged { paused });
+            refresh(app);
+        }
+        MENU_OPEN_OUTPUT => {
+            let Some(folder) = current_status().output_folder else {
+                return;
+            };
+            let opened = path_policy::check(Path::new(&folder), PathAccess::Open)
+                .map_err(|e| e.to_string())
+                .and_then(|path| open::that(path).map_err(|e| e.to_string()));
+            if let Err(e) = opened {
+                tracing::warn!(error = %e, "Failed to open output folder from tray");
+            }
+        }
+        MENU_QUIT => app.exit(0),
+        _ => {}
+    }
+}
+
+/// Push the stored status into the tray tooltip and menu
+fn refresh(app: &AppHandle) {
+    let status = current_status();
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip_text(&status)));
+    }
+    if let Some(menu) = app.try_state::<TrayMenu>() {
+        let _ = menu.status.set_text(status_text(&status));
+        let _ = menu.pause.set_text(pause_text(&status));
+        let _ = menu.pause.set_enabled(status.remaining > 0);
+        let _ = menu.open_output.set_enabled(status.output_folder.is_some());
+    }
+}
+
+/// Update the tray with the current queue status
+#[tauri::command]
+pub async fn update_tray_status(status: TrayStatus, app: AppHandle) -> Result<(), TahweelError> {
+    {
+        let mut guard = STATUS
+            .lock()
+            .map_err(|_| TahweelError::Internal("Tray status lock poisoned".to_string()))?;
+        *guard = Some(status);
+    }
+    refresh(&app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(remaining: u32) -> TrayStatus {
+        TrayStatus {
+            remaining,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_status_text_idle() {
+        assert_eq!(status_text(&status(0)), "No files in queue");
+    }
+
+    #[test]
+    fn test_status_text_with_current_file() {
+        let status = TrayStatus {
+            remaining: 3,
+            current_file: Some("book.pdf".to_string()),
+            percentage: 45,
+            ..Default::default()
+        };
+        assert_eq!(status_text(&status), "3 files remaining (book.pdf: 45%)");
+    }
+
+    #[test]
+    fn test_status_text_single_file_clamps_percentage() {
+        let status = TrayStatus {
+            remaining: 1,
+            current_file: Some("scan.png".to_string()),
+            percentage: 140,
+            ..Default::default()
+        };
+        assert_eq!(status_text(&status), "1 file remaining (scan.png: 100%)");
+    }
+
+    #[test]
+    fn test_status_text_paused() {
+        let status = TrayStatus {
+            remaining: 2,
+            current_file: Some("book.pdf".to_string()),
+            paused: true,
+            ..Default::default()
+        };
+        assert_eq!(status_text(&status), "Paused, 2 files remaining");
+        assert_eq!(pause_text(&status), "Resume queue");
+    }
+
+    #[test]
+    fn test_tooltip_text() {
+        assert_eq!(tooltip_text(&status(0)), "Tahweel - No files in queue");
+    }
+
+    #[test]
+    fn test_status_deserializes_camel_case() {
+        let status: TrayStatus = serde_json::from_value(serde_json::json!({
+            "remaining": 4,
+            "currentFile": "a.pdf",
+            "percentage": 10,
+            "paused": false,
+            "outputFolder": "/out",
+        }))
+        .unwrap();
+        assert_eq!(status.current_file.as_deref(), Some("a.pdf"));
+        assert_eq!(status.output_folder.as_deref(), Some("/out"));
+    }
+}
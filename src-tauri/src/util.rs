@@ -0,0 +1,40 @@
+/// Civil `(year, month, day)` in UTC for a Unix time
+pub fn civil_date(unix_secs: u64) -> (i64, i64, i64) {
+    let days = (unix_secs / 86_400) as i64;
+    // Howard Hinnant's days-from-civil inverse
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Percent-encode an object key or file name for a URL path, keeping `/` separators
+pub fn encode_key(key: &str) -> String {
+    key.split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(951_782_400), (2000, 2, 29));
+        assert_eq!(civil_date(1_709_210_096), (2024, 2, 29));
+    }
+
+    #[test]
+    fn test_encode_key() {
+        assert_eq!(encode_key("ocr/a b~c.txt"), "ocr/a%20b~c.txt");
+        assert_eq!(encode_key("كتاب"), "%D9%83%D8%AA%D8%A7%D8%A8");
+    }
+}
@@ -0,0 +1,233 @@
+use crate::error::TahweelError;
+use crate::long_path;
+use crate::output::OutputFormat;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Parts every DOCX writer (the frontend's `docx` package and `docx.rs`) produces
+const REQUIRED_DOCX_PARTS: &[&str] = &[
+    "[Content_Types].xml",
+    "_rels/.rels",
+    "word/_rels/document.xml.rels",
+    "word/document.xml",
+];
+/// A DOCX with its required parts cannot be smaller than this
+const MIN_DOCX_BYTES: u64 = 512;
+
+fn invalid(path: &Path, problem: impl std::fmt::Display) -> TahweelError {
+    TahweelError::OutputInvalid(format!("{}: {}", path.display(), problem))
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, TahweelError> {
+    fs::read(long_path::extended(path)).map_err(|e| invalid(path, e))
+}
+
+/// A TXT output holds a page separator between each pair of pages. Page text may
+/// contain the separator too (a blank line, say), so only missing ones are an error.
+pub fn verify_txt(path: &Path, page_count: usize, separator: &str) -> Result<(), TahweelError> {
+    let data = read(path)?;
+    let text = String::from_utf8(data).map_err(|_| invalid(path, "not valid UTF-8"))?;
+    if separator.is_empty() {
+        return Ok(());
+    }
+    let separators = text.matches(separator).count();
+    let expected = page_count.saturating_sub(1);
+    if separators < expected {
+        return Err(invalid(
+            path,
+            format!(
+                "{} page separators for {} pages (expected {})",
+                separators, page_count, expected
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// A JSON output parses and has one entry per page: an array of pages (the
+/// frontend writer) or an object with a `pages` array (`write_json_output`)
+pub fn verify_json(path: &Path, page_count: usize) -> Result<(), TahweelError> {
+    let data = read(path)?;
+    let value: serde_json::Value =
+        serde_json::from_slice(&data).map_err(|e| invalid(path, format!("invalid JSON: {}", e)))?;
+    let pages = value
+        .as_array()
+        .or_else(|| value.get("pages").and_then(|pages| pages.as_array()))
+        .ok_or_else(|| invalid(path, "no page list"))?;
+    if pages.len() != page_count {
+        return Err(invalid(
+            path,
+            format!("{} pages (expected {})", pages.len(), page_count),
+        ));
+    }
+    Ok(())
+}
+
+/// A DOCX output is a readable ZIP package with the required parts, and a
+/// non-empty main document
+pub fn verify_docx(path: &Path) -> Result<(), TahweelError> {
+    let file = fs::File::open(long_path::extended(path)).map_err(|e| invalid(path, e))?;
+    let size = file.metadata().map_err(|e| invalid(path, e))?.len();
+    if size < MIN_DOCX_BYTES {
+        return Err(invalid(path, format!("only {} bytes", size)));
+    }
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| invalid(path, format!("not a ZIP package: {}", e)))?;
+    if archive.len() < REQUIRED_DOCX_PARTS.len() {
+        return Err(invalid(
+            path,
+            format!(
+                "{} parts (expected at least {})",
+                archive.len(),
+                REQUIRED_DOCX_PARTS.len()
+            ),
+        ));
+    }
+    for part in REQUIRED_DOCX_PARTS {
+        let mut entry = archive
+            .by_name(part)
+            .map_err(|_| invalid(path, format!("missing part {}", part)))?;
+        // Reading the part checks its CRC
+        let mut content = Vec::new();
+        entry
+            .read_to_end(&mut content)
+            .map_err(|e| invalid(path, format!("unreadable part {}: {}", part, e)))?;
+        if *part == "word/document.xml" && content.is_empty() {
+            return Err(invalid(path, "empty document part"));
+        }
+    }
+    Ok(())
+}
+
+/// Check the `<output_path>.<format>` files of a finished job
+pub fn verify(
+    output_path: &str,
+    formats: &[OutputFormat],
+    page_count: usize,
+    page_separator: &str,
+) -> Result<(), TahweelError> {
+    for format in formats {
+        match format {
+            OutputFormat::Txt => verify_txt(
+                Path::new(&format!("{}.txt", output_path)),
+                page_count,
+                page_separator,
+            )?,
+            OutputFormat::Json => {
+                verify_json(Path::new(&format!("{}.json", output_path)), page_count)?
+            }
+            OutputFormat::Docx => verify_docx(Path::new(&format!("{}.docx", output_path)))?,
+        }
+    }
+    Ok(())
+}
+
+/// Verify a job's outputs after they are written, so a corrupt or truncated file
+/// fails the job instead of being left behind as a result
+#[tauri::command]
+#[tracing::instrument(skip(page_separator), err)]
+pub async fn verify_outputs(
+    output_path: String,
+    formats: Vec<OutputFormat>,
+    page_count: usize,
+    page_separator: String,
+) -> Result<(), TahweelError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        verify(&output_path, &formats, page_count, &page_separator)
+    })
+    .await
+    .map_err(|e| TahweelError::Internal(format!("Output verification failed: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docx::{write_docx, DocxPage, DocxSection};
+    use crate::output::DocumentMetadata;
+    use tempfile::tempdir;
+
+    const SEPARATOR: &str = "\n\nPAGE_SEPARATOR\n\n";
+
+    fn write_valid_outputs(base: &Path) {
+        fs::write(
+            base.with_extension("txt"),
+            ["one", "two", "three"].join(SEPARATOR),
+        )
+        .unwrap();
+        fs::write(
+            base.with_extension("json"),
+            r#"[{"page":1,"content":"one"},{"page":2,"content":"two"},{"page":3,"content":"three"}]"#,
+        )
+        .unwrap();
+        let section = DocxSection {
+            heading: None,
+            pages: vec![DocxPage::text("one")],
+        };
+        write_docx(
+            base.with_extension("docx").to_str().unwrap(),
+            &[section],
+            &DocumentMetadata::default(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_verify_accepts_valid_outputs() {
+        let dir = tempdir().unwrap();
+        let base = dir.path().join("book");
+        write_valid_outputs(&base);
+
+        let formats = [OutputFormat::Txt, OutputFormat::Json, OutputFormat::Docx];
+        verify(base.to_str().unwrap(), &formats, 3, SEPARATOR).unwrap();
+    }
+
+    #[test]
+    fn test_verify_txt_counts_separators() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("book.txt");
+        fs::write(&path, ["one", "two"].join(SEPARATOR)).unwrap();
+
+        assert!(verify_txt(&path, 2, SEPARATOR).is_ok());
+        assert!(verify_txt(&path, 1, SEPARATOR).is_ok());
+        let error = verify_txt(&path, 3, SEPARATOR).unwrap_err();
+        assert_eq!(error.kind(), "outputInvalid");
+    }
+
+    #[test]
+    fn test_verify_json_rejects_truncated_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("book.json");
+        fs::write(&path, r#"[{"page":1,"content":"one"},{"page":2,"#).unwrap();
+        assert!(matches!(
+            verify_json(&path, 2),
+            Err(TahweelError::OutputInvalid(_))
+        ));
+
+        fs::write(&path, r#"{"version":1,"pages":[{"page":1}]}"#).unwrap();
+        assert!(verify_json(&path, 1).is_ok());
+        assert!(verify_json(&path, 2).is_err());
+    }
+
+    #[test]
+    fn test_verify_docx_rejects_corrupt_package() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("book.docx");
+        fs::write(&path, vec![0u8; 2048]).unwrap();
+        assert!(matches!(
+            verify_docx(&path),
+            Err(TahweelError::OutputInvalid(_))
+        ));
+
+        fs::write(&path, b"PK").unwrap();
+        assert!(verify_docx(&path).is_err());
+    }
+
+    #[test]
+    fn test_verify_missing_output() {
+        let dir = tempdir().unwrap();
+        let base = dir.path().join("missing");
+        let result = verify(base.to_str().unwrap(), &[OutputFormat::Json], 1, SEPARATOR);
+        assert!(matches!(result, Err(TahweelError::OutputInvalid(_))));
+    }
+}
@@ -0,0 +1,632 @@
+use crate::error::TahweelError;
+use crate::http::{self, execute_with_retry, OperationClass};
+use crate::settings::{self, ConflictPolicy, WebDavSettings};
+use crate::util::encode_key;
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs::{self, File};
+use tokio::io::AsyncReadExt;
+
+/// Highest `name (N).ext` tried before giving up on a free name
+const MAX_RENAME_ATTEMPTS: u32 = 100;
+
+/// Nextcloud's WebDAV root; its chunked upload endpoint lives next to `files/`
+const NEXTCLOUD_FILES_PATH: &str = "/remote.php/dav/files/";
+
+/// How an output file ended up on the server
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadOutcome {
+    Uploaded,
+    Overwritten,
+    /// Uploaded under a new name because the original was taken
+    Renamed,
+    /// Not uploaded because the name was taken and the policy is `skip`
+    Skipped,
+}
+
+/// An output file sent to the WebDAV folder
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebDavUpload {
+    pub path: String,
+    pub url: String,
+    pub outcome: UploadOutcome,
+}
+
+/// `book.txt` -> `book (n).txt`
+pub fn numbered_name(name: &str, n: u32) -> String {
+    match name.rfind('.').filter(|&dot| dot > 0) {
+        Some(dot) => format!("{} ({}){}", &name[..dot], n, &name[dot..]),
+        None => format!("{} ({})", name, n),
+    }
+}
+
+fn method(name: &str) -> Method {
+    Method::from_bytes(name.as_bytes()).expect("valid WebDAV method")
+}
+
+fn file_url(webdav: &WebDavSettings, name: &str) -> String {
+    format!("{}/{}", webdav.url, encode_key(name))
+}
+
+/// Chunked upload collection for a Nextcloud files URL
+/// (`.../remote.php/dav/files/<user>/...` -> `.../remote.php/dav/uploads/<user>`),
+/// or `None` for other WebDAV servers
+pub fn nextcloud_uploads_url(url: &str) -> Option<String> {
+    let start = url.find(NEXTCLOUD_FILES_PATH)?;
+    let user = url[start + NEXTCLOUD_FILES_PATH.len()..]
+        .split('/')
+        .next()
+        .filter(|user| !user.is_empty())?;
+    Some(format!("{}/remote.php/dav/uploads/{}", &url[..start], user))
+}
+
+/// Check that the WebDAV settings are complete enough to upload
+fn check_configured(webdav: &WebDavSettings) -> Result<(), TahweelError> {
+    let missing: Vec<&str> = [
+        ("url", &webdav.url),
+        ("username", &webdav.username),
+        ("password", &webdav.password),
+    ]
+    .iter()
+    .filter(|(_, value)| value.is_empty())
+    .map(|(name, _)| *name)
+    .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(TahweelError::InvalidInput(format!(
+            "WebDAV settings are incomplete: {}",
+            missing.join(", ")
+        )))
+    }
+}
+
+/// `response` if it succeeded, otherwise the error for its status
+async fn checked(
+    response: reqwest::Response,
+    action: &str,
+) -> Result<reqwest::Response, TahweelError> {
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        return Err(TahweelError::from_status(
+            status,
+            format!("WebDAV {} failed ({}): {}", action, status, text),
+        ));
+    }
+    Ok(response)
+}
+
+/// Send the request `build` makes with the WebDAV login. Transient failures
+/// (5xx, 429, timeouts) are retried with `execute_with_retry`, as for Drive.
+async fn send(
+    webdav: &WebDavSettings,
+    action: &str,
+    build: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<reqwest::Response, TahweelError> {
+    execute_with_retry(|| async {
+        let response = build()
+            .basic_auth(&webdav.username, Some(&webdav.password))
+            .send()
+            .await?;
+        checked(response, action).await
+    })
+    .await
+}
+
+/// Whether a file already exists at `url`
+async fn exists(
+    client: &reqwest::Client,
+    webdav: &WebDavSettings,
+    url: &str,
+) -> Result<bool, TahweelError> {
+    execute_with_retry(|| async {
+        let response = client
+            .head(url)
+            .basic_auth(&webdav.username, Some(&webdav.password))
+            .send()
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(TahweelError::from_status(
+                status,
+                format!("WebDAV lookup of {} failed ({})", url, status),
+            )),
+        }
+    })
+    .await
+}
+
+/// Destination URL for `name` under the conflict policy, or `None` to skip
+async fn resolve_destination(
+    client: &reqwest::Client,
+    webdav: &WebDavSettings,
+    name: &str,
+) -> Result<Option<(String, UploadOutcome)>, TahweelError> {
+    let url = file_url(webdav, name);
+    if !exists(client, webdav, &url).await? {
+        return Ok(Some((url, UploadOutcome::Uploaded)));
+    }
+    match webdav.conflict {
+        ConflictPolicy::Overwrite => Ok(Some((url, UploadOutcome::Overwritten))),
+        ConflictPolicy::Skip => Ok(None),
+        ConflictPolicy::Rename => {
+            for n in 1..=MAX_RENAME_ATTEMPTS {
+                let url = file_url(webdav, &numbered_name(name, n));
+                if !exists(client, webdav, &url).await? {
+                    return Ok(Some((url, UploadOutcome::Renamed)));
+                }
+            }
+            Err(TahweelError::Api(format!(
+                "No free name for {} on the WebDAV server",
+                name
+            )))
+        }
+    }
+}
+
+fn read_error(path: &Path, e: std::io::Error) -> TahweelError {
+    TahweelError::Io(format!("Failed to read {}: {}", path.display(), e))
+}
+
+/// Nextcloud chunked upload (v2): create an upload folder, `PUT` numbered
+/// chunks into it, then `MOVE` the assembled `.file` to the destination.
+/// Chunks are read from `path` one at a time, so only one is in memory.
+#[allow(clippy::too_many_arguments)]
+async fn chunked_upload(
+    client: &reqwest::Client,
+    webdav: &WebDavSettings,
+    uploads_url: &str,
+    transfer_id: &str,
+    destination: &str,
+    path: &Path,
+    size: u64,
+    chunk_size: usize,
+) -> Result<(), TahweelError> {
+    let folder = format!("{}/{}", uploads_url, transfer_id);
+    let total = size.to_string();
+    send(webdav, "chunk folder creation", || {
+        client
+            .request(method("MKCOL"), &folder)
+            .header("Destination", destination)
+    })
+    .await?;
+
+    let result = async {
+        let mut file = File::open(path).await.map_err(|e| read_error(path, e))?;
+        let mut sent = 0u64;
+        let mut index = 0;
+        while sent < size {
+            let mut chunk = vec![0u8; chunk_size.min((size - sent) as usize)];
+            file.read_exact(&mut chunk)
+                .await
+                .map_err(|e| read_error(path, e))?;
+            sent += chunk.len() as u64;
+            index += 1;
+            send(webdav, "chunk upload", || {
+                client
+                    .put(format!("{}/{:05}", folder, index))
+                    .header("Destination", destination)
+                    .header("OC-Total-Length", &total)
+                    .body(chunk.clone())
+            })
+            .await?;
+        }
+        send(webdav, "chunk assembly", || {
+            client
+                .request(method("MOVE"), format!("{}/.file", folder))
+                .header("Destination", destination)
+                .header("OC-Total-Length", &total)
+                .header("Overwrite", "T")
+        })
+        .await
+        .map(|_| ())
+    }
+    .await;
+
+    if result.is_err() {
+        // Leave no half-finished transfer behind on the server
+        let _ = client
+            .delete(&folder)
+            .basic_auth(&webdav.username, Some(&webdav.password))
+            .send()
+            .await;
+    }
+    result
+}
+
+/// Upload one file, applying the conflict policy and chunking large files
+/// when the server is Nextcloud. The file is streamed from disk, never read
+/// into memory whole.
+pub async fn upload_file(
+    client: &reqwest::Client,
+    webdav: &WebDavSettings,
+    path: &Path,
+    transfer_id: &str,
+) -> Result<WebDavUpload, TahweelError> {
+    let size = fs::metadata(path)
+        .await
+        .map_err(|e| read_error(path, e))?
+        .len();
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let Some((url, outcome)) = resolve_destination(client, webdav, &name).await? else {
+        return Ok(WebDavUpload {
+            path: path.to_string_lossy().to_string(),
+            url: file_url(webdav, &name),
+            outcome: UploadOutcome::Skipped,
+        });
+    };
+
+    let chunk_size = webdav.chunk_size_mb as usize * 1024 * 1024;
+    match nextcloud_uploads_url(&webdav.url) {
+        Some(uploads_url) if chunk_size > 0 && size > chunk_size as u64 => {
+            chunked_upload(
+                client,
+                webdav,
+                &uploads_url,
+                transfer_id,
+                &url,
+                path,
+                size,
+                chunk_size,
+            )
+            .await?
+        }
+        _ => {
+            execute_with_retry(|| async {
+                // Opened per attempt: a streamed body cannot be sent twice
+                let file = File::open(path).await.map_err(|e| read_error(path, e))?;
+                let response = client
+                    .put(&url)
+                    .basic_auth(&webdav.username, Some(&webdav.password))
+                    .header(reqwest::header::CONTENT_LENGTH, size)
+                    .body(file)
+                    .send()
+                    .await?;
+                checked(response, "upload").await
+            })
+            .await?;
+        }
+    }
+
+    Ok(WebDavUpload {
+        path: path.to_string_lossy().to_string(),
+        url,
+        outcome,
+    })
+}
+
+/// Upload the given output files to the configured WebDAV folder.
+/// Returns no uploads when WebDAV upload is disabled.
+#[tauri::command]
+pub async fn upload_outputs_to_webdav(
+    paths: Vec<String>,
+) -> Result<Vec<WebDavUpload>, TahweelError> {
+    let webdav = settings::current().webdav;
+    if !webdav.enabled {
+        return Ok(Vec::new());
+    }
+    check_configured(&webdav)?;
+
+    let client = http::client(OperationClass::Upload)?;
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let mut uploads = Vec::with_capacity(paths.len());
+    for (index, path) in paths.iter().enumerate() {
+        let transfer_id = format!("tahweel-{}-{}", started, index);
+        uploads.push(upload_file(&client, &webdav, Path::new(path), &transfer_id).await?);
+    }
+    Ok(uploads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+    use tempfile::tempdir;
+
+    fn webdav(url: &str, conflict: ConflictPolicy) -> WebDavSettings {
+        WebDavSettings {
+            enabled: true,
+            url: url.to_string(),
+            username: "amina".to_string(),
+            password: "secret".to_string(),
+            conflict,
+            ..Default::default()
+        }
+    }
+
+    fn output(contents: &[u8]) -> (tempfile::TempDir, std::path::PathBuf) {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("book.txt");
+        std::fs::write(&path, contents).unwrap();
+        (temp, path)
+    }
+
+    #[test]
+    fn test_numbered_name() {
+        assert_eq!(numbered_name("book.txt", 1), "book (1).txt");
+        assert_eq!(numbered_name("book.tar.gz", 2), "book.tar (2).gz");
+        assert_eq!(numbered_name("README", 3), "README (3)");
+        assert_eq!(numbered_name(".hidden", 1), ".hidden (1)");
+    }
+
+    #[test]
+    fn test_nextcloud_uploads_url() {
+        assert_eq!(
+            nextcloud_uploads_url("https://cloud.example.com/remote.php/dav/files/amina/Books")
+                .as_deref(),
+            Some("https://cloud.example.com/remote.php/dav/uploads/amina")
+        );
+        assert_eq!(
+            nextcloud_uploads_url("https://example.com/nc/remote.php/dav/files/amina").as_deref(),
+            Some("https://example.com/nc/remote.php/dav/uploads/amina")
+        );
+        assert_eq!(nextcloud_uploads_url("https://dav.example.com/books"), None);
+    }
+
+    #[test]
+    fn test_check_configured() {
+        assert!(
+            check_configured(&webdav("https://dav.example.com", ConflictPolicy::Rename)).is_ok()
+        );
+        let err = check_configured(&WebDavSettings::default()).unwrap_err();
+        assert!(err.message().contains("url, username, password"));
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_new() {
+        let (_temp, path) = output(b"text");
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("HEAD", "/books/book.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+        let put = server
+            .mock("PUT", "/books/book.txt")
+            .match_header("authorization", "Basic YW1pbmE6c2VjcmV0")
+            .match_body("text")
+            .with_status(201)
+            .create_async()
+            .await;
+
+        let settings = webdav(&format!("{}/books", server.url()), ConflictPolicy::Rename);
+        let upload = upload_file(&reqwest::Client::new(), &settings, &path, "t1")
+            .await
+            .unwrap();
+
+        put.assert_async().await;
+        assert_eq!(upload.outcome, UploadOutcome::Uploaded);
+        assert_eq!(upload.url, format!("{}/books/book.txt", server.url()));
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_conflict_policies() {
+        let (_temp, path) = output(b"text");
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("HEAD", "/books/book.txt")
+            .with_status(200)
+            .create_async()
+            .await;
+        server
+            .mock("HEAD", "/books/book%20%281%29.txt")
+            .with_status(200)
+            .create_async()
+            .await;
+        server
+            .mock("HEAD", "/books/book%20%282%29.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+        let renamed = server
+            .mock("PUT", "/books/book%20%282%29.txt")
+            .with_status(201)
+            .create_async()
+            .await;
+        let overwritten = server
+            .mock("PUT", "/books/book.txt")
+            .with_status(204)
+            .create_async()
+            .await;
+        let client = reqwest::Client::new();
+        let url = format!("{}/books", server.url());
+
+        let upload = upload_file(&client, &webdav(&url, ConflictPolicy::Rename), &path, "t1")
+            .await
+            .unwrap();
+        renamed.assert_async().await;
+        assert_eq!(upload.outcome, UploadOutcome::Renamed);
+        assert!(upload.url.ends_with("/books/book%20%282%29.txt"));
+
+        let upload = upload_file(
+            &client,
+            &webdav(&url, ConflictPolicy::Overwrite),
+            &path,
+            "t2",
+        )
+        .await
+        .unwrap();
+        overwritten.assert_async().await;
+        assert_eq!(upload.outcome, UploadOutcome::Overwritten);
+
+        let upload = upload_file(&client, &webdav(&url, ConflictPolicy::Skip), &path, "t3")
+            .await
+            .unwrap();
+        assert_eq!(upload.outcome, UploadOutcome::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_nextcloud_chunks() {
+        let (_temp, path) = output(&[7u8; 2 * 1024 * 1024 + 10]);
+        let mut server = mockito::Server::new_async().await;
+        let destination = format!("{}/remote.php/dav/files/amina/Books/book.txt", server.url());
+        server
+            .mock("HEAD", "/remote.php/dav/files/amina/Books/book.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+        let mkcol = server
+            .mock("MKCOL", "/remote.php/dav/uploads/amina/t1")
+            .match_header("destination", destination.as_str())
+            .with_status(201)
+            .create_async()
+            .await;
+        let chunks = server
+            .mock(
+                "PUT",
+                Matcher::Regex(r"^/remote.php/dav/uploads/amina/t1/0000[1-3]$".to_string()),
+            )
+            .match_header("oc-total-length", "2097162")
+            .with_status(201)
+            .expect(3)
+            .create_async()
+            .await;
+        let assemble = server
+            .mock("MOVE", "/remote.php/dav/uploads/amina/t1/.file")
+            .match_header("destination", destination.as_str())
+            .with_status(201)
+            .create_async()
+            .await;
+
+        let settings = WebDavSettings {
+            chunk_size_mb: 1,
+            ..webdav(
+                &format!("{}/remote.php/dav/files/amina/Books", server.url()),
+                ConflictPolicy::Rename,
+            )
+        };
+        let upload = upload_file(&reqwest::Client::new(), &settings, &path, "t1")
+            .await
+            .unwrap();
+
+        mkcol.assert_async().await;
+        chunks.assert_async().await;
+        assemble.assert_async().await;
+        assert_eq!(upload.url, destination);
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_cleans_up_failed_chunks() {
+        let (_temp, path) = output(&[7u8; 1024 * 1024 + 1]);
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("HEAD", "/remote.php/dav/files/amina/book.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+        server
+            .mock("MKCOL", "/remote.php/dav/uploads/amina/t1")
+            .with_status(201)
+            .create_async()
+            .await;
+        server
+            .mock("PUT", "/remote.php/dav/uploads/amina/t1/00001")
+            .with_status(413)
+            .create_async()
+            .await;
+        let cleanup = server
+            .mock("DELETE", "/remote.php/dav/uploads/amina/t1")
+            .with_status(204)
+            .create_async()
+            .await;
+
+        let settings = WebDavSettings {
+            chunk_size_mb: 1,
+            ..webdav(
+                &format!("{}/remote.php/dav/files/amina", server.url()),
+                ConflictPolicy::Rename,
+            )
+        };
+        let err = upload_file(&reqwest::Client::new(), &settings, &path, "t1")
+            .await
+            .unwrap_err();
+
+        cleanup.assert_async().await;
+        assert!(matches!(err, TahweelError::Api(_)));
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_retries_transient_failures() {
+        let (_temp, path) = output(b"text");
+        let mut server = mockito::Server::new_async().await;
+        let busy = server
+            .mock("HEAD", "/book.txt")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("HEAD", "/book.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+        let throttled = server
+            .mock("PUT", "/book.txt")
+            .with_status(429)
+            .expect(1)
+            .create_async()
+            .await;
+        let put = server
+            .mock("PUT", "/book.txt")
+            .match_body("text")
+            .with_status(201)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let upload = upload_file(
+            &reqwest::Client::new(),
+            &webdav(&server.url(), ConflictPolicy::Rename),
+            &path,
+            "t1",
+        )
+        .await
+        .unwrap();
+
+        busy.assert_async().await;
+        throttled.assert_async().await;
+        put.assert_async().await;
+        assert_eq!(upload.outcome, UploadOutcome::Uploaded);
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_auth_failure() {
+        let (_temp, path) = output(b"text");
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("HEAD", "/book.txt")
+            .with_status(401)
+            .create_async()
+            .await;
+
+        let err = upload_file(
+            &reqwest::Client::new(),
+            &webdav(&server.url(), ConflictPolicy::Rename),
+            &path,
+            "t1",
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, TahweelError::Auth(_)));
+    }
+
+    #[tokio::test]
+    async fn test_upload_outputs_disabled_by_default() {
+        let uploads = upload_outputs_to_webdav(vec!["/nonexistent/book.txt".to_string()])
+            .await
+            .unwrap();
+        assert!(uploads.is_empty());
+    }
+}
@@ -15,19 +15,25 @@ Object.defineProperty(globalThis, "localStorage", { value: localStorageMock })
 vi.mock("@tauri-apps/plugin-dialog", () => ({
   open: vi.fn(),
   message: vi.fn(),
+  ask: vi.fn(),
 }))
 
 vi.mock("@tauri-apps/plugin-fs", () => ({
   readDir: vi.fn(),
+  readTextFile: vi.fn(),
   writeTextFile: vi.fn(),
 }))
 
 vi.mock("@tauri-apps/api/core", () => ({
   invoke: vi.fn(),
+  Channel: class {
+    onmessage: (message: unknown) => void = () => {}
+  },
 }))
 
 vi.mock("@tauri-apps/api/event", () => ({
   listen: vi.fn(),
+  emit: vi.fn(),
 }))
 
 vi.mock("@tauri-apps/api/path", () => ({
@@ -46,8 +52,9 @@ import { useFileProcessor } from "../useFileProcessor"
 import { useProcessingStore } from "@/stores/processing"
 import { useSettingsStore } from "@/stores/settings"
 import { useAuthStore } from "@/stores/auth"
-import { open, message } from "@tauri-apps/plugin-dialog"
-import { readDir, writeTextFile } from "@tauri-apps/plugin-fs"
+import { useToastStore } from "@/stores/toast"
+import { open, message, ask } from "@tauri-apps/plugin-dialog"
+import { readDir, readTextFile, writeTextFile } from "@tauri-apps/plugin-fs"
 import { invoke } from "@tauri-apps/api/core"
 import { listen } from "@tauri-apps/api/event"
 import { dirname, basename, join } from "@tauri-apps/api/path"
@@ -226,6 +233,27 @@ describe("useFileProcessor", () => {
       expect(files).toEqual(["/folder/a.pdf", "/folder/b.pdf", "/folder/c.pdf"])
     })
 
+    it("returns numbered files in natural order", async () => {
+      vi.mocked(readDir).mockResolvedValue([
+        { name: "page10.png", isFile: true, isDirectory: false, isSymlink: false },
+        { name: "page2.png", isFile: true, isDirectory: false, isSymlink: false },
+        { name: "page1.png", isFile: true, isDirectory: false, isSymlink: false },
+      ])
+
+      const { collectFiles } = useFileProcessor()
+
+      expect(await collectFiles("/folder")).toEqual([
+        "/folder/page1.png",
+        "/folder/page2.png",
+        "/folder/page10.png",
+      ])
+      expect(await collectFiles("/folder", ["/folder/page10.png"])).toEqual([
+        "/folder/page10.png",
+        "/folder/page1.png",
+        "/folder/page2.png",
+      ])
+    })
+
     it("handles case-insensitive extensions", async () => {
       vi.mocked(readDir).mockResolvedValue([
         {
@@ -309,143 +337,1092 @@ describe("useFileProcessor", () => {
       })
     })
 
-    it("processes file through full pipeline when authenticated", async () => {
-      setupAuthenticated()
-      setupFullProcessingMocks({ ocrText: "Hello World" })
-      vi.mocked(open).mockResolvedValue("/path/to/image.png")
+    it("processes file through full pipeline when authenticated", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ ocrText: "Hello World" })
+      vi.mocked(open).mockResolvedValue("/path/to/image.png")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      // Verify OCR was called
+      expect(invoke).toHaveBeenCalledWith(
+        "upload_to_google_drive",
+        expect.any(Object),
+      )
+      // Verify output was written
+      expect(writeTextFile).toHaveBeenCalled()
+      // Verify folder was opened
+      expect(invoke).toHaveBeenCalledWith("open_folder", expect.any(Object))
+      // Verify the input was added to recent files
+      expect(invoke).toHaveBeenCalledWith("record_recent_file", {
+        path: "/path/to/image.png",
+        options: expect.objectContaining({ formats: expect.any(Array) }),
+        outputs: expect.arrayContaining(["/path/to/image.txt"]),
+      })
+    })
+
+    it("stages content URIs before processing and removes the copy", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks()
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "resolve_output_dir") return "/app/Documents/Tahweel"
+        if (cmd === "stage_input_file") return { path: "/cache/tahweel/in-1/scan.png" }
+        return base(cmd, args as never)
+      })
+      vi.mocked(open).mockResolvedValue("content://media/document/image%3A12")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(invoke).toHaveBeenCalledWith("stage_input_file", {
+        path: "content://media/document/image%3A12",
+      })
+      expect(invoke).toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+        filePath: "/cache/tahweel/in-1/scan.png",
+      }))
+      expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", { path: "/cache/tahweel/in-1" })
+    })
+
+    it("OCRs ambiguous PDF pages twice and reports agreement in quality mode", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 2, ocrText: "text" })
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "plan_dual_pass") return { dpi: 300, pages: [2] }
+        if (cmd === "extract_pdf_page") return "/tmp/split/second_pass_2.png"
+        return base(cmd, args as never)
+      })
+      vi.mocked(open).mockResolvedValue("/path/to/book.pdf")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(invoke).toHaveBeenCalledWith("extract_pdf_page", {
+        pdfPath: "/path/to/book.pdf",
+        pageNumber: 2,
+        dpi: 300,
+        outputPath: "/tmp/split/second_pass_2.png",
+      })
+      expect(invoke).toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+        filePath: "/tmp/split/second_pass_2.png",
+      }))
+      expect(invoke).toHaveBeenCalledWith("generate_job_report", {
+        jobId: expect.any(String),
+        outputPath: "/path/to/book",
+        pages: ["text", "text"],
+        secondPass: [{ page: 2, text: "text" }],
+        storedObjects: [],
+        degradedPages: [],
+        downscaledPages: [],
+        duplicatePages: [],
+        metadata: {},
+      })
+    })
+
+    it("reports pages that only rendered on a retry", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 2, ocrText: "text" })
+      const degraded = { page: 2, dpi: 100, error: "Failed to render page 2" }
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "split_pdf") {
+          return {
+            imagePaths: ["/tmp/page_1.png", "/tmp/page_2.png"],
+            tempDir: "/tmp/split",
+            degradedPages: [degraded],
+          }
+        }
+        return base(cmd, args as never)
+      })
+      vi.mocked(open).mockResolvedValue("/path/to/book.pdf")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(invoke).toHaveBeenCalledWith("generate_job_report", {
+        jobId: expect.any(String),
+        outputPath: "/path/to/book",
+        pages: ["text", "text"],
+        secondPass: [],
+        storedObjects: [],
+        degradedPages: [degraded],
+        downscaledPages: [],
+        duplicatePages: [],
+        metadata: {},
+      })
+    })
+
+    it("reports pages downscaled to fit Drive's limits", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 2, ocrText: "text" })
+      const downscaled = {
+        page: 1,
+        originalWidth: 6000,
+        originalHeight: 9000,
+        width: 4082,
+        height: 6123,
+        bytes: 1_900_000,
+      }
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "split_pdf") {
+          return {
+            imagePaths: ["/tmp/page_1.png", "/tmp/page_2.png"],
+            tempDir: "/tmp/split",
+            downscaledPages: [downscaled],
+          }
+        }
+        return base(cmd, args as never)
+      })
+      vi.mocked(open).mockResolvedValue("/path/to/book.pdf")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(invoke).toHaveBeenCalledWith("generate_job_report", expect.objectContaining({
+        downscaledPages: [downscaled],
+      }))
+      expect(useToastStore().toasts.map((t) => t.messageKey)).toContain("toast.pagesDownscaled")
+    })
+
+    it("carries the PDF's metadata, with the job's overrides, into the report", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 1, ocrText: "بسم الله الرحمن الرحيم الحمد لله" })
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "get_pdf_info") {
+          return { pageCount: 1, title: "الرسالة", author: "Scanner", subject: "أصول الفقه" }
+        }
+        return base(cmd, args as never)
+      })
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/book.pdf"], "/path/to", {}, {
+        "/path/to/book.pdf": { author: "الشافعي", subject: " " },
+      })
+
+      expect(invoke).toHaveBeenCalledWith("get_pdf_info", { pdfPath: "/path/to/book.pdf" })
+      expect(invoke).toHaveBeenCalledWith(
+        "generate_job_report",
+        expect.objectContaining({
+          metadata: { title: "الرسالة", author: "الشافعي", subject: "أصول الفقه" },
+        }),
+      )
+      expect(invoke).toHaveBeenCalledWith(
+        "record_job",
+        expect.objectContaining({
+          docx: { metadata: { title: "الرسالة", author: "الشافعي", subject: "أصول الفقه" } },
+        }),
+      )
+    })
+
+    it("reuses the first occurrence's text for skipped duplicate pages", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 3 })
+      const duplicate = { page: 3, duplicateOf: 1, exact: true, skipped: true }
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "detect_duplicate_pages") return [duplicate]
+        if (cmd === "upload_to_google_drive") {
+          return { fileId: (args as { filePath: string }).filePath }
+        }
+        if (cmd === "export_google_doc_as_text") {
+          return { text: `text of ${(args as { fileId: string }).fileId}` }
+        }
+        return base(cmd, args as never)
+      })
+      vi.mocked(open).mockResolvedValue("/path/to/book.pdf")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(invoke).toHaveBeenCalledWith("detect_duplicate_pages", {
+        imagePaths: ["/tmp/page_1.png", "/tmp/page_2.png", "/tmp/page_3.png"],
+      })
+      const uploads = vi
+        .mocked(invoke)
+        .mock.calls.filter((call) => call[0] === "upload_to_google_drive")
+      expect(uploads).toHaveLength(2)
+      expect(invoke).toHaveBeenCalledWith("generate_job_report", {
+        jobId: expect.any(String),
+        outputPath: "/path/to/book",
+        pages: ["text of /tmp/page_1.png", "text of /tmp/page_2.png", "text of /tmp/page_1.png"],
+        secondPass: [],
+        storedObjects: [],
+        degradedPages: [],
+        downscaledPages: [],
+        duplicatePages: [duplicate],
+        metadata: {},
+      })
+    })
+
+    it("uploads outputs to storage and records them in the job report", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 1, ocrText: "text" })
+      const stored = {
+        path: "/path/to/book.txt",
+        key: "books/book.txt",
+        url: "https://s3.example.com/library/books/book.txt",
+      }
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "upload_outputs_to_storage") return [stored]
+        return base(cmd, args as never)
+      })
+      vi.mocked(open).mockResolvedValue("/path/to/book.pdf")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(invoke).toHaveBeenCalledWith("upload_outputs_to_storage", {
+        paths: expect.arrayContaining(["/path/to/book.txt"]),
+      })
+      expect(useProcessingStore().outputFiles).toEqual(["/path/to/book.txt", "/path/to/book.docx"])
+      expect(invoke).toHaveBeenCalledWith("generate_job_report", {
+        jobId: expect.any(String),
+        outputPath: "/path/to/book",
+        pages: ["text"],
+        secondPass: [],
+        storedObjects: [stored],
+        degradedPages: [],
+        downscaledPages: [],
+        duplicatePages: [],
+        metadata: {},
+      })
+      expect(invoke).toHaveBeenCalledWith("email_job_results", {
+        jobName: "book.pdf",
+        files: [
+          { path: "/path/to/book.txt", url: stored.url },
+          { path: "/path/to/book.docx", url: null },
+        ],
+      })
+    })
+
+    it("warns but still finishes when the WebDAV upload fails", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 1, ocrText: "text" })
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "upload_outputs_to_webdav") throw new Error("401 Unauthorized")
+        return base(cmd, args as never)
+      })
+      vi.mocked(open).mockResolvedValue("/path/to/book.pdf")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(invoke).toHaveBeenCalledWith("upload_outputs_to_webdav", {
+        paths: expect.arrayContaining(["/path/to/book.txt"]),
+      })
+      expect(writeTextFile).toHaveBeenCalledWith("/path/to/book.txt", "text")
+      expect(useToastStore().toasts.map((t) => t.messageKey)).toContain("toast.webdavUploadFailed")
+    })
+
+    it("sends detected page languages as OCR hints in the second pass", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 2, ocrText: "text" })
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "detect_page_languages") {
+          return [
+            { page: 1, script: "latin", language: "en" },
+            { page: 2, script: "arabic", language: "ar" },
+          ]
+        }
+        if (cmd === "plan_dual_pass") return { dpi: 300, pages: [2] }
+        if (cmd === "extract_pdf_page") return "/tmp/split/second_pass_2.png"
+        return base(cmd, args as never)
+      })
+      vi.mocked(open).mockResolvedValue("/path/to/book.pdf")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(invoke).toHaveBeenCalledWith("detect_page_languages", { pages: ["text", "text"] })
+      expect(invoke).toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+        filePath: "/tmp/split/second_pass_2.png",
+        ocrLanguage: "ar",
+      }))
+    })
+
+    it("OCRs footnote images separately and writes them below the body", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 2, ocrText: "text" })
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "split_page_footnotes") {
+          const { imagePath } = args as { imagePath: string }
+          return imagePath === "/tmp/page_1.png"
+            ? { body: "/tmp/page_1-body.png", footnotes: "/tmp/page_1-notes.png" }
+            : { body: imagePath, footnotes: null }
+        }
+        return base(cmd, args as never)
+      })
+      vi.mocked(open).mockResolvedValue("/path/to/book.pdf")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      for (const filePath of ["/tmp/page_1-body.png", "/tmp/page_1-notes.png", "/tmp/page_2.png"]) {
+        expect(invoke).toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+          filePath,
+        }))
+      }
+      expect(writeTextFile).toHaveBeenCalledWith(
+        "/path/to/book.txt",
+        "text\n\n__________\ntext\n\nPAGE_SEPARATOR\n\ntext",
+      )
+    })
+
+    it("stops before processing when offline", async () => {
+      setupAuthenticated()
+      vi.mocked(invoke).mockImplementation(async (cmd: string) =>
+        cmd === "check_connectivity" ? { status: "offline" } : undefined,
+      )
+      vi.mocked(open).mockResolvedValue("/path/to/image.png")
+      vi.mocked(message).mockResolvedValue("Ok")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(message).toHaveBeenCalledWith("messages.offline", {
+        title: "messages.errorTitle",
+        kind: "error",
+      })
+      expect(invoke).not.toHaveBeenCalledWith("upload_to_google_drive", expect.any(Object))
+    })
+
+    it("stops before processing when the backend is unusable", async () => {
+      setupAuthenticated()
+      vi.mocked(invoke).mockImplementation(async (cmd: string) =>
+        cmd === "check_backend"
+          ? {
+              backend: "googleDrive",
+              usable: false,
+              checks: [
+                { name: "token", status: "ok", message: "The access token is valid", hint: null },
+                {
+                  name: "scope",
+                  status: "failed",
+                  message: "Google Drive access was not granted",
+                  hint: "Sign in again",
+                },
+              ],
+            }
+          : undefined,
+      )
+      vi.mocked(open).mockResolvedValue("/path/to/image.png")
+      vi.mocked(message).mockResolvedValue("Ok")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(invoke).toHaveBeenCalledWith("check_backend", {
+        backendId: "googleDrive",
+        accessToken: "valid_token",
+      })
+      expect(message).toHaveBeenCalledWith(
+        "messages.backendUnusable\n\nGoogle Drive access was not granted. Sign in again.",
+        { title: "messages.errorTitle", kind: "error" },
+      )
+      expect(invoke).not.toHaveBeenCalledWith("upload_to_google_drive", expect.any(Object))
+    })
+
+    it("processes when the backend check itself fails", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks()
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "check_backend") throw new Error("network down")
+        return base(cmd, args as never)
+      })
+      vi.mocked(open).mockResolvedValue("/path/to/image.png")
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(invoke).toHaveBeenCalledWith("upload_to_google_drive", expect.any(Object))
+    })
+  })
+
+  describe("archival mode", () => {
+    it("writes outputs to the app folder instead of beside the source", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks()
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "prepare_archival_output") {
+          return { outputDirectory: "/app/outputs", workspace: "/tmp/tahweel" }
+        }
+        return base(cmd, args as never)
+      })
+      vi.mocked(open).mockResolvedValue("/mnt/share/image.png")
+      useSettingsStore().archivalMode = true
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(invoke).toHaveBeenCalledWith("prepare_archival_output", {
+        sourcePaths: ["/mnt/share/image.png"],
+      })
+      expect(useProcessingStore().outputFolder).toBe("/app/outputs")
+    })
+
+    it("stops before any work when the app folders are not usable", async () => {
+      setupAuthenticated()
+      vi.mocked(invoke).mockImplementation(async (cmd: string) => {
+        if (cmd === "prepare_archival_output") throw new Error("disk full")
+        return undefined
+      })
+      vi.mocked(open).mockResolvedValue("/mnt/share/image.png")
+      vi.mocked(message).mockResolvedValue("Ok")
+      useSettingsStore().archivalMode = true
+
+      const { selectFile } = useFileProcessor()
+      await selectFile()
+
+      expect(message).toHaveBeenCalledWith("messages.archivalUnavailable", {
+        title: "messages.errorTitle",
+        kind: "error",
+      })
+      expect(invoke).not.toHaveBeenCalledWith("upload_to_google_drive", expect.any(Object))
+    })
+  })
+
+  describe("processUrl", () => {
+    it("downloads the URL and processes the file into the returned folder", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks()
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "process_url") {
+          return {
+            path: "/tmp/tahweel/tahweel-download-1/scan.png",
+            outputDirectory: "/home/user/Downloads",
+          }
+        }
+        return base(cmd, args as never)
+      })
+
+      const { processUrl } = useFileProcessor()
+      await processUrl("https://archive.org/download/item/scan.png")
+
+      expect(invoke).toHaveBeenCalledWith("process_url", {
+        url: "https://archive.org/download/item/scan.png",
+        options: { outputDirectory: null },
+      })
+      expect(invoke).toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+        filePath: "/tmp/tahweel/tahweel-download-1/scan.png",
+      }))
+      expect(invoke).toHaveBeenCalledWith("record_recent_file", expect.objectContaining({
+        outputs: expect.arrayContaining(["/home/user/Downloads/scan.txt"]),
+      }))
+      expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
+        path: "/tmp/tahweel/tahweel-download-1",
+      })
+    })
+
+    it("shows the error when the download fails", async () => {
+      setupAuthenticated()
+      vi.mocked(invoke).mockImplementation(async (cmd: string) => {
+        if (cmd === "process_url") throw "Unsupported file type: text/html"
+        return undefined
+      })
+      vi.mocked(message).mockResolvedValue("Ok")
+
+      const { processUrl } = useFileProcessor()
+      await processUrl("https://example.com/page")
+
+      expect(message).toHaveBeenCalledWith(expect.any(String), {
+        title: "messages.errorTitle",
+        kind: "error",
+      })
+      expect(invoke).not.toHaveBeenCalledWith("upload_to_google_drive", expect.any(Object))
+    })
+  })
+
+  describe("processDriveFile", () => {
+    it("downloads the Drive file with the access token and processes it", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks()
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "process_drive_file") {
+          return {
+            path: "/tmp/tahweel/tahweel-download-2/book.pdf",
+            outputDirectory: "/home/user/Downloads",
+          }
+        }
+        return base(cmd, args as never)
+      })
+
+      const { processDriveFile } = useFileProcessor()
+      await processDriveFile("drive-file-1")
+
+      expect(invoke).toHaveBeenCalledWith("process_drive_file", {
+        fileId: "drive-file-1",
+        accessToken: "valid_token",
+        options: { outputDirectory: null },
+      })
+      expect(invoke).toHaveBeenCalledWith("split_pdf", expect.objectContaining({
+        pdfPath: "/tmp/tahweel/tahweel-download-2/book.pdf",
+      }))
+      expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
+        path: "/tmp/tahweel/tahweel-download-2",
+      })
+    })
+
+    it("asks to sign in first", async () => {
+      vi.mocked(message).mockResolvedValue("Ok")
+
+      const { processDriveFile } = useFileProcessor()
+      await processDriveFile("drive-file-1")
+
+      expect(message).toHaveBeenCalledWith("messages.authRequired", {
+        title: "messages.errorTitle",
+        kind: "error",
+      })
+      expect(invoke).not.toHaveBeenCalledWith("process_drive_file", expect.any(Object))
+    })
+  })
+
+  describe("scanAndProcess", () => {
+    it("OCRs the scanned pages as one document and removes the scan", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks()
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "scan_pages") {
+          return {
+            name: "scan-1760000000",
+            dir: "/tmp/tahweel/tahweel-scan-1",
+            pages: [
+              "/tmp/tahweel/tahweel-scan-1/page-0001.png",
+              "/tmp/tahweel/tahweel-scan-1/page-0002.png",
+            ],
+            outputDirectory: "/home/user/Documents",
+          }
+        }
+        return base(cmd, args as never)
+      })
+
+      const { scanAndProcess } = useFileProcessor()
+      await scanAndProcess("epson2:libusb:001:004", { feeder: true })
+
+      expect(invoke).toHaveBeenCalledWith("scan_pages", {
+        device: "epson2:libusb:001:004",
+        settings: { feeder: true },
+        outputDirectory: null,
+      })
+      expect(invoke).not.toHaveBeenCalledWith("split_pdf", expect.any(Object))
+      expect(invoke).toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+        filePath: "/tmp/tahweel/tahweel-scan-1/page-0002.png",
+      }))
+      expect(writeTextFile).toHaveBeenCalledWith(
+        "/home/user/Documents/scan-1760000000.txt",
+        expect.any(String),
+      )
+      expect(invoke).not.toHaveBeenCalledWith("record_recent_file", expect.any(Object))
+      expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
+        path: "/tmp/tahweel/tahweel-scan-1",
+      })
+    })
+  })
+
+  describe("selectFolder", () => {
+    it("shows error when no supported files found", async () => {
+      vi.mocked(open).mockResolvedValue("/empty/folder")
+      vi.mocked(readDir).mockResolvedValue([
+        {
+          name: "readme.txt",
+          isFile: true,
+          isDirectory: false,
+          isSymlink: false,
+        },
+      ])
+      vi.mocked(message).mockResolvedValue("Ok")
+
+      const { selectFolder } = useFileProcessor()
+      await selectFolder()
+
+      expect(message).toHaveBeenCalledWith("messages.noFiles", {
+        title: "messages.errorTitle",
+        kind: "error",
+      })
+    })
+  })
+
+  describe("processFiles - image processing", () => {
+    beforeEach(() => {
+      setupAuthenticated()
+      setupFullProcessingMocks()
+    })
+
+    it("processes single image file directly (no PDF split)", async () => {
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/image.png"], "/output")
+
+      // Should NOT call PDF-related commands
+      expect(invoke).not.toHaveBeenCalledWith(
+        "get_pdf_page_count",
+        expect.any(Object),
+      )
+      expect(invoke).not.toHaveBeenCalledWith("split_pdf", expect.any(Object))
+
+      // Should call OCR
+      expect(invoke).toHaveBeenCalledWith(
+        "upload_to_google_drive",
+        expect.any(Object),
+      )
+    })
+
+    it("updates processing store state correctly", async () => {
+      const store = useProcessingStore()
+      const { processFiles } = useFileProcessor()
+
+      await processFiles(["/path/to/image.png"], "/output")
+
+      expect(store.completedFiles).toBe(1)
+      expect(store.totalFiles).toBe(1)
+      expect(store.isProcessing).toBe(false)
+    })
+
+    it("writes output in configured formats", async () => {
+      const settings = useSettingsStore()
+      settings.formats = (["txt", "json"] as OutputFormat[])
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/image.png"], "/output")
+
+      // Should have written txt and json files
+      const writeTextFileCalls = vi.mocked(writeTextFile).mock.calls
+      const writtenPaths = writeTextFileCalls.map((call) => String(call[0]))
+
+      expect(writtenPaths.some((p) => p.endsWith(".txt"))).toBe(true)
+      expect(writtenPaths.some((p) => p.endsWith(".json"))).toBe(true)
+    })
+
+    it("records the job so corrections can rewrite its outputs", async () => {
+      const settings = useSettingsStore()
+      settings.formats = (["txt", "json"] as OutputFormat[])
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/image.png"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("record_job", expect.objectContaining({
+        jobId: expect.any(String),
+        sourcePath: "/path/to/image.png",
+        outputPath: "/output/image",
+        formats: ["txt", "json"],
+        pages: ["Extracted text"],
+      }))
+    })
+
+    it("runs the post-processing pipeline before writing", async () => {
+      const settings = useSettingsStore()
+      settings.formats = (["txt"] as OutputFormat[])
+      settings.setPostprocessing([{ step: "unifyLetters" }])
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "apply_postprocessing") return { pages: ["Processed text"] }
+        return base(cmd, args as never)
+      })
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/image.png"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("apply_postprocessing", {
+        pages: ["Extracted text"],
+        pipeline: [{ step: "unifyLetters" }],
+      })
+      expect(vi.mocked(writeTextFile)).toHaveBeenCalledWith("/output/image.txt", "Processed text")
+    })
+
+    it("combines a folder's images into one document", async () => {
+      const settings = useSettingsStore()
+      settings.formats = (["txt"] as OutputFormat[])
+      settings.deskewImages = true
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "prepare_image_document") {
+          return {
+            imagePaths: ["/tmp/images/page-0001.png", "/tmp/images/page-0002.png"],
+            tempDir: "/tmp/images",
+            sourcePaths: ["/photos/book/IMG_2.jpg", "/photos/book/IMG_10.jpg"],
+            skipped: [{ path: "/photos/book/IMG_3.jpg", error: "Failed to read image" }],
+            downscaledPages: [],
+          }
+        }
+        return base(cmd, args as never)
+      })
+
+      const store = useProcessingStore()
+      const { processFiles } = useFileProcessor()
+      await processFiles(
+        ["/photos/book/IMG_10.jpg", "/photos/book/IMG_2.jpg", "/photos/book/IMG_3.jpg"],
+        "/output",
+      )
+
+      expect(invoke).toHaveBeenCalledWith("prepare_image_document", {
+        imagePaths: ["/photos/book/IMG_2.jpg", "/photos/book/IMG_3.jpg", "/photos/book/IMG_10.jpg"],
+        options: { deskew: true, crop: false },
+        jobId: expect.any(String),
+      })
+      expect(store.totalFiles).toBe(1)
+      expect(store.errors).toEqual([])
+      expect(vi.mocked(writeTextFile)).toHaveBeenCalledWith(
+        "/output/book.txt",
+        expect.stringContaining("Extracted text"),
+      )
+      expect(useToastStore().toasts.map((t) => t.messageKey)).toContain("toast.imagesSkipped")
+      expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
+        path: "/tmp/images",
+        jobId: expect.any(String),
+      })
+    })
+
+    it("keeps each image a job of its own when combining is off", async () => {
+      useSettingsStore().combineImages = false
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/photos/book/IMG_1.jpg", "/photos/book/IMG_2.jpg"], "/output")
+
+      expect(invoke).not.toHaveBeenCalledWith("prepare_image_document", expect.any(Object))
+      expect(useProcessingStore().totalFiles).toBe(2)
+    })
+  })
+
+  describe("processFiles - PDF processing", () => {
+    beforeEach(() => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 3 })
+      // Use only txt format to avoid docx Blob issues in jsdom
+      const settings = useSettingsStore()
+      settings.formats = (["txt"] as OutputFormat[])
+    })
+
+    it("splits PDF into pages before OCR", async () => {
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/document.pdf"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("get_pdf_page_count", {
+        pdfPath: "/path/to/document.pdf",
+      })
+      expect(invoke).toHaveBeenCalledWith(
+        "split_pdf",
+        expect.objectContaining({
+          pdfPath: "/path/to/document.pdf",
+          totalPages: 3,
+        }),
+      )
+    })
+
+    it("cleans up temp directory after processing", async () => {
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/document.pdf"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
+        path: "/tmp/split",
+        jobId: expect.any(String),
+      })
+    })
+
+    it("keeps the rendered pages next to the outputs when enabled", async () => {
+      const settings = useSettingsStore()
+      settings.keepPageImages = true
+      settings.setPageImagesFolder("scans")
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/document.pdf"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
+        path: "/tmp/split",
+        keepImages: { outputPath: "/output/document", folder: "scans" },
+        jobId: expect.any(String),
+      })
+    })
+
+    it("warns when the page images cannot be kept", async () => {
+      useSettingsStore().keepPageImages = true
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "cleanup_temp_dir") throw new Error("Path not allowed")
+        return base(cmd, args as never)
+      })
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/document.pdf"], "/output")
+
+      expect(useToastStore().toasts.map((t) => t.messageKey)).toContain(
+        "toast.keepPageImagesFailed",
+      )
+      expect(useProcessingStore().completedFiles).toBe(1)
+    })
+
+    it("logs the job's stages to its event log", async () => {
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/document.pdf"], "/output")
+
+      const events = vi
+        .mocked(invoke)
+        .mock.calls.filter((call) => call[0] === "record_job_event")
+        .map((call) => call[1] as { jobId: string; kind: string; message: string })
+      expect(events.map((e) => `${e.kind}:${e.message}`)).toEqual([
+        "stage:started: /path/to/document.pdf",
+        "stage:render",
+        "stage:ocr",
+        "stage:writing",
+        "stage:done",
+      ])
+      expect(new Set(events.map((e) => e.jobId)).size).toBe(1)
+    })
+
+    it("renders and recognizes large PDFs in shards and stitches the text", async () => {
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        const params = args as Record<string, unknown>
+        if (cmd === "get_pdf_page_count") return 5
+        if (cmd === "split_pdf") {
+          const first = params.firstPage as number
+          return {
+            imagePaths: Array.from(
+              { length: params.totalPages as number },
+              (_, i) => `/tmp/shard-${first}/page-${first + i}.png`,
+            ),
+            tempDir: `/tmp/shard-${first}`,
+          }
+        }
+        if (cmd === "upload_to_google_drive") return { fileId: params.filePath }
+        if (cmd === "export_google_doc_as_text") {
+          return { text: `text of ${(params.fileId as string).split("/").pop()}` }
+        }
+        return base(cmd, args as never)
+      })
+      useSettingsStore().shardPages = 2
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/document.pdf"], "/output")
+
+      const splits = vi
+        .mocked(invoke)
+        .mock.calls.filter(([cmd]) => cmd === "split_pdf")
+        .map(([, args]) => {
+          const params = args as Record<string, unknown>
+          return [params.firstPage, params.totalPages]
+        })
+      expect(splits).toEqual([
+        [1, 2],
+        [3, 2],
+        [5, 1],
+      ])
+      // Each shard's workspace is removed once its pages are recognized
+      for (const first of [1, 3, 5]) {
+        expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
+          path: `/tmp/shard-${first}`,
+          jobId: expect.any(String),
+        })
+      }
+      // Each shard's text is spooled, and the outputs are streamed from the spool
+      const spooled = vi
+        .mocked(invoke)
+        .mock.calls.filter(([cmd]) => cmd === "spool_pages")
+        .map(([, args]) => {
+          const params = args as { firstPage: number; pages: Array<{ text: string }> }
+          return [params.firstPage, params.pages.map((page) => page.text)]
+        })
+      expect(spooled).toEqual([
+        [1, ["text of page-1.png", "text of page-2.png"]],
+        [3, ["text of page-3.png", "text of page-4.png"]],
+        [5, ["text of page-5.png"]],
+      ])
+      expect(invoke).toHaveBeenCalledWith(
+        "write_spooled_outputs",
+        expect.objectContaining({
+          outputPath: "/output/document",
+          pageCount: 5,
+          pageSeparator: "\n\nPAGE_SEPARATOR\n\n",
+        }),
+      )
+      expect(writeTextFile).not.toHaveBeenCalledWith("/output/document.txt", expect.anything())
+      expect(invoke).toHaveBeenCalledWith("discard_spool", { jobId: expect.any(String) })
+      expect(useProcessingStore().completedFiles).toBe(1)
+    })
+
+    it("logs the error that failed a job", async () => {
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "split_pdf") throw new Error("Failed to load PDF")
+        return base(cmd, args as never)
+      })
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/document.pdf"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith(
+        "record_job_event",
+        expect.objectContaining({ kind: "error", message: "Error: Failed to load PDF" }),
+      )
+    })
+
+    it("processes each page through OCR", async () => {
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/document.pdf"], "/output")
+
+      // 3 pages = 3 uploads
+      const uploadCalls = vi
+        .mocked(invoke)
+        .mock.calls.filter((call) => call[0] === "upload_to_google_drive")
+      expect(uploadCalls.length).toBe(3)
+    })
+  })
+
+  describe("processFiles - stage modes", () => {
+    beforeEach(() => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 2 })
+      useSettingsStore().formats = ["txt", "json"] as OutputFormat[]
+    })
+
+    it("render-only moves the pages to the output folder without OCR", async () => {
+      const settings = useSettingsStore()
+      settings.stageMode = "renderOnly"
+      settings.setPageImagesFolder("scans")
 
-      const { selectFile } = useFileProcessor()
-      await selectFile()
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/document.pdf"], "/output")
 
-      // Verify OCR was called
-      expect(invoke).toHaveBeenCalledWith(
-        "upload_to_google_drive",
-        expect.any(Object),
-      )
-      // Verify output was written
-      expect(writeTextFile).toHaveBeenCalled()
-      // Verify folder was opened
-      expect(invoke).toHaveBeenCalledWith("open_folder", expect.any(Object))
+      expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
+        path: "/tmp/split",
+        keepImages: { outputPath: "/output/document", folder: "scans" },
+        jobId: expect.any(String),
+      })
+      expect(invoke).not.toHaveBeenCalledWith("upload_to_google_drive", expect.any(Object))
+      expect(writeTextFile).not.toHaveBeenCalled()
+      expect(useProcessingStore().completedFiles).toBe(1)
     })
-  })
 
-  describe("selectFolder", () => {
-    it("shows error when no supported files found", async () => {
-      vi.mocked(open).mockResolvedValue("/empty/folder")
-      vi.mocked(readDir).mockResolvedValue([
-        {
-          name: "readme.txt",
-          isFile: true,
-          isDirectory: false,
-          isSymlink: false,
-        },
-      ])
-      vi.mocked(message).mockResolvedValue("Ok")
+    it("render-only does not need a Google sign-in", async () => {
+      useAuthStore().clearAuth()
+      useSettingsStore().stageMode = "renderOnly"
 
-      const { selectFolder } = useFileProcessor()
-      await selectFolder()
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/document.pdf"], "/output")
 
-      expect(message).toHaveBeenCalledWith("messages.noFiles", {
-        title: "messages.errorTitle",
-        kind: "error",
-      })
+      expect(message).not.toHaveBeenCalled()
+      expect(useProcessingStore().completedFiles).toBe(1)
     })
-  })
 
-  describe("processFiles - image processing", () => {
-    beforeEach(() => {
-      setupAuthenticated()
-      setupFullProcessingMocks()
-    })
+    it("OCR-only writes just the page text JSON", async () => {
+      useSettingsStore().stageMode = "ocrOnly"
 
-    it("processes single image file directly (no PDF split)", async () => {
       const { processFiles } = useFileProcessor()
       await processFiles(["/path/to/image.png"], "/output")
 
-      // Should NOT call PDF-related commands
-      expect(invoke).not.toHaveBeenCalledWith(
-        "get_pdf_page_count",
-        expect.any(Object),
+      const writtenPaths = vi.mocked(writeTextFile).mock.calls.map((call) => String(call[0]))
+      expect(writtenPaths).toEqual(["/output/image.json"])
+      expect(useProcessingStore().outputFiles).toEqual(["/output/image.json"])
+    })
+
+    it("assemble-only rewrites a JSON output in the other formats", async () => {
+      useSettingsStore().stageMode = "assembleOnly"
+      vi.mocked(readTextFile).mockResolvedValue(
+        JSON.stringify([
+          { page: 1, content: "first" },
+          { page: 2, content: "second" },
+        ]),
       )
-      expect(invoke).not.toHaveBeenCalledWith("split_pdf", expect.any(Object))
 
-      // Should call OCR
-      expect(invoke).toHaveBeenCalledWith(
-        "upload_to_google_drive",
-        expect.any(Object),
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/path/to/book.json"], "/output")
+
+      expect(readTextFile).toHaveBeenCalledWith("/path/to/book.json")
+      expect(writeTextFile).toHaveBeenCalledTimes(1)
+      expect(writeTextFile).toHaveBeenCalledWith(
+        "/output/book.txt",
+        expect.stringContaining("first"),
       )
+      expect(invoke).not.toHaveBeenCalledWith("upload_to_google_drive", expect.any(Object))
+      expect(useProcessingStore().completedFiles).toBe(1)
     })
 
-    it("updates processing store state correctly", async () => {
+    it("rejects inputs the mode does not start from", async () => {
       const store = useProcessingStore()
+      const settings = useSettingsStore()
       const { processFiles } = useFileProcessor()
 
-      await processFiles(["/path/to/image.png"], "/output")
-
-      expect(store.completedFiles).toBe(1)
-      expect(store.totalFiles).toBe(1)
-      expect(store.isProcessing).toBe(false)
+      const inputs = {
+        renderOnly: "/path/to/image.png",
+        ocrOnly: "/path/to/document.pdf",
+        assembleOnly: "/path/to/document.pdf",
+      } as const
+      for (const [mode, input] of Object.entries(inputs)) {
+        settings.stageMode = mode as keyof typeof inputs
+        await processFiles([input], "/output")
+        expect(store.errors).toEqual([{ file: input, error: "messages.stageModeInput" }])
+      }
+      expect(invoke).not.toHaveBeenCalledWith("split_pdf", expect.any(Object))
+      expect(invoke).not.toHaveBeenCalledWith("upload_to_google_drive", expect.any(Object))
     })
 
-    it("writes output in configured formats", async () => {
+    it("collects only the mode's inputs from folders", async () => {
+      vi.mocked(readDir).mockResolvedValue([
+        { name: "doc.pdf", isFile: true, isDirectory: false, isSymlink: false },
+        { name: "page.png", isFile: true, isDirectory: false, isSymlink: false },
+        { name: "doc.json", isFile: true, isDirectory: false, isSymlink: false },
+      ])
       const settings = useSettingsStore()
-      settings.formats = (["txt", "json"] as OutputFormat[])
-
-      const { processFiles } = useFileProcessor()
-      await processFiles(["/path/to/image.png"], "/output")
-
-      // Should have written txt and json files
-      const writeTextFileCalls = vi.mocked(writeTextFile).mock.calls
-      const writtenPaths = writeTextFileCalls.map((call) => String(call[0]))
+      const { collectFiles } = useFileProcessor()
 
-      expect(writtenPaths.some((p) => p.endsWith(".txt"))).toBe(true)
-      expect(writtenPaths.some((p) => p.endsWith(".json"))).toBe(true)
+      settings.stageMode = "renderOnly"
+      expect(await collectFiles("/folder")).toEqual(["/folder/doc.pdf"])
+      settings.stageMode = "assembleOnly"
+      expect(await collectFiles("/folder")).toEqual(["/folder/doc.json"])
     })
   })
 
-  describe("processFiles - PDF processing", () => {
+  describe("processFiles - parallel documents", () => {
     beforeEach(() => {
       setupAuthenticated()
-      setupFullProcessingMocks({ pageCount: 3 })
-      // Use only txt format to avoid docx Blob issues in jsdom
+      setupFullProcessingMocks({ pageCount: 2 })
       const settings = useSettingsStore()
       settings.formats = (["txt"] as OutputFormat[])
     })
 
-    it("splits PDF into pages before OCR", async () => {
-      const { processFiles } = useFileProcessor()
-      await processFiles(["/path/to/document.pdf"], "/output")
-
-      expect(invoke).toHaveBeenCalledWith("get_pdf_page_count", {
-        pdfPath: "/path/to/document.pdf",
+    async function trackSplits() {
+      const base = vi.mocked(invoke).getMockImplementation()!
+      let inFlight = 0
+      let maxInFlight = 0
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args) => {
+        if (cmd === "split_pdf") {
+          inFlight++
+          maxInFlight = Math.max(maxInFlight, inFlight)
+          await new Promise((r) => setTimeout(r, 5))
+          inFlight--
+        }
+        return base(cmd, args)
       })
-      expect(invoke).toHaveBeenCalledWith(
-        "split_pdf",
-        expect.objectContaining({
-          pdfPath: "/path/to/document.pdf",
-          totalPages: 3,
-        }),
-      )
-    })
-
-    it("cleans up temp directory after processing", async () => {
       const { processFiles } = useFileProcessor()
-      await processFiles(["/path/to/document.pdf"], "/output")
+      await processFiles(["/docs/a.pdf", "/docs/b.pdf", "/docs/c.pdf"], "/output")
+      return maxInFlight
+    }
 
-      expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
-        path: "/tmp/split",
-      })
+    it("processes one document at a time by default", async () => {
+      expect(await trackSplits()).toBe(1)
+      expect(useProcessingStore().completedFiles).toBe(3)
     })
 
-    it("processes each page through OCR", async () => {
-      const { processFiles } = useFileProcessor()
-      await processFiles(["/path/to/document.pdf"], "/output")
+    it("processes up to parallelDocuments documents at once", async () => {
+      useSettingsStore().setParallelDocuments(2)
 
-      // 3 pages = 3 uploads
-      const uploadCalls = vi
-        .mocked(invoke)
-        .mock.calls.filter((call) => call[0] === "upload_to_google_drive")
-      expect(uploadCalls.length).toBe(3)
+      expect(await trackSplits()).toBe(2)
+      const store = useProcessingStore()
+      expect(store.completedFiles).toBe(3)
+      expect(store.errors).toEqual([])
+      expect(store.outputFiles).toEqual(
+        expect.arrayContaining(["/output/a.txt", "/output/b.txt", "/output/c.txt"]),
+      )
     })
   })
 
@@ -472,6 +1449,8 @@ describe("useFileProcessor", () => {
         return undefined
       })
       vi.mocked(writeTextFile).mockResolvedValue(undefined)
+      // Each image is its own job
+      useSettingsStore().combineImages = false
 
       const store = useProcessingStore()
       const { processFiles } = useFileProcessor()
@@ -501,6 +1480,7 @@ describe("useFileProcessor", () => {
 
       expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
         path: "/tmp/split",
+        jobId: expect.any(String),
       })
     })
   })
@@ -525,6 +1505,7 @@ describe("useFileProcessor", () => {
         }
         return undefined
       })
+      useSettingsStore().combineImages = false
 
       const { processFiles } = useFileProcessor()
       await processFiles(["/file1.png", "/file2.png", "/file3.png"], "/output")
@@ -550,6 +1531,335 @@ describe("useFileProcessor", () => {
 
       expect(invoke).not.toHaveBeenCalledWith("open_folder", expect.any(Object))
     })
+
+    it("stops a split that is still rendering", async () => {
+      const store = useProcessingStore()
+      setupFullProcessingMocks({ pageCount: 2 })
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "split_pdf") {
+          store.cancelProcessing()
+          await new Promise((resolve) => setTimeout(resolve, 0))
+          throw new Error("Rendering cancelled")
+        }
+        return base(cmd, args as never)
+      })
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/books/book.pdf"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("cancel_split", { jobId: expect.any(String) })
+      expect(invoke).not.toHaveBeenCalledWith("upload_to_google_drive", expect.any(Object))
+      expect(invoke).toHaveBeenCalledWith("record_job_stop", expect.objectContaining({
+        stop: expect.objectContaining({ reason: "cancelled" }),
+      }))
+    })
+  })
+
+  describe("partial results", () => {
+    beforeEach(() => {
+      setupAuthenticated()
+    })
+
+    it("saves each page's text as soon as it is recognized", async () => {
+      setupFullProcessingMocks({ pageCount: 2 })
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/books/book.pdf"], "/output")
+
+      for (const page of [1, 2]) {
+        expect(invoke).toHaveBeenCalledWith("record_partial_page", {
+          jobId: expect.any(String),
+          page,
+          text: "Extracted text",
+        })
+      }
+    })
+
+    it("streams pages into a live TXT that the final TXT replaces", async () => {
+      setupFullProcessingMocks({ pageCount: 2 })
+      const settings = useSettingsStore()
+      settings.liveTxt = true
+      settings.formats = ["txt", "json"] as OutputFormat[]
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/books/book.pdf"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("start_live_txt", { outputPath: "/books/book" })
+      for (const page of [1, 2]) {
+        expect(invoke).toHaveBeenCalledWith("append_live_txt", {
+          outputPath: "/books/book",
+          page,
+          text: "Extracted text",
+        })
+      }
+      expect(invoke).toHaveBeenCalledWith("finish_live_txt", {
+        outputPath: "/books/book",
+        text: "Extracted text\n\nPAGE_SEPARATOR\n\nExtracted text",
+      })
+      const writtenPaths = vi.mocked(writeTextFile).mock.calls.map((call) => String(call[0]))
+      expect(writtenPaths.some((p) => p.endsWith(".txt"))).toBe(false)
+    })
+
+    it("records why a job stopped and offers its completed pages", async () => {
+      const store = useProcessingStore()
+      vi.mocked(invoke).mockImplementation(async (cmd: string) => {
+        if (cmd === "upload_to_google_drive") {
+          store.cancelProcessing()
+          throw new Error("Processing cancelled")
+        }
+        if (cmd === "get_partial_results") {
+          return { jobId: "job-1", stop: null, pages: [{ page: 1, text: "a" }], missingPages: [] }
+        }
+        return undefined
+      })
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/books/scan.png"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("record_job_stop", {
+        jobId: expect.any(String),
+        stop: {
+          reason: "cancelled",
+          errorKind: undefined,
+          message: "Error: Processing cancelled",
+          sourcePath: "/books/scan.png",
+          outputPath: "/output/scan",
+        },
+      })
+      expect(store.partialJobs).toEqual([
+        { jobId: expect.any(String), file: "/books/scan.png", pages: 1 },
+      ])
+    })
+
+    it("marks a job running until it ends", async () => {
+      setupFullProcessingMocks()
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/books/scan.png"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("begin_job", {
+        jobId: expect.any(String),
+        sourcePath: "/books/scan.png",
+        outputPath: "/output/scan",
+      })
+      const begun = vi.mocked(invoke).mock.calls.find(([cmd]) => cmd === "begin_job")!
+      const jobId = (begun[1] as { jobId: string }).jobId
+      expect(invoke).toHaveBeenCalledWith("end_job", { jobId })
+    })
+
+    it("assembles a stopped job's pages on demand", async () => {
+      const store = useProcessingStore()
+      store.addPartialJob({ jobId: "job-1", file: "/books/scan.png", pages: 1 })
+      vi.mocked(invoke).mockResolvedValue({
+        paths: ["/output/scan-partial.txt"],
+        missingPages: [],
+      })
+
+      const { savePartialResults } = useFileProcessor()
+      await savePartialResults("job-1")
+
+      expect(invoke).toHaveBeenCalledWith("assemble_partial_results", {
+        jobId: "job-1",
+        formats: useSettingsStore().formats,
+        pageSeparator: useSettingsStore().pageSeparator,
+      })
+      expect(store.outputFiles).toEqual(["/output/scan-partial.txt"])
+      expect(store.partialJobs).toEqual([])
+      expect(useToastStore().toasts[0].messageKey).toBe("toast.partialSaved")
+    })
+  })
+
+  describe("interrupted jobs", () => {
+    const interrupted = {
+      jobId: "job-1",
+      sourcePath: "/books/book.pdf",
+      outputPath: "/output/book",
+      pages: 3,
+    }
+
+    beforeEach(() => {
+      setupAuthenticated()
+      setupFullProcessingMocks()
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "take_interrupted_jobs") return [interrupted]
+        return base(cmd, args as never)
+      })
+    })
+
+    it("cleans up a job the user does not resume", async () => {
+      vi.mocked(ask).mockResolvedValue(false)
+
+      const { recoverInterruptedJobs } = useFileProcessor()
+      await recoverInterruptedJobs()
+
+      expect(ask).toHaveBeenCalledWith("messages.jobInterrupted", expect.objectContaining({
+        okLabel: "buttons.resumeJob",
+        cancelLabel: "buttons.cleanUpJob",
+      }))
+      expect(invoke).toHaveBeenCalledWith("discard_job", { jobId: "job-1" })
+      expect(invoke).not.toHaveBeenCalledWith("split_pdf", expect.anything())
+    })
+
+    it("converts a resumed job again into its folder", async () => {
+      vi.mocked(ask).mockResolvedValue(true)
+
+      const { recoverInterruptedJobs } = useFileProcessor()
+      await recoverInterruptedJobs()
+
+      expect(invoke).toHaveBeenCalledWith("discard_job", { jobId: "job-1" })
+      expect(invoke).toHaveBeenCalledWith("begin_job", {
+        jobId: expect.any(String),
+        sourcePath: "/books/book.pdf",
+        outputPath: "/output/book",
+      })
+      expect(useProcessingStore().outputFolder).toBe("/output")
+    })
+  })
+
+  describe("auto-rotation", () => {
+    it("uploads upright copies and masks them", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 2 })
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "correct_page_orientation") {
+          const { imagePath } = args as { imagePath: string }
+          return imagePath === "/tmp/page_2.png"
+            ? { rotation: 180, path: "/tmp/page_2-rotated.png" }
+            : { rotation: 0, path: imagePath }
+        }
+        if (cmd === "mask_page_image") {
+          return (args as { imagePath: string }).imagePath
+        }
+        return base(cmd, args as never)
+      })
+      useSettingsStore().setMaskRegions([{ x: 60, y: 80, width: 35, height: 15 }])
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/books/book.pdf"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("mask_page_image", expect.objectContaining({
+        imagePath: "/tmp/page_2-rotated.png",
+        page: 2,
+      }))
+      expect(invoke).toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+        filePath: "/tmp/page_2-rotated.png",
+      }))
+      expect(invoke).toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+        filePath: "/tmp/page_1.png",
+      }))
+    })
+
+    it("OCRs the page as it is when the check fails", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks()
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "correct_page_orientation") {
+          throw { kind: "io", message: "Failed to open image" }
+        }
+        return base(cmd, args as never)
+      })
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/books/book.pdf"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+        filePath: "/tmp/page_1.png",
+      }))
+    })
+  })
+
+  describe("mask regions", () => {
+    it("uploads masked copies of the rendered pages", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 2 })
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "mask_page_image") {
+          const { imagePath } = args as { imagePath: string }
+          return imagePath.replace(".png", "-masked.png")
+        }
+        return base(cmd, args as never)
+      })
+      const regions = [{ page: 2, x: 60, y: 80, width: 35, height: 15 }]
+      useSettingsStore().setMaskRegions(regions)
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/books/book.pdf"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("mask_page_image", {
+        imagePath: "/tmp/page_2.png",
+        page: 2,
+        regions,
+      })
+      expect(invoke).toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+        filePath: "/tmp/page_2-masked.png",
+      }))
+      expect(invoke).not.toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+        filePath: "/tmp/page_2.png",
+      }))
+    })
+
+    it("does not mask when no regions are set", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks()
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/books/book.pdf"], "/output")
+
+      expect(invoke).not.toHaveBeenCalledWith("mask_page_image", expect.any(Object))
+    })
+  })
+
+  describe("regions of interest", () => {
+    it("recognizes only the cropped regions and labels them in the JSON output", async () => {
+      setupAuthenticated()
+      setupFullProcessingMocks({ pageCount: 1, ocrText: "value" })
+      const base = vi.mocked(invoke).getMockImplementation()!
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "crop_page_regions") {
+          return [
+            { label: "Name", path: "/tmp/page_1-roi-1.png" },
+            { label: "Date", path: "/tmp/page_1-roi-2.png" },
+          ]
+        }
+        return base(cmd, args as never)
+      })
+      const settingsStore = useSettingsStore()
+      settingsStore.toggleFormat("json")
+      const regions = [
+        { label: "Name", x: 0, y: 0, width: 50, height: 10 },
+        { label: "Date", x: 50, y: 0, width: 50, height: 10 },
+      ]
+      settingsStore.setRoiRegions(regions)
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/books/form.pdf"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith("crop_page_regions", {
+        imagePath: "/tmp/page_1.png",
+        page: 1,
+        regions,
+      })
+      expect(invoke).not.toHaveBeenCalledWith("upload_to_google_drive", expect.objectContaining({
+        filePath: "/tmp/page_1.png",
+      }))
+      expect(writeTextFile).toHaveBeenCalledWith("/output/form.txt", "Name: value\nDate: value")
+      const json = vi.mocked(writeTextFile).mock.calls.find(([path]) => path === "/output/form.json")
+      expect(JSON.parse(json![1] as string)).toEqual([
+        {
+          page: 1,
+          content: "Name: value\nDate: value",
+          regions: [
+            { label: "Name", content: "value" },
+            { label: "Date", content: "value" },
+          ],
+        },
+      ])
+    })
   })
 
   describe("cancelProcessing", () => {
@@ -585,6 +1895,21 @@ describe("useFileProcessor", () => {
       )
     })
 
+    it("passes auto DPI to split_pdf when enabled", async () => {
+      const settings = useSettingsStore()
+      settings.autoDpi = true
+
+      const { processFiles } = useFileProcessor()
+      await processFiles(["/doc.pdf"], "/output")
+
+      expect(invoke).toHaveBeenCalledWith(
+        "split_pdf",
+        expect.objectContaining({
+          autoDpi: true,
+        }),
+      )
+    })
+
     it("uses custom output directory when configured", async () => {
       const settings = useSettingsStore()
       settings.outputDirectory = "/custom/output"
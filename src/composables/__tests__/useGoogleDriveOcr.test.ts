@@ -5,14 +5,22 @@ import { setActivePinia, createPinia } from "pinia"
 vi.mock("@tauri-apps/api/core", () => ({
   invoke: vi.fn(),
 }))
+vi.mock("@tauri-apps/api/event", () => ({
+  emit: vi.fn(),
+}))
 
-import { useGoogleDriveOcr } from "../useGoogleDriveOcr"
+import { useGoogleDriveOcr, type OcrProgress } from "../useGoogleDriveOcr"
 import { useProcessingStore } from "@/stores/processing"
 import { useAuthStore } from "@/stores/auth"
+import { useSettingsStore } from "@/stores/settings"
+import { useToastStore } from "@/stores/toast"
 import { invoke } from "@tauri-apps/api/core"
+import { emit } from "@tauri-apps/api/event"
+import { OCR_PAGE_STALL_MS } from "@/utils/watchdog"
 
 describe("useGoogleDriveOcr", () => {
   beforeEach(() => {
+    localStorage.clear()
     setActivePinia(createPinia())
     vi.clearAllMocks()
   })
@@ -29,6 +37,34 @@ describe("useGoogleDriveOcr", () => {
     })
   }
 
+  describe("listDriveFiles", () => {
+    it("lists Drive files matching a name", async () => {
+      setupAuthenticated()
+      const list = {
+        files: [{ id: "f1", name: "book.pdf", mimeType: "application/pdf", size: 10, modifiedTime: null }],
+        nextPageToken: "p2",
+      }
+      vi.mocked(invoke).mockResolvedValue(list)
+
+      const { listDriveFiles } = useGoogleDriveOcr()
+      const result = await listDriveFiles("book")
+
+      expect(result).toEqual(list)
+      expect(invoke).toHaveBeenCalledWith("list_drive_files", {
+        accessToken: "valid_token",
+        name: "book",
+        pageToken: null,
+      })
+    })
+
+    it("throws error when not authenticated", async () => {
+      const { listDriveFiles } = useGoogleDriveOcr()
+
+      await expect(listDriveFiles()).rejects.toThrow("Not authenticated")
+      expect(invoke).not.toHaveBeenCalled()
+    })
+  })
+
   describe("uploadFile", () => {
     it("uploads file and returns file ID when authenticated", async () => {
       setupAuthenticated()
@@ -113,6 +149,21 @@ describe("useGoogleDriveOcr", () => {
       expect(result).toBe("Page content\n\nMore content")
     })
 
+    it("passes the job ID and page so the export is timed", async () => {
+      setupAuthenticated()
+      vi.mocked(invoke).mockResolvedValue({ text: "Text" })
+
+      const { exportAsText } = useGoogleDriveOcr()
+      await exportAsText("file123", "job-1", 3)
+
+      expect(invoke).toHaveBeenCalledWith("export_google_doc_as_text", {
+        fileId: "file123",
+        accessToken: "valid_token",
+        jobId: "job-1",
+        page: 3,
+      })
+    })
+
     it("removes BOM followed by underscores", async () => {
       setupAuthenticated()
       vi.mocked(invoke).mockResolvedValue({
@@ -272,11 +323,7 @@ describe("useGoogleDriveOcr", () => {
         return undefined
       })
 
-      const progressCalls: Array<{
-        completed: number
-        total: number
-        percentage: number
-      }> = []
+      const progressCalls: OcrProgress[] = []
       const { extractText } = useGoogleDriveOcr()
       await extractText(["/p1.png", "/p2.png", "/p3.png"], 1, (p) =>
         progressCalls.push(p),
@@ -286,16 +333,19 @@ describe("useGoogleDriveOcr", () => {
         completed: 1,
         total: 3,
         percentage: 33,
+        concurrency: 20,
       })
       expect(progressCalls).toContainEqual({
         completed: 2,
         total: 3,
         percentage: 67,
+        concurrency: 20,
       })
       expect(progressCalls).toContainEqual({
         completed: 3,
         total: 3,
         percentage: 100,
+        concurrency: 20,
       })
     })
 
@@ -399,5 +449,164 @@ describe("useGoogleDriveOcr", () => {
 
       expect(deleteCalls).toContain("file123")
     })
+
+    it("uploads a page again when its export comes back empty", async () => {
+      setupAuthenticated()
+      const deleted: string[] = []
+      let uploads = 0
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args) => {
+        if (cmd === "upload_to_google_drive") return { fileId: `file${++uploads}` }
+        if (cmd === "export_google_doc_as_text") {
+          return { text: (args as { fileId: string }).fileId === "file1" ? "\uFEFF__\n" : "Text" }
+        }
+        if (cmd === "delete_google_drive_file") {
+          deleted.push((args as { fileId: string }).fileId)
+        }
+        return undefined
+      })
+
+      const { extractText } = useGoogleDriveOcr()
+      const result = await extractText(["/path.png"], 1)
+
+      expect(result).toEqual(["Text"])
+      expect(uploads).toBe(2)
+      expect(deleted).toEqual(["file1", "file2"])
+    })
+
+    it("warns about pages that stay empty after the retry", async () => {
+      setupAuthenticated()
+      let uploads = 0
+      vi.mocked(invoke).mockImplementation(async (cmd: string) => {
+        if (cmd === "upload_to_google_drive") return { fileId: `file${++uploads}` }
+        if (cmd === "export_google_doc_as_text") return { text: " " }
+        return undefined
+      })
+
+      const { extractText } = useGoogleDriveOcr()
+      const result = await extractText(["/blank.png"], 1)
+
+      expect(result).toEqual([""])
+      expect(uploads).toBe(2)
+      expect(useToastStore().toasts.map((t) => t.messageKey)).toContain("toast.ocrEmptyPages")
+    })
+
+    it("caps Drive pages in flight across concurrent documents", async () => {
+      setupAuthenticated()
+      useSettingsStore().setDriveConcurrency(2)
+      let inFlight = 0
+      let maxInFlight = 0
+      vi.mocked(invoke).mockImplementation(async (cmd: string) => {
+        if (cmd === "upload_to_google_drive") {
+          inFlight++
+          maxInFlight = Math.max(maxInFlight, inFlight)
+          await new Promise((r) => setTimeout(r, 5))
+          return { fileId: "file123" }
+        }
+        if (cmd === "export_google_doc_as_text") {
+          inFlight--
+          return { text: "Text" }
+        }
+        return undefined
+      })
+
+      const { extractText } = useGoogleDriveOcr()
+      const [first, second] = await Promise.all([
+        extractText(["/a1.png", "/a2.png", "/a3.png"], 5),
+        extractText(["/b1.png", "/b2.png", "/b3.png"], 5),
+      ])
+
+      expect(first).toEqual(["Text", "Text", "Text"])
+      expect(second).toEqual(["Text", "Text", "Text"])
+      expect(maxInFlight).toBe(2)
+    })
+
+    it("restarts a stalled page once and reports it", async () => {
+      setupAuthenticated()
+      vi.useFakeTimers()
+      vi.spyOn(console, "warn").mockImplementation(() => {})
+      let uploads = 0
+      vi.mocked(invoke).mockImplementation(async (cmd: string) => {
+        if (cmd === "upload_to_google_drive") {
+          uploads++
+          // The first upload never answers
+          if (uploads === 1) return new Promise(() => {})
+          return { fileId: "file123" }
+        }
+        if (cmd === "export_google_doc_as_text") return { text: "Text" }
+        return undefined
+      })
+
+      try {
+        const { extractText } = useGoogleDriveOcr()
+        const result = extractText(["/page.png"], 1, undefined, "job-1")
+        await vi.advanceTimersByTimeAsync(OCR_PAGE_STALL_MS)
+
+        expect(await result).toEqual(["Text"])
+        expect(uploads).toBe(2)
+        expect(emit).toHaveBeenCalledWith("job-stalled", {
+          jobId: "job-1",
+          stage: "ocr",
+          page: 1,
+          idleMs: OCR_PAGE_STALL_MS,
+          action: "restarted",
+        })
+      } finally {
+        vi.useRealTimers()
+      }
+    })
+
+    it("lowers the Drive limit when Drive throttles and reports it", async () => {
+      setupAuthenticated()
+      useSettingsStore().setDriveConcurrency(4)
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args) => {
+        const { filePath } = (args ?? {}) as { filePath?: string }
+        if (cmd === "upload_to_google_drive") {
+          if (filePath === "/throttled.png") {
+            throw {
+              kind: "rateLimited",
+              messageKey: "errors.rateLimited",
+              message: "Too many requests",
+              details: "Upload failed (429 Too Many Requests)",
+              retriable: true,
+            }
+          }
+          return { fileId: "file123" }
+        }
+        if (cmd === "export_google_doc_as_text") return { text: "Text" }
+        return undefined
+      })
+
+      const { extractText } = useGoogleDriveOcr()
+      const progress: OcrProgress[] = []
+      // Enough pages for the limit to reach the setting, whatever it was before
+      const pages = Array.from({ length: 10 }, (_, i) => `/page${i}.png`)
+      await extractText([...pages, "/throttled.png"], 1, (p) => progress.push(p))
+
+      expect(progress[pages.length - 1].concurrency).toBe(4)
+      expect(progress[pages.length].concurrency).toBe(2)
+    })
+
+    it("lowers the Drive limit when a throttled upload succeeds on a retry", async () => {
+      setupAuthenticated()
+      useSettingsStore().setDriveConcurrency(4)
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args) => {
+        const { filePath } = (args ?? {}) as { filePath?: string }
+        if (cmd === "upload_to_google_drive") {
+          // The backend got past a 429 with a retry
+          return { fileId: "file123", throttled: filePath === "/throttled.png" ? 1 : 0 }
+        }
+        if (cmd === "export_google_doc_as_text") return { text: "Text" }
+        return undefined
+      })
+
+      const { extractText } = useGoogleDriveOcr()
+      const progress: OcrProgress[] = []
+      const pages = Array.from({ length: 10 }, (_, i) => `/page${i}.png`)
+      const texts = await extractText([...pages, "/throttled.png"], 1, (p) => progress.push(p))
+
+      expect(texts[pages.length]).toBe("Text")
+      expect(progress[pages.length - 1].concurrency).toBe(4)
+      expect(progress[pages.length].concurrency).toBe(2)
+    })
   })
 })
@@ -3,13 +3,16 @@ import { describe, it, expect, vi, beforeEach } from "vitest"
 // Mock Tauri APIs before importing
 vi.mock("@tauri-apps/api/core", () => ({
   invoke: vi.fn(),
+  Channel: class {
+    onmessage: (message: unknown) => void = () => {}
+  },
 }))
 
 vi.mock("@tauri-apps/api/event", () => ({
   listen: vi.fn(),
 }))
 
-import { usePdfProcessor, cleanupTempDir } from "../usePdfProcessor"
+import { usePdfProcessor, cleanupTempDir, shardRanges, undoCleanup } from "../usePdfProcessor"
 import { invoke } from "@tauri-apps/api/core"
 import { listen } from "@tauri-apps/api/event"
 
@@ -29,6 +32,17 @@ describe("usePdfProcessor", () => {
       })
     })
 
+    it("passes where to keep the page images", async () => {
+      vi.mocked(invoke).mockResolvedValue(undefined)
+
+      await cleanupTempDir("/tmp/test-dir", { outputPath: "/out/book", folder: "pages" })
+
+      expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
+        path: "/tmp/test-dir",
+        keepImages: { outputPath: "/out/book", folder: "pages" },
+      })
+    })
+
     it("propagates errors from invoke", async () => {
       vi.mocked(invoke).mockRejectedValue(new Error("Cleanup failed"))
 
@@ -36,6 +50,25 @@ describe("usePdfProcessor", () => {
         "Cleanup failed",
       )
     })
+    it("passes the job the directory belonged to", async () => {
+      vi.mocked(invoke).mockResolvedValue(undefined)
+
+      await cleanupTempDir("/tmp/test-dir", undefined, "job-1")
+
+      expect(invoke).toHaveBeenCalledWith("cleanup_temp_dir", {
+        path: "/tmp/test-dir",
+        jobId: "job-1",
+      })
+    })
+  })
+
+  describe("undoCleanup", () => {
+    it("returns the restored directories", async () => {
+      vi.mocked(invoke).mockResolvedValue(["/tmp/test-dir"])
+
+      await expect(undoCleanup("job-1")).resolves.toEqual(["/tmp/test-dir"])
+      expect(invoke).toHaveBeenCalledWith("undo_cleanup", { jobId: "job-1" })
+    })
   })
 
   describe("getPageCount", () => {
@@ -69,6 +102,20 @@ describe("usePdfProcessor", () => {
     })
   })
 
+  describe("getPdfInfo", () => {
+    it("returns the page count and metadata from invoke", async () => {
+      const info = { pageCount: 3, title: "الرسالة", author: "الشافعي" }
+      vi.mocked(invoke).mockResolvedValue(info)
+
+      const { getPdfInfo } = usePdfProcessor()
+
+      expect(await getPdfInfo("/path/to/file.pdf")).toEqual(info)
+      expect(invoke).toHaveBeenCalledWith("get_pdf_info", {
+        pdfPath: "/path/to/file.pdf",
+      })
+    })
+  })
+
   describe("splitPdf", () => {
     it("splits PDF and returns result", async () => {
       const mockResult = {
@@ -161,6 +208,74 @@ describe("usePdfProcessor", () => {
       })
     })
 
+    it("ignores progress of other jobs' splits", async () => {
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      let progressCallback: (event: any) => void = () => {}
+      vi.mocked(listen).mockImplementation(async (_event, callback) => {
+        progressCallback = (callback as typeof progressCallback)
+        return () => {}
+      })
+      vi.mocked(invoke).mockImplementation(async (cmd: string) => {
+        if (cmd === "get_pdf_page_count") return 3
+        if (cmd === "split_pdf") {
+          progressCallback({
+            payload: { jobId: "other", currentPage: 9, totalPages: 40, percentage: 23 },
+          })
+          progressCallback({
+            payload: { currentPage: 1, totalPages: 3, percentage: 33 },
+          })
+          return { imagePaths: [], tempDir: "/tmp" }
+        }
+        return null
+      })
+
+      const onProgress = vi.fn()
+      const { splitPdf } = usePdfProcessor()
+      await splitPdf("/path/to/file.pdf", 150, onProgress)
+
+      expect(onProgress).toHaveBeenCalledTimes(1)
+      expect(onProgress).toHaveBeenCalledWith({ currentPage: 1, totalPages: 3, percentage: 33 })
+    })
+
+    it("takes a job's progress from its own channel", async () => {
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      let channel: any = null
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "get_pdf_page_count") return 2
+        if (cmd === "subscribe_job_events") {
+          channel = (args as { onEvent: unknown }).onEvent
+          return 7
+        }
+        if (cmd === "split_pdf") {
+          channel.onmessage({ event: "stage-timing", payload: { jobId: "job-1", page: 1 } })
+          channel.onmessage({
+            event: "split-progress",
+            payload: { jobId: "job-1", currentPage: 1, totalPages: 2, percentage: 50 },
+          })
+          return { imagePaths: [], tempDir: "/tmp" }
+        }
+        return null
+      })
+
+      const onProgress = vi.fn()
+      const { splitPdf } = usePdfProcessor()
+      await splitPdf("/path/to/file.pdf", 150, onProgress, "job-1")
+
+      expect(listen).not.toHaveBeenCalled()
+      expect(invoke).toHaveBeenCalledWith("subscribe_job_events", {
+        jobId: "job-1",
+        onEvent: channel,
+      })
+      expect(onProgress).toHaveBeenCalledTimes(1)
+      expect(onProgress).toHaveBeenCalledWith({
+        jobId: "job-1",
+        currentPage: 1,
+        totalPages: 2,
+        percentage: 50,
+      })
+      expect(invoke).toHaveBeenCalledWith("unsubscribe_job_events", { subscription: 7 })
+    })
+
     it("does not set up listener when no callback provided", async () => {
       vi.mocked(invoke).mockImplementation(async (cmd: string) => {
         if (cmd === "get_pdf_page_count") return 1
@@ -194,6 +309,90 @@ describe("usePdfProcessor", () => {
     })
   })
 
+  describe("shardRanges", () => {
+    it("splits into consecutive shards with a shorter last one", () => {
+      expect(shardRanges(1200, 500)).toEqual([
+        { firstPage: 1, pageCount: 500 },
+        { firstPage: 501, pageCount: 500 },
+        { firstPage: 1001, pageCount: 200 },
+      ])
+    })
+
+    it("keeps small documents and disabled sharding in one range", () => {
+      expect(shardRanges(40, 500)).toEqual([{ firstPage: 1, pageCount: 40 }])
+      expect(shardRanges(1200, 0)).toEqual([{ firstPage: 1, pageCount: 1200 }])
+      expect(shardRanges(0, 500)).toEqual([])
+    })
+  })
+
+  describe("splitPdf with a page range", () => {
+    it("renders only the range without counting pages", async () => {
+      vi.mocked(invoke).mockResolvedValue({ imagePaths: [], tempDir: "/tmp" })
+
+      const { splitPdf } = usePdfProcessor()
+      await splitPdf("/path/to/file.pdf", 150, undefined, "job-1", false, {
+        firstPage: 501,
+        pageCount: 500,
+      })
+
+      expect(invoke).not.toHaveBeenCalledWith("get_pdf_page_count", expect.anything())
+      expect(invoke).toHaveBeenCalledWith("split_pdf", {
+        pdfPath: "/path/to/file.pdf",
+        dpi: 150,
+        autoDpi: false,
+        totalPages: 500,
+        firstPage: 501,
+        jobId: "job-1",
+      })
+    })
+  })
+
+  describe("prepareImageDocument", () => {
+    it("prepares the pages with the job's progress", async () => {
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      let channel: any = null
+      const document = {
+        imagePaths: ["/tmp/images/page-0001.png"],
+        tempDir: "/tmp/images",
+        sourcePaths: ["/photos/a.jpg"],
+        skipped: [{ path: "/photos/b.jpg", error: "Failed to read image" }],
+        downscaledPages: [],
+      }
+      vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+        if (cmd === "subscribe_job_events") {
+          channel = (args as { onEvent: unknown }).onEvent
+          return 4
+        }
+        if (cmd === "prepare_image_document") {
+          channel.onmessage({
+            event: "split-progress",
+            payload: { jobId: "job-1", currentPage: 2, totalPages: 2, percentage: 100 },
+          })
+          return document
+        }
+        return null
+      })
+
+      const onProgress = vi.fn()
+      const { prepareImageDocument } = usePdfProcessor()
+      const result = await prepareImageDocument(
+        ["/photos/a.jpg", "/photos/b.jpg"],
+        { deskew: false, crop: true },
+        onProgress,
+        "job-1",
+      )
+
+      expect(result).toEqual(document)
+      expect(invoke).toHaveBeenCalledWith("prepare_image_document", {
+        imagePaths: ["/photos/a.jpg", "/photos/b.jpg"],
+        options: { deskew: false, crop: true },
+        jobId: "job-1",
+      })
+      expect(onProgress).toHaveBeenCalledWith(expect.objectContaining({ currentPage: 2 }))
+      expect(invoke).toHaveBeenCalledWith("unsubscribe_job_events", { subscription: 4 })
+    })
+  })
+
   describe("extractPage", () => {
     it("extracts single page from PDF", async () => {
       vi.mocked(invoke).mockResolvedValue("/output/page.png")
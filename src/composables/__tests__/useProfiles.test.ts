@@ -0,0 +1,52 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { useProfiles } from "../useProfiles"
+import { invoke } from "@tauri-apps/api/core"
+
+describe("useProfiles", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("lists the profiles", async () => {
+    const list = { profiles: ["default", "staff"], active: "staff" }
+    vi.mocked(invoke).mockResolvedValue(list)
+
+    await expect(useProfiles().listProfiles()).resolves.toEqual(list)
+    expect(invoke).toHaveBeenCalledWith("list_profiles")
+  })
+
+  it("creates a profile with a trimmed name", async () => {
+    vi.mocked(invoke).mockResolvedValue({ profiles: ["default", "staff"], active: "default" })
+
+    await useProfiles().createProfile("  staff ")
+
+    expect(invoke).toHaveBeenCalledWith("create_profile", { name: "staff" })
+  })
+
+  it("reloads the window after switching", async () => {
+    const reload = vi.fn()
+    vi.stubGlobal("location", { ...window.location, reload })
+    vi.mocked(invoke).mockResolvedValue({ profiles: ["default", "staff"], active: "staff" })
+
+    await useProfiles().switchProfile("staff")
+
+    expect(invoke).toHaveBeenCalledWith("switch_profile", { name: "staff" })
+    expect(reload).toHaveBeenCalled()
+    vi.unstubAllGlobals()
+  })
+
+  it("does not reload when the switch fails", async () => {
+    const reload = vi.fn()
+    vi.stubGlobal("location", { ...window.location, reload })
+    vi.mocked(invoke).mockRejectedValue(new Error("No profile named staff"))
+
+    await expect(useProfiles().switchProfile("staff")).rejects.toThrow("No profile named staff")
+    expect(reload).not.toHaveBeenCalled()
+    vi.unstubAllGlobals()
+  })
+})
@@ -0,0 +1,99 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+import { setActivePinia, createPinia } from "pinia"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { useQuickOcr } from "../useQuickOcr"
+import { useAuthStore } from "@/stores/auth"
+import { useToastStore } from "@/stores/toast"
+import { invoke } from "@tauri-apps/api/core"
+
+function setupAuthenticated() {
+  useAuthStore().setTokens({
+    accessToken: "token",
+    refreshToken: "refresh",
+    expiresAt: Date.now() + 60 * 60 * 1000,
+  })
+}
+
+function toastKeys() {
+  return useToastStore().toasts.map((t) => t.messageKey)
+}
+
+describe("useQuickOcr", () => {
+  beforeEach(() => {
+    setActivePinia(createPinia())
+    vi.clearAllMocks()
+  })
+
+  it("asks to sign in when there is no token", async () => {
+    const { runQuickOcr } = useQuickOcr()
+
+    expect(await runQuickOcr("region")).toBeNull()
+    expect(invoke).not.toHaveBeenCalled()
+    expect(toastKeys()).toContain("toast.quickOcrSignIn")
+  })
+
+  it("copies the text of a screen region", async () => {
+    setupAuthenticated()
+    vi.mocked(invoke).mockImplementation(async (cmd: string) => {
+      if (cmd === "ocr_screen_region") return "نص"
+      return 3
+    })
+
+    const { runQuickOcr } = useQuickOcr()
+    expect(await runQuickOcr("region")).toBe("نص")
+
+    expect(invoke).toHaveBeenCalledWith("ocr_screen_region", { accessToken: "token" })
+    expect(invoke).toHaveBeenCalledWith("copy_text_to_clipboard", { text: "نص" })
+    expect(toastKeys()).toContain("toast.quickOcrCopied")
+  })
+
+  it("does nothing when the region selection is cancelled", async () => {
+    setupAuthenticated()
+    vi.mocked(invoke).mockResolvedValue(null)
+
+    const { runQuickOcr } = useQuickOcr()
+    expect(await runQuickOcr("region")).toBeNull()
+
+    expect(invoke).toHaveBeenCalledTimes(1)
+    expect(toastKeys()).toEqual([])
+  })
+
+  it("lets the backend copy clipboard results", async () => {
+    setupAuthenticated()
+    vi.mocked(invoke).mockResolvedValue("  ")
+
+    const { runQuickOcr } = useQuickOcr()
+    await runQuickOcr("clipboard")
+
+    expect(invoke).toHaveBeenCalledWith("ocr_clipboard_image", {
+      accessToken: "token",
+      copyResult: true,
+    })
+    expect(invoke).not.toHaveBeenCalledWith("copy_text_to_clipboard", expect.anything())
+    expect(toastKeys()).toContain("toast.quickOcrEmpty")
+  })
+
+  it("reports failures and ignores presses while running", async () => {
+    setupAuthenticated()
+    let reject: (error: unknown) => void = () => {}
+    vi.mocked(invoke).mockImplementation(
+      () =>
+        new Promise((_, r) => {
+          reject = r
+        }),
+    )
+
+    const { runQuickOcr } = useQuickOcr()
+    const first = runQuickOcr("clipboard")
+    expect(await runQuickOcr("clipboard")).toBeNull()
+    await vi.waitFor(() => expect(invoke).toHaveBeenCalledTimes(1))
+    reject("No image on the clipboard")
+
+    expect(await first).toBeNull()
+    expect(toastKeys()).toContain("toast.quickOcrFailed")
+  })
+})
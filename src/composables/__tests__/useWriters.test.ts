@@ -3,6 +3,7 @@ import type { OutputFormat } from "@/stores/settings"
 
 // Mock Tauri APIs before importing useWriters
 vi.mock("@tauri-apps/plugin-fs", () => ({
+  readTextFile: vi.fn(),
   writeTextFile: vi.fn().mockResolvedValue(undefined),
 }))
 
@@ -10,11 +11,21 @@ vi.mock("@tauri-apps/api/core", () => ({
   invoke: vi.fn().mockResolvedValue(undefined),
 }))
 
-import { useWriters } from "../useWriters"
+import { useWriters, mergeMetadata, txtContent } from "../useWriters"
+import { invoke } from "@tauri-apps/api/core"
 
 describe("useWriters", () => {
-  const { isArabicText, compactText, writeTxt, writeJson, writeOutputs } =
-    useWriters()
+  const {
+    isArabicText,
+    compactText,
+    writeTxt,
+    writeJson,
+    readJson,
+    writeOutputs,
+    verifyOutputs,
+    applyCorrections,
+    exportToBaheth,
+  } = useWriters()
 
   describe("isArabicText", () => {
     it("returns true for Arabic-only text", () => {
@@ -128,6 +139,26 @@ describe("useWriters", () => {
         "Page 1\n\nPAGE_SEPARATOR\n\nPage 2",
       )
     })
+
+    it("writes separated footnotes below a rule line", async () => {
+      const { writeTextFile } = await import("@tauri-apps/plugin-fs")
+
+      await writeTxt(["Page 1", "Page 2"], "/output/test", {
+        pageSeparator: "\n---\n",
+        footnotes: [" 1. note ", null],
+      })
+
+      expect(writeTextFile).toHaveBeenCalledWith(
+        "/output/test.txt",
+        "Page 1\n\n__________\n1. note\n---\nPage 2",
+      )
+    })
+    it("builds the same content without writing it", async () => {
+      const { writeTextFile } = await import("@tauri-apps/plugin-fs")
+
+      expect(txtContent([" Page 1 ", "Page 2"])).toBe("Page 1\n\nPAGE_SEPARATOR\n\nPage 2")
+      expect(writeTextFile).not.toHaveBeenCalled()
+    })
   })
 
   describe("writeJson", () => {
@@ -166,6 +197,55 @@ describe("useWriters", () => {
       const parsed = JSON.parse(call[1])
       expect(parsed[0].content).toBe("trimmed")
     })
+
+    it("records detected page languages when provided", async () => {
+      const { writeTextFile } = await import("@tauri-apps/plugin-fs")
+
+      await writeJson(["نص", "text"], "/output/test", { languages: ["ar", null] })
+
+      const call = (writeTextFile as ReturnType<typeof vi.fn>).mock.calls[0]
+      const parsed = JSON.parse(call[1])
+      expect(parsed[0]).toEqual({ page: 1, content: "نص", language: "ar" })
+      expect(parsed[1]).toEqual({ page: 2, content: "text" })
+    })
+
+    it("records separated footnotes when provided", async () => {
+      const { writeTextFile } = await import("@tauri-apps/plugin-fs")
+
+      await writeJson(["body", "text"], "/output/test", { footnotes: ["1. note", ""] })
+
+      const call = (writeTextFile as ReturnType<typeof vi.fn>).mock.calls[0]
+      const parsed = JSON.parse(call[1])
+      expect(parsed[0]).toEqual({ page: 1, content: "body", footnotes: "1. note" })
+      expect(parsed[1]).toEqual({ page: 2, content: "text" })
+    })
+  })
+
+  describe("readJson", () => {
+    it("reads pages back in page order", async () => {
+      const { readTextFile } = await import("@tauri-apps/plugin-fs")
+      vi.mocked(readTextFile).mockResolvedValueOnce(
+        JSON.stringify([
+          { page: 2, content: "text" },
+          { page: 1, content: "body", language: "ar", footnotes: "1. note" },
+        ]),
+      )
+
+      expect(await readJson("/output/test.json")).toEqual({
+        texts: ["body", "text"],
+        languages: ["ar", null],
+        footnotes: ["1. note", null],
+      })
+    })
+
+    it("rejects files that are not page arrays", async () => {
+      const { readTextFile } = await import("@tauri-apps/plugin-fs")
+      vi.mocked(readTextFile).mockResolvedValueOnce(JSON.stringify({ page: 1, content: "x" }))
+      await expect(readJson("/output/other.json")).rejects.toThrow("Not a Tahweel JSON output")
+
+      vi.mocked(readTextFile).mockResolvedValueOnce(JSON.stringify([{ page: 1 }]))
+      await expect(readJson("/output/other.json")).rejects.toThrow("Not a Tahweel JSON output")
+    })
   })
 
   describe("writeOutputs", () => {
@@ -206,4 +286,93 @@ describe("useWriters", () => {
       expect(writeTextFile).not.toHaveBeenCalled()
     })
   })
+
+  describe("verifyOutputs", () => {
+    beforeEach(() => {
+      vi.clearAllMocks()
+    })
+
+    it("checks the written formats with the effective page separator", async () => {
+      await verifyOutputs("/output/test", ["txt", "docx"] as OutputFormat[], 3, {})
+
+      expect(invoke).toHaveBeenCalledWith("verify_outputs", {
+        outputPath: "/output/test",
+        formats: ["txt", "docx"],
+        pageCount: 3,
+        pageSeparator: "\n\nPAGE_SEPARATOR\n\n",
+      })
+    })
+
+    it("fails when an output is corrupt", async () => {
+      vi.mocked(invoke).mockRejectedValueOnce({ kind: "outputInvalid", message: "corrupt" })
+
+      await expect(verifyOutputs("/output/test", ["docx"] as OutputFormat[], 1)).rejects.toEqual(
+        expect.objectContaining({ kind: "outputInvalid" }),
+      )
+    })
+
+    it("skips the check without formats", async () => {
+      await verifyOutputs("/output/test", [], 1)
+
+      expect(invoke).not.toHaveBeenCalled()
+    })
+  })
+
+  describe("mergeMetadata", () => {
+    it("puts non-blank overrides over the source's fields", () => {
+      expect(
+        mergeMetadata(
+          { title: " الرسالة ", author: "Scanner", subject: "أصول الفقه" },
+          { author: "الشافعي", subject: "  " },
+        ),
+      ).toEqual({ title: "الرسالة", author: "الشافعي", subject: "أصول الفقه" })
+    })
+
+    it("drops blank fields", () => {
+      expect(mergeMetadata({ title: "" }, {})).toEqual({})
+      expect(mergeMetadata()).toEqual({})
+    })
+  })
+
+  describe("applyCorrections", () => {
+    it("sends the page edits for the job", async () => {
+      vi.mocked(invoke).mockResolvedValueOnce({
+        editedPages: [2],
+        paths: ["/out/book.txt"],
+      })
+
+      const result = await applyCorrections("job-1", [{ page: 2, text: "مصحح" }])
+
+      expect(invoke).toHaveBeenCalledWith("apply_corrections", {
+        jobId: "job-1",
+        pageEdits: [{ page: 2, text: "مصحح" }],
+      })
+      expect(result.editedPages).toEqual([2])
+    })
+  })
+
+  describe("exportToBaheth", () => {
+    it("sends the book metadata and page texts", async () => {
+      vi.mocked(invoke).mockResolvedValueOnce("/out/book.baheth.json")
+
+      const path = await exportToBaheth(
+        ["الصفحة الأولى", "الصفحة الثانية"],
+        "/out/book",
+        "/in/book.pdf",
+        { title: "كتاب", authors: ["مؤلف"] },
+        { footnotes: ["١. حاشية", null] },
+      )
+
+      expect(invoke).toHaveBeenCalledWith("write_baheth_export", {
+        outputPath: "/out/book",
+        sourcePath: "/in/book.pdf",
+        metadata: { title: "كتاب", authors: ["مؤلف"] },
+        pages: [
+          { text: "الصفحة الأولى", footnotes: "١. حاشية" },
+          { text: "الصفحة الثانية", footnotes: null },
+        ],
+      })
+      expect(path).toBe("/out/book.baheth.json")
+    })
+  })
 })
@@ -1,18 +1,65 @@
-import { open, message } from "@tauri-apps/plugin-dialog"
+import { open, message, ask } from "@tauri-apps/plugin-dialog"
 import { readDir } from "@tauri-apps/plugin-fs"
 import { invoke } from "@tauri-apps/api/core"
+import { watch } from "vue"
 import { useI18n } from "vue-i18n"
 import { useProcessingStore } from "@/stores/processing"
-import { useSettingsStore } from "@/stores/settings"
+import { useSettingsStore, type OutputFormat, type StageMode } from "@/stores/settings"
 import { useAuthStore } from "@/stores/auth"
 import { useToastStore } from "@/stores/toast"
-import { usePdfProcessor, cleanupTempDir } from "./usePdfProcessor"
-import { useGoogleDriveOcr } from "./useGoogleDriveOcr"
-import { useWriters } from "./useWriters"
+import { getErrorDetails, getErrorMessage, isTahweelError } from "@/utils/errors"
+import { logJobEvent } from "@/utils/jobEvents"
+import { RENDER_STALL_MS, reportStall, watchProgress } from "@/utils/watchdog"
+import { EtaModel, getThroughputEstimate, recordThroughput } from "@/utils/eta"
+import { appendLiveTxt, finishLiveTxt, startLiveTxt } from "@/utils/liveTxt"
+import { groupImageInputs } from "@/utils/imageDocuments"
+import { orderFiles } from "@/utils/naturalSort"
+import { discardSpool, spoolPages, writeSpooledOutputs } from "@/utils/spool"
+import { applyPostprocessing } from "@/utils/postprocessing"
+import {
+  assemblePartialResults,
+  beginJob,
+  discardJob,
+  endJob,
+  getPartialResults,
+  recordJobStop,
+  recordPartialPage,
+  takeInterruptedJobs,
+  type InterruptedJob,
+} from "@/utils/partialResults"
+import {
+  usePdfProcessor,
+  cleanupTempDir,
+  shardRanges,
+  type DegradedPage,
+  type DownscaledPage,
+  type ImageDocument,
+  type PageRange,
+  type SplitResult,
+} from "./usePdfProcessor"
+import { useAuth } from "./useAuth"
+import { useGoogleDriveOcr, isNearEmpty, type OcrProgress } from "./useGoogleDriveOcr"
+import {
+  useWriters,
+  withFootnotes,
+  mergeMetadata,
+  txtContent,
+  DEFAULT_PAGE_SEPARATOR,
+  type DocumentMetadata,
+  type RegionText,
+} from "./useWriters"
 import { dirname, basename, join } from "@tauri-apps/api/path"
 
 const SUPPORTED_EXTENSIONS = [".pdf", ".jpg", ".jpeg", ".png"]
 
+/** Inputs each stage mode starts from: PDFs to render, images to OCR, JSON to assemble */
+const STAGE_INPUTS: Record<StageMode, string[]> = {
+  full: SUPPORTED_EXTENSIONS,
+  renderOnly: [".pdf"],
+  ocrOnly: [".jpg", ".jpeg", ".png"],
+  assembleOnly: [".json"],
+}
+
 /**
  * Get file extension from filename, handling edge cases
  */
@@ -24,12 +71,133 @@ function getFileExtension(filename: string): string | null {
   return filename.slice(lastDot).toLowerCase()
 }
 
+/**
+ * Android's file picker returns content:// URIs instead of paths
+ */
+function isContentUri(path: string): boolean {
+  return path.startsWith("content://")
+}
+
 /**
  * Check if a file has a supported extension
  */
-function isSupportedFile(filename: string): boolean {
+function isSupportedFile(filename: string, extensions = SUPPORTED_EXTENSIONS): boolean {
   const ext = getFileExtension(filename)
-  return ext !== null && SUPPORTED_EXTENSIONS.includes(ext)
+  return ext !== null && extensions.includes(ext)
+}
+
+interface DownloadedFile {
+  path: string
+  outputDirectory: string
+}
+
+interface ScanResult {
+  name: string
+  dir: string
+  pages: string[]
+  outputDirectory: string
+}
+
+export interface ScanSettings {
+  dpi?: number
+  colorMode?: "color" | "gray" | "lineart"
+  feeder?: boolean
+  maxPages?: number
+}
+
+/** Output file uploaded to S3-compatible storage (see upload_outputs_to_storage) */
+interface StoredObject {
+  path: string
+  key: string
+  url: string
+}
+
+interface SecondPassText {
+  page: number
+  text: string
+}
+
+/** Page that repeats an earlier page (see detect_duplicate_pages) */
+interface DuplicatePage {
+  page: number
+  duplicateOf: number
+  exact: boolean
+  skipped: boolean
+}
+
+/**
+ * Expand per-page values of the OCRed pages to the whole document: pages whose
+ * OCR was skipped as duplicates take the value of their first occurrence
+ */
+function withDuplicates<T>(values: T[], pageCount: number, duplicates: DuplicatePage[]): T[] {
+  const skipped = new Map(
+    duplicates.filter((d) => d.skipped).map((d) => [d.page - 1, d.duplicateOf - 1]),
+  )
+  const result: T[] = []
+  let next = 0
+  for (let index = 0; index < pageCount; index++) {
+    const original = skipped.get(index)
+    result.push(original === undefined ? values[next++] : result[original])
+  }
+  return result
+}
+
+/** OCR result of a document's pages, or of one shard of them */
+interface RecognizedPages {
+  texts: string[]
+  /** Footnote text per page; empty when no page had footnotes */
+  footnotes: Array<string | null>
+  /** Labelled region text per page; empty when region-of-interest OCR is off */
+  regions: RegionText[][]
+  /** Page numbers are the document's */
+  duplicatePages: DuplicatePage[]
+}
+
+/** Stitch the recognized shards of a document back together, in page order */
+function mergeRecognized(parts: RecognizedPages[]): RecognizedPages {
+  if (parts.length === 1) return parts[0]
+  const hasFootnotes = parts.some((part) => part.footnotes.length > 0)
+  const hasRegions = parts.some((part) => part.regions.length > 0)
+  return {
+    texts: parts.flatMap((part) => part.texts),
+    footnotes: hasFootnotes
+      ? parts.flatMap((part) =>
+          part.footnotes.length > 0 ? part.footnotes : part.texts.map(() => null),
+        )
+      : [],
+    regions: hasRegions
+      ? parts.flatMap((part) =>
+          part.regions.length > 0 ? part.regions : part.texts.map(() => []),
+        )
+      : [],
+    duplicatePages: parts.flatMap((part) => part.duplicatePages),
+  }
+}
+
+/** Page text of region-of-interest OCR: one `label: text` line per region */
+function regionsText(regions: RegionText[]): string {
+  return regions.map((region) => `${region.label}: ${region.text.trim()}`).join("\n")
+}
+
+interface BackendCheck {
+  name: string
+  status: "ok" | "warning" | "failed"
+  message: string
+  /** What the user can do about a warning or failure */
+  hint: string | null
+}
+
+/** Result of the `check_backend` command */
+interface BackendHealth {
+  backend: string
+  usable: boolean
+  checks: BackendCheck[]
+}
+
+/** Directories an archival-mode run writes to (see prepare_archival_output) */
+interface ArchivalPaths {
+  outputDirectory: string
+  workspace: string
 }
 
 export function useFileProcessor() {
@@ -38,9 +206,15 @@ export function useFileProcessor() {
   const settingsStore = useSettingsStore()
   const authStore = useAuthStore()
   const toastStore = useToastStore()
-  const { splitPdf } = usePdfProcessor()
+  const { getPageCount, getPdfInfo, splitPdf, cancelSplit, prepareImageDocument } =
+    usePdfProcessor()
   const { extractText } = useGoogleDriveOcr()
-  const { writeOutputs } = useWriters()
+  const { ensureValidToken } = useAuth()
+  const { writeOutputs, verifyOutputs, readJson } = useWriters()
+  // Jobs with pages in the workspace spool, removed when the job ends
+  const spooledJobs = new Set<string>()
+  // Job paths whose page images are loose images to combine (`combineImages`)
+  const imageDocuments = new Set<string>()
 
   async function selectFile() {
     const selected = await open({
@@ -48,14 +222,16 @@ export function useFileProcessor() {
       filters: [
         {
           name: "Supported Files",
-          extensions: ["pdf", "jpg", "jpeg", "png"],
+          extensions: STAGE_INPUTS[settingsStore.stageMode].map((ext) => ext.slice(1)),
         },
       ],
     })
 
     if (selected) {
-      const inputDir = await dirname(selected)
-      const outputDir = settingsStore.outputDirectory ?? inputDir
+      // Content URIs have no folder to write next to; the backend picks an app folder
+      const outputDir = isContentUri(selected)
+        ? await invoke<string>("resolve_output_dir", { sourcePath: selected })
+        : (settingsStore.outputDirectory ?? (await dirname(selected)))
       await processFiles([selected], outputDir)
     }
   }
@@ -80,8 +256,137 @@ export function useFileProcessor() {
     }
   }
 
-  async function collectFiles(folderPath: string): Promise<string[]> {
+  /**
+   * Download a remote file (e.g. an archive.org link) into the workspace and process
+   * it like a picked file. A failed download is resumed when the same URL is retried.
+   */
+  async function processUrl(url: string) {
+    if (!authStore.isAuthenticated) {
+      await message(t("messages.authRequired"), {
+        title: t("messages.errorTitle"),
+        kind: "error",
+      })
+      return
+    }
+
+    let downloaded: DownloadedFile
+    try {
+      downloaded = await invoke<DownloadedFile>("process_url", {
+        url,
+        options: { outputDirectory: settingsStore.outputDirectory },
+      })
+    } catch (error) {
+      console.error(`Failed to download ${url}:`, getErrorDetails(error))
+      await message(getErrorMessage(error), {
+        title: t("messages.errorTitle"),
+        kind: "error",
+      })
+      return
+    }
+
+    try {
+      await processFiles([downloaded.path], downloaded.outputDirectory)
+    } finally {
+      try {
+        await cleanupTempDir(await dirname(downloaded.path))
+      } catch {
+        // Ignore cleanup errors
+      }
+    }
+  }
+
+  /**
+   * Download a file picked from the user's Google Drive (see listDriveFiles) into
+   * the workspace and process it like a picked file. Google Docs are processed
+   * as their PDF export.
+   */
+  async function processDriveFile(fileId: string) {
+    const accessToken = await ensureValidToken()
+    if (!authStore.isAuthenticated || !accessToken) {
+      await message(t("messages.authRequired"), {
+        title: t("messages.errorTitle"),
+        kind: "error",
+      })
+      return
+    }
+
+    let downloaded: DownloadedFile
+    try {
+      downloaded = await invoke<DownloadedFile>("process_drive_file", {
+        fileId,
+        accessToken,
+        options: { outputDirectory: settingsStore.outputDirectory },
+      })
+    } catch (error) {
+      console.error(`Failed to download Drive file ${fileId}:`, getErrorDetails(error))
+      await message(getErrorMessage(error), {
+        title: t("messages.errorTitle"),
+        kind: "error",
+      })
+      return
+    }
+
+    try {
+      await processFiles([downloaded.path], downloaded.outputDirectory)
+    } finally {
+      try {
+        await cleanupTempDir(await dirname(downloaded.path))
+      } catch {
+        // Ignore cleanup errors
+      }
+    }
+  }
+
+  /**
+   * Scan pages from a connected scanner (see list_scanners) and OCR them as one
+   * document named after the scan. The scanned images are removed afterwards.
+   */
+  async function scanAndProcess(device: string, settings?: ScanSettings) {
+    if (!authStore.isAuthenticated) {
+      await message(t("messages.authRequired"), {
+        title: t("messages.errorTitle"),
+        kind: "error",
+      })
+      return
+    }
+
+    let scan: ScanResult
+    try {
+      scan = await invoke<ScanResult>("scan_pages", {
+        device,
+        settings: settings ?? null,
+        outputDirectory: settingsStore.outputDirectory,
+      })
+    } catch (error) {
+      console.error(`Failed to scan from ${device}:`, getErrorDetails(error))
+      await message(getErrorMessage(error), {
+        title: t("messages.errorTitle"),
+        kind: "error",
+      })
+      return
+    }
+
+    // The job is identified by a path named after the scan; its pages are the images
+    const jobPath = await join(scan.dir, scan.name)
+    try {
+      await processFiles([jobPath], scan.outputDirectory, { [jobPath]: scan.pages })
+    } finally {
+      try {
+        await cleanupTempDir(scan.dir)
+      } catch {
+        // Ignore cleanup errors
+      }
+    }
+  }
+
+  /**
+   * Supported files under a folder, recursively, in natural order (`page2` before
+   * `page10`, Arabic-Indic digits read as numbers). `manualOrder` overrides it:
+   * those paths come first, in the given order.
+   */
+  async function collectFiles(folderPath: string, manualOrder: string[] = []): Promise<string[]> {
     const files: string[] = []
+    const extensions = STAGE_INPUTS[settingsStore.stageMode]
 
     async function scanDir(dir: string) {
       const entries = await readDir(dir)
@@ -89,18 +394,105 @@ export function useFileProcessor() {
         const fullPath = await join(dir, entry.name)
         if (entry.isDirectory) {
           await scanDir(fullPath)
-        } else if (entry.isFile && isSupportedFile(entry.name)) {
+        } else if (entry.isFile && isSupportedFile(entry.name, extensions)) {
           files.push(fullPath)
         }
       }
     }
 
     await scanDir(folderPath)
-    return files.sort()
+    return orderFiles(files, manualOrder)
   }
 
-  async function processFiles(filePaths: string[], outputDir: string) {
-    if (!authStore.isAuthenticated) {
+  /**
+   * Stop before starting a batch when Google cannot be reached at all.
+   * A failed check never blocks processing; the OCR retries still apply.
+   */
+  async function preflightConnectivity(): Promise<boolean> {
+    let status: string | undefined
+    try {
+      const report = await invoke<{ status: string } | undefined>("check_connectivity")
+      status = report?.status
+    } catch (error) {
+      console.warn("Connectivity check failed:", error)
+      return true
+    }
+
+    if (status === "offline" || status === "proxyUnreachable") {
+      await message(t(`messages.${status}`), {
+        title: t("messages.errorTitle"),
+        kind: "error",
+      })
+      return false
+    }
+    return true
+  }
+
+  /**
+   * Stop before starting a batch when the OCR backend is known to be unusable
+   * (token revoked, Drive access not granted, API refusing requests). Like the
+   * connectivity check, a check that cannot run never blocks processing.
+   */
+  async function preflightBackend(): Promise<boolean> {
+    let health: BackendHealth | undefined
+    try {
+      health = await invoke<BackendHealth | undefined>("check_backend", {
+        backendId: "googleDrive",
+        accessToken: authStore.accessToken,
+      })
+    } catch (error) {
+      console.warn("Backend check failed:", error)
+      return true
+    }
+    if (!health || health.usable) return true
+
+    const problems = health.checks
+      .filter((check) => check.status === "failed")
+      .map((check) => (check.hint ? `${check.message}. ${check.hint}.` : `${check.message}.`))
+    await message([t("messages.backendUnusable"), ...problems].join("\n\n"), {
+      title: t("messages.errorTitle"),
+      kind: "error",
+    })
+    return false
+  }
+
+  /**
+   * In archival mode every write goes to app-owned folders: check upfront that they
+   * are writable and return the output folder to use, so read-only sources and
+   * network shares fail before any work starts. Null once the error is shown.
+   */
+  async function preflightArchival(filePaths: string[]): Promise<string | null> {
+    try {
+      const paths = await invoke<ArchivalPaths>("prepare_archival_output", {
+        sourcePaths: filePaths,
+      })
+      return paths.outputDirectory
+    } catch (error) {
+      console.error("Archival mode check failed:", getErrorDetails(error))
+      await message(t("messages.archivalUnavailable", { error: getErrorMessage(error) }), {
+        title: t("messages.errorTitle"),
+        kind: "error",
+      })
+      return null
+    }
+  }
+
+  /**
+   * Process inputs one job per path, `parallelDocuments` of them at a time.
+   * `pageImages` gives the page images of inputs that are already split (scans)
+   * instead of a file to read; `metadata` per-input overrides of the title,
+   * author and subject read from the source. With `combineImages`, loose images
+   * of one folder are processed as one document (see groupImageInputs).
+   */
+  async function processFiles(
+    filePaths: string[],
+    outputDir: string,
+    pageImages: Record<string, string[]> = {},
+    metadata: Record<string, DocumentMetadata> = {},
+  ) {
+    // Rendering and assembling run offline; only OCR needs Google Drive
+    const needsDrive = settingsStore.stageMode === "full" || settingsStore.stageMode === "ocrOnly"
+    if (needsDrive && !authStore.isAuthenticated) {
       await message(t("messages.authRequired"), {
         title: t("messages.errorTitle"),
         kind: "error",
@@ -108,27 +500,61 @@ export function useFileProcessor() {
       return
     }
 
+    // Archival mode ignores the folder picked by the caller; nothing is written beside the source
+    if (settingsStore.archivalMode) {
+      const archivalDir = await preflightArchival(filePaths)
+      if (!archivalDir) return
+      outputDir = archivalDir
+    }
+
+    if (needsDrive && !(await preflightConnectivity())) return
+    if (needsDrive && !(await preflightBackend())) return
+
+    // Loose images of one folder are OCRed as one document; the images are its pages
+    if (needsDrive && settingsStore.combineImages) {
+      const grouped = groupImageInputs(filePaths)
+      filePaths = grouped.paths
+      pageImages = { ...pageImages, ...grouped.documents }
+      for (const jobPath of Object.keys(grouped.documents)) imageDocuments.add(jobPath)
+    }
+
     processingStore.startProcessing(filePaths, outputDir)
 
-    for (const filePath of filePaths) {
-      // Check for cancellation before processing each file
-      if (processingStore.isCancelled) {
-        break
-      }
+    // Each worker takes the next file from the queue; `parallelDocuments` workers
+    // run at once (the Drive cap is shared between them, see extractText)
+    let nextIndex = 0
+    let stopped = false
+    async function worker() {
+      while (!stopped) {
+        // Hold here while the queue is paused (from the tray)
+        await processingStore.waitWhilePaused()
 
-      try {
-        await processFile(filePath, outputDir)
-        processingStore.completeFile()
-      } catch (error) {
-        const errorMessage = String(error)
-        if (errorMessage.includes("cancelled")) {
-          break // Stop processing on cancellation
+        // Check for cancellation before processing each file
+        if (processingStore.isCancelled || stopped || nextIndex >= filePaths.length) {
+          break
+        }
+        const filePath = filePaths[nextIndex++]
+
+        try {
+          await processFile(filePath, outputDir, pageImages[filePath], metadata[filePath])
+          processingStore.completeFile(filePath)
+        } catch (error) {
+          const errorMessage = getErrorMessage(error)
+          if (errorMessage.includes("cancelled")) {
+            stopped = true // Stop processing on cancellation
+            break
+          }
+          console.error(`Failed to process ${filePath}:`, getErrorDetails(error))
+          processingStore.addError(filePath, errorMessage)
+          processingStore.completeFile(filePath)
         }
-        processingStore.addError(filePath, errorMessage)
-        processingStore.completeFile()
       }
     }
 
+    const workers = Math.min(settingsStore.parallelDocuments, filePaths.length)
+    await Promise.all(Array.from({ length: workers }, () => worker()))
+    for (const filePath of filePaths) imageDocuments.delete(filePath)
+
     processingStore.finishProcessing()
 
     // Auto-open output folder after conversion (only if not cancelled and has output)
@@ -146,46 +572,401 @@ export function useFileProcessor() {
     }
   }
 
-  async function processFile(filePath: string, baseOutputDir: string) {
-    const fileName = await basename(filePath)
-    const ext = getFileExtension(fileName) || ""
-    const nameWithoutExt = fileName.replace(/\.[^.]+$/, "")
+  /**
+   * Process a picked input. Content URIs are first copied into the app's workspace
+   * (the pipeline reads files by path) and the copy is removed afterwards.
+   */
+  async function processFile(
+    filePath: string,
+    baseOutputDir: string,
+    pageImages?: string[],
+    metadata?: DocumentMetadata,
+  ) {
+    if (pageImages || !isContentUri(filePath)) {
+      await processLocalFile(filePath, baseOutputDir, pageImages, metadata)
+      return
+    }
 
-    // Check for cancellation
-    if (processingStore.isCancelled) {
-      throw new Error("Processing cancelled")
+    const staged = await invoke<{ path: string }>("stage_input_file", { path: filePath })
+    try {
+      await processLocalFile(staged.path, baseOutputDir, undefined, metadata)
+    } finally {
+      try {
+        await cleanupTempDir(await dirname(staged.path))
+      } catch {
+        // Ignore cleanup errors
+      }
+    }
+  }
+
+  /**
+   * Auto-rotation (`autoRotatePages` setting): turn sideways and upside-down
+   * rendered pages upright. Best effort: a page that cannot be checked is OCRed
+   * as it is.
+   */
+  async function orientPages(imagePaths: string[]): Promise<string[]> {
+    const oriented: string[] = []
+    for (const imagePath of imagePaths) {
+      try {
+        const fix = await invoke<{ rotation: number; path: string }>(
+          "correct_page_orientation",
+          { imagePath },
+        )
+        oriented.push(fix?.path ?? imagePath)
+      } catch {
+        oriented.push(imagePath)
+      }
+    }
+    return oriented
+  }
+
+  /**
+   * Title, author and subject of a source PDF. Best effort: a PDF whose
+   * metadata cannot be read gets none.
+   */
+  async function readPdfMetadata(pdfPath: string): Promise<DocumentMetadata> {
+    try {
+      return (await getPdfInfo(pdfPath)) ?? {}
+    } catch {
+      return {}
+    }
+  }
+
+  /**
+   * Blank the `maskRegions` (signatures, stamps) on rendered pages before upload.
+   * A page that cannot be masked fails the job instead of being uploaded unmasked.
+   */
+  async function maskPages(imagePaths: string[], pages: number[]): Promise<string[]> {
+    const regions = settingsStore.maskRegions
+    if (regions.length === 0) return imagePaths
+    const masked: string[] = []
+    for (let index = 0; index < imagePaths.length; index++) {
+      masked.push(
+        await invoke<string>("mask_page_image", {
+          imagePath: imagePaths[index],
+          page: pages[index],
+          regions,
+        }),
+      )
+    }
+    return masked
+  }
+
+  /**
+   * Region-of-interest OCR (`roiRegions` setting): crop each page's regions and
+   * recognize only the crops, in region order
+   */
+  async function recognizeRegions(
+    jobId: string,
+    imagePaths: string[],
+    pages: number[],
+    onProgress: (progress: OcrProgress) => void,
+  ): Promise<RegionText[][]> {
+    const crops: Array<Array<{ label: string; path: string }>> = []
+    for (let index = 0; index < imagePaths.length; index++) {
+      crops.push(
+        await invoke<Array<{ label: string; path: string }>>("crop_page_regions", {
+          imagePath: imagePaths[index],
+          page: pages[index],
+          regions: settingsStore.roiRegions,
+        }),
+      )
+    }
+    const texts = await extractText(
+      crops.flat().map((crop) => crop.path),
+      settingsStore.ocrConcurrency,
+      onProgress,
+      jobId,
+    )
+    let next = 0
+    return crops.map((pageCrops) =>
+      pageCrops.map((crop) => ({ label: crop.label, text: texts[next++] })),
+    )
+  }
+
+  /**
+   * Footnote separation (`separateFootnotes` setting): split each rendered page
+   * at its footnote rule so the body and footnotes are recognized separately.
+   * Pages without a rule, or when the option is off, keep their image as the body.
+   */
+  async function splitFootnotes(
+    imagePaths: string[],
+  ): Promise<{ bodies: string[]; notes: Array<string | null> }> {
+    const bodies: string[] = []
+    const notes: Array<string | null> = []
+    for (const imagePath of imagePaths) {
+      try {
+        const split = await invoke<{ body: string; footnotes: string | null }>(
+          "split_page_footnotes",
+          { imagePath },
+        )
+        bodies.push(split?.body ?? imagePath)
+        notes.push(split?.footnotes ?? null)
+      } catch {
+        bodies.push(imagePath)
+        notes.push(null)
+      }
+    }
+    return { bodies, notes }
+  }
+
+  /**
+   * Pages that repeat an earlier page of the document. Best effort: returns
+   * none if detection fails.
+   */
+  async function detectDuplicates(imagePaths: string[]): Promise<DuplicatePage[]> {
+    if (imagePaths.length < 2) {
+      return []
+    }
+    try {
+      return (await invoke<DuplicatePage[]>("detect_duplicate_pages", { imagePaths })) ?? []
+    } catch {
+      return []
+    }
+  }
+
+  /**
+   * Detected language of each page from its first-pass text (null when unsure).
+   * Best effort: returns no languages if detection fails.
+   */
+  async function detectLanguages(texts: string[]): Promise<Array<string | null>> {
+    try {
+      const pages = await invoke<Array<{ language: string | null }>>(
+        "detect_page_languages",
+        { pages: texts },
+      )
+      return pages?.map((page) => page.language) ?? []
+    } catch {
+      return []
+    }
+  }
+
+  /**
+   * Dual-pass quality mode: OCR the pages the backend considers ambiguous again
+   * at a second DPI, so the job report can flag pages where the passes disagree.
+   * The second pass sends each page's detected language as the OCR hint.
+   * Returns no pages when the mode is off or the second pass fails.
+   */
+  async function runSecondPass(
+    filePath: string,
+    texts: string[],
+    tempDir: string,
+    languages: Array<string | null>,
+  ): Promise<SecondPassText[]> {
+    try {
+      const plan = await invoke<{ dpi: number; pages: number[] }>("plan_dual_pass", {
+        pages: texts,
+        dpi: settingsStore.dpi,
+      })
+      if (!plan?.pages.length) {
+        return []
+      }
+
+      const imagePaths: string[] = []
+      for (const page of plan.pages) {
+        imagePaths.push(
+          await invoke<string>("extract_pdf_page", {
+            pdfPath: filePath,
+            pageNumber: page,
+            dpi: plan.dpi,
+            outputPath: await join(tempDir, `second_pass_${page}.png`),
+          }),
+        )
+      }
+      const secondTexts = await extractText(
+        await maskPages(await orientPages(imagePaths), plan.pages),
+        settingsStore.ocrConcurrency,
+        undefined,
+        undefined,
+        plan.pages.map((page) => languages[page - 1] ?? null),
+      )
+      return plan.pages.map((page, index) => ({ page, text: secondTexts[index] }))
+    } catch {
+      return []
+    }
+  }
+
+  /**
+   * Assemble-only: write a JSON output's page text in the other configured
+   * formats. The JSON is the input, so it is not rewritten.
+   */
+  async function assemblePageText(filePath: string, fileName: string, outputBasePath: string) {
+    const formats = settingsStore.formats.filter((format) => format !== "json")
+    if (formats.length === 0) {
+      throw new Error(t("messages.assembleNeedsFormat"))
     }
 
-    // Update progress
     processingStore.updateFileProgress({
       filePath,
       fileName,
-      stage: "preparing",
+      stage: "writing",
       currentPage: 0,
       totalPages: 0,
-      percentage: 0,
+      percentage: 50,
     })
+    const { texts, languages, footnotes } = await readJson(filePath)
+    const writerOptions = {
+      pageSeparator: settingsStore.pageSeparator,
+      languages,
+      footnotes,
+    }
+    await writeOutputs(texts, outputBasePath, formats, writerOptions)
+    await verifyOutputs(outputBasePath, formats, texts.length, writerOptions)
+    processingStore.addOutputFiles(formats.map((format) => `${outputBasePath}.${format}`))
 
-    let imagePaths: string[]
-    let tempDir: string | null = null
+    processingStore.updateFileProgress({
+      filePath,
+      fileName,
+      stage: "done",
+      currentPage: texts.length,
+      totalPages: texts.length,
+      percentage: 100,
+    })
+  }
+
+  /** Remove a render workspace, ignoring errors (the job is failing anyway) */
+  async function discardTempDir(tempDir: string, jobId: string) {
+    try {
+      await cleanupTempDir(tempDir, undefined, jobId)
+    } catch {
+      // Ignore cleanup errors
+    }
+  }
 
-    if (ext === ".pdf") {
-      // Check for cancellation before PDF split
-      if (processingStore.isCancelled) {
-        throw new Error("Processing cancelled")
+  /**
+   * Remove a render workspace whose pages are recognized; with `keepPageImages`
+   * the pages are moved next to the outputs first (the directory stays if that fails)
+   */
+  async function releaseTempDir(tempDir: string, outputBasePath: string, jobId: string) {
+    const keepImages = settingsStore.keepPageImages
+      ? { outputPath: outputBasePath, folder: settingsStore.pageImagesFolder }
+      : undefined
+    try {
+      await cleanupTempDir(tempDir, keepImages, jobId)
+    } catch (error) {
+      if (keepImages) {
+        console.error("Failed to keep page images:", error)
+        toastStore.warning("toast.keepPageImagesFailed")
       }
+    }
+  }
 
-      // Split PDF into images
-      processingStore.updateFileProgress({
-        filePath,
-        fileName,
+  /**
+   * Render the pages of `range` to images in a new workspace. `pageCount` is the
+   * whole document's, so progress runs across shards.
+   */
+  async function renderPages(
+    jobId: string,
+    filePath: string,
+    fileName: string,
+    range: PageRange,
+    pageCount: number,
+  ): Promise<SplitResult> {
+    // Check for cancellation before PDF split
+    if (processingStore.isCancelled) {
+      throw new Error("Processing cancelled")
+    }
+
+    const offset = range.firstPage - 1
+    const sharded = range.pageCount < pageCount
+    await logJobEvent(
+      jobId,
+      "stage",
+      sharded ? `render: pages ${range.firstPage}-${offset + range.pageCount}` : "render",
+    )
+    processingStore.updateFileProgress({
+      filePath,
+      fileName,
+      stage: "splitting",
+      currentPage: offset,
+      totalPages: sharded ? pageCount : 0,
+      percentage: sharded ? Math.round((offset / pageCount) * 100) : 0,
+    })
+
+    // PDFium cannot be interrupted mid-page, so a stalled render is only reported
+    let renderedPages = 0
+    const watchdog = watchProgress(RENDER_STALL_MS, (idleMs) => {
+      void reportStall({
+        jobId,
         stage: "splitting",
-        currentPage: 0,
-        totalPages: 0,
-        percentage: 0,
+        page: offset + renderedPages + 1,
+        idleMs,
+        action: "reported",
+      })
+    })
+    // A stop reaches the render too, so pages held back on low battery don't wait on
+    const stopCancel = watch(
+      () => processingStore.isCancelled,
+      (cancelled) => {
+        if (cancelled) {
+          void cancelSplit(jobId).catch(() => {})
+        }
+      },
+    )
+    const result = await splitPdf(
+      filePath,
+      settingsStore.dpi,
+      (progress) => {
+        watchdog.touch()
+        renderedPages = progress.currentPage
+        processingStore.updateFileProgress({
+          filePath,
+          fileName,
+          stage: "splitting",
+          currentPage: offset + progress.currentPage,
+          totalPages: sharded ? pageCount : progress.totalPages,
+          percentage: sharded
+            ? Math.round(((offset + progress.currentPage) / pageCount) * 100)
+            : progress.percentage,
+        })
+      },
+      jobId,
+      settingsStore.autoDpi,
+      range,
+    )
+      .catch((error: unknown) => {
+        throw processingStore.isCancelled ? new Error("Processing cancelled") : error
+      })
+      .finally(() => {
+        watchdog.stop()
+        stopCancel()
       })
 
-      const result = await splitPdf(filePath, settingsStore.dpi, (progress) => {
+    if (processingStore.isCancelled) {
+      await discardTempDir(result.tempDir, jobId)
+      throw new Error("Processing cancelled")
+    }
+    return result
+  }
+
+  /**
+   * Turn the loose images of a combined document into its pages (see
+   * prepare_image_document), straightened (`deskewImages`) and cropped
+   * (`cropImageBorders`) on request. Images that cannot be read are left out.
+   */
+  async function prepareImages(
+    jobId: string,
+    filePath: string,
+    fileName: string,
+    images: string[],
+  ): Promise<ImageDocument> {
+    if (processingStore.isCancelled) {
+      throw new Error("Processing cancelled")
+    }
+
+    await logJobEvent(jobId, "stage", `images: ${images.length}`)
+    processingStore.updateFileProgress({
+      filePath,
+      fileName,
+      stage: "splitting",
+      currentPage: 0,
+      totalPages: images.length,
+      percentage: 0,
+    })
+    const document = await prepareImageDocument(
+      images,
+      { deskew: settingsStore.deskewImages, crop: settingsStore.cropImageBorders },
+      (progress) => {
         processingStore.updateFileProgress({
           filePath,
           fileName,
@@ -194,79 +975,487 @@ export function useFileProcessor() {
           totalPages: progress.totalPages,
           percentage: progress.percentage,
         })
-      })
+      },
+      jobId,
+    )
+    for (const skipped of document.skipped) {
+      await logJobEvent(jobId, "skip", `${skipped.path}: ${skipped.error}`)
+    }
 
-      imagePaths = result.imagePaths
-      tempDir = result.tempDir
-    } else {
-      // Single image
-      imagePaths = [filePath]
+    if (processingStore.isCancelled) {
+      await discardTempDir(document.tempDir, jobId)
+      throw new Error("Processing cancelled")
     }
+    return document
+  }
 
+  /**
+   * OCR page images. Pages repeating an earlier page of the set reuse its text
+   * (`skipDuplicatePages`); `rendered` pages are turned upright, masked, and
+   * split at their footnote rule or cropped to the regions of interest (the
+   * parts are written next to the page image). With `shard`, page numbers and
+   * progress are those of the whole document. With `liveOutput`, each page's
+   * text is appended to that output's live TXT as it arrives.
+   */
+  async function recognizePages(
+    jobId: string,
+    filePath: string,
+    fileName: string,
+    imagePaths: string[],
+    rendered: boolean,
+    shard?: { range: PageRange; pageCount: number },
+    liveOutput?: string,
+  ): Promise<RecognizedPages> {
     // Check for cancellation before OCR
     if (processingStore.isCancelled) {
-      // Clean up temp directory if we created one
-      if (tempDir) {
-        try {
-          await cleanupTempDir(tempDir)
-        } catch {
-          // Ignore cleanup errors
-        }
-      }
       throw new Error("Processing cancelled")
     }
+    const offset = shard ? shard.range.firstPage - 1 : 0
+
+    // Repeated pages (`skipDuplicatePages`) reuse their first occurrence's text
+    const duplicatePages = await detectDuplicates(imagePaths)
+    const skippedPages = new Set(duplicatePages.filter((d) => d.skipped).map((d) => d.page - 1))
+    const ocrPages = imagePaths.flatMap((_, index) => (skippedPages.has(index) ? [] : [index]))
+    const ocrPaths = ocrPages.map((index) => imagePaths[index])
+    for (const duplicate of duplicatePages.filter((d) => d.skipped)) {
+      await logJobEvent(
+        jobId,
+        "skip",
+        `duplicate of page ${duplicate.duplicateOf + offset}`,
+        duplicate.page + offset,
+      )
+    }
 
     // OCR all images
+    await logJobEvent(jobId, "stage", "ocr")
+    const ocrProgress = (completed: number, total: number, percentage: number) => {
+      if (!shard) {
+        return { currentPage: completed, totalPages: total, percentage }
+      }
+      const done = offset + Math.round((percentage / 100) * imagePaths.length)
+      return {
+        currentPage: done,
+        totalPages: shard.pageCount,
+        percentage: Math.round((done / shard.pageCount) * 100),
+      }
+    }
+    // Remaining time: the pace of earlier jobs at this DPI, refined by this one's
+    const throughputDpi = rendered && !settingsStore.autoDpi ? settingsStore.dpi : 0
+    const history = await getThroughputEstimate("googleDrive", throughputDpi)
+    const eta = new EtaModel(ocrPaths.length, history?.msPerPage ?? null)
+    const ocrStarted = Date.now()
     processingStore.updateFileProgress({
       filePath,
       fileName,
       stage: "ocr",
-      currentPage: 0,
-      totalPages: imagePaths.length,
-      percentage: 0,
+      ...ocrProgress(0, ocrPaths.length, 0),
+      etaMs: eta.remainingMs() ?? undefined,
     })
 
-    let texts: string[]
-    try {
-      texts = await extractText(
-        imagePaths,
+    const documentDuplicates = duplicatePages.map((duplicate) => ({
+      ...duplicate,
+      page: duplicate.page + offset,
+      duplicateOf: duplicate.duplicateOf + offset,
+    }))
+    const onProgress = (progress: OcrProgress) => {
+      eta.update(progress.completed, Date.now() - ocrStarted)
+      processingStore.updateFileProgress({
+        filePath,
+        fileName,
+        stage: "ocr",
+        ...ocrProgress(progress.completed, progress.total, progress.percentage),
+        concurrency: progress.concurrency,
+        etaMs: eta.remainingMs() ?? undefined,
+      })
+    }
+    const pageNumbers = ocrPages.map((index) => index + offset + 1)
+
+    // Forms: only the regions of interest are recognized, and the page text is
+    // their labelled text
+    // Rendered pages are turned upright before masking, since regions are
+    // placed on upright pages
+    const uploadPaths = rendered
+      ? await maskPages(await orientPages(ocrPaths), pageNumbers)
+      : ocrPaths
+    if (rendered && settingsStore.roiRegions.length > 0) {
+      const regions = await recognizeRegions(jobId, uploadPaths, pageNumbers, onProgress)
+      return {
+        texts: withDuplicates(regions.map(regionsText), imagePaths.length, duplicatePages),
+        footnotes: [],
+        regions: withDuplicates(regions, imagePaths.length, duplicatePages),
+        duplicatePages: documentDuplicates,
+      }
+    }
+
+    const { bodies, notes } = rendered
+      ? await splitFootnotes(uploadPaths)
+      : { bodies: ocrPaths, notes: [] as Array<string | null> }
+
+    const texts = await extractText(
+      bodies,
+      settingsStore.ocrConcurrency,
+      onProgress,
+      jobId,
+      undefined,
+      // Kept as they arrive so a cancelled or failed job can still be assembled
+      (index, text) => {
+        const page = ocrPages[index] + offset + 1
+        void recordPartialPage(jobId, page, text)
+        if (liveOutput) void appendLiveTxt(liveOutput, page, text)
+      },
+    )
+    if (!processingStore.isCancelled) {
+      await recordThroughput("googleDrive", throughputDpi, bodies.length, Date.now() - ocrStarted)
+    }
+
+    let footnotes: Array<string | null> = []
+    const notePages = notes.flatMap((path, index) => (path ? [index] : []))
+    if (notePages.length > 0) {
+      const noteTexts = await extractText(
+        notePages.map((index) => notes[index] as string),
         settingsStore.ocrConcurrency,
-        (progress) => {
-          processingStore.updateFileProgress({
-            filePath,
-            fileName,
-            stage: "ocr",
-            currentPage: progress.completed,
-            totalPages: progress.total,
-            percentage: progress.percentage,
-          })
+        undefined,
+        jobId,
+      )
+      footnotes = texts.map(() => null)
+      notePages.forEach((page, index) => {
+        footnotes[page] = noteTexts[index]
+      })
+      footnotes = withDuplicates(footnotes, imagePaths.length, duplicatePages)
+    }
+    return {
+      texts: withDuplicates(texts, imagePaths.length, duplicatePages),
+      footnotes,
+      regions: [],
+      duplicatePages: documentDuplicates,
+    }
+  }
+
+  /**
+   * Record why a job stopped and, when it had already recognized pages, offer
+   * them for assembly (see savePartialResults). Best effort.
+   */
+  async function keepPartialResults(
+    jobId: string,
+    filePath: string,
+    baseOutputDir: string,
+    cancelled: boolean,
+    error: unknown,
+  ) {
+    try {
+      const nameWithoutExt = (await basename(filePath)).replace(/\.[^.]+$/, "")
+      await recordJobStop(jobId, {
+        reason: cancelled ? "cancelled" : "failed",
+        errorKind: isTahweelError(error) ? error.kind : undefined,
+        message: getErrorMessage(error),
+        sourcePath: filePath,
+        outputPath: await join(baseOutputDir, nameWithoutExt),
+      })
+      const partial = await getPartialResults(jobId)
+      if (partial.pages.length > 0) {
+        processingStore.addPartialJob({
+          jobId,
+          file: filePath,
+          pages: partial.pages.length,
+        })
+      }
+    } catch (partialError) {
+      console.warn("Failed to keep partial results:", partialError)
+    }
+  }
+
+  /**
+   * Offer to resume or clean up the jobs an earlier session left running when
+   * it was killed or crashed (see take_interrupted_jobs). Resuming converts the
+   * file again into the folder it was going to; either way the pages the
+   * interrupted run recognized are dropped. A job resumed during a batch is
+   * not restarted; its recognized pages stay and can be saved as partial outputs.
+   */
+  async function recoverInterruptedJobs() {
+    let jobs: InterruptedJob[]
+    try {
+      jobs = (await takeInterruptedJobs()) ?? []
+    } catch {
+      return
+    }
+    for (const job of jobs) {
+      const resume = await ask(
+        t("messages.jobInterrupted", { file: job.sourcePath, count: job.pages }),
+        {
+          title: t("messages.jobInterruptedTitle"),
+          kind: "warning",
+          okLabel: t("buttons.resumeJob"),
+          cancelLabel: t("buttons.cleanUpJob"),
         },
       )
+      if (resume && processingStore.isProcessing) {
+        if (job.pages > 0) {
+          processingStore.addPartialJob({ jobId: job.jobId, file: job.sourcePath, pages: job.pages })
+        }
+        continue
+      }
+      try {
+        await discardJob(job.jobId)
+      } catch (error) {
+        console.warn("Failed to clean up interrupted job:", error)
+      }
+      if (resume) {
+        await processFiles([job.sourcePath], await dirname(job.outputPath))
+      }
+    }
+  }
+
+  /**
+   * Write the outputs of a cancelled or failed job from the pages it completed,
+   * as `<name>-partial.<ext>` in the configured formats
+   */
+  async function savePartialResults(jobId: string) {
+    try {
+      const result = await assemblePartialResults(
+        jobId,
+        settingsStore.formats,
+        settingsStore.pageSeparator || "\n\nPAGE_SEPARATOR\n\n",
+      )
+      processingStore.addOutputFiles(result.paths)
+      processingStore.removePartialJob(jobId)
+      toastStore.success("toast.partialSaved")
     } catch (error) {
-      // Clean up temp directory on error
-      if (tempDir) {
+      console.error("Failed to assemble partial results:", getErrorDetails(error))
+      toastStore.error("toast.partialSaveFailed", { error: getErrorMessage(error) })
+    }
+  }
+
+  async function processLocalFile(
+    filePath: string,
+    baseOutputDir: string,
+    pageImages?: string[],
+    metadataOverrides?: DocumentMetadata,
+  ) {
+    // Tags this file's render/upload/export timings (see get_performance_summary)
+    // and its event log (see get_job_events)
+    const jobId = crypto.randomUUID()
+    await logJobEvent(jobId, "stage", `started: ${filePath}`)
+    // Reported as interrupted at the next start if the app dies mid-job
+    const nameWithoutExt = (await basename(filePath)).replace(/\.[^.]+$/, "")
+    await beginJob(jobId, filePath, await join(baseOutputDir, nameWithoutExt))
+    try {
+      await convertLocalFile(jobId, filePath, baseOutputDir, pageImages, metadataOverrides)
+      await logJobEvent(jobId, "stage", "done")
+    } catch (error) {
+      const errorMessage = getErrorMessage(error)
+      const cancelled = errorMessage.includes("cancelled")
+      if (cancelled) {
+        await logJobEvent(jobId, "stage", "cancelled")
+      } else {
+        await logJobEvent(jobId, "error", errorMessage)
+      }
+      await keepPartialResults(jobId, filePath, baseOutputDir, cancelled, error)
+      throw error
+    } finally {
+      if (spooledJobs.delete(jobId)) await discardSpool(jobId)
+      await endJob(jobId)
+    }
+  }
+
+  async function convertLocalFile(
+    jobId: string,
+    filePath: string,
+    baseOutputDir: string,
+    pageImages?: string[],
+    metadataOverrides?: DocumentMetadata,
+  ) {
+    const fileName = await basename(filePath)
+    const ext = getFileExtension(fileName) || ""
+    const nameWithoutExt = fileName.replace(/\.[^.]+$/, "")
+
+    // Check for cancellation
+    if (processingStore.isCancelled) {
+      throw new Error("Processing cancelled")
+    }
+
+    // Single-stage modes only take that stage's input; scans are page images
+    const mode = settingsStore.stageMode
+    const accepted = pageImages ? mode === "ocrOnly" : STAGE_INPUTS[mode].includes(ext)
+    if (mode !== "full" && !accepted) {
+      throw new Error(t("messages.stageModeInput", { mode: t(`settings.stageModes.${mode}`) }))
+    }
+
+    // OCR-only stops at the page text: the JSON output, which assemble-only reads back
+    const formats: OutputFormat[] = mode === "ocrOnly" ? ["json"] : settingsStore.formats
+
+    // Update progress
+    processingStore.updateFileProgress({
+      filePath,
+      fileName,
+      stage: "preparing",
+      currentPage: 0,
+      totalPages: 0,
+      percentage: 0,
+    })
+
+    if (mode === "assembleOnly") {
+      await logJobEvent(jobId, "stage", "assemble")
+      await assemblePageText(filePath, fileName, await join(baseOutputDir, nameWithoutExt))
+      return
+    }
+
+    const outputBasePath = await join(baseOutputDir, nameWithoutExt)
+    // Pages are readable in `<output>.partial.txt` while the job runs (`liveTxt`)
+    const liveOutput = settingsStore.liveTxt && mode !== "renderOnly" ? outputBasePath : undefined
+    if (liveOutput) await startLiveTxt(liveOutput)
+    let pageCount: number
+    let tempDir: string | null = null
+    const degradedPages: DegradedPage[] = []
+    const downscaledPages: DownscaledPage[] = []
+    let recognized: RecognizedPages
+    let spool = false
+
+    if (pageImages && imageDocuments.has(filePath)) {
+      // Loose images combined into one document: their pages are prepared like
+      // rendered PDF pages and released with them at the end
+      const document = await prepareImages(jobId, filePath, fileName, pageImages)
+      downscaledPages.push(...document.downscaledPages)
+      if (document.skipped.length > 0) {
+        toastStore.warning("toast.imagesSkipped", { count: document.skipped.length })
+      }
+      if (downscaledPages.length > 0) {
+        toastStore.warning("toast.pagesDownscaled", { count: downscaledPages.length })
+      }
+      pageCount = document.imagePaths.length
+      try {
+        recognized = await recognizePages(
+          jobId,
+          filePath,
+          fileName,
+          document.imagePaths,
+          true,
+          undefined,
+          liveOutput,
+        )
+      } catch (error) {
+        await discardTempDir(document.tempDir, jobId)
+        throw error
+      }
+      tempDir = document.tempDir
+    } else if (pageImages || ext !== ".pdf") {
+      // Already split (scanned pages), or a single image
+      const imagePaths = pageImages ?? [filePath]
+      pageCount = imagePaths.length
+      recognized = await recognizePages(
+        jobId,
+        filePath,
+        fileName,
+        imagePaths,
+        false,
+        undefined,
+        liveOutput,
+      )
+    } else {
+      // Very large PDFs are rendered and recognized in consecutive shards of
+      // `shardPages` pages, so only one shard's page images exist at a time;
+      // the shards' text is stitched back together before writing
+      pageCount = await getPageCount(filePath)
+      const shards = shardRanges(pageCount, settingsStore.shardPages)
+      const parts: RecognizedPages[] = []
+      // Sharded documents keep their page text in a workspace spool, and the
+      // outputs are streamed from it. Form regions only go into the in-memory writers
+      spool = shards.length > 1 && mode !== "renderOnly" && settingsStore.roiRegions.length === 0
+      for (const range of shards) {
+        const result = await renderPages(jobId, filePath, fileName, range, pageCount)
+        degradedPages.push(...(result.degradedPages ?? []))
+        downscaledPages.push(...(result.downscaledPages ?? []))
+
+        // Render-only: the rendered pages are the output, moved next to where the
+        // text would go (the workspace stays if that fails)
+        if (mode === "renderOnly") {
+          await cleanupTempDir(
+            result.tempDir,
+            { outputPath: outputBasePath, folder: settingsStore.pageImagesFolder },
+            jobId,
+          )
+          continue
+        }
+
+        const shard = shards.length > 1 ? { range, pageCount } : undefined
         try {
-          await cleanupTempDir(tempDir)
-        } catch {
-          // Ignore cleanup errors
+          const part = await recognizePages(
+            jobId,
+            filePath,
+            fileName,
+            result.imagePaths,
+            true,
+            shard,
+            liveOutput,
+          )
+          parts.push(part)
+          if (spool) {
+            spooledJobs.add(jobId)
+            await spoolPages(jobId, range.firstPage, part.texts, part.footnotes)
+          }
+        } catch (error) {
+          await discardTempDir(result.tempDir, jobId)
+          throw error
+        }
+        // A single render workspace stays for the second pass and is released at the end
+        if (shard) {
+          await releaseTempDir(result.tempDir, outputBasePath, jobId)
+        } else {
+          tempDir = result.tempDir
         }
       }
-      throw error
+      // Drive-safe mode (`driveSafeImages`) shrank pages too large for Drive
+      if (downscaledPages.length > 0) {
+        toastStore.warning("toast.pagesDownscaled", { count: downscaledPages.length })
+      }
+
+      if (mode === "renderOnly") {
+        processingStore.updateFileProgress({
+          filePath,
+          fileName,
+          stage: "done",
+          currentPage: pageCount,
+          totalPages: pageCount,
+          percentage: 100,
+        })
+        return
+      }
+      recognized = mergeRecognized(parts)
     }
+    const { footnotes, regions, duplicatePages } = recognized
 
     // Check for cancellation before writing
     if (processingStore.isCancelled) {
       if (tempDir) {
-        try {
-          await cleanupTempDir(tempDir)
-        } catch {
-          // Ignore cleanup errors
-        }
+        await discardTempDir(tempDir, jobId)
       }
       throw new Error("Processing cancelled")
     }
 
+    // The configured post-processing pipeline runs before anything is written;
+    // spooled outputs run its page steps in the backend as they are streamed
+    const texts =
+      !spool && settingsStore.postprocessing.length > 0
+        ? (await applyPostprocessing(recognized.texts, settingsStore.postprocessing)).pages
+        : recognized.texts
+
+    // Whole-page text (body and footnotes) for detection, the second pass and the job record
+    const pageTexts = texts.map((text, index) => withFootnotes(text, footnotes[index]))
+    const languages = await detectLanguages(pageTexts)
+    // Region text is not comparable across DPIs, so forms skip the second pass
+    const secondPass =
+      ext === ".pdf" && tempDir && regions.length === 0
+        ? await runSecondPass(filePath, pageTexts, tempDir, languages)
+        : []
+
+    // The source's title, author and subject, with this job's overrides on top
+    const metadata = mergeMetadata(
+      ext === ".pdf" && !pageImages ? await readPdfMetadata(filePath) : {},
+      metadataOverrides,
+    )
+    const hasMetadata = Object.keys(metadata).length > 0
+
     // Write outputs
+    await logJobEvent(jobId, "stage", "writing")
     processingStore.updateFileProgress({
       filePath,
       fileName,
@@ -276,26 +1465,146 @@ export function useFileProcessor() {
       percentage: 90,
     })
 
-    const outputBasePath = await join(baseOutputDir, nameWithoutExt)
-    await writeOutputs(texts, outputBasePath, settingsStore.formats, {
+    const writerOptions = {
       pageSeparator: settingsStore.pageSeparator,
-    })
+      languages,
+      footnotes,
+      regions,
+      metadata,
+    }
+    if (spool) {
+      // Streamed from disk one page at a time; the live TXT is then just removed
+      await writeSpooledOutputs(jobId, outputBasePath, formats, pageCount, {
+        pageSeparator: settingsStore.pageSeparator || DEFAULT_PAGE_SEPARATOR,
+        languages,
+        metadata,
+      })
+      if (liveOutput) await finishLiveTxt(liveOutput, null)
+    } else {
+      // With a live TXT, the final TXT takes its place in one rename
+      const writtenFormats = liveOutput ? formats.filter((format) => format !== "txt") : formats
+      await writeOutputs(texts, outputBasePath, writtenFormats, writerOptions)
+      if (liveOutput) {
+        await finishLiveTxt(
+          liveOutput,
+          formats.includes("txt") ? txtContent(texts, writerOptions) : null,
+        )
+      }
+    }
+    // A corrupt output fails the job rather than being reported as done
+    await verifyOutputs(outputBasePath, formats, texts.length, writerOptions)
+    processingStore.addOutputFiles(
+      formats.map((format) => `${outputBasePath}.${format}`),
+    )
 
-    // Cleanup temp directory
-    if (tempDir) {
+    // Keep the page text so proofread corrections can rewrite the outputs (best effort)
+    try {
+      await invoke("record_job", {
+        jobId,
+        sourcePath: filePath,
+        outputPath: outputBasePath,
+        formats,
+        pageSeparator: settingsStore.pageSeparator || "\n\nPAGE_SEPARATOR\n\n",
+        pages: pageTexts,
+        // Corrections rewrite the DOCX with the same core properties
+        docx: { metadata },
+      })
+    } catch {
+      // Ignore job database errors
+    }
+
+    // Remember the input for the recent files list (best effort); scans have no
+    // input file to reopen
+    if (!pageImages) {
       try {
-        await cleanupTempDir(tempDir)
+        await invoke("record_recent_file", {
+          path: filePath,
+          options: { dpi: settingsStore.dpi, formats },
+          outputs: formats.map((format) => `${outputBasePath}.${format}`),
+        })
       } catch {
-        // Ignore cleanup errors
+        // Ignore recent files errors
       }
     }
 
+    // Push the outputs to S3-compatible storage when configured (best effort)
+    let storedObjects: StoredObject[] = []
+    try {
+      storedObjects =
+        (await invoke<StoredObject[]>("upload_outputs_to_storage", {
+          paths: formats.map((format) => `${outputBasePath}.${format}`),
+        })) ?? []
+    } catch (error) {
+      console.error("Failed to upload outputs to storage:", error)
+      toastStore.warning("toast.storageUploadFailed")
+    }
+
+    // Copy the outputs to the WebDAV/Nextcloud folder when configured (best effort)
+    try {
+      await invoke("upload_outputs_to_webdav", {
+        paths: formats.map((format) => `${outputBasePath}.${format}`),
+      })
+    } catch (error) {
+      console.error("Failed to upload outputs to WebDAV:", error)
+      toastStore.warning("toast.webdavUploadFailed")
+    }
+
+    // Flag pages where the two OCR passes disagree, that rendered only on a
+    // retry, or that stayed empty, log duplicate and downscaled pages, and record
+    // the stored object URLs and the source's metadata (best effort)
+    if (
+      secondPass.length > 0 ||
+      storedObjects.length > 0 ||
+      degradedPages.length > 0 ||
+      downscaledPages.length > 0 ||
+      duplicatePages.length > 0 ||
+      hasMetadata ||
+      texts.some(isNearEmpty)
+    ) {
+      try {
+        await invoke("generate_job_report", {
+          jobId,
+          outputPath: outputBasePath,
+          pages: pageTexts,
+          secondPass,
+          storedObjects,
+          degradedPages,
+          downscaledPages,
+          duplicatePages,
+          metadata,
+        })
+      } catch {
+        // Ignore report errors
+      }
+    }
+
+    // Email the outputs when "email me the results" is on (best effort);
+    // files too large to attach are sent as their storage links
+    try {
+      await invoke("email_job_results", {
+        jobName: fileName,
+        files: formats.map((format) => {
+          const path = `${outputBasePath}.${format}`
+          return { path, url: storedObjects.find((object) => object.path === path)?.url ?? null }
+        }),
+      })
+    } catch (error) {
+      console.error("Failed to email results:", error)
+      toastStore.warning("toast.emailResultsFailed")
+    }
+
+    // Cleanup temp directory; with `keepPageImages` the rendered pages are moved
+    // next to the outputs first
+    if (tempDir) {
+      await releaseTempDir(tempDir, outputBasePath, jobId)
+    }
+
     processingStore.updateFileProgress({
       filePath,
       fileName,
       stage: "done",
-      currentPage: imagePaths.length,
-      totalPages: imagePaths.length,
+      currentPage: pageCount,
+      totalPages: pageCount,
       percentage: 100,
     })
   }
@@ -307,8 +1616,13 @@ export function useFileProcessor() {
   return {
     selectFile,
     selectFolder,
+    processUrl,
+    processDriveFile,
+    scanAndProcess,
     processFiles,
     collectFiles,
     cancelProcessing,
+    savePartialResults,
+    recoverInterruptedJobs,
   }
 }
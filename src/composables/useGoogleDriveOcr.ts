@@ -1,6 +1,11 @@
 import { invoke } from "@tauri-apps/api/core"
 import { useProcessingStore } from "@/stores/processing"
+import { useSettingsStore } from "@/stores/settings"
 import { useToastStore } from "@/stores/toast"
+import { AdaptiveConcurrency, isThrottleError } from "@/utils/adaptiveConcurrency"
+import { getErrorMessage } from "@/utils/errors"
+import { logJobEvent } from "@/utils/jobEvents"
+import { OCR_PAGE_STALL_MS, StallError, reportStall, withStallTimeout } from "@/utils/watchdog"
 import { useAuth } from "./useAuth"
 import pLimit from "p-limit"
 
@@ -8,6 +13,8 @@ export interface OcrProgress {
   completed: number
   total: number
   percentage: number
+  /** Drive pages allowed in flight after the adaptive limit's last change */
+  concurrency: number
 }
 
 export interface OcrError {
@@ -25,23 +32,79 @@ export interface ExtractedText {
   fileId: string
 }
 
+/** A Drive file that can be imported as an input (see list_drive_files) */
+export interface DriveEntry {
+  id: string
+  name: string
+  mimeType: string
+  /** Bytes; Google Docs have no size */
+  size: number | null
+  modifiedTime: string | null
+}
+
+/** One page of Drive files, most recently modified first */
+export interface DriveFileList {
+  files: DriveEntry[]
+  /** Pass back to listDriveFiles for the next page; missing on the last one */
+  nextPageToken: string | null
+}
+
 interface UploadResult {
   fileId: string
+  /** Attempts Drive throttled before a backend retry went through */
+  throttled?: number
 }
 
 interface ExportResult {
   text: string
+  throttled?: number
+}
+
+// Exports with fewer non-whitespace characters than this count as a failed
+// conversion and the page is uploaded once more
+const NEAR_EMPTY_MAX_CHARS = 3
+
+/**
+ * Whether Drive's conversion came back (nearly) empty
+ */
+export function isNearEmpty(text: string): boolean {
+  return text.replace(/\s/g, "").length < NEAR_EMPTY_MAX_CHARS
+}
+
+// Shared by every extractText call, so documents processed in parallel together
+// keep at most `driveConcurrency` pages in flight against Drive. Within that
+// ceiling the limit adapts: it backs off when Drive throttles and grows back
+// while pages go through.
+const driveLimit = pLimit(20)
+const driveConcurrency = new AdaptiveConcurrency(20)
+
+/**
+ * Count the attempts Drive throttled before the backend's retries got a request
+ * through: the request succeeded, but Drive still asked for fewer requests
+ */
+function recordThrottled(throttled: number | undefined) {
+  for (let attempt = 0; attempt < (throttled ?? 0); attempt++) {
+    driveConcurrency.recordThrottle()
+  }
+  driveLimit.concurrency = driveConcurrency.current
 }
 
 export function useGoogleDriveOcr() {
   const processingStore = useProcessingStore()
+  const settingsStore = useSettingsStore()
   const toastStore = useToastStore()
   const { ensureValidToken } = useAuth()
 
   /**
-   * Upload a file to Google Drive as a Google Document (triggers OCR)
+   * Upload a file to Google Drive as a Google Document (triggers OCR).
+   * With a job ID, the backend records the upload time for the job's performance summary.
+   * `ocrLanguage` (ISO 639-1) hints the page's language; Drive detects it otherwise.
    */
-  async function uploadFile(filePath: string): Promise<string> {
+  async function uploadFile(
+    filePath: string,
+    jobId?: string,
+    ocrLanguage?: string,
+  ): Promise<string> {
     const accessToken = await ensureValidToken()
     if (!accessToken) {
       throw new Error("Not authenticated")
@@ -50,7 +113,10 @@ export function useGoogleDriveOcr() {
     const result = await invoke<UploadResult>("upload_to_google_drive", {
       filePath,
       accessToken,
+      jobId,
+      ocrLanguage,
     })
+    recordThrottled(result.throttled)
 
     return result.fileId
   }
@@ -58,7 +124,11 @@ export function useGoogleDriveOcr() {
   /**
    * Export a Google Document as plain text
    */
-  async function exportAsText(fileId: string): Promise<string> {
+  async function exportAsText(
+    fileId: string,
+    jobId?: string,
+    page?: number,
+  ): Promise<string> {
     const accessToken = await ensureValidToken()
     if (!accessToken) {
       throw new Error("Not authenticated")
@@ -67,7 +137,10 @@ export function useGoogleDriveOcr() {
     const result = await invoke<ExportResult>("export_google_doc_as_text", {
       fileId,
       accessToken,
+      jobId,
+      page,
     })
+    recordThrottled(result.throttled)
 
     // Clean up the text (remove Google's OCR artifacts)
     // Google Drive adds BOM + underscores as page separators/artifacts
@@ -122,73 +195,133 @@ export function useGoogleDriveOcr() {
   /**
    * Extract text from multiple images with controlled concurrency.
    * Supports cancellation and returns partial results with errors.
+   * `languages` gives an optional OCR language hint per image.
+   * `concurrency` bounds this call; the `driveConcurrency` setting bounds all calls together,
+   * with the shared limit lowered while Drive answers with 429 or rate-limit 403 errors,
+   * including 429s the backend retried past.
+   * A page whose export comes back (nearly) empty is uploaded once more; pages
+   * still empty after that are reported in a warning toast.
+   * `onPageText` gets each page's text as soon as it is recognized.
+   * A page that stalls (see `OCR_PAGE_STALL_MS`) is restarted once and reported as `job-stalled`.
    */
   async function extractText(
     imagePaths: string[],
     concurrency: number,
     onProgress?: (progress: OcrProgress) => void,
+    jobId?: string,
+    languages?: Array<string | null>,
+    onPageText?: (index: number, text: string) => void,
   ): Promise<string[]> {
     const limit = pLimit(concurrency)
+    driveConcurrency.setMax(settingsStore.driveConcurrency)
+    driveLimit.concurrency = driveConcurrency.current
     const results: Array<string | null> = Array.from(
       { length: imagePaths.length },
       () => null,
     )
     const uploadedFileIds: string[] = []
     const errors: OcrError[] = []
+    const emptyPages: number[] = []
     let completed = 0
 
-    const tasks = imagePaths.map((path, index) =>
-      limit(async () => {
-        // Check for cancellation before starting
-        if (processingStore.isCancelled) {
-          throw new Error("Processing cancelled")
-        }
+    // Upload one page, export its text, and delete the Doc
+    async function ocrPage(path: string, index: number): Promise<string> {
+      const fileId = await uploadFile(path, jobId, languages?.[index] ?? undefined)
+      uploadedFileIds.push(fileId)
 
-        let fileId: string | null = null
+      // Check for cancellation after upload
+      if (processingStore.isCancelled) {
+        throw new Error("Processing cancelled")
+      }
 
-        try {
-          // Upload and track the file ID
-          fileId = await uploadFile(path)
-          uploadedFileIds.push(fileId)
+      // Export text
+      const text = await exportAsText(fileId, jobId, index + 1)
+
+      // Delete the file from Drive
+      try {
+        await deleteFile(fileId)
+        // Remove from tracking since it's deleted
+        const idx = uploadedFileIds.indexOf(fileId)
+        if (idx > -1) uploadedFileIds.splice(idx, 1)
+      } catch {
+        // Ignore delete errors, file will be orphaned but that's ok
+      }
+      return text
+    }
+
+    // A page stuck for OCR_PAGE_STALL_MS is abandoned and started once more;
+    // stalling again fails it like any other page error
+    async function ocrPageWatched(path: string, index: number): Promise<string> {
+      try {
+        return await withStallTimeout(ocrPage(path, index), OCR_PAGE_STALL_MS)
+      } catch (error) {
+        if (!(error instanceof StallError)) throw error
+        await reportStall({
+          jobId,
+          stage: "ocr",
+          page: index + 1,
+          idleMs: error.idleMs,
+          action: "restarted",
+        })
+        return await withStallTimeout(ocrPage(path, index), OCR_PAGE_STALL_MS)
+      }
+    }
 
-          // Check for cancellation after upload
+    const tasks = imagePaths.map((path, index) =>
+      limit(() =>
+        driveLimit(async () => {
+          // Pages already uploading finish; new ones wait while paused
+          await processingStore.waitWhilePaused()
+
+          // Check for cancellation before starting
           if (processingStore.isCancelled) {
             throw new Error("Processing cancelled")
           }
 
-          // Export text
-          const text = await exportAsText(fileId)
-          results[index] = text
-
-          // Delete the file from Drive
           try {
-            await deleteFile(fileId)
-            // Remove from tracking since it's deleted
-            const idx = uploadedFileIds.indexOf(fileId)
-            if (idx > -1) uploadedFileIds.splice(idx, 1)
-          } catch {
-            // Ignore delete errors, file will be orphaned but that's ok
+            let text = await ocrPageWatched(path, index)
+
+            // Drive sometimes finishes the upload but converts it to an empty Doc;
+            // a second upload usually converts properly
+            if (isNearEmpty(text)) {
+              await logJobEvent(jobId, "retry", "empty export, uploading again", index + 1)
+              const retried = await ocrPageWatched(path, index)
+              if (retried.length > text.length) text = retried
+              if (isNearEmpty(text)) {
+                emptyPages.push(index + 1)
+                await logJobEvent(jobId, "error", "no text after retry", index + 1)
+              }
+            }
+            results[index] = text
+            onPageText?.(index, text)
+            driveConcurrency.recordSuccess()
+          } catch (error) {
+            if (isThrottleError(error)) {
+              driveConcurrency.recordThrottle()
+            }
+            const errorMessage = getErrorMessage(error)
+            if (!errorMessage.includes("cancelled")) {
+              errors.push({ index, error: errorMessage })
+              // Set empty string for failed pages to maintain order
+              results[index] = ""
+            } else {
+              throw error // Re-throw cancellation
+            }
           }
-        } catch (error) {
-          const errorMessage = String(error)
-          if (!errorMessage.includes("cancelled")) {
-            errors.push({ index, error: errorMessage })
-            // Set empty string for failed pages to maintain order
-            results[index] = ""
-          } else {
-            throw error // Re-throw cancellation
+
+          driveLimit.concurrency = driveConcurrency.current
+
+          completed++
+          if (onProgress) {
+            onProgress({
+              completed,
+              total: imagePaths.length,
+              percentage: Math.round((completed / imagePaths.length) * 100),
+              concurrency: driveConcurrency.current,
+            })
           }
-        }
-
-        completed++
-        if (onProgress) {
-          onProgress({
-            completed,
-            total: imagePaths.length,
-            percentage: Math.round((completed / imagePaths.length) * 100),
-          })
-        }
-      }),
+        }),
+      ),
     )
 
     try {
@@ -206,12 +339,33 @@ export function useGoogleDriveOcr() {
       console.warn(`OCR completed with ${errors.length} errors:`, errors)
       toastStore.warning("toast.ocrPartialErrors", { count: errors.length })
     }
+    if (emptyPages.length > 0 && !processingStore.isCancelled) {
+      console.warn("OCR returned no text for pages:", emptyPages)
+      toastStore.warning("toast.ocrEmptyPages", { count: emptyPages.length })
+    }
 
     // Return results, using empty string for any null values
     return results.map((r) => r ?? "")
   }
 
+  /**
+   * List the PDFs, images and Google Docs in the user's Drive whose names
+   * contain `name`. Drive only lists files the app may access.
+   */
+  async function listDriveFiles(name?: string, pageToken?: string): Promise<DriveFileList> {
+    const accessToken = await ensureValidToken()
+    if (!accessToken) {
+      throw new Error("Not authenticated")
+    }
+    return invoke<DriveFileList>("list_drive_files", {
+      accessToken,
+      name: name ?? null,
+      pageToken: pageToken ?? null,
+    })
+  }
+
   return {
+    listDriveFiles,
     uploadFile,
     exportAsText,
     deleteFile,
@@ -1,7 +1,11 @@
 import { invoke } from "@tauri-apps/api/core"
 import { listen, type UnlistenFn } from "@tauri-apps/api/event"
+import type { DocumentMetadata } from "./useWriters"
+import { subscribeJobEvents } from "@/utils/jobChannel"
 
 export interface SplitProgress {
+  /** Job being split; missing for a split without a job */
+  jobId?: string
   currentPage: number
   totalPages: number
   percentage: number
@@ -10,13 +14,122 @@ export interface SplitProgress {
 export interface SplitResult {
   imagePaths: string[]
   tempDir: string
+  /** Render resolution of each page (varies per page with auto DPI) */
+  pageDpis?: number[]
+  /** Pages that only rendered on a retry, usually at a lower DPI */
+  degradedPages?: DegradedPage[]
+  /** Pages scaled down to fit Drive's image limits (`driveSafeImages` setting) */
+  downscaledPages?: DownscaledPage[]
+}
+
+export interface DegradedPage {
+  page: number
+  dpi: number
+  error: string
+}
+
+export interface DownscaledPage {
+  page: number
+  originalWidth: number
+  originalHeight: number
+  width: number
+  height: number
+  bytes: number
+}
+
+/** Pages of a PDF rendered together; `firstPage` is 1-based */
+export interface PageRange {
+  firstPage: number
+  pageCount: number
+}
+
+/**
+ * Split a document into consecutive shards of at most `shardPages` pages.
+ * `shardPages` 0 (sharding off) keeps the whole document in one range.
+ */
+export function shardRanges(pageCount: number, shardPages: number): PageRange[] {
+  const size = shardPages > 0 ? shardPages : pageCount
+  const ranges: PageRange[] = []
+  for (let firstPage = 1; firstPage <= pageCount; firstPage += size) {
+    ranges.push({ firstPage, pageCount: Math.min(size, pageCount - firstPage + 1) })
+  }
+  return ranges
+}
+
+/** Where cleanup moves the rendered pages instead of deleting them */
+export interface KeepImages {
+  /** The job's output base path (`<dir>/<name>`) */
+  outputPath: string
+  /** Subfolder of the output directory; pages go to `<folder>/<name>/` */
+  folder: string
 }
 
 /**
- * Cleanup a temporary directory
+ * Cleanup a temporary directory, first moving its rendered pages out with `keepImages`.
+ * The directory goes to the workspace trash; with `jobId` it can be brought back
+ * with undoCleanup for a while.
  */
-export async function cleanupTempDir(path: string): Promise<void> {
-  await invoke("cleanup_temp_dir", { path })
+export async function cleanupTempDir(
+  path: string,
+  keepImages?: KeepImages,
+  jobId?: string,
+): Promise<void> {
+  await invoke("cleanup_temp_dir", { path, keepImages, jobId })
+}
+
+/** Restore the directories cleaned up for a job in the last minutes; returns their paths */
+export function undoCleanup(jobId: string): Promise<string[]> {
+  return invoke<string[]>("undo_cleanup", { jobId })
+}
+
+/** Page count and descriptive metadata of a PDF (blank fields are left out) */
+export interface PdfInfo extends DocumentMetadata {
+  pageCount: number
+}
+
+/** An input image left out of an image document because it could not be read */
+export interface SkippedImage {
+  path: string
+  error: string
+}
+
+/** Pages made from loose images by `prepareImageDocument`, numbered in input order */
+export interface ImageDocument {
+  imagePaths: string[]
+  tempDir: string
+  /** Input image of each page */
+  sourcePaths: string[]
+  skipped: SkippedImage[]
+  downscaledPages: DownscaledPage[]
+}
+
+/** Optional clean-up of each image of an image document */
+export interface ImageDocumentOptions {
+  deskew: boolean
+  crop: boolean
+}
+
+/**
+ * Listen for `split-progress` events: a job's own channel with `jobId`,
+ * otherwise the global events that carry no job
+ */
+async function listenSplitProgress(
+  onProgress?: (progress: SplitProgress) => void,
+  jobId?: string,
+): Promise<UnlistenFn | (() => Promise<void>) | null> {
+  if (onProgress && jobId) {
+    return subscribeJobEvents(jobId, (event) => {
+      if (event.event === "split-progress") onProgress(event.payload as SplitProgress)
+    })
+  }
+  if (onProgress) {
+    return listen<SplitProgress>("split-progress", (event) => {
+      // Progress of other jobs' splits
+      if (event.payload.jobId) return
+      onProgress(event.payload)
+    })
+  }
+  return null
 }
 
 export function usePdfProcessor() {
@@ -28,37 +141,79 @@ export function usePdfProcessor() {
   }
 
   /**
-   * Split a PDF into individual page images
+   * Get the page count and the title, author and subject of a PDF file
+   */
+  async function getPdfInfo(pdfPath: string): Promise<PdfInfo> {
+    return await invoke<PdfInfo>("get_pdf_info", { pdfPath })
+  }
+
+  /**
+   * Split a PDF into individual page images. With `autoDpi`, each page is
+   * rendered at a resolution picked from its size and scan resolution and
+   * `dpi` is ignored. With `range`, only those pages are rendered (one shard of
+   * a large document); progress then counts the range's pages. With `jobId`,
+   * progress comes from the job's own channel, so documents split side by side
+   * don't mix their progress.
    */
   async function splitPdf(
     pdfPath: string,
     dpi: number,
     onProgress?: (progress: SplitProgress) => void,
+    jobId?: string,
+    autoDpi?: boolean,
+    range?: PageRange,
   ): Promise<SplitResult> {
     // Get total pages first
-    const totalPages = await getPageCount(pdfPath)
+    const totalPages = range ? range.pageCount : await getPageCount(pdfPath)
 
     // Set up event listener for progress updates
-    let unlisten: UnlistenFn | null = null
-    if (onProgress) {
-      unlisten = await listen<SplitProgress>("split-progress", (event) => {
-        onProgress(event.payload)
-      })
-    }
+    const unlisten = await listenSplitProgress(onProgress, jobId)
 
     try {
       // Create temp directory and start splitting
       const result = await invoke<SplitResult>("split_pdf", {
         pdfPath,
         dpi,
+        autoDpi,
         totalPages,
+        firstPage: range?.firstPage,
+        jobId,
       })
 
       return result
     } finally {
       // Clean up the event listener
       if (unlisten) {
-        unlisten()
+        await unlisten()
+      }
+    }
+  }
+
+  /**
+   * Stop the split running under `jobId`: pages not yet rendered are skipped
+   * and `splitPdf` rejects
+   */
+  async function cancelSplit(jobId: string): Promise<void> {
+    await invoke("cancel_split", { jobId })
+  }
+
+  /**
+   * Turn loose images, in reading order, into the page images of one document
+   * (upright, optionally straightened and cropped). Unreadable images are
+   * skipped and listed. Progress is reported like `splitPdf`'s.
+   */
+  async function prepareImageDocument(
+    imagePaths: string[],
+    options: ImageDocumentOptions,
+    onProgress?: (progress: SplitProgress) => void,
+    jobId?: string,
+  ): Promise<ImageDocument> {
+    const unlisten = await listenSplitProgress(onProgress, jobId)
+    try {
+      return await invoke<ImageDocument>("prepare_image_document", { imagePaths, options, jobId })
+    } finally {
+      if (unlisten) {
+        await unlisten()
       }
     }
   }
@@ -82,7 +237,10 @@ export function usePdfProcessor() {
 
   return {
     getPageCount,
+    getPdfInfo,
     splitPdf,
+    cancelSplit,
+    prepareImageDocument,
     extractPage,
   }
 }
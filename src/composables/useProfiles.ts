@@ -0,0 +1,24 @@
+import { invoke } from "@tauri-apps/api/core"
+import type { ProfileList } from "@/utils/profiles"
+
+export function useProfiles() {
+  function listProfiles(): Promise<ProfileList> {
+    return invoke<ProfileList>("list_profiles")
+  }
+
+  /** Create a profile with default settings; the active profile is unchanged */
+  function createProfile(name: string): Promise<ProfileList> {
+    return invoke<ProfileList>("create_profile", { name: name.trim() })
+  }
+
+  /**
+   * Make `name` the active profile, then reload the window so the stores
+   * pick up its settings, sign-in and history
+   */
+  async function switchProfile(name: string): Promise<void> {
+    await invoke<ProfileList>("switch_profile", { name })
+    window.location.reload()
+  }
+
+  return { listProfiles, createProfile, switchProfile }
+}
@@ -0,0 +1,58 @@
+import { invoke } from "@tauri-apps/api/core"
+import { useAuth } from "./useAuth"
+import { useToastStore } from "@/stores/toast"
+import { getErrorMessage } from "@/utils/errors"
+
+export type QuickOcrSource = "region" | "clipboard"
+
+/** Payload of the backend `quick-ocr` event sent when the global shortcut is pressed */
+export interface QuickOcrRequest {
+  source: QuickOcrSource
+}
+
+export function useQuickOcr() {
+  const { ensureValidToken } = useAuth()
+  const toastStore = useToastStore()
+  // A second press while the previous capture is still open is ignored
+  let running = false
+
+  /**
+   * OCR a screen region or the clipboard image and put the text on the clipboard.
+   * Returns the text, or null when cancelled, signed out or failed.
+   */
+  async function runQuickOcr(source: QuickOcrSource): Promise<string | null> {
+    if (running) return null
+    running = true
+    try {
+      const accessToken = await ensureValidToken()
+      if (!accessToken) {
+        toastStore.warning("toast.quickOcrSignIn")
+        return null
+      }
+
+      let text: string | null
+      if (source === "clipboard") {
+        text = await invoke<string>("ocr_clipboard_image", { accessToken, copyResult: true })
+      } else {
+        text = await invoke<string | null>("ocr_screen_region", { accessToken })
+        if (text === null) return null
+        await invoke("copy_text_to_clipboard", { text })
+      }
+
+      if (text.trim() === "") {
+        toastStore.warning("toast.quickOcrEmpty")
+      } else {
+        toastStore.success("toast.quickOcrCopied")
+      }
+      return text
+    } catch (error) {
+      console.error("Quick OCR failed:", error)
+      toastStore.error("toast.quickOcrFailed", { error: getErrorMessage(error) })
+      return null
+    } finally {
+      running = false
+    }
+  }
+
+  return { runQuickOcr }
+}
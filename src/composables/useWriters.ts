@@ -1,10 +1,97 @@
-import { writeTextFile } from "@tauri-apps/plugin-fs"
-import { Document, Packer, Paragraph, TextRun, PageBreak } from "docx"
+import { readTextFile, writeTextFile } from "@tauri-apps/plugin-fs"
+import { BorderStyle, Document, Packer, Paragraph, TextRun, PageBreak } from "docx"
 import { invoke } from "@tauri-apps/api/core"
 import type { OutputFormat } from "@/stores/settings"
 
+/** Line between a page's body and its footnotes in TXT output (matches footnotes.rs) */
+export const FOOTNOTE_SEPARATOR = "\n\n__________\n"
+
 export interface WriterOptions {
   pageSeparator?: string
+  /** Detected language per page (see detect_page_languages), recorded in JSON */
+  languages?: Array<string | null>
+  /** Footnotes separated from each page's body (see split_page_footnotes) */
+  footnotes?: Array<string | null>
+  /** Labelled text of each page's regions of interest (see crop_page_regions), recorded in JSON */
+  regions?: RegionText[][]
+  /** Title, author and subject stored in the DOCX core properties */
+  metadata?: DocumentMetadata
+}
+
+/** Descriptive metadata of a source document (see get_pdf_info) */
+export interface DocumentMetadata {
+  title?: string
+  author?: string
+  subject?: string
+}
+
+/**
+ * The source's metadata with the non-blank fields of a job's overrides on top;
+ * blank fields are dropped
+ */
+export function mergeMetadata(
+  source: DocumentMetadata = {},
+  overrides: DocumentMetadata = {},
+): DocumentMetadata {
+  const merged: DocumentMetadata = {}
+  for (const field of ["title", "author", "subject"] as const) {
+    const value = overrides[field]?.trim() || source[field]?.trim()
+    if (value) merged[field] = value
+  }
+  return merged
+}
+
+/** Text recognized in one region of interest of a page */
+export interface RegionText {
+  label: string
+  text: string
+}
+
+/**
+ * Page text followed by its footnotes, if any, below the footnote separator
+ */
+export function withFootnotes(text: string, footnotes?: string | null): string {
+  const notes = footnotes?.trim()
+  return notes ? `${text.trim()}${FOOTNOTE_SEPARATOR}${notes}` : text.trim()
+}
+
+/** Separator between pages of a TXT output when the setting is empty */
+export const DEFAULT_PAGE_SEPARATOR = "\n\nPAGE_SEPARATOR\n\n"
+
+/** Content of a TXT output: the pages with their footnotes, joined by the page separator */
+export function txtContent(texts: string[], options: WriterOptions = {}): string {
+  const separator = options.pageSeparator || DEFAULT_PAGE_SEPARATOR
+  return texts.map((t, index) => withFootnotes(t, options.footnotes?.[index])).join(separator)
+}
+
+/** Page text read back from a JSON output (see readJson) */
+export interface PageText {
+  texts: string[]
+  languages: Array<string | null>
+  footnotes: Array<string | null>
+}
+
+/** Corrected text for one page (1-based) */
+export interface PageEdit {
+  page: number
+  text: string
+}
+
+export interface AppliedCorrections {
+  editedPages: number[]
+  paths: string[]
+}
+
+/** Book metadata for a Baheth/iEasyBooks library contribution */
+export interface BookMetadata {
+  title: string
+  authors?: string[]
+  publisher?: string
+  edition?: string
+  publicationYear?: number
+  /** ISO 639-1 code of the book's main language (defaults to "ar") */
+  language?: string
+  categories?: string[]
 }
 
 export function useWriters() {
@@ -80,32 +167,79 @@ export function useWriters() {
     outputPath: string,
     options: WriterOptions = {},
   ): Promise<void> {
-    const separator = options.pageSeparator || "\n\nPAGE_SEPARATOR\n\n"
-    const content = texts.map((t) => t.trim()).join(separator)
-    await writeTextFile(`${outputPath}.txt`, content)
+    await writeTextFile(`${outputPath}.txt`, txtContent(texts, options))
   }
 
   /**
    * Write JSON output
    */
-  async function writeJson(texts: string[], outputPath: string): Promise<void> {
-    const data = texts.map((text, index) => ({
-      page: index + 1,
-      content: text.trim(),
-    }))
+  async function writeJson(
+    texts: string[],
+    outputPath: string,
+    options: WriterOptions = {},
+  ): Promise<void> {
+    const data = texts.map((text, index) => {
+      const language = options.languages?.[index]
+      const footnotes = options.footnotes?.[index]?.trim()
+      const regions = options.regions?.[index]?.map((region) => ({
+        label: region.label,
+        content: region.text.trim(),
+      }))
+      return {
+        page: index + 1,
+        content: text.trim(),
+        ...(language ? { language } : {}),
+        ...(footnotes ? { footnotes } : {}),
+        ...(regions?.length ? { regions } : {}),
+      }
+    })
     await writeTextFile(`${outputPath}.json`, JSON.stringify(data, null, 2))
   }
 
+  /**
+   * Read the page text of a JSON output written by writeJson, in page order.
+   * Throws when the file is not an array of `{ page, content }` objects.
+   */
+  async function readJson(inputPath: string): Promise<PageText> {
+    const data: unknown = JSON.parse(await readTextFile(inputPath))
+    const isPage = (item: unknown): item is Record<string, unknown> =>
+      typeof item === "object" &&
+      item !== null &&
+      Number.isInteger((item as Record<string, unknown>).page) &&
+      typeof (item as Record<string, unknown>).content === "string"
+    if (!Array.isArray(data) || data.length === 0 || !data.every(isPage)) {
+      throw new Error(`Not a Tahweel JSON output: ${inputPath}`)
+    }
+
+    const pages = [...data].sort((a, b) => (a.page as number) - (b.page as number))
+    const optional = (value: unknown) => (typeof value === "string" ? value : null)
+    return {
+      texts: pages.map((page) => page.content as string),
+      languages: pages.map((page) => optional(page.language)),
+      footnotes: pages.map((page) => optional(page.footnotes)),
+    }
+  }
+
   /**
    * Write DOCX output with proper RTL support
    * Each PDF page becomes a separate DOCX page with proper page breaks
    * Matches Ruby gem behavior: paragraph content followed by page break
+   * Separated footnotes follow the body in a smaller paragraph with a rule above
+   * The source's title, author and subject become the core properties
    */
-  async function writeDocx(texts: string[], outputPath: string): Promise<void> {
+  async function writeDocx(
+    texts: string[],
+    outputPath: string,
+    options: WriterOptions = {},
+  ): Promise<void> {
     const children: Paragraph[] = []
 
-    for (let i = 0; i < texts.length; i++) {
-      let text = texts[i]
+    function textParagraph(
+      rawText: string,
+      pageBreak: boolean,
+      footnote: boolean,
+    ): Paragraph {
+      let text = rawText
         .replace(/\r\n?/g, "\n")
         .replace(/(\s)\1+/g, "$1")
         .trim()
@@ -115,7 +249,6 @@ export function useWriters() {
 
       const isRtl = isArabicText(text)
       const lines = text.split("\n")
-      const isLastPage = i === texts.length - 1
 
       // Build children: TextRuns with line breaks, then PageBreak at the end (except last page)
       const paragraphChildren: Array<TextRun | PageBreak> = []
@@ -124,7 +257,7 @@ export function useWriters() {
         paragraphChildren.push(
           new TextRun({
             text: line,
-            size: 20, // 10pt (size is in half-points)
+            size: footnote ? 16 : 20, // 8pt / 10pt (size is in half-points)
             rightToLeft: isRtl,
           }),
         )
@@ -137,21 +270,36 @@ export function useWriters() {
 
       // Add page break after content (except for the last page)
       // This matches Ruby's: docx.page if index < texts.size - 1
-      if (!isLastPage) {
+      if (pageBreak) {
         paragraphChildren.push(new PageBreak())
       }
 
       // Create paragraph with proper alignment and bidirectional text
-      const paragraph = new Paragraph({
+      return new Paragraph({
         alignment: isRtl ? "right" : "left",
         bidirectional: isRtl,
         children: paragraphChildren,
+        border: footnote
+          ? { top: { style: BorderStyle.SINGLE, size: 4, space: 4, color: "auto" } }
+          : undefined,
       })
+    }
 
-      children.push(paragraph)
+    for (let i = 0; i < texts.length; i++) {
+      const isLastPage = i === texts.length - 1
+      const footnotes = options.footnotes?.[i]?.trim()
+
+      children.push(textParagraph(texts[i], !isLastPage && !footnotes, false))
+      if (footnotes) {
+        children.push(textParagraph(footnotes, !isLastPage, true))
+      }
     }
 
+    const { title, author, subject } = options.metadata ?? {}
     const doc = new Document({
+      ...(title ? { title } : {}),
+      ...(author ? { creator: author } : {}),
+      ...(subject ? { subject } : {}),
       sections: [
         {
           children,
@@ -189,22 +337,79 @@ export function useWriters() {
     }
 
     if (formats.includes("json")) {
-      promises.push(writeJson(texts, outputBasePath))
+      promises.push(writeJson(texts, outputBasePath, options))
     }
 
     if (formats.includes("docx")) {
-      promises.push(writeDocx(texts, outputBasePath))
+      promises.push(writeDocx(texts, outputBasePath, options))
     }
 
     await Promise.all(promises)
   }
 
+  /**
+   * Check the written outputs (see verify.rs): the DOCX is a complete package,
+   * the TXT has a separator between each pair of pages, the JSON parses with one
+   * entry per page. Throws an `outputInvalid` error for a corrupt file.
+   */
+  async function verifyOutputs(
+    outputBasePath: string,
+    formats: OutputFormat[],
+    pageCount: number,
+    options: WriterOptions = {},
+  ): Promise<void> {
+    if (formats.length === 0) return
+    await invoke("verify_outputs", {
+      outputPath: outputBasePath,
+      formats,
+      pageCount,
+      pageSeparator: options.pageSeparator || DEFAULT_PAGE_SEPARATOR,
+    })
+  }
+
+  /**
+   * Save proofread text back into a recorded job (see record_job): updates the
+   * stored pages, keeps an edit history, and rewrites the job's outputs
+   */
+  async function applyCorrections(
+    jobId: string,
+    pageEdits: PageEdit[],
+  ): Promise<AppliedCorrections> {
+    return invoke<AppliedCorrections>("apply_corrections", { jobId, pageEdits })
+  }
+
+  /**
+   * Export a book in the Baheth library ingestion schema (book metadata, page
+   * texts, and checksums) to `<outputPath>.baheth.json`. Returns the written path.
+   */
+  async function exportToBaheth(
+    texts: string[],
+    outputPath: string,
+    sourcePath: string,
+    metadata: BookMetadata,
+    options: WriterOptions = {},
+  ): Promise<string> {
+    return invoke<string>("write_baheth_export", {
+      outputPath,
+      sourcePath,
+      metadata,
+      pages: texts.map((text, index) => ({
+        text,
+        footnotes: options.footnotes?.[index] ?? null,
+      })),
+    })
+  }
+
   return {
     isArabicText,
     compactText,
     writeTxt,
     writeJson,
+    readJson,
     writeDocx,
     writeOutputs,
+    verifyOutputs,
+    applyCorrections,
+    exportToBaheth,
   }
 }
@@ -14,8 +14,12 @@ export const messages = {
       signIn: "تسجيل الدخول",
       signOut: "تسجيل الخروج",
       openFolder: "فتح المجلد",
+      share: "مشاركة",
       cancel: "إلغاء",
       newConversion: "تحويل جديد",
+      savePartial: "حفظ الصفحات المنجزة ({count})",
+      resumeJob: "استئناف",
+      cleanUpJob: "تنظيف",
       languageSelector: "اختر اللغة",
       conversionOptions: "خيارات التحويل",
       completionActions: "إجراءات ما بعد الانتهاء",
@@ -30,6 +34,11 @@ export const messages = {
       done: "انتهى",
       cancelling: "جارٍ الإلغاء...",
       progressRegion: "تقدم التحويل",
+      partialResults: "ملفات توقفت قبل اكتمالها ويمكن حفظ ما تم التعرف عليه منها:",
+      otherFiles: "ملفات أخرى قيد المعالجة",
+      concurrency: "{count} صفحات متزامنة",
+      etaMinutes: "يتبقى نحو {count} دقيقة",
+      etaUnderMinute: "يتبقى أقل من دقيقة",
     },
     messages: {
       successTitle: "اكتمل التحويل",
@@ -37,21 +46,132 @@ export const messages = {
       noFiles: "لم نعثر على ملفات لتحويلها.",
       badExtension: "صيغة الملف غير مدعومة.",
       authRequired: "يرجى تسجيل الدخول أولاً.",
+      offline: "لا يوجد اتصال بالإنترنت. تحقق من اتصالك ثم حاول مرة أخرى.",
+      proxyUnreachable: "تعذر الاتصال بالخادم الوكيل (Proxy). تحقق من إعداداته.",
+      backendUnusable: "لا يمكن استخدام Google Drive للتعرف على النص حالياً:",
+      archivalUnavailable: "تعذر بدء وضع الأرشفة: {error}",
       conversionComplete: "انتهى تحويل {count} ملف بنجاح.",
       conversionCompleteOne: "انتهى تحويل ملف واحد بنجاح.",
       conversionCompleteTwo: "انتهى تحويل ملفين بنجاح.",
       errorList: "قائمة الأخطاء",
+      stageModeInput: "هذا الملف لا يناسب وضع «{mode}».",
+      assembleNeedsFormat: "اختر صيغة إخراج غير JSON لتجميع النص.",
+      jobInterruptedTitle: "تحويل لم يكتمل",
+      jobInterrupted: "أُغلق تحويل قبل اكتماله أثناء معالجة {file} (تم التعرف على {count} صفحة). هل تريد استئنافه من جديد أم تنظيف ما تبقى منه؟",
     },
     settings: {
       title: "الإعدادات",
       dpi: "دقة الصورة (DPI)",
+      autoDpi: "تلقائية حسب حجم الصفحة",
       formats: "صيغ الإخراج",
       concurrency: "التزامن",
       ocrConcurrency: "عدد عمليات OCR المتزامنة",
+      parallelDocuments: "عدد الملفات المعالجة معًا",
+      driveConcurrency: "الحد الأقصى لعمليات Google Drive لكل الملفات",
       outputDirectory: "مجلد الإخراج",
       outputDirectoryHint: "اختر مجلد الإخراج (اختياري)",
       clearOutputDirectory: "مسح",
       useInputDirectory: "استخدام مجلد الإدخال",
+      archivalMode: "وضع الأرشفة (قراءة فقط)",
+      archivalModeHint: "لا يُكتب أي شيء بجوار الملفات الأصلية؛ تُحفظ النتائج والملفات المؤقتة في مجلدات التطبيق.",
+      maskRegions: "مناطق مخفية (تواقيع وأختام)",
+      maskRegionsHint: "تُبيّض هذه المناطق على صور الصفحات قبل رفعها. القيم نسبة مئوية من عرض الصفحة وارتفاعها؛ اترك رقم الصفحة فارغًا لتطبيقها على كل الصفحات.",
+      roiRegions: "مناطق التعرف (النماذج)",
+      roiRegionsHint: "عند تحديد مناطق لا يُتعرف إلا على نصها، ويُسمّى نص كل منطقة باسمها في ملف JSON. تنطبق على صفحات PDF فقط.",
+      regionName: "اسم المنطقة",
+      regionLabel: "منطقة {n}",
+      regionPage: "الصفحة",
+      regionAllPages: "الكل",
+      regionFields: {
+        x: "من اليسار (%)",
+        y: "من الأعلى (%)",
+        width: "العرض (%)",
+        height: "الارتفاع (%)",
+      },
+      addRegion: "إضافة منطقة",
+      removeRegion: "حذف المنطقة",
+      keepPageImages: "الاحتفاظ بصور الصفحات",
+      liveTxt: "كتابة النص أولًا بأول",
+      liveTxtHint: "يُضاف نص كل صفحة إلى ملف .partial.txt فور التعرف عليه، فيمكن قراءته أثناء المعالجة. يحل الملف النصي النهائي محله عند الانتهاء.",
+      combineImages: "دمج صور المجلد الواحد في مستند واحد",
+      combineImagesHint: "تُعالَج الصور المتعددة من مجلد واحد صفحاتٍ لمستند واحد مرتبةً بأسمائها، فتنتج ملفات نصية واحدة باسم المجلد.",
+      deskewImages: "تقويم الصفحات المائلة",
+      cropImageBorders: "قص الهوامش الفارغة والخلفية الداكنة",
+      postprocessing: "المعالجة اللاحقة",
+      postprocessSteps: {
+        normalizeWhitespace: "توحيد المسافات",
+        stripDiacritics: "حذف التشكيل والتطويل",
+        unifyLetters: "توحيد الألف والياء",
+        removeHeadersFooters: "حذف الترويسات والتذييلات",
+        reflowParagraphs: "دمج الأسطر في فقرات",
+        quranVerses: "تصحيح الآيات القرآنية",
+        replace: "استبدال نص",
+      },
+      postprocessStep: "خطوة",
+      addStep: "إضافة",
+      moveStepUp: "تحريك للأعلى",
+      moveStepDown: "تحريك للأسفل",
+      removeStep: "حذف الخطوة",
+      keepShadda: "إبقاء الشدة",
+      quranScript: "رسم الآيات",
+      quranScripts: {
+        uthmani: "الرسم العثماني",
+        imlaei: "الرسم الإملائي",
+      },
+      replaceFind: "النص المطلوب",
+      replaceWith: "يُستبدل بـ",
+      previewSample: "الصق نص صفحة لمعاينة النتيجة",
+      postprocessingHint: "تُنفذ الخطوات بالترتيب على نص كل مستند قبل كتابة الملفات. لا تظهر الخطوات التي تحتاج المستند كاملًا في المعاينة ولا في المستندات الكبيرة المقسمة.",
+      pageImagesFolder: "مجلد صور الصفحات",
+      stageMode: "مراحل المعالجة",
+      shardPages: "حجم أجزاء ملفات PDF الكبيرة (صفحة)",
+      shardPagesHint: "تُعالج ملفات PDF الأطول على أجزاء متتالية بهذا العدد من الصفحات ثم تُجمع نتائجها. 0 للإيقاف.",
+      backgroundMode: "وضع الخلفية (توفير الطاقة)",
+      backgroundModeHint: "يحوّل الصفحات بأولوية منخفضة وبنصف عدد الخيوط، ويتوقف مؤقتًا عند العمل على البطارية إذا انخفض شحنها عن الحد أدناه.",
+      batteryPausePercent: "إيقاف مؤقت عند شحن البطارية أقل من (%)",
+      profile: "الملف الشخصي",
+      defaultProfile: "الافتراضي",
+      newProfile: "اسم ملف شخصي جديد",
+      createProfile: "إنشاء",
+      profileHint: "لكل ملف شخصي إعداداته وتسجيل دخوله وسجله. يُعاد تحميل البرنامج عند التبديل.",
+      appData: "ملفات البرنامج",
+      appDataHint: "المجلدات التي يحفظ فيها البرنامج إعداداته وسجله وملفاته المؤقتة",
+      appDataAreas: {
+        config: "الإعدادات",
+        cache: "الذاكرة المؤقتة",
+        workspaces: "مساحات العمل",
+        logs: "السجلات",
+        db: "قواعد البيانات",
+      },
+      historyReport: "تقرير السجل",
+      historyFrom: "من شهر",
+      historyTo: "إلى شهر",
+      exportHistoryCsv: "تصدير CSV",
+      exportHistoryJson: "تصدير JSON",
+      historyReportHint: "عدد المستندات والصفحات المعالجة في كل شهر من سجل المهام. اترك الشهر فارغًا لعدم التقييد.",
+      storage: "التخزين",
+      storageStores: {
+        workspace: "مساحة العمل",
+        cache: "الذاكرة المؤقتة",
+        history: "سجل المهام",
+        logs: "السجلات",
+      },
+      storageUsed: "{size} م.ب · {entries} عنصر",
+      retentionDays: "أقصى عمر (أيام)",
+      retentionSize: "أقصى حجم (م.ب)",
+      cleanUpNow: "تنظيف الآن",
+      storageHint: "يُحذف الأقدم أولًا كل ست ساعات عند تجاوز الحد. القيمة 0 تلغي الحد.",
+      dailyBudget: "الحد اليومي لـ Google Drive",
+      budgetPages: "أقصى عدد للصفحات",
+      budgetApiCalls: "أقصى عدد للطلبات",
+      budgetUsed: "اليوم: {pages} صفحة · {apiCalls} طلب · يتجدد الساعة {time}",
+      budgetHint: "تتوقف المعالجة مؤقتًا عند بلوغ الحد وتُستأنف تلقائيًا عند تجدده. القيمة 0 تلغي الحد.",
+      stageModes: {
+        full: "التحويل الكامل",
+        renderOnly: "تحويل PDF إلى صور فقط",
+        ocrOnly: "التعرف على نص الصور فقط",
+        assembleOnly: "تجميع نص JSON فقط",
+      },
     },
     auth: {
       signedIn: "تم تسجيل الدخول إلى Google Drive",
@@ -71,7 +191,50 @@ export const messages = {
       signOutWarning: "تعذر مسح بيانات الجلسة المحلية",
       sessionExpired: "انتهت صلاحية الجلسة. يرجى تسجيل الدخول مرة أخرى.",
       ocrPartialErrors: "فشل استخراج النص من {count} صفحة/صفحات",
+      ocrEmptyPages: "لم يُعثر على نص في {count} صفحة/صفحات حتى بعد إعادة المحاولة",
       openFolderFailed: "تعذر فتح المجلد",
+      shareFailed: "تعذرت مشاركة الملف",
+      storageUploadFailed: "تعذر رفع الملفات الناتجة إلى التخزين",
+      webdavUploadFailed: "تعذر رفع الملفات الناتجة إلى مجلد WebDAV",
+      emailResultsFailed: "تعذر إرسال النتائج بالبريد الإلكتروني",
+      keepPageImagesFailed: "تعذر نقل صور الصفحات إلى مجلد الإخراج",
+      pagesDownscaled: "صُغّرت {count} صفحة/صفحات لتناسب حدود Google Drive",
+      imagesSkipped: "تُركت {count} صورة/صور تعذرت قراءتها",
+      batteryPaused: "توقفت المعالجة مؤقتًا: شحن البطارية {percent}%. صِل الجهاز بالشاحن للمتابعة",
+      batteryResumed: "استُؤنفت المعالجة بعد توصيل الشاحن",
+      quotaWarning: "استُهلك {percent}% من الحد اليومي لـ Google Drive. يتجدد الساعة {time}",
+      quotaPaused: "توقفت المعالجة مؤقتًا: بلغت الحد اليومي لـ Google Drive. تُستأنف الساعة {time}",
+      quotaResumed: "استُؤنفت المعالجة بعد تجدد الحد اليومي",
+      quickOcrCopied: "نُسخ النص المستخرج إلى الحافظة",
+      partialSaved: "حُفظت الصفحات المنجزة في ملفات جزئية",
+      partialSaveFailed: "تعذر حفظ الصفحات المنجزة: {error}",
+      quickOcrEmpty: "لم يُعثر على نص في الصورة",
+      quickOcrFailed: "فشل التعرف السريع على النص: {error}",
+      quickOcrSignIn: "سجّل الدخول إلى Google Drive لاستخدام اختصار التعرف السريع",
+      profileFailed: "تعذر تغيير الملفات الشخصية: {error}",
+      historyExported: "صُدّر السجل: {documents} مستند/مستندات و{pages} صفحة",
+      historyExportFailed: "تعذر تصدير السجل: {error}",
+      cleanupDone: "اكتمل التنظيف: حُرّر {size} م.ب",
+      cleanupFailed: "تعذر التنظيف: {error}",
+      budgetFailed: "تعذر تغيير الحد اليومي: {error}",
+      pageStallRestarted: "توقفت الصفحة {page} عن التقدم، وتجري إعادة معالجتها",
+      renderStalled: "توقف تحويل الصفحة {page} إلى صورة عن التقدم",
+    },
+    errors: {
+      auth: "انتهت صلاحية تسجيل الدخول. يرجى تسجيل الدخول مرة أخرى.",
+      network: "تعذر الاتصال بالخادم. تحقق من اتصالك بالإنترنت.",
+      timeout: "انتهت مهلة الطلب.",
+      rateLimited: "تم تجاوز حد الطلبات. حاول مرة أخرى بعد قليل.",
+      server: "خدمة Google غير متاحة حاليًا. حاول مرة أخرى لاحقًا.",
+      api: "رفضت Google الطلب.",
+      notFound: "الملف غير موجود.",
+      io: "تعذرت قراءة الملف أو كتابته.",
+      pdfiumUnavailable: "مكتبة PDFium غير متوفرة.",
+      pdf: "تعذر فتح ملف PDF.",
+      invalidInput: "مدخلات غير صالحة.",
+      pathNotAllowed: "لا يُسمح بالوصول إلى هذا المسار.",
+      outputInvalid: "الملف الناتج تالف ولم يُحفظ بشكل صحيح.",
+      internal: "حدث خطأ غير متوقع.",
     },
   },
   en: {
@@ -89,8 +252,12 @@ export const messages = {
       signIn: "Sign In",
       signOut: "Sign Out",
       openFolder: "Open Folder",
+      share: "Share",
       cancel: "Cancel",
       newConversion: "New Conversion",
+      savePartial: "Save completed pages ({count})",
+      resumeJob: "Resume",
+      cleanUpJob: "Clean up",
       languageSelector: "Select language",
       conversionOptions: "Conversion options",
       completionActions: "Completion actions",
@@ -105,6 +272,11 @@ export const messages = {
       done: "Done",
       cancelling: "Cancelling...",
       progressRegion: "Conversion progress",
+      partialResults: "These files stopped before finishing; their recognized pages can still be saved:",
+      otherFiles: "Other files in progress",
+      concurrency: "{count} pages in parallel",
+      etaMinutes: "about {count} min left",
+      etaUnderMinute: "less than a minute left",
     },
     messages: {
       successTitle: "Conversion Complete",
@@ -112,21 +284,132 @@ export const messages = {
       noFiles: "No files found to convert.",
       badExtension: "Unsupported file format.",
       authRequired: "Please sign in first.",
+      offline: "You appear to be offline. Check your internet connection and try again.",
+      proxyUnreachable: "Could not connect to the configured proxy. Check its settings.",
+      backendUnusable: "Google Drive cannot be used for OCR right now:",
+      archivalUnavailable: "Archival mode cannot start: {error}",
       conversionComplete: "Finished converting {count} file(s) successfully.",
       conversionCompleteOne: "Finished converting 1 file successfully.",
       conversionCompleteTwo: "Finished converting 2 files successfully.",
       errorList: "Error list",
+      stageModeInput: "This file is not an input for \"{mode}\".",
+      assembleNeedsFormat: "Choose an output format other than JSON to assemble text.",
+      jobInterruptedTitle: "Unfinished conversion",
+      jobInterrupted: "Tahweel closed while converting {file} ({count} page(s) recognized). Convert it again, or clean up what is left of it?",
     },
     settings: {
       title: "Settings",
       dpi: "Image Resolution (DPI)",
+      autoDpi: "Automatic (based on page size)",
       formats: "Output Formats",
       concurrency: "Concurrency",
       ocrConcurrency: "Concurrent OCR Operations",
+      parallelDocuments: "Documents Processed at Once",
+      driveConcurrency: "Drive Operations Limit (All Documents)",
       outputDirectory: "Output Folder",
       outputDirectoryHint: "Choose output folder (optional)",
       clearOutputDirectory: "Clear",
       useInputDirectory: "Use input folder",
+      archivalMode: "Archival mode (read-only sources)",
+      archivalModeHint: "Nothing is written next to the source files; outputs and temporary files go to the app's own folders.",
+      maskRegions: "Masked regions (signatures, stamps)",
+      maskRegionsHint: "These regions are blanked on page images before upload. Values are percentages of the page width and height; leave the page empty to mask every page.",
+      roiRegions: "Regions of interest (forms)",
+      roiRegionsHint: "When set, only these regions are recognized and each region's text is labelled in the JSON output. Applies to PDF pages only.",
+      regionName: "Region name",
+      regionLabel: "Region {n}",
+      regionPage: "Page",
+      regionAllPages: "All",
+      regionFields: {
+        x: "From left (%)",
+        y: "From top (%)",
+        width: "Width (%)",
+        height: "Height (%)",
+      },
+      addRegion: "Add region",
+      removeRegion: "Remove region",
+      keepPageImages: "Keep page images",
+      liveTxt: "Write text as it arrives",
+      liveTxtHint: "Each page's text is appended to a .partial.txt file as soon as it is recognized, so it can be read while processing continues. The final TXT replaces it when the job ends.",
+      combineImages: "Combine a folder's images into one document",
+      combineImagesHint: "Several images from one folder are processed as the pages of one document, ordered by name, giving one set of outputs named after the folder.",
+      deskewImages: "Straighten tilted pages",
+      cropImageBorders: "Crop empty margins and dark backgrounds",
+      postprocessing: "Post-processing",
+      postprocessSteps: {
+        normalizeWhitespace: "Normalize whitespace",
+        stripDiacritics: "Remove diacritics and tatweel",
+        unifyLetters: "Unify alef and yaa forms",
+        removeHeadersFooters: "Remove headers and footers",
+        reflowParagraphs: "Reflow paragraphs",
+        quranVerses: "Correct Quranic verses",
+        replace: "Replace text",
+      },
+      postprocessStep: "Step",
+      addStep: "Add",
+      moveStepUp: "Move up",
+      moveStepDown: "Move down",
+      removeStep: "Remove step",
+      keepShadda: "Keep shadda",
+      quranScript: "Verse script",
+      quranScripts: {
+        uthmani: "Uthmani",
+        imlaei: "Imlaei",
+      },
+      replaceFind: "Find",
+      replaceWith: "Replace with",
+      previewSample: "Paste a page of text to preview the result",
+      postprocessingHint: "Steps run in order on each document's text before its outputs are written. Steps that need the whole document are left out of the preview and of large documents written in shards.",
+      pageImagesFolder: "Page images folder",
+      stageMode: "Processing Stages",
+      shardPages: "Large PDF shard size (pages)",
+      shardPagesHint: "Longer PDFs are processed in consecutive parts of this many pages, then joined. 0 turns this off.",
+      backgroundMode: "Background mode (low power)",
+      backgroundModeHint: "Renders pages at lower priority with half the threads, and pauses on battery when the charge drops below the level below.",
+      batteryPausePercent: "Pause on battery below (%)",
+      profile: "Profile",
+      defaultProfile: "Default",
+      newProfile: "New profile name",
+      createProfile: "Create",
+      profileHint: "Each profile has its own settings, sign-in and history. Tahweel reloads when you switch.",
+      appData: "App data",
+      appDataHint: "Folders where Tahweel keeps its settings, history and temporary files",
+      appDataAreas: {
+        config: "Settings",
+        cache: "Cache",
+        workspaces: "Workspaces",
+        logs: "Logs",
+        db: "Databases",
+      },
+      historyReport: "History report",
+      historyFrom: "From month",
+      historyTo: "To month",
+      exportHistoryCsv: "Export CSV",
+      exportHistoryJson: "Export JSON",
+      historyReportHint: "Documents and pages processed each month, from the job history. Leave a month blank for no limit.",
+      storage: "Storage",
+      storageStores: {
+        workspace: "Workspace",
+        cache: "Cache",
+        history: "Job history",
+        logs: "Logs",
+      },
+      storageUsed: "{size} MB · {entries} item(s)",
+      retentionDays: "Max age (days)",
+      retentionSize: "Max size (MB)",
+      cleanUpNow: "Clean up now",
+      storageHint: "Every six hours the oldest items are removed once a limit is exceeded. 0 turns a limit off.",
+      dailyBudget: "Google Drive daily budget",
+      budgetPages: "Max pages",
+      budgetApiCalls: "Max API calls",
+      budgetUsed: "Today: {pages} page(s) · {apiCalls} call(s) · resets at {time}",
+      budgetHint: "Processing pauses once a limit is reached and resumes by itself when it resets. 0 turns a limit off.",
+      stageModes: {
+        full: "Full conversion",
+        renderOnly: "Render PDF to images only",
+        ocrOnly: "OCR images only",
+        assembleOnly: "Assemble JSON text only",
+      },
     },
     auth: {
       signedIn: "Signed in to Google Drive",
@@ -146,7 +429,50 @@ export const messages = {
       signOutWarning: "Could not clear local session data",
       sessionExpired: "Session expired. Please sign in again.",
       ocrPartialErrors: "Failed to extract text from {count} page(s)",
+      ocrEmptyPages: "No text found on {count} page(s), even after retrying",
       openFolderFailed: "Could not open folder",
+      shareFailed: "Could not share the file",
+      storageUploadFailed: "Could not upload the output files to storage",
+      webdavUploadFailed: "Could not upload the output files to the WebDAV folder",
+      emailResultsFailed: "Could not email the results",
+      keepPageImagesFailed: "Could not move the page images to the output folder",
+      pagesDownscaled: "{count} page(s) were scaled down to fit Google Drive's limits",
+      imagesSkipped: "{count} image(s) could not be read and were left out",
+      batteryPaused: "Processing paused: battery at {percent}%. Plug in to continue",
+      batteryResumed: "Processing resumed on external power",
+      quotaWarning: "{percent}% of today's Google Drive budget is used. It resets at {time}",
+      quotaPaused: "Processing paused: today's Google Drive budget is used up. Resuming at {time}",
+      quotaResumed: "Processing resumed: the daily budget was reset",
+      quickOcrCopied: "Recognized text copied to the clipboard",
+      partialSaved: "Completed pages saved as partial outputs",
+      partialSaveFailed: "Could not save the completed pages: {error}",
+      quickOcrEmpty: "No text found in the image",
+      quickOcrFailed: "Quick OCR failed: {error}",
+      quickOcrSignIn: "Sign in to Google Drive to use the quick OCR shortcut",
+      profileFailed: "Could not change profiles: {error}",
+      historyExported: "History exported: {documents} document(s), {pages} page(s)",
+      historyExportFailed: "Could not export the history: {error}",
+      cleanupDone: "Cleanup finished: {size} MB freed",
+      cleanupFailed: "Cleanup failed: {error}",
+      budgetFailed: "Could not change the daily budget: {error}",
+      pageStallRestarted: "Page {page} stopped making progress and is being processed again",
+      renderStalled: "Rendering page {page} stopped making progress",
+    },
+    errors: {
+      auth: "Your sign-in has expired. Please sign in again.",
+      network: "Could not reach the server. Check your internet connection.",
+      timeout: "The request timed out.",
+      rateLimited: "Too many requests. Please try again shortly.",
+      server: "Google's service is unavailable. Please try again later.",
+      api: "Google rejected the request.",
+      notFound: "File not found.",
+      io: "Could not read or write the file.",
+      pdfiumUnavailable: "The PDFium library is not available.",
+      pdf: "Could not open the PDF file.",
+      invalidInput: "Invalid input.",
+      pathNotAllowed: "Access to this path is not allowed.",
+      outputInvalid: "An output file is corrupt and was not written correctly.",
+      internal: "An unexpected error occurred.",
     },
   },
 }
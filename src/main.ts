@@ -1,9 +1,11 @@
 import { createApp } from "vue"
 import { createPinia } from "pinia"
 import { createI18n } from "vue-i18n"
+import { invoke } from "@tauri-apps/api/core"
 
 import App from "./App.vue"
 import { messages } from "./i18n"
+import { setActiveProfile, type ProfileList } from "./utils/profiles"
 import "./assets/main.css"
 
 // Load saved locale or default to Arabic
@@ -19,6 +21,14 @@ const i18n = createI18n({
 const pinia = createPinia()
 const app = createApp(App)
 
-app.use(pinia)
-app.use(i18n)
-app.mount("#app")
+// Settings are stored per profile, so the active one must be known before the stores load
+invoke<ProfileList>("list_profiles")
+  .then(({ active }) => setActiveProfile(active))
+  .catch(() => {
+    // Keep the default profile
+  })
+  .finally(() => {
+    app.use(pinia)
+    app.use(i18n)
+    app.mount("#app")
+  })
@@ -44,6 +44,11 @@ describe("useProcessingStore", () => {
       const store = useProcessingStore()
       expect(store.outputFolder).toBeNull()
     })
+
+    it("starts with no output files", () => {
+      const store = useProcessingStore()
+      expect(store.outputFiles).toEqual([])
+    })
   })
 
   describe("globalProgress", () => {
@@ -208,6 +213,34 @@ describe("useProcessingStore", () => {
       store.completeFile()
       expect(store.currentFileIndex).toBe(1)
     })
+
+    it("tracks each file in progress until it completes", () => {
+      const store = useProcessingStore()
+      store.startProcessing(["/a.pdf", "/b.pdf"], "/output")
+      for (const filePath of ["/a.pdf", "/b.pdf"]) {
+        store.updateFileProgress({
+          filePath,
+          fileName: filePath.slice(1),
+          stage: "ocr",
+          currentPage: 1,
+          totalPages: 4,
+          percentage: 25,
+        })
+      }
+      store.updateFileProgress({
+        filePath: "/a.pdf",
+        fileName: "a.pdf",
+        stage: "ocr",
+        currentPage: 2,
+        totalPages: 4,
+        percentage: 50,
+      })
+
+      expect(store.activeFiles.map((file) => file.percentage)).toEqual([50, 25])
+
+      store.completeFile("/a.pdf")
+      expect(store.activeFiles.map((file) => file.filePath)).toEqual(["/b.pdf"])
+    })
   })
 
   describe("addError", () => {
@@ -227,6 +260,19 @@ describe("useProcessingStore", () => {
     })
   })
 
+  describe("partialJobs", () => {
+    it("tracks stopped jobs until they are saved or a new run starts", () => {
+      const store = useProcessingStore()
+      store.addPartialJob({ jobId: "job-1", file: "/a.pdf", pages: 3 })
+      store.addPartialJob({ jobId: "job-2", file: "/b.pdf", pages: 1 })
+      store.removePartialJob("job-1")
+      expect(store.partialJobs).toEqual([{ jobId: "job-2", file: "/b.pdf", pages: 1 }])
+
+      store.startProcessing(["/c.pdf"], "/output")
+      expect(store.partialJobs).toEqual([])
+    })
+  })
+
   describe("finishProcessing", () => {
     it("sets isProcessing to false", () => {
       const store = useProcessingStore()
@@ -273,6 +319,7 @@ describe("useProcessingStore", () => {
         percentage: 50,
       })
       store.addError("/file.pdf", "Error")
+      store.addOutputFiles(["/out/file.txt"])
       store.completeFile()
       store.finishProcessing()
 
@@ -288,6 +335,92 @@ describe("useProcessingStore", () => {
       expect(store.errors).toEqual([])
       expect(store.lastCompleted).toBe(false)
       expect(store.outputFolder).toBeNull()
+      expect(store.outputFiles).toEqual([])
+    })
+  })
+
+  describe("pause", () => {
+    it("starts not paused", () => {
+      const store = useProcessingStore()
+      expect(store.isPaused).toBe(false)
+    })
+
+    it("waitWhilePaused resolves immediately when not paused", async () => {
+      const store = useProcessingStore()
+      await expect(store.waitWhilePaused()).resolves.toBeUndefined()
+    })
+
+    it("waitWhilePaused resolves after resume", async () => {
+      const store = useProcessingStore()
+      store.setPaused(true)
+      let resumed = false
+      const waiting = store.waitWhilePaused().then(() => {
+        resumed = true
+      })
+
+      await Promise.resolve()
+      expect(resumed).toBe(false)
+
+      store.setPaused(false)
+      await waiting
+      expect(resumed).toBe(true)
+    })
+
+    it("cancelling releases paused waiters", async () => {
+      const store = useProcessingStore()
+      store.setPaused(true)
+      const waiting = store.waitWhilePaused()
+      store.cancelProcessing()
+      await expect(waiting).resolves.toBeUndefined()
+    })
+
+    it("waits until both the user and low battery pauses are lifted", async () => {
+      const store = useProcessingStore()
+      store.setPaused(true)
+      store.setPowerPaused(true)
+      let resumed = false
+      const waiting = store.waitWhilePaused().then(() => {
+        resumed = true
+      })
+
+      store.setPaused(false)
+      await Promise.resolve()
+      expect(resumed).toBe(false)
+
+      store.setPowerPaused(false)
+      await waiting
+      expect(resumed).toBe(true)
+    })
+
+    it("waits for a used-up daily budget to reset", async () => {
+      const store = useProcessingStore()
+      store.setQuotaPaused(true)
+      let resumed = false
+      const waiting = store.waitWhilePaused().then(() => {
+        resumed = true
+      })
+
+      store.setPaused(false)
+      await Promise.resolve()
+      expect(resumed).toBe(false)
+
+      store.setQuotaPaused(false)
+      await waiting
+      expect(resumed).toBe(true)
+    })
+
+    it("startProcessing keeps the low battery pause", () => {
+      const store = useProcessingStore()
+      store.setPowerPaused(true)
+      store.startProcessing(["/file.pdf"], "/output")
+      expect(store.isPowerPaused).toBe(true)
+    })
+
+    it("startProcessing clears the paused flag", () => {
+      const store = useProcessingStore()
+      store.setPaused(true)
+      store.startProcessing(["/file.pdf"], "/output")
+      expect(store.isPaused).toBe(false)
     })
   })
 
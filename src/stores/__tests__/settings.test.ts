@@ -1,4 +1,4 @@
-import { describe, it, expect, beforeEach, vi } from "vitest"
+import { describe, it, expect, beforeEach, afterEach, vi } from "vitest"
 import { setActivePinia, createPinia } from "pinia"
 
 // Mock localStorage
@@ -23,6 +23,7 @@ Object.defineProperty(global, "localStorage", {
 })
 
 import { useSettingsStore } from "../settings"
+import { setActiveProfile, DEFAULT_PROFILE } from "@/utils/profiles"
 
 describe("useSettingsStore", () => {
   beforeEach(() => {
@@ -52,6 +53,11 @@ describe("useSettingsStore", () => {
       expect(store.pageSeparator).toBe("\n\nPAGE_SEPARATOR\n\n")
     })
 
+    it("has auto DPI disabled by default", () => {
+      const store = useSettingsStore()
+      expect(store.autoDpi).toBe(false)
+    })
+
     it("has null output directory by default", () => {
       const store = useSettingsStore()
       expect(store.outputDirectory).toBeNull()
@@ -116,6 +122,247 @@ describe("useSettingsStore", () => {
     })
   })
 
+  describe("parallel documents", () => {
+    it("processes one document at a time by default", () => {
+      const store = useSettingsStore()
+      expect(store.parallelDocuments).toBe(1)
+      expect(store.driveConcurrency).toBe(20)
+    })
+
+    it("clamps parallel documents and the Drive cap", () => {
+      const store = useSettingsStore()
+      store.setParallelDocuments(10)
+      expect(store.parallelDocuments).toBe(4)
+      store.setParallelDocuments(0)
+      expect(store.parallelDocuments).toBe(1)
+      store.setDriveConcurrency(500)
+      expect(store.driveConcurrency).toBe(60)
+      store.setDriveConcurrency(-1)
+      expect(store.driveConcurrency).toBe(1)
+    })
+
+    it("clamps tampered values from localStorage", () => {
+      localStorageMock.getItem.mockReturnValueOnce(
+        JSON.stringify({ parallelDocuments: 99, driveConcurrency: 0 }),
+      )
+
+      setActivePinia(createPinia())
+      const store = useSettingsStore()
+
+      expect(store.parallelDocuments).toBe(4)
+      expect(store.driveConcurrency).toBe(1)
+    })
+  })
+
+  describe("page images", () => {
+    it("deletes page images by default", () => {
+      const store = useSettingsStore()
+      expect(store.keepPageImages).toBe(false)
+      expect(store.pageImagesFolder).toBe("pages")
+    })
+
+    it("accepts only a single folder name", () => {
+      const store = useSettingsStore()
+      store.setPageImagesFolder(" scans ")
+      expect(store.pageImagesFolder).toBe("scans")
+      for (const name of ["", "..", "a/b", "a\\b"]) {
+        store.setPageImagesFolder(name)
+        expect(store.pageImagesFolder).toBe("scans")
+      }
+    })
+
+    it("falls back to the default folder for tampered values", () => {
+      localStorageMock.getItem.mockReturnValueOnce(
+        JSON.stringify({ keepPageImages: "yes", pageImagesFolder: "../../etc" }),
+      )
+
+      setActivePinia(createPinia())
+      const store = useSettingsStore()
+
+      expect(store.keepPageImages).toBe(false)
+      expect(store.pageImagesFolder).toBe("pages")
+    })
+  })
+
+  describe("stage mode", () => {
+    it("runs every stage by default", () => {
+      expect(useSettingsStore().stageMode).toBe("full")
+    })
+
+    it("loads a saved single-stage mode and ignores unknown ones", () => {
+      localStorageMock.getItem.mockReturnValueOnce(JSON.stringify({ stageMode: "ocrOnly" }))
+      setActivePinia(createPinia())
+      expect(useSettingsStore().stageMode).toBe("ocrOnly")
+
+      localStorageMock.getItem.mockReturnValueOnce(JSON.stringify({ stageMode: "uploadOnly" }))
+      setActivePinia(createPinia())
+      expect(useSettingsStore().stageMode).toBe("full")
+    })
+  })
+
+  describe("shard pages", () => {
+    it("shards at 500 pages by default", () => {
+      expect(useSettingsStore().shardPages).toBe(500)
+    })
+
+    it("clamps sizes and keeps 0 as off", () => {
+      const store = useSettingsStore()
+      store.setShardPages(10)
+      expect(store.shardPages).toBe(50)
+      store.setShardPages(100000)
+      expect(store.shardPages).toBe(5000)
+      store.setShardPages(0)
+      expect(store.shardPages).toBe(0)
+    })
+
+    it("validates the saved size", () => {
+      localStorageMock.getItem.mockReturnValueOnce(JSON.stringify({ shardPages: -3 }))
+      setActivePinia(createPinia())
+      expect(useSettingsStore().shardPages).toBe(0)
+
+      localStorageMock.getItem.mockReturnValueOnce(JSON.stringify({ shardPages: "many" }))
+      setActivePinia(createPinia())
+      expect(useSettingsStore().shardPages).toBe(500)
+    })
+  })
+
+  describe("archival mode", () => {
+    it("is off by default and only loads a saved true", () => {
+      expect(useSettingsStore().archivalMode).toBe(false)
+
+      localStorageMock.getItem.mockReturnValueOnce(JSON.stringify({ archivalMode: "yes" }))
+      setActivePinia(createPinia())
+      expect(useSettingsStore().archivalMode).toBe(false)
+
+      localStorageMock.getItem.mockReturnValueOnce(JSON.stringify({ archivalMode: true }))
+      setActivePinia(createPinia())
+      expect(useSettingsStore().archivalMode).toBe(true)
+    })
+  })
+
+  describe("image documents", () => {
+    it("combines images without clean-up by default", () => {
+      const store = useSettingsStore()
+      expect(store.combineImages).toBe(true)
+      expect(store.deskewImages).toBe(false)
+      expect(store.cropImageBorders).toBe(false)
+    })
+
+    it("loads saved choices", () => {
+      localStorageMock.getItem.mockReturnValueOnce(
+        JSON.stringify({ combineImages: false, deskewImages: true, cropImageBorders: "yes" }),
+      )
+      setActivePinia(createPinia())
+      const store = useSettingsStore()
+      expect(store.combineImages).toBe(false)
+      expect(store.deskewImages).toBe(true)
+      expect(store.cropImageBorders).toBe(false)
+    })
+  })
+
+  describe("background mode", () => {
+    it("is off by default and pauses below 20% battery", () => {
+      const store = useSettingsStore()
+      expect(store.backgroundMode).toBe(false)
+      expect(store.batteryPausePercent).toBe(20)
+    })
+
+    it("clamps the battery threshold", () => {
+      const store = useSettingsStore()
+      store.setBatteryPausePercent(1)
+      expect(store.batteryPausePercent).toBe(5)
+      store.setBatteryPausePercent(100)
+      expect(store.batteryPausePercent).toBe(95)
+      store.setBatteryPausePercent(32.6)
+      expect(store.batteryPausePercent).toBe(33)
+    })
+
+    it("validates saved values", () => {
+      localStorageMock.getItem.mockReturnValueOnce(
+        JSON.stringify({ backgroundMode: true, batteryPausePercent: "low" }),
+      )
+      setActivePinia(createPinia())
+      const store = useSettingsStore()
+      expect(store.backgroundMode).toBe(true)
+      expect(store.batteryPausePercent).toBe(20)
+    })
+  })
+
+  describe("mask regions", () => {
+    it("keeps valid regions and drops the rest", () => {
+      const store = useSettingsStore()
+      store.setMaskRegions([
+        { x: 60, y: 80, width: 35, height: 15 },
+        { page: 2, x: 0, y: 0, width: 10, height: 10 },
+        { x: 90, y: 0, width: 20, height: 10 },
+        { x: 0, y: 0, width: 0, height: 10 },
+        { page: 0, x: 0, y: 0, width: 10, height: 10 },
+      ])
+      expect(store.maskRegions).toEqual([
+        { x: 60, y: 80, width: 35, height: 15 },
+        { page: 2, x: 0, y: 0, width: 10, height: 10 },
+      ])
+    })
+
+    it("validates saved regions", () => {
+      localStorageMock.getItem.mockReturnValueOnce(
+        JSON.stringify({ maskRegions: [{ x: "1", y: 0, width: 5, height: 5 }, null] }),
+      )
+      setActivePinia(createPinia())
+      expect(useSettingsStore().maskRegions).toEqual([])
+
+      localStorageMock.getItem.mockReturnValueOnce(JSON.stringify({ maskRegions: "all" }))
+      setActivePinia(createPinia())
+      expect(useSettingsStore().maskRegions).toEqual([])
+    })
+  })
+
+  describe("post-processing", () => {
+    it("starts with an empty pipeline", () => {
+      expect(useSettingsStore().postprocessing).toEqual([])
+    })
+
+    it("keeps valid steps in order", () => {
+      localStorageMock.getItem.mockReturnValueOnce(
+        JSON.stringify({
+          postprocessing: [{ step: "reflowParagraphs" }, { step: "bogus" }, { step: "unifyLetters" }],
+        }),
+      )
+      setActivePinia(createPinia())
+      expect(useSettingsStore().postprocessing).toEqual([
+        { step: "reflowParagraphs" },
+        { step: "unifyLetters" },
+      ])
+    })
+  })
+
+  describe("regions of interest", () => {
+    it("requires a label on each region", () => {
+      const store = useSettingsStore()
+      store.setRoiRegions([
+        { label: " Name ", x: 0, y: 0, width: 50, height: 10 },
+        { label: "  ", page: 1, x: 0, y: 10, width: 50, height: 10 },
+        { label: "Date", page: 1, x: 50, y: 0, width: 60, height: 10 },
+      ])
+      expect(store.roiRegions).toEqual([{ label: "Name", x: 0, y: 0, width: 50, height: 10 }])
+    })
+
+    it("loads saved regions", () => {
+      localStorageMock.getItem.mockReturnValueOnce(
+        JSON.stringify({
+          roiRegions: [
+            { label: "ID", page: 2, x: 10, y: 10, width: 20, height: 5 },
+            { x: 0, y: 0, width: 10, height: 10 },
+          ],
+        }),
+      )
+      setActivePinia(createPinia())
+      expect(useSettingsStore().roiRegions).toEqual([
+        { label: "ID", page: 2, x: 10, y: 10, width: 20, height: 5 },
+      ])
+    })
+  })
+
   describe("setOutputDirectory", () => {
     it("sets output directory path", () => {
       const store = useSettingsStore()
@@ -189,6 +436,20 @@ describe("useSettingsStore", () => {
       expect(store.outputDirectory).toBe("/custom/path")
     })
 
+    it("loads auto DPI only when it is exactly true", () => {
+      localStorageMock.getItem.mockReturnValueOnce(
+        JSON.stringify({ autoDpi: true }),
+      )
+      setActivePinia(createPinia())
+      expect(useSettingsStore().autoDpi).toBe(true)
+
+      localStorageMock.getItem.mockReturnValueOnce(
+        JSON.stringify({ autoDpi: "yes" }),
+      )
+      setActivePinia(createPinia())
+      expect(useSettingsStore().autoDpi).toBe(false)
+    })
+
     it("uses defaults for missing fields", () => {
       const partialSettings = {
         dpi: 250,
@@ -261,4 +522,27 @@ describe("useSettingsStore", () => {
       expect(parsed.dpi).toBe(200)
     })
   })
+
+  describe("profiles", () => {
+    afterEach(() => {
+      setActiveProfile(DEFAULT_PROFILE)
+    })
+
+    it("keeps each profile's settings under its own key", async () => {
+      setActiveProfile("staff")
+      localStorageMock.setItem("tahweel-settings", JSON.stringify({ dpi: 100 }))
+      localStorageMock.setItem("tahweel-settings:staff", JSON.stringify({ dpi: 250 }))
+
+      const store = useSettingsStore()
+      expect(store.dpi).toBe(250)
+
+      store.setDpi(200)
+      await new Promise((r) => setTimeout(r, 10))
+      expect(localStorageMock.setItem).toHaveBeenLastCalledWith(
+        "tahweel-settings:staff",
+        expect.stringContaining('"dpi":200'),
+      )
+      expect(JSON.parse(localStorageMock.getItem("tahweel-settings")!).dpi).toBe(100)
+    })
+  })
 })
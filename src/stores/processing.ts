@@ -8,6 +8,14 @@ export interface ProcessingError {
   error: string
 }
 
+/** A job that stopped midway after recognizing some pages (see savePartialResults) */
+export interface PartialJob {
+  jobId: string
+  file: string
+  /** Pages recognized before it stopped */
+  pages: number
+}
+
 export interface FileProgress {
   filePath: string
   fileName: string
@@ -15,6 +23,10 @@ export interface FileProgress {
   currentPage: number
   totalPages: number
   percentage: number
+  /** Drive pages allowed in flight during OCR (adaptive, see useGoogleDriveOcr) */
+  concurrency?: number
+  /** Expected milliseconds until the stage ends (see utils/eta) */
+  etaMs?: number
 }
 
 export interface ProcessingState {
@@ -29,14 +41,25 @@ export interface ProcessingState {
 export const useProcessingStore = defineStore("processing", () => {
   const isProcessing = ref(false)
   const isCancelled = ref(false)
+  const isPaused = ref(false)
+  /** Held back by background mode while on low battery (see power.rs) */
+  const isPowerPaused = ref(false)
+  /** Held back until the OCR backend's daily budget resets (see quota.rs) */
+  const isQuotaPaused = ref(false)
   const files = ref<string[]>([])
   const currentFileIndex = ref(0)
   const currentFile = ref<FileProgress | null>(null)
+  /** Files in progress; more than one when documents are processed in parallel */
+  const activeFiles = ref<FileProgress[]>([])
   const completedFiles = ref(0)
   const totalFiles = ref(0)
   const errors = ref<ProcessingError[]>([])
+  const partialJobs = ref<PartialJob[]>([])
   const lastCompleted = ref(false)
   const outputFolder = ref<string | null>(null)
+  /** Output files written in this run, in the order they were written */
+  const outputFiles = ref<string[]>([])
+  let resumeWaiters: Array<() => void> = []
 
   const globalProgress = computed(() => {
     if (totalFiles.value === 0) return 0
@@ -47,73 +70,154 @@ export const useProcessingStore = defineStore("processing", () => {
     return currentFile.value?.percentage ?? 0
   })
 
+  function releaseWaiters() {
+    const waiters = resumeWaiters
+    resumeWaiters = []
+    waiters.forEach((resolve) => resolve())
+  }
+
   function startProcessing(filePaths: string[], outputDir: string) {
     isProcessing.value = true
     isCancelled.value = false
+    isPaused.value = false
     files.value = filePaths
     totalFiles.value = filePaths.length
     currentFileIndex.value = 0
     completedFiles.value = 0
     currentFile.value = null
+    activeFiles.value = []
     errors.value = []
+    partialJobs.value = []
     lastCompleted.value = false
     outputFolder.value = outputDir
+    outputFiles.value = []
   }
 
   function cancelProcessing() {
     isCancelled.value = true
+    releaseWaiters()
+  }
+
+  function isHeld() {
+    return isPaused.value || isPowerPaused.value || isQuotaPaused.value
+  }
+
+  function setPaused(paused: boolean) {
+    isPaused.value = paused
+    if (!isHeld()) releaseWaiters()
+  }
+
+  function setPowerPaused(paused: boolean) {
+    isPowerPaused.value = paused
+    if (!isHeld()) releaseWaiters()
+  }
+
+  function setQuotaPaused(paused: boolean) {
+    isQuotaPaused.value = paused
+    if (!isHeld()) releaseWaiters()
+  }
+
+  /**
+   * Resolves immediately unless the queue is paused (by the user, on low
+   * battery, or by a used-up daily budget), otherwise once it is resumed or
+   * cancelled. Called before starting each file and each OCR page.
+   */
+  function waitWhilePaused(): Promise<void> {
+    if (!isHeld() || isCancelled.value) return Promise.resolve()
+    return new Promise((resolve) => resumeWaiters.push(resolve))
   }
 
   function updateFileProgress(progress: FileProgress) {
     currentFile.value = progress
+    const index = activeFiles.value.findIndex((file) => file.filePath === progress.filePath)
+    if (index === -1) {
+      activeFiles.value.push(progress)
+    } else {
+      activeFiles.value[index] = progress
+    }
   }
 
-  function completeFile() {
+  /** Count a finished (or failed) file; with its path, it leaves `activeFiles` */
+  function completeFile(filePath?: string) {
     completedFiles.value++
     currentFileIndex.value++
+    if (filePath) {
+      activeFiles.value = activeFiles.value.filter((file) => file.filePath !== filePath)
+    }
+  }
+
+  function addOutputFiles(paths: string[]) {
+    outputFiles.value.push(...paths)
   }
 
   function addError(file: string, error: string) {
     errors.value.push({ file, error })
   }
 
+  function addPartialJob(job: PartialJob) {
+    partialJobs.value.push(job)
+  }
+
+  function removePartialJob(jobId: string) {
+    partialJobs.value = partialJobs.value.filter((job) => job.jobId !== jobId)
+  }
+
   function finishProcessing() {
     isProcessing.value = false
     currentFile.value = null
+    activeFiles.value = []
     lastCompleted.value = true
   }
 
   function reset() {
     isProcessing.value = false
     isCancelled.value = false
+    isPaused.value = false
+    releaseWaiters()
     files.value = []
     currentFileIndex.value = 0
     currentFile.value = null
+    activeFiles.value = []
     completedFiles.value = 0
     totalFiles.value = 0
     errors.value = []
+    partialJobs.value = []
     lastCompleted.value = false
     outputFolder.value = null
+    outputFiles.value = []
   }
 
   return {
     isProcessing,
     isCancelled,
+    isPaused,
+    isPowerPaused,
+    isQuotaPaused,
     files,
     currentFileIndex,
     currentFile,
+    activeFiles,
     completedFiles,
     totalFiles,
     errors,
+    partialJobs,
     lastCompleted,
     outputFolder,
+    outputFiles,
     globalProgress,
     fileProgress,
     startProcessing,
     cancelProcessing,
+    setPaused,
+    setPowerPaused,
+    setQuotaPaused,
+    waitWhilePaused,
     updateFileProgress,
     completeFile,
+    addOutputFiles,
     addError,
+    addPartialJob,
+    removePartialJob,
     finishProcessing,
     reset,
   }
@@ -1,14 +1,58 @@
 import { defineStore } from "pinia"
 import { ref, watch } from "vue"
+import { profileStorageKey } from "@/utils/profiles"
+import { validPipeline, type PostprocessStep } from "@/utils/postprocessing"
 
 export type OutputFormat = "txt" | "docx" | "json"
 
+/**
+ * Pipeline stages to run: everything, or one stage on its own (render PDFs to
+ * page images, OCR images to page text, assemble page text into the outputs)
+ */
+export type StageMode = "full" | "renderOnly" | "ocrOnly" | "assembleOnly"
+
+export const STAGE_MODES: StageMode[] = ["full", "renderOnly", "ocrOnly", "assembleOnly"]
+
+/**
+ * A rectangle (signature, stamp) blanked on rendered pages before upload, in
+ * percent of the page width and height; `page` limits it to one 1-based page
+ */
+export interface MaskRegion {
+  page?: number
+  x: number
+  y: number
+  width: number
+  height: number
+}
+
+/** A labelled region of a form page; with any set, only these regions are OCRed */
+export interface RoiRegion extends MaskRegion {
+  label: string
+}
+
 export interface Settings {
   dpi: number
+  autoDpi: boolean
   formats: OutputFormat[]
   ocrConcurrency: number
+  parallelDocuments: number
+  driveConcurrency: number
   pageSeparator: string
+  postprocessing: PostprocessStep[]
   outputDirectory: string | null
+  keepPageImages: boolean
+  pageImagesFolder: string
+  stageMode: StageMode
+  shardPages: number
+  archivalMode: boolean
+  liveTxt: boolean
+  combineImages: boolean
+  deskewImages: boolean
+  cropImageBorders: boolean
+  backgroundMode: boolean
+  batteryPausePercent: number
+  maskRegions: MaskRegion[]
+  roiRegions: RoiRegion[]
 }
 
 const STORAGE_KEY = "tahweel-settings"
@@ -18,34 +62,153 @@ const DPI_MIN = 72
 const DPI_MAX = 300
 const CONCURRENCY_MIN = 1
 const CONCURRENCY_MAX = 20
+const PARALLEL_DOCUMENTS_MIN = 1
+const PARALLEL_DOCUMENTS_MAX = 4
+const DRIVE_CONCURRENCY_MIN = 1
+const DRIVE_CONCURRENCY_MAX = 60
+const SHARD_PAGES_MIN = 50
+const SHARD_PAGES_MAX = 5000
+const SHARD_PAGES_DEFAULT = 500
+const BATTERY_PAUSE_MIN = 5
+const BATTERY_PAUSE_MAX = 95
+const BATTERY_PAUSE_DEFAULT = 20
+
+/** A single folder name: no separators, not `.` or `..` */
+function isFolderName(value: unknown): value is string {
+  return (
+    typeof value === "string" &&
+    value.trim() !== "" &&
+    !/[\\/]/.test(value) &&
+    value !== "." &&
+    value !== ".."
+  )
+}
 
 function clamp(value: number, min: number, max: number): number {
   return Math.max(min, Math.min(max, value))
 }
 
+/** 0 turns sharding off; other sizes are clamped to the supported range */
+function validShardPages(value: number): number {
+  if (!Number.isFinite(value)) return SHARD_PAGES_DEFAULT
+  return value <= 0 ? 0 : clamp(Math.round(value), SHARD_PAGES_MIN, SHARD_PAGES_MAX)
+}
+
+/** The region's area when it lies within the page with a positive size */
+function validArea(region: unknown): MaskRegion | null {
+  if (typeof region !== "object" || region === null) return null
+  const { page, x, y, width, height } = region as Record<string, unknown>
+  const numbers = [x, y, width, height]
+  if (!numbers.every((n) => typeof n === "number" && Number.isFinite(n))) return null
+  const area = { x, y, width, height } as Omit<MaskRegion, "page">
+  if (
+    area.x < 0 ||
+    area.y < 0 ||
+    area.width <= 0 ||
+    area.height <= 0 ||
+    area.x + area.width > 100 ||
+    area.y + area.height > 100
+  ) {
+    return null
+  }
+  if (page === undefined) return area
+  return Number.isInteger(page) && (page as number) > 0 ? { page: page as number, ...area } : null
+}
+
+/** Regions within the page with a positive size; anything else is dropped */
+function validMaskRegions(value: unknown): MaskRegion[] {
+  if (!Array.isArray(value)) return []
+  return value.flatMap((region) => validArea(region) ?? [])
+}
+
+/** Like mask regions, and each needs a label to name its text */
+function validRoiRegions(value: unknown): RoiRegion[] {
+  if (!Array.isArray(value)) return []
+  return value.flatMap((region) => {
+    const area = validArea(region)
+    const label = typeof region?.label === "string" ? region.label.trim() : ""
+    return area && label ? [{ label, ...area }] : []
+  })
+}
+
 export const useSettingsStore = defineStore("settings", () => {
   const dpi = ref(150)
+  // Pick each page's resolution from its size and scan resolution instead of `dpi`
+  const autoDpi = ref(false)
   const formats = ref<OutputFormat[]>(["txt", "docx"])
   const ocrConcurrency = ref(12)
+  // Documents processed at once, each with its own `ocrConcurrency` pages in flight
+  const parallelDocuments = ref(1)
+  // Cap on Drive pages in flight across all documents being processed
+  const driveConcurrency = ref(20)
   const pageSeparator = ref("\n\nPAGE_SEPARATOR\n\n")
+  // Post-processing steps run in order on each document before writing (postprocess.rs)
+  const postprocessing = ref<PostprocessStep[]>([])
   const outputDirectory = ref<string | null>(null)
+  // Move rendered PDF pages to `<output dir>/<pageImagesFolder>/<name>/` instead of deleting them
+  const keepPageImages = ref(false)
+  const pageImagesFolder = ref("pages")
+  const stageMode = ref<StageMode>("full")
+  // PDFs longer than this are rendered and recognized in shards of this many pages
+  const shardPages = ref(SHARD_PAGES_DEFAULT)
+  // Read-only sources: outputs and temp files go only to app-owned folders
+  const archivalMode = ref(false)
+  // Append each page's text to `<output>.partial.txt` as it is recognized
+  const liveTxt = ref(false)
+  // Loose images of one folder are OCRed as one document, optionally
+  // straightened and cropped first (image_document.rs)
+  const combineImages = ref(true)
+  const deskewImages = ref(false)
+  const cropImageBorders = ref(false)
+  // Low-power rendering, paused on battery below `batteryPausePercent` (power.rs)
+  const backgroundMode = ref(false)
+  const batteryPausePercent = ref(BATTERY_PAUSE_DEFAULT)
+  const maskRegions = ref<MaskRegion[]>([])
+  const roiRegions = ref<RoiRegion[]>([])
 
   // Load settings from localStorage with validation
   function loadSettings() {
     try {
-      const stored = localStorage.getItem(STORAGE_KEY)
+      const stored = localStorage.getItem(profileStorageKey(STORAGE_KEY))
       if (stored) {
         const parsed = JSON.parse(stored) as Settings
         // Validate and clamp values to prevent tampered localStorage values
         dpi.value = clamp(parsed.dpi ?? 150, DPI_MIN, DPI_MAX)
+        autoDpi.value = parsed.autoDpi === true
         formats.value = parsed.formats ?? ["txt", "docx"]
         ocrConcurrency.value = clamp(
           parsed.ocrConcurrency ?? 12,
           CONCURRENCY_MIN,
           CONCURRENCY_MAX,
         )
+        parallelDocuments.value = clamp(
+          parsed.parallelDocuments ?? 1,
+          PARALLEL_DOCUMENTS_MIN,
+          PARALLEL_DOCUMENTS_MAX,
+        )
+        driveConcurrency.value = clamp(
+          parsed.driveConcurrency ?? 20,
+          DRIVE_CONCURRENCY_MIN,
+          DRIVE_CONCURRENCY_MAX,
+        )
         pageSeparator.value = parsed.pageSeparator ?? "\n\nPAGE_SEPARATOR\n\n"
+        postprocessing.value = validPipeline(parsed.postprocessing)
         outputDirectory.value = parsed.outputDirectory ?? null
+        keepPageImages.value = parsed.keepPageImages === true
+        pageImagesFolder.value = isFolderName(parsed.pageImagesFolder)
+          ? parsed.pageImagesFolder
+          : "pages"
+        stageMode.value = STAGE_MODES.includes(parsed.stageMode) ? parsed.stageMode : "full"
+        shardPages.value = validShardPages(parsed.shardPages ?? SHARD_PAGES_DEFAULT)
+        archivalMode.value = parsed.archivalMode === true
+        liveTxt.value = parsed.liveTxt === true
+        combineImages.value = parsed.combineImages !== false
+        deskewImages.value = parsed.deskewImages === true
+        cropImageBorders.value = parsed.cropImageBorders === true
+        backgroundMode.value = parsed.backgroundMode === true
+        setBatteryPausePercent(parsed.batteryPausePercent ?? BATTERY_PAUSE_DEFAULT)
+        maskRegions.value = validMaskRegions(parsed.maskRegions)
+        roiRegions.value = validRoiRegions(parsed.roiRegions)
       }
     } catch {
       // Ignore errors, use defaults
@@ -61,25 +224,105 @@ export const useSettingsStore = defineStore("settings", () => {
     ocrConcurrency.value = clamp(value, CONCURRENCY_MIN, CONCURRENCY_MAX)
   }
 
+  function setParallelDocuments(value: number) {
+    parallelDocuments.value = clamp(value, PARALLEL_DOCUMENTS_MIN, PARALLEL_DOCUMENTS_MAX)
+  }
+
+  function setDriveConcurrency(value: number) {
+    driveConcurrency.value = clamp(value, DRIVE_CONCURRENCY_MIN, DRIVE_CONCURRENCY_MAX)
+  }
+
+  function setShardPages(value: number) {
+    shardPages.value = validShardPages(value)
+  }
+
+  function setBatteryPausePercent(value: number) {
+    batteryPausePercent.value = Number.isFinite(value)
+      ? clamp(Math.round(value), BATTERY_PAUSE_MIN, BATTERY_PAUSE_MAX)
+      : BATTERY_PAUSE_DEFAULT
+  }
+
+  /** Invalid regions (outside the page, empty, bad page number) are dropped */
+  function setMaskRegions(regions: MaskRegion[]) {
+    maskRegions.value = validMaskRegions(regions)
+  }
+
+  /** Invalid regions (as for masks, or without a label) are dropped */
+  function setRoiRegions(regions: RoiRegion[]) {
+    roiRegions.value = validRoiRegions(regions)
+  }
+
+  /** Invalid steps are dropped */
+  function setPostprocessing(pipeline: PostprocessStep[]) {
+    postprocessing.value = validPipeline(pipeline)
+  }
+
   function setOutputDirectory(path: string | null) {
     outputDirectory.value = path
   }
 
+  /** Ignores names that are not a single folder */
+  function setPageImagesFolder(name: string) {
+    if (isFolderName(name.trim())) {
+      pageImagesFolder.value = name.trim()
+    }
+  }
+
   // Save settings to localStorage
   function saveSettings() {
     const settings: Settings = {
       dpi: dpi.value,
+      autoDpi: autoDpi.value,
       formats: formats.value,
       ocrConcurrency: ocrConcurrency.value,
+      parallelDocuments: parallelDocuments.value,
+      driveConcurrency: driveConcurrency.value,
       pageSeparator: pageSeparator.value,
+      postprocessing: postprocessing.value,
       outputDirectory: outputDirectory.value,
+      keepPageImages: keepPageImages.value,
+      pageImagesFolder: pageImagesFolder.value,
+      stageMode: stageMode.value,
+      shardPages: shardPages.value,
+      archivalMode: archivalMode.value,
+      liveTxt: liveTxt.value,
+      combineImages: combineImages.value,
+      deskewImages: deskewImages.value,
+      cropImageBorders: cropImageBorders.value,
+      backgroundMode: backgroundMode.value,
+      batteryPausePercent: batteryPausePercent.value,
+      maskRegions: maskRegions.value,
+      roiRegions: roiRegions.value,
     }
-    localStorage.setItem(STORAGE_KEY, JSON.stringify(settings))
+    localStorage.setItem(profileStorageKey(STORAGE_KEY), JSON.stringify(settings))
   }
 
   // Auto-save when settings change
   watch(
-    [dpi, formats, ocrConcurrency, pageSeparator, outputDirectory],
+    [
+      dpi,
+      autoDpi,
+      formats,
+      ocrConcurrency,
+      parallelDocuments,
+      driveConcurrency,
+      pageSeparator,
+      postprocessing,
+      outputDirectory,
+      keepPageImages,
+      pageImagesFolder,
+      stageMode,
+      shardPages,
+      archivalMode,
+      liveTxt,
+      combineImages,
+      deskewImages,
+      cropImageBorders,
+      backgroundMode,
+      batteryPausePercent,
+      maskRegions,
+      roiRegions,
+    ],
     saveSettings,
     { deep: true },
   )
@@ -98,15 +341,40 @@ export const useSettingsStore = defineStore("settings", () => {
 
   return {
     dpi,
+    autoDpi,
     formats,
     ocrConcurrency,
+    parallelDocuments,
+    driveConcurrency,
     pageSeparator,
+    postprocessing,
     outputDirectory,
+    keepPageImages,
+    pageImagesFolder,
+    stageMode,
+    shardPages,
+    archivalMode,
+    liveTxt,
+    combineImages,
+    deskewImages,
+    cropImageBorders,
+    backgroundMode,
+    batteryPausePercent,
+    maskRegions,
+    roiRegions,
     toggleFormat,
     loadSettings,
     saveSettings,
     setDpi,
     setOcrConcurrency,
+    setParallelDocuments,
+    setDriveConcurrency,
+    setShardPages,
+    setBatteryPausePercent,
+    setMaskRegions,
+    setRoiRegions,
+    setPostprocessing,
     setOutputDirectory,
+    setPageImagesFolder,
   }
 })
@@ -0,0 +1,64 @@
+import { describe, it, expect } from "vitest"
+import { AdaptiveConcurrency, isThrottleError } from "../adaptiveConcurrency"
+
+function backendError(kind: string, details: string) {
+  return { kind, messageKey: `errors.${kind}`, message: "", details, retriable: false }
+}
+
+describe("adaptiveConcurrency", () => {
+  it("recognizes throttling errors", () => {
+    expect(isThrottleError(backendError("rateLimited", "429"))).toBe(true)
+    expect(
+      isThrottleError(backendError("api", "Upload failed (403 Forbidden): userRateLimitExceeded")),
+    ).toBe(true)
+    expect(
+      isThrottleError(backendError("api", "Upload failed (403 Forbidden): insufficientPermissions")),
+    ).toBe(false)
+    expect(isThrottleError(backendError("server", "503"))).toBe(false)
+    expect(isThrottleError(new Error("429"))).toBe(false)
+  })
+
+  it("halves on throttling and grows back one round at a time", () => {
+    const limit = new AdaptiveConcurrency(8)
+    expect(limit.current).toBe(8)
+
+    limit.recordThrottle()
+    expect(limit.current).toBe(4)
+
+    for (let i = 0; i < 4; i++) limit.recordSuccess()
+    expect(limit.current).toBe(5)
+    for (let i = 0; i < 5; i++) limit.recordSuccess()
+    expect(limit.current).toBe(6)
+  })
+
+  it("counts a burst of throttling errors once per round", () => {
+    const limit = new AdaptiveConcurrency(8)
+    limit.recordThrottle()
+    limit.recordThrottle()
+    limit.recordThrottle()
+    expect(limit.current).toBe(4)
+
+    // Once a round of pages has finished, throttling backs off further
+    limit.recordThrottle()
+    limit.recordThrottle()
+    expect(limit.current).toBe(4)
+    limit.recordThrottle()
+    expect(limit.current).toBe(2)
+  })
+
+  it("stays between 1 and the setting", () => {
+    const limit = new AdaptiveConcurrency(2)
+    for (let i = 0; i < 10; i++) limit.recordSuccess()
+    expect(limit.current).toBe(2)
+
+    for (let i = 0; i < 10; i++) limit.recordThrottle()
+    expect(limit.current).toBe(1)
+
+    limit.setMax(6)
+    expect(limit.current).toBe(1)
+    for (let i = 0; i < 20; i++) limit.recordSuccess()
+    expect(limit.current).toBe(6)
+    limit.setMax(3)
+    expect(limit.current).toBe(3)
+  })
+})
@@ -0,0 +1,33 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { APP_PATH_AREAS, getAppPaths } from "../appPaths"
+
+describe("appPaths", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("returns the layout folders from the backend", async () => {
+    const paths = {
+      root: "/data/tahweel",
+      config: "/data/tahweel/config",
+      cache: "/data/tahweel/cache",
+      workspaces: "/data/tahweel/workspaces",
+      logs: "/data/tahweel/logs",
+      db: "/data/tahweel/db",
+      profile: "default",
+    }
+    vi.mocked(invoke).mockResolvedValueOnce(paths)
+
+    await expect(getAppPaths()).resolves.toEqual(paths)
+    expect(invoke).toHaveBeenCalledWith("get_app_paths")
+    for (const area of APP_PATH_AREAS) {
+      expect(paths[area]).toBe(`/data/tahweel/${area}`)
+    }
+  })
+})
@@ -0,0 +1,33 @@
+import { describe, it, expect } from "vitest"
+import { getErrorDetails, getErrorMessage, isTahweelError } from "../errors"
+
+const backendError = {
+  kind: "rateLimited",
+  messageKey: "errors.rateLimited",
+  message: "Too many requests. Please try again shortly.",
+  details: "Upload failed (429 Too Many Requests): slow down",
+  retriable: true,
+}
+
+describe("errors", () => {
+  it("recognizes backend errors", () => {
+    expect(isTahweelError(backendError)).toBe(true)
+    expect(isTahweelError("Upload failed")).toBe(false)
+    expect(isTahweelError(null)).toBe(false)
+    expect(isTahweelError(new Error("boom"))).toBe(false)
+  })
+
+  it("uses the backend message", () => {
+    expect(getErrorMessage(backendError)).toBe(backendError.message)
+  })
+
+  it("keeps technical details separate", () => {
+    expect(getErrorDetails(backendError)).toBe(backendError.details)
+    expect(getErrorDetails("plain")).toBe("plain")
+  })
+
+  it("falls back to String() for other values", () => {
+    expect(getErrorMessage("plain")).toBe("plain")
+    expect(getErrorMessage(new Error("Processing cancelled"))).toBe("Error: Processing cancelled")
+  })
+})
@@ -0,0 +1,64 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { EtaModel, getThroughputEstimate, recordThroughput } from "../eta"
+
+describe("eta", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("starts from the history and moves toward the job's own pace", () => {
+    const eta = new EtaModel(100, 2000)
+    expect(eta.remainingMs()).toBe(200000)
+
+    // 5 pages at 4s each: history and observed pace weigh the same
+    eta.update(5, 20000)
+    expect(eta.msPerPage()).toBe(3000)
+    expect(eta.remainingMs()).toBe(95 * 3000)
+
+    // Later on, the observed pace dominates
+    eta.update(95, 380000)
+    expect(eta.msPerPage()).toBe(0.95 * 4000 + 0.05 * 2000)
+  })
+
+  it("has no estimate without history until a page finishes", () => {
+    const eta = new EtaModel(10, null)
+    expect(eta.remainingMs()).toBeNull()
+    eta.update(2, 6000)
+    expect(eta.remainingMs()).toBe(24000)
+    eta.update(10, 30000)
+    expect(eta.remainingMs()).toBe(0)
+  })
+
+  it("reads and records throughput history", async () => {
+    vi.mocked(invoke).mockResolvedValue({ msPerPage: 2500, samples: 3, dpi: 150 })
+    expect(await getThroughputEstimate("googleDrive", 150)).toEqual({
+      msPerPage: 2500,
+      samples: 3,
+      dpi: 150,
+    })
+    expect(invoke).toHaveBeenCalledWith("get_throughput_estimate", {
+      backend: "googleDrive",
+      dpi: 150,
+    })
+
+    await recordThroughput("googleDrive", 150, 12, 30000.4)
+    expect(invoke).toHaveBeenCalledWith("record_job_throughput", {
+      backend: "googleDrive",
+      dpi: 150,
+      pages: 12,
+      elapsedMs: 30000,
+    })
+  })
+
+  it("ignores job database errors", async () => {
+    vi.mocked(invoke).mockRejectedValue(new Error("locked"))
+    expect(await getThroughputEstimate("googleDrive", 150)).toBeNull()
+    await expect(recordThroughput("googleDrive", 150, 1, 10)).resolves.toBeUndefined()
+  })
+})
@@ -0,0 +1,39 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { exportHistory, monthRange } from "../history"
+
+describe("history", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("covers whole months from the first to the last", () => {
+    expect(monthRange("2026-01", "2026-03")).toEqual({
+      fromMs: Date.UTC(2026, 0, 1),
+      toMs: Date.UTC(2026, 3, 1),
+    })
+    expect(monthRange("2025-12", "2025-12").toMs).toBe(Date.UTC(2026, 0, 1))
+  })
+
+  it("leaves blank ends open", () => {
+    expect(monthRange("", "")).toEqual({})
+    expect(monthRange("2026-05", "")).toEqual({ fromMs: Date.UTC(2026, 4, 1) })
+  })
+
+  it("exports through the backend", async () => {
+    const exported = { path: "/reports/history.csv", documents: 3, pages: 22 }
+    vi.mocked(invoke).mockResolvedValueOnce(exported)
+    const range = monthRange("2026-01", "2026-01")
+    await expect(exportHistory("csv", "/reports/history.csv", range)).resolves.toEqual(exported)
+    expect(invoke).toHaveBeenCalledWith("export_history", {
+      format: "csv",
+      dateRange: range,
+      outputPath: "/reports/history.csv",
+    })
+  })
+})
@@ -0,0 +1,36 @@
+import { describe, it, expect } from "vitest"
+import { groupImageInputs } from "../imageDocuments"
+
+describe("imageDocuments", () => {
+  it("combines the images of a folder into one document", () => {
+    const grouped = groupImageInputs([
+      "/in/book.pdf",
+      "/in/photos/IMG_10.jpg",
+      "/in/photos/IMG_2.JPG",
+      "/in/cover.png",
+      "/in/photos/IMG_1.png",
+    ])
+
+    expect(grouped.paths).toEqual(["/in/book.pdf", "/in/photos/photos", "/in/cover.png"])
+    expect(grouped.documents).toEqual({
+      "/in/photos/photos": ["/in/photos/IMG_1.png", "/in/photos/IMG_2.JPG", "/in/photos/IMG_10.jpg"],
+    })
+  })
+
+  it("keeps single images, other folders and content URIs apart", () => {
+    const inputs = [
+      "C:\\scans\\a\\page1.jpg",
+      "C:\\scans\\b\\page1.jpg",
+      "content://media/1.jpg",
+      "content://media/2.jpg",
+    ]
+    expect(groupImageInputs(inputs)).toEqual({ paths: inputs, documents: {} })
+
+    const windows = groupImageInputs(["C:\\scans\\a\\page2.jpg", "C:\\scans\\a\\page1.jpg"])
+    expect(windows.paths).toEqual(["C:\\scans\\a\\a"])
+    expect(windows.documents["C:\\scans\\a\\a"]).toEqual([
+      "C:\\scans\\a\\page1.jpg",
+      "C:\\scans\\a\\page2.jpg",
+    ])
+  })
+})
@@ -0,0 +1,51 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+  Channel: class {
+    onmessage: (message: unknown) => void = () => {}
+  },
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { subscribeJobEvents, type JobChannelEvent } from "../jobChannel"
+
+describe("jobChannel", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("passes a job's events to the handler until unsubscribed", async () => {
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    let channel: any = null
+    vi.mocked(invoke).mockImplementation(async (cmd: string, args?: unknown) => {
+      if (cmd === "subscribe_job_events") {
+        channel = (args as { onEvent: unknown }).onEvent
+        return 3
+      }
+      return true
+    })
+    const received: JobChannelEvent[] = []
+
+    const unsubscribe = await subscribeJobEvents("job-1", (event) => received.push(event))
+    channel.onmessage({ event: "job-report", payload: { jobId: "job-1", pageCount: 4 } })
+    await unsubscribe()
+
+    expect(invoke).toHaveBeenCalledWith("subscribe_job_events", { jobId: "job-1", onEvent: channel })
+    expect(received).toEqual([{ event: "job-report", payload: { jobId: "job-1", pageCount: 4 } }])
+    expect(invoke).toHaveBeenCalledWith("unsubscribe_job_events", { subscription: 3 })
+  })
+
+  it("does not fail when unsubscribing fails", async () => {
+    vi.mocked(invoke).mockImplementation(async (cmd: string) => {
+      if (cmd === "subscribe_job_events") return 1
+      throw new Error("window closed")
+    })
+    const consoleError = vi.spyOn(console, "error").mockImplementation(() => {})
+
+    const unsubscribe = await subscribeJobEvents("job-1", () => {})
+    await expect(unsubscribe()).resolves.toBeUndefined()
+    expect(consoleError).toHaveBeenCalled()
+    consoleError.mockRestore()
+  })
+})
@@ -0,0 +1,49 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { getJobDetails, uploadedBytesPerPage, type JobDetails } from "../jobDetails"
+
+function details(overrides: Partial<JobDetails> = {}): JobDetails {
+  return {
+    jobId: "job-1",
+    sourcePath: "/books/kitab.pdf",
+    outputPath: "/out/kitab",
+    formats: ["txt"],
+    pageCount: 4,
+    createdAt: 1700000000,
+    updatedAt: 1700000000,
+    running: false,
+    tags: [],
+    note: null,
+    usage: {
+      cpuMs: 5200,
+      peakMemoryBytes: 64 * 1024 * 1024,
+      bytesUploaded: 2_000_002,
+      bytesDownloaded: 8_000,
+      driveApiCalls: 14,
+    },
+    ...overrides,
+  }
+}
+
+describe("jobDetails", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("reads a job's details", async () => {
+    vi.mocked(invoke).mockResolvedValueOnce(details())
+    await expect(getJobDetails("job-1")).resolves.toEqual(details())
+    expect(invoke).toHaveBeenCalledWith("get_job_details", { jobId: "job-1" })
+  })
+
+  it("averages uploaded bytes per page", () => {
+    expect(uploadedBytesPerPage(details())).toBe(500_001)
+    expect(uploadedBytesPerPage(details({ pageCount: 0 }))).toBeNull()
+    expect(uploadedBytesPerPage(details({ usage: null }))).toBeNull()
+  })
+})
@@ -0,0 +1,39 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { getJobEvents, logJobEvent } from "../jobEvents"
+
+describe("jobEvents", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("records events for a job", async () => {
+    await logJobEvent("job-1", "skip", "Duplicate of page 1", 3)
+    expect(invoke).toHaveBeenCalledWith("record_job_event", {
+      jobId: "job-1",
+      kind: "skip",
+      page: 3,
+      message: "Duplicate of page 1",
+    })
+  })
+
+  it("ignores events without a job and database errors", async () => {
+    await logJobEvent(undefined, "stage", "ocr")
+    expect(invoke).not.toHaveBeenCalled()
+
+    vi.mocked(invoke).mockRejectedValueOnce(new Error("Job database error"))
+    await expect(logJobEvent("job-1", "stage", "ocr")).resolves.toBeUndefined()
+  })
+
+  it("loads a job's events", async () => {
+    const events = [{ atMs: 1, kind: "stage", message: "ocr" }]
+    vi.mocked(invoke).mockResolvedValueOnce(events)
+    expect(await getJobEvents("job-1")).toEqual(events)
+    expect(invoke).toHaveBeenCalledWith("get_job_events", { jobId: "job-1" })
+  })
+})
@@ -0,0 +1,49 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { getJobLabels, searchJobsByTag, setJobNote, setJobTags } from "../jobLabels"
+
+describe("jobLabels", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("sets tags and returns them as stored", async () => {
+    vi.mocked(invoke).mockResolvedValueOnce(["waqf collection"])
+    await expect(setJobTags("job-1", [" Waqf Collection "])).resolves.toEqual(["waqf collection"])
+    expect(invoke).toHaveBeenCalledWith("set_job_tags", {
+      jobId: "job-1",
+      tags: [" Waqf Collection "],
+    })
+  })
+
+  it("sets and reads a note", async () => {
+    vi.mocked(invoke).mockResolvedValueOnce(undefined)
+    await setJobNote("job-1", "Second volume")
+    expect(invoke).toHaveBeenCalledWith("set_job_note", { jobId: "job-1", note: "Second volume" })
+
+    vi.mocked(invoke).mockResolvedValueOnce({ tags: ["fiqh"], note: "Second volume" })
+    await expect(getJobLabels("job-1")).resolves.toEqual({ tags: ["fiqh"], note: "Second volume" })
+    expect(invoke).toHaveBeenLastCalledWith("get_job_labels", { jobId: "job-1" })
+  })
+
+  it("searches jobs by tag", async () => {
+    const jobs = [
+      {
+        jobId: "job-1",
+        sourcePath: "/books/kitab.pdf",
+        outputPath: "/out/kitab",
+        tags: ["fiqh"],
+        note: null,
+        updatedAt: 1700000000,
+      },
+    ]
+    vi.mocked(invoke).mockResolvedValueOnce(jobs)
+    await expect(searchJobsByTag("fiqh")).resolves.toEqual(jobs)
+    expect(invoke).toHaveBeenCalledWith("search_jobs_by_tag", { tag: "fiqh" })
+  })
+})
@@ -0,0 +1,40 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { appendLiveTxt, finishLiveTxt, startLiveTxt } from "../liveTxt"
+
+describe("liveTxt", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("starts, appends to and finishes the live file of an output", async () => {
+    await startLiveTxt("/out/book")
+    await appendLiveTxt("/out/book", 3, "text")
+    await finishLiveTxt("/out/book", "final")
+
+    expect(invoke).toHaveBeenNthCalledWith(1, "start_live_txt", { outputPath: "/out/book" })
+    expect(invoke).toHaveBeenNthCalledWith(2, "append_live_txt", {
+      outputPath: "/out/book",
+      page: 3,
+      text: "text",
+    })
+    expect(invoke).toHaveBeenNthCalledWith(3, "finish_live_txt", {
+      outputPath: "/out/book",
+      text: "final",
+    })
+  })
+
+  it("never fails a job while streaming, but does when finishing", async () => {
+    vi.spyOn(console, "warn").mockImplementation(() => {})
+    vi.mocked(invoke).mockRejectedValue(new Error("disk full"))
+
+    await expect(startLiveTxt("/out/book")).resolves.toBeUndefined()
+    await expect(appendLiveTxt("/out/book", 1, "text")).resolves.toBeUndefined()
+    await expect(finishLiveTxt("/out/book", null)).rejects.toThrow("disk full")
+  })
+})
@@ -0,0 +1,54 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import {
+  freedBytes,
+  getStorageUsage,
+  runMaintenanceNow,
+  setRetentionPolicy,
+  toMegabytes,
+} from "../maintenance"
+
+describe("maintenance", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("shows sizes in megabytes with one decimal", () => {
+    expect(toMegabytes(0)).toBe(0)
+    expect(toMegabytes(1024 * 1024)).toBe(1)
+    expect(toMegabytes(1.25 * 1024 * 1024)).toBe(1.3)
+  })
+
+  it("adds up what every store freed", () => {
+    expect(
+      freedBytes({
+        stores: [
+          { store: "workspace", removed: 0, freedBytes: 0, skipped: true },
+          { store: "cache", removed: 2, freedBytes: 1500, skipped: false },
+          { store: "logs", removed: 1, freedBytes: 500, skipped: false },
+        ],
+      }),
+    ).toBe(2000)
+  })
+
+  it("reads usage and runs maintenance through the backend", async () => {
+    vi.mocked(invoke).mockResolvedValueOnce([]).mockResolvedValueOnce({ stores: [] })
+    await expect(getStorageUsage()).resolves.toEqual([])
+    await expect(runMaintenanceNow()).resolves.toEqual({ stores: [] })
+    expect(invoke).toHaveBeenNthCalledWith(1, "get_storage_usage")
+    expect(invoke).toHaveBeenNthCalledWith(2, "run_maintenance_now")
+  })
+
+  it("patches only the changed store's policy", async () => {
+    vi.mocked(invoke).mockResolvedValueOnce({})
+    await setRetentionPolicy("logs", { maxAgeDays: 14 })
+    expect(invoke).toHaveBeenCalledWith("update_settings", {
+      patch: { retention: { logs: { maxAgeDays: 14 } } },
+    })
+  })
+})
@@ -0,0 +1,46 @@
+import { describe, it, expect } from "vitest"
+import { naturalCompare, orderFiles, toAsciiDigits } from "../naturalSort"
+import sharedVectors from "../../../src-tauri/tests/fixtures/natural_sort.json"
+
+describe("naturalSort", () => {
+  it("orders numbers by value", () => {
+    const names = ["page10.png", "Page2.png", "page1.png"]
+    expect(names.sort((a, b) => naturalCompare(a, b))).toEqual([
+      "page1.png",
+      "Page2.png",
+      "page10.png",
+    ])
+  })
+
+  it("reads Arabic-Indic digits as numbers", () => {
+    expect(toAsciiDigits("صفحة ١٠ و ۳")).toBe("صفحة 10 و 3")
+    const names = ["صفحة ١٠.png", "صفحة ۳.png", "صفحة 2.png"]
+    expect(names.sort((a, b) => naturalCompare(a, b))).toEqual([
+      "صفحة 2.png",
+      "صفحة ۳.png",
+      "صفحة ١٠.png",
+    ])
+  })
+
+  it("compares paths folder by folder", () => {
+    expect(naturalCompare("/in/part2/a.pdf", "/in/part10/a.pdf")).toBeLessThan(0)
+    expect(naturalCompare("/in/b.pdf", "/in/a/z.pdf")).toBeGreaterThan(0)
+    expect(naturalCompare("/in/a.pdf", "/in/a.pdf")).toBe(0)
+  })
+
+  it("orders the vectors shared with natural_cmp in dropped.rs", () => {
+    for (const expected of sharedVectors) {
+      expect([...expected].reverse().sort(naturalCompare)).toEqual(expected)
+    }
+  })
+
+  it("puts manually ordered files first", () => {
+    const files = ["/in/page10.png", "/in/page2.png", "/in/cover.png", "/in/page1.png"]
+    expect(orderFiles(files, ["/in/cover.png", "/in/missing.png"])).toEqual([
+      "/in/cover.png",
+      "/in/page1.png",
+      "/in/page2.png",
+      "/in/page10.png",
+    ])
+  })
+})
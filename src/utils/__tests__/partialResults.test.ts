@@ -0,0 +1,59 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import {
+  assemblePartialResults,
+  getPartialResults,
+  recordJobStop,
+  recordPartialPage,
+} from "../partialResults"
+
+describe("partialResults", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("records recognized pages for a job", async () => {
+    await recordPartialPage("job-1", 3, "text")
+    expect(invoke).toHaveBeenCalledWith("record_partial_page", {
+      jobId: "job-1",
+      page: 3,
+      text: "text",
+    })
+  })
+
+  it("ignores pages without a job and database errors", async () => {
+    await recordPartialPage(undefined, 1, "text")
+    expect(invoke).not.toHaveBeenCalled()
+
+    vi.mocked(invoke).mockRejectedValueOnce(new Error("Job database error"))
+    await expect(recordPartialPage("job-1", 1, "text")).resolves.toBeUndefined()
+    vi.mocked(invoke).mockRejectedValueOnce(new Error("Job database error"))
+    await expect(
+      recordJobStop("job-1", {
+        reason: "cancelled",
+        message: "Processing cancelled",
+        sourcePath: "/books/a.pdf",
+        outputPath: "/books/a",
+      }),
+    ).resolves.toBeUndefined()
+  })
+
+  it("loads and assembles a stopped job's pages", async () => {
+    const partial = { jobId: "job-1", stop: null, pages: [{ page: 1, text: "a" }], missingPages: [] }
+    vi.mocked(invoke).mockResolvedValueOnce(partial)
+    expect(await getPartialResults("job-1")).toEqual(partial)
+    expect(invoke).toHaveBeenCalledWith("get_partial_results", { jobId: "job-1" })
+
+    await assemblePartialResults("job-1", ["txt"], "\n")
+    expect(invoke).toHaveBeenCalledWith("assemble_partial_results", {
+      jobId: "job-1",
+      formats: ["txt"],
+      pageSeparator: "\n",
+    })
+  })
+})
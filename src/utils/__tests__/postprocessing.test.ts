@@ -0,0 +1,67 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import {
+  MAX_PIPELINE_STEPS,
+  applyPostprocessing,
+  newStep,
+  previewPostprocessing,
+  syncPostprocessing,
+  validPipeline,
+} from "../postprocessing"
+
+describe("postprocessing", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("creates steps with default parameters", () => {
+    expect(newStep("reflowParagraphs")).toEqual({ step: "reflowParagraphs" })
+    expect(newStep("quranVerses")).toEqual({ step: "quranVerses", script: "uthmani" })
+    expect(newStep("replace")).toEqual({ step: "replace", find: "", replace: "" })
+  })
+
+  it("keeps valid steps in order and drops the rest", () => {
+    expect(
+      validPipeline([
+        { step: "unifyLetters", extra: 1 },
+        { step: "quranVerses", script: "kufic" },
+        { step: "stripDiacritics", keepShadda: "yes" },
+        { step: "replace", find: "ـ", replace: "" },
+        null,
+        "reflowParagraphs",
+      ]),
+    ).toEqual([
+      { step: "unifyLetters" },
+      { step: "stripDiacritics", keepShadda: false },
+      { step: "replace", find: "ـ", replace: "" },
+    ])
+    expect(validPipeline("all")).toEqual([])
+    expect(validPipeline(Array(40).fill({ step: "unifyLetters" }))).toHaveLength(
+      MAX_PIPELINE_STEPS,
+    )
+  })
+
+  it("talks to the backend", async () => {
+    const pipeline = [newStep("normalizeWhitespace")]
+    vi.mocked(invoke).mockResolvedValue(undefined)
+    await syncPostprocessing(pipeline)
+    await applyPostprocessing(["page"], pipeline)
+    await previewPostprocessing("sample", pipeline)
+    expect(invoke).toHaveBeenNthCalledWith(1, "update_settings", {
+      patch: { postprocessing: pipeline },
+    })
+    expect(invoke).toHaveBeenNthCalledWith(2, "apply_postprocessing", {
+      pages: ["page"],
+      pipeline,
+    })
+    expect(invoke).toHaveBeenNthCalledWith(3, "preview_postprocessing", {
+      pageText: "sample",
+      config: pipeline,
+    })
+  })
+})
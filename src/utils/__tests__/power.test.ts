@@ -0,0 +1,49 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+import { setActivePinia, createPinia } from "pinia"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { useProcessingStore } from "../../stores/processing"
+import { useToastStore } from "../../stores/toast"
+import { applyPowerStatus, syncBackgroundMode, type PowerStatus } from "../power"
+
+function status(paused: boolean, batteryPercent: number | null = 12): PowerStatus {
+  return { onBattery: paused, batteryPercent, backgroundMode: true, paused }
+}
+
+describe("power", () => {
+  beforeEach(() => {
+    setActivePinia(createPinia())
+    vi.clearAllMocks()
+  })
+
+  it("sends background mode to the backend settings", async () => {
+    vi.mocked(invoke).mockResolvedValueOnce({})
+    await syncBackgroundMode(true, 30)
+    expect(invoke).toHaveBeenCalledWith("update_settings", {
+      patch: { backgroundMode: true, batteryPausePercent: 30 },
+    })
+  })
+
+  it("pauses processing on low battery and resumes when plugged in", () => {
+    const processingStore = useProcessingStore()
+    processingStore.startProcessing(["/book.pdf"], "/output")
+
+    applyPowerStatus(status(true))
+    expect(processingStore.isPowerPaused).toBe(true)
+    expect(useToastStore().toasts.map((t) => t.messageKey)).toContain("toast.batteryPaused")
+
+    applyPowerStatus(status(false))
+    expect(processingStore.isPowerPaused).toBe(false)
+    expect(useToastStore().toasts.map((t) => t.messageKey)).toContain("toast.batteryResumed")
+  })
+
+  it("ignores repeated statuses", () => {
+    applyPowerStatus(status(true))
+    applyPowerStatus(status(true, 10))
+    expect(useToastStore().toasts).toHaveLength(1)
+  })
+})
@@ -0,0 +1,66 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+import { setActivePinia, createPinia } from "pinia"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { useProcessingStore } from "../../stores/processing"
+import { useToastStore } from "../../stores/toast"
+import { applyQuotaStatus, setDailyBudget, warnQuota, type QuotaStatus } from "../quota"
+
+function status(pages: number, paused = pages >= 100): QuotaStatus {
+  return {
+    backend: "googleDrive",
+    budget: { maxPages: 100, maxApiCalls: 0 },
+    pages,
+    apiCalls: pages * 3,
+    periodStart: 1710057600,
+    resetsAt: 1710144000,
+    used: pages / 100,
+    warning: pages >= 80,
+    paused,
+  }
+}
+
+describe("quota", () => {
+  beforeEach(() => {
+    setActivePinia(createPinia())
+    vi.clearAllMocks()
+  })
+
+  it("sends a budget to the backend settings", async () => {
+    vi.mocked(invoke).mockResolvedValueOnce({})
+    await setDailyBudget("googleDrive", { maxPages: 500 })
+    expect(invoke).toHaveBeenCalledWith("update_settings", {
+      patch: { quota: { googleDrive: { maxPages: 500 } } },
+    })
+  })
+
+  it("pauses processing when the budget is used up and resumes after the reset", () => {
+    const processingStore = useProcessingStore()
+    processingStore.startProcessing(["/book.pdf"], "/output")
+
+    applyQuotaStatus(status(100))
+    expect(processingStore.isQuotaPaused).toBe(true)
+    expect(useToastStore().toasts.map((t) => t.messageKey)).toContain("toast.quotaPaused")
+
+    applyQuotaStatus(status(0))
+    expect(processingStore.isQuotaPaused).toBe(false)
+    expect(useToastStore().toasts.map((t) => t.messageKey)).toContain("toast.quotaResumed")
+  })
+
+  it("ignores repeated statuses", () => {
+    applyQuotaStatus(status(100))
+    applyQuotaStatus(status(120))
+    expect(useToastStore().toasts).toHaveLength(1)
+  })
+
+  it("warns with the share used", () => {
+    warnQuota(status(85))
+    const toast = useToastStore().toasts[0]
+    expect(toast.messageKey).toBe("toast.quotaWarning")
+    expect(toast.messageParams?.percent).toBe(85)
+  })
+})
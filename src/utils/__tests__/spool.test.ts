@@ -0,0 +1,53 @@
+import { describe, it, expect, vi, beforeEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { discardSpool, spoolPages, writeSpooledOutputs } from "../spool"
+
+describe("spool", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+  })
+
+  it("spools a shard's pages with their footnotes", async () => {
+    await spoolPages("job-1", 501, ["a", "b"], ["note"])
+
+    expect(invoke).toHaveBeenCalledWith("spool_pages", {
+      jobId: "job-1",
+      firstPage: 501,
+      pages: [
+        { text: "a", footnotes: "note" },
+        { text: "b", footnotes: null },
+      ],
+    })
+  })
+
+  it("writes the outputs from the spool", async () => {
+    vi.mocked(invoke).mockResolvedValueOnce(["/out/book.txt"])
+
+    const paths = await writeSpooledOutputs("job-1", "/out/book", ["txt"], 1200, {
+      pageSeparator: "---",
+    })
+
+    expect(paths).toEqual(["/out/book.txt"])
+    expect(invoke).toHaveBeenCalledWith("write_spooled_outputs", {
+      jobId: "job-1",
+      outputPath: "/out/book",
+      formats: ["txt"],
+      pageCount: 1200,
+      pageSeparator: "---",
+      languages: null,
+      metadata: null,
+    })
+  })
+
+  it("ignores discard failures", async () => {
+    vi.mocked(invoke).mockRejectedValueOnce(new Error("gone"))
+    vi.spyOn(console, "warn").mockImplementation(() => {})
+
+    await expect(discardSpool("job-1")).resolves.toBeUndefined()
+  })
+})
@@ -0,0 +1,75 @@
+import { describe, it, expect, vi, beforeEach, afterEach } from "vitest"
+
+vi.mock("@tauri-apps/api/core", () => ({
+  invoke: vi.fn(),
+}))
+vi.mock("@tauri-apps/api/event", () => ({
+  emit: vi.fn(),
+}))
+
+import { invoke } from "@tauri-apps/api/core"
+import { emit } from "@tauri-apps/api/event"
+import { StallError, reportStall, watchProgress, withStallTimeout } from "../watchdog"
+
+describe("watchdog", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+    vi.useFakeTimers()
+  })
+
+  afterEach(() => {
+    vi.useRealTimers()
+  })
+
+  it("rejects an operation that does not settle in time", async () => {
+    const stuck = withStallTimeout(new Promise(() => {}), 1000)
+    const assertion = expect(stuck).rejects.toBeInstanceOf(StallError)
+    await vi.advanceTimersByTimeAsync(1000)
+    await assertion
+  })
+
+  it("passes through an operation that settles in time", async () => {
+    const done = withStallTimeout(Promise.resolve("text"), 1000)
+    await expect(done).resolves.toBe("text")
+    expect(vi.getTimerCount()).toBe(0)
+  })
+
+  it("fires once per idle interval until touched or stopped", async () => {
+    const onStall = vi.fn()
+    const watchdog = watchProgress(1000, onStall)
+
+    await vi.advanceTimersByTimeAsync(900)
+    watchdog.touch()
+    await vi.advanceTimersByTimeAsync(900)
+    expect(onStall).not.toHaveBeenCalled()
+
+    await vi.advanceTimersByTimeAsync(100)
+    expect(onStall).toHaveBeenCalledWith(1000)
+    await vi.advanceTimersByTimeAsync(1000)
+    expect(onStall).toHaveBeenCalledTimes(2)
+
+    watchdog.stop()
+    await vi.advanceTimersByTimeAsync(5000)
+    expect(onStall).toHaveBeenCalledTimes(2)
+  })
+
+  it("reports a stall as an event and in the job log", async () => {
+    vi.spyOn(console, "warn").mockImplementation(() => {})
+    const stall = {
+      jobId: "job-1",
+      stage: "ocr" as const,
+      page: 4,
+      idleMs: 600000,
+      action: "restarted" as const,
+    }
+    await reportStall(stall)
+
+    expect(emit).toHaveBeenCalledWith("job-stalled", stall)
+    expect(invoke).toHaveBeenCalledWith("record_job_event", {
+      jobId: "job-1",
+      kind: "error",
+      page: 4,
+      message: "ocr stalled for 600s (restarted)",
+    })
+  })
+})
@@ -0,0 +1,67 @@
+import { isTahweelError } from "./errors"
+
+// Drive reports per-user quota trouble as 403 with one of these reasons
+const RATE_LIMIT_REASON = /rateLimitExceeded|userRateLimitExceeded|quotaExceeded/i
+
+/**
+ * Whether an error means Drive wants fewer requests: a 429, or a 403 with a
+ * rate-limit reason
+ */
+export function isThrottleError(error: unknown): boolean {
+  if (!isTahweelError(error)) return false
+  if (error.kind === "rateLimited") return true
+  return error.kind === "api" && RATE_LIMIT_REASON.test(error.details ?? "")
+}
+
+/**
+ * AIMD concurrency controller: the limit grows by one after a round of
+ * successes (as many as the current limit) and halves on a throttling error,
+ * at most once per round so one burst of 429s is counted once.
+ * It never goes above `max` (the `driveConcurrency` setting) or below 1.
+ */
+export class AdaptiveConcurrency {
+  private limit: number
+  private ceiling: number
+  private successes = 0
+  // Completions since the last decrease; a decrease waits for a full round
+  private sinceDecrease = Infinity
+
+  constructor(max: number) {
+    this.ceiling = Math.max(1, max)
+    this.limit = this.ceiling
+  }
+
+  /** Pages allowed in flight right now */
+  get current(): number {
+    return this.limit
+  }
+
+  get max(): number {
+    return this.ceiling
+  }
+
+  /** Follow a changed setting; the current limit is lowered if it is above it */
+  setMax(max: number): void {
+    this.ceiling = Math.max(1, max)
+    this.limit = Math.min(this.limit, this.ceiling)
+  }
+
+  /** A page went through without throttling */
+  recordSuccess(): void {
+    this.sinceDecrease++
+    this.successes++
+    if (this.successes >= this.limit) {
+      this.successes = 0
+      this.limit = Math.min(this.limit + 1, this.ceiling)
+    }
+  }
+
+  /** Drive throttled a request */
+  recordThrottle(): void {
+    this.sinceDecrease++
+    if (this.sinceDecrease <= this.limit) return
+    this.limit = Math.max(1, Math.floor(this.limit / 2))
+    this.successes = 0
+    this.sinceDecrease = 0
+  }
+}
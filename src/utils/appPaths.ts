@@ -0,0 +1,19 @@
+import { invoke } from "@tauri-apps/api/core"
+
+/** Folders of the app-data layout for the active profile (see app_paths.rs) */
+export interface AppPaths {
+  root: string
+  config: string
+  cache: string
+  workspaces: string
+  logs: string
+  db: string
+  profile: string
+}
+
+/** Areas of the layout, in display order */
+export const APP_PATH_AREAS = ["config", "cache", "workspaces", "logs", "db"] as const
+
+export function getAppPaths(): Promise<AppPaths> {
+  return invoke<AppPaths>("get_app_paths")
+}
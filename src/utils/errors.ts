@@ -0,0 +1,32 @@
+/** Error shape returned by backend commands (see `src-tauri/src/error.rs`) */
+export interface TahweelError {
+  kind: string
+  messageKey: string
+  /** Localized in the backend `language` setting */
+  message: string
+  /** Technical text (API response, OS error) for logs and bug reports */
+  details: string
+  retriable: boolean
+}
+
+export function isTahweelError(error: unknown): error is TahweelError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    "kind" in error &&
+    "messageKey" in error &&
+    "message" in error
+  )
+}
+
+/** Readable message for anything thrown by `invoke` or by frontend code */
+export function getErrorMessage(error: unknown): string {
+  if (isTahweelError(error)) return error.message
+  return String(error)
+}
+
+/** Technical detail for logs; the message itself for non-backend errors */
+export function getErrorDetails(error: unknown): string {
+  if (isTahweelError(error)) return error.details ?? error.message
+  return String(error)
+}
@@ -0,0 +1,82 @@
+import { invoke } from "@tauri-apps/api/core"
+
+/** Time per page from earlier jobs (see `ThroughputEstimate` in jobs.rs) */
+export interface ThroughputEstimate {
+  msPerPage: number
+  /** Jobs the average is built from */
+  samples: number
+  /** DPI of those jobs; 0 for automatic DPI */
+  dpi: number
+}
+
+// The history counts as this many pages of the current job: early on the
+// estimate follows the history, and the job's own pace takes over as pages finish
+const PRIOR_PAGES = 5
+
+/**
+ * Remaining-time estimate for one stage of a job, seeded with the backend's
+ * throughput in earlier jobs and refined by the pages finished so far
+ */
+export class EtaModel {
+  private completed = 0
+  private elapsedMs = 0
+
+  constructor(
+    private totalPages: number,
+    private seedMsPerPage: number | null,
+  ) {}
+
+  /** `completed` pages finished `elapsedMs` after the stage started */
+  update(completed: number, elapsedMs: number): void {
+    this.completed = completed
+    this.elapsedMs = elapsedMs
+  }
+
+  /** Expected time per page, or null before anything is known */
+  msPerPage(): number | null {
+    const observed = this.completed > 0 ? this.elapsedMs / this.completed : null
+    if (this.seedMsPerPage === null) return observed
+    if (observed === null) return this.seedMsPerPage
+    const weight = this.completed / (this.completed + PRIOR_PAGES)
+    return weight * observed + (1 - weight) * this.seedMsPerPage
+  }
+
+  /** Milliseconds until the remaining pages are done, or null when unknown */
+  remainingMs(): number | null {
+    const perPage = this.msPerPage()
+    if (perPage === null) return null
+    return Math.round(Math.max(0, this.totalPages - this.completed) * perPage)
+  }
+}
+
+/** Throughput of `backend` in earlier jobs near `dpi`; null without history. Best effort. */
+export async function getThroughputEstimate(
+  backend: string,
+  dpi: number,
+): Promise<ThroughputEstimate | null> {
+  try {
+    return await invoke<ThroughputEstimate | null>("get_throughput_estimate", { backend, dpi })
+  } catch {
+    return null
+  }
+}
+
+/** Add a finished stage's pace to the history. Best effort. */
+export async function recordThroughput(
+  backend: string,
+  dpi: number,
+  pages: number,
+  elapsedMs: number,
+): Promise<void> {
+  if (pages <= 0) return
+  try {
+    await invoke("record_job_throughput", {
+      backend,
+      dpi,
+      pages,
+      elapsedMs: Math.round(elapsedMs),
+    })
+  } catch {
+    // Ignore job database errors
+  }
+}
@@ -0,0 +1,44 @@
+import { invoke } from "@tauri-apps/api/core"
+
+export type HistoryFormat = "csv" | "json"
+
+/** Milliseconds since the Unix epoch; `toMs` is exclusive, a missing end is open */
+export interface DateRange {
+  fromMs?: number
+  toMs?: number
+}
+
+/** Result of `export_history` */
+export interface ExportedHistory {
+  path: string
+  documents: number
+  pages: number
+}
+
+function monthStart(month: string, offset = 0): number | undefined {
+  const match = /^(\d{4})-(\d{2})$/.exec(month)
+  if (!match) return undefined
+  return Date.UTC(Number(match[1]), Number(match[2]) - 1 + offset, 1)
+}
+
+/**
+ * Range covering the months `from` through `to` (`YYYY-MM`, as given by
+ * `<input type="month">`, in UTC like the export); blank or invalid ends are open
+ */
+export function monthRange(from: string, to: string): DateRange {
+  const range: DateRange = {}
+  const fromMs = monthStart(from)
+  const toMs = monthStart(to, 1)
+  if (fromMs !== undefined) range.fromMs = fromMs
+  if (toMs !== undefined) range.toMs = toMs
+  return range
+}
+
+/** Write the documents and pages processed per month to `outputPath` */
+export function exportHistory(
+  format: HistoryFormat,
+  outputPath: string,
+  dateRange: DateRange = {},
+): Promise<ExportedHistory> {
+  return invoke<ExportedHistory>("export_history", { format, dateRange, outputPath })
+}
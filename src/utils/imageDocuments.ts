@@ -0,0 +1,50 @@
+import { naturalCompare } from "./naturalSort"
+
+const IMAGE_EXTENSIONS = [".jpg", ".jpeg", ".png"]
+
+/** Inputs after combining loose images (see groupImageInputs) */
+export interface GroupedInputs {
+  /** One path per job, in the order their first input appeared */
+  paths: string[]
+  /** Images of each combined document, by its job path, in page order */
+  documents: Record<string, string[]>
+}
+
+function isImage(path: string): boolean {
+  const lower = path.toLowerCase()
+  return IMAGE_EXTENSIONS.some((ext) => lower.endsWith(ext))
+}
+
+/**
+ * Combine loose page images into one document per folder: two or more images
+ * in the same folder become a job named after the folder (`<dir>/<dirName>`,
+ * like a scan), their pages naturally ordered. PDFs, single images and content
+ * URIs stay jobs of their own.
+ */
+export function groupImageInputs(filePaths: string[]): GroupedInputs {
+  const folders = new Map<string, string[]>()
+  for (const path of filePaths) {
+    const cut = Math.max(path.lastIndexOf("/"), path.lastIndexOf("\\"))
+    if (cut < 0 || path.startsWith("content://") || !isImage(path)) continue
+    const dir = path.slice(0, cut)
+    const images = folders.get(dir)
+    if (images) {
+      images.push(path)
+    } else {
+      folders.set(dir, [path])
+    }
+  }
+
+  const documents: Record<string, string[]> = {}
+  const grouped = new Map<string, string>()
+  for (const [dir, images] of folders) {
+    if (images.length < 2) continue
+    const separator = dir.includes("\\") && !dir.includes("/") ? "\\" : "/"
+    const folderName = dir.split(/[\\/]/).pop() || "images"
+    const jobPath = `${dir}${separator}${folderName}`
+    documents[jobPath] = [...images].sort(naturalCompare)
+    for (const image of images) grouped.set(image, jobPath)
+  }
+  const paths = [...new Set(filePaths.map((path) => grouped.get(path) ?? path))]
+  return { paths, documents }
+}
@@ -0,0 +1,34 @@
+import { Channel, invoke } from "@tauri-apps/api/core"
+
+/** Events a job's channel carries; each mirrors the global event of that name */
+export type JobChannelEventName = "split-progress" | "stage-timing" | "job-report"
+
+/** One event of a job (see `JobEvent` in job_channels.rs) */
+export interface JobChannelEvent<T = unknown> {
+  event: JobChannelEventName
+  payload: T
+}
+
+/**
+ * Receive the progress, timing and report events of one job only, so jobs
+ * running side by side don't see each other's progress. Resolves to a
+ * function that ends the subscription (best effort).
+ */
+export async function subscribeJobEvents(
+  jobId: string,
+  onEvent: (event: JobChannelEvent) => void,
+): Promise<() => Promise<void>> {
+  const channel = new Channel<JobChannelEvent>()
+  channel.onmessage = onEvent
+  const subscription = await invoke<number>("subscribe_job_events", {
+    jobId,
+    onEvent: channel,
+  })
+  return async () => {
+    try {
+      await invoke("unsubscribe_job_events", { subscription })
+    } catch (error) {
+      console.error("Failed to end the job event subscription:", error)
+    }
+  }
+}
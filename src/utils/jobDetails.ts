@@ -0,0 +1,42 @@
+import { invoke } from "@tauri-apps/api/core"
+import type { OutputFormat } from "@/stores/settings"
+
+/** Resources a job used (see `ResourceUsage` in job_usage.rs) */
+export interface ResourceUsage {
+  /** CPU time of rendering; wall time on platforms without a per-thread clock */
+  cpuMs: number
+  /** Largest estimated memory the pipeline held while the job ran */
+  peakMemoryBytes: number
+  bytesUploaded: number
+  bytesDownloaded: number
+  /** Drive requests: upload and export attempts and conversion status polls */
+  driveApiCalls: number
+}
+
+/** What the job history knows about a job (see `JobDetails` in jobs.rs) */
+export interface JobDetails {
+  jobId: string
+  /** Missing until the job has started or been recorded */
+  sourcePath: string | null
+  outputPath: string | null
+  formats: OutputFormat[]
+  pageCount: number
+  /** Seconds since the Unix epoch; missing for a job that was never recorded */
+  createdAt: number | null
+  updatedAt: number | null
+  running: boolean
+  tags: string[]
+  note: string | null
+  usage: ResourceUsage | null
+}
+
+/** A job's details, including what a running job has used so far */
+export function getJobDetails(jobId: string): Promise<JobDetails> {
+  return invoke<JobDetails>("get_job_details", { jobId })
+}
+
+/** Average bytes sent per page, or null for a job without pages or usage */
+export function uploadedBytesPerPage(details: JobDetails): number | null {
+  if (!details.usage || details.pageCount === 0) return null
+  return Math.round(details.usage.bytesUploaded / details.pageCount)
+}
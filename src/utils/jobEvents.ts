@@ -0,0 +1,37 @@
+import { invoke } from "@tauri-apps/api/core"
+
+/** Kinds of entries in a job's event log (see `JobEventKind` in jobs.rs) */
+export type JobEventKind = "stage" | "retry" | "error" | "skip"
+
+/** One entry of a job's event log */
+export interface JobEvent {
+  /** Milliseconds since the Unix epoch */
+  atMs: number
+  kind: JobEventKind
+  /** 1-based page the event is about, if any */
+  page?: number
+  message: string
+}
+
+/**
+ * Add an entry to a job's event log. Best effort: the pipeline never fails
+ * because its log could not be written.
+ */
+export async function logJobEvent(
+  jobId: string | undefined,
+  kind: JobEventKind,
+  message: string,
+  page?: number,
+): Promise<void> {
+  if (!jobId) return
+  try {
+    await invoke("record_job_event", { jobId, kind, page, message })
+  } catch {
+    // Ignore job database errors
+  }
+}
+
+/** A job's event log (stages, retries, errors, skips) in the order it was written */
+export function getJobEvents(jobId: string): Promise<JobEvent[]> {
+  return invoke<JobEvent[]>("get_job_events", { jobId })
+}
@@ -0,0 +1,41 @@
+import { invoke } from "@tauri-apps/api/core"
+
+/** A job's tags and note (see `JobLabels` in jobs.rs) */
+export interface JobLabels {
+  tags: string[]
+  note: string | null
+}
+
+/** A job in the history carrying a searched tag */
+export interface TaggedJob {
+  jobId: string
+  /** Missing for a job that was tagged but has not finished */
+  sourcePath: string | null
+  outputPath: string | null
+  tags: string[]
+  note: string | null
+  /** Seconds since the Unix epoch the job was last recorded */
+  updatedAt: number | null
+}
+
+/**
+ * Replace a job's tags. Tags are stored trimmed and lowercase, without
+ * duplicates; the stored list is returned.
+ */
+export function setJobTags(jobId: string, tags: string[]): Promise<string[]> {
+  return invoke<string[]>("set_job_tags", { jobId, tags })
+}
+
+/** Set a job's note; a blank note removes it */
+export function setJobNote(jobId: string, note: string): Promise<void> {
+  return invoke("set_job_note", { jobId, note })
+}
+
+export function getJobLabels(jobId: string): Promise<JobLabels> {
+  return invoke<JobLabels>("get_job_labels", { jobId })
+}
+
+/** Jobs carrying `tag`, most recently recorded first */
+export function searchJobsByTag(tag: string): Promise<TaggedJob[]> {
+  return invoke<TaggedJob[]>("search_jobs_by_tag", { tag })
+}
@@ -0,0 +1,35 @@
+import { invoke } from "@tauri-apps/api/core"
+
+/**
+ * Start an empty `<outputBasePath>.partial.txt` for the `liveTxt` setting.
+ * Best effort: a job never fails because its live TXT could not be written.
+ */
+export async function startLiveTxt(outputBasePath: string): Promise<void> {
+  try {
+    await invoke("start_live_txt", { outputPath: outputBasePath })
+  } catch (error) {
+    console.warn("Failed to start live TXT:", error)
+  }
+}
+
+/** Append a recognized page (1-based) to the live TXT; best effort */
+export async function appendLiveTxt(
+  outputBasePath: string,
+  page: number,
+  text: string,
+): Promise<void> {
+  try {
+    await invoke("append_live_txt", { outputPath: outputBasePath, page, text })
+  } catch {
+    // The final TXT is written anyway
+  }
+}
+
+/**
+ * Replace the live TXT with the final output: `text` (null when TXT is not a
+ * selected format) is written to `<outputBasePath>.txt` at once, and the live
+ * file is removed
+ */
+export async function finishLiveTxt(outputBasePath: string, text: string | null): Promise<void> {
+  await invoke("finish_live_txt", { outputPath: outputBasePath, text })
+}
@@ -0,0 +1,60 @@
+import { invoke } from "@tauri-apps/api/core"
+
+/** Stores with a retention policy (see maintenance.rs) */
+export type Store = "workspace" | "cache" | "history" | "logs"
+
+export const STORES: Store[] = ["workspace", "cache", "history", "logs"]
+
+/** 0 turns a limit off */
+export interface RetentionPolicy {
+  maxAgeDays: number
+  maxSizeMb: number
+}
+
+export interface StoreUsage {
+  store: Store
+  path: string
+  bytes: number
+  /** Top-level files and folders; jobs, for the history */
+  entries: number
+  policy: RetentionPolicy
+}
+
+export interface StoreCleanup {
+  store: Store
+  removed: number
+  freedBytes: number
+  /** Not cleaned this time (the workspace while jobs run) */
+  skipped: boolean
+}
+
+export interface MaintenanceReport {
+  stores: StoreCleanup[]
+}
+
+/** Size in MB with one decimal, for showing usage next to the MB limit */
+export function toMegabytes(bytes: number): number {
+  return Math.round((bytes / (1024 * 1024)) * 10) / 10
+}
+
+/** Bytes freed across all stores */
+export function freedBytes(report: MaintenanceReport): number {
+  return report.stores.reduce((total, cleanup) => total + cleanup.freedBytes, 0)
+}
+
+export function getStorageUsage(): Promise<StoreUsage[]> {
+  return invoke<StoreUsage[]>("get_storage_usage")
+}
+
+/** Enforce the retention policies now instead of at the next periodic run */
+export function runMaintenanceNow(): Promise<MaintenanceReport> {
+  return invoke<MaintenanceReport>("run_maintenance_now")
+}
+
+/** Change the retention policy of one store; the backend clamps the limits */
+export async function setRetentionPolicy(
+  store: Store,
+  policy: Partial<RetentionPolicy>,
+): Promise<void> {
+  await invoke("update_settings", { patch: { retention: { [store]: policy } } })
+}
@@ -0,0 +1,77 @@
+// Arabic-Indic (U+0660-0669) and Extended Arabic-Indic (U+06F0-06F9) digits
+const ARABIC_DIGITS = /[٠-٩۰-۹]/g
+
+// One run of digits (any of the above or ASCII) or of other characters
+const CHUNKS = /[0-9٠-٩۰-۹]+|[^0-9٠-٩۰-۹]+/g
+const DIGIT_RUN = /^[0-9٠-٩۰-۹]/
+
+/** Replace Arabic-Indic digits with ASCII ones so `صفحة ١٠` sorts as `صفحة 10` */
+export function toAsciiDigits(value: string): string {
+  return value.replace(ARABIC_DIGITS, (digit) => String(digit.charCodeAt(0) & 0xf))
+}
+
+/** Compare by Unicode code point (`<` compares UTF-16 code units) */
+function compareCodePoints(a: string, b: string): number {
+  const aPoints = Array.from(a, (char) => char.codePointAt(0) ?? 0)
+  const bPoints = Array.from(b, (char) => char.codePointAt(0) ?? 0)
+  for (let index = 0; index < Math.min(aPoints.length, bPoints.length); index++) {
+    if (aPoints[index] !== bPoints[index]) return aPoints[index] - bPoints[index]
+  }
+  return aPoints.length - bPoints.length
+}
+
+/** Compare two digit runs by value, whatever digits they are written with */
+function compareNumbers(a: string, b: string): number {
+  const aTrimmed = toAsciiDigits(a).replace(/^0+/, "")
+  const bTrimmed = toAsciiDigits(b).replace(/^0+/, "")
+  if (aTrimmed.length !== bTrimmed.length) return aTrimmed.length - bTrimmed.length
+  return aTrimmed < bTrimmed ? -1 : aTrimmed > bTrimmed ? 1 : 0
+}
+
+/** Compare one folder or file name chunk by chunk */
+function compareSegments(a: string, b: string): number {
+  const aChunks = a.match(CHUNKS) ?? []
+  const bChunks = b.match(CHUNKS) ?? []
+  for (let index = 0; index < Math.min(aChunks.length, bChunks.length); index++) {
+    const [x, y] = [aChunks[index], bChunks[index]]
+    const ordering =
+      DIGIT_RUN.test(x) && DIGIT_RUN.test(y)
+        ? compareNumbers(x, y)
+        : compareCodePoints(x.toLowerCase(), y.toLowerCase())
+    if (ordering !== 0) return ordering
+  }
+  return aChunks.length - bChunks.length
+}
+
+/**
+ * Order names the way people expect: `page2` before `page10`, case-insensitive,
+ * Arabic-Indic digits read as numbers. Paths are compared folder by folder;
+ * exact ties fall back to code points so the order is stable. Same algorithm as
+ * `natural_cmp` in dropped.rs; both are checked against
+ * src-tauri/tests/fixtures/natural_sort.json.
+ */
+export function naturalCompare(a: string, b: string): number {
+  const aParts = a.split(/[\\/]/)
+  const bParts = b.split(/[\\/]/)
+  for (let index = 0; index < Math.min(aParts.length, bParts.length); index++) {
+    const ordering = compareSegments(aParts[index], bParts[index])
+    if (ordering !== 0) return ordering
+  }
+  if (aParts.length !== bParts.length) return aParts.length - bParts.length
+  return compareCodePoints(a, b)
+}
+
+/**
+ * Order discovered input files: the paths in `manualOrder` first, in that order,
+ * then the rest naturally sorted. Entries of `manualOrder` that are not among
+ * `paths` are ignored.
+ */
+export function orderFiles(paths: string[], manualOrder: string[] = []): string[] {
+  const available = new Set(paths)
+  const pinned = [...new Set(manualOrder)].filter((path) => available.has(path))
+  const pinnedSet = new Set(pinned)
+  const rest = paths
+    .filter((path) => !pinnedSet.has(path))
+    .sort(naturalCompare)
+  return [...pinned, ...rest]
+}
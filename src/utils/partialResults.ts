@@ -0,0 +1,110 @@
+import { invoke } from "@tauri-apps/api/core"
+import type { OutputFormat } from "@/stores/settings"
+
+/** Why a job stopped before writing its outputs (see `StopReason` in jobs.rs) */
+export type StopReason = "cancelled" | "failed" | "interrupted"
+
+/** How and where a job stopped midway */
+export interface JobStop {
+  reason: StopReason
+  /** `kind` of the backend error that ended the job, if any */
+  errorKind?: string
+  message: string
+  sourcePath: string
+  /** Output path without extension the job would have written */
+  outputPath: string
+}
+
+/** Pages a job recognized before it stopped (see get_partial_results) */
+export interface PartialResults {
+  jobId: string
+  stop: (JobStop & { atMs: number }) | null
+  pages: Array<{ page: number; text: string }>
+  missingPages: number[]
+}
+
+/** A job an earlier session left running when it was killed or crashed (see take_interrupted_jobs) */
+export interface InterruptedJob {
+  jobId: string
+  sourcePath: string
+  /** Output path without extension the job would have written */
+  outputPath: string
+  /** Pages recognized before the session died */
+  pages: number
+}
+
+/** Result of assemble_partial_results */
+export interface AssembledPartial {
+  paths: string[]
+  missingPages: number[]
+}
+
+/**
+ * Save a page's text as soon as it is recognized (1-based `page`). Best effort,
+ * like the event log: a database error never fails the OCR.
+ */
+export async function recordPartialPage(
+  jobId: string | undefined,
+  page: number,
+  text: string,
+): Promise<void> {
+  if (!jobId) return
+  try {
+    await invoke("record_partial_page", { jobId, page, text })
+  } catch {
+    // Ignore job database errors
+  }
+}
+
+/** Record why a job stopped; best effort */
+export async function recordJobStop(jobId: string, stop: JobStop): Promise<void> {
+  try {
+    await invoke("record_job_stop", { jobId, stop })
+  } catch {
+    // Ignore job database errors
+  }
+}
+
+export function getPartialResults(jobId: string): Promise<PartialResults> {
+  return invoke<PartialResults>("get_partial_results", { jobId })
+}
+
+/** Write `<output>-partial.<ext>` from the pages a stopped job completed */
+export function assemblePartialResults(
+  jobId: string,
+  formats: OutputFormat[],
+  pageSeparator: string,
+): Promise<AssembledPartial> {
+  return invoke<AssembledPartial>("assemble_partial_results", { jobId, formats, pageSeparator })
+}
+
+/**
+ * Mark a job as running, so it is reported as interrupted if the app dies
+ * before `endJob`. Best effort.
+ */
+export async function beginJob(jobId: string, sourcePath: string, outputPath: string): Promise<void> {
+  try {
+    await invoke("begin_job", { jobId, sourcePath, outputPath })
+  } catch {
+    // Ignore job database errors
+  }
+}
+
+/** The job ended, however it went; best effort */
+export async function endJob(jobId: string): Promise<void> {
+  try {
+    await invoke("end_job", { jobId })
+  } catch {
+    // Ignore job database errors
+  }
+}
+
+/** Interrupted jobs found since the last call (also announced by `jobs-interrupted`) */
+export function takeInterruptedJobs(): Promise<InterruptedJob[]> {
+  return invoke<InterruptedJob[]>("take_interrupted_jobs")
+}
+
+/** Drop what an unfinished job kept (its recognized pages and how it stopped) */
+export function discardJob(jobId: string): Promise<void> {
+  return invoke("discard_job", { jobId })
+}
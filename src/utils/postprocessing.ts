@@ -0,0 +1,107 @@
+import { invoke } from "@tauri-apps/api/core"
+
+/** Most steps a pipeline keeps (matches postprocess.rs) */
+export const MAX_PIPELINE_STEPS = 32
+
+export type QuranScript = "uthmani" | "imlaei"
+
+/** One step of a post-processing pipeline with its parameters (see postprocess.rs) */
+export type PostprocessStep =
+  | { step: "normalizeWhitespace" }
+  | { step: "stripDiacritics"; keepShadda?: boolean }
+  | { step: "unifyLetters" }
+  | { step: "removeHeadersFooters" }
+  | { step: "reflowParagraphs" }
+  | { step: "quranVerses"; script: QuranScript }
+  | { step: "replace"; find: string; replace: string }
+
+export type PostprocessStepName = PostprocessStep["step"]
+
+export const POSTPROCESS_STEPS: PostprocessStepName[] = [
+  "normalizeWhitespace",
+  "stripDiacritics",
+  "unifyLetters",
+  "removeHeadersFooters",
+  "reflowParagraphs",
+  "quranVerses",
+  "replace",
+]
+
+/** Result of `apply_postprocessing` */
+export interface PostprocessResult {
+  pages: string[]
+  stripReport: unknown | null
+  quranSubstitutions: unknown[] | null
+}
+
+/** Result of `preview_postprocessing`: the final text and the text after each step */
+export interface PostprocessPreview {
+  text: string
+  stages: Array<{ step: PostprocessStepName; text: string; skipped: boolean }>
+}
+
+/** A new step with default parameters */
+export function newStep(name: PostprocessStepName): PostprocessStep {
+  switch (name) {
+    case "stripDiacritics":
+      return { step: name, keepShadda: false }
+    case "quranVerses":
+      return { step: name, script: "uthmani" }
+    case "replace":
+      return { step: name, find: "", replace: "" }
+    default:
+      return { step: name }
+  }
+}
+
+/** The step with its known parameters, or null when it is not a valid step */
+function validStep(value: unknown): PostprocessStep | null {
+  if (typeof value !== "object" || value === null) return null
+  const step = value as Record<string, unknown>
+  switch (step.step) {
+    case "normalizeWhitespace":
+    case "unifyLetters":
+    case "removeHeadersFooters":
+    case "reflowParagraphs":
+      return { step: step.step }
+    case "stripDiacritics":
+      return { step: step.step, keepShadda: step.keepShadda === true }
+    case "quranVerses":
+      return step.script === "uthmani" || step.script === "imlaei"
+        ? { step: step.step, script: step.script }
+        : null
+    case "replace":
+      return typeof step.find === "string" && typeof step.replace === "string"
+        ? { step: step.step, find: step.find, replace: step.replace }
+        : null
+    default:
+      return null
+  }
+}
+
+/** Valid steps, in order, up to `MAX_PIPELINE_STEPS`; anything else is dropped */
+export function validPipeline(value: unknown): PostprocessStep[] {
+  if (!Array.isArray(value)) return []
+  return value.flatMap((step) => validStep(step) ?? []).slice(0, MAX_PIPELINE_STEPS)
+}
+
+/** Hand the pipeline to the backend, which also runs it on streamed outputs */
+export async function syncPostprocessing(pipeline: PostprocessStep[]): Promise<void> {
+  await invoke("update_settings", { patch: { postprocessing: pipeline } })
+}
+
+/** Run `pipeline` over a document's pages before its outputs are written */
+export function applyPostprocessing(
+  pages: string[],
+  pipeline: PostprocessStep[],
+): Promise<PostprocessResult> {
+  return invoke<PostprocessResult>("apply_postprocessing", { pages, pipeline })
+}
+
+/** What `config` does to one page, for the live preview in settings */
+export function previewPostprocessing(
+  pageText: string,
+  config: PostprocessStep[],
+): Promise<PostprocessPreview> {
+  return invoke<PostprocessPreview>("preview_postprocessing", { pageText, config })
+}
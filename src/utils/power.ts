@@ -0,0 +1,37 @@
+import { invoke } from "@tauri-apps/api/core"
+import { useProcessingStore } from "../stores/processing"
+import { useToastStore } from "../stores/toast"
+
+/** Payload of the `power-status` event and `get_power_status` (see power.rs) */
+export interface PowerStatus {
+  onBattery: boolean
+  batteryPercent: number | null
+  backgroundMode: boolean
+  /** Work is held back until the machine is plugged in or charged */
+  paused: boolean
+}
+
+/** Hand background mode to the backend, which renders and pauses accordingly */
+export async function syncBackgroundMode(
+  backgroundMode: boolean,
+  batteryPausePercent: number,
+): Promise<void> {
+  await invoke("update_settings", { patch: { backgroundMode, batteryPausePercent } })
+}
+
+/** Pause or resume OCR with rendering, telling the user when that changes */
+export function applyPowerStatus(status: PowerStatus) {
+  const processingStore = useProcessingStore()
+  const toastStore = useToastStore()
+  if (status.paused === processingStore.isPowerPaused) return
+  processingStore.setPowerPaused(status.paused)
+  if (status.paused) {
+    toastStore.warning("toast.batteryPaused", { percent: status.batteryPercent ?? "" })
+  } else if (processingStore.isProcessing) {
+    toastStore.info("toast.batteryResumed")
+  }
+}
+
+export function getPowerStatus(): Promise<PowerStatus> {
+  return invoke<PowerStatus>("get_power_status")
+}
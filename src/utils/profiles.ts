@@ -0,0 +1,24 @@
+/** Profile using the original settings, sign-in and history (see profiles.rs) */
+export const DEFAULT_PROFILE = "default"
+
+/** Profiles on this machine and the active one, as returned by list_profiles */
+export interface ProfileList {
+  profiles: string[]
+  active: string
+}
+
+// Set by main.ts before the stores load
+let activeProfile = DEFAULT_PROFILE
+
+export function setActiveProfile(name: string) {
+  activeProfile = name
+}
+
+export function getActiveProfile(): string {
+  return activeProfile
+}
+
+/** localStorage key of `key` for the active profile; the default profile keeps the plain key */
+export function profileStorageKey(key: string): string {
+  return activeProfile === DEFAULT_PROFILE ? key : `${key}:${activeProfile}`
+}
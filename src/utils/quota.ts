@@ -0,0 +1,69 @@
+import { invoke } from "@tauri-apps/api/core"
+import { useProcessingStore } from "../stores/processing"
+import { useToastStore } from "../stores/toast"
+
+/** OCR backends with a daily budget (see quota.rs) */
+export type QuotaBackend = "googleDrive"
+
+/** 0 turns a limit off */
+export interface DailyBudget {
+  maxPages: number
+  maxApiCalls: number
+}
+
+/** Payload of the `quota-status` and `quota-warning` events and `get_quota_status` */
+export interface QuotaStatus {
+  backend: QuotaBackend
+  budget: DailyBudget
+  pages: number
+  apiCalls: number
+  /** Seconds since the Unix epoch the budget day started and ends */
+  periodStart: number
+  resetsAt: number
+  /** Largest share of a limit used, from 0 */
+  used: number
+  warning: boolean
+  /** A limit is used up; work waits for `resetsAt` */
+  paused: boolean
+}
+
+/** Local time of day the budget resets, e.g. "11:00" */
+export function resetTime(status: QuotaStatus): string {
+  return new Date(status.resetsAt * 1000).toLocaleTimeString([], {
+    hour: "2-digit",
+    minute: "2-digit",
+  })
+}
+
+/** Pause or resume the queue with the backend's budget, telling the user when that changes */
+export function applyQuotaStatus(status: QuotaStatus) {
+  const processingStore = useProcessingStore()
+  const toastStore = useToastStore()
+  if (status.paused === processingStore.isQuotaPaused) return
+  processingStore.setQuotaPaused(status.paused)
+  if (status.paused) {
+    toastStore.warning("toast.quotaPaused", { time: resetTime(status) })
+  } else if (processingStore.isProcessing) {
+    toastStore.info("toast.quotaResumed")
+  }
+}
+
+/** Tell the user a daily budget is nearly used up */
+export function warnQuota(status: QuotaStatus) {
+  useToastStore().warning("toast.quotaWarning", {
+    percent: Math.floor(status.used * 100),
+    time: resetTime(status),
+  })
+}
+
+export function getQuotaStatus(): Promise<QuotaStatus[]> {
+  return invoke<QuotaStatus[]>("get_quota_status")
+}
+
+/** Change a backend's daily budget; the queue resumes if it is no longer used up */
+export async function setDailyBudget(
+  backend: QuotaBackend,
+  budget: Partial<DailyBudget>,
+): Promise<void> {
+  await invoke("update_settings", { patch: { quota: { [backend]: budget } } })
+}
@@ -0,0 +1,56 @@
+import { invoke } from "@tauri-apps/api/core"
+import type { OutputFormat } from "@/stores/settings"
+import type { DocumentMetadata } from "@/composables/useWriters"
+
+/** Options of an output assembled from the spool (see spool.rs) */
+export interface SpoolWriteOptions {
+  pageSeparator: string
+  languages?: Array<string | null>
+  metadata?: DocumentMetadata
+}
+
+/**
+ * Store a shard's recognized pages in the job's workspace spool, starting at
+ * `firstPage` (1-based), so the outputs of very large documents are assembled
+ * from disk instead of one string per format
+ */
+export async function spoolPages(
+  jobId: string,
+  firstPage: number,
+  texts: string[],
+  footnotes: Array<string | null>,
+): Promise<void> {
+  await invoke("spool_pages", {
+    jobId,
+    firstPage,
+    pages: texts.map((text, index) => ({ text, footnotes: footnotes[index] ?? null })),
+  })
+}
+
+/** Write `<outputBasePath>.<format>` from the spooled pages; returns the written paths */
+export async function writeSpooledOutputs(
+  jobId: string,
+  outputBasePath: string,
+  formats: OutputFormat[],
+  pageCount: number,
+  options: SpoolWriteOptions,
+): Promise<string[]> {
+  return invoke<string[]>("write_spooled_outputs", {
+    jobId,
+    outputPath: outputBasePath,
+    formats,
+    pageCount,
+    pageSeparator: options.pageSeparator,
+    languages: options.languages ?? null,
+    metadata: options.metadata ?? null,
+  })
+}
+
+/** Remove the job's spool; best effort */
+export async function discardSpool(jobId: string): Promise<void> {
+  try {
+    await invoke("discard_spool", { jobId })
+  } catch (error) {
+    console.warn("Failed to discard spooled pages:", error)
+  }
+}
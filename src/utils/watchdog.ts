@@ -0,0 +1,82 @@
+import { emit } from "@tauri-apps/api/event"
+import { logJobEvent } from "./jobEvents"
+
+/** No rendering progress for this long counts as a stalled render */
+export const RENDER_STALL_MS = 2 * 60 * 1000
+/**
+ * A page still uploading or exporting after this long is abandoned and started
+ * again. Above the backend's own retries of slow requests (read timeouts with
+ * backoff), so only a page that is truly stuck gets here.
+ */
+export const OCR_PAGE_STALL_MS = 10 * 60 * 1000
+
+/** Payload of the `job-stalled` event */
+export interface JobStall {
+  jobId?: string
+  stage: "splitting" | "ocr"
+  /** 1-based page the stall is about, if any */
+  page?: number
+  /** How long nothing happened before the watchdog fired */
+  idleMs: number
+  /** `restarted`: the operation was started again; `reported`: it cannot be interrupted */
+  action: "restarted" | "reported"
+}
+
+/** Thrown by `withStallTimeout` when the operation did not finish in time */
+export class StallError extends Error {
+  constructor(public idleMs: number) {
+    super(`No progress for ${Math.round(idleMs / 1000)}s`)
+    this.name = "StallError"
+  }
+}
+
+/**
+ * Run `operation`, rejecting with a `StallError` when it has not settled after
+ * `idleMs`. The operation itself keeps running; its result is ignored.
+ */
+export function withStallTimeout<T>(operation: Promise<T>, idleMs: number): Promise<T> {
+  let timer: ReturnType<typeof setTimeout> | undefined
+  const stalled = new Promise<never>((_, reject) => {
+    timer = setTimeout(() => reject(new StallError(idleMs)), idleMs)
+  })
+  return Promise.race([operation, stalled]).finally(() => clearTimeout(timer))
+}
+
+/**
+ * Watch a stage for progress: `onStall` runs once each time `idleMs` passes
+ * without a `touch`. Call `stop` when the stage ends.
+ */
+export function watchProgress(
+  idleMs: number,
+  onStall: (idleMs: number) => void,
+): { touch: () => void; stop: () => void } {
+  let timer: ReturnType<typeof setTimeout> | undefined
+  const arm = () => {
+    clearTimeout(timer)
+    timer = setTimeout(() => {
+      onStall(idleMs)
+      arm()
+    }, idleMs)
+  }
+  arm()
+  return { touch: arm, stop: () => clearTimeout(timer) }
+}
+
+/**
+ * Tell the app a job stalled: emit `job-stalled` and add it to the job's
+ * event log. Best effort.
+ */
+export async function reportStall(stall: JobStall): Promise<void> {
+  console.warn("Job stalled:", stall)
+  await logJobEvent(
+    stall.jobId,
+    "error",
+    `${stall.stage} stalled for ${Math.round(stall.idleMs / 1000)}s (${stall.action})`,
+    stall.page,
+  )
+  try {
+    await emit("job-stalled", stall)
+  } catch {
+    // No listener outside the app window (tests)
+  }
+}